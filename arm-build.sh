@@ -1,3 +1,9 @@
+# Set MINIMAL=1 to build without native libraries (no libudev-dev needed).
+CARGO_FEATURES=""
+if [ "${MINIMAL:-0}" = "1" ]; then
+  CARGO_FEATURES="--no-default-features --features minimal"
+fi
+
 docker run --rm -t \
   -v "$PWD":/work -w /work \
   --platform linux/arm64 \
@@ -7,5 +13,5 @@ docker run --rm -t \
     apt-get install -y curl build-essential pkg-config libudev-dev &&
     curl https://sh.rustup.rs -sSf | sh -s -- -y &&
     source /root/.cargo/env &&
-    cargo build --release
+    cargo build --release -p backend $CARGO_FEATURES
   "
@@ -0,0 +1,73 @@
+//! Records the commit and time the server was built from, for `GET /version`, and
+//! with the `grpc` feature generates the gRPC service.
+
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let out = String::from_utf8(output.stdout).ok()?;
+    Some(out.trim().to_string()).filter(|out| !out.is_empty())
+}
+
+fn main() {
+    // Builds from a tarball or in a container without `.git` can pass the commit in.
+    println!("cargo:rerun-if-env-changed=DAYROLL_GIT_COMMIT");
+    let commit = std::env::var("DAYROLL_GIT_COMMIT")
+        .ok()
+        .filter(|commit| !commit.is_empty())
+        .or_else(|| git(&["rev-parse", "--short=12", "HEAD"]))
+        .unwrap_or_else(|| "unknown".into());
+    println!("cargo:rustc-env=DAYROLL_GIT_COMMIT={commit}");
+    if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]) {
+        println!("cargo:rerun-if-changed={git_dir}/HEAD");
+        if let Some(head) = git(&["symbolic-ref", "HEAD"]) {
+            println!("cargo:rerun-if-changed={git_dir}/{head}");
+        }
+    }
+
+    // Rebuilt sources get a new build time; SOURCE_DATE_EPOCH pins it for
+    // reproducible builds.
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    let built_at = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|since| since.as_secs())
+                .unwrap_or_default()
+        });
+    println!("cargo:rustc-env=DAYROLL_BUILT_AT={built_at}");
+    println!(
+        "cargo:rustc-env=DAYROLL_TARGET={}",
+        std::env::var("TARGET").unwrap_or_default()
+    );
+
+    #[cfg(feature = "grpc")]
+    compile_protos();
+}
+
+/// Generates `proto/dayroll.proto`'s messages and server, with the protoc bundled
+/// with the build unless `PROTOC` names another.
+#[cfg(feature = "grpc")]
+fn compile_protos() {
+    use std::path::PathBuf;
+
+    let mut config = tonic_prost_build::Config::new();
+    let mut includes = vec![PathBuf::from("proto")];
+    if std::env::var_os("PROTOC").is_none() {
+        let protoc = protoc_bin_vendored::protoc_bin_path()
+            .expect("no protoc is bundled for this platform; set PROTOC to one");
+        config.protoc_executable(protoc);
+        includes.extend(protoc_bin_vendored::include_path().ok());
+    }
+    tonic_prost_build::configure()
+        .build_client(false)
+        .compile_with_config(config, &[PathBuf::from("proto/dayroll.proto")], &includes)
+        .expect("proto/dayroll.proto should compile");
+}
@@ -0,0 +1,34 @@
+CREATE TABLE schedules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL,
+    time_of_day TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE schedule_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    schedule_id INTEGER NOT NULL REFERENCES schedules (id) ON DELETE CASCADE,
+    started_at TIMESTAMP NOT NULL,
+    finished_at TIMESTAMP,
+    outcome TEXT NOT NULL,
+    bytes_printed INTEGER NOT NULL DEFAULT 0,
+    error TEXT
+);
+
+CREATE INDEX schedule_runs_schedule_id_idx ON schedule_runs (schedule_id, started_at);
+
+CREATE TABLE schedule_run_sections (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    run_id INTEGER NOT NULL REFERENCES schedule_runs (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    integration TEXT NOT NULL,
+    fetch_ms INTEGER NOT NULL,
+    render_ms INTEGER NOT NULL,
+    bytes INTEGER NOT NULL,
+    outcome TEXT NOT NULL,
+    error TEXT
+);
+
+CREATE INDEX schedule_run_sections_run_id_idx ON schedule_run_sections (run_id);
@@ -0,0 +1,3 @@
+ALTER TABLE schedules DROP COLUMN truncation;
+ALTER TABLE schedules DROP COLUMN budget_mm;
+ALTER TABLE schedules DROP COLUMN budget_lines;
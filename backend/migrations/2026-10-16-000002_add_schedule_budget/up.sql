@@ -0,0 +1,3 @@
+ALTER TABLE schedules ADD COLUMN budget_lines INTEGER;
+ALTER TABLE schedules ADD COLUMN budget_mm INTEGER;
+ALTER TABLE schedules ADD COLUMN truncation TEXT NOT NULL DEFAULT 'more';
@@ -0,0 +1,24 @@
+CREATE TABLE printers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL,
+    transport TEXT NOT NULL,
+    path TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE jobs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    printer_id INTEGER NOT NULL REFERENCES printers (id),
+    state TEXT NOT NULL,
+    source TEXT NOT NULL DEFAULT 'api',
+    payload TEXT NOT NULL,
+    bytes INTEGER,
+    error TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    started_at TIMESTAMP,
+    finished_at TIMESTAMP
+);
+
+CREATE INDEX jobs_printer_id_state_idx ON jobs (printer_id, state);
@@ -0,0 +1,2 @@
+DROP TABLE schedule_previews;
+ALTER TABLE schedules DROP COLUMN public_token;
@@ -0,0 +1,8 @@
+ALTER TABLE schedules ADD COLUMN public_token TEXT;
+
+CREATE TABLE schedule_previews (
+    schedule_id INTEGER PRIMARY KEY NOT NULL REFERENCES schedules (id) ON DELETE CASCADE,
+    text TEXT NOT NULL,
+    png BLOB NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1 @@
+ALTER TABLE schedules ADD COLUMN printer_id INTEGER REFERENCES printers (id);
@@ -0,0 +1,8 @@
+CREATE TABLE job_webhooks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    printer_id INTEGER REFERENCES printers(id) ON DELETE CASCADE,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
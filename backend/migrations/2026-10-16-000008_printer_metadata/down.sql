@@ -0,0 +1,3 @@
+ALTER TABLE printers DROP COLUMN location;
+ALTER TABLE printers DROP COLUMN icon;
+ALTER TABLE printers DROP COLUMN display_name;
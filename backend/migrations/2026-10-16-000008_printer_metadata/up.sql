@@ -0,0 +1,3 @@
+ALTER TABLE printers ADD COLUMN display_name TEXT;
+ALTER TABLE printers ADD COLUMN icon TEXT;
+ALTER TABLE printers ADD COLUMN location TEXT;
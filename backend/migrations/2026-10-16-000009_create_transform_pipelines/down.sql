@@ -0,0 +1 @@
+DROP TABLE transform_pipelines;
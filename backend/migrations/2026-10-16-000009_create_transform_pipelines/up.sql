@@ -0,0 +1,7 @@
+CREATE TABLE transform_pipelines (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    source TEXT NOT NULL UNIQUE,
+    steps TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
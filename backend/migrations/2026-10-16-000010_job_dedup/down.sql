@@ -0,0 +1,3 @@
+DROP INDEX jobs_printer_id_content_hash_idx;
+ALTER TABLE jobs DROP COLUMN content_hash;
+ALTER TABLE printers DROP COLUMN dedup_window_mins;
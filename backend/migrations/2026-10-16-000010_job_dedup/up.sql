@@ -0,0 +1,4 @@
+ALTER TABLE printers ADD COLUMN dedup_window_mins INTEGER;
+ALTER TABLE jobs ADD COLUMN content_hash TEXT;
+
+CREATE INDEX jobs_printer_id_content_hash_idx ON jobs (printer_id, content_hash);
@@ -0,0 +1,2 @@
+ALTER TABLE jobs DROP COLUMN urgent;
+ALTER TABLE printers DROP COLUMN quiet_hours;
@@ -0,0 +1,2 @@
+ALTER TABLE printers ADD COLUMN quiet_hours TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE jobs ADD COLUMN urgent BOOLEAN NOT NULL DEFAULT 0;
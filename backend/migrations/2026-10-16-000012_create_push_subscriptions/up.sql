@@ -0,0 +1,14 @@
+CREATE TABLE push_subscriptions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    url TEXT NOT NULL,
+    topic TEXT NOT NULL,
+    printer_id INTEGER NOT NULL REFERENCES printers (id) ON DELETE CASCADE,
+    buffer_size INTEGER NOT NULL DEFAULT 20,
+    overflow TEXT NOT NULL DEFAULT 'drop_oldest',
+    rate_per_min INTEGER NOT NULL DEFAULT 6,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
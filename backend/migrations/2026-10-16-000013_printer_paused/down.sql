@@ -0,0 +1 @@
+ALTER TABLE printers DROP COLUMN paused;
@@ -0,0 +1 @@
+ALTER TABLE printers ADD COLUMN paused BOOLEAN NOT NULL DEFAULT 0;
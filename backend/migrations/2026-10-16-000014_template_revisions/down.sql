@@ -0,0 +1,2 @@
+DROP TABLE template_revisions;
+ALTER TABLE templates DROP COLUMN revision;
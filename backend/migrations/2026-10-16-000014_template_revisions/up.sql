@@ -0,0 +1,13 @@
+ALTER TABLE templates ADD COLUMN revision INTEGER NOT NULL DEFAULT 1;
+
+CREATE TABLE template_revisions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    template_id INTEGER NOT NULL REFERENCES templates (id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL,
+    payload TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (template_id, revision)
+);
+
+INSERT INTO template_revisions (template_id, revision, payload, created_at)
+SELECT id, 1, payload, updated_at FROM templates;
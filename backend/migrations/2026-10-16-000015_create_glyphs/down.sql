@@ -0,0 +1,2 @@
+ALTER TABLE printers DROP COLUMN user_glyphs;
+DROP TABLE glyphs;
@@ -0,0 +1,9 @@
+CREATE TABLE glyphs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    width INTEGER NOT NULL,
+    bitmap BLOB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+ALTER TABLE printers ADD COLUMN user_glyphs BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+ALTER TABLE printers ADD COLUMN native_qr BOOLEAN NOT NULL DEFAULT 1;
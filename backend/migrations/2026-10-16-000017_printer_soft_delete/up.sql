@@ -0,0 +1 @@
+ALTER TABLE printers ADD COLUMN deleted_at TIMESTAMP;
@@ -0,0 +1,4 @@
+ALTER TABLE templates DROP COLUMN footer;
+ALTER TABLE templates DROP COLUMN header;
+ALTER TABLE printers DROP COLUMN footer;
+ALTER TABLE printers DROP COLUMN header;
@@ -0,0 +1,4 @@
+ALTER TABLE printers ADD COLUMN header TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE printers ADD COLUMN footer TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE templates ADD COLUMN header TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE templates ADD COLUMN footer TEXT NOT NULL DEFAULT '[]';
@@ -0,0 +1 @@
+ALTER TABLE printers ADD COLUMN replacement_char TEXT NOT NULL DEFAULT '?';
@@ -0,0 +1 @@
+ALTER TABLE printers ADD COLUMN code_pages TEXT NOT NULL DEFAULT '["cp437"]';
@@ -0,0 +1 @@
+ALTER TABLE printers ADD COLUMN emoji TEXT NOT NULL DEFAULT 'raster';
@@ -0,0 +1 @@
+ALTER TABLE printers DROP COLUMN page_mode;
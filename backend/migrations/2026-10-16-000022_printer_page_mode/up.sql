@@ -0,0 +1 @@
+ALTER TABLE printers ADD COLUMN page_mode BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+ALTER TABLE printers DROP COLUMN rotate_180;
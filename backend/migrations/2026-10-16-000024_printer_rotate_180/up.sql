@@ -0,0 +1 @@
+ALTER TABLE printers ADD COLUMN rotate_180 BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,8 @@
+CREATE TABLE integration_instances (
+    instance_id TEXT PRIMARY KEY NOT NULL,
+    slug TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    settings TEXT NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,15 @@
+CREATE TABLE quote_lists (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE quotes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    list_id INTEGER NOT NULL REFERENCES quote_lists (id) ON DELETE CASCADE,
+    text TEXT NOT NULL,
+    author TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX quotes_list_id_idx ON quotes (list_id);
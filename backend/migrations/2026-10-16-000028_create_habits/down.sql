@@ -0,0 +1,2 @@
+DROP TABLE habit_checks;
+DROP TABLE habits;
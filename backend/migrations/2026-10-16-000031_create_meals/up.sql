@@ -0,0 +1,12 @@
+CREATE TABLE meals (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    day DATE NOT NULL,
+    slot TEXT NOT NULL DEFAULT 'dinner',
+    title TEXT NOT NULL,
+    notes TEXT,
+    defrost TEXT,
+    source TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (day, slot)
+);
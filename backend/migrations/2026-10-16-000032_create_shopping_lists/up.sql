@@ -0,0 +1,15 @@
+CREATE TABLE shopping_lists (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE shopping_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    list_id INTEGER NOT NULL REFERENCES shopping_lists (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    quantity TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX shopping_items_list_id_idx ON shopping_items (list_id);
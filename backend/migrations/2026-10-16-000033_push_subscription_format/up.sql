@@ -0,0 +1 @@
+ALTER TABLE push_subscriptions ADD COLUMN format TEXT NOT NULL DEFAULT 'text';
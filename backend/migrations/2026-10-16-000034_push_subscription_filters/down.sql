@@ -0,0 +1,2 @@
+ALTER TABLE push_subscriptions DROP COLUMN allowed_senders;
+ALTER TABLE push_subscriptions DROP COLUMN subject_filter;
@@ -0,0 +1,2 @@
+ALTER TABLE push_subscriptions ADD COLUMN subject_filter TEXT;
+ALTER TABLE push_subscriptions ADD COLUMN allowed_senders TEXT;
@@ -0,0 +1,10 @@
+CREATE TABLE hooks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    token TEXT NOT NULL,
+    printer_id INTEGER NOT NULL REFERENCES printers (id) ON DELETE CASCADE,
+    payload TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,5 @@
+CREATE TABLE stock_quotes (
+    symbol TEXT PRIMARY KEY NOT NULL,
+    data TEXT NOT NULL,
+    fetched_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,4 @@
+ALTER TABLE integration_instances DROP COLUMN fetched_at;
+ALTER TABLE integration_instances DROP COLUMN data;
+ALTER TABLE integration_instances DROP COLUMN refresh_minutes;
+ALTER TABLE integration_instances DROP COLUMN print_on;
@@ -0,0 +1,4 @@
+ALTER TABLE integration_instances ADD COLUMN print_on TEXT NOT NULL DEFAULT 'both';
+ALTER TABLE integration_instances ADD COLUMN refresh_minutes INTEGER;
+ALTER TABLE integration_instances ADD COLUMN data TEXT;
+ALTER TABLE integration_instances ADD COLUMN fetched_at TIMESTAMP;
@@ -0,0 +1,3 @@
+ALTER TABLE schedules DROP COLUMN footer;
+ALTER TABLE schedules DROP COLUMN header;
+ALTER TABLE integration_instances DROP COLUMN position;
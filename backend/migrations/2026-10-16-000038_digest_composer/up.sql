@@ -0,0 +1,3 @@
+ALTER TABLE integration_instances ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE schedules ADD COLUMN header TEXT;
+ALTER TABLE schedules ADD COLUMN footer TEXT;
@@ -0,0 +1 @@
+ALTER TABLE integration_instances ADD COLUMN template TEXT;
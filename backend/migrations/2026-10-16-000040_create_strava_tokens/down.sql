@@ -0,0 +1 @@
+DROP TABLE strava_tokens;
@@ -0,0 +1,6 @@
+CREATE TABLE strava_tokens (
+    athlete_id BIGINT PRIMARY KEY NOT NULL,
+    refresh_token TEXT NOT NULL,
+    access_token TEXT NOT NULL,
+    expires_at TIMESTAMP NOT NULL
+);
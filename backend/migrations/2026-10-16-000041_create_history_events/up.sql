@@ -0,0 +1,10 @@
+-- Wikimedia's "on this day" feeds, kept by language, feed and day of the year.
+CREATE TABLE history_events (
+    language TEXT NOT NULL,
+    feed TEXT NOT NULL,
+    -- MM-DD
+    day TEXT NOT NULL,
+    data TEXT NOT NULL,
+    fetched_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (language, feed, day)
+);
@@ -0,0 +1,2 @@
+DROP TABLE webhook_attempts;
+ALTER TABLE job_webhooks DROP COLUMN events;
@@ -0,0 +1,17 @@
+-- Which events each webhook is sent; those made before keep getting job events.
+ALTER TABLE job_webhooks ADD COLUMN events TEXT NOT NULL DEFAULT '["job.done","job.failed"]';
+
+-- Every try at delivering an event to a webhook, newest kept, to look into failures.
+CREATE TABLE webhook_attempts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    webhook_id INTEGER NOT NULL REFERENCES job_webhooks(id) ON DELETE CASCADE,
+    delivery_id TEXT NOT NULL,
+    event TEXT NOT NULL,
+    attempt INTEGER NOT NULL,
+    status INTEGER,
+    error TEXT,
+    duration_ms INTEGER NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX webhook_attempts_webhook ON webhook_attempts (webhook_id, id);
@@ -0,0 +1,3 @@
+-- What each key may do: `admin` for everything, `print` for all but administration.
+-- Keys issued before scopes keep the access they had.
+ALTER TABLE api_keys ADD COLUMN scope TEXT NOT NULL DEFAULT 'admin';
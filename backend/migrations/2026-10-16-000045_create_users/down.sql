@@ -0,0 +1,5 @@
+ALTER TABLE api_keys DROP COLUMN user_id;
+ALTER TABLE schedules DROP COLUMN user_id;
+ALTER TABLE habits DROP COLUMN user_id;
+ALTER TABLE tasks DROP COLUMN user_id;
+DROP TABLE users;
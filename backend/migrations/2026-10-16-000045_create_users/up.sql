@@ -0,0 +1,15 @@
+-- The people sharing the install, each signing in with a password.
+CREATE TABLE users (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    password_hash TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Whose each is; unset for the household's, as everything was before.
+ALTER TABLE tasks ADD COLUMN user_id INTEGER REFERENCES users (id) ON DELETE SET NULL;
+ALTER TABLE habits ADD COLUMN user_id INTEGER REFERENCES users (id) ON DELETE SET NULL;
+ALTER TABLE schedules ADD COLUMN user_id INTEGER REFERENCES users (id) ON DELETE SET NULL;
+
+-- Who a key was issued to, to attribute what it's used for; a user's keys go with them.
+ALTER TABLE api_keys ADD COLUMN user_id INTEGER REFERENCES users (id) ON DELETE CASCADE;
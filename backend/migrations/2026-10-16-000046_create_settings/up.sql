@@ -0,0 +1,13 @@
+-- Instance-wide settings, changed through the API rather than the environment. A
+-- single row; unset columns fall back to the environment and built-in defaults.
+CREATE TABLE settings (
+    id INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+    timezone TEXT,
+    locale TEXT,
+    default_printer_id INTEGER REFERENCES printers (id) ON DELETE SET NULL,
+    digest_time TEXT,
+    theme TEXT,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO settings (id) VALUES (1);
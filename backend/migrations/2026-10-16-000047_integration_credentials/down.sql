@@ -0,0 +1,2 @@
+ALTER TABLE integration_instances DROP COLUMN credentials_error;
+ALTER TABLE integration_instances DROP COLUMN credentials_invalid;
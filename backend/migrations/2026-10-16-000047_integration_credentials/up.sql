@@ -0,0 +1,4 @@
+-- Whether the service refused an instance's credentials when they were last checked,
+-- and why.
+ALTER TABLE integration_instances ADD COLUMN credentials_invalid BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE integration_instances ADD COLUMN credentials_error TEXT;
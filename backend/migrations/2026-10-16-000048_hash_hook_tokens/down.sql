@@ -0,0 +1,2 @@
+-- Hashed tokens can't be recovered; hooks need new tokens after this.
+ALTER TABLE hooks RENAME COLUMN token_hash TO token;
@@ -0,0 +1,4 @@
+-- Tokens are stored hashed from now on. Those already stored are marked, so the
+-- server hashes them when it starts (SQLite has no SHA-256 of its own).
+UPDATE hooks SET token = 'plain:' || token;
+ALTER TABLE hooks RENAME COLUMN token TO token_hash;
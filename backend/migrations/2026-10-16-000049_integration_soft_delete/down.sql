@@ -0,0 +1 @@
+ALTER TABLE integration_instances DROP COLUMN deleted_at;
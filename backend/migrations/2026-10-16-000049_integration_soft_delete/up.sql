@@ -0,0 +1 @@
+ALTER TABLE integration_instances ADD COLUMN deleted_at TIMESTAMP;
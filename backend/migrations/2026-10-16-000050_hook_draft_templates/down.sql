@@ -0,0 +1 @@
+ALTER TABLE hooks DROP COLUMN draft_template_id;
@@ -0,0 +1 @@
+ALTER TABLE hooks ADD COLUMN draft_template_id INTEGER REFERENCES templates (id) ON DELETE SET NULL;
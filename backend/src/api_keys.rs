@@ -0,0 +1,169 @@
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+
+use crate::schema::api_keys;
+
+/// What a key may do.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Scope {
+    /// Everything, administration included.
+    Admin,
+    /// Printing and everyday use, as kiosks and automations need; not the
+    /// administration only [`Scope::Admin`] keys may do.
+    #[default]
+    Print,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Admin => "admin",
+            Scope::Print => "print",
+        }
+    }
+}
+
+impl FromStr for Scope {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "admin" => Ok(Scope::Admin),
+            "print" => Ok(Scope::Print),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A key a client, such as the web frontend or a kiosk, presents to use the API.
+/// Only its hash is stored; the key itself is shown once, when it is issued.
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize)]
+#[diesel(table_name = api_keys)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ApiKey {
+    pub id: i32,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    /// A [`Scope`]: `admin` or `print`.
+    pub scope: String,
+    /// Whose key it is, for keys issued when someone signs in.
+    pub user_id: Option<i32>,
+}
+
+impl ApiKey {
+    /// Keys with a scope this server doesn't know are held to the narrowest.
+    pub fn scope(&self) -> Scope {
+        self.scope.parse().unwrap_or(Scope::Print)
+    }
+}
+
+/// Hex SHA-256 of a secret, as stored in place of it.
+pub fn hash(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<ApiKey>> {
+    let rows = api_keys::table
+        .order(api_keys::id.asc())
+        .select(ApiKey::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+/// Whether any key has been issued. Until one is, the API is open so a fresh
+/// install can be set up.
+pub fn any(conn: &mut SqliteConnection) -> Result<bool> {
+    let count: i64 = api_keys::table.count().get_result(conn)?;
+    Ok(count > 0)
+}
+
+/// Issue a key named `name` with `scope`, attributed to `user_id` if given, returning
+/// it alongside the plain key. The first key issued is an admin key whatever `scope`
+/// says, as it closes the API and someone must still be able to administer it.
+pub fn create(
+    conn: &mut SqliteConnection,
+    name: &str,
+    scope: Scope,
+    user_id: Option<i32>,
+) -> Result<(ApiKey, String)> {
+    let key = format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    );
+    conn.transaction(|conn| {
+        let scope = if any(conn)? { scope } else { Scope::Admin };
+        let row = diesel::insert_into(api_keys::table)
+            .values((
+                api_keys::name.eq(name),
+                api_keys::key_hash.eq(hash(&key)),
+                api_keys::scope.eq(scope.as_str()),
+                api_keys::user_id.eq(user_id),
+            ))
+            .returning(ApiKey::as_returning())
+            .get_result(conn)?;
+        Ok((row, key))
+    })
+}
+
+/// Whether `id` is the only admin key left while other keys remain, so revoking it
+/// would leave no one able to administer the API.
+fn is_last_admin(conn: &mut SqliteConnection, id: i32) -> Result<bool> {
+    let admins: Vec<i32> = api_keys::table
+        .filter(api_keys::scope.eq(Scope::Admin.as_str()))
+        .select(api_keys::id)
+        .load(conn)?;
+    let total: i64 = api_keys::table.count().get_result(conn)?;
+    Ok(admins == [id] && total > 1)
+}
+
+/// Whether revoking `user_id`'s keys, as removing them does, would leave no admin
+/// key, or no key at all and so open the API up again.
+pub fn strands_user(conn: &mut SqliteConnection, user_id: i32) -> Result<bool> {
+    let owned: i64 = api_keys::table
+        .filter(api_keys::user_id.eq(user_id))
+        .count()
+        .get_result(conn)?;
+    if owned == 0 {
+        return Ok(false);
+    }
+    let admins_left: i64 = api_keys::table
+        .filter(api_keys::scope.eq(Scope::Admin.as_str()))
+        .filter(
+            api_keys::user_id
+                .is_null()
+                .or(api_keys::user_id.ne(user_id)),
+        )
+        .count()
+        .get_result(conn)?;
+    Ok(admins_left == 0)
+}
+
+/// The key matching `key`, marked as used now.
+pub fn authenticate(conn: &mut SqliteConnection, key: &str) -> Result<Option<ApiKey>> {
+    let row = diesel::update(api_keys::table.filter(api_keys::key_hash.eq(hash(key))))
+        .set(api_keys::last_used_at.eq(Utc::now().naive_utc()))
+        .returning(ApiKey::as_returning())
+        .get_result(conn)
+        .optional()?;
+    Ok(row)
+}
+
+/// Revoke `id`, returning whether it existed, or `None`, keeping it, when it's the
+/// last admin key and other keys remain.
+pub fn delete(conn: &mut SqliteConnection, id: i32) -> Result<Option<bool>> {
+    conn.transaction(|conn| {
+        if is_last_admin(conn, id)? {
+            return Ok(None);
+        }
+        let deleted = diesel::delete(api_keys::table.find(id)).execute(conn)?;
+        Ok(Some(deleted > 0))
+    })
+}
@@ -1,8 +1,74 @@
+use crate::config::Cors;
 use crate::state::AppState;
-use axum::Router;
+use axum::extract::DefaultBodyLimit;
+use axum::http::{HeaderName, HeaderValue, header};
+use axum::{Router, middleware};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::{DefaultOnResponse, TraceLayer};
+use tracing::Level;
+
+const REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
 
 pub fn build_app(state: AppState) -> Router {
-    Router::new()
-        .merge(crate::routes::router())
-        .with_state(state)
+    let mut app = Router::new().merge(crate::routes::router());
+    // Behind the same key check and request ids as the REST endpoints.
+    #[cfg(feature = "grpc")]
+    {
+        app = app.merge(crate::grpc::router(state.clone()));
+    }
+    app = app
+        // Routes taking bigger bodies, such as picture uploads, raise it for themselves.
+        .layer(DefaultBodyLimit::max(state.config.max_body_bytes))
+        .layer(middleware::from_fn(crate::routes::auth::require_key))
+        .layer(middleware::from_fn(crate::telemetry::scope_request_id));
+    // Set after the layers above, so the UI's files, needed before there's a key to
+    // send, are served without one.
+    #[cfg(feature = "web-ui")]
+    {
+        app = app.fallback_service(
+            Router::new()
+                .fallback(crate::web::serve)
+                .layer(middleware::from_fn(crate::telemetry::scope_request_id)),
+        );
+    }
+    // Outside the key check, so preflight requests, which carry no key, are answered.
+    if let Some(cors) = cors_layer(&state.config.cors) {
+        app = app.layer(cors);
+    }
+    // Every request gets an id, echoed back in `X-Request-Id` and recorded with
+    // each log line written while handling it.
+    app.layer(
+        TraceLayer::new_for_http()
+            .make_span_with(crate::telemetry::request_span)
+            .on_response(DefaultOnResponse::new().level(Level::INFO)),
+    )
+    .layer(PropagateRequestIdLayer::new(REQUEST_ID))
+    .layer(SetRequestIdLayer::new(REQUEST_ID, MakeRequestUuid))
+    .with_state(state)
+}
+
+/// `None` when no origins are allowed, leaving cross-origin requests to the
+/// browser's defaults.
+fn cors_layer(cors: &Cors) -> Option<CorsLayer> {
+    if cors.allowed_origins.is_empty() {
+        return None;
+    }
+    let origins = if cors.allowed_origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            cors.allowed_origins
+                .iter()
+                .filter_map(|o| HeaderValue::from_str(o.trim_end_matches('/')).ok()),
+        )
+    };
+    Some(
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(cors.allowed_methods.clone())
+            .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE, REQUEST_ID])
+            .expose_headers([REQUEST_ID])
+            .allow_credentials(cors.allow_credentials),
+    )
 }
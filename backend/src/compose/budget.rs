@@ -0,0 +1,256 @@
+use std::cmp::Reverse;
+use std::str::FromStr;
+
+use super::Section;
+
+/// Height of one line at the printer's default 1/6" line spacing.
+pub const LINE_HEIGHT_MM: f32 = 4.23;
+
+/// How a section is cut down when the composition exceeds its budget.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Truncation {
+    /// Remove the whole section.
+    Drop,
+    /// Replace the body with the section's one-line summary.
+    Summarize,
+    /// Keep the first lines and end with "…and N more".
+    #[default]
+    More,
+}
+
+impl Truncation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Truncation::Drop => "drop",
+            Truncation::Summarize => "summarize",
+            Truncation::More => "more",
+        }
+    }
+}
+
+impl FromStr for Truncation {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "drop" => Ok(Truncation::Drop),
+            "summarize" => Ok(Truncation::Summarize),
+            "more" => Ok(Truncation::More),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    pub max_lines: Option<usize>,
+    pub max_mm: Option<u32>,
+    pub truncation: Truncation,
+}
+
+impl Budget {
+    /// Effective limit in lines; a millimetre budget is converted at the default line height.
+    pub fn line_limit(&self) -> Option<usize> {
+        let from_mm = self
+            .max_mm
+            .map(|mm| (mm as f32 / LINE_HEIGHT_MM).floor() as usize);
+        match (self.max_lines, from_mm) {
+            (Some(lines), Some(mm)) => Some(lines.min(mm)),
+            (lines, mm) => lines.or(mm),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Fitted {
+    pub sections: Vec<Section>,
+    pub trimmed: Vec<String>,
+}
+
+/// Trim sections until they fit the budget.
+///
+/// Sections are visited lowest priority first (later sections before earlier ones on
+/// ties) and cut with the configured strategy. Strategies that only shorten a section
+/// may not free enough space, in which case whole sections are dropped in the same order.
+pub fn fit(mut sections: Vec<Section>, budget: &Budget) -> Fitted {
+    let Some(limit) = budget.line_limit() else {
+        return Fitted {
+            sections,
+            trimmed: Vec::new(),
+        };
+    };
+
+    let mut order: Vec<usize> = (0..sections.len()).collect();
+    order.sort_by_key(|&i| (sections[i].priority, Reverse(i)));
+
+    let mut dropped = vec![false; sections.len()];
+    let mut trimmed = Vec::new();
+
+    for &i in &order {
+        let total = total_height(&sections, &dropped);
+        if total <= limit {
+            break;
+        }
+        let changed = match budget.truncation {
+            Truncation::Drop => {
+                dropped[i] = true;
+                true
+            }
+            Truncation::Summarize => summarize(&mut sections[i]),
+            Truncation::More => and_more(&mut sections[i], total - limit),
+        };
+        if changed {
+            trimmed.push(sections[i].integration.clone());
+        }
+    }
+
+    for &i in &order {
+        if total_height(&sections, &dropped) <= limit {
+            break;
+        }
+        if !dropped[i] {
+            dropped[i] = true;
+            if !trimmed.contains(&sections[i].integration) {
+                trimmed.push(sections[i].integration.clone());
+            }
+        }
+    }
+
+    let sections = sections
+        .into_iter()
+        .zip(dropped)
+        .filter_map(|(section, dropped)| (!dropped).then_some(section))
+        .collect();
+
+    Fitted { sections, trimmed }
+}
+
+fn total_height(sections: &[Section], dropped: &[bool]) -> usize {
+    sections
+        .iter()
+        .zip(dropped)
+        .filter(|(_, dropped)| !**dropped)
+        .map(|(s, _)| s.height())
+        .sum()
+}
+
+fn summarize(section: &mut Section) -> bool {
+    let Some(summary) = section
+        .summary
+        .clone()
+        .or_else(|| section.lines.first().cloned())
+    else {
+        return false;
+    };
+    if section.blocks.is_empty()
+        && section.lines.len() <= 1
+        && section.lines.first() == Some(&summary)
+    {
+        return false;
+    }
+    section.lines = vec![summary];
+    section.blocks.clear();
+    true
+}
+
+fn and_more(section: &mut Section, overflow: usize) -> bool {
+    let len = section.lines.len();
+    // Swapping a single line for "…and 1 more" saves nothing.
+    if len <= 1 {
+        return false;
+    }
+    let keep = len.saturating_sub(overflow + 1);
+    let hidden = len - keep;
+    section.lines.truncate(keep);
+    section.lines.push(format!("…and {hidden} more"));
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(integration: &str, priority: i32, lines: usize) -> Section {
+        Section {
+            integration: integration.into(),
+            title: integration.into(),
+            priority,
+            lines: (1..=lines).map(|n| format!("line {n}")).collect(),
+            blocks: Vec::new(),
+            summary: None,
+        }
+    }
+
+    fn budget(max_lines: usize, truncation: Truncation) -> Budget {
+        Budget {
+            max_lines: Some(max_lines),
+            max_mm: None,
+            truncation,
+        }
+    }
+
+    #[test]
+    fn the_tighter_limit_wins() {
+        let mm = Budget {
+            max_mm: Some(100),
+            ..Budget::default()
+        };
+        assert_eq!(mm.line_limit(), Some(23));
+        let both = Budget {
+            max_lines: Some(20),
+            ..mm.clone()
+        };
+        assert_eq!(both.line_limit(), Some(20));
+        assert_eq!(Budget::default().line_limit(), None);
+    }
+
+    #[test]
+    fn lowest_priority_is_cut_to_a_count_of_what_is_left() {
+        let sections = vec![section("weather", 1, 5), section("news", 0, 10)];
+        let fitted = fit(sections, &budget(15, Truncation::More));
+        assert_eq!(fitted.trimmed, ["news"]);
+        assert_eq!(fitted.sections[0].lines.len(), 5);
+        let news = &fitted.sections[1].lines;
+        assert_eq!(news.len(), 6);
+        assert_eq!(news.last().unwrap(), "…and 5 more");
+        assert!(fitted.sections.iter().map(Section::height).sum::<usize>() <= 15);
+    }
+
+    #[test]
+    fn summarized_sections_keep_one_line() {
+        let mut news = section("news", 0, 10);
+        news.summary = Some("10 stories".into());
+        let fitted = fit(
+            vec![section("weather", 1, 5), news],
+            &budget(15, Truncation::Summarize),
+        );
+        assert_eq!(fitted.trimmed, ["news"]);
+        assert_eq!(fitted.sections[1].lines, ["10 stories"]);
+    }
+
+    #[test]
+    fn later_sections_are_dropped_first_on_ties() {
+        let sections = vec![section("weather", 0, 3), section("news", 0, 3)];
+        let fitted = fit(sections, &budget(6, Truncation::Drop));
+        assert_eq!(fitted.trimmed, ["news"]);
+        assert_eq!(fitted.sections.len(), 1);
+        assert_eq!(fitted.sections[0].integration, "weather");
+    }
+
+    #[test]
+    fn sections_that_cannot_shrink_are_dropped() {
+        let sections = vec![section("weather", 0, 1), section("news", 0, 1)];
+        let fitted = fit(sections, &budget(4, Truncation::More));
+        assert_eq!(fitted.trimmed, ["news"]);
+        assert_eq!(fitted.sections.len(), 1);
+        assert_eq!(fitted.sections[0].lines, ["line 1"]);
+    }
+
+    #[test]
+    fn sections_within_the_budget_are_untouched() {
+        let sections = vec![section("weather", 0, 3), section("news", 0, 3)];
+        let fitted = fit(sections, &budget(10, Truncation::Drop));
+        assert!(fitted.trimmed.is_empty());
+        assert_eq!(fitted.sections.len(), 2);
+    }
+}
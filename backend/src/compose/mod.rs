@@ -0,0 +1,114 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use qrcode::{EcLevel, QrCode};
+use std::io::Cursor;
+
+use crate::compose::budget::LINE_HEIGHT_MM;
+use crate::document::{Block, Document};
+use crate::render::DOTS_PER_MM;
+use crate::render::theme::Theme;
+use crate::schedules::Schedule;
+
+pub mod budget;
+pub mod runner;
+
+/// One integration's contribution to a composed printout.
+#[derive(Debug, Clone)]
+pub struct Section {
+    /// Slug of the integration that produced the section.
+    pub integration: String,
+    pub title: String,
+    /// Higher values are more important and are trimmed last.
+    pub priority: i32,
+    pub lines: Vec<String>,
+    /// Images and other blocks printed between the title and the lines, for what
+    /// text can't show, such as a puzzle's grid.
+    pub blocks: Vec<Block>,
+    /// Optional one-line condensed form used by the `summarize` truncation strategy.
+    pub summary: Option<String>,
+}
+
+impl Section {
+    /// Printed height in lines: title, blocks, body and the blank separator after
+    /// it. Images count as the lines of text they are as tall as.
+    pub fn height(&self) -> usize {
+        let blocks: usize = self.blocks.iter().map(block_height).sum();
+        self.lines.len() + blocks + 2
+    }
+}
+
+/// Result of laying out a schedule's sections.
+#[derive(Debug, Clone)]
+pub struct Composition {
+    pub sections: Vec<Section>,
+    /// Integrations whose sections were shortened or removed to fit the budget.
+    pub trimmed: Vec<String>,
+}
+
+impl Composition {
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for section in &self.sections {
+            if !section.title.is_empty() {
+                out.push_str(&section.title.to_uppercase());
+                out.push('\n');
+            }
+            for block in &section.blocks {
+                if let Block::Glyph { text, .. } = block {
+                    out.push_str(text);
+                    out.push('\n');
+                }
+            }
+            for line in &section.lines {
+                out.push_str(line);
+                out.push('\n');
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// The sections laid out by `theme`, followed by a cut.
+    pub fn to_document(&self, theme: Theme) -> Document {
+        let layout = theme.layout();
+        let mut blocks: Vec<Block> = self
+            .sections
+            .iter()
+            .flat_map(|section| layout.section(&section.title, &section.blocks, &section.lines))
+            .collect();
+        blocks.push(Block::Cut { partial: false });
+        Document {
+            blocks,
+            ..Document::default()
+        }
+    }
+}
+
+fn block_height(block: &Block) -> usize {
+    let dots = match block {
+        Block::Image { data, .. } => BASE64
+            .decode(data)
+            .ok()
+            .and_then(|bytes| {
+                image::ImageReader::new(Cursor::new(bytes))
+                    .with_guessed_format()
+                    .ok()?
+                    .into_dimensions()
+                    .ok()
+            })
+            .map_or(0, |(_, height)| height),
+        Block::Qr { data, size, .. } => QrCode::with_error_correction_level(data, EcLevel::M)
+            .map_or(0, |code| code.width() as u32 * u32::from(*size)),
+        _ => return 1,
+    };
+    (dots as f32 / (LINE_HEIGHT_MM * DOTS_PER_MM)).ceil() as usize
+}
+
+/// Lay out sections for a schedule, enforcing its length budget.
+pub fn compose(schedule: &Schedule, sections: Vec<Section>) -> Composition {
+    let fitted = budget::fit(sections, &schedule.budget());
+    Composition {
+        sections: fitted.sections,
+        trimmed: fitted.trimmed,
+    }
+}
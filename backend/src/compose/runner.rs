@@ -0,0 +1,237 @@
+use anyhow::{Result, anyhow};
+use chrono::NaiveDateTime;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+use tracing::{Instrument, info, instrument, warn};
+
+use super::Section;
+use crate::db;
+use crate::document::Document;
+use crate::integrations::{self, Context};
+use crate::jobs::{JobPayload, NewJob};
+use crate::printers::{self, Printer};
+use crate::queue::QueueManager;
+use crate::render::Profile;
+use crate::render::theme::Theme;
+use crate::schedules::Schedule;
+use crate::schedules::previews;
+use crate::schedules::runs::{RunOutcome, RunRecorder, SectionTiming};
+use crate::settings;
+
+/// Longest an integration may take to fetch before the digest goes without it.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Compose a schedule's printout, refresh its preview and submit it to its target.
+/// Schedules without a theme of their own are laid out in the one set in the
+/// settings, or `theme` when none is.
+#[instrument(name = "digest", skip_all, fields(schedule = schedule.id))]
+pub async fn run_schedule(queue: &QueueManager, schedule: Schedule, theme: Theme) -> Result<()> {
+    let mut recorder = RunRecorder::start(schedule.id).await?;
+    info!(
+        "running schedule {} ('{}') as run {}",
+        schedule.id,
+        schedule.name,
+        recorder.run_id()
+    );
+
+    let settings = db::run_blocking_db(settings::get).await?;
+    let printer = match schedule.printer_id {
+        Some(id) => db::run_blocking_db(move |conn| printers::get(conn, id)).await?,
+        None => None,
+    };
+    let sections =
+        collect_sections(&mut recorder, &schedule, printer.as_ref(), settings.now()).await;
+    let composition = super::compose(&schedule, sections);
+    if !composition.trimmed.is_empty() {
+        info!(
+            "schedule {} trimmed to fit budget: {}",
+            schedule.id,
+            composition.trimmed.join(", ")
+        );
+    }
+
+    let theme = schedule.theme().or(settings.theme()).unwrap_or(theme);
+    let result = deliver(
+        queue,
+        &schedule,
+        composition.to_text(),
+        composition.to_document(theme),
+    )
+    .await;
+    match result {
+        Ok(bytes) => {
+            let outcome = if recorder.failed_sections() > 0 {
+                RunOutcome::Partial
+            } else {
+                RunOutcome::Success
+            };
+            recorder.finish(outcome, bytes, None).await
+        }
+        Err(err) => {
+            warn!("schedule {} failed: {err:#}", schedule.id);
+            recorder
+                .finish(RunOutcome::Failed, 0, Some(format!("{err:#}")))
+                .await
+        }
+    }
+}
+
+/// The date header for `now`, then a section from each enabled integration instance
+/// that goes into the digest, in digest order, then the schedule's footer. Instances
+/// are fetched all at once; one that fails or times out is left out and recorded as
+/// failed rather than holding up the rest.
+async fn collect_sections(
+    recorder: &mut RunRecorder,
+    schedule: &Schedule,
+    printer: Option<&Printer>,
+    now: NaiveDateTime,
+) -> Vec<Section> {
+    let started = Instant::now();
+    let header = Section {
+        integration: "date".into(),
+        title: now.format("%A, %B %-d").to_string(),
+        priority: i32::MAX,
+        lines: schedule
+            .header
+            .clone()
+            .into_iter()
+            .chain(printer.map(Printer::attribution))
+            .collect(),
+        blocks: Vec::new(),
+        summary: None,
+    };
+    recorder.record_section(SectionTiming {
+        integration: header.integration.clone(),
+        fetch: started.elapsed(),
+        render: Default::default(),
+        bytes: 0,
+        error: None,
+    });
+    let mut sections = vec![header];
+
+    let instances = match db::run_blocking_db(integrations::list).await {
+        Ok(instances) => instances,
+        Err(err) => {
+            warn!(
+                "schedule {}: could not list integrations: {err:#}",
+                schedule.id
+            );
+            Vec::new()
+        }
+    };
+    let ctx = Context { date: now.date() };
+    let mut fetches = JoinSet::new();
+    for (i, instance) in instances
+        .into_iter()
+        .filter(|i| i.enabled && i.print_on().in_digest())
+        .enumerate()
+    {
+        let ctx = ctx.clone();
+        fetches.spawn(
+            async move {
+                let started = Instant::now();
+                let fetched = tokio::time::timeout(FETCH_TIMEOUT, instance.fetch(&ctx))
+                    .await
+                    .unwrap_or_else(|_| Err(anyhow!("timed out after {FETCH_TIMEOUT:?}")));
+                (i, instance, fetched, started.elapsed())
+            }
+            .in_current_span(),
+        );
+    }
+    let mut fetched = Vec::new();
+    while let Some(done) = fetches.join_next().await {
+        match done {
+            Ok(done) => fetched.push(done),
+            Err(err) => warn!("schedule {}: an integration panicked: {err}", schedule.id),
+        }
+    }
+    fetched.sort_by_key(|(i, ..)| *i);
+
+    for (_, instance, result, fetch) in fetched {
+        let started = Instant::now();
+        let section = result.and_then(|data| instance.render(&ctx, &data));
+        let render = started.elapsed();
+        match section {
+            Ok(section) => {
+                recorder.record_section(SectionTiming {
+                    integration: instance.slug.clone(),
+                    fetch,
+                    render,
+                    bytes: section.lines.iter().map(String::len).sum(),
+                    error: None,
+                });
+                sections.push(section);
+            }
+            Err(err) => {
+                warn!(
+                    "schedule {}: {} ({}) left out: {err:#}",
+                    schedule.id, instance.instance_id, instance.slug
+                );
+                recorder.record_section(SectionTiming {
+                    integration: instance.slug.clone(),
+                    fetch,
+                    render,
+                    bytes: 0,
+                    error: Some(format!("{err:#}")),
+                });
+            }
+        }
+    }
+
+    if let Some(footer) = &schedule.footer {
+        sections.push(Section {
+            integration: "footer".into(),
+            title: String::new(),
+            priority: i32::MAX,
+            lines: vec![footer.clone()],
+            blocks: Vec::new(),
+            summary: None,
+        });
+    }
+    sections
+}
+
+/// Returns the number of bytes submitted for printing.
+async fn deliver(
+    queue: &QueueManager,
+    schedule: &Schedule,
+    text: String,
+    document: Document,
+) -> Result<usize> {
+    previews::store(schedule.id, text, &document).await?;
+
+    let Some(printer_id) = schedule.printer_id else {
+        return Ok(0);
+    };
+
+    let rendered = queue.limits().render(&document, &Profile::default())?;
+    if let Some(budget) = schedule.budget_mm
+        && rendered.length_mm() > budget as f32
+    {
+        warn!(
+            "schedule {} prints {:.0} mm, over its budget of {budget} mm",
+            schedule.id,
+            rendered.length_mm()
+        );
+    }
+    let bytes = rendered.bytes.len();
+    let submitted = queue
+        .submit(NewJob::new(
+            printer_id,
+            format!("schedule:{}", schedule.id),
+            &JobPayload::Document(document),
+        )?)
+        .await?;
+    if submitted.duplicate {
+        info!(
+            "schedule {} matched recent job {}; not printing again",
+            schedule.id, submitted.job.id
+        );
+        return Ok(0);
+    }
+    info!(
+        "schedule {} submitted job {}",
+        schedule.id, submitted.job.id
+    );
+    Ok(bytes)
+}
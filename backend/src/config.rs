@@ -1,15 +1,241 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
+use axum::http::Method;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::queue::Fairness;
+use crate::render::limits::Limits;
+use crate::render::theme::Theme;
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub bind_addr: String,
+    /// Upper bound on printers being written to concurrently.
+    pub max_inflight_prints: usize,
+    /// Caps on rendered job size; see [`Limits::from_env`].
+    pub limits: Limits,
+    /// How each printer picks its next job among those waiting.
+    pub queue_fairness: Fairness,
+    /// Days a deleted printer or integration instance can be restored before it is
+    /// purged.
+    pub deleted_retention_days: i64,
+    /// Layout of digests whose schedule doesn't pick a theme.
+    pub digest_theme: Theme,
+    /// Broker to publish printer status to, as `mqtt://host:1883/prefix`.
+    pub mqtt_status_url: Option<String>,
+    /// Which browser origins may call the API; see [`Cors::from_env`].
+    pub cors: Cors,
+    /// Serve HTTPS rather than HTTP; see [`Tls::from_env`].
+    pub tls: Option<Tls>,
+    /// How long a printing job may take to finish when the server is stopped.
+    pub shutdown_timeout: Duration,
+    /// Largest request body accepted, short of routes with a limit of their own.
+    pub max_body_bytes: usize,
+    /// Printer whose device must have been found recently for `/health/ready`.
+    pub ready_printer: Option<ReadyPrinter>,
+}
+
+/// A printer readiness depends on; see [`ReadyPrinter::from_env`].
+#[derive(Debug, Clone, Copy)]
+pub struct ReadyPrinter {
+    pub id: i32,
+    /// How long ago its device may last have been found.
+    pub within: Duration,
+}
+
+impl ReadyPrinter {
+    /// Reads `READY_PRINTER_ID` and `READY_PRINTER_WITHIN_SECS`, 60 by default.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(id) = std::env::var("READY_PRINTER_ID")
+            .ok()
+            .filter(|v| !v.is_empty())
+        else {
+            return Ok(None);
+        };
+        let id = id
+            .parse()
+            .context("READY_PRINTER_ID must be a printer id")?;
+        let within = std::env::var("READY_PRINTER_WITHIN_SECS")
+            .ok()
+            .map(|v| {
+                v.parse()
+                    .context("READY_PRINTER_WITHIN_SECS must be a number")
+            })
+            .transpose()?
+            .unwrap_or(60);
+        Ok(Some(Self {
+            id,
+            within: Duration::from_secs(within),
+        }))
+    }
+}
+
+/// Certificate and key the server terminates TLS with.
+#[derive(Debug, Clone)]
+pub struct Tls {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+    /// Generate a self-signed certificate at the paths when neither file exists.
+    pub self_signed: bool,
+}
+
+impl Tls {
+    /// Reads `TLS_CERT` and `TLS_KEY`, PEM files, and `TLS_SELF_SIGNED`, which
+    /// keeps a generated certificate in `tls/` unless the paths say otherwise.
+    pub fn from_env() -> Result<Option<Self>> {
+        let path = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let self_signed = std::env::var("TLS_SELF_SIGNED")
+            .ok()
+            .map(|v| v.parse().context("TLS_SELF_SIGNED must be true or false"))
+            .transpose()?
+            .unwrap_or(false);
+        let (cert_path, key_path) = match (path("TLS_CERT"), path("TLS_KEY")) {
+            (Some(cert), Some(key)) => (cert, key),
+            (None, None) if self_signed => ("tls/cert.pem".into(), "tls/key.pem".into()),
+            (None, None) => return Ok(None),
+            _ => bail!("TLS_CERT and TLS_KEY must be set together"),
+        };
+        Ok(Some(Self {
+            cert_path: cert_path.into(),
+            key_path: key_path.into(),
+            self_signed,
+        }))
+    }
+}
+
+/// Cross-origin access for a frontend served from somewhere other than the API.
+#[derive(Debug, Clone, Default)]
+pub struct Cors {
+    /// Origins such as `http://dayroll.local:8080`, or `*` for any. Empty turns
+    /// cross-origin requests off.
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<Method>,
+    /// Let the browser send cookies and HTTP authentication along.
+    pub allow_credentials: bool,
+}
+
+impl Cors {
+    /// Reads `CORS_ALLOWED_ORIGINS` and `CORS_ALLOWED_METHODS`, both comma-separated,
+    /// and `CORS_ALLOW_CREDENTIALS`.
+    pub fn from_env() -> Result<Self> {
+        let list = |name: &str| -> Vec<String> {
+            std::env::var(name)
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        let allowed_origins = list("CORS_ALLOWED_ORIGINS");
+        let allowed_methods = match list("CORS_ALLOWED_METHODS") {
+            methods if methods.is_empty() => vec![
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::PATCH,
+                Method::DELETE,
+            ],
+            methods => methods
+                .iter()
+                .map(|m| {
+                    m.to_ascii_uppercase()
+                        .parse()
+                        .with_context(|| format!("CORS_ALLOWED_METHODS: {m} is not a method"))
+                })
+                .collect::<Result<_>>()?,
+        };
+        let allow_credentials = std::env::var("CORS_ALLOW_CREDENTIALS")
+            .ok()
+            .map(|v| {
+                v.parse()
+                    .context("CORS_ALLOW_CREDENTIALS must be true or false")
+            })
+            .transpose()?
+            .unwrap_or(false);
+        if allow_credentials && allowed_origins.iter().any(|o| o == "*") {
+            bail!("CORS_ALLOW_CREDENTIALS needs CORS_ALLOWED_ORIGINS to list origins, not *");
+        }
+        for origin in allowed_origins.iter().filter(|o| *o != "*") {
+            match reqwest::Url::parse(origin) {
+                Ok(url) if url.origin().ascii_serialization() == origin.trim_end_matches('/') => {}
+                _ => bail!(
+                    "CORS_ALLOWED_ORIGINS: {origin} is not an origin such as http://host:8080"
+                ),
+            }
+        }
+        Ok(Self {
+            allowed_origins,
+            allowed_methods,
+            allow_credentials,
+        })
+    }
 }
 
 impl Config {
     pub fn from_env() -> Result<Self> {
         let _ = dotenvy::dotenv();
         let bind_addr = std::env::var("BIND_ADDR").unwrap_or_else(|_| "127.0.0.1:3000".into());
+        let max_inflight_prints = std::env::var("MAX_INFLIGHT_PRINTS")
+            .ok()
+            .map(|v| v.parse().context("MAX_INFLIGHT_PRINTS must be a number"))
+            .transpose()?
+            .unwrap_or(2);
+        let queue_fairness = std::env::var("QUEUE_FAIRNESS")
+            .ok()
+            .map(|v| {
+                v.parse()
+                    .map_err(|_| anyhow::anyhow!("QUEUE_FAIRNESS must be fifo or round_robin"))
+            })
+            .transpose()?
+            .unwrap_or_default();
+        let deleted_retention_days = std::env::var("DELETED_RETENTION_DAYS")
+            .ok()
+            .map(|v| v.parse().context("DELETED_RETENTION_DAYS must be a number"))
+            .transpose()?
+            .unwrap_or(30);
+        let digest_theme = std::env::var("DIGEST_THEME")
+            .ok()
+            .map(|v| {
+                v.parse().map_err(|_| {
+                    anyhow::anyhow!("DIGEST_THEME must be compact, relaxed, boxed or retro")
+                })
+            })
+            .transpose()?
+            .unwrap_or_default();
+        let mqtt_status_url = std::env::var("MQTT_STATUS_URL")
+            .ok()
+            .filter(|v| !v.is_empty());
+        if let Some(url) = &mqtt_status_url
+            && !url.starts_with("mqtt://")
+        {
+            bail!("MQTT_STATUS_URL must start with mqtt://");
+        }
+        let max_body_bytes = std::env::var("MAX_BODY_BYTES")
+            .ok()
+            .map(|v| v.parse().context("MAX_BODY_BYTES must be a number"))
+            .transpose()?
+            .unwrap_or(1024 * 1024);
+        let shutdown_timeout = std::env::var("SHUTDOWN_TIMEOUT_SECS")
+            .ok()
+            .map(|v| v.parse().context("SHUTDOWN_TIMEOUT_SECS must be a number"))
+            .transpose()?
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(30));
 
-        Ok(Self { bind_addr })
+        Ok(Self {
+            bind_addr,
+            max_inflight_prints,
+            limits: Limits::from_env()?,
+            queue_fairness,
+            deleted_retention_days,
+            digest_theme,
+            mqtt_status_url,
+            cors: Cors::from_env()?,
+            tls: Tls::from_env()?,
+            shutdown_timeout,
+            max_body_bytes,
+            ready_printer: ReadyPrinter::from_env()?,
+        })
     }
 }
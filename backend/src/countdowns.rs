@@ -0,0 +1,88 @@
+//! Named dates to count down to, such as a holiday or an exam. A countdown is
+//! removed once its date has passed.
+
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::error::FieldError;
+use crate::schema::countdowns;
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize)]
+#[diesel(table_name = countdowns)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Countdown {
+    pub id: i32,
+    pub name: String,
+    pub target_date: NaiveDate,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Writable countdown fields, used both for creation and full (`PUT`) updates.
+#[derive(Debug, Clone, Deserialize, Insertable, AsChangeset)]
+#[diesel(table_name = countdowns)]
+pub struct NewCountdown {
+    pub name: String,
+    pub target_date: NaiveDate,
+}
+
+impl NewCountdown {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        if self.name.trim().is_empty() {
+            return Err(FieldError::new("name", "must not be empty"));
+        }
+        Ok(())
+    }
+}
+
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Countdown>> {
+    let rows = countdowns::table
+        .order((countdowns::target_date.asc(), countdowns::id.asc()))
+        .select(Countdown::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn get(conn: &mut SqliteConnection, id: i32) -> Result<Option<Countdown>> {
+    let row = countdowns::table
+        .find(id)
+        .select(Countdown::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn create(conn: &mut SqliteConnection, new: NewCountdown) -> Result<Countdown> {
+    let row = diesel::insert_into(countdowns::table)
+        .values(&new)
+        .returning(Countdown::as_returning())
+        .get_result(conn)?;
+    Ok(row)
+}
+
+pub fn update(
+    conn: &mut SqliteConnection,
+    id: i32,
+    changes: NewCountdown,
+) -> Result<Option<Countdown>> {
+    let row = diesel::update(countdowns::table.find(id))
+        .set((&changes, countdowns::updated_at.eq(Utc::now().naive_utc())))
+        .returning(Countdown::as_returning())
+        .get_result(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn delete(conn: &mut SqliteConnection, id: i32) -> Result<bool> {
+    let deleted = diesel::delete(countdowns::table.find(id)).execute(conn)?;
+    Ok(deleted > 0)
+}
+
+/// Remove the countdowns whose date is before `today`.
+pub fn purge(conn: &mut SqliteConnection, today: NaiveDate) -> Result<usize> {
+    let n = diesel::delete(countdowns::table.filter(countdowns::target_date.lt(today)))
+        .execute(conn)?;
+    Ok(n)
+}
@@ -1,21 +1,55 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use diesel::connection::SimpleConnection;
 use diesel::{Connection, SqliteConnection};
+use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
 use std::env;
+use tracing::info_span;
+
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
 
 pub fn establish_connection() -> Result<SqliteConnection> {
     let database_url = env::var("DATABASE_URL")?;
-    let conn = SqliteConnection::establish(&database_url)?;
+    let mut conn = SqliteConnection::establish(&database_url)?;
+    // Cascading deletes rely on foreign keys, which SQLite leaves off per connection.
+    conn.batch_execute("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;")?;
     Ok(conn)
 }
 
+/// Apply any pending embedded migrations. Called once at startup before serving.
+pub fn run_migrations() -> Result<()> {
+    let mut conn = establish_connection()?;
+    conn.run_pending_migrations(MIGRATIONS)
+        .map_err(|e| anyhow!("failed to run migrations: {e}"))?;
+    Ok(())
+}
+
+/// Whether some embedded migration hasn't been applied to the database.
+pub fn has_pending_migrations(conn: &mut SqliteConnection) -> Result<bool> {
+    conn.has_pending_migration(MIGRATIONS)
+        .map_err(|e| anyhow!("failed to check migrations: {e}"))
+}
+
 pub async fn run_blocking_db<T, F>(f: F) -> Result<T>
 where
     T: Send + 'static,
     F: FnOnce(&mut SqliteConnection) -> Result<T> + Send + 'static,
 {
+    // Spans don't cross into the blocking pool on their own.
+    let span = info_span!("db");
     tokio::task::spawn_blocking(move || {
+        let _span = span.enter();
         let mut conn = establish_connection()?;
         f(&mut conn)
     })
     .await?
 }
+
+/// A fresh in-memory database with every migration applied, for tests.
+#[cfg(test)]
+pub fn test_connection() -> SqliteConnection {
+    let mut conn = SqliteConnection::establish(":memory:").expect("in-memory database");
+    conn.batch_execute("PRAGMA foreign_keys = ON;")
+        .expect("foreign keys");
+    conn.run_pending_migrations(MIGRATIONS).expect("migrations");
+    conn
+}
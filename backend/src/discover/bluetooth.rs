@@ -0,0 +1,77 @@
+//! Paired Bluetooth printers, discovered through BlueZ.
+
+use anyhow::{Result, anyhow};
+use bluer::Uuid;
+
+use crate::model::{Candidate, Transport};
+
+/// Serial Port Profile, used by Bluetooth receipt printers.
+const SPP_UUID: Uuid = Uuid::from_u128(0x0000_1101_0000_1000_8000_0080_5f9b_34fb);
+
+/// List paired devices that offer SPP. They still need an `rfcomm bind` before they
+/// can be registered as a serial printer, so they are reported with low confidence.
+pub fn discover() -> Result<Vec<Candidate>> {
+    block_on(discover_async)
+}
+
+/// Check that BlueZ is reachable and has an adapter.
+pub fn probe() -> Result<()> {
+    block_on(|| async {
+        bluer::Session::new().await?.default_adapter().await?;
+        Ok(())
+    })
+}
+
+/// bluer is async; run it on its own thread so this works from any caller.
+fn block_on<T, F, Fut>(f: F) -> Result<T>
+where
+    T: Send,
+    F: FnOnce() -> Fut + Send,
+    Fut: Future<Output = Result<T>>,
+{
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?
+                .block_on(f())
+        })
+        .join()
+        .map_err(|_| anyhow!("bluetooth discovery panicked"))?
+    })
+}
+
+async fn discover_async() -> Result<Vec<Candidate>> {
+    let session = bluer::Session::new().await?;
+    let adapter = session.default_adapter().await?;
+
+    let mut out = Vec::new();
+    for address in adapter.device_addresses().await? {
+        let device = adapter.device(address)?;
+        if !device.is_paired().await? {
+            continue;
+        }
+        let has_spp = device
+            .uuids()
+            .await?
+            .is_some_and(|uuids| uuids.contains(&SPP_UUID));
+        if !has_spp {
+            continue;
+        }
+
+        out.push(Candidate {
+            transport: Transport::Bluetooth {
+                address: address.to_string(),
+            },
+            make_model: device.name().await?,
+            serial: None,
+            vid: None,
+            pid: None,
+            confidence: 30,
+            notes: vec![format!(
+                "Paired Bluetooth SPP device; bind it with `rfcomm bind <n> {address}` to print"
+            )],
+        });
+    }
+    Ok(out)
+}
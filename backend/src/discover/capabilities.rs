@@ -0,0 +1,106 @@
+//! What this binary can actually do, so the UI can hide discovery methods that
+//! would only ever return empty results.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Serialize)]
+pub struct Capabilities {
+    /// Cargo features the binary was built with.
+    pub features: Vec<&'static str>,
+    pub providers: BTreeMap<&'static str, Provider>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Provider {
+    /// Support was compiled in.
+    pub compiled: bool,
+    /// Support was compiled in and the runtime dependency (daemon, library,
+    /// subsystem) responded.
+    pub available: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl Provider {
+    /// `probe` is `None` when support wasn't compiled in, otherwise whether the
+    /// runtime dependency responded.
+    fn new(probe: Option<anyhow::Result<()>>) -> Self {
+        Self {
+            compiled: probe.is_some(),
+            available: matches!(probe, Some(Ok(()))),
+            detail: probe.and_then(Result::err).map(|e| format!("{e:#}")),
+        }
+    }
+}
+
+/// Probe every provider. Blocking: bluetooth talks to D-Bus.
+pub fn detect() -> Capabilities {
+    let mut providers = BTreeMap::new();
+    providers.insert("udev", udev());
+    providers.insert("libusb", libusb());
+    providers.insert("serial", serial());
+    providers.insert("bluetooth", bluetooth());
+    // No build has mDNS discovery or a PostgreSQL backend yet; listed so clients can
+    // rely on the keys being present.
+    providers.insert("mdns", Provider::new(None));
+    providers.insert("postgres", Provider::new(None));
+
+    Capabilities {
+        features: compiled_features(),
+        providers,
+    }
+}
+
+fn compiled_features() -> Vec<&'static str> {
+    [
+        ("linux-udev", cfg!(feature = "linux-udev")),
+        ("usb", cfg!(feature = "usb")),
+        ("serial", cfg!(feature = "serial")),
+        ("bluetooth", cfg!(feature = "bluetooth")),
+        ("bundled-sqlite", cfg!(feature = "bundled-sqlite")),
+        ("minimal", cfg!(feature = "minimal")),
+        ("otel", cfg!(feature = "otel")),
+        ("grpc", cfg!(feature = "grpc")),
+        ("web-ui", cfg!(feature = "web-ui")),
+    ]
+    .into_iter()
+    .filter_map(|(name, on)| on.then_some(name))
+    .collect()
+}
+
+fn udev() -> Provider {
+    #[cfg(all(target_os = "linux", feature = "linux-udev"))]
+    let probe = Some(
+        ::udev::Enumerator::new()
+            .and_then(|mut en| en.scan_devices().map(drop))
+            .map_err(Into::into),
+    );
+    #[cfg(not(all(target_os = "linux", feature = "linux-udev")))]
+    let probe = None;
+    Provider::new(probe)
+}
+
+fn libusb() -> Provider {
+    #[cfg(all(target_os = "linux", feature = "usb"))]
+    let probe = Some(rusb::Context::new().map(drop).map_err(Into::into));
+    #[cfg(not(all(target_os = "linux", feature = "usb")))]
+    let probe = None;
+    Provider::new(probe)
+}
+
+fn serial() -> Provider {
+    #[cfg(all(target_os = "linux", feature = "serial"))]
+    let probe = Some(serialport::available_ports().map(drop).map_err(Into::into));
+    #[cfg(not(all(target_os = "linux", feature = "serial")))]
+    let probe = None;
+    Provider::new(probe)
+}
+
+fn bluetooth() -> Provider {
+    #[cfg(all(target_os = "linux", feature = "bluetooth"))]
+    let probe = Some(super::bluetooth::probe());
+    #[cfg(not(all(target_os = "linux", feature = "bluetooth")))]
+    let probe = None;
+    Provider::new(probe)
+}
@@ -36,6 +36,15 @@ impl LinuxDiscovery {
             enrich_with_udev(&mut cands)?;
         }
 
+        #[cfg(feature = "serial")]
+        super::serial::enrich(&mut cands)?;
+
+        #[cfg(feature = "usb")]
+        super::usb::enrich(&mut cands)?;
+
+        #[cfg(feature = "bluetooth")]
+        cands.extend(super::bluetooth::discover()?);
+
         dedup_by_transport_path(&mut cands);
         cands.sort_by(|a, b| b.confidence.cmp(&a.confidence));
 
@@ -66,7 +75,7 @@ fn scan_usb_lp_nodes() -> Result<Vec<Candidate>> {
 /// Scan serial devices that are commonly used for receipt printers.
 fn scan_serial_nodes() -> Result<Vec<Candidate>> {
     let mut out = Vec::new();
-    for pat in ["/dev/ttyUSB*", "/dev/ttyACM*"] {
+    for pat in ["/dev/ttyUSB*", "/dev/ttyACM*", "/dev/rfcomm*"] {
         for entry in glob(pat)? {
             let Ok(path) = entry else { continue };
             let p = path.to_string_lossy().to_string();
@@ -150,31 +159,41 @@ fn enrich_with_udev(cands: &mut [Candidate]) -> Result<()> {
             }
         }
 
-        // Keyword heuristic: doesn't gate, only boosts confidence a bit.
-        if let Some(mm) = &cand.make_model {
-            let mm_l = mm.to_lowercase();
-            for kw in [
-                "epson", "star", "bixolon", "citizen", "sewoo", "zjiang", "xprinter", "pos",
-                "receipt", "thermal",
-            ] {
-                if mm_l.contains(kw) {
-                    cand.confidence = cand.confidence.max(70);
-                    cand.notes
-                        .push(format!("make/model contains keyword '{kw}'"));
-                    break;
-                }
-            }
-        }
+        boost_by_keywords(cand);
     }
 
     Ok(())
 }
 
+/// Without libudev, read the same identifiers straight from sysfs.
 #[cfg(not(feature = "linux-udev"))]
-fn enrich_with_udev(_cands: &mut [Candidate]) -> Result<()> {
+fn enrich_with_udev(cands: &mut [Candidate]) -> Result<()> {
+    for cand in cands.iter_mut() {
+        super::sysfs::enrich(cand);
+        boost_by_keywords(cand);
+    }
     Ok(())
 }
 
+/// Keyword heuristic: doesn't gate, only boosts confidence a bit.
+fn boost_by_keywords(cand: &mut Candidate) {
+    let Some(mm) = &cand.make_model else {
+        return;
+    };
+    let mm_l = mm.to_lowercase();
+    for kw in [
+        "epson", "star", "bixolon", "citizen", "sewoo", "zjiang", "xprinter", "pos", "receipt",
+        "thermal",
+    ] {
+        if mm_l.contains(kw) {
+            cand.confidence = cand.confidence.max(70);
+            cand.notes
+                .push(format!("make/model contains keyword '{kw}'"));
+            break;
+        }
+    }
+}
+
 /// Enumerate udev devices and build a map from devnode -> property map.
 /// We scan multiple subsystems because distros vary in where devnodes appear.
 #[cfg(feature = "linux-udev")]
@@ -1,7 +1,16 @@
 use crate::model::Candidate;
 
+#[cfg(all(target_os = "linux", feature = "bluetooth"))]
+mod bluetooth;
+pub mod capabilities;
 #[cfg(target_os = "linux")]
 mod linux;
+#[cfg(all(target_os = "linux", feature = "serial"))]
+mod serial;
+#[cfg(all(target_os = "linux", not(feature = "linux-udev")))]
+mod sysfs;
+#[cfg(all(target_os = "linux", feature = "usb"))]
+mod usb;
 
 pub trait DiscoveryProvider {
     fn discover_default(&self) -> anyhow::Result<Vec<Candidate>> {
@@ -0,0 +1,45 @@
+//! Serial port enrichment via the `serialport` crate.
+
+use anyhow::Result;
+use serialport::SerialPortType;
+
+use crate::model::Candidate;
+
+pub fn enrich(cands: &mut [Candidate]) -> Result<()> {
+    for port in serialport::available_ports()? {
+        let SerialPortType::UsbPort(info) = port.port_type else {
+            continue;
+        };
+        let Some(cand) = cands
+            .iter_mut()
+            .find(|c| c.transport_path() == Some(port.port_name.as_str()))
+        else {
+            continue;
+        };
+
+        if cand.make_model.is_none() {
+            let mm = format!(
+                "{} {}",
+                info.manufacturer.unwrap_or_default(),
+                info.product.unwrap_or_default()
+            )
+            .trim()
+            .to_string();
+            if !mm.is_empty() {
+                cand.make_model = Some(mm);
+                cand.confidence = cand.confidence.max(55);
+            }
+        }
+        if cand.serial.is_none() {
+            cand.serial = info.serial_number;
+        }
+        if cand.vid.is_none() {
+            cand.vid = Some(format!("{:04x}", info.vid));
+        }
+        if cand.pid.is_none() {
+            cand.pid = Some(format!("{:04x}", info.pid));
+        }
+        cand.notes.push("serialport: USB serial adapter".into());
+    }
+    Ok(())
+}
@@ -0,0 +1,69 @@
+//! Pure-Rust fallback for udev enrichment, reading device attributes from sysfs.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::model::Candidate;
+
+pub fn enrich(cand: &mut Candidate) {
+    let Some(name) = cand
+        .transport_path()
+        .and_then(|p| Path::new(p).file_name())
+        .map(|n| n.to_string_lossy().to_string())
+    else {
+        return;
+    };
+
+    // /sys/class/<class>/<node>/device points at the USB interface the node is bound to.
+    let Some(interface) = ["usbmisc", "tty"]
+        .iter()
+        .map(|class| PathBuf::from(format!("/sys/class/{class}/{name}/device")))
+        .find_map(|p| fs::canonicalize(p).ok())
+    else {
+        return;
+    };
+
+    if read_attr(&interface, "bInterfaceClass").as_deref() == Some("07") {
+        cand.confidence = cand.confidence.max(90);
+        cand.notes
+            .push("sysfs: bInterfaceClass indicates USB printer class (07)".into());
+    }
+
+    // Walk up to the USB device, which carries the descriptor strings.
+    let Some(device) = interface
+        .ancestors()
+        .take(4)
+        .find(|dir| dir.join("idVendor").exists())
+    else {
+        return;
+    };
+
+    if cand.make_model.is_none() {
+        let mm = format!(
+            "{} {}",
+            read_attr(device, "manufacturer").unwrap_or_default(),
+            read_attr(device, "product").unwrap_or_default()
+        )
+        .trim()
+        .to_string();
+        if !mm.is_empty() {
+            cand.make_model = Some(mm);
+            cand.confidence = cand.confidence.max(55);
+        }
+    }
+    if cand.serial.is_none() {
+        cand.serial = read_attr(device, "serial");
+    }
+    if cand.vid.is_none() {
+        cand.vid = read_attr(device, "idVendor");
+    }
+    if cand.pid.is_none() {
+        cand.pid = read_attr(device, "idProduct");
+    }
+}
+
+fn read_attr(dir: &Path, name: &str) -> Option<String> {
+    let value = fs::read_to_string(dir.join(name)).ok()?;
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
@@ -0,0 +1,39 @@
+//! libusb-based confirmation of USB printer-class devices.
+
+use anyhow::Result;
+
+use crate::model::Candidate;
+
+/// USB interface class code for printers.
+const PRINTER_CLASS: u8 = 0x07;
+
+pub fn enrich(cands: &mut [Candidate]) -> Result<()> {
+    for device in rusb::devices()?.iter() {
+        let Ok(desc) = device.device_descriptor() else {
+            continue;
+        };
+
+        let is_printer = (0..desc.num_configurations())
+            .filter_map(|i| device.config_descriptor(i).ok())
+            .any(|config| {
+                config
+                    .interfaces()
+                    .any(|iface| iface.descriptors().any(|d| d.class_code() == PRINTER_CLASS))
+            });
+        if !is_printer {
+            continue;
+        }
+
+        let vid = format!("{:04x}", desc.vendor_id());
+        let pid = format!("{:04x}", desc.product_id());
+        for cand in cands
+            .iter_mut()
+            .filter(|c| c.vid.as_deref() == Some(&vid) && c.pid.as_deref() == Some(&pid))
+        {
+            cand.confidence = cand.confidence.max(90);
+            cand.notes
+                .push("libusb: device exposes a printer-class interface".into());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,634 @@
+//! A subset of HTML, for tools that already produce HTML receipts. Paragraphs,
+//! headings, lists, rules, images and tables become blocks; unknown tags are ignored
+//! and their text kept, so a page never fails to render.
+
+use super::{Align, Block, Font, RuleStyle, Style};
+
+/// Convert HTML to blocks: `p` and `div`, `h1`-`h3` (deeper headings print as `h3`),
+/// `ul`/`ol` lists, `hr`, `img` with a base64 `data:` source, `table` (a first row of
+/// `th` cells becomes the header), `pre` in font B and `br`. A paragraph entirely in
+/// `b`/`strong` is printed bold, and one entirely in `u`/`em`/`i` underlined; `align`
+/// and `text-align` center or right-align paragraphs and headings. Links are shown
+/// as `text (url)`.
+pub fn to_blocks(html: &str) -> Vec<Block> {
+    let mut builder = Builder::default();
+    for token in tokens(html) {
+        match token {
+            Token::Text(text) => builder.text(&decode(text)),
+            Token::Open { name, attrs } => builder.open(&name, attrs),
+            Token::Close(name) => builder.close(&name),
+        }
+    }
+    builder.flush();
+    builder.blocks
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    Open { name: String, attrs: &'a str },
+    Close(String),
+}
+
+/// Split markup into text and tags, dropping comments, doctypes and the contents of
+/// `script` and `style`. A `<` that doesn't start a tag is text.
+fn tokens(html: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = html;
+    while let Some(start) = tag_start(rest) {
+        if start > 0 {
+            tokens.push(Token::Text(&rest[..start]));
+        }
+        rest = &rest[start..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        if let Some(name) = tag.strip_prefix('/') {
+            tokens.push(Token::Close(name.trim().to_ascii_lowercase()));
+            continue;
+        }
+        if tag.starts_with('!') {
+            continue;
+        }
+        let tag = tag.trim_end_matches('/');
+        let (name, attrs) = tag
+            .split_once(|c: char| c.is_ascii_whitespace())
+            .unwrap_or((tag, ""));
+        let name = name.to_ascii_lowercase();
+        if name == "script" || name == "style" {
+            let close = format!("</{name}");
+            rest = rest
+                .to_ascii_lowercase()
+                .find(&close)
+                .map_or("", |i| &rest[i..]);
+            continue;
+        }
+        tokens.push(Token::Open { name, attrs });
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+    tokens
+}
+
+/// Byte offset of the next `<` that starts a tag, comment or doctype.
+fn tag_start(text: &str) -> Option<usize> {
+    text.match_indices('<').map(|(i, _)| i).find(|i| {
+        text[i + 1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '!')
+    })
+}
+
+/// The value of attribute `name`, unquoted.
+fn attr<'a>(attrs: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attrs;
+    loop {
+        rest = rest.trim_start();
+        let key_end = rest.find(|c: char| c == '=' || c.is_ascii_whitespace())?;
+        let key = &rest[..key_end];
+        rest = rest[key_end..].trim_start();
+        let Some(after) = rest.strip_prefix('=') else {
+            continue;
+        };
+        rest = after.trim_start();
+        let value = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let end = rest[1..].find(quote).map_or(rest.len(), |i| i + 1);
+                let value = &rest[1..end];
+                rest = rest.get(end + 1..).unwrap_or("");
+                value
+            }
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_ascii_whitespace())
+                    .unwrap_or(rest.len());
+                let value = &rest[..end];
+                rest = &rest[end..];
+                value
+            }
+        };
+        if key.eq_ignore_ascii_case(name) {
+            return Some(value);
+        }
+    }
+}
+
+/// `align="..."` or a `text-align` style.
+fn align(attrs: &str) -> Align {
+    let style = attr(attrs, "style")
+        .unwrap_or("")
+        .replace(' ', "")
+        .to_ascii_lowercase();
+    let value = attr(attrs, "align")
+        .map(str::to_ascii_lowercase)
+        .or_else(|| {
+            let at = style.find("text-align:")? + "text-align:".len();
+            Some(style[at..].split(';').next()?.to_string())
+        });
+    match value.as_deref() {
+        Some("center") => Align::Center,
+        Some("right") => Align::Right,
+        _ => Align::Left,
+    }
+}
+
+/// Replace character references such as `&amp;`, `&mdash;` and `&#8217;`. Unknown
+/// ones are left as written.
+fn decode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('&') {
+        out.push_str(&rest[..at]);
+        rest = &rest[at..];
+        let name = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .map(|end| &rest[1..=end]);
+        match name.and_then(entity) {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[name.map_or(0, str::len) + 2..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "ndash" => '\u{2013}',
+        "mdash" => '\u{2014}',
+        "hellip" => '\u{2026}',
+        "lsquo" => '\u{2018}',
+        "rsquo" => '\u{2019}',
+        "ldquo" => '\u{201c}',
+        "rdquo" => '\u{201d}',
+        "bull" => '\u{2022}',
+        "middot" => '\u{b7}',
+        "deg" => '\u{b0}',
+        "times" => '\u{d7}',
+        "euro" => '\u{20ac}',
+        "pound" => '\u{a3}',
+        "copy" => '\u{a9}',
+        "reg" => '\u{ae}',
+        "trade" => '\u{2122}',
+        _ => return None,
+    })
+}
+
+/// Table being collected; the first row is the header if it was all `th` cells.
+#[derive(Default)]
+struct Table {
+    header: Option<Vec<String>>,
+    rows: Vec<Vec<String>>,
+    row: Vec<String>,
+    header_row: bool,
+    cell: Option<String>,
+}
+
+impl Table {
+    /// Cells and rows may be left unclosed, so each ends at the next one.
+    fn end_cell(&mut self) {
+        if let Some(cell) = self.cell.take() {
+            self.row.push(cell.trim().to_string());
+        }
+    }
+
+    fn end_row(&mut self) {
+        self.end_cell();
+        if self.row.is_empty() {
+            return;
+        }
+        let row = std::mem::take(&mut self.row);
+        if self.header_row && self.header.is_none() && self.rows.is_empty() {
+            self.header = Some(row);
+        } else {
+            self.rows.push(row);
+        }
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    blocks: Vec<Block>,
+    /// Text of the paragraph, heading or list item being read.
+    text: String,
+    /// List marker at the start of `text`, which doesn't count as its content.
+    marker_len: usize,
+    heading: Option<u8>,
+    align: Align,
+    pre: bool,
+    /// Open `b`/`strong` and `u`/`em`/`i` elements.
+    bold: usize,
+    underline: usize,
+    /// Some of the paragraph's text is outside bold, or underline, elements.
+    some_unbold: bool,
+    some_plain: bool,
+    /// Open lists: `None` for bullets, or the next number.
+    lists: Vec<Option<usize>>,
+    /// Targets of open links, shown after their text.
+    links: Vec<Option<String>>,
+    table: Option<Table>,
+}
+
+impl Builder {
+    fn text(&mut self, text: &str) {
+        if let Some(cell) = self.table.as_mut().and_then(|t| t.cell.as_mut()) {
+            push_collapsed(cell, text);
+            return;
+        }
+        if self.table.is_some() {
+            // Whitespace between rows and cells.
+            return;
+        }
+        if self.pre {
+            self.text.push_str(text);
+        } else {
+            push_collapsed(&mut self.text, text);
+        }
+        if !text.trim().is_empty() {
+            self.some_unbold |= self.bold == 0;
+            self.some_plain |= self.underline == 0;
+        }
+    }
+
+    fn open(&mut self, name: &str, attrs: &str) {
+        match name {
+            "p" | "div" | "section" | "article" | "header" | "footer" | "blockquote" => {
+                self.flush();
+                self.align = align(attrs);
+            }
+            "center" => {
+                self.flush();
+                self.align = Align::Center;
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.flush();
+                self.heading = Some(name.as_bytes()[1].saturating_sub(b'0').min(3));
+                self.align = align(attrs);
+            }
+            "br" => match self.table.as_mut().and_then(|t| t.cell.as_mut()) {
+                Some(cell) => cell.push(' '),
+                None => self.text.push('\n'),
+            },
+            "hr" => {
+                self.flush();
+                self.blocks.push(Block::Rule {
+                    style: RuleStyle::Dashed,
+                    character: None,
+                });
+            }
+            "b" | "strong" => self.bold += 1,
+            "u" | "ins" | "em" | "i" => self.underline += 1,
+            "a" => self.links.push(attr(attrs, "href").map(decode)),
+            "ul" | "ol" => {
+                self.flush();
+                let start = attr(attrs, "start").and_then(|s| s.parse().ok());
+                self.lists
+                    .push((name == "ol").then_some(start.unwrap_or(1)));
+            }
+            "li" => {
+                self.flush();
+                let depth = self.lists.len().max(1) - 1;
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}.", *n - 1)
+                    }
+                    _ => "*".to_string(),
+                };
+                self.text = format!("{}{marker} ", "  ".repeat(depth));
+                self.marker_len = self.text.len();
+            }
+            "img" => self.image(attrs),
+            "pre" => {
+                self.flush();
+                self.pre = true;
+            }
+            "table" => {
+                self.flush();
+                self.table = Some(Table::default());
+            }
+            "tr" => {
+                if let Some(table) = &mut self.table {
+                    table.end_row();
+                    table.header_row = true;
+                }
+            }
+            "td" | "th" => {
+                if let Some(table) = &mut self.table {
+                    table.end_cell();
+                    table.header_row &= name == "th";
+                    table.cell = Some(String::new());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn close(&mut self, name: &str) {
+        match name {
+            "p" | "div" | "section" | "article" | "header" | "footer" | "blockquote" | "center"
+            | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "li" => {
+                self.flush();
+                self.align = Align::Left;
+                self.heading = None;
+            }
+            "b" | "strong" => self.bold = self.bold.saturating_sub(1),
+            "u" | "ins" | "em" | "i" => self.underline = self.underline.saturating_sub(1),
+            "a" => {
+                if let Some(Some(href)) = self.links.pop()
+                    && !href.starts_with('#')
+                    && !href.starts_with("javascript:")
+                    && !self.text.trim_end().ends_with(&href)
+                {
+                    self.text(&format!(" ({href})"));
+                }
+            }
+            "ul" | "ol" => {
+                self.flush();
+                self.lists.pop();
+            }
+            "pre" => {
+                self.flush();
+                self.pre = false;
+            }
+            "td" | "th" => {
+                if let Some(table) = &mut self.table {
+                    table.end_cell();
+                }
+            }
+            "tr" => {
+                if let Some(table) = &mut self.table {
+                    table.end_row();
+                }
+            }
+            "table" => {
+                let Some(mut table) = self.table.take() else {
+                    return;
+                };
+                table.end_row();
+                if table.header.is_some() || !table.rows.is_empty() {
+                    self.blocks.push(Block::Table {
+                        header: table.header,
+                        rows: table.rows,
+                        columns: Vec::new(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// An embedded picture becomes an image block; others are represented by their
+    /// alt text.
+    fn image(&mut self, attrs: &str) {
+        let data = attr(attrs, "src")
+            .filter(|src| src.starts_with("data:image/"))
+            .and_then(|src| src.split_once(";base64,"))
+            .map(|(_, data)| data.trim().to_string());
+        match data {
+            Some(data) => {
+                self.flush();
+                self.blocks.push(Block::Image {
+                    data,
+                    align: Align::Center,
+                });
+            }
+            None => {
+                if let Some(alt) = attr(attrs, "alt").filter(|alt| !alt.trim().is_empty()) {
+                    self.text(&format!("[{}]", decode(alt)));
+                }
+            }
+        }
+    }
+
+    /// End the current paragraph, heading or list item.
+    fn flush(&mut self) {
+        let text = std::mem::take(&mut self.text);
+        let has_text = !text[self.marker_len.min(text.len())..].trim().is_empty();
+        let (unbold, plain) = (self.some_unbold, self.some_plain);
+        self.marker_len = 0;
+        self.some_unbold = false;
+        self.some_plain = false;
+        if !has_text {
+            return;
+        }
+
+        if self.pre {
+            self.blocks.push(Block::Text {
+                text: text.trim_matches('\n').to_string(),
+                spans: Vec::new(),
+                style: Style {
+                    font: Font::B,
+                    ..Style::default()
+                },
+            });
+            return;
+        }
+        let text = text
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim_start_matches('\n')
+            .trim_end()
+            .to_string();
+        let block = match self.heading {
+            Some(level) => Block::Heading {
+                text,
+                level: level.max(1),
+                align: self.align,
+            },
+            None => Block::Text {
+                text,
+                spans: Vec::new(),
+                style: Style {
+                    bold: !unbold,
+                    underline: !plain,
+                    align: self.align,
+                    ..Style::default()
+                },
+            },
+        };
+        self.blocks.push(block);
+    }
+}
+
+/// Append text with runs of whitespace, including line breaks, collapsed to one space
+/// and no space at the start of a line.
+fn push_collapsed(out: &mut String, text: &str) {
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !out.is_empty() && !out.ends_with([' ', '\n']) {
+                out.push(' ');
+            }
+        } else {
+            out.push(c);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn styled(text: &str, style: Style) -> Block {
+        Block::Text {
+            text: text.into(),
+            spans: Vec::new(),
+            style,
+        }
+    }
+
+    #[test]
+    fn headings_and_paragraphs_keep_their_alignment() {
+        let html = r#"<h1 style="text-align: center">Menu &amp; more</h1>
+            <p align=right>Line one<br>line   two</p>
+            <h5>Small print</h5>"#;
+        assert_eq!(
+            to_blocks(html),
+            [
+                Block::Heading {
+                    text: "Menu & more".into(),
+                    level: 1,
+                    align: Align::Center,
+                },
+                styled(
+                    "Line one\nline two",
+                    Style {
+                        align: Align::Right,
+                        ..Style::default()
+                    }
+                ),
+                Block::Heading {
+                    text: "Small print".into(),
+                    level: 3,
+                    align: Align::Left,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn only_wholly_emphasised_paragraphs_are_styled() {
+        let html = "<p><strong>All bold</strong></p><p><b>Half</b> plain</p><p><em>Aside</em></p>";
+        assert_eq!(
+            to_blocks(html),
+            [
+                styled(
+                    "All bold",
+                    Style {
+                        bold: true,
+                        ..Style::default()
+                    }
+                ),
+                Block::text("Half plain"),
+                styled(
+                    "Aside",
+                    Style {
+                        underline: true,
+                        ..Style::default()
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_lists_are_indented_and_numbered() {
+        let html = r#"<ul><li>milk<li>eggs<ol start="3"><li>three</li><li>four</li></ol></ul>"#;
+        assert_eq!(
+            to_blocks(html),
+            [
+                Block::text("* milk"),
+                Block::text("* eggs"),
+                Block::text("  3. three"),
+                Block::text("  4. four"),
+            ]
+        );
+    }
+
+    #[test]
+    fn links_and_images() {
+        let html = r##"<p>See <a href="https://example.com/docs">docs</a>, <a href="#top">top</a>
+            and <a href="https://example.com">https://example.com</a></p>
+            <img src="data:image/png;base64, iVBORw0K " alt="logo">
+            <img src="logo.png" alt="Logo">"##;
+        assert_eq!(
+            to_blocks(html),
+            [
+                Block::text("See docs (https://example.com/docs), top and https://example.com"),
+                Block::Image {
+                    data: "iVBORw0K".into(),
+                    align: Align::Center,
+                },
+                Block::text("[Logo]"),
+            ]
+        );
+    }
+
+    #[test]
+    fn tables_take_a_header_from_a_row_of_th_cells() {
+        let html = "<table><tr><th>Item<th>Qty</tr><tr><td>Tea</td><td>2</td></tr>\
+                    <tr><td>Milk<br>semi</td><td>1</td></tr></table>";
+        assert_eq!(
+            to_blocks(html),
+            [Block::Table {
+                header: Some(vec!["Item".into(), "Qty".into()]),
+                rows: vec![
+                    vec!["Tea".into(), "2".into()],
+                    vec!["Milk semi".into(), "1".into()],
+                ],
+                columns: Vec::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn preformatted_text_keeps_its_spacing() {
+        assert_eq!(
+            to_blocks("<pre>\n  a  b\n c\n</pre>"),
+            [styled(
+                "  a  b\n c",
+                Style {
+                    font: Font::B,
+                    ..Style::default()
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn scripts_comments_and_entities() {
+        let html = r#"<!DOCTYPE html><style>p { color: red }</style>
+            <script>alert("<p>x</p>")</script><!-- <p>hidden</p> -->
+            1 < 2 &lt; 3 &#8217;&#x2019; &bogus;"#;
+        assert_eq!(
+            to_blocks(html),
+            [Block::text("1 < 2 < 3 \u{2019}\u{2019} &bogus;")]
+        );
+    }
+}
@@ -0,0 +1,319 @@
+//! A pragmatic Markdown subset for quick notes. Anything not recognised prints as
+//! plain text, so a note never fails to render.
+
+use super::{Block, Font, RuleStyle, Style};
+
+/// Convert Markdown to blocks: `#` headings, `-`/`*`/`1.` lists, `---` rules, fenced
+/// code in font B and paragraphs. A paragraph wrapped entirely in `**` or `__` is
+/// printed bold, and one wrapped in `*` or `_` underlined; other emphasis markers and
+/// inline code ticks are dropped.
+pub fn to_blocks(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph = String::new();
+    let mut code: Option<Vec<&str>> = None;
+
+    for line in text.lines() {
+        if let Some(lines) = &mut code {
+            if line.trim_start().starts_with("```") {
+                blocks.push(Block::Text {
+                    text: lines.join("\n"),
+                    spans: Vec::new(),
+                    style: Style {
+                        font: Font::B,
+                        ..Style::default()
+                    },
+                });
+                code = None;
+            } else {
+                lines.push(line);
+            }
+            continue;
+        }
+
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            flush(&mut blocks, &mut paragraph);
+            code = Some(Vec::new());
+        } else if trimmed.is_empty() {
+            flush(&mut blocks, &mut paragraph);
+            if !blocks.is_empty() && !matches!(blocks.last(), Some(Block::Feed { .. })) {
+                blocks.push(Block::Feed { lines: 1 });
+            }
+        } else if let Some((level, title)) = heading(trimmed) {
+            flush(&mut blocks, &mut paragraph);
+            blocks.push(Block::Heading {
+                text: strip_inline(title),
+                level,
+                align: Default::default(),
+            });
+        } else if is_rule(trimmed) {
+            flush(&mut blocks, &mut paragraph);
+            blocks.push(Block::Rule {
+                style: RuleStyle::Dashed,
+                character: None,
+            });
+        } else if let Some(item) = list_item(line) {
+            flush(&mut blocks, &mut paragraph);
+            blocks.push(Block::text(item));
+        } else {
+            let hard_break = line.ends_with("  ") || line.ends_with('\\');
+            let content = trimmed
+                .trim_start_matches('>')
+                .trim()
+                .trim_end_matches('\\');
+            if !paragraph.is_empty() && !paragraph.ends_with('\n') {
+                paragraph.push(' ');
+            }
+            paragraph.push_str(content);
+            if hard_break {
+                paragraph.push('\n');
+            }
+        }
+    }
+    if let Some(lines) = code {
+        // Unterminated fence: print what there is.
+        blocks.push(Block::text(lines.join("\n")));
+    }
+    flush(&mut blocks, &mut paragraph);
+    if matches!(blocks.last(), Some(Block::Feed { .. })) {
+        blocks.pop();
+    }
+    blocks
+}
+
+fn flush(blocks: &mut Vec<Block>, paragraph: &mut String) {
+    if paragraph.is_empty() {
+        return;
+    }
+    let text = std::mem::take(paragraph);
+    let text = text.trim_end();
+
+    let (style, inner) = if let Some(inner) = wrapped(text, "**").or_else(|| wrapped(text, "__")) {
+        (
+            Style {
+                bold: true,
+                ..Style::default()
+            },
+            inner,
+        )
+    } else if let Some(inner) = wrapped(text, "*").or_else(|| wrapped(text, "_")) {
+        (
+            Style {
+                underline: true,
+                ..Style::default()
+            },
+            inner,
+        )
+    } else {
+        (Style::default(), text)
+    };
+    blocks.push(Block::Text {
+        text: strip_inline(inner),
+        spans: Vec::new(),
+        style,
+    });
+}
+
+/// `text` without the `marker` it is entirely wrapped in.
+fn wrapped<'a>(text: &'a str, marker: &str) -> Option<&'a str> {
+    let inner = text.strip_prefix(marker)?.strip_suffix(marker)?;
+    (!inner.is_empty() && !inner.contains(marker)).then_some(inner)
+}
+
+fn heading(line: &str) -> Option<(u8, &str)> {
+    let hashes = line.bytes().take_while(|b| *b == b'#').count();
+    if !(1..=6).contains(&hashes) {
+        return None;
+    }
+    let title = line[hashes..].strip_prefix(' ')?;
+    Some((
+        hashes.min(3) as u8,
+        title.trim().trim_end_matches('#').trim_end(),
+    ))
+}
+
+fn is_rule(line: &str) -> bool {
+    let marks: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3 && ['-', '*', '_'].contains(&marks[0]) && marks.iter().all(|c| *c == marks[0])
+}
+
+/// A list item as a printed line: bullets become `*`, numbers are kept and nesting
+/// is indented two spaces per level.
+fn list_item(line: &str) -> Option<String> {
+    let indent = line.len() - line.trim_start().len();
+    let rest = line.trim_start();
+    let (marker, item) = if let Some(item) = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| rest.strip_prefix(bullet))
+    {
+        ("*".to_string(), item)
+    } else {
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        let item = rest[digits..].strip_prefix(". ").filter(|_| digits > 0)?;
+        (format!("{}.", &rest[..digits]), item)
+    };
+    Some(format!(
+        "{}{marker} {}",
+        " ".repeat(indent / 2 * 2),
+        strip_inline(item)
+    ))
+}
+
+/// Drop emphasis markers and inline code ticks, and show links as `text (url)`.
+fn strip_inline(text: &str) -> String {
+    let mut out = text.replace("**", "").replace("__", "").replace('`', "");
+    let mut from = 0;
+    while let Some(open) = out[from..].find('[').map(|i| from + i) {
+        let link = out[open..].find("](").map(|i| open + i).and_then(|close| {
+            let end = out[close..].find(')').map(|i| close + i)?;
+            (!out[open + 1..close].contains('[')).then_some((close, end))
+        });
+        let Some((close, end)) = link else {
+            from = open + 1;
+            continue;
+        };
+        let label = &out[open + 1..close];
+        let url = &out[close + 2..end];
+        let link = if label == url || label.is_empty() {
+            url.to_string()
+        } else {
+            format!("{label} ({url})")
+        };
+        out.replace_range(open..=end, &link);
+        from = open + link.len();
+    }
+    strip_stars(&out)
+}
+
+/// Remove `*` used for emphasis, keeping ones that stand alone, e.g. `2 * 3`.
+fn strip_stars(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    chars
+        .iter()
+        .enumerate()
+        .filter(|(i, c)| {
+            if **c != '*' {
+                return true;
+            }
+            let before = i.checked_sub(1).map(|j| chars[j]);
+            let after = chars.get(i + 1).copied();
+            before.is_none_or(char::is_whitespace) && after.is_none_or(char::is_whitespace)
+        })
+        .map(|(_, c)| *c)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Align;
+
+    fn styled(text: &str, style: Style) -> Block {
+        Block::Text {
+            text: text.into(),
+            spans: Vec::new(),
+            style,
+        }
+    }
+
+    #[test]
+    fn headings_lists_and_rules() {
+        let text = "# Shopping ##\n- milk\n  * **eggs**\n10. bread\n----";
+        assert_eq!(
+            to_blocks(text),
+            [
+                Block::Heading {
+                    text: "Shopping".into(),
+                    level: 1,
+                    align: Align::default(),
+                },
+                Block::text("* milk"),
+                Block::text("  * eggs"),
+                Block::text("10. bread"),
+                Block::Rule {
+                    style: RuleStyle::Dashed,
+                    character: None,
+                },
+            ]
+        );
+        assert_eq!(
+            to_blocks("#hashtag"),
+            [Block::text("#hashtag")],
+            "a heading needs a space after its marks"
+        );
+    }
+
+    #[test]
+    fn paragraphs_join_their_lines_until_a_blank_line() {
+        let text = "one\ntwo  \nthree\n\n\nfour\n\n";
+        assert_eq!(
+            to_blocks(text),
+            [
+                Block::text("one two\nthree"),
+                Block::Feed { lines: 1 },
+                Block::text("four"),
+            ]
+        );
+    }
+
+    #[test]
+    fn wrapped_paragraphs_are_bold_or_underlined() {
+        assert_eq!(
+            to_blocks("**Take the bins out**"),
+            [styled(
+                "Take the bins out",
+                Style {
+                    bold: true,
+                    ..Style::default()
+                }
+            )]
+        );
+        assert_eq!(
+            to_blocks("_later_"),
+            [styled(
+                "later",
+                Style {
+                    underline: true,
+                    ..Style::default()
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn inline_markers_are_dropped_and_links_spelled_out() {
+        assert_eq!(
+            to_blocks("a *b* `c` and 2 * 3"),
+            [Block::text("a b c and 2 * 3")]
+        );
+        assert_eq!(
+            to_blocks(
+                "[docs](https://example.com/docs) or [https://example.com](https://example.com)"
+            ),
+            [Block::text(
+                "docs (https://example.com/docs) or https://example.com"
+            )]
+        );
+    }
+
+    #[test]
+    fn fenced_code_prints_in_font_b() {
+        assert_eq!(
+            to_blocks("```sh\n  make   all\n```\nafter"),
+            [
+                styled(
+                    "  make   all",
+                    Style {
+                        font: Font::B,
+                        ..Style::default()
+                    }
+                ),
+                Block::text("after"),
+            ]
+        );
+        assert_eq!(
+            to_blocks("```\nunterminated"),
+            [Block::text("unterminated")]
+        );
+    }
+}
@@ -0,0 +1,661 @@
+//! Structured receipts. Every print API, template and integration produces a
+//! [`Document`], and the renderers in [`crate::render`] turn it into printer commands.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::Local;
+use qrcode::QrCode;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use utoipa::ToSchema;
+
+use crate::error::FieldError;
+use crate::render::COLUMNS;
+use crate::render::preview::PAPER_WIDTH_DOTS;
+
+pub mod html;
+pub mod markdown;
+
+/// Blocks printed around every job, such as a logo and title or a "printed at"
+/// line. `{date}` and `{time}` in their text become the local date and time.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Fragment(pub Vec<Block>);
+
+impl Fragment {
+    /// The first invalid block, by its index.
+    pub fn validate(&self) -> Result<(), FieldError> {
+        for (i, block) in self.0.iter().enumerate() {
+            block
+                .validate()
+                .map_err(|e| FieldError::new(i.to_string(), e))?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Fragment> for String {
+    fn from(fragment: Fragment) -> Self {
+        serde_json::to_string(&fragment).unwrap_or_else(|_| "[]".into())
+    }
+}
+
+/// Number of blocks before the closing run of cuts, feeds and spaces.
+pub fn body_len(blocks: &[Block]) -> usize {
+    let tail = blocks
+        .iter()
+        .rev()
+        .take_while(|b| {
+            matches!(
+                b,
+                Block::Cut { .. } | Block::Feed { .. } | Block::Space { .. }
+            )
+        })
+        .count();
+    blocks.len() - tail
+}
+
+/// A receipt, printed top to bottom.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Document {
+    pub blocks: Vec<Block>,
+    /// Print upside down and last block first, so a printer whose paper feeds
+    /// downwards, such as one mounted on a wall, prints the receipt readable as it
+    /// comes out.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub rotate_180: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Block {
+    /// One or more lines of text; each newline starts a new printed line.
+    Text {
+        #[serde(default)]
+        text: String,
+        /// Runs of text in their own bold, underline or reverse, continuing the
+        /// line after `text`.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        spans: Vec<Span>,
+        #[serde(flatten)]
+        style: Style,
+    },
+    /// A title. Level 1 prints double size, 2 bold and underlined, 3 bold.
+    Heading {
+        text: String,
+        #[serde(default = "default_level")]
+        level: u8,
+        #[serde(default)]
+        align: Align,
+    },
+    /// Large type drawn from a TrueType font and printed as an image, such as the date
+    /// across the top of the daily sheet.
+    Banner {
+        text: String,
+        /// Height of each line in dots, 8 to the millimetre.
+        #[serde(default = "default_banner_height")]
+        height: u16,
+        #[serde(default = "centered")]
+        align: Align,
+        /// Run the text down the length of the paper instead of across it, for long
+        /// party banners. Lines then lie side by side across the paper.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        vertical: bool,
+    },
+    /// An uploaded glyph or built-in icon, such as a checkbox or weather icon,
+    /// followed by text on the same line.
+    Glyph {
+        name: String,
+        #[serde(default)]
+        text: String,
+    },
+    /// A line across the full width of the paper.
+    Rule {
+        #[serde(default)]
+        style: RuleStyle,
+        /// Draw the line with this character instead.
+        #[serde(default, rename = "char", skip_serializing_if = "Option::is_none")]
+        character: Option<char>,
+    },
+    Qr {
+        data: String,
+        /// Module size in dots, 1-16.
+        #[serde(default = "default_qr_size")]
+        size: u8,
+        #[serde(default = "centered")]
+        align: Align,
+    },
+    Barcode {
+        data: String,
+        #[serde(default)]
+        symbology: Symbology,
+        /// Bar height in dots, 1-255.
+        #[serde(default = "default_barcode_height")]
+        height: u8,
+        /// Narrow bar width in dots, 2-6.
+        #[serde(default = "default_barcode_width")]
+        width: u8,
+        /// Where the human-readable digits are printed.
+        #[serde(default)]
+        hri: Hri,
+        #[serde(default = "centered")]
+        align: Align,
+    },
+    /// A base64-encoded PNG, scaled down to the paper width if it is wider.
+    Image {
+        data: String,
+        #[serde(default = "centered")]
+        align: Align,
+    },
+    /// Rows of cells laid out in columns across the paper.
+    Table {
+        #[serde(default)]
+        header: Option<Vec<String>>,
+        rows: Vec<Vec<String>>,
+        /// Layout of each column, in order; columns without one size to their
+        /// contents.
+        #[serde(default)]
+        columns: Vec<Column>,
+    },
+    /// An area of the paper with text placed at exact positions, for side-by-side
+    /// columns and labels. Printers without page mode print the text on a grid of
+    /// character cells instead, as close to the positions as the grid allows.
+    Page {
+        /// Height of the area in dots.
+        height: u16,
+        items: Vec<Placed>,
+    },
+    Cut {
+        /// Leave a small hinge so the slip doesn't fall.
+        #[serde(default)]
+        partial: bool,
+    },
+    /// Blank paper, in lines.
+    Feed {
+        #[serde(default = "default_feed")]
+        lines: u8,
+    },
+    /// Blank paper in dots, 8 to the millimetre, for finer spacing than whole lines.
+    Space { dots: u16 },
+}
+
+fn default_level() -> u8 {
+    1
+}
+
+/// Tallest page most printers can lay out in one go.
+const MAX_PAGE_HEIGHT: u16 = 1600;
+
+fn default_banner_height() -> u16 {
+    96
+}
+
+fn default_qr_size() -> u8 {
+    6
+}
+
+fn default_barcode_height() -> u8 {
+    80
+}
+
+fn default_barcode_width() -> u8 {
+    3
+}
+
+fn default_feed() -> u8 {
+    1
+}
+
+fn centered() -> Align {
+    Align::Center
+}
+
+/// Formatting for a text block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct Style {
+    pub bold: bool,
+    pub underline: bool,
+    /// Character width and height multiplier, 1-8.
+    pub size: u8,
+    pub align: Align,
+    /// White text on black.
+    pub invert: bool,
+    pub font: Font,
+    /// What happens to lines longer than the paper is wide.
+    pub overflow: Overflow,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            bold: false,
+            underline: false,
+            size: 1,
+            align: Align::Left,
+            invert: false,
+            font: Font::A,
+            overflow: Overflow::Wrap,
+        }
+    }
+}
+
+/// Text at a position on a page, in dots from the page's top left corner.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Placed {
+    pub x: u16,
+    pub y: u16,
+    /// Width to wrap and align the text in; the rest of the line if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u16>,
+    pub text: String,
+    #[serde(flatten)]
+    pub style: Style,
+}
+
+/// Part of a line styled differently from the rest of its text block, such as a
+/// bold time or an inverted "CANCELLED" tag. Unset attributes are the block's.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Span {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub underline: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invert: Option<bool>,
+}
+
+impl Placed {
+    fn validate(&self, height: u16) -> Result<(), String> {
+        if !(1..=8).contains(&self.style.size) {
+            return Err("size must be between 1 and 8".into());
+        }
+        if u32::from(self.x) >= PAPER_WIDTH_DOTS || self.y >= height {
+            return Err("position must be inside the page".into());
+        }
+        if self.width == Some(0) {
+            return Err("width must be positive".into());
+        }
+        Ok(())
+    }
+}
+
+impl Span {
+    /// The block's `style` with this span's attributes applied.
+    pub fn style(&self, style: &Style) -> Style {
+        Style {
+            bold: self.bold.unwrap_or(style.bold),
+            underline: self.underline.unwrap_or(style.underline),
+            invert: self.invert.unwrap_or(style.invert),
+            ..*style
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Font A is 12x24 dots, 48 characters per line; font B is a condensed 9x17.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Font {
+    #[default]
+    A,
+    B,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Overflow {
+    /// Continue on the next line, breaking between words.
+    #[default]
+    Wrap,
+    /// Cut the line off at the paper edge.
+    Truncate,
+    /// Cut the line off and end it with `...`.
+    Ellipsis,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleStyle {
+    /// A row of `-` characters.
+    #[default]
+    Dashed,
+    /// An unbroken bar.
+    Solid,
+    /// Two thin bars.
+    Double,
+}
+
+/// How a table column is sized and how its cells sit in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct Column {
+    pub width: ColumnWidth,
+    pub align: Align,
+    /// What happens to cells wider than the column; wrapped cells make the row taller.
+    pub overflow: Overflow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnWidth {
+    /// As wide as the widest cell, narrowed first when the row doesn't fit.
+    #[default]
+    Auto,
+    /// Exactly this many characters.
+    Fixed(usize),
+    /// A share of the width left over by the other columns, relative to the other
+    /// weighted columns.
+    Weight(usize),
+}
+
+/// How the `text` of a plain payload is interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+pub enum ContentType {
+    #[default]
+    #[serde(rename = "text/plain")]
+    Plain,
+    #[serde(rename = "text/markdown")]
+    Markdown,
+    #[serde(rename = "text/html")]
+    Html,
+}
+
+impl ContentType {
+    pub fn is_plain(&self) -> bool {
+        *self == ContentType::Plain
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Hri {
+    None,
+    Above,
+    #[default]
+    Below,
+    Both,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Symbology {
+    /// Printable ASCII, up to 250 characters.
+    Code128,
+    Ean13,
+    Ean8,
+    Upca,
+    Upce,
+    /// Uppercase letters, digits and ` -.$/+%`.
+    #[default]
+    Code39,
+    /// An even number of digits.
+    Itf,
+    Codabar,
+}
+
+impl Symbology {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Symbology::Code128 => "code128",
+            Symbology::Ean13 => "ean13",
+            Symbology::Ean8 => "ean8",
+            Symbology::Upca => "upca",
+            Symbology::Upce => "upce",
+            Symbology::Code39 => "code39",
+            Symbology::Itf => "itf",
+            Symbology::Codabar => "codabar",
+        }
+    }
+
+    fn validate(&self, data: &str) -> Result<(), String> {
+        let digits =
+            |lens: &[usize]| data.bytes().all(|b| b.is_ascii_digit()) && lens.contains(&data.len());
+        let ok = match self {
+            Symbology::Code128 => {
+                // '{' is escaped on the wire, so it counts twice.
+                let encoded = data.len() + data.matches('{').count();
+                (1..=250).contains(&encoded) && data.bytes().all(|b| (b' '..=b'~').contains(&b))
+            }
+            Symbology::Ean13 => digits(&[12, 13]),
+            Symbology::Ean8 => digits(&[7, 8]),
+            Symbology::Upca => digits(&[11, 12]),
+            Symbology::Upce => digits(&[6, 7, 8, 11, 12]),
+            Symbology::Code39 => {
+                !data.is_empty()
+                    && data.bytes().all(|b| {
+                        b.is_ascii_uppercase() || b.is_ascii_digit() || b" -.$/+%".contains(&b)
+                    })
+            }
+            Symbology::Itf => {
+                !data.is_empty()
+                    && data.len().is_multiple_of(2)
+                    && data.bytes().all(|b| b.is_ascii_digit())
+            }
+            Symbology::Codabar => {
+                !data.is_empty()
+                    && data
+                        .bytes()
+                        .all(|b| b.is_ascii_digit() || b"-$:/.+ABCDabcd".contains(&b))
+            }
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(format!("'{data}' is not valid {} data", self.as_str()))
+        }
+    }
+}
+
+impl Block {
+    /// Unstyled text.
+    pub fn text(text: impl Into<String>) -> Self {
+        Block::Text {
+            text: text.into(),
+            spans: Vec::new(),
+            style: Style::default(),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            Block::Text { style, .. } if !(1..=8).contains(&style.size) => {
+                Err("size must be between 1 and 8".into())
+            }
+            Block::Heading { level, .. } if !(1..=3).contains(level) => {
+                Err("heading level must be 1, 2 or 3".into())
+            }
+            Block::Banner { text, .. } if text.trim().is_empty() => {
+                Err("banner text must not be empty".into())
+            }
+            Block::Banner {
+                text,
+                height,
+                vertical: true,
+                ..
+            } if *height < 24
+                || u32::from(*height) * text.lines().count() as u32 > PAPER_WIDTH_DOTS =>
+            {
+                Err(format!(
+                    "vertical banner lines must be at least 24 dots and together at most {PAPER_WIDTH_DOTS}"
+                ))
+            }
+            Block::Banner {
+                height,
+                vertical: false,
+                ..
+            } if !(24..=480).contains(height) => {
+                Err("banner height must be between 24 and 480".into())
+            }
+            Block::Glyph { name, .. } if name.is_empty() => {
+                Err("glyph name must not be empty".into())
+            }
+            Block::Glyph { text, .. } if text.contains('\n') => {
+                Err("glyph text must be a single line".into())
+            }
+            Block::Qr { data, .. } if data.is_empty() => Err("qr data must not be empty".into()),
+            Block::Qr { size, .. } if !(1..=16).contains(size) => {
+                Err("qr size must be between 1 and 16".into())
+            }
+            Block::Qr { data, .. } => QrCode::new(data)
+                .map(|_| ())
+                .map_err(|e| format!("qr data cannot be encoded: {e}")),
+            Block::Barcode { width, .. } if !(2..=6).contains(width) => {
+                Err("barcode width must be between 2 and 6".into())
+            }
+            Block::Barcode { height: 0, .. } => Err("barcode height must be positive".into()),
+            Block::Barcode {
+                data, symbology, ..
+            } => symbology.validate(data),
+            Block::Image { data, .. } => {
+                let bytes = BASE64
+                    .decode(data)
+                    .map_err(|e| format!("image is not valid base64: {e}"))?;
+                image::ImageReader::new(Cursor::new(bytes))
+                    .with_guessed_format()
+                    .map_err(|e| e.to_string())?
+                    .into_dimensions()
+                    .map_err(|e| format!("image could not be read: {e}"))?;
+                Ok(())
+            }
+            Block::Rule {
+                character: Some(c), ..
+            } if !c.is_ascii_graphic() => {
+                Err("rule char must be a printable ASCII character".into())
+            }
+            Block::Page { height, .. } if !(1..=MAX_PAGE_HEIGHT).contains(height) => Err(format!(
+                "page height must be between 1 and {MAX_PAGE_HEIGHT}"
+            )),
+            Block::Page { height, items } => {
+                for (i, item) in items.iter().enumerate() {
+                    item.validate(*height)
+                        .map_err(|e| format!("item {i}: {e}"))?;
+                }
+                Ok(())
+            }
+            Block::Table { header, rows, .. } if rows.is_empty() && header.is_none() => {
+                Err("table has no rows".into())
+            }
+            Block::Table { columns, .. } => {
+                for (i, column) in columns.iter().enumerate() {
+                    if let ColumnWidth::Fixed(n) | ColumnWidth::Weight(n) = column.width
+                        && !(1..=COLUMNS).contains(&n)
+                    {
+                        return Err(format!("column {i}: width must be between 1 and {COLUMNS}"));
+                    }
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Document {
+    /// Lines of plain text followed by a cut, or by a blank line if `cut` is false.
+    pub fn from_text(text: impl Into<String>, cut: bool) -> Self {
+        Self::from_content(text.into(), ContentType::Plain, cut)
+    }
+
+    /// Text of the given content type, ended like [`Document::from_text`].
+    pub fn from_content(text: String, content_type: ContentType, cut: bool) -> Self {
+        let mut blocks = match content_type {
+            ContentType::Plain => vec![Block::text(text)],
+            ContentType::Markdown => markdown::to_blocks(&text),
+            ContentType::Html => html::to_blocks(&text),
+        };
+        blocks.push(if cut {
+            Block::Cut { partial: false }
+        } else {
+            Block::Feed { lines: 1 }
+        });
+        Self {
+            blocks,
+            rotate_180: false,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), FieldError> {
+        for (i, block) in self.blocks.iter().enumerate() {
+            block
+                .validate()
+                .map_err(|e| FieldError::new(format!("blocks.{i}"), e))?;
+        }
+        Ok(())
+    }
+
+    /// Put `header` before the body and `footer` after it, ahead of the closing cut
+    /// or feed, filling in their `{date}` and `{time}`.
+    pub fn frame(&mut self, header: Fragment, footer: Fragment) {
+        if header.0.is_empty() && footer.0.is_empty() {
+            return;
+        }
+        let now = Local::now();
+        let date = now.format("%a %-d %b %Y").to_string();
+        let time = now.format("%H:%M").to_string();
+        let expand = |fragment: Fragment| {
+            let mut blocks = Document {
+                blocks: fragment.0,
+                ..Document::default()
+            };
+            blocks.map_text(|text| text.replace("{date}", &date).replace("{time}", &time));
+            blocks.blocks
+        };
+        let end = body_len(&self.blocks);
+        self.blocks.splice(end..end, expand(footer));
+        self.blocks.splice(0..0, expand(header));
+    }
+
+    /// Rewrite all printed prose: text, headings and table cells. Machine-read data
+    /// such as QR and barcode contents is left alone.
+    pub fn map_text(&mut self, f: impl Fn(&str) -> String) {
+        for block in &mut self.blocks {
+            match block {
+                Block::Text { text, spans, .. } => {
+                    *text = f(text);
+                    for span in spans {
+                        span.text = f(&span.text);
+                    }
+                }
+                Block::Heading { text, .. }
+                | Block::Banner { text, .. }
+                | Block::Glyph { text, .. } => *text = f(text),
+                Block::Page { items, .. } => {
+                    for item in items {
+                        item.text = f(&item.text);
+                    }
+                }
+                Block::Table { header, rows, .. } => {
+                    for cell in header.iter_mut().flatten().chain(rows.iter_mut().flatten()) {
+                        *cell = f(cell);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Names of the glyphs the document uses, each once, in order of appearance.
+    pub fn glyph_names(&self) -> Vec<&str> {
+        let used = self.blocks.iter().filter_map(|block| match block {
+            Block::Glyph { name, .. } => Some(name.as_str()),
+            _ => None,
+        });
+        let mut names = Vec::new();
+        for name in used {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+}
@@ -0,0 +1,180 @@
+use axum::Json;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use std::fmt;
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::queue::ShuttingDown;
+use crate::render::limits::LimitExceeded;
+use crate::telemetry;
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+/// Error returned by route handlers, each kind with its own status and
+/// [`code`](ApiError::code). Errors bubbled up with `?` are reported as a 500,
+/// except for those with a kind of their own, such as [`LimitExceeded`] and
+/// [`ShuttingDown`].
+#[derive(Debug)]
+pub enum ApiError {
+    NotFound,
+    /// No API key, or one that isn't recognised.
+    Unauthorized,
+    /// A key whose scope doesn't cover the endpoint, such as a print key calling
+    /// an administrative one.
+    Forbidden,
+    BadRequest(String),
+    Conflict(String),
+    /// Input that fails validation field by field, reported as a 422 listing each
+    /// field's error so a form can show them next to the fields.
+    Invalid(Vec<FieldError>),
+    /// A job over the configured size limits.
+    TooLarge(String),
+    /// The printer exists but can't print, such as when it's disabled.
+    PrinterUnavailable(String),
+    /// A service an integration fetches from failed or refused.
+    Upstream(String),
+    /// The server is stopping and takes no more jobs.
+    ShuttingDown(String),
+    Internal(anyhow::Error),
+}
+
+impl ApiError {
+    /// What went wrong, for clients to tell errors apart by without parsing the
+    /// message.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::NotFound => "not_found",
+            ApiError::Unauthorized => "unauthorized",
+            ApiError::Forbidden => "forbidden",
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::Invalid(_) => "invalid",
+            ApiError::TooLarge(_) => "too_large",
+            ApiError::PrinterUnavailable(_) => "printer_unavailable",
+            ApiError::Upstream(_) => "upstream_failed",
+            ApiError::ShuttingDown(_) => "shutting_down",
+            ApiError::Internal(_) => "internal",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::NotFound => StatusCode::NOT_FOUND,
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden => StatusCode::FORBIDDEN,
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Conflict(_) | ApiError::PrinterUnavailable(_) => StatusCode::CONFLICT,
+            ApiError::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            ApiError::ShuttingDown(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// A field of the input that is invalid, and why. Routes report it as a 422.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FieldError {
+    /// Dotted path to the field, such as `items.2.name` or `settings.teams.0.league`.
+    pub field: String,
+    /// What is wrong, worded to follow the field's name: `must not be empty`.
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+
+    /// The same error, for a field nested under `parent`.
+    pub fn within(self, parent: &str) -> Self {
+        let field = if self.field.is_empty() {
+            parent.to_string()
+        } else {
+            format!("{parent}.{}", self.field)
+        };
+        Self { field, ..self }
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.field, self.message)
+    }
+}
+
+impl From<FieldError> for ApiError {
+    fn from(err: FieldError) -> Self {
+        ApiError::Invalid(vec![err])
+    }
+}
+
+/// The body of every error response.
+#[derive(Serialize, ToSchema)]
+pub struct ErrorBody {
+    error: String,
+    /// One of `not_found`, `unauthorized`, `forbidden`, `bad_request`, `conflict`,
+    /// `invalid`, `too_large`, `printer_unavailable`, `upstream_failed`,
+    /// `shutting_down` or `internal`.
+    #[schema(example = "not_found")]
+    code: &'static str,
+    /// Each invalid field's error, for 422 responses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fields: Option<Vec<FieldError>>,
+    /// The request's `X-Request-Id`, to find its log lines by.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let code = self.code();
+        let (message, fields) = match self {
+            ApiError::Invalid(fields) => ("some fields are invalid".to_string(), Some(fields)),
+            ApiError::NotFound => ("not found".to_string(), None),
+            ApiError::Unauthorized => ("a valid API key is required".to_string(), None),
+            ApiError::Forbidden => ("this needs an admin API key".to_string(), None),
+            ApiError::BadRequest(msg)
+            | ApiError::Conflict(msg)
+            | ApiError::TooLarge(msg)
+            | ApiError::PrinterUnavailable(msg)
+            | ApiError::Upstream(msg)
+            | ApiError::ShuttingDown(msg) => (msg, None),
+            ApiError::Internal(err) => {
+                error!("{err:#}");
+                (format!("{err:#}"), None)
+            }
+        };
+
+        let body = ErrorBody {
+            error: message,
+            code,
+            fields,
+            request_id: telemetry::request_id(),
+        };
+        (status, Json(body)).into_response()
+    }
+}
+
+impl<E> From<E> for ApiError
+where
+    E: Into<anyhow::Error>,
+{
+    fn from(err: E) -> Self {
+        let err = err.into();
+        if let Some(stopping) = err.downcast_ref::<ShuttingDown>() {
+            return ApiError::ShuttingDown(stopping.to_string());
+        }
+        match err.downcast::<LimitExceeded>() {
+            Ok(limit) => ApiError::TooLarge(limit.to_string()),
+            Err(err) => ApiError::Internal(err),
+        }
+    }
+}
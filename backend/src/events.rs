@@ -0,0 +1,167 @@
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Events kept per subscriber before a slow client starts missing them, and kept
+/// for clients resuming after a dropped connection.
+const CAPACITY: usize = 256;
+
+/// Something that happened inside the server that clients may want to react to.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// A job moved to `state` (`queued`, `printing`, `done` or `failed`).
+    Job {
+        job_id: i32,
+        printer_id: i32,
+        state: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// A printer's queue was paused or resumed.
+    PrinterPaused { printer_id: i32, paused: bool },
+    /// A printer's device appeared or went away, such as when it's switched off or
+    /// unplugged.
+    PrinterOnline { printer_id: i32, online: bool },
+    /// A device that looks like a printer was plugged in or out.
+    Hotplug { path: String, attached: bool },
+    /// Settings were changed through the API. Background tasks that act on them, the
+    /// scheduler and the printer workers, reload straight away instead of on their
+    /// next periodic check.
+    ConfigChanged { scope: ConfigScope, id: ConfigId },
+}
+
+/// Each [`Event`]'s `type`, which clients subscribe to events by.
+pub const TOPICS: &[&str] = &[
+    "job",
+    "printer_paused",
+    "printer_online",
+    "hotplug",
+    "config_changed",
+];
+
+/// What kind of record a [`Event::ConfigChanged`] refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigScope {
+    Printer,
+    Schedule,
+    /// The instance-wide [settings](crate::settings), whose id is always 1.
+    Settings,
+    /// An integration instance, whose id is its `instance_id`.
+    Integration,
+}
+
+/// Id of the record a [`Event::ConfigChanged`] refers to: a number, or the text id
+/// of an integration instance.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum ConfigId {
+    Id(i32),
+    InstanceId(String),
+}
+
+impl From<i32> for ConfigId {
+    fn from(id: i32) -> Self {
+        ConfigId::Id(id)
+    }
+}
+
+impl From<String> for ConfigId {
+    fn from(id: String) -> Self {
+        ConfigId::InstanceId(id)
+    }
+}
+
+/// An [`Event`] with its id. Ids go up by one with each event, and start from the
+/// time the server started in milliseconds so they keep going up across restarts.
+#[derive(Debug, Clone)]
+pub struct Numbered {
+    pub id: u64,
+    pub event: Event,
+}
+
+/// The events most recently published, and the id of the next.
+struct Recent {
+    next_id: u64,
+    events: VecDeque<Numbered>,
+}
+
+/// In-process fan-out of [`Event`]s. Publishing never blocks and is a no-op when
+/// nobody is listening, apart from the last events being kept to [`resume`] from.
+///
+/// [`resume`]: EventBus::resume
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<Numbered>,
+    recent: Arc<Mutex<Recent>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(CAPACITY);
+        let recent = Recent {
+            next_id: Utc::now().timestamp_millis().max(0) as u64,
+            events: VecDeque::with_capacity(CAPACITY),
+        };
+        Self {
+            tx,
+            recent: Arc::new(Mutex::new(recent)),
+        }
+    }
+
+    pub fn publish(&self, event: Event) {
+        let mut recent = self.recent.lock().unwrap();
+        let numbered = Numbered {
+            id: recent.next_id,
+            event,
+        };
+        recent.next_id += 1;
+        if recent.events.len() == CAPACITY {
+            recent.events.pop_front();
+        }
+        recent.events.push_back(numbered.clone());
+        let _ = self.tx.send(numbered);
+    }
+
+    pub fn subscribe(&self) -> Receiver {
+        Receiver(self.tx.subscribe())
+    }
+
+    /// The kept events published after the one with id `after`, if given, and a
+    /// receiver for those that follow them, with none missed or repeated in between.
+    /// Also says whether events after `after` were missed, being no longer kept.
+    pub fn resume(
+        &self,
+        after: Option<u64>,
+    ) -> (Vec<Numbered>, bool, broadcast::Receiver<Numbered>) {
+        let recent = self.recent.lock().unwrap();
+        let rx = self.tx.subscribe();
+        let Some(after) = after else {
+            return (Vec::new(), false, rx);
+        };
+        let missed = recent
+            .events
+            .front()
+            .is_some_and(|first| first.id > after.saturating_add(1));
+        let kept = recent
+            .events
+            .iter()
+            .filter(|n| n.id > after)
+            .cloned()
+            .collect();
+        (kept, missed, rx)
+    }
+}
+
+/// Events published after [`EventBus::subscribe`] was called.
+pub struct Receiver(broadcast::Receiver<Numbered>);
+
+impl Receiver {
+    pub async fn recv(&mut self) -> Result<Event, RecvError> {
+        self.0.recv().await.map(|numbered| numbered.event)
+    }
+}
@@ -0,0 +1,745 @@
+//! Icons built into every install, for glyph blocks that name one no uploaded glyph
+//! has: weather, sun and moon, calendar, checkboxes, alarms, bin collections and the
+//! like.
+
+use chrono::DateTime;
+use image::{GrayImage, Luma};
+
+use super::{Glyph, HEIGHT, MAX_WIDTH};
+
+/// Names of the built-in icons.
+pub fn names() -> impl Iterator<Item = &'static str> {
+    ICONS.iter().map(|(name, _)| *name)
+}
+
+/// The icon called `name` as a glyph. Built-in icons have id 0.
+pub fn glyph(name: &str) -> Option<Glyph> {
+    let image = image(name)?;
+    let mut bitmap = vec![0u8; MAX_WIDTH as usize * 3];
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if pixel.0[0] < 128 {
+            bitmap[x as usize * 3 + y as usize / 8] |= 0x80 >> (y % 8);
+        }
+    }
+    Some(Glyph {
+        id: 0,
+        name: name.to_string(),
+        width: MAX_WIDTH as i32,
+        bitmap,
+        created_at: DateTime::UNIX_EPOCH.naive_utc(),
+    })
+}
+
+/// The icon called `name`, black on white and centred vertically in a character cell.
+pub fn image(name: &str) -> Option<GrayImage> {
+    let (_, rows) = ICONS.iter().find(|(icon, _)| *icon == name)?;
+    let top = (HEIGHT - rows.len() as u32) / 2;
+    Some(GrayImage::from_fn(MAX_WIDTH, HEIGHT, |x, y| {
+        let dark = y
+            .checked_sub(top)
+            .and_then(|row| rows.get(row as usize))
+            .is_some_and(|row| row.as_bytes().get(x as usize) == Some(&b'#'));
+        Luma([if dark { 0 } else { 255 }])
+    }))
+}
+
+/// 12 dots wide, `#` for black.
+const ICONS: &[(&str, &[&str])] = &[
+    (
+        "sun",
+        &[
+            ".....##.....",
+            ".#...##...#.",
+            "..#......#..",
+            "....####....",
+            "...######...",
+            "##.######.##",
+            "##.######.##",
+            "...######...",
+            "....####....",
+            "..#......#..",
+            ".#...##...#.",
+            ".....##.....",
+        ],
+    ),
+    (
+        "sunrise",
+        &[
+            ".....##.....",
+            "....####....",
+            "...######...",
+            ".....##.....",
+            "..#..##..#..",
+            "...#....#...",
+            "....####....",
+            "..########..",
+            ".##########.",
+            "############",
+            "............",
+            "############",
+        ],
+    ),
+    (
+        "sunset",
+        &[
+            ".....##.....",
+            "...######...",
+            "....####....",
+            ".....##.....",
+            "..#......#..",
+            "...#....#...",
+            "....####....",
+            "..########..",
+            ".##########.",
+            "############",
+            "............",
+            "############",
+        ],
+    ),
+    (
+        "partly-cloudy",
+        &[
+            "..#.........",
+            "#.#.#.......",
+            ".###........",
+            "##.####.....",
+            ".#.#...#....",
+            "..#.....###.",
+            ".##........#",
+            "#..........#",
+            "#..........#",
+            ".##########.",
+        ],
+    ),
+    (
+        "cloud",
+        &[
+            "....###.....",
+            "...#...#....",
+            "..#.....###.",
+            ".##........#",
+            "#..........#",
+            "#..........#",
+            "#..........#",
+            ".##########.",
+        ],
+    ),
+    (
+        "rain",
+        &[
+            "....###.....",
+            "...#...#....",
+            "..#.....###.",
+            ".##........#",
+            "#..........#",
+            "#..........#",
+            ".##########.",
+            "............",
+            "..#...#...#.",
+            ".#...#...#..",
+            "............",
+            "...#...#....",
+            "..#...#.....",
+        ],
+    ),
+    (
+        "snow",
+        &[
+            "#....#....#.",
+            ".#...#...#..",
+            "..#..#..#...",
+            "...#.#.#....",
+            "....###.....",
+            "###########.",
+            "....###.....",
+            "...#.#.#....",
+            "..#..#..#...",
+            ".#...#...#..",
+            "#....#....#.",
+        ],
+    ),
+    (
+        "storm",
+        &[
+            "....###.....",
+            "...#...#....",
+            "..#.....###.",
+            ".##........#",
+            "#..........#",
+            "#..........#",
+            ".##########.",
+            "......##....",
+            ".....##.....",
+            "....####....",
+            "......##....",
+            ".....##.....",
+            ".....#......",
+        ],
+    ),
+    (
+        "fog",
+        &[
+            ".#########..",
+            "............",
+            "...#########",
+            "............",
+            "##########..",
+            "............",
+            "..#########.",
+            "............",
+            ".########...",
+        ],
+    ),
+    (
+        "wind",
+        &[
+            ".......##...",
+            "......#..#..",
+            ".........#..",
+            "#########...",
+            "............",
+            "###########.",
+            "............",
+            "#######.....",
+            "........#...",
+            ".......#....",
+        ],
+    ),
+    (
+        "calendar",
+        &[
+            "..#......#..",
+            "############",
+            "#..........#",
+            "############",
+            "#..........#",
+            "#.#.#.#.#..#",
+            "#..........#",
+            "#.#.#.#.#..#",
+            "#..........#",
+            "#.#.#......#",
+            "#..........#",
+            "############",
+        ],
+    ),
+    (
+        "clock",
+        &[
+            "...######...",
+            "..#......#..",
+            ".#...#....#.",
+            "#....#.....#",
+            "#....#.....#",
+            "#....####..#",
+            "#..........#",
+            "#..........#",
+            ".#........#.",
+            "..#......#..",
+            "...######...",
+        ],
+    ),
+    (
+        "alarm",
+        &[
+            ".##......##.",
+            "##..####..##",
+            "#.##....##.#",
+            "..#......#..",
+            ".#...#....#.",
+            ".#...#....#.",
+            ".#...###..#.",
+            ".#........#.",
+            "..#......#..",
+            "...######...",
+            "..#......#..",
+            ".#........#.",
+        ],
+    ),
+    (
+        "bell",
+        &[
+            ".....##.....",
+            "....####....",
+            "...#....#...",
+            "..#......#..",
+            "..#......#..",
+            "..#......#..",
+            "..#......#..",
+            ".#........#.",
+            "#..........#",
+            "############",
+            ".....##.....",
+            "............",
+        ],
+    ),
+    (
+        "checkbox",
+        &[
+            "############",
+            "#..........#",
+            "#..........#",
+            "#..........#",
+            "#..........#",
+            "#..........#",
+            "#..........#",
+            "#..........#",
+            "#..........#",
+            "#..........#",
+            "#..........#",
+            "############",
+        ],
+    ),
+    (
+        "checkbox-checked",
+        &[
+            "############",
+            "#..........#",
+            "#.........##",
+            "#........###",
+            "#.......##.#",
+            "#.##...##..#",
+            "#.###.##...#",
+            "#..####....#",
+            "#...##.....#",
+            "#..........#",
+            "#..........#",
+            "############",
+        ],
+    ),
+    (
+        "star",
+        &[
+            ".....##.....",
+            ".....##.....",
+            "....####....",
+            "############",
+            ".##########.",
+            "..########..",
+            "...######...",
+            "..###..###..",
+            "..##....##..",
+            ".##......##.",
+        ],
+    ),
+    (
+        "warning",
+        &[
+            ".....##.....",
+            "....####....",
+            "....#..#....",
+            "...#.##.#...",
+            "...#.##.#...",
+            "..#..##..#..",
+            "..#..##..#..",
+            ".#...##...#.",
+            ".#........#.",
+            "#....##....#",
+            "############",
+        ],
+    ),
+    (
+        "trash",
+        &[
+            "....####....",
+            "############",
+            "............",
+            ".##########.",
+            ".#..#..#..#.",
+            ".#..#..#..#.",
+            ".#..#..#..#.",
+            ".#..#..#..#.",
+            ".#..#..#..#.",
+            ".#..#..#..#.",
+            "..########..",
+        ],
+    ),
+    (
+        "recycling",
+        &[
+            "....####....",
+            "############",
+            "............",
+            ".##########.",
+            ".#...##...#.",
+            ".#..#..#..#.",
+            ".#.#....#.#.",
+            ".#.######.#.",
+            ".#........#.",
+            ".#........#.",
+            "..########..",
+        ],
+    ),
+    (
+        "compost",
+        &[
+            "....####....",
+            "############",
+            "............",
+            ".##########.",
+            ".#......#.#.",
+            ".#....###.#.",
+            ".#...####.#.",
+            ".#..####..#.",
+            ".#..##....#.",
+            ".#.#......#.",
+            "..########..",
+        ],
+    ),
+    (
+        "cake",
+        &[
+            "............",
+            "...#....#...",
+            "...#....#...",
+            "...#....#...",
+            ".##########.",
+            ".#........#.",
+            ".#.#.##.#.#.",
+            ".##########.",
+            ".#........#.",
+            ".#........#.",
+            "############",
+            "............",
+        ],
+    ),
+    (
+        "party",
+        &[
+            "........#..#",
+            "..#..#......",
+            "......#.#...",
+            "....#.....#.",
+            "...##..#....",
+            "...#.#....#.",
+            "..#...#.....",
+            "..#....#....",
+            ".#.....##...",
+            ".#...##.....",
+            "#.###.......",
+            "##..........",
+        ],
+    ),
+    (
+        "gift",
+        &[
+            "...##..##...",
+            "....#..#....",
+            "############",
+            "#....##....#",
+            "############",
+            ".#...##...#.",
+            ".#...##...#.",
+            ".#...##...#.",
+            ".#...##...#.",
+            ".#...##...#.",
+            ".##########.",
+            "............",
+        ],
+    ),
+    (
+        "home",
+        &[
+            ".....##.....",
+            "....#..#....",
+            "...#....#...",
+            "..#......#..",
+            ".#........#.",
+            "############",
+            ".#........#.",
+            ".#.##..##.#.",
+            ".#.##..##.#.",
+            ".#........#.",
+            ".#...##...#.",
+            ".##########.",
+        ],
+    ),
+    (
+        "car",
+        &[
+            "............",
+            "............",
+            "...######...",
+            "..#..#...#..",
+            ".#...#....#.",
+            "############",
+            "#..........#",
+            "#.##....##.#",
+            "############",
+            "..##....##..",
+            "............",
+            "............",
+        ],
+    ),
+    (
+        "pill",
+        &[
+            "............",
+            "............",
+            "............",
+            "..########..",
+            ".#....#####.",
+            "#.....######",
+            "#.....######",
+            ".#....#####.",
+            "..########..",
+            "............",
+            "............",
+            "............",
+        ],
+    ),
+    (
+        "cart",
+        &[
+            "##..........",
+            ".#..........",
+            ".#########..",
+            ".#.#.#.#.#..",
+            "..########..",
+            "..#.#.#.#...",
+            "..#######...",
+            "..#.........",
+            "..########..",
+            "...#....#...",
+            "..###..###..",
+            "...#....#...",
+        ],
+    ),
+    (
+        "bulb",
+        &[
+            "....####....",
+            "...#....#...",
+            "..#......#..",
+            "..#......#..",
+            "..#......#..",
+            "...#....#...",
+            "....#..#....",
+            "....####....",
+            "....####....",
+            "....####....",
+            ".....##.....",
+            "............",
+        ],
+    ),
+    (
+        "moon-new",
+        &[
+            "....####....",
+            "..###..###..",
+            ".##......##.",
+            ".#........#.",
+            "##........##",
+            "#..........#",
+            "#..........#",
+            "##........##",
+            ".#........#.",
+            ".##......##.",
+            "..###..###..",
+            "....####....",
+        ],
+    ),
+    (
+        "moon-waxing-crescent",
+        &[
+            "....####....",
+            "..###..###..",
+            ".##.....###.",
+            ".#.......##.",
+            "##.......###",
+            "#........###",
+            "#........###",
+            "##.......###",
+            ".#.......##.",
+            ".##.....###.",
+            "..###..###..",
+            "....####....",
+        ],
+    ),
+    (
+        "moon-first-quarter",
+        &[
+            "....####....",
+            "..###.####..",
+            ".##...#####.",
+            ".#....#####.",
+            "##....######",
+            "#.....######",
+            "#.....######",
+            "##....######",
+            ".#....#####.",
+            ".##...#####.",
+            "..###.####..",
+            "....####....",
+        ],
+    ),
+    (
+        "moon-waxing-gibbous",
+        &[
+            "....####....",
+            "..########..",
+            ".##.#######.",
+            ".#.########.",
+            "##.#########",
+            "#..#########",
+            "#..#########",
+            "##.#########",
+            ".#.########.",
+            ".##.#######.",
+            "..########..",
+            "....####....",
+        ],
+    ),
+    (
+        "moon-full",
+        &[
+            "....####....",
+            "..########..",
+            ".##########.",
+            ".##########.",
+            "############",
+            "############",
+            "############",
+            "############",
+            ".##########.",
+            ".##########.",
+            "..########..",
+            "....####....",
+        ],
+    ),
+    (
+        "moon-waning-gibbous",
+        &[
+            "....####....",
+            "..########..",
+            ".#######.##.",
+            ".########.#.",
+            "#########.##",
+            "#########..#",
+            "#########..#",
+            "#########.##",
+            ".########.#.",
+            ".#######.##.",
+            "..########..",
+            "....####....",
+        ],
+    ),
+    (
+        "moon-last-quarter",
+        &[
+            "....####....",
+            "..####.###..",
+            ".#####...##.",
+            ".#####....#.",
+            "######....##",
+            "######.....#",
+            "######.....#",
+            "######....##",
+            ".#####....#.",
+            ".#####...##.",
+            "..####.###..",
+            "....####....",
+        ],
+    ),
+    (
+        "moon-waning-crescent",
+        &[
+            "....####....",
+            "..###..###..",
+            ".###.....##.",
+            ".##.......#.",
+            "###.......##",
+            "###........#",
+            "###........#",
+            "###.......##",
+            ".##.......#.",
+            ".###.....##.",
+            "..###..###..",
+            "....####....",
+        ],
+    ),
+    (
+        "level-0",
+        &[
+            "............",
+            "............",
+            "............",
+            "............",
+            "............",
+            "............",
+            "............",
+            "............",
+            "............",
+            "............",
+            "............",
+            ".##.##.##.##",
+        ],
+    ),
+    (
+        "level-1",
+        &[
+            "............",
+            "............",
+            "............",
+            "............",
+            "............",
+            "............",
+            "............",
+            "............",
+            "............",
+            ".##.........",
+            ".##.........",
+            ".##.##.##.##",
+        ],
+    ),
+    (
+        "level-2",
+        &[
+            "............",
+            "............",
+            "............",
+            "............",
+            "............",
+            "............",
+            "....##......",
+            "....##......",
+            "....##......",
+            ".##.##......",
+            ".##.##......",
+            ".##.##.##.##",
+        ],
+    ),
+    (
+        "level-3",
+        &[
+            "............",
+            "............",
+            "............",
+            ".......##...",
+            ".......##...",
+            ".......##...",
+            "....##.##...",
+            "....##.##...",
+            "....##.##...",
+            ".##.##.##...",
+            ".##.##.##...",
+            ".##.##.##.##",
+        ],
+    ),
+    (
+        "level-4",
+        &[
+            "..........##",
+            "..........##",
+            "..........##",
+            ".......##.##",
+            ".......##.##",
+            ".......##.##",
+            "....##.##.##",
+            "....##.##.##",
+            "....##.##.##",
+            ".##.##.##.##",
+            ".##.##.##.##",
+            ".##.##.##.##",
+        ],
+    ),
+];
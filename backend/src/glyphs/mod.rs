@@ -0,0 +1,143 @@
+use anyhow::Result;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use image::imageops::FilterType;
+use serde::{Deserialize, Serialize};
+
+use crate::error::FieldError;
+use crate::render::photo;
+use crate::schema::glyphs;
+
+pub mod icons;
+
+/// Width of a font A character cell in dots; user-defined characters can't be wider.
+pub const MAX_WIDTH: u32 = 12;
+/// Height of a font A character cell in dots.
+pub const HEIGHT: u32 = 24;
+
+/// A small bitmap symbol that documents can place next to text by name.
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize)]
+#[diesel(table_name = glyphs)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Glyph {
+    pub id: i32,
+    pub name: String,
+    /// Width in dots; glyphs are always [`HEIGHT`] dots tall.
+    pub width: i32,
+    /// Column-major, three bytes per column with the top dot in the high bit: the
+    /// layout both `ESC &` and 24-dot `ESC *` expect.
+    #[serde(skip)]
+    pub bitmap: Vec<u8>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GlyphInput {
+    pub name: String,
+    /// Base64-encoded PNG. Scaled to fit a 12x24 dot character cell.
+    pub png: String,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = glyphs)]
+struct GlyphRow {
+    name: String,
+    width: i32,
+    bitmap: Vec<u8>,
+}
+
+impl GlyphInput {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        if self.name.is_empty()
+            || !self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(FieldError::new(
+                "name",
+                "must be letters, digits, '_' or '-'",
+            ));
+        }
+        self.to_bitmap()
+            .map(|_| ())
+            .map_err(|e| FieldError::new("png", e))
+    }
+
+    /// Threshold the image to black and white, scaled to the character cell and
+    /// centred vertically. Errors are worded to follow the `png` field's name.
+    fn to_bitmap(&self) -> Result<(u32, Vec<u8>), String> {
+        let bytes = BASE64
+            .decode(&self.png)
+            .map_err(|e| format!("is not valid base64: {e}"))?;
+        let image = photo::decode(&bytes).map_err(|e| format!("could not be read: {e}"))?;
+        let image = image
+            .resize(MAX_WIDTH, HEIGHT, FilterType::Nearest)
+            .to_luma8();
+        let top = (HEIGHT - image.height()) / 2;
+
+        let mut bitmap = vec![0u8; image.width() as usize * 3];
+        for (x, y, pixel) in image.enumerate_pixels() {
+            if pixel.0[0] < 128 {
+                let row = y + top;
+                bitmap[x as usize * 3 + row as usize / 8] |= 0x80 >> (row % 8);
+            }
+        }
+        Ok((image.width(), bitmap))
+    }
+}
+
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Glyph>> {
+    let rows = glyphs::table
+        .order(glyphs::name.asc())
+        .select(Glyph::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn get(conn: &mut SqliteConnection, id: i32) -> Result<Option<Glyph>> {
+    let row = glyphs::table
+        .find(id)
+        .select(Glyph::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn get_by_name(conn: &mut SqliteConnection, name: &str) -> Result<Option<Glyph>> {
+    let row = glyphs::table
+        .filter(glyphs::name.eq(name))
+        .select(Glyph::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+/// The glyphs among `names` that exist.
+pub fn by_names(conn: &mut SqliteConnection, names: &[&str]) -> Result<Vec<Glyph>> {
+    let rows = glyphs::table
+        .filter(glyphs::name.eq_any(names))
+        .select(Glyph::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn create(conn: &mut SqliteConnection, input: GlyphInput) -> Result<Glyph> {
+    let (width, bitmap) = input.to_bitmap().map_err(|e| anyhow::anyhow!("png {e}"))?;
+    let row = diesel::insert_into(glyphs::table)
+        .values(GlyphRow {
+            name: input.name,
+            width: width as i32,
+            bitmap,
+        })
+        .returning(Glyph::as_returning())
+        .get_result(conn)?;
+    Ok(row)
+}
+
+pub fn delete(conn: &mut SqliteConnection, id: i32) -> Result<bool> {
+    let n = diesel::delete(glyphs::table.find(id)).execute(conn)?;
+    Ok(n > 0)
+}
@@ -0,0 +1,229 @@
+//! The gRPC service described by `proto/dayroll.proto`, for point-of-sale systems and
+//! other services that speak gRPC rather than JSON. Each call does what its REST
+//! endpoint does, through the same code, and is served on the HTTP port behind the
+//! same API keys.
+
+use axum::Router;
+use chrono::NaiveDateTime;
+use std::path::Path;
+use tonic::{Request, Response, Status};
+use tracing::error;
+
+use crate::discover::{DefaultDiscovery, DiscoveryProvider};
+use crate::error::{ApiError, ApiResult};
+use crate::jobs::{self, JobContent, JobPayload, JobState, NewJob};
+use crate::model::{Candidate, Transport};
+use crate::routes::extract::Json;
+use crate::routes::jobs::{JobResponse, default_source, resolve, submit};
+use crate::routes::settings::printer_or_default;
+use crate::state::AppState;
+use crate::{db, printers};
+use proto::dayroll_server::{Dayroll, DayrollServer, SERVICE_NAME};
+
+#[allow(clippy::all, clippy::pedantic)]
+pub mod proto {
+    tonic::include_proto!("dayroll.v1");
+}
+
+/// Routes every call of the service to it, to be merged into the HTTP app.
+pub fn router(state: AppState) -> Router<AppState> {
+    Router::new().route_service(
+        &format!("/{SERVICE_NAME}/{{*method}}"),
+        DayrollServer::new(Service { state }),
+    )
+}
+
+struct Service {
+    state: AppState,
+}
+
+#[tonic::async_trait]
+impl Dayroll for Service {
+    async fn submit_job(
+        &self,
+        request: Request<proto::SubmitJobRequest>,
+    ) -> Result<Response<proto::SubmitJobResponse>, Status> {
+        Ok(Response::new(self.queue_job(request.into_inner()).await?))
+    }
+
+    async fn get_job(
+        &self,
+        request: Request<proto::GetJobRequest>,
+    ) -> Result<Response<proto::Job>, Status> {
+        let id = request.into_inner().id;
+        let job = db::run_blocking_db(move |conn| jobs::get(conn, id))
+            .await
+            .map_err(ApiError::from)?
+            .ok_or(ApiError::NotFound)?;
+        Ok(Response::new(job.into()))
+    }
+
+    async fn get_printer_status(
+        &self,
+        request: Request<proto::GetPrinterStatusRequest>,
+    ) -> Result<Response<proto::PrinterStatus>, Status> {
+        let printer_id = request.into_inner().printer_id;
+        Ok(Response::new(self.printer_status(printer_id).await?))
+    }
+
+    async fn discover(
+        &self,
+        _request: Request<proto::DiscoverRequest>,
+    ) -> Result<Response<proto::DiscoverResponse>, Status> {
+        let mut found = tokio::task::spawn_blocking(|| DefaultDiscovery.discover_default())
+            .await
+            .map_err(ApiError::from)?
+            .map_err(ApiError::from)?;
+        found.sort_by_key(|c| std::cmp::Reverse(c.confidence));
+        Ok(Response::new(proto::DiscoverResponse {
+            candidates: found.into_iter().map(Into::into).collect(),
+        }))
+    }
+}
+
+impl Service {
+    async fn queue_job(&self, req: proto::SubmitJobRequest) -> ApiResult<proto::SubmitJobResponse> {
+        let content = match req.content {
+            Some(proto::submit_job_request::Content::Text(text)) => {
+                JobContent::Inline(JobPayload::text(text))
+            }
+            Some(proto::submit_job_request::Content::Json(json)) => serde_json::from_str(&json)
+                .map_err(|err| ApiError::BadRequest(format!("json is not a job: {err}")))?,
+            None => return Err(ApiError::BadRequest("text or json is required".into())),
+        };
+        let source = Some(req.source)
+            .filter(|source| !source.is_empty())
+            .unwrap_or_else(default_source);
+        let payload = resolve(content).await?;
+        let mut new = NewJob::new(printer_or_default(req.printer_id).await?, source, &payload)?;
+        new.urgent = req.urgent;
+        let (
+            _,
+            Json(JobResponse {
+                job,
+                estimate,
+                duplicate,
+            }),
+        ) = submit(&self.state, new, payload, req.dry_run).await?;
+        Ok(proto::SubmitJobResponse {
+            job: Some(job.into()),
+            duplicate,
+            estimated_duration_ms: estimate.as_ref().map(|e| e.estimated_duration_ms),
+            eta_ms: estimate.as_ref().map(|e| e.eta_ms),
+        })
+    }
+
+    async fn printer_status(&self, printer_id: i32) -> ApiResult<proto::PrinterStatus> {
+        let printer = db::run_blocking_db(move |conn| printers::get(conn, printer_id))
+            .await?
+            .ok_or(ApiError::NotFound)?;
+        let job_id = self
+            .state
+            .queue
+            .estimates(printer_id)
+            .await?
+            .into_iter()
+            .find(|queued| queued.job.state == JobState::Printing.as_str())
+            .map(|queued| queued.job.id);
+        // Only local devices can be looked for; the monitor does the same.
+        let online = match printer.transport() {
+            Ok(Transport::UsbLp { path } | Transport::Serial { path }) => {
+                Some(Path::new(&path).exists())
+            }
+            _ => None,
+        };
+        let state = if printer.paused {
+            "paused"
+        } else if online == Some(false) {
+            "offline"
+        } else if job_id.is_some() {
+            "printing"
+        } else {
+            "idle"
+        };
+        Ok(proto::PrinterStatus {
+            printer_id,
+            name: printer.name,
+            state: state.into(),
+            enabled: printer.enabled,
+            paused: printer.paused,
+            online,
+            job_id,
+            queued_job_ids: self.state.queue.pending(printer_id),
+        })
+    }
+}
+
+fn timestamp(at: NaiveDateTime) -> prost_types::Timestamp {
+    let at = at.and_utc();
+    prost_types::Timestamp {
+        seconds: at.timestamp(),
+        nanos: at.timestamp_subsec_nanos() as i32,
+    }
+}
+
+impl From<jobs::Job> for proto::Job {
+    fn from(job: jobs::Job) -> Self {
+        Self {
+            id: job.id,
+            printer_id: job.printer_id,
+            state: job.state,
+            source: job.source,
+            bytes: job.bytes,
+            error: job.error,
+            created_at: Some(timestamp(job.created_at)),
+            started_at: job.started_at.map(timestamp),
+            finished_at: job.finished_at.map(timestamp),
+            urgent: job.urgent,
+        }
+    }
+}
+
+impl From<Candidate> for proto::Candidate {
+    fn from(candidate: Candidate) -> Self {
+        let (transport, address) = match candidate.transport {
+            Transport::UsbLp { path } => ("usb_lp", path),
+            Transport::Serial { path } => ("serial", path),
+            Transport::VirtualDisplay { url } => ("virtual_display", url),
+            Transport::Bluetooth { address } => ("bluetooth", address),
+        };
+        Self {
+            transport: transport.into(),
+            address,
+            make_model: candidate.make_model,
+            serial: candidate.serial,
+            vid: candidate.vid,
+            pid: candidate.pid,
+            confidence: candidate.confidence.into(),
+            notes: candidate.notes,
+        }
+    }
+}
+
+/// Each kind of [`ApiError`] as the gRPC status closest to its HTTP one.
+impl From<ApiError> for Status {
+    fn from(err: ApiError) -> Self {
+        match err {
+            ApiError::NotFound => Status::not_found("not found"),
+            ApiError::Unauthorized => Status::unauthenticated("a valid API key is required"),
+            ApiError::Forbidden => Status::permission_denied("this needs an admin API key"),
+            ApiError::BadRequest(msg) => Status::invalid_argument(msg),
+            ApiError::Invalid(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|f| format!("{}: {}", f.field, f.message))
+                    .collect();
+                Status::invalid_argument(fields.join("; "))
+            }
+            ApiError::Conflict(msg) | ApiError::PrinterUnavailable(msg) => {
+                Status::failed_precondition(msg)
+            }
+            ApiError::TooLarge(msg) => Status::resource_exhausted(msg),
+            ApiError::Upstream(msg) | ApiError::ShuttingDown(msg) => Status::unavailable(msg),
+            ApiError::Internal(err) => {
+                error!("{err:#}");
+                Status::internal(format!("{err:#}"))
+            }
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Habits to keep up day after day. Each day a habit is done is checked off, through
+//! the API or by scanning its code on the printout, and the days in a row it has
+//! been done make its streak.
+
+use anyhow::Result;
+use chrono::{Days, NaiveDate, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::error::FieldError;
+use crate::schema::{habit_checks, habits};
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize)]
+#[diesel(table_name = habits)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Habit {
+    pub id: i32,
+    pub name: String,
+    /// Lets the link printed in the habit's QR code check it off without other access.
+    #[serde(skip)]
+    pub check_token: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// Whose habit it is; everyone's when unset.
+    pub user_id: Option<i32>,
+}
+
+/// A habit as it stands on a day.
+#[derive(Debug, Clone, Serialize)]
+pub struct Progress {
+    #[serde(flatten)]
+    pub habit: Habit,
+    /// Whether the habit is checked off for the day.
+    pub done: bool,
+    /// Days in a row the habit has been done, up to the day, or up to the day
+    /// before while the day itself isn't checked off yet.
+    pub streak: u32,
+}
+
+/// Writable habit fields, used both for creation and full (`PUT`) updates.
+#[derive(Debug, Deserialize)]
+pub struct NewHabit {
+    pub name: String,
+    #[serde(default)]
+    pub user_id: Option<i32>,
+}
+
+impl NewHabit {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        if self.name.trim().is_empty() {
+            return Err(FieldError::new("name", "must not be empty"));
+        }
+        Ok(())
+    }
+}
+
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Habit>> {
+    let rows = habits::table
+        .order(habits::id.asc())
+        .select(Habit::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn get(conn: &mut SqliteConnection, id: i32) -> Result<Option<Habit>> {
+    let row = habits::table
+        .find(id)
+        .select(Habit::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn create(conn: &mut SqliteConnection, new: NewHabit) -> Result<Habit> {
+    let row = diesel::insert_into(habits::table)
+        .values((
+            habits::name.eq(new.name),
+            habits::user_id.eq(new.user_id),
+            habits::check_token.eq(uuid::Uuid::new_v4().simple().to_string()),
+        ))
+        .returning(Habit::as_returning())
+        .get_result(conn)?;
+    Ok(row)
+}
+
+pub fn update(conn: &mut SqliteConnection, id: i32, changes: NewHabit) -> Result<Option<Habit>> {
+    let row = diesel::update(habits::table.find(id))
+        .set((
+            habits::name.eq(changes.name),
+            habits::user_id.eq(changes.user_id),
+            habits::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .returning(Habit::as_returning())
+        .get_result(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn delete(conn: &mut SqliteConnection, id: i32) -> Result<bool> {
+    let deleted = diesel::delete(habits::table.find(id)).execute(conn)?;
+    Ok(deleted > 0)
+}
+
+/// Check the habit off for `day`; checking it off twice changes nothing.
+pub fn check(conn: &mut SqliteConnection, id: i32, day: NaiveDate) -> Result<()> {
+    diesel::insert_or_ignore_into(habit_checks::table)
+        .values((habit_checks::habit_id.eq(id), habit_checks::day.eq(day)))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Check off the habit whose link carries `token`, as its QR code does.
+pub fn check_with_token(
+    conn: &mut SqliteConnection,
+    id: i32,
+    token: &str,
+    day: NaiveDate,
+) -> Result<Option<Habit>> {
+    let Some(habit) = get(conn, id)?.filter(|h| h.check_token == token) else {
+        return Ok(None);
+    };
+    check(conn, id, day)?;
+    Ok(Some(habit))
+}
+
+pub fn uncheck(conn: &mut SqliteConnection, id: i32, day: NaiveDate) -> Result<bool> {
+    let deleted = diesel::delete(
+        habit_checks::table
+            .filter(habit_checks::habit_id.eq(id))
+            .filter(habit_checks::day.eq(day)),
+    )
+    .execute(conn)?;
+    Ok(deleted > 0)
+}
+
+/// Every habit with its progress as of `date`.
+pub fn progress(conn: &mut SqliteConnection, date: NaiveDate) -> Result<Vec<Progress>> {
+    let habits = list(conn)?;
+    let checks: Vec<(i32, NaiveDate)> = habit_checks::table
+        .filter(habit_checks::day.le(date))
+        .order(habit_checks::day.desc())
+        .select((habit_checks::habit_id, habit_checks::day))
+        .load(conn)?;
+    let mut days: HashMap<i32, Vec<NaiveDate>> = HashMap::new();
+    for (habit_id, day) in checks {
+        days.entry(habit_id).or_default().push(day);
+    }
+
+    Ok(habits
+        .into_iter()
+        .map(|habit| {
+            let days = days.remove(&habit.id).unwrap_or_default();
+            let done = days.first() == Some(&date);
+            let mut expected = if done {
+                Some(date)
+            } else {
+                date.checked_sub_days(Days::new(1))
+            };
+            let mut streak = 0;
+            for day in days {
+                if Some(day) != expected {
+                    break;
+                }
+                streak += 1;
+                expected = day.checked_sub_days(Days::new(1));
+            }
+            Progress {
+                habit,
+                done,
+                streak,
+            }
+        })
+        .collect())
+}
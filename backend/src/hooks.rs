@@ -0,0 +1,307 @@
+//! Inbound webhooks: URLs that Slack, IFTTT or a CI system can post to, each mapping
+//! whatever it is sent onto a receipt.
+
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::api_keys::hash;
+use crate::error::FieldError;
+use crate::jobs::JobPayload;
+use crate::schema::hooks;
+use crate::templates::{self, Template, TemplateInput, draft, expand, json_text};
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize)]
+#[diesel(table_name = hooks)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Hook {
+    pub id: i32,
+    pub name: String,
+    /// Hash of the token callers must present to print. The token itself is only
+    /// returned on creation.
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub printer_id: i32,
+    /// Job payload whose strings are templates over the posted body; see
+    /// [`Hook::render`].
+    #[serde(serialize_with = "json_text")]
+    pub payload: String,
+    pub enabled: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// Template drafted from the first body the hook received, as a starting point
+    /// for its payload; see [`save_draft`]. Deleting it has the next body drafted.
+    pub draft_template_id: Option<i32>,
+}
+
+impl Hook {
+    /// Fill the hook's payload in from a posted body. The fields of a JSON object
+    /// or form are variables of their own, and the whole body is `payload`.
+    pub fn render(&self, body: Value) -> Result<JobPayload, String> {
+        let mut vars = match &body {
+            Value::Object(fields) => fields.clone(),
+            _ => Map::new(),
+        };
+        vars.insert("payload".into(), body);
+        let payload: Value = serde_json::from_str(&self.payload).map_err(|e| e.to_string())?;
+        let expanded = expand::expand_value(payload, &vars)?;
+        serde_json::from_value(expanded).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HookInput {
+    pub name: String,
+    pub printer_id: i32,
+    /// Prints the posted `text` field when omitted, which suits Slack's outgoing
+    /// webhooks.
+    #[serde(default = "default_payload")]
+    pub payload: Value,
+    /// Generated when omitted on creation, and left as it is on update.
+    #[serde(default)]
+    pub token: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_payload() -> Value {
+    json!({ "text": "{{ text }}", "cut": true })
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Insertable, AsChangeset)]
+#[diesel(table_name = hooks)]
+struct HookRow {
+    name: String,
+    printer_id: i32,
+    payload: String,
+    enabled: bool,
+}
+
+impl HookInput {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        if self.name.trim().is_empty() {
+            return Err(FieldError::new("name", "must not be empty"));
+        }
+        if !self.token.is_empty() && self.token.len() < 16 {
+            return Err(FieldError::new("token", "must be at least 16 characters"));
+        }
+        serde_json::from_value::<JobPayload>(self.payload.clone())
+            .map_err(|e| FieldError::new("payload", format!("is not a valid job: {e}")))?;
+        expand::check(&self.payload)
+            .map_err(|e| FieldError::new("payload", format!("is not a valid template: {e}")))
+    }
+
+    fn into_row(self) -> (HookRow, String) {
+        let row = HookRow {
+            name: self.name,
+            printer_id: self.printer_id,
+            payload: self.payload.to_string(),
+            enabled: self.enabled,
+        };
+        (row, self.token)
+    }
+}
+
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Hook>> {
+    let rows = hooks::table
+        .order(hooks::id.asc())
+        .select(Hook::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn get(conn: &mut SqliteConnection, id: i32) -> Result<Option<Hook>> {
+    let row = hooks::table
+        .find(id)
+        .select(Hook::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn get_by_name(conn: &mut SqliteConnection, name: &str) -> Result<Option<Hook>> {
+    let row = hooks::table
+        .filter(hooks::name.eq(name))
+        .select(Hook::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+/// The enabled hook `id`, only if `token` is its token. The hashes are compared in
+/// constant time, so how long a wrong token takes to refuse gives nothing away.
+// ring has deprecated its comparison for use outside the crate, with nothing to
+// replace it yet.
+#[allow(deprecated)]
+pub fn get_with_token(conn: &mut SqliteConnection, id: i32, token: &str) -> Result<Option<Hook>> {
+    let token_hash = hash(token);
+    Ok(get(conn, id)?.filter(|hook| {
+        hook.enabled
+            && ring::constant_time::verify_slices_are_equal(
+                hook.token_hash.as_bytes(),
+                token_hash.as_bytes(),
+            )
+            .is_ok()
+    }))
+}
+
+/// Hash the tokens stored before they were stored hashed, which the migration
+/// that started hashing them marked with a `plain:` prefix.
+pub fn hash_stored(conn: &mut SqliteConnection) -> Result<()> {
+    let plain: Vec<(i32, String)> = hooks::table
+        .filter(hooks::token_hash.like("plain:%"))
+        .select((hooks::id, hooks::token_hash))
+        .load(conn)?;
+    for (id, token) in plain {
+        let token = token.trim_start_matches("plain:");
+        diesel::update(hooks::table.find(id))
+            .set(hooks::token_hash.eq(hash(token)))
+            .execute(conn)?;
+    }
+    Ok(())
+}
+
+/// Create a hook, returning it alongside its plain token.
+pub fn create(conn: &mut SqliteConnection, input: HookInput) -> Result<(Hook, String)> {
+    let (row, mut token) = input.into_row();
+    if token.is_empty() {
+        token = uuid::Uuid::new_v4().simple().to_string();
+    }
+    let hook = diesel::insert_into(hooks::table)
+        .values((&row, hooks::token_hash.eq(hash(&token))))
+        .returning(Hook::as_returning())
+        .get_result(conn)?;
+    Ok((hook, token))
+}
+
+pub fn update(conn: &mut SqliteConnection, id: i32, input: HookInput) -> Result<Option<Hook>> {
+    let (row, token) = input.into_row();
+    let now = Utc::now().naive_utc();
+    let hook = if token.is_empty() {
+        diesel::update(hooks::table.find(id))
+            .set((&row, hooks::updated_at.eq(now)))
+            .returning(Hook::as_returning())
+            .get_result(conn)
+    } else {
+        diesel::update(hooks::table.find(id))
+            .set((
+                &row,
+                hooks::token_hash.eq(hash(&token)),
+                hooks::updated_at.eq(now),
+            ))
+            .returning(Hook::as_returning())
+            .get_result(conn)
+    }
+    .optional()?;
+    Ok(hook)
+}
+
+/// Save a template laid out from `sample`, a body hook `id` received, unless the
+/// hook has a draft already. Returns the template saved.
+pub fn save_draft(
+    conn: &mut SqliteConnection,
+    id: i32,
+    sample: &Value,
+) -> Result<Option<Template>> {
+    // Slack sends the hook's token in the body; keep it out of the template.
+    let mut sample = sample.clone();
+    if let Value::Object(fields) = &mut sample {
+        fields.remove("token");
+    }
+    conn.immediate_transaction(|conn| {
+        let Some(hook) = get(conn, id)?.filter(|h| h.draft_template_id.is_none()) else {
+            return Ok(None);
+        };
+        let mut name = format!("{} draft", hook.name);
+        let mut n = 1;
+        while templates::get_by_name(conn, &name)?.is_some() {
+            n += 1;
+            name = format!("{} draft {n}", hook.name);
+        }
+        let template = templates::insert(
+            conn,
+            TemplateInput {
+                name,
+                description: Some(format!("Drafted from a body posted to hook {}", hook.name)),
+                payload: draft::from_sample(&sample).payload,
+                header: Default::default(),
+                footer: Default::default(),
+            },
+        )?;
+        diesel::update(hooks::table.find(id))
+            .set(hooks::draft_template_id.eq(template.id))
+            .execute(conn)?;
+        Ok(Some(template))
+    })
+}
+
+pub fn delete(conn: &mut SqliteConnection, id: i32) -> Result<bool> {
+    let deleted = diesel::delete(hooks::table.find(id)).execute(conn)?;
+    Ok(deleted > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::printers::{self, NewPrinter};
+
+    fn hook(conn: &mut SqliteConnection) -> Hook {
+        let printer = printers::create(
+            conn,
+            serde_json::from_value::<NewPrinter>(json!({
+                "name": "desk",
+                "transport": "usb_lp",
+                "path": "/dev/usb/lp0",
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+        let input = HookInput {
+            name: "alerts".into(),
+            printer_id: printer.id,
+            payload: default_payload(),
+            token: String::new(),
+            enabled: true,
+        };
+        create(conn, input).unwrap().0
+    }
+
+    #[test]
+    fn first_body_is_drafted_into_a_template() {
+        let mut conn = db::test_connection();
+        let hook = hook(&mut conn);
+        let body = json!({
+            "token": "secret-token-value",
+            "alert": {"title": "Disk full", "message": "/var is at 98%"},
+        });
+
+        let template = save_draft(&mut conn, hook.id, &body).unwrap().unwrap();
+        assert_eq!(template.name, "alerts draft");
+        assert!(template.payload.contains("{{ alert.title }}"));
+        assert!(template.payload.contains("{{ alert.message }}"));
+        assert!(!template.payload.contains("token"));
+        let hook = get(&mut conn, hook.id).unwrap().unwrap();
+        assert_eq!(hook.draft_template_id, Some(template.id));
+    }
+
+    #[test]
+    fn later_bodies_keep_the_draft() {
+        let mut conn = db::test_connection();
+        let hook = hook(&mut conn);
+        save_draft(&mut conn, hook.id, &json!({"text": "first"})).unwrap();
+
+        assert!(
+            save_draft(&mut conn, hook.id, &json!({"title": "second"}))
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(templates::list(&mut conn).unwrap().len(), 1);
+    }
+}
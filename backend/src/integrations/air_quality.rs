@@ -0,0 +1,274 @@
+//! The air quality index with its dominant pollutant, and the day's pollen, from
+//! Open-Meteo's air quality API, each next to an icon of bars showing how bad it is.
+//! Pollen is only forecast for Europe; elsewhere just the index is printed.
+
+use anyhow::{Context as _, anyhow};
+use chrono::TimeDelta;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use super::sun_moon::coordinate;
+use super::{Context, Fetch, Integration};
+use crate::compose::Section;
+use crate::document::Block;
+
+const DEFAULT_API: &str = "https://air-quality-api.open-meteo.com/v1/air-quality";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const POLLUTANTS: &[(&str, &str)] = &[
+    ("pm2_5", "fine particles"),
+    ("pm10", "particles"),
+    ("nitrogen_dioxide", "nitrogen dioxide"),
+    ("ozone", "ozone"),
+    ("sulphur_dioxide", "sulphur dioxide"),
+    ("carbon_monoxide", "carbon monoxide"),
+];
+/// Pollen types with the grains per m³ at which they become low, moderate, high
+/// and very high.
+const POLLEN: &[(&str, &str, [f64; 4])] = &[
+    ("alder_pollen", "Alder", [1.0, 15.0, 90.0, 1500.0]),
+    ("birch_pollen", "Birch", [1.0, 15.0, 90.0, 1500.0]),
+    ("olive_pollen", "Olive", [1.0, 15.0, 90.0, 1500.0]),
+    ("grass_pollen", "Grass", [1.0, 5.0, 20.0, 200.0]),
+    ("mugwort_pollen", "Mugwort", [1.0, 10.0, 50.0, 500.0]),
+    ("ragweed_pollen", "Ragweed", [1.0, 10.0, 50.0, 500.0]),
+];
+const POLLEN_LEVELS: [&str; 5] = ["none", "low", "moderate", "high", "very high"];
+
+pub struct AirQuality;
+
+/// The air as fetched.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Air {
+    /// `european` or `us`.
+    index: String,
+    aqi: Option<f64>,
+    /// Each pollutant's own index, by Open-Meteo's name for it.
+    #[serde(default)]
+    pollutants: BTreeMap<String, f64>,
+    /// The day's highest grains per m³, by Open-Meteo's name for the pollen.
+    #[serde(default)]
+    pollen: BTreeMap<String, f64>,
+}
+
+/// `aqi`'s category and its severity from 0 to 4, on the European or US scale.
+fn category(index: &str, aqi: f64) -> (&'static str, usize) {
+    let bands: [(f64, &str); 5] = if index == "us" {
+        [
+            (50.0, "good"),
+            (100.0, "moderate"),
+            (150.0, "unhealthy for sensitive groups"),
+            (200.0, "unhealthy"),
+            (300.0, "very unhealthy"),
+        ]
+    } else {
+        [
+            (20.0, "good"),
+            (40.0, "fair"),
+            (60.0, "moderate"),
+            (80.0, "poor"),
+            (100.0, "very poor"),
+        ]
+    };
+    match bands.iter().position(|(top, _)| aqi <= *top) {
+        Some(i) => (bands[i].1, i.min(4)),
+        None if index == "us" => ("hazardous", 4),
+        None => ("extremely poor", 4),
+    }
+}
+
+impl Integration for AirQuality {
+    fn slug(&self) -> &'static str {
+        "air_quality"
+    }
+
+    fn name(&self) -> &'static str {
+        "Air quality and pollen"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "required": ["latitude", "longitude"],
+            "properties": {
+                "latitude": { "type": "number", "minimum": -90, "maximum": 90 },
+                "longitude": { "type": "number", "minimum": -180, "maximum": 180 },
+                "timezone": {
+                    "type": "string",
+                    "description": "IANA time zone the day's pollen is taken in; the coordinates' when unset"
+                },
+                "title": { "type": "string", "default": "Air quality" },
+                "index": {
+                    "type": "string",
+                    "enum": ["european", "us"],
+                    "default": "european",
+                    "description": "Which air quality index to print"
+                },
+                "pollen": { "type": "boolean", "default": true },
+                "api_url": {
+                    "type": "string",
+                    "format": "uri",
+                    "default": DEFAULT_API,
+                    "description": "Open-Meteo's air quality API or a self-hosted one"
+                }
+            }
+        })
+    }
+
+    fn fetch<'a>(&'a self, ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        Box::pin(async move {
+            let latitude = coordinate(settings, "latitude", 90.0)?;
+            let longitude = coordinate(settings, "longitude", 180.0)?;
+            let index = match settings.get("index").and_then(Value::as_str) {
+                Some("us") => "us",
+                _ => "european",
+            };
+            let pollen = settings
+                .get("pollen")
+                .and_then(Value::as_bool)
+                .unwrap_or(true);
+            let timezone = settings
+                .get("timezone")
+                .and_then(Value::as_str)
+                .unwrap_or("auto");
+            let api = settings
+                .get("api_url")
+                .and_then(Value::as_str)
+                .unwrap_or(DEFAULT_API);
+
+            let mut current = vec![format!("{index}_aqi")];
+            current.extend(
+                POLLUTANTS
+                    .iter()
+                    // The European index leaves carbon monoxide out.
+                    .filter(|(p, _)| index == "us" || *p != "carbon_monoxide")
+                    .map(|(p, _)| format!("{index}_aqi_{p}")),
+            );
+            let mut query = vec![
+                ("latitude", latitude.to_string()),
+                ("longitude", longitude.to_string()),
+                ("timezone", timezone.to_string()),
+                ("current", current.join(",")),
+                ("start_date", ctx.date.to_string()),
+                ("end_date", ctx.date.to_string()),
+            ];
+            if pollen {
+                let hourly: Vec<&str> = POLLEN.iter().map(|(p, ..)| *p).collect();
+                query.push(("hourly", hourly.join(",")));
+            }
+            let response: Response = reqwest::Client::builder()
+                .timeout(TIMEOUT)
+                .build()?
+                .get(api)
+                .query(&query)
+                .send()
+                .await?
+                .error_for_status()
+                .with_context(|| format!("fetching {api}"))?
+                .json()
+                .await?;
+
+            let current = response
+                .current
+                .ok_or_else(|| anyhow!("no current air quality"))?;
+            let prefix = format!("{index}_aqi_");
+            let mut air = Air {
+                index: index.into(),
+                aqi: current.get(&format!("{index}_aqi")).and_then(Value::as_f64),
+                ..Air::default()
+            };
+            for (name, value) in &current {
+                if let (Some(pollutant), Some(value)) = (name.strip_prefix(&prefix), value.as_f64())
+                {
+                    air.pollutants.insert(pollutant.into(), value);
+                }
+            }
+            for (name, values) in response.hourly.unwrap_or_default() {
+                // Hours without a forecast, as outside Europe, are null.
+                let highest = values
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_f64)
+                    .reduce(f64::max);
+                if let Some(highest) = highest
+                    && POLLEN.iter().any(|(p, ..)| *p == name)
+                {
+                    air.pollen.insert(name, highest);
+                }
+            }
+            Ok(serde_json::to_value(air)?)
+        })
+    }
+
+    fn render(&self, _ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        let air: Air = serde_json::from_value(data.clone()).unwrap_or_default();
+        let glyph = |level: usize, text: String| Block::Glyph {
+            name: format!("level-{level}"),
+            text,
+        };
+        let mut blocks = Vec::new();
+        let mut lines = Vec::new();
+        let summary = match air.aqi {
+            Some(aqi) => {
+                let (name, level) = category(&air.index, aqi);
+                let dominant = air
+                    .pollutants
+                    .iter()
+                    .max_by(|a, b| a.1.total_cmp(b.1))
+                    .and_then(|(p, _)| POLLUTANTS.iter().find(|(name, _)| name == p))
+                    .map(|(_, label)| format!(", mostly {label}"))
+                    .unwrap_or_default();
+                blocks.push(glyph(level, format!("AQI {aqi:.0}, {name}{dominant}")));
+                Some(format!("AQI {aqi:.0} ({name})"))
+            }
+            None => {
+                lines.push("Air quality unavailable".into());
+                None
+            }
+        };
+        let mut any_pollen = false;
+        for (key, label, thresholds) in POLLEN {
+            let Some(grains) = air.pollen.get(*key) else {
+                continue;
+            };
+            any_pollen = true;
+            let level = thresholds.iter().filter(|t| *grains >= **t).count();
+            if level > 0 {
+                blocks.push(glyph(
+                    level,
+                    format!("{label} pollen {}", POLLEN_LEVELS[level]),
+                ));
+            }
+        }
+        if any_pollen && !air.pollen.values().any(|g| *g >= 1.0) {
+            blocks.push(glyph(0, "No pollen".into()));
+        }
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("Air quality")
+                .into(),
+            priority: 0,
+            lines,
+            blocks,
+            summary,
+        }
+    }
+
+    fn cache_ttl(&self) -> TimeDelta {
+        TimeDelta::minutes(30)
+    }
+}
+
+#[derive(Deserialize)]
+struct Response {
+    #[serde(default)]
+    current: Option<Map<String, Value>>,
+    /// Each pollen's hourly values, and the hours themselves under `time`.
+    #[serde(default)]
+    hourly: Option<Map<String, Value>>,
+}
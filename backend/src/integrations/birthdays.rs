@@ -0,0 +1,151 @@
+//! Birthdays and anniversaries falling on the day, and those coming up in the days
+//! after it as a reminder.
+
+use chrono::{Datelike, Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use super::{Context, Fetch, Integration};
+use crate::compose::Section;
+use crate::db;
+use crate::occasions::{self, Kind};
+
+pub struct Birthdays;
+
+/// An occasion as fetched, on its next date.
+#[derive(Debug, Serialize, Deserialize)]
+struct Item {
+    name: String,
+    kind: Kind,
+    date: NaiveDate,
+    /// Birthday or anniversary it is, when the year it started is known.
+    years: Option<i32>,
+}
+
+impl Item {
+    fn describe(&self) -> String {
+        let nth = self.years.filter(|&y| y > 0).map(ordinal);
+        match (self.kind, nth) {
+            (Kind::Birthday, Some(nth)) => format!("{} {nth} birthday", possessive(&self.name)),
+            (Kind::Birthday, None) => format!("{} birthday", possessive(&self.name)),
+            (Kind::Anniversary, Some(nth)) => format!("{} ({nth} anniversary)", self.name),
+            (Kind::Anniversary, None) => format!("{} anniversary", self.name),
+            (Kind::Other, _) => self.name.clone(),
+        }
+    }
+
+    fn emoji(&self) -> &'static str {
+        match self.kind {
+            Kind::Birthday => "🎂",
+            Kind::Anniversary => "🎉",
+            Kind::Other => "📅",
+        }
+    }
+}
+
+fn possessive(name: &str) -> String {
+    if name.ends_with('s') {
+        format!("{name}'")
+    } else {
+        format!("{name}'s")
+    }
+}
+
+fn ordinal(n: i32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+impl Integration for Birthdays {
+    fn slug(&self) -> &'static str {
+        "birthdays"
+    }
+
+    fn name(&self) -> &'static str {
+        "Birthdays and anniversaries"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string", "default": "Birthdays" },
+                "days_ahead": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "default": 7,
+                    "description": "Also remind of occasions this many days after the printout's"
+                }
+            }
+        })
+    }
+
+    fn fetch<'a>(&'a self, ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        let days_ahead = settings
+            .get("days_ahead")
+            .and_then(Value::as_u64)
+            .unwrap_or(7);
+        let today = ctx.date;
+        Box::pin(async move {
+            let until = today
+                .checked_add_days(Days::new(days_ahead))
+                .unwrap_or(today);
+            let mut items: Vec<Item> = db::run_blocking_db(occasions::list)
+                .await?
+                .into_iter()
+                .filter_map(|occasion| {
+                    let date = occasion.next(today).filter(|d| *d <= until)?;
+                    Some(Item {
+                        kind: occasion.kind(),
+                        years: occasion.year.map(|y| date.year() - y),
+                        name: occasion.name,
+                        date,
+                    })
+                })
+                .collect();
+            items.sort_by_key(|i| i.date);
+            Ok(serde_json::to_value(items)?)
+        })
+    }
+
+    fn render(&self, ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        let items: Vec<Item> = serde_json::from_value(data.clone()).unwrap_or_default();
+        let mut lines: Vec<String> = items
+            .iter()
+            .map(|item| {
+                if item.date == ctx.date {
+                    format!("Today: {} {}", item.describe(), item.emoji())
+                } else {
+                    format!("{}: {}", item.date.format("%a %-d %b"), item.describe())
+                }
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push("Nothing coming up".into());
+        }
+        let today = items.iter().filter(|i| i.date == ctx.date).count();
+        let summary = match today {
+            0 => format!("{} coming up", items.len()),
+            n => format!("{n} today, {} coming up", items.len() - n),
+        };
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("Birthdays")
+                .into(),
+            // Worth keeping over most sections on a short printout when it's today.
+            priority: if today > 0 { 1 } else { 0 },
+            lines,
+            blocks: Vec::new(),
+            summary: Some(summary),
+        }
+    }
+}
@@ -0,0 +1,374 @@
+//! The day's events straight from a CalDAV server such as Nextcloud, Radicale or
+//! Baïkal. The account's calendars are discovered from the server address, and
+//! each is asked only for the events around the day.
+
+use anyhow::{Context as _, Result, bail};
+use chrono::{Days, NaiveTime, TimeDelta};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, WWW_AUTHENTICATE};
+use reqwest::{Method, StatusCode, Url};
+use serde_json::{Map, Value, json};
+
+use super::digest::Challenge;
+use super::ical::{self, Zone};
+use super::remote_calendar;
+use super::{Check, Context, CredentialsRefused, Fetch, Integration};
+use crate::compose::Section;
+
+pub struct CalDav;
+
+impl Integration for CalDav {
+    fn slug(&self) -> &'static str {
+        "caldav"
+    }
+
+    fn name(&self) -> &'static str {
+        "Calendar (CalDAV)"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "format": "uri",
+                    "description": "Server, account or calendar address"
+                },
+                "username": { "type": "string" },
+                "password": { "type": "string", "format": "password" },
+                "calendars": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Names of the calendars to print; all of them when empty"
+                },
+                "title": { "type": "string", "default": "Calendar" },
+                "timezone": {
+                    "type": "string",
+                    "description": "IANA time zone the times are printed in; the server's when unset"
+                },
+                "show_location": { "type": "boolean", "default": true }
+            },
+            "required": ["url"]
+        })
+    }
+
+    fn fetch<'a>(&'a self, ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        Box::pin(async move {
+            let text = |key| settings.get(key).and_then(Value::as_str);
+            let url = Url::parse(text("url").context("url is not set")?)?;
+            let zone = match text("timezone") {
+                Some(name) => Zone::Named(
+                    name.parse()
+                        .map_err(|_| anyhow::anyhow!("unknown time zone {name}"))?,
+                ),
+                None => Zone::Local,
+            };
+            let wanted: Vec<&str> = settings
+                .get("calendars")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .collect();
+            let dav = Dav::new(settings);
+
+            let mut events = Vec::new();
+            for calendar in dav.calendars(&url).await? {
+                if !wanted.is_empty() && !wanted.contains(&calendar.name.as_str()) {
+                    continue;
+                }
+                for data in dav.events_around(&calendar.url, ctx).await? {
+                    events.extend(ical::parse(&data));
+                }
+            }
+            Ok(serde_json::to_value(ical::agenda(&events, ctx.date, zone))?)
+        })
+    }
+
+    fn render(&self, ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        Section {
+            integration: self.slug().into(),
+            ..remote_calendar::RemoteCalendar.render(ctx, settings, data)
+        }
+    }
+
+    fn cache_ttl(&self) -> TimeDelta {
+        TimeDelta::minutes(15)
+    }
+
+    fn check_credentials<'a>(&'a self, settings: &'a Map<String, Value>) -> Option<Check<'a>> {
+        let dav = Dav::new(settings);
+        if dav.username.is_empty() {
+            return None;
+        }
+        Some(Box::pin(async move {
+            let url = settings
+                .get("url")
+                .and_then(Value::as_str)
+                .context("url is not set")?;
+            dav.request("PROPFIND", &Url::parse(url)?, "0", PROPFIND_START)
+                .await?;
+            Ok(())
+        }))
+    }
+}
+
+/// A calendar collection on the server.
+struct Calendar {
+    url: Url,
+    name: String,
+}
+
+/// WebDAV requests as one user, with Basic or Digest authentication as the server
+/// asks.
+struct Dav {
+    client: reqwest::Client,
+    username: String,
+    password: String,
+}
+
+impl Dav {
+    fn new(settings: &Map<String, Value>) -> Self {
+        let text = |key| {
+            settings
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+        };
+        Self {
+            client: reqwest::Client::new(),
+            username: text("username").to_string(),
+            password: text("password").to_string(),
+        }
+    }
+
+    /// Calendars under `url`: the collection itself if it is one, otherwise those in
+    /// the home of the principal it leads to.
+    async fn calendars(&self, url: &Url) -> Result<Vec<Calendar>> {
+        let body = self.request("PROPFIND", url, "0", PROPFIND_START).await?;
+        if let Some(calendar) = responses(&body, url)
+            .into_iter()
+            .find(|(_, props)| is_calendar(props))
+        {
+            return Ok(vec![named(calendar)]);
+        }
+
+        let principal = match first_href(&body, "current-user-principal") {
+            Some(href) => url.join(&href)?,
+            None => {
+                let well_known = url.join("/.well-known/caldav")?;
+                let body = self
+                    .request("PROPFIND", &well_known, "0", PROPFIND_START)
+                    .await?;
+                let href = first_href(&body, "current-user-principal")
+                    .context("the server didn't say which principal the account is")?;
+                well_known.join(&href)?
+            }
+        };
+        let body = self
+            .request("PROPFIND", &principal, "0", PROPFIND_HOME)
+            .await?;
+        let home = principal.join(
+            &first_href(&body, "calendar-home-set").context("the account has no calendar home")?,
+        )?;
+        let body = self
+            .request("PROPFIND", &home, "1", PROPFIND_CALENDARS)
+            .await?;
+        Ok(responses(&body, &home)
+            .into_iter()
+            .filter(|(_, props)| is_calendar(props))
+            .map(named)
+            .collect())
+    }
+
+    /// iCalendar data of the events in `calendar` from the day before `ctx.date` to
+    /// the day after, so every time zone's view of the day is covered.
+    async fn events_around(&self, calendar: &Url, ctx: &Context) -> Result<Vec<String>> {
+        let from = ctx.date.checked_sub_days(Days::new(1)).unwrap_or(ctx.date);
+        let to = ctx.date.checked_add_days(Days::new(2)).unwrap_or(ctx.date);
+        let stamp = |date: chrono::NaiveDate| {
+            date.and_time(NaiveTime::MIN)
+                .format("%Y%m%dT%H%M%SZ")
+                .to_string()
+        };
+        let query = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop><C:calendar-data/></D:prop>
+  <C:filter>
+    <C:comp-filter name="VCALENDAR">
+      <C:comp-filter name="VEVENT">
+        <C:time-range start="{}" end="{}"/>
+      </C:comp-filter>
+    </C:comp-filter>
+  </C:filter>
+</C:calendar-query>"#,
+            stamp(from),
+            stamp(to)
+        );
+        let body = self.request("REPORT", calendar, "1", &query).await?;
+        Ok(elements(&body, "calendar-data")
+            .into_iter()
+            .map(unescape)
+            .collect())
+    }
+
+    /// Send a WebDAV request, answering a Digest challenge if the server makes one.
+    async fn request(&self, method: &str, url: &Url, depth: &str, body: &str) -> Result<String> {
+        let method = Method::from_bytes(method.as_bytes())?;
+        let send = |authorization: Option<String>| {
+            let mut request = self
+                .client
+                .request(method.clone(), url.clone())
+                .header("Depth", depth)
+                .header(CONTENT_TYPE, "application/xml; charset=utf-8")
+                .body(body.to_string());
+            request = match authorization {
+                Some(header) => request.header(AUTHORIZATION, header),
+                None if !self.username.is_empty() => {
+                    request.basic_auth(&self.username, Some(&self.password))
+                }
+                None => request,
+            };
+            request.send()
+        };
+
+        let mut response = send(None)
+            .await
+            .with_context(|| format!("failed to reach {url}"))?;
+        if response.status() == StatusCode::UNAUTHORIZED
+            && let Some(challenge) = response
+                .headers()
+                .get_all(WWW_AUTHENTICATE)
+                .iter()
+                .filter_map(|h| h.to_str().ok())
+                .find_map(Challenge::parse)
+        {
+            let uri = match url.query() {
+                Some(query) => format!("{}?{query}", url.path()),
+                None => url.path().to_string(),
+            };
+            let header =
+                challenge.authorization(&self.username, &self.password, method.as_str(), &uri);
+            response = send(Some(header)).await?;
+        }
+        match response.status() {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                Err(CredentialsRefused(format!("{url} refused the username and password")).into())
+            }
+            status if !status.is_success() => bail!("{method} {url} failed with {status}"),
+            _ => Ok(response.text().await?),
+        }
+    }
+}
+
+const PROPFIND_START: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:propfind xmlns:D="DAV:">
+  <D:prop><D:current-user-principal/><D:resourcetype/><D:displayname/></D:prop>
+</D:propfind>"#;
+
+const PROPFIND_HOME: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:propfind xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop><C:calendar-home-set/></D:prop>
+</D:propfind>"#;
+
+const PROPFIND_CALENDARS: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:propfind xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop><D:resourcetype/><D:displayname/><C:supported-calendar-component-set/></D:prop>
+</D:propfind>"#;
+
+/// Each `response` in a multistatus body: its resolved href and its XML.
+fn responses<'a>(body: &'a str, base: &Url) -> Vec<(Url, &'a str)> {
+    elements(body, "response")
+        .into_iter()
+        .filter_map(|response| {
+            let href = elements(response, "href").into_iter().next()?;
+            Some((base.join(unescape(href).trim()).ok()?, response))
+        })
+        .collect()
+}
+
+/// A collection whose resource type is a calendar that can hold events.
+fn is_calendar(props: &str) -> bool {
+    let calendar = elements(props, "resourcetype")
+        .iter()
+        .any(|t| !elements(t, "calendar").is_empty());
+    let components = elements(props, "supported-calendar-component-set");
+    calendar
+        && components
+            .iter()
+            .all(|set| set.contains("\"VEVENT\"") || set.contains("'VEVENT'"))
+}
+
+fn named((url, props): (Url, &str)) -> Calendar {
+    let name = elements(props, "displayname")
+        .into_iter()
+        .next()
+        .map(unescape)
+        .filter(|n| !n.trim().is_empty())
+        .unwrap_or_else(|| {
+            url.path_segments()
+                .and_then(|mut s| s.rfind(|s| !s.is_empty()))
+                .unwrap_or_default()
+                .to_string()
+        });
+    Calendar { url, name }
+}
+
+/// The `href` inside the first `property` element.
+fn first_href(body: &str, property: &str) -> Option<String> {
+    let inner = elements(body, property).into_iter().next()?;
+    let href = elements(inner, "href").into_iter().next()?;
+    Some(unescape(href).trim().to_string())
+}
+
+/// Contents of every element called `local` in any namespace, outermost first.
+/// Self-closing elements are empty.
+fn elements<'a>(xml: &'a str, local: &str) -> Vec<&'a str> {
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(open) = rest.find('<') {
+        rest = &rest[open + 1..];
+        let end = rest.find('>').unwrap_or(rest.len());
+        let tag = &rest[..end];
+        let name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        let matches = name.rsplit(':').next() == Some(local) && !tag.starts_with(['/', '?', '!']);
+        if !matches {
+            continue;
+        }
+        let after = &rest[(end + 1).min(rest.len())..];
+        if tag.ends_with('/') {
+            found.push("");
+            rest = after;
+            continue;
+        }
+        let close = format!("</{name}");
+        let Some(stop) = after.find(&close) else {
+            break;
+        };
+        found.push(&after[..stop]);
+        rest = &after[stop..];
+    }
+    found
+}
+
+/// Text content with entities and CDATA sections resolved.
+fn unescape(text: &str) -> String {
+    let text = text.trim();
+    if let Some(cdata) = text
+        .strip_prefix("<![CDATA[")
+        .and_then(|t| t.strip_suffix("]]>"))
+    {
+        return cdata.to_string();
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#13;", "\r")
+        .replace("&#xD;", "\r")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,92 @@
+//! The days left until each countdown's date, nearest first.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use super::{Context, Fetch, Integration};
+use crate::compose::Section;
+use crate::{countdowns, db};
+
+pub struct Countdowns;
+
+/// A countdown as fetched.
+#[derive(Debug, Serialize, Deserialize)]
+struct Item {
+    name: String,
+    date: NaiveDate,
+}
+
+impl Integration for Countdowns {
+    fn slug(&self) -> &'static str {
+        "countdowns"
+    }
+
+    fn name(&self) -> &'static str {
+        "Countdowns"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string", "default": "Countdowns" },
+                "limit": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Print only this many of the nearest countdowns"
+                }
+            }
+        })
+    }
+
+    fn fetch<'a>(&'a self, ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        let limit = settings
+            .get("limit")
+            .and_then(Value::as_u64)
+            .map_or(usize::MAX, |n| n as usize);
+        let date = ctx.date;
+        Box::pin(async move {
+            let items: Vec<Item> = db::run_blocking_db(countdowns::list)
+                .await?
+                .into_iter()
+                .filter(|c| c.target_date >= date)
+                .take(limit)
+                .map(|c| Item {
+                    name: c.name,
+                    date: c.target_date,
+                })
+                .collect();
+            Ok(serde_json::to_value(items)?)
+        })
+    }
+
+    fn render(&self, ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        let items: Vec<Item> = serde_json::from_value(data.clone()).unwrap_or_default();
+        let mut lines: Vec<String> = items
+            .iter()
+            .map(|item| match (item.date - ctx.date).num_days() {
+                0 => format!("{} is today!", item.name),
+                1 => format!("1 day until {}", item.name),
+                n => format!("{n} days until {}", item.name),
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push("Nothing to count down to".into());
+        }
+        // The nearest countdown stands for the rest on a short printout.
+        let summary = lines[0].clone();
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("Countdowns")
+                .into(),
+            priority: 0,
+            lines,
+            blocks: Vec::new(),
+            summary: Some(summary),
+        }
+    }
+}
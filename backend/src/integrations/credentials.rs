@@ -0,0 +1,139 @@
+//! Checks of instances' stored credentials, hourly and whenever an instance changes,
+//! so an expired token or changed password is flagged on the instance, and sent to
+//! the webhooks as `integration.credentials_invalid`, before the next digest prints
+//! without its section.
+
+use anyhow::{Result, anyhow};
+use diesel::prelude::*;
+use serde_json::json;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+use super::{CredentialsRefused, Instance};
+use crate::events::{ConfigId, ConfigScope, Event, EventBus};
+use crate::schema::integration_instances;
+use crate::{db, webhooks};
+
+const EVERY: Duration = Duration::from_secs(60 * 60);
+/// Longest a service may take to answer a check.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn spawn(events: EventBus) {
+    let mut rx = events.subscribe();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(EVERY);
+        loop {
+            let only = tokio::select! {
+                _ = interval.tick() => None,
+                event = rx.recv() => match event {
+                    // New settings may be new credentials, or the ones that stop working.
+                    Ok(Event::ConfigChanged {
+                        scope: ConfigScope::Integration,
+                        id: ConfigId::InstanceId(id),
+                    }) => Some(id),
+                    Err(RecvError::Closed) => {
+                        interval.tick().await;
+                        None
+                    }
+                    _ => continue,
+                },
+            };
+            if let Err(err) = check(only).await {
+                warn!("checking integration credentials failed: {err:#}");
+            }
+        }
+    });
+}
+
+/// Check the credentials of every enabled instance, or only of `only`.
+async fn check(only: Option<String>) -> Result<()> {
+    let instances = db::run_blocking_db(super::list).await?;
+    let mut checks = JoinSet::new();
+    for instance in instances
+        .into_iter()
+        .filter(|i| i.enabled && only.as_ref().is_none_or(|id| *id == i.instance_id))
+    {
+        checks.spawn(check_instance(instance));
+    }
+    while checks.join_next().await.is_some() {}
+    Ok(())
+}
+
+/// Check one instance's credentials and record what was found, alerting when they
+/// stop working. A check that fails for another reason, such as the service being
+/// down, leaves the instance as it was.
+async fn check_instance(instance: Instance) {
+    let Ok(integration) = instance.integration() else {
+        return;
+    };
+    let result = match instance.decrypted_settings() {
+        Ok(settings) => {
+            let Some(check) = integration.check_credentials(&settings) else {
+                return;
+            };
+            tokio::time::timeout(TIMEOUT, check)
+                .await
+                .unwrap_or_else(|_| Err(anyhow!("timed out")))
+        }
+        Err(err) => Err(CredentialsRefused(format!("they can't be decrypted: {err:#}")).into()),
+    };
+    let error = match result {
+        Ok(()) => None,
+        Err(err) => match err
+            .chain()
+            .find_map(|e| e.downcast_ref::<CredentialsRefused>())
+        {
+            Some(refused) => Some(refused.to_string()),
+            None => {
+                warn!(
+                    "could not check {}'s credentials: {err:#}",
+                    instance.instance_id
+                );
+                return;
+            }
+        },
+    };
+    if error == instance.credentials_error && error.is_some() == instance.credentials_invalid {
+        return;
+    }
+
+    let (id, stored) = (instance.instance_id.clone(), error.clone());
+    let recorded = db::run_blocking_db(move |conn| {
+        diesel::update(integration_instances::table.find(&id))
+            .set((
+                integration_instances::credentials_invalid.eq(stored.is_some()),
+                integration_instances::credentials_error.eq(stored),
+            ))
+            .execute(conn)?;
+        Ok(())
+    })
+    .await;
+    if let Err(err) = recorded {
+        warn!(
+            "could not record {}'s credentials check: {err:#}",
+            instance.instance_id
+        );
+        return;
+    }
+    match error {
+        Some(error) if !instance.credentials_invalid => {
+            warn!(
+                "{} ({}): credentials no longer work: {error}",
+                instance.instance_id, instance.slug
+            );
+            webhooks::deliver::notify(
+                "integration.credentials_invalid",
+                None,
+                json!({
+                    "instance_id": instance.instance_id,
+                    "slug": instance.slug,
+                    "error": error,
+                }),
+            );
+        }
+        None => info!("{}'s credentials work again", instance.instance_id),
+        Some(_) => {}
+    }
+}
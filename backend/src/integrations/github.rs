@@ -0,0 +1,409 @@
+//! Pull requests waiting on the user's review and failing CI runs, from GitHub's
+//! API with a personal access token, grouped by repository with a QR code to open
+//! each one.
+
+use anyhow::{Context as _, Result, anyhow};
+use chrono::{DateTime, Days, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tracing::warn;
+
+use super::{Check, Context, CredentialsRefused, Fetch, Integration};
+use crate::compose::Section;
+use crate::document::{Align, Block, Style};
+
+const DEFAULT_API: &str = "https://api.github.com";
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Codes printed at most, so a busy morning doesn't run off the roll.
+const MAX_CODES: usize = 8;
+
+pub struct GitHub;
+
+/// A pull request the user's review is requested on.
+#[derive(Debug, Serialize, Deserialize)]
+struct Review {
+    repo: String,
+    number: u64,
+    title: String,
+    author: String,
+    url: String,
+}
+
+/// A workflow whose latest run on a branch failed.
+#[derive(Debug, Serialize, Deserialize)]
+struct Failure {
+    repo: String,
+    workflow: String,
+    branch: String,
+    url: String,
+    at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Notifications {
+    reviews: Vec<Review>,
+    failures: Vec<Failure>,
+    /// Repositories whose runs couldn't be fetched.
+    #[serde(default)]
+    unavailable: Vec<String>,
+}
+
+impl Integration for GitHub {
+    fn slug(&self) -> &'static str {
+        "github"
+    }
+
+    fn name(&self) -> &'static str {
+        "GitHub reviews and CI"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "required": ["token"],
+            "properties": {
+                "title": { "type": "string", "default": "GitHub" },
+                "token": {
+                    "type": "string",
+                    "format": "password",
+                    "description": "Personal access token that can read the repositories' pull requests and actions"
+                },
+                "ci_repositories": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Repositories, as owner/name, whose failing workflow runs are printed"
+                },
+                "qr_codes": {
+                    "type": "boolean",
+                    "default": true,
+                    "description": "Print a code to open each pull request and run"
+                },
+                "api_url": {
+                    "type": "string",
+                    "format": "uri",
+                    "default": DEFAULT_API,
+                    "description": "GitHub's API, or a GitHub Enterprise server's such as https://github.example.com/api/v3"
+                }
+            }
+        })
+    }
+
+    fn fetch<'a>(&'a self, ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        Box::pin(async move {
+            let token = settings
+                .get("token")
+                .and_then(Value::as_str)
+                .context("token is not set")?;
+            let repos: Vec<String> = settings
+                .get("ci_repositories")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(|r| r.trim().trim_matches('/').to_string())
+                .filter(|r| !r.is_empty())
+                .collect();
+            let api = api(settings).to_string();
+            let client = client(token)?;
+            // Runs since yesterday's start, so the evening's failures make the
+            // morning's printout.
+            let since = ctx.date.checked_sub_days(Days::new(1)).unwrap_or(ctx.date);
+
+            let mut downloads = JoinSet::new();
+            for repo in repos {
+                let (client, api) = (client.clone(), api.clone());
+                downloads.spawn(async move {
+                    let runs = failures(&client, &api, &repo, &since.to_string()).await;
+                    (repo, runs)
+                });
+            }
+            let mut fetched = Notifications {
+                reviews: reviews(&client, &api).await?,
+                ..Notifications::default()
+            };
+            while let Some(done) = downloads.join_next().await {
+                match done? {
+                    (_, Ok(runs)) => fetched.failures.extend(runs),
+                    (repo, Err(e)) => {
+                        warn!("github: {repo}: {e:#}");
+                        fetched.unavailable.push(repo);
+                    }
+                }
+            }
+            fetched.failures.sort_by_key(|f| f.at);
+            fetched.unavailable.sort();
+            Ok(serde_json::to_value(fetched)?)
+        })
+    }
+
+    fn render(&self, _ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        let fetched: Notifications = serde_json::from_value(data.clone()).unwrap_or_default();
+        let mut by_repo: BTreeMap<&str, (Vec<&Review>, Vec<&Failure>)> = BTreeMap::new();
+        for review in &fetched.reviews {
+            by_repo.entry(&review.repo).or_default().0.push(review);
+        }
+        for failure in &fetched.failures {
+            by_repo.entry(&failure.repo).or_default().1.push(failure);
+        }
+
+        let mut lines = Vec::new();
+        // Label and URL of each code, in the order the lines list them.
+        let mut links = Vec::new();
+        for (repo, (reviews, failures)) in &by_repo {
+            lines.push(repo.to_string());
+            for review in reviews {
+                lines.push(format!(
+                    "  Review #{} {} (@{})",
+                    review.number, review.title, review.author
+                ));
+                links.push((format!("{repo}#{}", review.number), &review.url));
+            }
+            for failure in failures {
+                lines.push(format!(
+                    "  Failing: {} on {}",
+                    failure.workflow, failure.branch
+                ));
+                links.push((
+                    format!("{repo} {} on {}", failure.workflow, failure.branch),
+                    &failure.url,
+                ));
+            }
+        }
+        for repo in &fetched.unavailable {
+            lines.push(format!("{repo} unavailable"));
+        }
+        if lines.is_empty() {
+            lines.push("Nothing waiting on you".into());
+        }
+
+        let mut blocks = Vec::new();
+        let codes = settings
+            .get("qr_codes")
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+        if codes {
+            for (label, url) in links.into_iter().take(MAX_CODES) {
+                blocks.push(Block::Text {
+                    text: label,
+                    spans: Vec::new(),
+                    style: Style {
+                        align: Align::Center,
+                        ..Style::default()
+                    },
+                });
+                blocks.push(Block::Qr {
+                    data: url.clone(),
+                    size: 3,
+                    align: Align::Center,
+                });
+            }
+        }
+
+        let summary = match (fetched.reviews.len(), fetched.failures.len()) {
+            (0, 0) => None,
+            (reviews, failures) => Some(
+                [
+                    plural(reviews, "review", "reviews"),
+                    plural(failures, "failing run", "failing runs"),
+                ]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(", "),
+            ),
+        };
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("GitHub")
+                .into(),
+            priority: 0,
+            lines,
+            blocks,
+            summary,
+        }
+    }
+
+    fn cache_ttl(&self) -> TimeDelta {
+        TimeDelta::minutes(15)
+    }
+
+    fn check_credentials<'a>(&'a self, settings: &'a Map<String, Value>) -> Option<Check<'a>> {
+        Some(Box::pin(async move {
+            let token = settings
+                .get("token")
+                .and_then(Value::as_str)
+                .ok_or_else(|| CredentialsRefused("token is not set".into()))?;
+            let url = format!("{}/user", api(settings));
+            let response = client(token)?.get(&url).send().await?;
+            if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+                return Err(CredentialsRefused(
+                    "GitHub refused the token; it may have expired or been revoked".into(),
+                )
+                .into());
+            }
+            response
+                .error_for_status()
+                .with_context(|| format!("fetching {url}"))?;
+            Ok(())
+        }))
+    }
+}
+
+/// "2 reviews", or nothing for none.
+fn plural(n: usize, one: &str, many: &str) -> Option<String> {
+    match n {
+        0 => None,
+        1 => Some(format!("1 {one}")),
+        n => Some(format!("{n} {many}")),
+    }
+}
+
+fn api(settings: &Map<String, Value>) -> &str {
+    settings
+        .get("api_url")
+        .and_then(Value::as_str)
+        .unwrap_or(DEFAULT_API)
+        .trim_end_matches('/')
+}
+
+fn client(token: &str) -> Result<reqwest::Client> {
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        reqwest::header::AUTHORIZATION,
+        format!("Bearer {token}")
+            .parse()
+            .map_err(|_| anyhow!("token is not a valid header value"))?,
+    );
+    headers.insert(
+        reqwest::header::ACCEPT,
+        "application/vnd.github+json".parse()?,
+    );
+    headers.insert("X-GitHub-Api-Version", "2022-11-28".parse()?);
+    Ok(reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .user_agent("dayroll")
+        .default_headers(headers)
+        .build()?)
+}
+
+#[derive(Deserialize)]
+struct SearchResults {
+    items: Vec<Issue>,
+}
+
+#[derive(Deserialize)]
+struct Issue {
+    number: u64,
+    title: String,
+    html_url: String,
+    /// API URL of the repository, ending in `owner/name`.
+    repository_url: String,
+    user: User,
+}
+
+#[derive(Deserialize)]
+struct User {
+    login: String,
+}
+
+/// Open pull requests the token's user is asked to review.
+async fn reviews(client: &reqwest::Client, api: &str) -> Result<Vec<Review>> {
+    let url = format!("{api}/search/issues");
+    let results: SearchResults = client
+        .get(&url)
+        .query(&[
+            ("q", "is:open is:pr review-requested:@me archived:false"),
+            ("sort", "updated"),
+            ("per_page", "50"),
+        ])
+        .send()
+        .await?
+        .error_for_status()
+        .with_context(|| format!("fetching {url}"))?
+        .json()
+        .await?;
+    Ok(results
+        .items
+        .into_iter()
+        .map(|issue| {
+            let mut parts = issue.repository_url.rsplit('/');
+            let name = parts.next().unwrap_or_default();
+            let owner = parts.next().unwrap_or_default();
+            Review {
+                repo: format!("{owner}/{name}"),
+                number: issue.number,
+                title: issue.title,
+                author: issue.user.login,
+                url: issue.html_url,
+            }
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct Runs {
+    workflow_runs: Vec<Run>,
+}
+
+#[derive(Deserialize)]
+struct Run {
+    name: Option<String>,
+    head_branch: Option<String>,
+    conclusion: Option<String>,
+    html_url: String,
+    created_at: DateTime<Utc>,
+}
+
+/// The workflows of `repo` whose latest run on a branch since `since` failed.
+async fn failures(
+    client: &reqwest::Client,
+    api: &str,
+    repo: &str,
+    since: &str,
+) -> Result<Vec<Failure>> {
+    let url = format!("{api}/repos/{repo}/actions/runs");
+    let runs: Runs = client
+        .get(&url)
+        .query(&[
+            ("created", format!(">={since}").as_str()),
+            ("per_page", "100"),
+        ])
+        .send()
+        .await?
+        .error_for_status()
+        .with_context(|| format!("fetching {url}"))?
+        .json()
+        .await?;
+    // Runs come newest first, so the first of each workflow and branch is its
+    // latest; a failure since fixed by a later run isn't printed.
+    let mut latest: BTreeMap<(String, String), Run> = BTreeMap::new();
+    for run in runs.workflow_runs {
+        let key = (
+            run.name.clone().unwrap_or_default(),
+            run.head_branch.clone().unwrap_or_default(),
+        );
+        latest.entry(key).or_insert(run);
+    }
+    Ok(latest
+        .into_iter()
+        .filter(|(_, run)| {
+            matches!(
+                run.conclusion.as_deref(),
+                Some("failure" | "timed_out" | "startup_failure")
+            )
+        })
+        .map(|((workflow, branch), run)| Failure {
+            repo: repo.to_string(),
+            workflow,
+            branch,
+            url: run.html_url,
+            at: run.created_at,
+        })
+        .collect())
+}
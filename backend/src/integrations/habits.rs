@@ -0,0 +1,130 @@
+//! Each habit with its streak and a box to tick for the day, and optionally a QR
+//! code per habit that checks it off when scanned.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use super::{Context, Fetch, Integration};
+use crate::compose::Section;
+use crate::document::{Align, Block, Style};
+use crate::{db, habits};
+
+pub struct Habits;
+
+/// A habit's progress as fetched.
+#[derive(Debug, Serialize, Deserialize)]
+struct Item {
+    id: i32,
+    name: String,
+    done: bool,
+    streak: u32,
+    check_token: String,
+}
+
+impl Integration for Habits {
+    fn slug(&self) -> &'static str {
+        "habits"
+    }
+
+    fn name(&self) -> &'static str {
+        "Habits"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string", "default": "Habits" },
+                "public_url": {
+                    "type": "string",
+                    "format": "uri",
+                    "description": "Address phones reach the server at, such as http://dayroll.local:3000; prints a code to check off each habit"
+                },
+                "user_id": {
+                    "type": "integer",
+                    "description": "Only this user's habits; everyone's when unset"
+                }
+            }
+        })
+    }
+
+    fn fetch<'a>(&'a self, ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        let user_id = settings
+            .get("user_id")
+            .and_then(Value::as_i64)
+            .map(|id| id as i32);
+        let date = ctx.date;
+        Box::pin(async move {
+            let items: Vec<Item> = db::run_blocking_db(move |conn| habits::progress(conn, date))
+                .await?
+                .into_iter()
+                .filter(|p| user_id.is_none_or(|id| p.habit.user_id == Some(id)))
+                .map(|p| Item {
+                    id: p.habit.id,
+                    name: p.habit.name,
+                    done: p.done,
+                    streak: p.streak,
+                    check_token: p.habit.check_token,
+                })
+                .collect();
+            Ok(serde_json::to_value(items)?)
+        })
+    }
+
+    fn render(&self, ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        let items: Vec<Item> = serde_json::from_value(data.clone()).unwrap_or_default();
+        let mut lines: Vec<String> = items
+            .iter()
+            .map(|item| {
+                let mark = if item.done { "[x]" } else { "[ ]" };
+                match item.streak {
+                    0 => format!("{mark} {}", item.name),
+                    1 => format!("{mark} {} (1 day)", item.name),
+                    n => format!("{mark} {} ({n}-day streak)", item.name),
+                }
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push("No habits yet".into());
+        }
+
+        let mut blocks = Vec::new();
+        if let Some(url) = settings.get("public_url").and_then(Value::as_str) {
+            for item in items.iter().filter(|i| !i.done) {
+                blocks.push(Block::Text {
+                    text: item.name.clone(),
+                    spans: Vec::new(),
+                    style: Style {
+                        align: Align::Center,
+                        ..Style::default()
+                    },
+                });
+                blocks.push(Block::Qr {
+                    data: format!(
+                        "{}/public/habits/{}/check?token={}&date={}",
+                        url.trim_end_matches('/'),
+                        item.id,
+                        item.check_token,
+                        ctx.date
+                    ),
+                    size: 3,
+                    align: Align::Center,
+                });
+            }
+        }
+
+        let done = items.iter().filter(|i| i.done).count();
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("Habits")
+                .into(),
+            priority: 0,
+            lines,
+            blocks,
+            summary: Some(format!("{done} of {} habits done", items.len())),
+        }
+    }
+}
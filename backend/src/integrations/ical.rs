@@ -0,0 +1,689 @@
+//! Just enough iCalendar (RFC 5545) to print a day's agenda: events with their
+//! times, time zones and recurrence rules, expanded into the occurrences on one day.
+
+use chrono::{
+    DateTime, Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta,
+    TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// Most recurrence periods looked at for one event, so a rule with no end can't
+/// keep the expansion going forever.
+const MAX_PERIODS: u32 = 50_000;
+
+/// An event's start or end as written in the calendar.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum When {
+    /// A whole day.
+    Date(NaiveDate),
+    /// Wall-clock time wherever the reader is.
+    Floating(NaiveDateTime),
+    Utc(NaiveDateTime),
+    Zoned(NaiveDateTime, Tz),
+}
+
+impl When {
+    fn naive(&self) -> NaiveDateTime {
+        match *self {
+            When::Date(date) => date.and_time(NaiveTime::MIN),
+            When::Floating(at) | When::Utc(at) | When::Zoned(at, _) => at,
+        }
+    }
+
+    /// The same kind of time at a different wall-clock time, for recurrences.
+    fn with_naive(&self, at: NaiveDateTime) -> When {
+        match *self {
+            When::Date(_) => When::Date(at.date()),
+            When::Floating(_) => When::Floating(at),
+            When::Utc(_) => When::Utc(at),
+            When::Zoned(_, tz) => When::Zoned(at, tz),
+        }
+    }
+
+    /// Wall-clock time in `zone`.
+    fn local(&self, zone: Zone) -> NaiveDateTime {
+        let utc = match *self {
+            When::Date(_) | When::Floating(_) => return self.naive(),
+            When::Utc(at) => at.and_utc(),
+            When::Zoned(at, tz) => resolve(&tz, at).with_timezone(&Utc),
+        };
+        zone.local(utc)
+    }
+}
+
+/// `at` in `tz`, taking the earlier reading of an ambiguous time and moving a time
+/// skipped by a clock change forward an hour.
+fn resolve<T: TimeZone>(tz: &T, at: NaiveDateTime) -> DateTime<T> {
+    tz.from_local_datetime(&at)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(at + TimeDelta::hours(1)))
+                .earliest()
+        })
+        .unwrap_or_else(|| tz.from_utc_datetime(&at))
+}
+
+/// Time zone the agenda is printed in.
+#[derive(Debug, Clone, Copy)]
+pub enum Zone {
+    /// The server's.
+    Local,
+    Named(Tz),
+}
+
+impl Zone {
+    /// Wall-clock time of `utc` in the zone.
+    pub fn local(&self, utc: DateTime<Utc>) -> NaiveDateTime {
+        match *self {
+            Zone::Local => utc.with_timezone(&Local).naive_local(),
+            Zone::Named(tz) => utc.with_timezone(&tz).naive_local(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Event {
+    pub uid: String,
+    pub summary: String,
+    pub location: Option<String>,
+    pub start: Option<When>,
+    pub end: Option<When>,
+    /// From `DURATION`, when there is no `DTEND`.
+    pub duration: Option<TimeDelta>,
+    pub rrule: Option<String>,
+    pub rdates: Vec<When>,
+    pub exdates: Vec<When>,
+    /// Set on an event that replaces one occurrence of a recurring event.
+    pub recurrence_id: Option<When>,
+    pub cancelled: bool,
+}
+
+/// One occurrence of an event on the agenda.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Occurrence {
+    /// Start, or `None` for an all-day event.
+    pub start: Option<NaiveDateTime>,
+    pub end: Option<NaiveDateTime>,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+}
+
+/// The events in an iCalendar file. Events without a start are left out.
+pub fn parse(text: &str) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut current: Option<Event> = None;
+    // Nesting inside the event, e.g. VALARM, whose properties aren't the event's.
+    let mut depth = 0;
+    for line in unfold(text) {
+        let Some((name, params, value)) = property(&line) else {
+            continue;
+        };
+        match (name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VEVENT") => {
+                current = Some(Event::default());
+                depth = 0;
+            }
+            ("BEGIN", Some(_)) => depth += 1,
+            ("END", Some(_)) if depth > 0 => depth -= 1,
+            ("END", Some(_)) => {
+                if let Some(event) = current.take().filter(|e| e.start.is_some()) {
+                    events.push(event);
+                }
+            }
+            (_, Some(event)) if depth == 0 => match name.as_str() {
+                "UID" => event.uid = value,
+                "SUMMARY" => event.summary = unescape(&value),
+                "LOCATION" => event.location = Some(unescape(&value)).filter(|l| !l.is_empty()),
+                "DTSTART" => event.start = when(&params, &value),
+                "DTEND" => event.end = when(&params, &value),
+                "DURATION" => event.duration = duration(&value),
+                "RRULE" => event.rrule = Some(value),
+                "RDATE" => event.rdates.extend(whens(&params, &value)),
+                "EXDATE" => event.exdates.extend(whens(&params, &value)),
+                "RECURRENCE-ID" => event.recurrence_id = when(&params, &value),
+                "STATUS" => event.cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Occurrences of `events` on `date` in `zone`, all-day events first, then by time.
+pub fn agenda(events: &[Event], date: NaiveDate, zone: Zone) -> Vec<Occurrence> {
+    let day_start = date.and_time(NaiveTime::MIN);
+    let day_end = day_start + TimeDelta::days(1);
+    // Occurrences moved or cancelled by a separate event with the same UID.
+    let replaced: Vec<(&str, NaiveDateTime)> = events
+        .iter()
+        .filter_map(|e| Some((e.uid.as_str(), e.recurrence_id?.naive())))
+        .collect();
+
+    let mut agenda = Vec::new();
+    for event in events.iter().filter(|e| !e.cancelled) {
+        let Some(start) = event.start else {
+            continue;
+        };
+        let length = event.length();
+        let starts = if event.recurrence_id.is_some() {
+            vec![start]
+        } else {
+            occurrences(event, start, date)
+                .into_iter()
+                .filter(|s| !replaced.contains(&(event.uid.as_str(), s.naive())))
+                .collect()
+        };
+        for occurrence in starts {
+            let occurrence_end = occurrence.with_naive(occurrence.naive() + length);
+            let all_day = matches!(occurrence, When::Date(_));
+            let (from, to) = (occurrence.local(zone), occurrence_end.local(zone));
+            let overlaps = if length.is_zero() {
+                from >= day_start && from < day_end
+            } else {
+                from < day_end && to > day_start
+            };
+            if overlaps {
+                agenda.push(Occurrence {
+                    start: (!all_day).then_some(from),
+                    end: (!all_day && !length.is_zero()).then_some(to),
+                    title: event.summary.clone(),
+                    location: event.location.clone(),
+                });
+            }
+        }
+    }
+    agenda.sort_by_key(|o| o.start);
+    agenda
+}
+
+impl Event {
+    /// How long each occurrence lasts.
+    fn length(&self) -> TimeDelta {
+        match (self.start, self.end, self.duration) {
+            (Some(start), Some(end), _) => end.naive() - start.naive(),
+            (_, _, Some(duration)) => duration,
+            (Some(When::Date(_)), None, None) => TimeDelta::days(1),
+            _ => TimeDelta::zero(),
+        }
+    }
+}
+
+/// Starts of `event` from its first up to a couple of days past `until`, in the
+/// event's own time zone, less its exceptions.
+fn occurrences(event: &Event, start: When, until: NaiveDate) -> Vec<When> {
+    let horizon = until
+        .checked_add_days(Days::new(2))
+        .unwrap_or(until)
+        .and_time(NaiveTime::MIN);
+    let mut starts = vec![start.naive()];
+    if let Some(rule) = event.rrule.as_deref().and_then(Rule::parse) {
+        starts = rule.expand(start.naive(), horizon);
+    }
+    starts.extend(event.rdates.iter().map(When::naive));
+    starts.retain(|s| {
+        *s < horizon
+            && !event.exdates.iter().any(|x| match x {
+                When::Date(date) => *date == s.date(),
+                _ => x.naive() == *s,
+            })
+    });
+    starts.sort();
+    starts.dedup();
+    starts.into_iter().map(|s| start.with_naive(s)).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A parsed `RRULE`. Rules more frequent than daily aren't supported.
+#[derive(Debug, Clone)]
+struct Rule {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<u32>,
+    until: Option<NaiveDateTime>,
+    /// Weekdays, each with an optional position in the month such as `-1` for the last.
+    by_day: Vec<(Option<i32>, Weekday)>,
+    by_month_day: Vec<i32>,
+    by_month: Vec<u32>,
+}
+
+impl Rule {
+    fn parse(text: &str) -> Option<Rule> {
+        let mut rule = Rule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+        };
+        let mut frequency = None;
+        for part in text.split(';') {
+            let (key, value) = part.split_once('=')?;
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return None,
+                    })
+                }
+                "INTERVAL" => rule.interval = value.parse().ok().filter(|i| *i > 0)?,
+                "COUNT" => rule.count = value.parse().ok(),
+                "UNTIL" => rule.until = when(&[], value).map(|u| u.naive()),
+                "BYDAY" => rule.by_day = value.split(',').filter_map(weekday).collect(),
+                "BYMONTHDAY" => rule.by_month_day = numbers(value),
+                "BYMONTH" => rule.by_month = numbers(value),
+                _ => {}
+            }
+        }
+        rule.frequency = frequency?;
+        Some(rule)
+    }
+
+    /// Starts from `start` until `horizon` or the rule's own end.
+    fn expand(&self, start: NaiveDateTime, horizon: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let time = start.time();
+        let mut starts = Vec::new();
+        let mut seen = 0;
+        for period in 0..MAX_PERIODS {
+            let Some(anchor) = self.period(start.date(), period) else {
+                break;
+            };
+            if anchor.and_time(NaiveTime::MIN) >= horizon {
+                break;
+            }
+            let mut days = self.days(start.date(), anchor);
+            days.sort();
+            for day in days {
+                let at = day.and_time(time);
+                if at < start {
+                    continue;
+                }
+                if self.until.is_some_and(|until| at > until)
+                    || self.count.is_some_and(|count| seen >= count)
+                {
+                    return starts;
+                }
+                seen += 1;
+                if at >= horizon {
+                    return starts;
+                }
+                starts.push(at);
+            }
+        }
+        starts
+    }
+
+    /// First day of the `n`th period after the one `start` is in.
+    fn period(&self, start: NaiveDate, n: u32) -> Option<NaiveDate> {
+        let steps = n.checked_mul(self.interval)?;
+        match self.frequency {
+            Frequency::Daily => start.checked_add_days(Days::new(steps.into())),
+            Frequency::Weekly => start
+                .checked_sub_days(Days::new(start.weekday().num_days_from_monday().into()))?
+                .checked_add_days(Days::new(u64::from(steps) * 7)),
+            Frequency::Monthly => start.with_day(1)?.checked_add_months(Months::new(steps)),
+            Frequency::Yearly => start
+                .with_day(1)?
+                .with_month(1)?
+                .checked_add_months(Months::new(steps.checked_mul(12)?)),
+        }
+    }
+
+    /// Days of the period beginning `anchor` the rule picks.
+    fn days(&self, start: NaiveDate, anchor: NaiveDate) -> Vec<NaiveDate> {
+        match self.frequency {
+            Frequency::Daily => {
+                let month = self.by_month.is_empty() || self.by_month.contains(&anchor.month());
+                let weekday = self.by_day.is_empty()
+                    || self.by_day.iter().any(|(_, w)| *w == anchor.weekday());
+                let day = self.by_month_day.is_empty()
+                    || month_days(anchor.year(), anchor.month(), &self.by_month_day)
+                        .contains(&anchor);
+                if month && weekday && day {
+                    vec![anchor]
+                } else {
+                    Vec::new()
+                }
+            }
+            Frequency::Weekly => {
+                let weekdays: Vec<Weekday> = if self.by_day.is_empty() {
+                    vec![start.weekday()]
+                } else {
+                    self.by_day.iter().map(|(_, w)| *w).collect()
+                };
+                weekdays
+                    .into_iter()
+                    .filter_map(|w| {
+                        anchor.checked_add_days(Days::new(w.num_days_from_monday().into()))
+                    })
+                    .filter(|d| self.by_month.is_empty() || self.by_month.contains(&d.month()))
+                    .collect()
+            }
+            Frequency::Monthly => {
+                if !self.by_month.is_empty() && !self.by_month.contains(&anchor.month()) {
+                    return Vec::new();
+                }
+                self.month(start, anchor.year(), anchor.month())
+            }
+            Frequency::Yearly => {
+                let months = if self.by_month.is_empty() {
+                    vec![start.month()]
+                } else {
+                    self.by_month.clone()
+                };
+                months
+                    .into_iter()
+                    .flat_map(|m| self.month(start, anchor.year(), m))
+                    .collect()
+            }
+        }
+    }
+
+    /// Days the rule picks in one month.
+    fn month(&self, start: NaiveDate, year: i32, month: u32) -> Vec<NaiveDate> {
+        if !self.by_month_day.is_empty() {
+            let days = month_days(year, month, &self.by_month_day);
+            if self.by_day.is_empty() {
+                return days;
+            }
+            return days
+                .into_iter()
+                .filter(|d| self.by_day.iter().any(|(_, w)| *w == d.weekday()))
+                .collect();
+        }
+        if !self.by_day.is_empty() {
+            return self
+                .by_day
+                .iter()
+                .flat_map(|&(nth, weekday)| weekdays_in_month(year, month, weekday, nth))
+                .collect();
+        }
+        // A plain monthly rule skips months without the start's day, such as the 31st.
+        NaiveDate::from_ymd_opt(year, month, start.day())
+            .into_iter()
+            .collect()
+    }
+}
+
+/// The days of a month numbered in `days`, negative numbers counting from its end.
+fn month_days(year: i32, month: u32, days: &[i32]) -> Vec<NaiveDate> {
+    let length = month_length(year, month) as i32;
+    days.iter()
+        .map(|&d| if d < 0 { length + d + 1 } else { d })
+        .filter(|d| (1..=length).contains(d))
+        .filter_map(|d| NaiveDate::from_ymd_opt(year, month, d as u32))
+        .collect()
+}
+
+/// Every `weekday` in a month, or only the `nth` (from the end when negative).
+fn weekdays_in_month(year: i32, month: u32, weekday: Weekday, nth: Option<i32>) -> Vec<NaiveDate> {
+    let all: Vec<NaiveDate> = (1..=month_length(year, month))
+        .filter_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+        .filter(|d| d.weekday() == weekday)
+        .collect();
+    match nth {
+        None => all,
+        Some(n) if n > 0 => all.get(n as usize - 1).copied().into_iter().collect(),
+        Some(n) => all
+            .len()
+            .checked_sub(n.unsigned_abs() as usize)
+            .and_then(|i| all.get(i).copied())
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn month_length(year: i32, month: u32) -> u32 {
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|first| first.checked_add_months(Months::new(1)))
+        .and_then(|next| next.pred_opt())
+        .map_or(31, |last| last.day())
+}
+
+fn numbers<T: std::str::FromStr>(list: &str) -> Vec<T> {
+    list.split(',')
+        .filter_map(|v| v.trim().parse().ok())
+        .collect()
+}
+
+/// `MO`, `2TU`, `-1FR` and so on.
+fn weekday(text: &str) -> Option<(Option<i32>, Weekday)> {
+    let text = text.trim();
+    let (nth, day) = text.split_at(text.len().checked_sub(2)?);
+    let weekday = match day.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    };
+    let nth = if nth.is_empty() || nth == "+" {
+        None
+    } else {
+        Some(nth.trim_start_matches('+').parse().ok()?)
+    };
+    Some((nth, weekday))
+}
+
+/// Content lines with folded continuations joined back on.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Parameters of a property, such as `TZID`, with upper-cased names.
+type Params = Vec<(String, String)>;
+
+/// `NAME;PARAM=value;...:value`, with the name upper-cased.
+fn property(line: &str) -> Option<(String, Params, String)> {
+    // The value starts at the first colon outside a quoted parameter value.
+    let mut quoted = false;
+    let split = line.char_indices().find_map(|(i, c)| {
+        match c {
+            '"' => quoted = !quoted,
+            ':' if !quoted => return Some(i),
+            _ => {}
+        }
+        None
+    })?;
+    let (head, value) = (&line[..split], &line[split + 1..]);
+    let mut parts = head.split(';');
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| {
+            (
+                k.trim().to_ascii_uppercase(),
+                v.trim_matches('"').to_string(),
+            )
+        })
+        .collect();
+    Some((name, params, value.trim_end().to_string()))
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push(' '),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+/// A date or date-time value and its `TZID`. Unknown zones, such as Windows names,
+/// are read as floating times.
+fn when(params: &[(String, String)], value: &str) -> Option<When> {
+    let value = value.trim();
+    let tzid = params.iter().find(|(k, _)| k == "TZID").map(|(_, v)| v);
+    if let Some(utc) = value.strip_suffix(['Z', 'z']) {
+        return NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+            .ok()
+            .map(When::Utc);
+    }
+    if let Ok(at) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        return Some(match tzid.and_then(|t| t.parse::<Tz>().ok()) {
+            Some(tz) => When::Zoned(at, tz),
+            None => When::Floating(at),
+        });
+    }
+    NaiveDate::parse_from_str(value, "%Y%m%d")
+        .ok()
+        .map(When::Date)
+}
+
+/// A comma-separated list of dates or date-times, as in `EXDATE`.
+fn whens(params: &[(String, String)], value: &str) -> Vec<When> {
+    value.split(',').filter_map(|v| when(params, v)).collect()
+}
+
+/// `P1D`, `PT1H30M`, `P2W` and the like.
+fn duration(text: &str) -> Option<TimeDelta> {
+    let (negative, text) = match text.trim().strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.trim().trim_start_matches('+')),
+    };
+    let text = text.strip_prefix('P')?;
+    let mut total = TimeDelta::zero();
+    let mut number = String::new();
+    for c in text.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {}
+            unit => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                total += match unit {
+                    'W' => TimeDelta::weeks(n),
+                    'D' => TimeDelta::days(n),
+                    'H' => TimeDelta::hours(n),
+                    'M' => TimeDelta::minutes(n),
+                    'S' => TimeDelta::seconds(n),
+                    _ => return None,
+                };
+            }
+        }
+    }
+    Some(if negative { -total } else { total })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LONDON: Zone = Zone::Named(chrono_tz::Europe::London);
+
+    fn calendar(event: &str) -> Vec<Event> {
+        parse(&format!(
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:1\r\nSUMMARY:Standup\r\n\
+             {event}END:VEVENT\r\nEND:VCALENDAR\r\n"
+        ))
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 1, day).unwrap()
+    }
+
+    fn at(day: u32, hour: u32) -> Option<NaiveDateTime> {
+        date(day).and_hms_opt(hour, 0, 0)
+    }
+
+    /// Days of January 2026 the event is on.
+    fn days(events: &[Event], zone: Zone) -> Vec<u32> {
+        (1..=31)
+            .filter(|&d| !agenda(events, date(d), zone).is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn weekly_rule_stops_at_until() {
+        // Mondays and Wednesdays from Monday 5 January, up to the morning of the 19th.
+        let events = calendar(
+            "DTSTART:20260105T090000Z\r\nDTEND:20260105T093000Z\r\n\
+             RRULE:FREQ=WEEKLY;BYDAY=MO,WE;UNTIL=20260119T080000Z\r\n",
+        );
+        assert_eq!(days(&events, LONDON), vec![5, 7, 12, 14]);
+    }
+
+    #[test]
+    fn weekly_rule_stops_after_count() {
+        let events = calendar(
+            "DTSTART:20260105T090000Z\r\nDURATION:PT30M\r\n\
+             RRULE:FREQ=WEEKLY;INTERVAL=2;COUNT=3\r\n",
+        );
+        assert_eq!(days(&events, LONDON), vec![5, 19]);
+        let events =
+            calendar("DTSTART:20260105T090000Z\r\nDURATION:PT30M\r\nRRULE:FREQ=WEEKLY;COUNT=3\r\n");
+        assert_eq!(days(&events, LONDON), vec![5, 12, 19]);
+    }
+
+    #[test]
+    fn exdates_remove_occurrences() {
+        let events = calendar(
+            "DTSTART;TZID=Europe/London:20260105T090000\r\nDURATION:PT30M\r\n\
+             RRULE:FREQ=DAILY;COUNT=5\r\n\
+             EXDATE;TZID=Europe/London:20260106T090000,20260108T090000\r\n",
+        );
+        assert_eq!(days(&events, LONDON), vec![5, 7, 9]);
+
+        let events = calendar(
+            "DTSTART;VALUE=DATE:20260105\r\nRRULE:FREQ=WEEKLY\r\nEXDATE;VALUE=DATE:20260112\r\n",
+        );
+        assert_eq!(&days(&events, LONDON)[..3], &[5, 19, 26]);
+    }
+
+    #[test]
+    fn all_day_events_have_no_times() {
+        let events = calendar("DTSTART;VALUE=DATE:20260110\r\nDTEND;VALUE=DATE:20260111\r\n");
+        assert_eq!(days(&events, LONDON), vec![10]);
+        let agenda = agenda(&events, date(10), LONDON);
+        assert_eq!((agenda[0].start, agenda[0].end), (None, None));
+    }
+
+    #[test]
+    fn zoned_event_crossing_midnight() {
+        // 10pm to 1am in New York, five hours behind London in January.
+        let events = calendar(
+            "DTSTART;TZID=America/New_York:20260110T220000\r\n\
+             DTEND;TZID=America/New_York:20260111T010000\r\n",
+        );
+        let new_york = Zone::Named(chrono_tz::America::New_York);
+        assert_eq!(days(&events, new_york), vec![10, 11]);
+        let evening = agenda(&events, date(10), new_york);
+        assert_eq!((evening[0].start, evening[0].end), (at(10, 22), at(11, 1)));
+
+        assert_eq!(days(&events, LONDON), vec![11]);
+        let morning = agenda(&events, date(11), LONDON);
+        assert_eq!((morning[0].start, morning[0].end), (at(11, 3), at(11, 6)));
+    }
+}
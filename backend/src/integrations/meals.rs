@@ -0,0 +1,120 @@
+//! What's planned to eat on the day, and a reminder of what to take out of the
+//! freezer for the day after.
+
+use chrono::Days;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use super::{Context, Fetch, Integration};
+use crate::compose::Section;
+use crate::{db, meals};
+
+pub struct Meals;
+
+/// The plan as fetched.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Plan {
+    /// The day's meals in the chosen slots, as slot and title.
+    today: Vec<(String, String)>,
+    /// What to defrost for the next day's meals, with the meal it is for.
+    defrost: Vec<(String, String)>,
+}
+
+impl Integration for Meals {
+    fn slug(&self) -> &'static str {
+        "meals"
+    }
+
+    fn name(&self) -> &'static str {
+        "Meal plan"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string", "default": "Tonight" },
+                "slots": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "default": ["dinner"],
+                    "description": "Meals of the day to print, such as breakfast, lunch and dinner"
+                }
+            }
+        })
+    }
+
+    fn fetch<'a>(&'a self, ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        let slots: Vec<String> = match settings.get("slots").and_then(Value::as_array) {
+            Some(slots) => slots
+                .iter()
+                .filter_map(Value::as_str)
+                .map(meals::slot_key)
+                .collect(),
+            None => vec!["dinner".into()],
+        };
+        let today = ctx.date;
+        Box::pin(async move {
+            let tomorrow = today.checked_add_days(Days::new(1)).unwrap_or(today);
+            let planned =
+                db::run_blocking_db(move |conn| meals::between(conn, today, tomorrow)).await?;
+            let mut plan = Plan::default();
+            for slot in &slots {
+                if let Some(meal) = planned.iter().find(|m| m.day == today && &m.slot == slot) {
+                    plan.today.push((meal.slot.clone(), meal.title.clone()));
+                }
+            }
+            for meal in planned.iter().filter(|m| m.day == tomorrow) {
+                if let Some(defrost) = meal.defrost.as_ref().filter(|d| !d.trim().is_empty()) {
+                    plan.defrost.push((defrost.clone(), meal.title.clone()));
+                }
+            }
+            Ok(serde_json::to_value(plan)?)
+        })
+    }
+
+    fn render(&self, _ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        let plan: Plan = serde_json::from_value(data.clone()).unwrap_or_default();
+        let mut lines: Vec<String> = match plan.today.as_slice() {
+            [(_, title)] => vec![title.clone()],
+            meals => meals
+                .iter()
+                .map(|(slot, title)| format!("{}: {title}", capitalized(slot)))
+                .collect(),
+        };
+        if lines.is_empty() {
+            lines.push("Nothing planned".into());
+        }
+        for (defrost, meal) in &plan.defrost {
+            lines.push(format!("Defrost {defrost} for tomorrow's {meal}"));
+        }
+        // The defrost reminder is what can't be caught up on later, so it stays.
+        let mut summary = match plan.today.first() {
+            Some((_, title)) => title.clone(),
+            None => "Nothing planned".into(),
+        };
+        if let Some((defrost, _)) = plan.defrost.first() {
+            summary.push_str(&format!("; defrost {defrost}"));
+        }
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("Tonight")
+                .into(),
+            priority: 0,
+            lines,
+            blocks: Vec::new(),
+            summary: Some(summary),
+        }
+    }
+}
+
+fn capitalized(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
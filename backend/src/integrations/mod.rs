@@ -0,0 +1,918 @@
+//! Sources of digest sections, such as calendars and to-do lists. Each kind is an
+//! [`Integration`] built into the server; users add instances of them, each with its
+//! own settings, and the composer asks every enabled instance for its section.
+
+use anyhow::{Context as _, Result};
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeDelta, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use tracing::{instrument, warn};
+use utoipa::ToSchema;
+
+use crate::compose::Section;
+use crate::db;
+use crate::document::Block;
+use crate::error::FieldError;
+use crate::schema::integration_instances;
+use crate::secrets;
+use crate::templates::{expand, json_text};
+use crate::webhooks;
+
+mod air_quality;
+mod birthdays;
+mod caldav;
+mod countdowns;
+pub mod credentials;
+mod digest;
+mod github;
+mod habits;
+pub mod ical;
+mod meals;
+mod note;
+mod on_this_day;
+mod puzzle;
+mod quotes;
+mod remote_calendar;
+mod shopping;
+mod sports;
+mod stocks;
+mod strava;
+mod sun_moon;
+mod tasks;
+mod timetable;
+mod validate;
+mod word_of_the_day;
+
+/// Data an integration fetched, still to be rendered.
+pub type Fetch<'a> = Pin<Box<dyn Future<Output = Result<Value>> + Send + 'a>>;
+
+/// Whether an instance's credentials still work; see [`Integration::check_credentials`].
+pub type Check<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// Settings an integration keeps after signing in; see [`Integration::connect`].
+pub type Connect<'a> = Pin<Box<dyn Future<Output = Result<Map<String, Value>>> + Send + 'a>>;
+
+/// The service refused an instance's credentials, or they expired, rather than
+/// failing for some other reason such as being unreachable.
+#[derive(Debug)]
+pub struct CredentialsRefused(pub String);
+
+impl std::fmt::Display for CredentialsRefused {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for CredentialsRefused {}
+
+/// What an integration is fetching and rendering for.
+#[derive(Debug, Clone)]
+pub struct Context {
+    /// Day the printout is for.
+    pub date: NaiveDate,
+}
+
+/// A kind of section source. Fetching and rendering are separate so fetched data
+/// can be kept and rendered again.
+pub trait Integration: Send + Sync {
+    /// Stable identifier instances refer to, such as `remote_calendar`.
+    fn slug(&self) -> &'static str;
+
+    /// Human-readable name.
+    fn name(&self) -> &'static str;
+
+    /// JSON Schema of an instance's settings.
+    fn config_schema(&self) -> Value;
+
+    /// Fetch what the section shows, as JSON.
+    fn fetch<'a>(&'a self, ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a>;
+
+    /// Lay fetched data out as a section.
+    fn render(&self, ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section;
+
+    /// How long fetched data is printed again before it is fetched afresh. Data kept
+    /// in the server's own database is always fetched afresh.
+    fn cache_ttl(&self) -> TimeDelta {
+        TimeDelta::zero()
+    }
+
+    /// Ask the service whether the instance's stored credentials, such as a token or
+    /// password, still work, without fetching the section. Credentials that are
+    /// refused or have expired fail with [`CredentialsRefused`]; other errors say
+    /// nothing about them. `None` for integrations that keep none.
+    fn check_credentials<'a>(&'a self, _settings: &'a Map<String, Value>) -> Option<Check<'a>> {
+        None
+    }
+
+    /// Where to send the user to let an integration that signs in with OAuth into
+    /// their account. The service sends them back to `redirect` with `state` and a
+    /// code for [`Integration::connect`]. `None` for integrations that don't.
+    fn authorize_url(
+        &self,
+        _settings: &Map<String, Value>,
+        _redirect: &str,
+        _state: &str,
+    ) -> Option<String> {
+        None
+    }
+
+    /// Trade the code the service sent back for settings to merge into the
+    /// instance's, such as the account signed in to.
+    fn connect<'a>(
+        &'a self,
+        _settings: &'a Map<String, Value>,
+        _code: &'a str,
+        _redirect: &'a str,
+    ) -> Connect<'a> {
+        Box::pin(async move { anyhow::bail!("{} doesn't sign in with OAuth", self.slug()) })
+    }
+}
+
+/// Every integration built into the server.
+static BUILT_IN: &[&dyn Integration] = &[
+    &note::Note,
+    &remote_calendar::RemoteCalendar,
+    &caldav::CalDav,
+    &tasks::Tasks,
+    &word_of_the_day::WordOfTheDay,
+    &quotes::Quotes,
+    &puzzle::Puzzle,
+    &habits::Habits,
+    &birthdays::Birthdays,
+    &countdowns::Countdowns,
+    &sun_moon::SunMoon,
+    &meals::Meals,
+    &shopping::Shopping,
+    &stocks::Stocks,
+    &sports::Sports,
+    &github::GitHub,
+    &strava::Strava,
+    &on_this_day::OnThisDay,
+    &air_quality::AirQuality,
+    &timetable::Timetable,
+];
+
+pub fn built_in() -> &'static [&'static dyn Integration] {
+    BUILT_IN
+}
+
+/// The built-in integration called `slug`.
+pub fn find(slug: &str) -> Option<&'static dyn Integration> {
+    BUILT_IN.iter().copied().find(|i| i.slug() == slug)
+}
+
+/// Which printouts an instance's section goes into.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PrintOn {
+    /// Only the daily digest.
+    Digest,
+    /// Only when printed by itself.
+    OnDemand,
+    #[default]
+    Both,
+}
+
+impl PrintOn {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PrintOn::Digest => "digest",
+            PrintOn::OnDemand => "on_demand",
+            PrintOn::Both => "both",
+        }
+    }
+
+    pub fn in_digest(&self) -> bool {
+        matches!(self, PrintOn::Digest | PrintOn::Both)
+    }
+
+    pub fn on_demand(&self) -> bool {
+        matches!(self, PrintOn::OnDemand | PrintOn::Both)
+    }
+}
+
+impl FromStr for PrintOn {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "digest" => Ok(PrintOn::Digest),
+            "on_demand" => Ok(PrintOn::OnDemand),
+            "both" => Ok(PrintOn::Both),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Data an instance's section is rendered from.
+#[derive(Debug, Clone)]
+pub struct Fetched {
+    data: Value,
+    /// When the data was fetched, if fetching it afresh failed.
+    stale_since: Option<NaiveDateTime>,
+}
+
+impl Fetched {
+    pub fn data(&self) -> &Value {
+        &self.data
+    }
+}
+
+/// A configured instance of an integration.
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize, ToSchema)]
+#[diesel(table_name = integration_instances)]
+#[diesel(primary_key(instance_id))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Instance {
+    pub instance_id: String,
+    /// Slug of the [`Integration`] this is an instance of.
+    pub slug: String,
+    pub enabled: bool,
+    /// JSON object, shaped by the integration's [`Integration::config_schema`].
+    #[serde(serialize_with = "json_text")]
+    #[schema(value_type = Object)]
+    pub settings: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// Which printouts the section goes into; see [`PrintOn`].
+    pub print_on: String,
+    /// Minutes between the scheduler's fetches of the section's data. Unset, it is
+    /// fetched whenever the section is printed.
+    pub refresh_minutes: Option<i32>,
+    /// Data from the last scheduled fetch, as JSON.
+    #[serde(skip)]
+    pub data: Option<String>,
+    pub fetched_at: Option<NaiveDateTime>,
+    /// Where the section goes in the digest, lowest first.
+    pub position: i32,
+    /// Tera template the section's lines are rendered with instead of the
+    /// integration's own; see [`Instance::render`].
+    pub template: Option<String>,
+    /// Whether the service refused the instance's credentials, or they had expired,
+    /// when they were last [checked](credentials).
+    pub credentials_invalid: bool,
+    /// Why, while they're invalid.
+    pub credentials_error: Option<String>,
+    /// Set while the instance sits in the trash; it can be restored, credentials and
+    /// template intact, until the retention window passes.
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+impl Instance {
+    /// The settings as stored, with their secrets still encrypted.
+    pub fn settings(&self) -> Result<Map<String, Value>> {
+        Ok(serde_json::from_str(&self.settings)?)
+    }
+
+    /// The instance as the API shows it, with its secrets blank so they can't be
+    /// read back, encrypted or not. Sent back blank, they are kept.
+    pub fn redacted(mut self) -> Result<Self> {
+        let Some(integration) = find(&self.slug) else {
+            return Ok(self);
+        };
+        let mut settings = self.settings()?;
+        for_each_secret(&integration.config_schema(), &mut settings, &mut |s| {
+            s.clear();
+            Ok(())
+        })?;
+        self.settings = Value::Object(settings).to_string();
+        Ok(self)
+    }
+
+    /// The settings with their secrets decrypted, to fetch with.
+    pub fn decrypted_settings(&self) -> Result<Map<String, Value>> {
+        let mut settings = self.settings()?;
+        for_each_secret(
+            &self.integration()?.config_schema(),
+            &mut settings,
+            &mut |s| {
+                *s = secrets::open(s)?;
+                Ok(())
+            },
+        )?;
+        Ok(settings)
+    }
+
+    pub fn print_on(&self) -> PrintOn {
+        self.print_on.parse().unwrap_or_default()
+    }
+
+    /// Whether the scheduler should fetch the instance's data again at `now`, given
+    /// when it last tried to, successfully or not.
+    pub fn refresh_due(&self, now: NaiveDateTime, attempted: Option<NaiveDateTime>) -> bool {
+        let Some(minutes) = self.refresh_minutes.filter(|_| self.enabled) else {
+            return false;
+        };
+        self.fetched_at
+            .max(attempted)
+            .is_none_or(|at| now - at >= TimeDelta::minutes(minutes.into()))
+    }
+
+    /// The last data fetched, and when.
+    fn last_fetch(&self) -> Option<(Value, NaiveDateTime)> {
+        let data = serde_json::from_str(self.data.as_deref()?).ok()?;
+        Some((data, self.fetched_at?))
+    }
+
+    /// How long fetched data is used before fetching again: the integration's
+    /// [`Integration::cache_ttl`], or two refreshes for instances the scheduler
+    /// refreshes, whichever is longer.
+    fn ttl(&self, integration: &dyn Integration) -> TimeDelta {
+        let refreshes = self
+            .refresh_minutes
+            .map_or(TimeDelta::zero(), |m| TimeDelta::minutes(2 * i64::from(m)));
+        integration.cache_ttl().max(refreshes)
+    }
+
+    /// Fetch and render this instance's section.
+    pub async fn section(&self, ctx: &Context) -> Result<Section> {
+        let fetched = self.fetch(ctx).await?;
+        self.render(ctx, &fetched)
+    }
+
+    /// The data to render the section from. Data fetched for `ctx`'s day within the
+    /// instance's TTL is used as it is; otherwise the data is fetched again, and if
+    /// that fails the last data fetched is used instead.
+    #[instrument(name = "integration", skip_all, fields(instance = %self.instance_id, slug = %self.slug))]
+    pub async fn fetch(&self, ctx: &Context) -> Result<Fetched> {
+        let integration = self.integration()?;
+        let settings = self.decrypted_settings()?;
+        let last = self.last_fetch();
+        if let Some((data, at)) = &last {
+            let age = Utc::now().naive_utc() - *at;
+            if local_date(*at) == ctx.date && age < self.ttl(integration) {
+                return Ok(Fetched {
+                    data: data.clone(),
+                    stale_since: None,
+                });
+            }
+        }
+        match self.fetch_from(integration, ctx, &settings).await {
+            Ok(data) => {
+                if let Err(err) = self.store(&data).await {
+                    warn!("could not cache {}'s data: {err:#}", self.instance_id);
+                }
+                Ok(Fetched {
+                    data,
+                    stale_since: None,
+                })
+            }
+            Err(err) => {
+                let Some((data, at)) = last else {
+                    return Err(err);
+                };
+                warn!(
+                    "{} ({}) failed, printing its data from {at}: {err:#}",
+                    self.instance_id, self.slug
+                );
+                Ok(Fetched {
+                    data,
+                    stale_since: Some(at),
+                })
+            }
+        }
+    }
+
+    /// Lay fetched data out as the section; stale data is marked with how old it is.
+    ///
+    /// An instance with a template of its own has its text rendered by it, in place
+    /// of the built-in lines and icon lines; images and codes are kept. The template
+    /// has the fetched `data`, the `settings` and the built-in section's `title`,
+    /// `lines` (icon lines first) and `summary` as variables. Should it fail, the
+    /// built-in layout is printed instead.
+    pub fn render(&self, ctx: &Context, fetched: &Fetched) -> Result<Section> {
+        let settings = self.settings()?;
+        let mut section = self.integration()?.render(ctx, &settings, &fetched.data);
+        if let Some(template) = &self.template {
+            let mut vars = Map::new();
+            vars.insert("data".into(), fetched.data.clone());
+            vars.insert("settings".into(), settings.into());
+            vars.insert("title".into(), section.title.clone().into());
+            let glyphs = section.blocks.iter().filter_map(|block| match block {
+                Block::Glyph { text, .. } => Some(text.clone()),
+                _ => None,
+            });
+            let lines: Vec<String> = glyphs.chain(section.lines.iter().cloned()).collect();
+            vars.insert("lines".into(), lines.into());
+            vars.insert("summary".into(), section.summary.clone().into());
+            match expand::expand_value(Value::String(template.clone()), &vars) {
+                Ok(Value::String(text)) => {
+                    section.lines = text.trim_end().lines().map(str::to_string).collect();
+                    section
+                        .blocks
+                        .retain(|block| !matches!(block, Block::Glyph { .. }));
+                }
+                Ok(_) => {}
+                Err(err) => warn!(
+                    "{}'s template failed, printing the built-in layout: {err}",
+                    self.instance_id
+                ),
+            }
+        }
+        if let Some(at) = fetched.stale_since {
+            section.lines.insert(0, as_of(at, ctx.date));
+        }
+        Ok(section)
+    }
+
+    fn integration(&self) -> Result<&'static dyn Integration> {
+        find(&self.slug).with_context(|| format!("integration {} no longer exists", self.slug))
+    }
+
+    /// Fetch the instance's data afresh, whatever is kept, and keep it for
+    /// [`Instance::section`].
+    pub async fn fetch_now(&self, ctx: &Context) -> Result<Fetched> {
+        let data = self
+            .fetch_from(self.integration()?, ctx, &self.decrypted_settings()?)
+            .await?;
+        self.store(&data).await?;
+        Ok(Fetched {
+            data,
+            stale_since: None,
+        })
+    }
+
+    /// Ask `integration` for the instance's data, sending `integration.failed` to the
+    /// webhooks subscribed to it should that fail.
+    async fn fetch_from(
+        &self,
+        integration: &dyn Integration,
+        ctx: &Context,
+        settings: &Map<String, Value>,
+    ) -> Result<Value> {
+        let fetched = integration.fetch(ctx, settings).await;
+        if let Err(err) = &fetched {
+            webhooks::deliver::notify(
+                "integration.failed",
+                None,
+                serde_json::json!({
+                    "instance_id": self.instance_id,
+                    "slug": self.slug,
+                    "error": format!("{err:#}"),
+                }),
+            );
+        }
+        fetched
+    }
+
+    async fn store(&self, data: &Value) -> Result<()> {
+        let data = data.to_string();
+        let instance_id = self.instance_id.clone();
+        db::run_blocking_db(move |conn| {
+            diesel::update(integration_instances::table.find(&instance_id))
+                .set((
+                    integration_instances::data.eq(data),
+                    integration_instances::fetched_at.eq(Utc::now().naive_utc()),
+                ))
+                .execute(conn)?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+/// Local date of a UTC timestamp.
+fn local_date(at: NaiveDateTime) -> NaiveDate {
+    at.and_utc().with_timezone(&Local).date_naive()
+}
+
+/// When stale data was fetched, relative to the day it is printed on: "As of 22:40
+/// yesterday".
+fn as_of(at: NaiveDateTime, today: NaiveDate) -> String {
+    let local = at.and_utc().with_timezone(&Local);
+    let time = local.format("%H:%M");
+    match (today - local.date_naive()).num_days() {
+        0 => format!("As of {time}"),
+        1 => format!("As of {time} yesterday"),
+        _ => format!("As of {time} on {}", local.format("%a %-d %b")),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NewInstance {
+    pub slug: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub settings: Map<String, Value>,
+    /// `digest`, `on_demand` or `both`.
+    #[serde(default = "default_print_on")]
+    pub print_on: String,
+    #[serde(default)]
+    pub refresh_minutes: Option<i32>,
+    /// Goes after every other instance when omitted.
+    #[serde(default)]
+    pub position: Option<i32>,
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+fn default_print_on() -> String {
+    PrintOn::default().as_str().into()
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl NewInstance {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        let integration = find(&self.slug).ok_or_else(|| {
+            FieldError::new("slug", format!("{} is not an integration", self.slug))
+        })?;
+        validate_scheduling(Some(&self.print_on), self.refresh_minutes)?;
+        validate_template(self.template.as_deref())?;
+        validate_settings(integration, &self.settings)
+    }
+}
+
+/// Changes to an instance; fields left out are kept.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct InstancePatch {
+    #[serde(default)]
+    pub enabled: Option<bool>,
+    /// Replaces the settings as a whole.
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub settings: Option<Map<String, Value>>,
+    #[serde(default)]
+    pub print_on: Option<String>,
+    /// 0 stops scheduled refreshes.
+    #[serde(default)]
+    pub refresh_minutes: Option<i32>,
+    #[serde(default)]
+    pub position: Option<i32>,
+    /// An empty template goes back to the built-in layout.
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+impl InstancePatch {
+    pub fn validate(&self, instance: &Instance) -> Result<(), FieldError> {
+        validate_scheduling(
+            self.print_on.as_deref(),
+            self.refresh_minutes.filter(|m| *m != 0),
+        )?;
+        validate_template(self.template.as_deref())?;
+        match (&self.settings, find(&instance.slug)) {
+            (Some(settings), Some(integration)) => validate_settings(integration, settings),
+            (Some(_), None) => Err(FieldError::new(
+                "settings",
+                format!("can't be checked: {} is not an integration", instance.slug),
+            )),
+            (None, _) => Ok(()),
+        }
+    }
+}
+
+#[derive(AsChangeset)]
+#[diesel(table_name = integration_instances)]
+struct InstanceChanges {
+    enabled: Option<bool>,
+    settings: Option<String>,
+    print_on: Option<String>,
+    position: Option<i32>,
+}
+
+fn validate_template(template: Option<&str>) -> Result<(), FieldError> {
+    match template {
+        Some(template) => expand::check(&Value::String(template.into()))
+            .map_err(|e| FieldError::new("template", format!("is not a valid template: {e}"))),
+        None => Ok(()),
+    }
+}
+
+fn validate_scheduling(
+    print_on: Option<&str>,
+    refresh_minutes: Option<i32>,
+) -> Result<(), FieldError> {
+    if print_on.is_some_and(|p| p.parse::<PrintOn>().is_err()) {
+        return Err(FieldError::new(
+            "print_on",
+            "must be one of: digest, on_demand, both",
+        ));
+    }
+    if refresh_minutes.is_some_and(|m| m < 1) {
+        return Err(FieldError::new("refresh_minutes", "must be positive"));
+    }
+    Ok(())
+}
+
+/// Every way `settings` break the integration's [`Integration::config_schema`].
+pub fn settings_errors(
+    integration: &dyn Integration,
+    settings: &Map<String, Value>,
+) -> Vec<FieldError> {
+    validate::validate(&integration.config_schema(), settings)
+}
+
+/// Settings must match the integration's schema; the first mismatch is reported.
+fn validate_settings(
+    integration: &dyn Integration,
+    settings: &Map<String, Value>,
+) -> Result<(), FieldError> {
+    match settings_errors(integration, settings).into_iter().next() {
+        Some(error) => Err(error.within("settings")),
+        None => Ok(()),
+    }
+}
+
+/// Applies `f` to every string setting that `schema` marks as a secret with
+/// `"format": "password"`, however deeply it is nested.
+fn for_each_secret(
+    schema: &Value,
+    settings: &mut Map<String, Value>,
+    f: &mut dyn FnMut(&mut String) -> Result<()>,
+) -> Result<()> {
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return Ok(());
+    };
+    for (name, value) in settings {
+        if let Some(property) = properties.get(name) {
+            visit_secrets(property, value, f)?;
+        }
+    }
+    Ok(())
+}
+
+fn visit_secrets(
+    schema: &Value,
+    value: &mut Value,
+    f: &mut dyn FnMut(&mut String) -> Result<()>,
+) -> Result<()> {
+    match value {
+        Value::String(text) if schema.get("format").and_then(Value::as_str) == Some("password") => {
+            f(text)
+        }
+        Value::Object(fields) => for_each_secret(schema, fields, f),
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for item in items {
+                    visit_secrets(item_schema, item, f)?;
+                }
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Give the secrets left blank in `settings`, as [`Instance::redacted`] shows them,
+/// their value from `stored`.
+fn keep_blank_secrets(
+    schema: &Value,
+    settings: &mut Map<String, Value>,
+    stored: &Map<String, Value>,
+) {
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return;
+    };
+    for (name, value) in settings.iter_mut() {
+        let (Some(property), Some(old)) = (properties.get(name), stored.get(name)) else {
+            continue;
+        };
+        match (value, old) {
+            (Value::String(text), Value::String(old))
+                if text.is_empty()
+                    && property.get("format").and_then(Value::as_str) == Some("password") =>
+            {
+                text.clone_from(old);
+            }
+            (Value::Object(fields), Value::Object(old)) => {
+                keep_blank_secrets(property, fields, old);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `settings` as they are stored, with their secrets encrypted.
+fn seal_settings(slug: &str, mut settings: Map<String, Value>) -> Result<String> {
+    if let Some(integration) = find(slug) {
+        for_each_secret(&integration.config_schema(), &mut settings, &mut |s| {
+            *s = secrets::seal(s)?;
+            Ok(())
+        })?;
+    }
+    Ok(Value::Object(settings).to_string())
+}
+
+/// Encrypt the secrets of instances stored before `SECRET_KEY` was set, once it
+/// is. Returns how many secrets are left unencrypted for want of a key.
+pub fn seal_stored(conn: &mut SqliteConnection) -> Result<usize> {
+    let mut plain = 0;
+    for instance in list(conn)?.into_iter().chain(list_deleted(conn)?) {
+        let Some(integration) = find(&instance.slug) else {
+            continue;
+        };
+        let mut settings = instance.settings()?;
+        let mut changed = false;
+        for_each_secret(&integration.config_schema(), &mut settings, &mut |s| {
+            if !secrets::is_sealed(s) {
+                if secrets::enabled() {
+                    *s = secrets::seal(s)?;
+                    changed = true;
+                } else if !s.is_empty() {
+                    plain += 1;
+                }
+            }
+            Ok(())
+        })?;
+        if changed {
+            diesel::update(integration_instances::table.find(&instance.instance_id))
+                .set(integration_instances::settings.eq(Value::Object(settings).to_string()))
+                .execute(conn)?;
+        }
+    }
+    Ok(plain)
+}
+
+/// Every instance not in the trash, in digest order.
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Instance>> {
+    let rows = integration_instances::table
+        .filter(integration_instances::deleted_at.is_null())
+        .order((
+            integration_instances::position.asc(),
+            integration_instances::created_at.asc(),
+        ))
+        .select(Instance::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+/// Instances in the trash, most recently deleted first.
+pub fn list_deleted(conn: &mut SqliteConnection) -> Result<Vec<Instance>> {
+    let rows = integration_instances::table
+        .filter(integration_instances::deleted_at.is_not_null())
+        .order(integration_instances::deleted_at.desc())
+        .select(Instance::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn get(conn: &mut SqliteConnection, instance_id: &str) -> Result<Option<Instance>> {
+    let row = integration_instances::table
+        .find(instance_id)
+        .filter(integration_instances::deleted_at.is_null())
+        .select(Instance::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn create(conn: &mut SqliteConnection, new: NewInstance) -> Result<Instance> {
+    let instance_id = uuid::Uuid::new_v4().to_string().to_uppercase();
+    let position = match new.position {
+        Some(position) => position,
+        None => integration_instances::table
+            .select(diesel::dsl::max(integration_instances::position))
+            .first::<Option<i32>>(conn)?
+            .map_or(0, |last| last + 1),
+    };
+    let row = diesel::insert_into(integration_instances::table)
+        .values((
+            integration_instances::instance_id.eq(instance_id),
+            integration_instances::slug.eq(&new.slug),
+            integration_instances::enabled.eq(new.enabled),
+            integration_instances::settings.eq(seal_settings(&new.slug, new.settings)?),
+            integration_instances::print_on.eq(new.print_on),
+            integration_instances::refresh_minutes.eq(new.refresh_minutes),
+            integration_instances::position.eq(position),
+            integration_instances::template.eq(new.template.filter(|t| !t.is_empty())),
+        ))
+        .returning(Instance::as_returning())
+        .get_result(conn)?;
+    Ok(row)
+}
+
+pub fn update(
+    conn: &mut SqliteConnection,
+    instance_id: &str,
+    patch: InstancePatch,
+) -> Result<Option<Instance>> {
+    conn.transaction(|conn| {
+        let target = integration_instances::table
+            .find(instance_id)
+            .filter(integration_instances::deleted_at.is_null());
+        let Some((slug, stored)) = target
+            .select((integration_instances::slug, integration_instances::settings))
+            .first::<(String, String)>(conn)
+            .optional()?
+        else {
+            return Ok(None);
+        };
+        let settings = match (patch.settings, find(&slug)) {
+            (Some(mut settings), Some(integration)) => {
+                let stored = serde_json::from_str(&stored)?;
+                keep_blank_secrets(&integration.config_schema(), &mut settings, &stored);
+                Some(seal_settings(&slug, settings)?)
+            }
+            (Some(settings), None) => Some(seal_settings(&slug, settings)?),
+            (None, _) => None,
+        };
+        let changes = InstanceChanges {
+            enabled: patch.enabled,
+            settings,
+            print_on: patch.print_on,
+            position: patch.position,
+        };
+        // Data fetched with the old settings is no use with the new ones.
+        if changes.settings.is_some() {
+            diesel::update(target)
+                .set((
+                    integration_instances::data.eq(None::<String>),
+                    integration_instances::fetched_at.eq(None::<NaiveDateTime>),
+                ))
+                .execute(conn)?;
+        }
+        if let Some(template) = patch.template {
+            diesel::update(target)
+                .set(integration_instances::template.eq((!template.is_empty()).then_some(template)))
+                .execute(conn)?;
+        }
+        if let Some(minutes) = patch.refresh_minutes {
+            diesel::update(target)
+                .set(integration_instances::refresh_minutes.eq((minutes != 0).then_some(minutes)))
+                .execute(conn)?;
+        }
+        let row = diesel::update(target)
+            .set((
+                changes,
+                integration_instances::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .returning(Instance::as_returning())
+            .get_result(conn)
+            .optional()?;
+        Ok(row)
+    })
+}
+
+/// Put the instances in `order` first, in that order, followed by the rest as
+/// they were. Returns the instances in their new order.
+pub fn reorder(conn: &mut SqliteConnection, order: &[String]) -> Result<Vec<Instance>> {
+    conn.transaction(|conn| {
+        let mut instances = list(conn)?;
+        instances.sort_by_key(|i| {
+            order
+                .iter()
+                .position(|id| *id == i.instance_id)
+                .unwrap_or(order.len())
+        });
+        for (position, instance) in instances.iter_mut().enumerate() {
+            instance.position = position as i32;
+            diesel::update(integration_instances::table.find(&instance.instance_id))
+                .set(integration_instances::position.eq(instance.position))
+                .execute(conn)?;
+        }
+        Ok(instances)
+    })
+}
+
+/// Move an instance to the trash. It keeps its settings and template, but is no
+/// longer fetched, printed or listed.
+pub fn delete(conn: &mut SqliteConnection, instance_id: &str) -> Result<bool> {
+    let n = diesel::update(
+        integration_instances::table
+            .find(instance_id)
+            .filter(integration_instances::deleted_at.is_null()),
+    )
+    .set(integration_instances::deleted_at.eq(Utc::now().naive_utc()))
+    .execute(conn)?;
+    Ok(n > 0)
+}
+
+/// Take an instance out of the trash, provided it was deleted no earlier than
+/// `deleted_since`.
+pub fn restore(
+    conn: &mut SqliteConnection,
+    instance_id: &str,
+    deleted_since: NaiveDateTime,
+) -> Result<Option<Instance>> {
+    let row = diesel::update(
+        integration_instances::table
+            .find(instance_id)
+            .filter(integration_instances::deleted_at.ge(deleted_since)),
+    )
+    .set((
+        integration_instances::deleted_at.eq(None::<NaiveDateTime>),
+        integration_instances::updated_at.eq(Utc::now().naive_utc()),
+    ))
+    .returning(Instance::as_returning())
+    .get_result(conn)
+    .optional()?;
+    Ok(row)
+}
+
+/// Permanently remove instances deleted before `deleted_before`. Returns the number
+/// removed.
+pub fn purge(conn: &mut SqliteConnection, deleted_before: NaiveDateTime) -> Result<usize> {
+    let n = diesel::delete(
+        integration_instances::table.filter(integration_instances::deleted_at.lt(deleted_before)),
+    )
+    .execute(conn)?;
+    Ok(n)
+}
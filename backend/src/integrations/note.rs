@@ -0,0 +1,55 @@
+//! A fixed note, such as the Wi-Fi password for guests or a reminder to water the
+//! plants, printed every day until it is removed.
+
+use serde_json::{Map, Value, json};
+
+use super::{Context, Fetch, Integration};
+use crate::compose::Section;
+
+pub struct Note;
+
+impl Integration for Note {
+    fn slug(&self) -> &'static str {
+        "note"
+    }
+
+    fn name(&self) -> &'static str {
+        "Note"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string", "default": "Note" },
+                "text": { "type": "string" }
+            },
+            "required": ["text"]
+        })
+    }
+
+    fn fetch<'a>(&'a self, _ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        let text = settings.get("text").cloned().unwrap_or_default();
+        Box::pin(async move { Ok(text) })
+    }
+
+    fn render(&self, _ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("Note")
+                .into(),
+            priority: 0,
+            lines: data
+                .as_str()
+                .unwrap_or_default()
+                .lines()
+                .map(String::from)
+                .collect(),
+            blocks: Vec::new(),
+            summary: None,
+        }
+    }
+}
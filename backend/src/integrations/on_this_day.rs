@@ -0,0 +1,223 @@
+//! A notable event or two from history on the day's date, from Wikimedia's "on this
+//! day" feeds. A day's feed hardly changes, so each is kept in the server's database
+//! and fetched again only once a month, or printed from there when Wikimedia can't
+//! be reached.
+
+use anyhow::{Context as _, Result, anyhow};
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tracing::warn;
+
+use super::{Context, Fetch, Integration};
+use crate::compose::Section;
+use crate::db;
+use crate::schema::history_events;
+
+const DEFAULT_API: &str = "https://api.wikimedia.org/feed/v1/wikipedia";
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a kept feed is printed from before it is fetched again.
+const KEEP: TimeDelta = TimeDelta::days(30);
+const FEEDS: &[&str] = &["selected", "events", "births", "deaths", "holidays"];
+
+pub struct OnThisDay;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Event {
+    /// Missing for holidays.
+    #[serde(default)]
+    year: Option<i32>,
+    text: String,
+}
+
+impl Integration for OnThisDay {
+    fn slug(&self) -> &'static str {
+        "on_this_day"
+    }
+
+    fn name(&self) -> &'static str {
+        "On this day"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string", "default": "On this day" },
+                "count": { "type": "integer", "minimum": 1, "maximum": 5, "default": 2 },
+                "feed": {
+                    "type": "string",
+                    "enum": FEEDS,
+                    "default": "selected",
+                    "description": "Wikipedia's selected anniversaries, or all events, births, deaths or holidays"
+                },
+                "language": {
+                    "type": "string",
+                    "default": "en",
+                    "description": "Wikipedia edition, such as en, de or fr"
+                },
+                "api_url": {
+                    "type": "string",
+                    "format": "uri",
+                    "default": DEFAULT_API,
+                    "description": "Wikimedia's feed API or a compatible proxy"
+                }
+            }
+        })
+    }
+
+    fn fetch<'a>(&'a self, ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        let text = |name: &str, default: &str| {
+            settings
+                .get(name)
+                .and_then(Value::as_str)
+                .unwrap_or(default)
+                .trim_end_matches('/')
+                .to_string()
+        };
+        let (api, language, feed) = (
+            text("api_url", DEFAULT_API),
+            text("language", "en"),
+            text("feed", "selected"),
+        );
+        let count = settings.get("count").and_then(Value::as_u64).unwrap_or(2) as usize;
+        let date = ctx.date;
+        Box::pin(async move {
+            let day = date.format("%m-%d").to_string();
+            let key = (language.clone(), feed.clone(), day.clone());
+            let kept = db::run_blocking_db(move |conn| kept(conn, &key)).await?;
+            let events = match kept {
+                Some((events, at)) if Utc::now().naive_utc() - at < KEEP => events,
+                kept => match download(&api, &language, &feed, &day).await {
+                    Ok(events) => {
+                        let (language, feed, day) = (language.clone(), feed.clone(), day);
+                        let data = serde_json::to_string(&events)?;
+                        db::run_blocking_db(move |conn| store(conn, &language, &feed, &day, &data))
+                            .await?;
+                        events
+                    }
+                    Err(e) => {
+                        let (events, at) = kept.ok_or(e)?;
+                        warn!("on this day: Wikimedia unreachable, printing the feed from {at}");
+                        events
+                    }
+                },
+            };
+            Ok(serde_json::to_value(pick(&events, count, date))?)
+        })
+    }
+
+    fn render(&self, _ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        let events: Vec<Event> = serde_json::from_value(data.clone()).unwrap_or_default();
+        let line = |event: &Event| match event.year {
+            Some(year) if year < 0 => format!("{} BC: {}", -year, event.text),
+            Some(year) => format!("{year}: {}", event.text),
+            None => event.text.clone(),
+        };
+        let mut lines: Vec<String> = events.iter().map(line).collect();
+        if lines.is_empty() {
+            lines.push("Nothing recorded for today".into());
+        }
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("On this day")
+                .into(),
+            priority: 0,
+            lines,
+            blocks: Vec::new(),
+            summary: events.first().map(line),
+        }
+    }
+
+    /// The events only change with the day.
+    fn cache_ttl(&self) -> TimeDelta {
+        TimeDelta::days(1)
+    }
+}
+
+/// `count` of the events, the same ones for every printer on `date` but different
+/// from one year to the next, in the order they happened.
+fn pick(events: &[Event], count: usize, date: NaiveDate) -> Vec<Event> {
+    let hash = Sha256::digest(date.format("%Y-%m-%d").to_string());
+    let seed = u64::from_be_bytes(hash[..8].try_into().unwrap_or_default()) as usize;
+    // Spread across the feed, which lists the events newest first.
+    let count = count.min(events.len());
+    let chosen =
+        (0..count).map(|i| (seed % events.len() + i * events.len() / count) % events.len());
+    let mut picked: Vec<Event> = chosen.map(|i| events[i].clone()).collect();
+    picked.sort_by_key(|e| e.year);
+    picked
+}
+
+fn kept(
+    conn: &mut SqliteConnection,
+    (language, feed, day): &(String, String, String),
+) -> Result<Option<(Vec<Event>, NaiveDateTime)>> {
+    let row: Option<(String, NaiveDateTime)> = history_events::table
+        .find((language, feed, day))
+        .select((history_events::data, history_events::fetched_at))
+        .first(conn)
+        .optional()?;
+    row.map(|(data, at)| Ok((serde_json::from_str(&data)?, at)))
+        .transpose()
+}
+
+fn store(
+    conn: &mut SqliteConnection,
+    language: &str,
+    feed: &str,
+    day: &str,
+    data: &str,
+) -> Result<()> {
+    let now = Utc::now().naive_utc();
+    diesel::insert_into(history_events::table)
+        .values((
+            history_events::language.eq(language),
+            history_events::feed.eq(feed),
+            history_events::day.eq(day),
+            history_events::data.eq(data),
+            history_events::fetched_at.eq(now),
+        ))
+        .on_conflict((
+            history_events::language,
+            history_events::feed,
+            history_events::day,
+        ))
+        .do_update()
+        .set((
+            history_events::data.eq(data),
+            history_events::fetched_at.eq(now),
+        ))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// `{api}/{language}/onthisday/{feed}/{MM}/{DD}`, whose events are listed under
+/// the feed's name.
+async fn download(api: &str, language: &str, feed: &str, day: &str) -> Result<Vec<Event>> {
+    let url = format!(
+        "{api}/{language}/onthisday/{feed}/{}",
+        day.replace('-', "/")
+    );
+    let mut response: Map<String, Value> = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .user_agent("dayroll (https://github.com/noahhusby/dayroll)")
+        .build()?
+        .get(&url)
+        .send()
+        .await?
+        .error_for_status()
+        .with_context(|| format!("fetching {url}"))?
+        .json()
+        .await?;
+    let events = response
+        .remove(feed)
+        .ok_or_else(|| anyhow!("{url} has no {feed}"))?;
+    Ok(serde_json::from_value(events)?)
+}
@@ -0,0 +1,429 @@
+//! A puzzle for the breakfast table: a sudoku or a word search, printed as a grid.
+//! Puzzles are generated from the day, so every printer prints the same one.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+
+use super::{Context, Fetch, Integration};
+use crate::compose::Section;
+use crate::document::{Align, Block};
+use crate::render::grid;
+
+/// Words hidden in a word search when the instance doesn't list its own.
+const WORDS: &str = "\
+    APPLE BAGEL BANANA BREAD BUTTER CEREAL CHEESE COCOA COFFEE CREAM CROISSANT EGGS \
+    FLOUR GRAPE HONEY JAM JUICE KETTLE LEMON MANGO MAPLE MELON MILK MUFFIN OATS ORANGE \
+    PANCAKE PEACH PEAR PLUM PORRIDGE SCONE SUGAR SYRUP TEA TOAST WAFFLE YOGURT AUTUMN \
+    BREEZE CLOUD DAWN GARDEN MEADOW MORNING RIVER SUNRISE WINDOW";
+
+pub struct Puzzle;
+
+/// Which puzzle an instance prints.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Kind {
+    #[default]
+    Sudoku,
+    WordSearch,
+}
+
+impl Kind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Kind::Sudoku => "sudoku",
+            Kind::WordSearch => "word_search",
+        }
+    }
+}
+
+impl FromStr for Kind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sudoku" => Ok(Kind::Sudoku),
+            "word_search" => Ok(Kind::WordSearch),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Difficulty {
+    /// A sudoku with 40 clues; a 10x10 word search read across and down.
+    Easy,
+    /// A sudoku with about 32 clues; a 12x12 word search with diagonals too.
+    #[default]
+    Medium,
+    /// A sudoku with as few as 26 clues; a 14x14 word search read in any direction.
+    Hard,
+}
+
+impl Difficulty {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A generated puzzle: its grid a row per string, a space for an empty square, and
+/// the words a word search hides.
+#[derive(Debug, Serialize, Deserialize)]
+struct Generated {
+    kind: String,
+    difficulty: String,
+    rows: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    words: Vec<String>,
+}
+
+impl Integration for Puzzle {
+    fn slug(&self) -> &'static str {
+        "puzzle"
+    }
+
+    fn name(&self) -> &'static str {
+        "Puzzle"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string", "default": "Puzzle" },
+                "kind": {
+                    "type": "string",
+                    "enum": ["sudoku", "word_search"],
+                    "default": "sudoku"
+                },
+                "difficulty": {
+                    "type": "string",
+                    "enum": ["easy", "medium", "hard"],
+                    "default": "medium"
+                },
+                "words": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Words to hide in a word search; a built-in list when empty"
+                }
+            }
+        })
+    }
+
+    fn fetch<'a>(&'a self, ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        let text = |key| settings.get(key).and_then(Value::as_str);
+        let kind: Kind = text("kind")
+            .and_then(|k| k.parse().ok())
+            .unwrap_or_default();
+        let difficulty: Difficulty = text("difficulty")
+            .and_then(|d| d.parse().ok())
+            .unwrap_or_default();
+        let words: Vec<String> = settings
+            .get("words")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(String::from)
+            .collect();
+        let mut rng = Rng::new(ctx.date, kind, difficulty);
+        let (rows, words) = match kind {
+            Kind::Sudoku => (sudoku(&mut rng, difficulty), Vec::new()),
+            Kind::WordSearch => word_search(&mut rng, difficulty, &words),
+        };
+        let generated = Generated {
+            kind: kind.as_str().into(),
+            difficulty: difficulty.as_str().into(),
+            rows,
+            words,
+        };
+        Box::pin(async move { Ok(serde_json::to_value(generated)?) })
+    }
+
+    fn render(&self, _ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        let mut lines = Vec::new();
+        let mut blocks = Vec::new();
+        let mut summary = None;
+        if let Ok(puzzle) = serde_json::from_value::<Generated>(data.clone()) {
+            let kind: Kind = puzzle.kind.parse().unwrap_or_default();
+            let rows: Vec<Vec<char>> = puzzle.rows.iter().map(|r| r.chars().collect()).collect();
+            let image = match kind {
+                Kind::Sudoku => grid::png(&rows, 60, 3, true),
+                Kind::WordSearch => grid::png(&rows, 40, 0, false),
+            };
+            if let Ok(png) = image {
+                blocks.push(Block::Image {
+                    data: BASE64.encode(png),
+                    align: Align::Center,
+                });
+            }
+            match kind {
+                Kind::Sudoku => {
+                    lines.push("Fill every row, column and box with 1 to 9.".into());
+                }
+                Kind::WordSearch => lines.extend(word_lines(&puzzle.words)),
+            }
+            summary = Some(match kind {
+                Kind::Sudoku => format!("Sudoku ({})", puzzle.difficulty),
+                Kind::WordSearch => format!("Word search ({})", puzzle.difficulty),
+            });
+        }
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("Puzzle")
+                .into(),
+            priority: 0,
+            lines,
+            blocks,
+            summary,
+        }
+    }
+}
+
+/// The words to find, a few to a line.
+fn word_lines(words: &[String]) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in words {
+        match lines.last_mut() {
+            Some(line) if line.len() + word.len() + 2 <= 40 => {
+                line.push_str("  ");
+                line.push_str(word);
+            }
+            _ => lines.push(word.clone()),
+        }
+    }
+    lines
+}
+
+/// A sudoku grid with as many clues removed as `difficulty` asks for, keeping its
+/// solution unique.
+fn sudoku(rng: &mut Rng, difficulty: Difficulty) -> Vec<String> {
+    let mut grid = [0u8; 81];
+    fill(&mut grid, rng);
+    let clues = match difficulty {
+        Difficulty::Easy => 40,
+        Difficulty::Medium => 32,
+        Difficulty::Hard => 26,
+    };
+    let mut cells: Vec<usize> = (0..81).collect();
+    rng.shuffle(&mut cells);
+    let mut filled = 81;
+    for cell in cells {
+        if filled <= clues {
+            break;
+        }
+        let digit = grid[cell];
+        grid[cell] = 0;
+        if solutions(&mut grid.clone(), 2) == 1 {
+            filled -= 1;
+        } else {
+            grid[cell] = digit;
+        }
+    }
+    grid.chunks(9)
+        .map(|row| {
+            row.iter()
+                .map(|&d| if d == 0 { ' ' } else { char::from(b'0' + d) })
+                .collect()
+        })
+        .collect()
+}
+
+/// Digits `cell` can still take, as bits 1 to 9.
+fn candidates(grid: &[u8; 81], cell: usize) -> u16 {
+    let (row, column) = (cell / 9, cell % 9);
+    let corner = (row / 3) * 27 + (column / 3) * 3;
+    let mut used = 0u16;
+    for i in 0..9 {
+        used |= 1 << grid[row * 9 + i];
+        used |= 1 << grid[i * 9 + column];
+        used |= 1 << grid[corner + (i / 3) * 9 + i % 3];
+    }
+    !used & 0b11_1111_1110
+}
+
+/// Complete `grid` with digits tried in random order.
+fn fill(grid: &mut [u8; 81], rng: &mut Rng) -> bool {
+    let Some(cell) = grid.iter().position(|&d| d == 0) else {
+        return true;
+    };
+    let mut digits: Vec<u8> = (1..=9).collect();
+    rng.shuffle(&mut digits);
+    for digit in digits {
+        if candidates(grid, cell) & (1 << digit) != 0 {
+            grid[cell] = digit;
+            if fill(grid, rng) {
+                return true;
+            }
+        }
+    }
+    grid[cell] = 0;
+    false
+}
+
+/// How many ways `grid` can be completed, counting no further than `limit`.
+fn solutions(grid: &mut [u8; 81], limit: usize) -> usize {
+    let Some((cell, options)) = (0..81)
+        .filter(|&c| grid[c] == 0)
+        .map(|c| (c, candidates(grid, c)))
+        .min_by_key(|(_, options)| options.count_ones())
+    else {
+        return 1;
+    };
+    let mut count = 0;
+    for digit in 1..=9 {
+        if options & (1 << digit) != 0 {
+            grid[cell] = digit;
+            count += solutions(grid, limit - count);
+            if count >= limit {
+                break;
+            }
+        }
+    }
+    grid[cell] = 0;
+    count
+}
+
+/// A word search hiding words from `own` (or the built-in list), and the words it
+/// hides, alphabetically.
+fn word_search(
+    rng: &mut Rng,
+    difficulty: Difficulty,
+    own: &[String],
+) -> (Vec<String>, Vec<String>) {
+    const ACROSS_DOWN: &[(i32, i32)] = &[(0, 1), (1, 0)];
+    const DIAGONAL: &[(i32, i32)] = &[(0, 1), (1, 0), (1, 1), (-1, 1)];
+    const ANY: &[(i32, i32)] = &[
+        (0, 1),
+        (1, 0),
+        (1, 1),
+        (-1, 1),
+        (0, -1),
+        (-1, 0),
+        (-1, -1),
+        (1, -1),
+    ];
+    let (size, count, directions) = match difficulty {
+        Difficulty::Easy => (10, 8, ACROSS_DOWN),
+        Difficulty::Medium => (12, 10, DIAGONAL),
+        Difficulty::Hard => (14, 12, ANY),
+    };
+
+    let mut pool: Vec<String> = own
+        .iter()
+        .map(|w| {
+            w.chars()
+                .filter(char::is_ascii_alphabetic)
+                .collect::<String>()
+                .to_ascii_uppercase()
+        })
+        .filter(|w| (3..=size).contains(&w.len()))
+        .collect();
+    if pool.is_empty() {
+        pool = WORDS.split_whitespace().map(String::from).collect();
+    }
+    rng.shuffle(&mut pool);
+
+    let mut grid = vec![vec![' '; size]; size];
+    let mut hidden = Vec::new();
+    for word in pool {
+        if hidden.len() == count {
+            break;
+        }
+        if !hidden.contains(&word) && hide(&mut grid, &word, directions, rng) {
+            hidden.push(word);
+        }
+    }
+    for square in grid.iter_mut().flatten().filter(|c| **c == ' ') {
+        *square = char::from(b'A' + rng.below(26) as u8);
+    }
+    hidden.sort();
+    let rows = grid.into_iter().map(String::from_iter).collect();
+    (rows, hidden)
+}
+
+/// Write `word` somewhere it fits, crossing other words only where they share a
+/// letter.
+fn hide(grid: &mut [Vec<char>], word: &str, directions: &[(i32, i32)], rng: &mut Rng) -> bool {
+    let size = grid.len() as i32;
+    let letters: Vec<char> = word.chars().collect();
+    for _ in 0..200 {
+        let (dy, dx) = directions[rng.below(directions.len())];
+        let (y, x) = (
+            rng.below(size as usize) as i32,
+            rng.below(size as usize) as i32,
+        );
+        let squares: Vec<(usize, usize)> = (0..letters.len() as i32)
+            .map(|i| (y + dy * i, x + dx * i))
+            .take_while(|&(y, x)| (0..size).contains(&y) && (0..size).contains(&x))
+            .map(|(y, x)| (y as usize, x as usize))
+            .collect();
+        if squares.len() < letters.len() {
+            continue;
+        }
+        let fits = squares
+            .iter()
+            .zip(&letters)
+            .all(|(&(y, x), &c)| grid[y][x] == ' ' || grid[y][x] == c);
+        if fits {
+            for (&(y, x), &c) in squares.iter().zip(&letters) {
+                grid[y][x] = c;
+            }
+            return true;
+        }
+    }
+    false
+}
+
+/// splitmix64, seeded from the day and the kind of puzzle.
+struct Rng(u64);
+
+impl Rng {
+    fn new(date: NaiveDate, kind: Kind, difficulty: Difficulty) -> Self {
+        let seed = format!("{date}:{}:{}", kind.as_str(), difficulty.as_str());
+        let hash = Sha256::digest(seed);
+        Rng(u64::from_be_bytes(hash[..8].try_into().unwrap_or_default()))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number from 0 up to but not including `n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! A quote a day, from a collection built into the server and the user's own quote
+//! lists. The choice depends only on the day and the quotes available, so every
+//! printer prints the same one.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use sha2::{Digest, Sha256};
+
+use super::{Context, Fetch, Integration};
+use crate::compose::Section;
+use crate::{db, quotes};
+
+/// Tab-separated quote and author, one per line.
+static QUOTES: &str = include_str!("../../assets/quotes/quotes.tsv");
+
+pub struct Quotes;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Quote {
+    text: String,
+    author: Option<String>,
+}
+
+impl Integration for Quotes {
+    fn slug(&self) -> &'static str {
+        "quotes"
+    }
+
+    fn name(&self) -> &'static str {
+        "Quote of the day"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string", "default": "Quote of the day" },
+                "lists": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Names of quote lists to choose from"
+                },
+                "built_in": {
+                    "type": "boolean",
+                    "default": true,
+                    "description": "Also choose from the quotes built into the server"
+                }
+            }
+        })
+    }
+
+    fn fetch<'a>(&'a self, ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        let lists: Vec<String> = settings
+            .get("lists")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(String::from)
+            .collect();
+        let built_in = settings
+            .get("built_in")
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+        Box::pin(async move {
+            let mut pool = if built_in { bundled() } else { Vec::new() };
+            if !lists.is_empty() {
+                let own = db::run_blocking_db(move |conn| quotes::quotes_in(conn, &lists)).await?;
+                pool.extend(own.into_iter().map(|q| Quote {
+                    text: q.text,
+                    author: q.author,
+                }));
+            }
+            if pool.is_empty() {
+                pool = bundled();
+            }
+            let quote = pool.swap_remove(pick(ctx.date, pool.len()));
+            Ok(serde_json::to_value(quote)?)
+        })
+    }
+
+    fn render(&self, _ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        let mut lines = Vec::new();
+        let mut summary = None;
+        if let Ok(quote) = serde_json::from_value::<Quote>(data.clone()) {
+            let text = format!("\"{}\"", quote.text);
+            lines.push(text.clone());
+            if let Some(author) = &quote.author {
+                lines.push(format!("- {author}"));
+            }
+            summary = Some(text);
+        }
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("Quote of the day")
+                .into(),
+            priority: 0,
+            lines,
+            blocks: Vec::new(),
+            summary,
+        }
+    }
+}
+
+fn bundled() -> Vec<Quote> {
+    QUOTES
+        .lines()
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|line| {
+            let (text, author) = line.split_once('\t').unwrap_or((line, ""));
+            Quote {
+                text: text.into(),
+                author: Some(author.to_string()).filter(|a| !a.is_empty()),
+            }
+        })
+        .collect()
+}
+
+/// Index of `date`'s quote among `count`, scattered so consecutive days don't
+/// print neighbouring quotes.
+fn pick(date: NaiveDate, count: usize) -> usize {
+    let hash = Sha256::digest(date.format("%Y-%m-%d").to_string());
+    let seed = u64::from_be_bytes(hash[..8].try_into().unwrap_or_default());
+    (seed % count as u64) as usize
+}
@@ -0,0 +1,118 @@
+//! The day's events from a calendar published as an ICS file, such as a Google
+//! Calendar secret address or any `webcal://` subscription link.
+
+use anyhow::{Context as _, Result};
+use chrono::TimeDelta;
+use serde_json::{Map, Value, json};
+
+use super::ical::{self, Occurrence, Zone};
+use super::{Context, Fetch, Integration};
+use crate::compose::Section;
+
+pub struct RemoteCalendar;
+
+impl Integration for RemoteCalendar {
+    fn slug(&self) -> &'static str {
+        "remote_calendar"
+    }
+
+    fn name(&self) -> &'static str {
+        "Calendar (ICS link)"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "url": { "type": "string", "format": "uri" },
+                "title": { "type": "string", "default": "Calendar" },
+                "timezone": {
+                    "type": "string",
+                    "description": "IANA time zone the times are printed in; the server's when unset"
+                },
+                "show_location": { "type": "boolean", "default": true }
+            },
+            "required": ["url"]
+        })
+    }
+
+    fn fetch<'a>(&'a self, ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        Box::pin(async move {
+            let url = settings
+                .get("url")
+                .and_then(Value::as_str)
+                .context("url is not set")?;
+            let zone = match settings.get("timezone").and_then(Value::as_str) {
+                Some(name) => Zone::Named(
+                    name.parse()
+                        .map_err(|_| anyhow::anyhow!("unknown time zone {name}"))?,
+                ),
+                None => Zone::Local,
+            };
+            let text = download(url).await?;
+            let events = ical::parse(&text);
+            Ok(serde_json::to_value(ical::agenda(&events, ctx.date, zone))?)
+        })
+    }
+
+    fn render(&self, _ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        let occurrences: Vec<Occurrence> = serde_json::from_value(data.clone()).unwrap_or_default();
+        let show_location = settings
+            .get("show_location")
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+        let mut lines = Vec::new();
+        for occurrence in &occurrences {
+            let time = match occurrence.start {
+                Some(start) => start.format("%H:%M").to_string(),
+                None => "all day".into(),
+            };
+            lines.push(format!("{time:<7} {}", occurrence.title));
+            if let Some(location) = occurrence.location.as_ref().filter(|_| show_location) {
+                lines.push(format!("{:<7} {location}", ""));
+            }
+        }
+        if lines.is_empty() {
+            lines.push("Nothing scheduled".into());
+        }
+        let summary = match occurrences.iter().find_map(|o| o.start) {
+            Some(first) => format!(
+                "{} events, the first at {}",
+                occurrences.len(),
+                first.format("%H:%M")
+            ),
+            None => format!("{} events", occurrences.len()),
+        };
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("Calendar")
+                .into(),
+            priority: 0,
+            lines,
+            blocks: Vec::new(),
+            summary: Some(summary),
+        }
+    }
+
+    fn cache_ttl(&self) -> TimeDelta {
+        TimeDelta::minutes(15)
+    }
+}
+
+/// The calendar at `url`; `webcal://` is fetched over HTTPS.
+async fn download(url: &str) -> Result<String> {
+    let url = match url.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{rest}"),
+        None => url.to_string(),
+    };
+    let response = reqwest::Client::new()
+        .get(&url)
+        .send()
+        .await
+        .with_context(|| format!("failed to fetch calendar {url}"))?
+        .error_for_status()?;
+    Ok(response.text().await?)
+}
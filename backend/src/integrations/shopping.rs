@@ -0,0 +1,102 @@
+//! What's on the shopping lists, as a checklist.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use super::{Context, Fetch, Integration};
+use crate::compose::Section;
+use crate::{db, shopping};
+
+pub struct Shopping;
+
+/// A list and its items' labels, as fetched.
+#[derive(Debug, Serialize, Deserialize)]
+struct List {
+    name: String,
+    items: Vec<String>,
+}
+
+impl Integration for Shopping {
+    fn slug(&self) -> &'static str {
+        "shopping"
+    }
+
+    fn name(&self) -> &'static str {
+        "Shopping list"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string", "default": "Shopping" },
+                "lists": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Names of the lists to print; all of them when unset"
+                }
+            }
+        })
+    }
+
+    fn fetch<'a>(&'a self, _ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        let names = settings.get("lists").and_then(Value::as_array);
+        let names: Option<Vec<String>> = names.map(|names| {
+            names
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        });
+        Box::pin(async move {
+            let lists: Vec<List> = db::run_blocking_db(move |conn| {
+                let mut lists = Vec::new();
+                for list in shopping::list(conn)? {
+                    if names
+                        .as_ref()
+                        .is_some_and(|names| !names.contains(&list.name))
+                    {
+                        continue;
+                    }
+                    let items = shopping::items_of(conn, &list)?;
+                    lists.push(List {
+                        name: list.name,
+                        items: items.iter().map(shopping::Item::label).collect(),
+                    });
+                }
+                Ok(lists)
+            })
+            .await?;
+            Ok(serde_json::to_value(lists)?)
+        })
+    }
+
+    fn render(&self, _ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        let lists: Vec<List> = serde_json::from_value(data.clone()).unwrap_or_default();
+        let lists: Vec<&List> = lists.iter().filter(|l| !l.items.is_empty()).collect();
+        let mut lines = Vec::new();
+        for list in &lists {
+            // With several lists, each item goes under its list's name.
+            if lists.len() > 1 {
+                lines.push(format!("{}:", list.name));
+            }
+            lines.extend(list.items.iter().map(|item| format!("[ ] {item}")));
+        }
+        if lines.is_empty() {
+            lines.push("Nothing to buy".into());
+        }
+        let count: usize = lists.iter().map(|l| l.items.len()).sum();
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("Shopping")
+                .into(),
+            priority: 0,
+            lines,
+            blocks: Vec::new(),
+            summary: Some(format!("{count} items to buy")),
+        }
+    }
+}
@@ -0,0 +1,397 @@
+//! Yesterday's results and today's fixtures for chosen teams, from ESPN's public
+//! scoreboards, with kickoff times in the instance's time zone.
+
+use anyhow::{Context as _, Result, anyhow};
+use chrono::{DateTime, Days, NaiveDate, NaiveDateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tracing::warn;
+
+use super::ical::Zone;
+use super::{Context, Fetch, Integration};
+use crate::compose::Section;
+
+const DEFAULT_API: &str = "https://site.api.espn.com/apis/site/v2/sports";
+const TIMEOUT: Duration = Duration::from_secs(8);
+
+pub struct Sports;
+
+/// A team to follow, as configured.
+#[derive(Debug, Clone, Deserialize)]
+struct Team {
+    /// Sport and league as ESPN names them, such as `soccer/eng.1` or
+    /// `football/nfl`.
+    league: String,
+    /// Name, short name, abbreviation or ESPN id.
+    team: String,
+}
+
+/// A game involving a followed team, as fetched.
+#[derive(Debug, Serialize, Deserialize)]
+struct Game {
+    /// Kickoff, in the instance's time zone.
+    kickoff: NaiveDateTime,
+    home: String,
+    away: String,
+    home_score: Option<String>,
+    away_score: Option<String>,
+    state: State,
+    /// ESPN's word on the game, such as "FT", "63'" or "Postponed".
+    detail: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum State {
+    Scheduled,
+    Playing,
+    Finished,
+    /// Postponed, cancelled or otherwise off.
+    Off,
+}
+
+/// What was fetched: the games, and the teams whose league couldn't be reached.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Games {
+    games: Vec<Game>,
+    #[serde(default)]
+    unavailable: Vec<String>,
+}
+
+impl Integration for Sports {
+    fn slug(&self) -> &'static str {
+        "sports"
+    }
+
+    fn name(&self) -> &'static str {
+        "Sports scores and fixtures"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "required": ["teams"],
+            "properties": {
+                "title": { "type": "string", "default": "Sports" },
+                "teams": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["league", "team"],
+                        "properties": {
+                            "league": {
+                                "type": "string",
+                                "description": "ESPN sport and league, such as soccer/eng.1, football/nfl or basketball/nba"
+                            },
+                            "team": {
+                                "type": "string",
+                                "description": "Name, abbreviation or ESPN id of the team"
+                            }
+                        }
+                    }
+                },
+                "timezone": {
+                    "type": "string",
+                    "description": "IANA time zone kickoff times are printed in; the server's when unset"
+                },
+                "api_url": {
+                    "type": "string",
+                    "format": "uri",
+                    "default": DEFAULT_API,
+                    "description": "ESPN's site API or a compatible proxy"
+                }
+            }
+        })
+    }
+
+    fn fetch<'a>(&'a self, ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        Box::pin(async move {
+            let teams: Vec<Team> = match settings.get("teams") {
+                Some(teams) => {
+                    serde_json::from_value(teams.clone()).context("teams must be a list")?
+                }
+                None => Vec::new(),
+            };
+            let zone = match settings.get("timezone").and_then(Value::as_str) {
+                Some(name) => Zone::Named(
+                    name.parse()
+                        .map_err(|_| anyhow::anyhow!("unknown time zone {name}"))?,
+                ),
+                None => Zone::Local,
+            };
+            let api = settings
+                .get("api_url")
+                .and_then(Value::as_str)
+                .unwrap_or(DEFAULT_API)
+                .trim_end_matches('/')
+                .to_string();
+            let yesterday = ctx.date.checked_sub_days(Days::new(1)).unwrap_or(ctx.date);
+
+            let mut leagues: Vec<String> = teams.iter().map(|t| t.league.clone()).collect();
+            leagues.sort();
+            leagues.dedup();
+            let client = reqwest::Client::builder().timeout(TIMEOUT).build()?;
+            let mut downloads = JoinSet::new();
+            for league in leagues {
+                let (client, api) = (client.clone(), api.clone());
+                // A day either side, since the scoreboard's days aren't the zone's.
+                let from = yesterday
+                    .checked_sub_days(Days::new(1))
+                    .unwrap_or(yesterday);
+                let to = ctx.date.checked_add_days(Days::new(1)).unwrap_or(ctx.date);
+                downloads.spawn(async move {
+                    let events = download(&client, &api, &league, from, to).await;
+                    (league, events)
+                });
+            }
+
+            let mut fetched = Games::default();
+            while let Some(done) = downloads.join_next().await {
+                let (league, events) = done?;
+                let followed = teams.iter().filter(|t| t.league == league);
+                let events = match events {
+                    Ok(events) => events,
+                    Err(e) => {
+                        warn!("sports: {league}: {e:#}");
+                        fetched.unavailable.extend(followed.map(|t| t.team.clone()));
+                        continue;
+                    }
+                };
+                let followed: Vec<&Team> = followed.collect();
+                for event in events {
+                    let Some(game) = game(&event, zone) else {
+                        continue;
+                    };
+                    let day = game.kickoff.date();
+                    if day != yesterday && day != ctx.date {
+                        continue;
+                    }
+                    let competitors = event
+                        .competitions
+                        .first()
+                        .map(|c| c.competitors.as_slice())
+                        .unwrap_or_default();
+                    if competitors
+                        .iter()
+                        .any(|c| followed.iter().any(|t| c.team.is(&t.team)))
+                    {
+                        fetched.games.push(game);
+                    }
+                }
+            }
+            fetched.games.sort_by_key(|g| g.kickoff);
+            Ok(serde_json::to_value(fetched)?)
+        })
+    }
+
+    fn render(&self, ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        let fetched: Games = serde_json::from_value(data.clone()).unwrap_or_default();
+        let (today, yesterday): (Vec<&Game>, Vec<&Game>) = fetched
+            .games
+            .iter()
+            .partition(|g| g.kickoff.date() == ctx.date);
+        let mut lines = Vec::new();
+        if !yesterday.is_empty() {
+            lines.push("Yesterday:".to_string());
+            lines.extend(yesterday.iter().map(|g| format!("  {}", result(g))));
+        }
+        if !today.is_empty() {
+            lines.push("Today:".to_string());
+            lines.extend(today.iter().map(|g| match g.state {
+                State::Scheduled => {
+                    format!("  {} {} v {}", g.kickoff.format("%H:%M"), g.home, g.away)
+                }
+                _ => format!("  {}", result(g)),
+            }));
+        }
+        for team in &fetched.unavailable {
+            lines.push(format!("{team} unavailable"));
+        }
+        if lines.is_empty() {
+            lines.push("No games".into());
+        }
+
+        let summary = match (yesterday.len(), today.first()) {
+            (_, Some(first)) if first.state == State::Scheduled => Some(format!(
+                "{} v {} at {}",
+                first.home,
+                first.away,
+                first.kickoff.format("%H:%M")
+            )),
+            (0, None) => None,
+            _ => yesterday.last().map(|g| result(g)),
+        };
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("Sports")
+                .into(),
+            priority: 0,
+            lines,
+            blocks: Vec::new(),
+            summary,
+        }
+    }
+
+    fn cache_ttl(&self) -> TimeDelta {
+        TimeDelta::minutes(10)
+    }
+}
+
+/// A game that has started or been called off, as "Arsenal 2-1 Chelsea (FT)".
+fn result(game: &Game) -> String {
+    match (game.state, &game.home_score, &game.away_score) {
+        (State::Playing | State::Finished, Some(home), Some(away)) => format!(
+            "{} {home}-{away} {} ({})",
+            game.home, game.away, game.detail
+        ),
+        _ => format!("{} v {}, {}", game.home, game.away, game.detail),
+    }
+}
+
+#[derive(Deserialize)]
+struct Scoreboard {
+    #[serde(default)]
+    events: Vec<Event>,
+}
+
+#[derive(Deserialize)]
+struct Event {
+    date: String,
+    #[serde(default)]
+    competitions: Vec<Competition>,
+    status: Status,
+}
+
+#[derive(Deserialize)]
+struct Competition {
+    #[serde(default)]
+    competitors: Vec<Competitor>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Competitor {
+    home_away: String,
+    team: EspnTeam,
+    #[serde(default)]
+    score: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EspnTeam {
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    abbreviation: String,
+    #[serde(default)]
+    display_name: String,
+    #[serde(default)]
+    short_display_name: String,
+}
+
+impl EspnTeam {
+    /// Whether `name` is one of the team's names, abbreviation or id.
+    fn is(&self, name: &str) -> bool {
+        let name = name.trim();
+        [
+            &self.id,
+            &self.abbreviation,
+            &self.display_name,
+            &self.short_display_name,
+        ]
+        .iter()
+        .any(|known| !known.is_empty() && known.eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Deserialize)]
+struct Status {
+    #[serde(rename = "type")]
+    kind: StatusType,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StatusType {
+    /// `pre`, `in` or `post`.
+    state: String,
+    #[serde(default)]
+    completed: bool,
+    #[serde(default)]
+    short_detail: String,
+    #[serde(default)]
+    description: String,
+}
+
+async fn download(
+    client: &reqwest::Client,
+    api: &str,
+    league: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<Event>> {
+    let url = format!("{api}/{}/scoreboard", league.trim_matches('/'));
+    let dates = format!("{}-{}", from.format("%Y%m%d"), to.format("%Y%m%d"));
+    let scoreboard: Scoreboard = client
+        .get(&url)
+        .query(&[("dates", dates.as_str()), ("limit", "500")])
+        .send()
+        .await?
+        .error_for_status()
+        .with_context(|| format!("fetching {url}"))?
+        .json()
+        .await?;
+    Ok(scoreboard.events)
+}
+
+/// An event as a game, with its kickoff in `zone`. Events without a home and an
+/// away side or a readable date are skipped.
+fn game(event: &Event, zone: Zone) -> Option<Game> {
+    let kickoff = kickoff(&event.date)
+        .inspect_err(|e| warn!("sports: {e}"))
+        .ok()?;
+    let competitors = &event.competitions.first()?.competitors;
+    let side = |which: &str| competitors.iter().find(|c| c.home_away == which);
+    let (home, away) = (side("home")?, side("away")?);
+    let status = &event.status.kind;
+    let state = match status.state.as_str() {
+        "in" => State::Playing,
+        "post" if status.completed => State::Finished,
+        "post" => State::Off,
+        _ if status.description.eq_ignore_ascii_case("postponed")
+            || status.description.eq_ignore_ascii_case("canceled") =>
+        {
+            State::Off
+        }
+        _ => State::Scheduled,
+    };
+    let detail = match state {
+        State::Off => status.description.to_lowercase(),
+        _ => status.short_detail.clone(),
+    };
+    Some(Game {
+        kickoff: zone.local(kickoff),
+        home: home.team.display_name.clone(),
+        away: away.team.display_name.clone(),
+        home_score: home.score.clone(),
+        away_score: away.score.clone(),
+        state,
+        detail,
+    })
+}
+
+/// ESPN's dates, which leave out the seconds: "2026-10-16T19:00Z".
+fn kickoff(date: &str) -> Result<DateTime<Utc>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(date) {
+        return Ok(at.with_timezone(&Utc));
+    }
+    NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%MZ")
+        .map(|at| at.and_utc())
+        .map_err(|_| anyhow!("unreadable date {date}"))
+}
@@ -0,0 +1,361 @@
+//! Prices of stocks, funds and crypto from Yahoo Finance's chart API, with a month's
+//! trend drawn as a sparkline. Every quote fetched is kept, so when the service is
+//! slow or down the section prints the last one known, marked with when it was from,
+//! rather than holding up the printout.
+
+use anyhow::{Context as _, Result, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::{Local, NaiveDateTime, TimeDelta, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tracing::warn;
+
+use super::{Context, Fetch, Integration};
+use crate::compose::Section;
+use crate::db;
+use crate::document::{Align, Block};
+use crate::render::sparkline;
+use crate::schema::stock_quotes;
+
+const DEFAULT_API: &str = "https://query1.finance.yahoo.com";
+/// Longest wait for one ticker before its cached quote is used instead.
+const TIMEOUT: Duration = Duration::from_secs(8);
+
+pub struct Stocks;
+
+/// A ticker's latest price and its daily closes over the past month.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Quote {
+    symbol: String,
+    #[serde(default)]
+    currency: Option<String>,
+    price: f64,
+    previous_close: f64,
+    /// Daily closes, oldest first, ending with the latest session.
+    closes: Vec<f64>,
+}
+
+/// One ticker as fetched: its quote, if any is known, and whether it came from the
+/// cache.
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    symbol: String,
+    quote: Option<Quote>,
+    /// When the cached quote was fetched, if the service couldn't be reached now.
+    #[serde(default)]
+    stale_since: Option<NaiveDateTime>,
+}
+
+impl Integration for Stocks {
+    fn slug(&self) -> &'static str {
+        "stocks"
+    }
+
+    fn name(&self) -> &'static str {
+        "Stocks and crypto"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "required": ["tickers"],
+            "properties": {
+                "title": { "type": "string", "default": "Markets" },
+                "tickers": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Yahoo Finance symbols, such as AAPL, ^GSPC, VWRL.AS or BTC-USD"
+                },
+                "sparklines": { "type": "boolean", "default": true },
+                "api_url": {
+                    "type": "string",
+                    "format": "uri",
+                    "default": DEFAULT_API,
+                    "description": "Yahoo Finance or a compatible proxy"
+                }
+            }
+        })
+    }
+
+    fn fetch<'a>(&'a self, _ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        let tickers: Vec<String> = settings
+            .get("tickers")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(|t| t.trim().to_uppercase())
+            .filter(|t| !t.is_empty())
+            .collect();
+        let api = settings
+            .get("api_url")
+            .and_then(Value::as_str)
+            .unwrap_or(DEFAULT_API)
+            .trim_end_matches('/')
+            .to_string();
+        Box::pin(async move {
+            let client = reqwest::Client::builder()
+                .timeout(TIMEOUT)
+                .user_agent("Mozilla/5.0 (compatible; dayroll)")
+                .build()?;
+            let mut downloads = JoinSet::new();
+            for (i, symbol) in tickers.iter().enumerate() {
+                let (client, api, symbol) = (client.clone(), api.clone(), symbol.clone());
+                downloads.spawn(async move { (i, download(&client, &api, &symbol).await) });
+            }
+            let mut fresh = vec![None; tickers.len()];
+            while let Some(done) = downloads.join_next().await {
+                let (i, result) = done?;
+                match result {
+                    Ok(quote) => fresh[i] = Some(quote),
+                    Err(e) => warn!("stocks: {}: {e:#}", tickers[i]),
+                }
+            }
+
+            let entries = db::run_blocking_db(move |conn| {
+                let mut entries = Vec::new();
+                for (symbol, quote) in tickers.into_iter().zip(fresh) {
+                    let entry = match quote {
+                        Some(quote) => {
+                            store(conn, &quote)?;
+                            Entry {
+                                symbol,
+                                quote: Some(quote),
+                                stale_since: None,
+                            }
+                        }
+                        None => match cached(conn, &symbol)? {
+                            Some((quote, fetched_at)) => Entry {
+                                symbol,
+                                quote: Some(quote),
+                                stale_since: Some(fetched_at),
+                            },
+                            None => Entry {
+                                symbol,
+                                quote: None,
+                                stale_since: None,
+                            },
+                        },
+                    };
+                    entries.push(entry);
+                }
+                Ok(entries)
+            })
+            .await?;
+            Ok(serde_json::to_value(entries)?)
+        })
+    }
+
+    fn render(&self, _ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        let entries: Vec<Entry> = serde_json::from_value(data.clone()).unwrap_or_default();
+        let mut lines = Vec::new();
+        let mut summary = Vec::new();
+        for entry in &entries {
+            let Some(quote) = &entry.quote else {
+                lines.push(format!("{} unavailable", entry.symbol));
+                continue;
+            };
+            let change = quote.price - quote.previous_close;
+            let percent = if quote.previous_close == 0.0 {
+                0.0
+            } else {
+                change / quote.previous_close * 100.0
+            };
+            let currency = quote
+                .currency
+                .as_deref()
+                .map(|c| format!(" {c}"))
+                .unwrap_or_default();
+            lines.push(format!(
+                "{} {}{currency} {}{} ({percent:+.2}%)",
+                quote.symbol,
+                price(quote.price),
+                if change < 0.0 { "-" } else { "+" },
+                price(change.abs()),
+            ));
+            // A quote from the cache says how old it is.
+            let as_of = entry
+                .stale_since
+                .map(|at| {
+                    let at = at.and_utc().with_timezone(&Local);
+                    format!(", as of {}", at.format("%a %H:%M"))
+                })
+                .unwrap_or_default();
+            lines.push(format!(
+                "  prev close {}{as_of}",
+                price(quote.previous_close)
+            ));
+            summary.push(format!("{} {percent:+.1}%", quote.symbol));
+        }
+        if lines.is_empty() {
+            lines.push("No tickers".into());
+        }
+
+        let mut blocks = Vec::new();
+        let sparklines = settings
+            .get("sparklines")
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+        let series: Vec<(&str, &[f64])> = entries
+            .iter()
+            .filter_map(|e| e.quote.as_ref())
+            .filter(|q| q.closes.len() >= 2)
+            .map(|q| (q.symbol.as_str(), q.closes.as_slice()))
+            .collect();
+        if sparklines
+            && !series.is_empty()
+            && let Ok(png) = sparkline::png(&series)
+        {
+            blocks.push(Block::Image {
+                data: BASE64.encode(png),
+                align: Align::Center,
+            });
+        }
+
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("Markets")
+                .into(),
+            priority: 0,
+            lines,
+            blocks,
+            summary: (!summary.is_empty()).then(|| summary.join(", ")),
+        }
+    }
+
+    fn cache_ttl(&self) -> TimeDelta {
+        TimeDelta::minutes(5)
+    }
+}
+
+/// Two decimals, or four for prices under 1 such as many coins'.
+fn price(value: f64) -> String {
+    if value.abs() < 1.0 && value != 0.0 {
+        format!("{value:.4}")
+    } else {
+        format!("{value:.2}")
+    }
+}
+
+#[derive(Deserialize)]
+struct ChartResponse {
+    chart: Chart,
+}
+
+#[derive(Deserialize)]
+struct Chart {
+    #[serde(default)]
+    result: Option<Vec<ChartResult>>,
+    #[serde(default)]
+    error: Option<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChartResult {
+    meta: ChartMeta,
+    #[serde(default)]
+    indicators: Indicators,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChartMeta {
+    #[serde(default)]
+    currency: Option<String>,
+    #[serde(default)]
+    regular_market_price: Option<f64>,
+}
+
+#[derive(Default, Deserialize)]
+struct Indicators {
+    #[serde(default)]
+    quote: Vec<Closes>,
+}
+
+#[derive(Deserialize)]
+struct Closes {
+    /// Missing sessions are null.
+    #[serde(default)]
+    close: Vec<Option<f64>>,
+}
+
+async fn download(client: &reqwest::Client, api: &str, symbol: &str) -> Result<Quote> {
+    let url = format!("{api}/v8/finance/chart/{symbol}");
+    let response: ChartResponse = client
+        .get(&url)
+        .query(&[("range", "1mo"), ("interval", "1d")])
+        .send()
+        .await?
+        .error_for_status()
+        .with_context(|| format!("fetching {url}"))?
+        .json()
+        .await?;
+    let result = response
+        .chart
+        .result
+        .and_then(|results| results.into_iter().next())
+        .ok_or_else(|| match response.chart.error {
+            Some(error) => anyhow!("no quote: {error}"),
+            None => anyhow!("no quote"),
+        })?;
+    let closes: Vec<f64> = result
+        .indicators
+        .quote
+        .into_iter()
+        .next()
+        .map(|q| q.close.into_iter().flatten().collect())
+        .unwrap_or_default();
+    let price = result
+        .meta
+        .regular_market_price
+        .or_else(|| closes.last().copied())
+        .ok_or_else(|| anyhow!("no price"))?;
+    // The last close is the latest session's, so the one before it is the
+    // previous close.
+    let previous_close = closes.len().checked_sub(2).map_or(price, |i| closes[i]);
+    Ok(Quote {
+        symbol: symbol.to_string(),
+        currency: result.meta.currency,
+        price,
+        previous_close,
+        closes,
+    })
+}
+
+fn store(conn: &mut SqliteConnection, quote: &Quote) -> Result<()> {
+    let data = serde_json::to_string(quote)?;
+    let now = Utc::now().naive_utc();
+    diesel::insert_into(stock_quotes::table)
+        .values((
+            stock_quotes::symbol.eq(&quote.symbol),
+            stock_quotes::data.eq(&data),
+            stock_quotes::fetched_at.eq(now),
+        ))
+        .on_conflict(stock_quotes::symbol)
+        .do_update()
+        .set((
+            stock_quotes::data.eq(&data),
+            stock_quotes::fetched_at.eq(now),
+        ))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// The last quote fetched for `symbol` and when it was fetched.
+fn cached(conn: &mut SqliteConnection, symbol: &str) -> Result<Option<(Quote, NaiveDateTime)>> {
+    let row: Option<(String, NaiveDateTime)> = stock_quotes::table
+        .find(symbol)
+        .select((stock_quotes::data, stock_quotes::fetched_at))
+        .first(conn)
+        .optional()?;
+    row.map(|(data, fetched_at)| Ok((serde_json::from_str(&data)?, fetched_at)))
+        .transpose()
+}
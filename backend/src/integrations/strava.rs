@@ -0,0 +1,492 @@
+//! Yesterday's activities and the past week's totals from Strava. Instances sign
+//! in to the athlete's account with OAuth; the tokens are kept, encrypted, in the
+//! server's database and refreshed as they expire.
+
+use anyhow::{Context as _, Result, anyhow};
+use chrono::{DateTime, Days, NaiveDate, NaiveDateTime, TimeDelta, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use super::{Check, Connect, Context, CredentialsRefused, Fetch, Integration};
+use crate::compose::Section;
+use crate::schema::strava_tokens;
+use crate::{db, secrets};
+
+const DEFAULT_URL: &str = "https://www.strava.com";
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Days the totals add up, ending yesterday.
+const WEEK: u64 = 7;
+
+pub struct Strava;
+
+/// An activity as fetched.
+#[derive(Debug, Serialize, Deserialize)]
+struct Activity {
+    name: String,
+    /// Strava's sport, such as `Run`, `Ride` or `Swim`.
+    sport: String,
+    /// Start, in the athlete's time zone.
+    start: NaiveDateTime,
+    /// Metres.
+    distance: f64,
+    /// Seconds spent moving.
+    moving_time: i64,
+    /// Metres climbed.
+    elevation: f64,
+}
+
+impl Activity {
+    /// Sports whose speed reads better as a pace per kilometre or mile.
+    fn is_paced(&self) -> bool {
+        matches!(
+            self.sport.as_str(),
+            "Run" | "TrailRun" | "VirtualRun" | "Walk" | "Hike"
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Units {
+    Metric,
+    Imperial,
+}
+
+impl Units {
+    fn distance(self, metres: f64) -> String {
+        match self {
+            Units::Metric => format!("{:.1} km", metres / 1000.0),
+            Units::Imperial => format!("{:.1} mi", metres / 1609.344),
+        }
+    }
+
+    fn elevation(self, metres: f64) -> String {
+        match self {
+            Units::Metric => format!("{metres:.0} m up"),
+            Units::Imperial => format!("{:.0} ft up", metres * 3.28084),
+        }
+    }
+
+    /// Pace for paced sports, speed for the rest.
+    fn speed(self, activity: &Activity) -> Option<String> {
+        if activity.distance <= 0.0 || activity.moving_time <= 0 {
+            return None;
+        }
+        let (unit, name) = match self {
+            Units::Metric => (1000.0, "km"),
+            Units::Imperial => (1609.344, "mi"),
+        };
+        let seconds = activity.moving_time as f64;
+        if activity.is_paced() {
+            let pace = (seconds / (activity.distance / unit)).round() as i64;
+            Some(format!("{}:{:02} /{name}", pace / 60, pace % 60))
+        } else {
+            let per_hour = activity.distance / unit / (seconds / 3600.0);
+            let name = if name == "km" { "km/h" } else { "mph" };
+            Some(format!("{per_hour:.1} {name}"))
+        }
+    }
+}
+
+/// "1:02:05", or "43:40" under an hour.
+fn duration(seconds: i64) -> String {
+    let (h, m, s) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{m}:{s:02}")
+    }
+}
+
+impl Integration for Strava {
+    fn slug(&self) -> &'static str {
+        "strava"
+    }
+
+    fn name(&self) -> &'static str {
+        "Strava activities"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "required": ["client_id", "client_secret"],
+            "properties": {
+                "title": { "type": "string", "default": "Fitness" },
+                "client_id": {
+                    "type": "string",
+                    "description": "Client ID of your API application at strava.com/settings/api"
+                },
+                "client_secret": { "type": "string", "format": "password" },
+                "athlete_id": {
+                    "type": "integer",
+                    "description": "Set by connecting the instance to Strava"
+                },
+                "units": { "type": "string", "enum": ["metric", "imperial"], "default": "metric" },
+                "api_url": {
+                    "type": "string",
+                    "format": "uri",
+                    "default": DEFAULT_URL,
+                    "description": "Strava, or a server answering its OAuth and API paths"
+                }
+            }
+        })
+    }
+
+    fn fetch<'a>(&'a self, ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        Box::pin(async move {
+            let athlete_id = settings
+                .get("athlete_id")
+                .and_then(Value::as_i64)
+                .context("not connected to Strava yet")?;
+            let client = client()?;
+            let token = access_token(&client, settings, athlete_id).await?;
+
+            let from = ctx
+                .date
+                .checked_sub_days(Days::new(WEEK))
+                .unwrap_or(ctx.date);
+            // A day early, since the query is in UTC and the days are the athlete's.
+            let after = from.checked_sub_days(Days::new(1)).unwrap_or(from);
+            let url = format!("{}/api/v3/athlete/activities", base(settings));
+            let activities: Vec<StravaActivity> = client
+                .get(&url)
+                .bearer_auth(token)
+                .query(&[
+                    ("after", midnight(after).to_string()),
+                    ("before", midnight(ctx.date).to_string()),
+                    ("per_page", "100".into()),
+                ])
+                .send()
+                .await?
+                .error_for_status()
+                .with_context(|| format!("fetching {url}"))?
+                .json()
+                .await?;
+            let mut activities: Vec<Activity> = activities
+                .into_iter()
+                .map(|a| Activity {
+                    name: a.name,
+                    sport: a.sport_type.or(a.kind).unwrap_or_default(),
+                    start: a.start_date_local.naive_utc(),
+                    distance: a.distance,
+                    moving_time: a.moving_time,
+                    elevation: a.total_elevation_gain,
+                })
+                .filter(|a| (from..ctx.date).contains(&a.start.date()))
+                .collect();
+            activities.sort_by_key(|a| a.start);
+            Ok(serde_json::to_value(activities)?)
+        })
+    }
+
+    fn render(&self, ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        let activities: Vec<Activity> = serde_json::from_value(data.clone()).unwrap_or_default();
+        let units = match settings.get("units").and_then(Value::as_str) {
+            Some("imperial") => Units::Imperial,
+            _ => Units::Metric,
+        };
+        let yesterday = ctx.date.checked_sub_days(Days::new(1)).unwrap_or(ctx.date);
+        let mut lines = Vec::new();
+        let yesterdays: Vec<&Activity> = activities
+            .iter()
+            .filter(|a| a.start.date() == yesterday)
+            .collect();
+        if yesterdays.is_empty() {
+            lines.push("Rest day yesterday".to_string());
+        } else {
+            lines.push("Yesterday:".to_string());
+            for activity in &yesterdays {
+                lines.push(format!("  {} ({})", activity.name, activity.sport));
+                let details: Vec<String> = [
+                    (activity.distance > 0.0).then(|| units.distance(activity.distance)),
+                    units.speed(activity),
+                    (activity.elevation >= 1.0).then(|| units.elevation(activity.elevation)),
+                    Some(duration(activity.moving_time)),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                lines.push(format!("  {}", details.join(", ")));
+            }
+        }
+
+        // Count, distance, time and climb per sport.
+        let mut totals: BTreeMap<&str, (usize, f64, i64, f64)> = BTreeMap::new();
+        for activity in &activities {
+            let total = totals.entry(&activity.sport).or_default();
+            total.0 += 1;
+            total.1 += activity.distance;
+            total.2 += activity.moving_time;
+            total.3 += activity.elevation;
+        }
+        lines.push(format!("Last {WEEK} days:"));
+        if totals.is_empty() {
+            lines.push("  No activities".into());
+        }
+        for (sport, (count, distance, time, elevation)) in &totals {
+            let mut line = format!("  {sport} x{count}");
+            if *distance > 0.0 {
+                line.push_str(&format!(", {}", units.distance(*distance)));
+            }
+            line.push_str(&format!(", {}", duration(*time)));
+            if *elevation >= 1.0 {
+                line.push_str(&format!(", {}", units.elevation(*elevation)));
+            }
+            lines.push(line);
+        }
+
+        let summary = match yesterdays.first() {
+            Some(first) if first.distance > 0.0 => Some(format!(
+                "{} {}",
+                first.sport,
+                units.distance(first.distance)
+            )),
+            Some(first) => Some(format!("{} {}", first.sport, duration(first.moving_time))),
+            None => Some("Rest day".into()),
+        };
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("Fitness")
+                .into(),
+            priority: 0,
+            lines,
+            blocks: Vec::new(),
+            summary,
+        }
+    }
+
+    fn cache_ttl(&self) -> TimeDelta {
+        TimeDelta::minutes(30)
+    }
+
+    fn check_credentials<'a>(&'a self, settings: &'a Map<String, Value>) -> Option<Check<'a>> {
+        Some(Box::pin(async move {
+            let athlete_id = settings
+                .get("athlete_id")
+                .and_then(Value::as_i64)
+                .ok_or_else(|| CredentialsRefused("not connected to Strava yet".into()))?;
+            let client = client()?;
+            // Refreshes the access token if it's expiring, which is what stops working
+            // when the athlete revokes access.
+            let token = access_token(&client, settings, athlete_id).await?;
+            let url = format!("{}/api/v3/athlete", base(settings));
+            let response = client.get(&url).bearer_auth(token).send().await?;
+            if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+                return Err(CredentialsRefused(
+                    "Strava refused the access token; connect the instance again".into(),
+                )
+                .into());
+            }
+            response
+                .error_for_status()
+                .with_context(|| format!("fetching {url}"))?;
+            Ok(())
+        }))
+    }
+
+    fn authorize_url(
+        &self,
+        settings: &Map<String, Value>,
+        redirect: &str,
+        state: &str,
+    ) -> Option<String> {
+        let client_id = settings.get("client_id").and_then(Value::as_str)?;
+        let url = reqwest::Url::parse_with_params(
+            &format!("{}/oauth/authorize", base(settings)),
+            &[
+                ("client_id", client_id),
+                ("response_type", "code"),
+                ("redirect_uri", redirect),
+                ("approval_prompt", "auto"),
+                ("scope", "activity:read_all"),
+                ("state", state),
+            ],
+        )
+        .ok()?;
+        Some(url.into())
+    }
+
+    fn connect<'a>(
+        &'a self,
+        settings: &'a Map<String, Value>,
+        code: &'a str,
+        _redirect: &'a str,
+    ) -> Connect<'a> {
+        Box::pin(async move {
+            let tokens = request_tokens(
+                &client()?,
+                settings,
+                &[("grant_type", "authorization_code"), ("code", code)],
+            )
+            .await?;
+            let athlete_id = tokens
+                .athlete
+                .as_ref()
+                .map(|a| a.id)
+                .ok_or_else(|| anyhow!("Strava didn't say which athlete signed in"))?;
+            store(athlete_id, tokens).await?;
+            let mut changes = Map::new();
+            changes.insert("athlete_id".into(), athlete_id.into());
+            Ok(changes)
+        })
+    }
+}
+
+fn base(settings: &Map<String, Value>) -> &str {
+    settings
+        .get("api_url")
+        .and_then(Value::as_str)
+        .unwrap_or(DEFAULT_URL)
+        .trim_end_matches('/')
+}
+
+fn client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder().timeout(TIMEOUT).build()?)
+}
+
+/// Seconds since the epoch at the start of `date`, in UTC.
+fn midnight(date: NaiveDate) -> i64 {
+    date.and_time(Default::default()).and_utc().timestamp()
+}
+
+#[derive(Deserialize)]
+struct StravaActivity {
+    name: String,
+    #[serde(default)]
+    sport_type: Option<String>,
+    #[serde(default, rename = "type")]
+    kind: Option<String>,
+    /// The athlete's wall-clock time, though marked as UTC.
+    start_date_local: DateTime<Utc>,
+    #[serde(default)]
+    distance: f64,
+    #[serde(default)]
+    moving_time: i64,
+    #[serde(default)]
+    total_elevation_gain: f64,
+}
+
+#[derive(Deserialize)]
+struct Tokens {
+    access_token: String,
+    refresh_token: String,
+    /// Seconds since the epoch.
+    expires_at: i64,
+    #[serde(default)]
+    athlete: Option<Athlete>,
+}
+
+#[derive(Deserialize)]
+struct Athlete {
+    id: i64,
+}
+
+/// Ask Strava's token endpoint for tokens with the instance's client credentials
+/// and a `grant`.
+async fn request_tokens(
+    client: &reqwest::Client,
+    settings: &Map<String, Value>,
+    grant: &[(&str, &str)],
+) -> Result<Tokens> {
+    let text = |name: &str| {
+        settings
+            .get(name)
+            .and_then(Value::as_str)
+            .with_context(|| format!("{name} is not set"))
+    };
+    let mut form = vec![
+        ("client_id", text("client_id")?),
+        ("client_secret", text("client_secret")?),
+    ];
+    form.extend_from_slice(grant);
+    let url = format!("{}/oauth/token", base(settings));
+    let response = client.post(&url).form(&form).send().await?;
+    if response.status() == reqwest::StatusCode::BAD_REQUEST
+        || response.status() == reqwest::StatusCode::UNAUTHORIZED
+    {
+        return Err(CredentialsRefused(
+            "Strava refused the sign-in; connect the instance again".into(),
+        )
+        .into());
+    }
+    Ok(response
+        .error_for_status()
+        .with_context(|| format!("fetching {url}"))?
+        .json()
+        .await?)
+}
+
+/// A current access token for the athlete, refreshing the kept one if it is about
+/// to expire.
+async fn access_token(
+    client: &reqwest::Client,
+    settings: &Map<String, Value>,
+    athlete_id: i64,
+) -> Result<String> {
+    let row: Option<(String, String, NaiveDateTime)> = db::run_blocking_db(move |conn| {
+        Ok(strava_tokens::table
+            .find(athlete_id)
+            .select((
+                strava_tokens::refresh_token,
+                strava_tokens::access_token,
+                strava_tokens::expires_at,
+            ))
+            .first(conn)
+            .optional()?)
+    })
+    .await?;
+    let (refresh_token, access_token, expires_at) = row.ok_or_else(|| {
+        CredentialsRefused("not connected to Strava; connect the instance again".into())
+    })?;
+    if expires_at - Utc::now().naive_utc() > TimeDelta::minutes(5) {
+        return secrets::open(&access_token);
+    }
+    let refresh_token = secrets::open(&refresh_token)?;
+    let tokens = request_tokens(
+        client,
+        settings,
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &refresh_token),
+        ],
+    )
+    .await?;
+    let access_token = tokens.access_token.clone();
+    store(athlete_id, tokens).await?;
+    Ok(access_token)
+}
+
+/// Keep the athlete's tokens, encrypted. Strava may hand out a new refresh token
+/// with each access token, and only the latest one works.
+async fn store(athlete_id: i64, tokens: Tokens) -> Result<()> {
+    let refresh_token = secrets::seal(&tokens.refresh_token)?;
+    let access_token = secrets::seal(&tokens.access_token)?;
+    let expires_at = DateTime::from_timestamp(tokens.expires_at, 0)
+        .unwrap_or_default()
+        .naive_utc();
+    db::run_blocking_db(move |conn| {
+        diesel::insert_into(strava_tokens::table)
+            .values((
+                strava_tokens::athlete_id.eq(athlete_id),
+                strava_tokens::refresh_token.eq(&refresh_token),
+                strava_tokens::access_token.eq(&access_token),
+                strava_tokens::expires_at.eq(expires_at),
+            ))
+            .on_conflict(strava_tokens::athlete_id)
+            .do_update()
+            .set((
+                strava_tokens::refresh_token.eq(&refresh_token),
+                strava_tokens::access_token.eq(&access_token),
+                strava_tokens::expires_at.eq(expires_at),
+            ))
+            .execute(conn)?;
+        Ok(())
+    })
+    .await
+}
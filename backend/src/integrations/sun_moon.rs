@@ -0,0 +1,301 @@
+//! Sunrise, sunset, the length of the day and the moon's phase, worked out from the
+//! coordinates without going online. Without a title the section prints under the
+//! date at the top of the digest.
+
+use anyhow::{Context as _, Result, bail};
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::f64::consts::PI;
+
+use super::ical::Zone;
+use super::{Context, Fetch, Integration};
+use crate::compose::Section;
+use crate::document::Block;
+
+pub struct SunMoon;
+
+/// Julian day of 2000-01-01 12:00 UTC.
+const J2000: f64 = 2_451_545.0;
+/// Julian day of the Unix epoch.
+const UNIX_EPOCH: f64 = 2_440_587.5;
+/// Julian day of a new moon, 2000-01-06 18:14 UTC.
+const NEW_MOON: f64 = 2_451_550.26;
+/// Mean days from one new moon to the next.
+const SYNODIC_MONTH: f64 = 29.530_588_853;
+const OBLIQUITY: f64 = 23.4397;
+
+/// The sky over the coordinates on one day, as fetched.
+#[derive(Debug, Serialize, Deserialize)]
+struct Sky {
+    sun: Sun,
+    /// Seconds of daylight more than the day before; negative when shorter.
+    change: Option<i64>,
+    moon: Phase,
+    /// Share of the moon's disc that is lit, 0 to 1.
+    illumination: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Sun {
+    Rises {
+        sunrise: NaiveTime,
+        sunset: NaiveTime,
+        /// Seconds from sunrise to sunset.
+        length: i64,
+    },
+    /// Polar day.
+    UpAllDay,
+    /// Polar night.
+    DownAllDay,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Phase {
+    New,
+    WaxingCrescent,
+    FirstQuarter,
+    WaxingGibbous,
+    Full,
+    WaningGibbous,
+    LastQuarter,
+    WaningCrescent,
+}
+
+impl Phase {
+    const ALL: [Phase; 8] = [
+        Phase::New,
+        Phase::WaxingCrescent,
+        Phase::FirstQuarter,
+        Phase::WaxingGibbous,
+        Phase::Full,
+        Phase::WaningGibbous,
+        Phase::LastQuarter,
+        Phase::WaningCrescent,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Phase::New => "New moon",
+            Phase::WaxingCrescent => "Waxing crescent",
+            Phase::FirstQuarter => "First quarter",
+            Phase::WaxingGibbous => "Waxing gibbous",
+            Phase::Full => "Full moon",
+            Phase::WaningGibbous => "Waning gibbous",
+            Phase::LastQuarter => "Last quarter",
+            Phase::WaningCrescent => "Waning crescent",
+        }
+    }
+
+    /// Built-in icon drawing the phase.
+    fn icon(&self) -> &'static str {
+        match self {
+            Phase::New => "moon-new",
+            Phase::WaxingCrescent => "moon-waxing-crescent",
+            Phase::FirstQuarter => "moon-first-quarter",
+            Phase::WaxingGibbous => "moon-waxing-gibbous",
+            Phase::Full => "moon-full",
+            Phase::WaningGibbous => "moon-waning-gibbous",
+            Phase::LastQuarter => "moon-last-quarter",
+            Phase::WaningCrescent => "moon-waning-crescent",
+        }
+    }
+}
+
+impl Integration for SunMoon {
+    fn slug(&self) -> &'static str {
+        "sun_moon"
+    }
+
+    fn name(&self) -> &'static str {
+        "Sun and moon"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "latitude": { "type": "number", "minimum": -90, "maximum": 90 },
+                "longitude": { "type": "number", "minimum": -180, "maximum": 180 },
+                "timezone": {
+                    "type": "string",
+                    "description": "IANA time zone the times are printed in; the server's when unset"
+                },
+                "title": {
+                    "type": "string",
+                    "default": "",
+                    "description": "Print as a section of its own under this title instead of under the date"
+                }
+            },
+            "required": ["latitude", "longitude"]
+        })
+    }
+
+    fn fetch<'a>(&'a self, ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        Box::pin(async move {
+            let latitude = coordinate(settings, "latitude", 90.0)?;
+            let longitude = coordinate(settings, "longitude", 180.0)?;
+            let zone = match settings.get("timezone").and_then(Value::as_str) {
+                Some(name) => Zone::Named(
+                    name.parse()
+                        .map_err(|_| anyhow::anyhow!("unknown time zone {name}"))?,
+                ),
+                None => Zone::Local,
+            };
+            let today = sun(ctx.date, latitude, longitude, zone);
+            let yesterday = ctx
+                .date
+                .checked_sub_days(Days::new(1))
+                .map(|day| sun(day, latitude, longitude, zone));
+            let change = match (today, yesterday) {
+                (Sun::Rises { length, .. }, Some(Sun::Rises { length: before, .. })) => {
+                    Some(length - before)
+                }
+                _ => None,
+            };
+            let age = moon_age(ctx.date);
+            Ok(serde_json::to_value(Sky {
+                sun: today,
+                change,
+                moon: Phase::ALL[(age / SYNODIC_MONTH * 8.0).round() as usize % 8],
+                illumination: (1.0 - (2.0 * PI * age / SYNODIC_MONTH).cos()) / 2.0,
+            })?)
+        })
+    }
+
+    fn render(&self, _ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        let Ok(sky) = serde_json::from_value::<Sky>(data.clone()) else {
+            return Section {
+                integration: self.slug().into(),
+                title: String::new(),
+                priority: 0,
+                lines: Vec::new(),
+                blocks: Vec::new(),
+                summary: None,
+            };
+        };
+        let glyph = |name: &str, text: String| Block::Glyph {
+            name: name.into(),
+            text,
+        };
+        let mut blocks = Vec::new();
+        let summary = match sky.sun {
+            Sun::Rises {
+                sunrise,
+                sunset,
+                length,
+            } => {
+                blocks.push(glyph(
+                    "sunrise",
+                    format!("Sunrise {}", sunrise.format("%H:%M")),
+                ));
+                blocks.push(glyph(
+                    "sunset",
+                    format!("Sunset {}", sunset.format("%H:%M")),
+                ));
+                let change = match sky.change {
+                    Some(change) => format!(" ({})", signed(change)),
+                    None => String::new(),
+                };
+                blocks.push(glyph("sun", format!("Daylight {}{change}", hours(length))));
+                format!("Sun {}-{}", sunrise.format("%H:%M"), sunset.format("%H:%M"))
+            }
+            Sun::UpAllDay => {
+                blocks.push(glyph("sun", "Sun up all day".into()));
+                "Sun up all day".into()
+            }
+            Sun::DownAllDay => {
+                blocks.push(glyph("sun", "Sun down all day".into()));
+                "Sun down all day".into()
+            }
+        };
+        blocks.push(glyph(
+            sky.moon.icon(),
+            format!("{}, {:.0}% lit", sky.moon.name(), sky.illumination * 100.0),
+        ));
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .into(),
+            priority: 0,
+            lines: Vec::new(),
+            blocks,
+            summary: Some(summary),
+        }
+    }
+}
+
+pub(super) fn coordinate(settings: &Map<String, Value>, key: &str, max: f64) -> Result<f64> {
+    let value = settings
+        .get(key)
+        .and_then(Value::as_f64)
+        .with_context(|| format!("{key} is not set"))?;
+    if !(-max..=max).contains(&value) {
+        bail!("{key} must be between -{max} and {max}");
+    }
+    Ok(value)
+}
+
+/// Sunrise and sunset on `date` at the coordinates, by the NOAA sunrise equation,
+/// which is good to a minute or so away from the poles.
+fn sun(date: NaiveDate, latitude: f64, longitude: f64, zone: Zone) -> Sun {
+    let noon = julian(date) - longitude / 360.0;
+    let anomaly = (357.5291 + 0.985_600_28 * (noon - J2000)).rem_euclid(360.0);
+    let m = anomaly.to_radians();
+    let center = 1.9148 * m.sin() + 0.02 * (2.0 * m).sin() + 0.0003 * (3.0 * m).sin();
+    let ecliptic = (anomaly + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit = noon + 0.0053 * m.sin() - 0.0069 * (2.0 * ecliptic).sin();
+    let declination = (ecliptic.sin() * OBLIQUITY.to_radians().sin()).asin();
+    // -0.833° allows for refraction and the sun's disc.
+    let latitude = latitude.to_radians();
+    let cos_hour = ((-0.833f64).to_radians().sin() - latitude.sin() * declination.sin())
+        / (latitude.cos() * declination.cos());
+    if cos_hour < -1.0 {
+        return Sun::UpAllDay;
+    }
+    if cos_hour > 1.0 {
+        return Sun::DownAllDay;
+    }
+    let half = cos_hour.acos().to_degrees() / 360.0;
+    let time = |day: f64| {
+        let utc = DateTime::<Utc>::from_timestamp(((day - UNIX_EPOCH) * 86_400.0) as i64, 0)
+            .unwrap_or_default();
+        zone.local(utc).time()
+    };
+    Sun::Rises {
+        sunrise: time(transit - half),
+        sunset: time(transit + half),
+        length: (2.0 * half * 86_400.0).round() as i64,
+    }
+}
+
+/// Days since the last new moon at noon UTC on `date`.
+fn moon_age(date: NaiveDate) -> f64 {
+    (julian(date) - NEW_MOON).rem_euclid(SYNODIC_MONTH)
+}
+
+/// Julian day of noon UTC on `date`.
+fn julian(date: NaiveDate) -> f64 {
+    let days = (date - NaiveDate::from_ymd_opt(2000, 1, 1).unwrap_or_default()).num_days();
+    J2000 + days as f64
+}
+
+/// `seconds` as "10h 42m".
+fn hours(seconds: i64) -> String {
+    let minutes = seconds / 60;
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+/// A change in day length as "+2m 14s" or "-1m 05s".
+fn signed(seconds: i64) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+    format!("{sign}{}m {:02}s", seconds / 60, seconds % 60)
+}
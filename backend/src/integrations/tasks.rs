@@ -0,0 +1,125 @@
+//! The open tasks from the built-in to-do list, as a checklist to tick off with a
+//! pen.
+
+use chrono::{Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use super::{Context, Fetch, Integration};
+use crate::compose::Section;
+use crate::{db, tasks};
+
+pub struct Tasks;
+
+/// An open task as fetched.
+#[derive(Debug, Serialize, Deserialize)]
+struct Item {
+    title: String,
+    due: Option<NaiveDate>,
+}
+
+impl Integration for Tasks {
+    fn slug(&self) -> &'static str {
+        "tasks"
+    }
+
+    fn name(&self) -> &'static str {
+        "To-do list"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string", "default": "To do" },
+                "days_ahead": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "default": 0,
+                    "description": "Also list tasks due this many days after the printout's"
+                },
+                "show_undated": { "type": "boolean", "default": true },
+                "user_id": {
+                    "type": "integer",
+                    "description": "Only this user's tasks; everyone's when unset"
+                }
+            }
+        })
+    }
+
+    fn fetch<'a>(&'a self, ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        let days_ahead = settings
+            .get("days_ahead")
+            .and_then(Value::as_u64)
+            .unwrap_or(0);
+        let show_undated = settings
+            .get("show_undated")
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+        let user_id = settings
+            .get("user_id")
+            .and_then(Value::as_i64)
+            .map(|id| id as i32);
+        let date = ctx.date;
+        Box::pin(async move {
+            let until = date.checked_add_days(Days::new(days_ahead)).unwrap_or(date);
+            let items: Vec<Item> = db::run_blocking_db(tasks::list)
+                .await?
+                .into_iter()
+                .filter(|task| user_id.is_none_or(|id| task.user_id == Some(id)))
+                .filter(|task| !task.is_done(date))
+                .filter_map(|task| {
+                    let due = task.due_on(date);
+                    let listed = match due {
+                        Some(due) => due <= until,
+                        None => show_undated,
+                    };
+                    listed.then_some(Item {
+                        title: task.title,
+                        due,
+                    })
+                })
+                .collect();
+            Ok(serde_json::to_value(items)?)
+        })
+    }
+
+    fn render(&self, ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        let items: Vec<Item> = serde_json::from_value(data.clone()).unwrap_or_default();
+        let mut lines: Vec<String> = items
+            .iter()
+            .map(|item| match item.due {
+                Some(due) if due < ctx.date => {
+                    format!("[ ] {} (due {})", item.title, due.format("%-d %b"))
+                }
+                Some(due) if due > ctx.date => {
+                    format!("[ ] {} ({})", item.title, due.format("%a %-d"))
+                }
+                _ => format!("[ ] {}", item.title),
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push("Nothing to do".into());
+        }
+        let overdue = items
+            .iter()
+            .filter(|i| i.due.is_some_and(|due| due < ctx.date))
+            .count();
+        let summary = match overdue {
+            0 => format!("{} open tasks", items.len()),
+            n => format!("{} open tasks, {n} overdue", items.len()),
+        };
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("To do")
+                .into(),
+            priority: 0,
+            lines,
+            blocks: Vec::new(),
+            summary: Some(summary),
+        }
+    }
+}
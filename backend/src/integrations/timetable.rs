@@ -0,0 +1,342 @@
+//! The day's lessons or shifts from a rotating timetable: weeks that take turns
+//! (week A, week B) or numbered days that cycle through the school days (day 1 to
+//! day 6). Holidays print instead of the timetable and don't move the rotation on.
+
+use anyhow::{Context as _, Result, bail};
+use chrono::{Datelike, Days, NaiveDate, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use super::{Context, Fetch, Integration};
+use crate::compose::Section;
+
+pub struct Timetable;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rotation {
+    /// Each week is the next of `cycle_length` lettered weeks.
+    Weeks,
+    /// Each school day is the next of `cycle_length` numbered days.
+    Days,
+}
+
+#[derive(Debug, Deserialize)]
+struct Holiday {
+    from: NaiveDate,
+    /// The last day off; just `from` when left out.
+    #[serde(default)]
+    to: Option<NaiveDate>,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Lesson {
+    #[serde(default)]
+    start: Option<NaiveTime>,
+    #[serde(default)]
+    end: Option<NaiveTime>,
+    name: String,
+    #[serde(default)]
+    location: Option<String>,
+}
+
+/// The day as fetched.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Day {
+    /// "Week A, Monday" or "Day 3"; unset on days off.
+    label: Option<String>,
+    /// Why there's no timetable, on holidays and days off.
+    off: Option<String>,
+    lessons: Vec<Lesson>,
+}
+
+/// How the configured school days, holidays and rotation fall on the calendar.
+struct Calendar {
+    rotation: Rotation,
+    length: usize,
+    start: NaiveDate,
+    school_days: Vec<Weekday>,
+    holidays: Vec<Holiday>,
+}
+
+impl Calendar {
+    fn holiday(&self, date: NaiveDate) -> Option<&Holiday> {
+        self.holidays
+            .iter()
+            .find(|h| h.from <= date && date <= h.to.unwrap_or(h.from))
+    }
+
+    fn is_school_day(&self, date: NaiveDate) -> bool {
+        self.school_days.contains(&date.weekday()) && self.holiday(date).is_none()
+    }
+
+    /// Where in the rotation `date` falls, from 0: the school days or the weeks
+    /// with any school day in them since the start.
+    fn position(&self, date: NaiveDate) -> usize {
+        let counted = match self.rotation {
+            Rotation::Days => self
+                .start
+                .iter_days()
+                .take_while(|d| *d < date)
+                .filter(|d| self.is_school_day(*d))
+                .count(),
+            Rotation::Weeks => {
+                let monday = |d: NaiveDate| d.week(Weekday::Mon).first_day();
+                let mut week = monday(self.start);
+                let mut weeks = 0;
+                while week < monday(date) {
+                    if week.iter_days().take(7).any(|d| self.is_school_day(d)) {
+                        weeks += 1;
+                    }
+                    week = week + Days::new(7);
+                }
+                weeks
+            }
+        };
+        counted % self.length
+    }
+
+    fn label(&self, date: NaiveDate) -> String {
+        let position = self.position(date);
+        match self.rotation {
+            Rotation::Weeks => format!("Week {}, {}", letter(position), weekday_name(date)),
+            Rotation::Days => format!("Day {}", position + 1),
+        }
+    }
+
+    /// Keys of `timetable` that `date` takes its lessons from, most specific first:
+    /// `A mon`, then `mon` and `A` for week rotations, or `3` for day rotations.
+    fn keys(&self, date: NaiveDate) -> Vec<String> {
+        let position = self.position(date);
+        let day = weekday_key(date.weekday());
+        match self.rotation {
+            Rotation::Weeks => {
+                let week = letter(position);
+                vec![format!("{week} {day}"), day.to_string(), week.to_string()]
+            }
+            Rotation::Days => vec![(position + 1).to_string()],
+        }
+    }
+}
+
+fn letter(position: usize) -> char {
+    char::from(b'A' + (position % 26) as u8)
+}
+
+fn weekday_key(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "mon",
+        Weekday::Tue => "tue",
+        Weekday::Wed => "wed",
+        Weekday::Thu => "thu",
+        Weekday::Fri => "fri",
+        Weekday::Sat => "sat",
+        Weekday::Sun => "sun",
+    }
+}
+
+fn weekday_name(date: NaiveDate) -> String {
+    date.format("%A").to_string()
+}
+
+impl Integration for Timetable {
+    fn slug(&self) -> &'static str {
+        "timetable"
+    }
+
+    fn name(&self) -> &'static str {
+        "Rotating timetable"
+    }
+
+    fn config_schema(&self) -> Value {
+        let lessons = json!({
+            "type": "array",
+            "items": {
+                "type": "object",
+                "required": ["name"],
+                "properties": {
+                    "start": { "type": "string", "description": "HH:MM" },
+                    "end": { "type": "string", "description": "HH:MM" },
+                    "name": { "type": "string" },
+                    "location": { "type": "string" }
+                }
+            }
+        });
+        json!({
+            "type": "object",
+            "required": ["start_date", "timetable"],
+            "properties": {
+                "title": { "type": "string", "default": "Timetable" },
+                "rotation": {
+                    "type": "string",
+                    "enum": ["weeks", "days"],
+                    "default": "weeks",
+                    "description": "Weeks that take turns (A, B) or numbered days that cycle through the school days"
+                },
+                "cycle_length": { "type": "integer", "minimum": 1, "maximum": 26, "default": 2 },
+                "start_date": {
+                    "type": "string",
+                    "description": "A day of week A, or day 1, as YYYY-MM-DD"
+                },
+                "school_days": {
+                    "type": "array",
+                    "items": { "type": "string", "enum": ["mon", "tue", "wed", "thu", "fri", "sat", "sun"] },
+                    "default": ["mon", "tue", "wed", "thu", "fri"]
+                },
+                "timetable": {
+                    "type": "object",
+                    "description": "Lessons by day: \"A mon\", \"mon\" or \"A\" for week rotations, \"1\" to \"6\" for day rotations",
+                    "additionalProperties": lessons
+                },
+                "holidays": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["from"],
+                        "properties": {
+                            "from": { "type": "string", "description": "YYYY-MM-DD" },
+                            "to": { "type": "string", "description": "Last day off, YYYY-MM-DD" },
+                            "name": { "type": "string" }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn fetch<'a>(&'a self, ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        Box::pin(async move {
+            let calendar = calendar(settings)?;
+            let date = ctx.date;
+            if date < calendar.start {
+                return Ok(serde_json::to_value(Day {
+                    off: Some(format!("Timetable starts {}", calendar.start)),
+                    ..Day::default()
+                })?);
+            }
+            if let Some(holiday) = calendar.holiday(date) {
+                return Ok(serde_json::to_value(Day {
+                    off: Some(holiday.name.clone().unwrap_or_else(|| "Holiday".into())),
+                    ..Day::default()
+                })?);
+            }
+            if !calendar.is_school_day(date) {
+                return Ok(serde_json::to_value(Day {
+                    off: Some("No lessons today".into()),
+                    ..Day::default()
+                })?);
+            }
+
+            let timetable = settings
+                .get("timetable")
+                .and_then(Value::as_object)
+                .context("timetable is not set")?;
+            let mut lessons: Vec<Lesson> = Vec::new();
+            for key in calendar.keys(date) {
+                if let Some(day) = timetable.get(&key) {
+                    lessons = serde_json::from_value(day.clone())
+                        .with_context(|| format!("timetable.{key} must be a list of lessons"))?;
+                    break;
+                }
+            }
+            lessons.sort_by_key(|l| l.start);
+            Ok(serde_json::to_value(Day {
+                label: Some(calendar.label(date)),
+                off: None,
+                lessons,
+            })?)
+        })
+    }
+
+    fn render(&self, _ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        let day: Day = serde_json::from_value(data.clone()).unwrap_or_default();
+        let mut lines = Vec::new();
+        if let Some(off) = &day.off {
+            lines.push(off.clone());
+        }
+        if let Some(label) = &day.label {
+            lines.push(label.clone());
+        }
+        for lesson in &day.lessons {
+            let time = match (lesson.start, lesson.end) {
+                (Some(start), Some(end)) => {
+                    format!("{}-{} ", start.format("%H:%M"), end.format("%H:%M"))
+                }
+                (Some(start), None) => format!("{} ", start.format("%H:%M")),
+                _ => String::new(),
+            };
+            let location = lesson
+                .location
+                .as_ref()
+                .map(|l| format!(" ({l})"))
+                .unwrap_or_default();
+            lines.push(format!("{time}{}{location}", lesson.name));
+        }
+        if day.label.is_some() && day.lessons.is_empty() {
+            lines.push("Nothing timetabled".into());
+        }
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("Timetable")
+                .into(),
+            priority: 0,
+            lines,
+            blocks: Vec::new(),
+            summary: day.off.or(day.label),
+        }
+    }
+}
+
+fn calendar(settings: &Map<String, Value>) -> Result<Calendar> {
+    let rotation = match settings.get("rotation").and_then(Value::as_str) {
+        Some("days") => Rotation::Days,
+        _ => Rotation::Weeks,
+    };
+    let start = settings
+        .get("start_date")
+        .and_then(Value::as_str)
+        .context("start_date is not set")?;
+    let start: NaiveDate = start
+        .parse()
+        .with_context(|| format!("start_date {start} is not a YYYY-MM-DD date"))?;
+    let school_days = match settings.get("school_days").and_then(Value::as_array) {
+        Some(days) => days
+            .iter()
+            .filter_map(Value::as_str)
+            .map(|d| {
+                d.parse::<Weekday>()
+                    .map_err(|_| anyhow::anyhow!("unknown school day {d}"))
+            })
+            .collect::<Result<Vec<_>>>()?,
+        None => vec![
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+        ],
+    };
+    if school_days.is_empty() {
+        bail!("school_days must not be empty");
+    }
+    let holidays: Vec<Holiday> = match settings.get("holidays") {
+        Some(holidays) => serde_json::from_value(holidays.clone())
+            .context("holidays must be a list of {from, to, name} with YYYY-MM-DD dates")?,
+        None => Vec::new(),
+    };
+    Ok(Calendar {
+        rotation,
+        length: settings
+            .get("cycle_length")
+            .and_then(Value::as_u64)
+            .unwrap_or(2)
+            .max(1) as usize,
+        start,
+        school_days,
+        holidays,
+    })
+}
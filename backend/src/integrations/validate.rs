@@ -0,0 +1,121 @@
+//! Checks instance settings against an integration's JSON Schema, covering the
+//! keywords the built-in schemas use: `type`, `properties`, `required`, `items`,
+//! `enum`, `minimum`, `maximum` and the `uri` format.
+
+use serde_json::{Map, Value};
+
+use crate::error::FieldError;
+
+/// Every way `settings` break `schema`; empty when they match it.
+pub fn validate(schema: &Value, settings: &Map<String, Value>) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    check_object(schema, settings, "", &mut errors);
+    errors
+}
+
+fn check(schema: &Value, value: &Value, path: &str, errors: &mut Vec<FieldError>) {
+    // An unset setting is left to `required`.
+    if value.is_null() {
+        return;
+    }
+    let mut fail = |message: String| {
+        errors.push(FieldError {
+            field: path.to_string(),
+            message,
+        })
+    };
+    if let Some(kind) = schema.get("type").and_then(Value::as_str)
+        && !is_type(value, kind)
+    {
+        let article = if kind.starts_with(['a', 'e', 'i', 'o', 'u']) {
+            "an"
+        } else {
+            "a"
+        };
+        fail(format!("must be {article} {kind}"));
+        return;
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array)
+        && !allowed.contains(value)
+    {
+        let allowed: Vec<String> = allowed
+            .iter()
+            .map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string))
+            .collect();
+        fail(format!("must be one of: {}", allowed.join(", ")));
+        return;
+    }
+    if let Some(n) = value.as_f64() {
+        if let Some(min) = schema.get("minimum").and_then(Value::as_f64)
+            && n < min
+        {
+            fail(format!("must be at least {min}"));
+        }
+        if let Some(max) = schema.get("maximum").and_then(Value::as_f64)
+            && n > max
+        {
+            fail(format!("must be at most {max}"));
+        }
+    }
+    if let Some(text) = value.as_str()
+        && schema.get("format").and_then(Value::as_str) == Some("uri")
+        && reqwest::Url::parse(text).is_err()
+    {
+        fail("must be a URL".into());
+    }
+    match value {
+        Value::Object(fields) => check_object(schema, fields, path, errors),
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(item_schema, item, &join(path, &i.to_string()), errors);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_object(
+    schema: &Value,
+    fields: &Map<String, Value>,
+    path: &str,
+    errors: &mut Vec<FieldError>,
+) {
+    let required = schema["required"].as_array().into_iter().flatten();
+    for name in required.filter_map(Value::as_str) {
+        if fields.get(name).is_none_or(Value::is_null) {
+            errors.push(FieldError {
+                field: join(path, name),
+                message: "is required".into(),
+            });
+        }
+    }
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+        for (name, value) in fields {
+            if let Some(property) = properties.get(name) {
+                check(property, value, &join(path, name), errors);
+            }
+        }
+    }
+}
+
+fn is_type(value: &Value, kind: &str) -> bool {
+    match kind {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => true,
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{path}.{name}")
+    }
+}
@@ -0,0 +1,132 @@
+//! A new word to learn each day, from a dictionary service or, without one or when
+//! it can't be reached, from a word list built into the server.
+
+use anyhow::{Context as _, Result};
+use chrono::{NaiveDate, TimeDelta};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use tracing::warn;
+
+use super::{Context, Fetch, Integration};
+use crate::compose::Section;
+
+/// Tab-separated word, pronunciation, part of speech and definition, one per line.
+static WORDS: &str = include_str!("../../assets/words/words.tsv");
+
+pub struct WordOfTheDay;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Word {
+    word: String,
+    #[serde(default)]
+    pronunciation: Option<String>,
+    #[serde(default)]
+    part_of_speech: Option<String>,
+    definition: String,
+}
+
+impl Integration for WordOfTheDay {
+    fn slug(&self) -> &'static str {
+        "word_of_the_day"
+    }
+
+    fn name(&self) -> &'static str {
+        "Word of the day"
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string", "default": "Word of the day" },
+                "api_url": {
+                    "type": "string",
+                    "format": "uri",
+                    "description": "Answers with {word, pronunciation, part_of_speech, definition}; {date} is replaced by the day. The built-in list is used when unset"
+                }
+            }
+        })
+    }
+
+    fn fetch<'a>(&'a self, ctx: &'a Context, settings: &'a Map<String, Value>) -> Fetch<'a> {
+        Box::pin(async move {
+            let word = match settings.get("api_url").and_then(Value::as_str) {
+                Some(url) => match download(url, ctx.date).await {
+                    Ok(word) => word,
+                    Err(e) => {
+                        warn!("word of the day: {e:#}; using the built-in list");
+                        bundled(ctx.date)
+                    }
+                },
+                None => bundled(ctx.date),
+            };
+            Ok(serde_json::to_value(word)?)
+        })
+    }
+
+    fn render(&self, _ctx: &Context, settings: &Map<String, Value>, data: &Value) -> Section {
+        let mut lines = Vec::new();
+        let mut summary = None;
+        if let Ok(word) = serde_json::from_value::<Word>(data.clone()) {
+            let details: Vec<String> = [
+                word.pronunciation.as_ref().map(|p| format!("/{p}/")),
+                word.part_of_speech.clone(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            lines.push(if details.is_empty() {
+                word.word.clone()
+            } else {
+                format!("{} {}", word.word, details.join(", "))
+            });
+            lines.push(word.definition.clone());
+            summary = Some(word.word);
+        }
+        Section {
+            integration: self.slug().into(),
+            title: settings
+                .get("title")
+                .and_then(Value::as_str)
+                .unwrap_or("Word of the day")
+                .into(),
+            priority: 0,
+            lines,
+            blocks: Vec::new(),
+            summary,
+        }
+    }
+
+    /// The word only changes with the day.
+    fn cache_ttl(&self) -> TimeDelta {
+        TimeDelta::days(1)
+    }
+}
+
+/// The built-in list's word for `date`, going through the list a day at a time.
+fn bundled(date: NaiveDate) -> Word {
+    let words: Vec<&str> = WORDS
+        .lines()
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .collect();
+    let day = date.signed_duration_since(NaiveDate::MIN).num_days() as usize;
+    let mut fields = words[day % words.len()].split('\t');
+    let mut field = || fields.next().map(String::from);
+    Word {
+        word: field().unwrap_or_default(),
+        pronunciation: field(),
+        part_of_speech: field(),
+        definition: field().unwrap_or_default(),
+    }
+}
+
+async fn download(url: &str, date: NaiveDate) -> Result<Word> {
+    let url = url.replace("{date}", &date.format("%Y-%m-%d").to_string());
+    let response = reqwest::Client::new()
+        .get(&url)
+        .send()
+        .await
+        .with_context(|| format!("failed to fetch {url}"))?
+        .error_for_status()?;
+    Ok(response.json().await?)
+}
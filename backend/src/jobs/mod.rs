@@ -0,0 +1,341 @@
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::document::{ContentType, Document};
+use crate::schema::jobs;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Queued,
+    Printing,
+    Done,
+    Failed,
+    /// Rendered for a dry run; never sent to a printer.
+    Simulated,
+}
+
+impl JobState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Printing => "printing",
+            JobState::Done => "done",
+            JobState::Failed => "failed",
+            JobState::Simulated => "simulated",
+        }
+    }
+}
+
+impl FromStr for JobState {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "queued" => Ok(JobState::Queued),
+            "printing" => Ok(JobState::Printing),
+            "done" => Ok(JobState::Done),
+            "failed" => Ok(JobState::Failed),
+            "simulated" => Ok(JobState::Simulated),
+            _ => Err(()),
+        }
+    }
+}
+
+/// What to print. Stored as JSON in `jobs.payload`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum JobPayload {
+    Document(Document),
+    /// Plain lines of text, or Markdown, for callers that don't need any layout.
+    Text {
+        text: String,
+        #[serde(default, skip_serializing_if = "ContentType::is_plain")]
+        content_type: ContentType,
+        #[serde(default = "default_cut")]
+        cut: bool,
+        /// See [`Document::rotate_180`].
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        rotate_180: bool,
+    },
+}
+
+fn default_cut() -> bool {
+    true
+}
+
+impl JobPayload {
+    /// Plain text followed by a cut.
+    pub fn text(text: impl Into<String>) -> Self {
+        JobPayload::Text {
+            text: text.into(),
+            content_type: ContentType::Plain,
+            cut: true,
+            rotate_180: false,
+        }
+    }
+
+    pub fn into_document(self) -> Document {
+        match self {
+            JobPayload::Document(document) => document,
+            JobPayload::Text {
+                text,
+                content_type,
+                cut,
+                rotate_180,
+            } => Document {
+                rotate_180,
+                ..Document::from_content(text, content_type, cut)
+            },
+        }
+    }
+}
+
+/// A job body as submitted: either a full payload or a stored template plus variables.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum JobContent {
+    Template {
+        template: String,
+        #[serde(default)]
+        #[schema(value_type = Object)]
+        vars: Map<String, Value>,
+    },
+    Inline(JobPayload),
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize, ToSchema)]
+#[diesel(table_name = jobs)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Job {
+    pub id: i32,
+    pub printer_id: i32,
+    pub state: String,
+    pub source: String,
+    #[serde(skip)]
+    pub payload: String,
+    pub bytes: Option<i32>,
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub started_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+    /// SHA-256 of the payload, used to suppress duplicate submissions.
+    #[serde(skip)]
+    pub content_hash: Option<String>,
+    /// Printed even during the printer's quiet hours.
+    pub urgent: bool,
+}
+
+impl Job {
+    pub fn payload(&self) -> Result<JobPayload> {
+        Ok(serde_json::from_str(&self.payload)?)
+    }
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = jobs)]
+pub struct NewJob {
+    pub printer_id: i32,
+    pub state: String,
+    pub source: String,
+    pub payload: String,
+    pub content_hash: String,
+    pub urgent: bool,
+}
+
+impl NewJob {
+    pub fn new(printer_id: i32, source: impl Into<String>, payload: &JobPayload) -> Result<Self> {
+        let payload = serde_json::to_string(payload)?;
+        Ok(Self {
+            printer_id,
+            state: JobState::Queued.as_str().into(),
+            source: source.into(),
+            content_hash: hex::encode(Sha256::digest(payload.as_bytes())),
+            payload,
+            urgent: false,
+        })
+    }
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct JobFilter {
+    pub printer_id: Option<i32>,
+    pub state: Option<String>,
+    pub source: Option<String>,
+}
+
+/// Fields [`list`] can sort jobs by.
+pub const SORTABLE: &[&str] = &["id", "created_at", "finished_at", "printer_id", "state"];
+
+fn filtered(filter: &JobFilter) -> jobs::BoxedQuery<'static, Sqlite> {
+    let mut query = jobs::table.into_boxed();
+    if let Some(printer_id) = filter.printer_id {
+        query = query.filter(jobs::printer_id.eq(printer_id));
+    }
+    if let Some(state) = &filter.state {
+        query = query.filter(jobs::state.eq(state.clone()));
+    }
+    if let Some(source) = &filter.source {
+        query = query.filter(jobs::source.eq(source.clone()));
+    }
+    query
+}
+
+pub fn create(conn: &mut SqliteConnection, new: NewJob) -> Result<Job> {
+    let row = diesel::insert_into(jobs::table)
+        .values(&new)
+        .returning(Job::as_returning())
+        .get_result(conn)?;
+    Ok(row)
+}
+
+/// Record a dry-run job that has already been rendered. It is never queued.
+pub fn create_simulated(conn: &mut SqliteConnection, new: NewJob, bytes: usize) -> Result<Job> {
+    let now = Utc::now().naive_utc();
+    let row = diesel::insert_into(jobs::table)
+        .values((
+            &new,
+            jobs::bytes.eq(bytes as i32),
+            jobs::started_at.eq(now),
+            jobs::finished_at.eq(now),
+        ))
+        .returning(Job::as_returning())
+        .get_result(conn)?;
+    Ok(row)
+}
+
+pub fn get(conn: &mut SqliteConnection, id: i32) -> Result<Option<Job>> {
+    let row = jobs::table
+        .find(id)
+        .select(Job::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+/// A page of the jobs matching `filter`, newest first unless sorted by `sort`, one
+/// of [`SORTABLE`] and whether descending, and how many match in all.
+pub fn list(
+    conn: &mut SqliteConnection,
+    filter: &JobFilter,
+    sort: Option<(&str, bool)>,
+    offset: i64,
+    limit: i64,
+) -> Result<(Vec<Job>, i64)> {
+    let total = filtered(filter).count().get_result(conn)?;
+    let query = filtered(filter).select(Job::as_select());
+    let query = match sort {
+        Some(("created_at", false)) => query.order((jobs::created_at.asc(), jobs::id.asc())),
+        Some(("created_at", true)) => query.order((jobs::created_at.desc(), jobs::id.desc())),
+        Some(("finished_at", false)) => query.order((jobs::finished_at.asc(), jobs::id.asc())),
+        Some(("finished_at", true)) => query.order((jobs::finished_at.desc(), jobs::id.desc())),
+        Some(("printer_id", false)) => query.order((jobs::printer_id.asc(), jobs::id.asc())),
+        Some(("printer_id", true)) => query.order((jobs::printer_id.desc(), jobs::id.desc())),
+        Some(("state", false)) => query.order((jobs::state.asc(), jobs::id.asc())),
+        Some(("state", true)) => query.order((jobs::state.desc(), jobs::id.desc())),
+        Some(("id", false)) => query.order(jobs::id.asc()),
+        _ => query.order(jobs::id.desc()),
+    };
+    let rows = query.offset(offset).limit(limit).load(conn)?;
+    Ok((rows, total))
+}
+
+/// Jobs that were accepted but never finished, oldest first. Used to rebuild the
+/// in-memory queues after a restart.
+pub fn unfinished(conn: &mut SqliteConnection) -> Result<Vec<Job>> {
+    let rows = jobs::table
+        .filter(jobs::state.eq_any([JobState::Queued.as_str(), JobState::Printing.as_str()]))
+        .order(jobs::id.asc())
+        .select(Job::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+/// A job with the same content submitted to `printer_id` since `since` that has not
+/// failed, i.e. one that is printing, will print, or already has.
+pub fn find_duplicate(
+    conn: &mut SqliteConnection,
+    printer_id: i32,
+    content_hash: &str,
+    since: NaiveDateTime,
+) -> Result<Option<Job>> {
+    let row = jobs::table
+        .filter(jobs::printer_id.eq(printer_id))
+        .filter(jobs::content_hash.eq(content_hash))
+        .filter(jobs::created_at.ge(since))
+        .filter(jobs::state.eq_any([
+            JobState::Queued.as_str(),
+            JobState::Printing.as_str(),
+            JobState::Done.as_str(),
+        ]))
+        .order(jobs::id.desc())
+        .select(Job::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+/// Most recent successfully printed jobs on a printer, newest first.
+pub fn recent_done(conn: &mut SqliteConnection, printer_id: i32, limit: i64) -> Result<Vec<Job>> {
+    let rows = jobs::table
+        .filter(jobs::printer_id.eq(printer_id))
+        .filter(jobs::state.eq(JobState::Done.as_str()))
+        .order(jobs::id.desc())
+        .limit(limit)
+        .select(Job::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+/// The job currently being written to a printer, if any.
+pub fn printing(conn: &mut SqliteConnection, printer_id: i32) -> Result<Option<Job>> {
+    let row = jobs::table
+        .filter(jobs::printer_id.eq(printer_id))
+        .filter(jobs::state.eq(JobState::Printing.as_str()))
+        .select(Job::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn set_state(conn: &mut SqliteConnection, id: i32, state: JobState) -> Result<()> {
+    let now = Utc::now().naive_utc();
+    match state {
+        JobState::Printing => diesel::update(jobs::table.find(id))
+            .set((jobs::state.eq(state.as_str()), jobs::started_at.eq(now)))
+            .execute(conn)?,
+        _ => diesel::update(jobs::table.find(id))
+            .set(jobs::state.eq(state.as_str()))
+            .execute(conn)?,
+    };
+    Ok(())
+}
+
+pub fn mark_done(conn: &mut SqliteConnection, id: i32, bytes: usize) -> Result<()> {
+    diesel::update(jobs::table.find(id))
+        .set((
+            jobs::state.eq(JobState::Done.as_str()),
+            jobs::bytes.eq(bytes as i32),
+            jobs::finished_at.eq(Utc::now().naive_utc()),
+        ))
+        .execute(conn)?;
+    Ok(())
+}
+
+pub fn mark_failed(conn: &mut SqliteConnection, id: i32, error: String) -> Result<()> {
+    diesel::update(jobs::table.find(id))
+        .set((
+            jobs::state.eq(JobState::Failed.as_str()),
+            jobs::error.eq(error),
+            jobs::finished_at.eq(Utc::now().naive_utc()),
+        ))
+        .execute(conn)?;
+    Ok(())
+}
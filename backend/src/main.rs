@@ -1,36 +1,64 @@
+#[cfg(all(
+    feature = "minimal",
+    any(feature = "linux-udev", feature = "usb", feature = "bluetooth")
+))]
+compile_error!(
+    "`minimal` excludes features that link native libraries; build with --no-default-features --features minimal"
+);
+
+mod api_keys;
 mod app;
+mod compose;
 mod config;
+mod countdowns;
 mod db;
 mod discover;
+mod document;
+mod error;
+mod events;
+mod glyphs;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod habits;
+mod hooks;
+mod integrations;
+mod jobs;
+mod meals;
 mod model;
+mod occasions;
+mod output;
+mod pairing;
+mod printers;
+mod push;
+mod queue;
+mod quotes;
+mod render;
+mod retention;
 mod routes;
+mod scheduler;
+mod schedules;
+mod schema;
+mod secrets;
+mod settings;
+mod shopping;
+mod shutdown;
 mod state;
+mod tasks;
+mod telemetry;
+mod templates;
+mod tls;
+mod transforms;
+mod users;
+#[cfg(feature = "web-ui")]
+mod web;
+mod webhooks;
 
 use crate::discover::{DefaultDiscovery, DiscoveryProvider};
-use axum::{Json, Router};
-use escpos::driver::FileDriver;
-use escpos::printer::Printer;
-use escpos::printer_options::PrinterOptions;
-use escpos::utils::{
-    DebugMode, JustifyMode, Protocol, RealTimeStatusRequest, RealTimeStatusResponse, UnderlineMode,
-};
-use log::info;
-use serde_json::{Value, json};
+use escpos::driver::{Driver, FileDriver};
+use escpos::utils::{RealTimeStatusRequest, RealTimeStatusResponse};
+use serde_json::json;
 use std::path::Path;
-
-async fn integrations() -> Json<Value> {
-    Json(json!({
-        "response_code": 200,
-        "error": false,
-        "integrations": [
-            {
-                "slug": "remote_calendar",
-                "instance_id": "48265F8E-8A69-4AD3-B46B-713094B7E240",
-                "enabled": true,
-            }
-        ]
-    }))
-}
+use tracing::{info, warn};
 
 async fn pmenu() -> Result<(), Box<dyn std::error::Error>> {
     let path = Path::new("/dev/usb/lp1");
@@ -38,31 +66,18 @@ async fn pmenu() -> Result<(), Box<dyn std::error::Error>> {
 
     let command = std::env::args().nth(1).expect("No command given");
     let driver = FileDriver::open(&path)?;
-    let mut printer = Printer::new(
-        driver.clone(),
-        Protocol::default(),
-        Some(PrinterOptions::default()),
-    );
     if command == "print" {
         info!("Printing!");
-        printer
-            .debug_mode(Some(DebugMode::Dec))
-            .init()?
-            .smoothing(true)?
-            .bold(true)?
-            .underline(UnderlineMode::Single)?
-            .writeln("Bold underline")?
-            .justify(JustifyMode::CENTER)?
-            .reverse(true)?
-            .bold(false)?
-            .writeln("Hello world - Reverse")?
-            .feed()?
-            .justify(JustifyMode::RIGHT)?
-            .reverse(false)?
-            .underline(UnderlineMode::None)?
-            .size(2, 3)?
-            .writeln("Hello world - Normal")?
-            .print_cut()?; // print() or print_cut() is mandatory to send the data to the printer
+        let document: document::Document = serde_json::from_value(json!({
+            "blocks": [
+                { "type": "text", "text": "Bold underline", "bold": true, "underline": true },
+                { "type": "text", "text": "Hello world - Reverse", "align": "center", "invert": true },
+                { "type": "feed" },
+                { "type": "text", "text": "Hello world - Normal", "align": "right", "size": 2 },
+                { "type": "cut" }
+            ]
+        }))?;
+        driver.write(&render::render(&document, &render::Profile::default())?.bytes)?;
     } else if command == "detect" {
         let provider = DefaultDiscovery::default();
         let printers = provider.discover_default()?;
@@ -85,22 +100,66 @@ async fn pmenu() -> Result<(), Box<dyn std::error::Error>> {
         //     status.get(&RealTimeStatusResponse::Online).unwrap_or(&false)
         // );
     }
-    // let app = Router::new()
-    //     .route("/", get(|| async { "Root get request!" }))
-    //     .route("/integrations", get(integrations));
-    //
-    // let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
-    // axum::serve(listener, app).await.unwrap();
     Ok(())
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cfg = config::Config::from_env()?;
+    let telemetry = telemetry::init();
+    db::run_migrations()?;
+    let plain =
+        db::run_blocking_db(|conn| Ok(integrations::seal_stored(conn)? + push::seal_stored(conn)?))
+            .await?;
+    if plain > 0 {
+        warn!("{plain} stored credentials are unencrypted; set SECRET_KEY to encrypt them");
+    }
+    db::run_blocking_db(hooks::hash_stored).await?;
     let state = state::AppState::new(cfg.clone());
+    state.queue.restore().await?;
+    state.queue.watch_config();
+    state.push.start_all().await?;
+    if let Some(url) = cfg.mqtt_status_url.clone() {
+        push::status::spawn(url, state.events.clone());
+    }
+    scheduler::spawn(
+        state.queue.clone(),
+        state.events.clone(),
+        state.config.digest_theme,
+    );
+    integrations::credentials::spawn(state.events.clone());
+    retention::spawn(cfg.deleted_retention_days);
+    printers::monitor::spawn(state.events.clone(), state.printers_seen.clone());
+    webhooks::deliver::spawn_printer_alerts(&state.events);
+    let queue = state.queue.clone();
+    let stopping = {
+        let (queue, shutdown) = (state.queue.clone(), state.shutdown.clone());
+        async move {
+            shutdown::signal().await;
+            info!("shutting down; no longer accepting jobs");
+            queue.close();
+            shutdown.begin();
+        }
+    };
     let app = app::build_app(state);
-    let listener = tokio::net::TcpListener::bind(cfg.bind_addr).await?;
-
-    axum::serve(listener, app).await?;
+    match &cfg.tls {
+        Some(tls) => {
+            axum::serve(tls::bind(&cfg.bind_addr, tls).await?, app)
+                .with_graceful_shutdown(stopping)
+                .await?
+        }
+        None => {
+            axum::serve(tokio::net::TcpListener::bind(&cfg.bind_addr).await?, app)
+                .with_graceful_shutdown(stopping)
+                .await?
+        }
+    }
+    let drained = queue.drain(cfg.shutdown_timeout).await;
+    info!("stopped");
+    if !drained {
+        // A write stuck on the device would keep the runtime from ever shutting down.
+        drop(telemetry);
+        std::process::exit(0);
+    }
     Ok(())
 }
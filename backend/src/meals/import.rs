@@ -0,0 +1,167 @@
+//! Meal plans kept in Mealie or Tandoor, read through their APIs with an API token.
+
+use anyhow::{Context as _, Result};
+use chrono::NaiveDate;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::Value;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    Mealie,
+    Tandoor,
+}
+
+impl Provider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Provider::Mealie => "mealie",
+            Provider::Tandoor => "tandoor",
+        }
+    }
+}
+
+impl FromStr for Provider {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mealie" => Ok(Provider::Mealie),
+            "tandoor" => Ok(Provider::Tandoor),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A meal read from a service's plan.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub day: NaiveDate,
+    pub slot: String,
+    pub title: String,
+    pub notes: Option<String>,
+}
+
+/// The meals planned from `from` to `to` on the `provider` instance at `url`.
+pub async fn fetch(
+    provider: Provider,
+    url: &str,
+    token: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<Entry>> {
+    let url = url.trim_end_matches('/');
+    let client = reqwest::Client::new();
+    match provider {
+        Provider::Mealie => {
+            let query = format!("start_date={from}&end_date={to}&page=1&perPage=-1");
+            let mut response = client
+                .get(format!("{url}/api/households/mealplans?{query}"))
+                .bearer_auth(token)
+                .send()
+                .await
+                .with_context(|| format!("failed to reach Mealie at {url}"))?;
+            // Mealie before 2.0 kept meal plans per group.
+            if response.status() == StatusCode::NOT_FOUND {
+                response = client
+                    .get(format!("{url}/api/groups/mealplans?{query}"))
+                    .bearer_auth(token)
+                    .send()
+                    .await
+                    .with_context(|| format!("failed to reach Mealie at {url}"))?;
+            }
+            let page: MealiePage = response.error_for_status()?.json().await?;
+            Ok(page
+                .items
+                .into_iter()
+                .filter_map(MealieItem::entry)
+                .collect())
+        }
+        Provider::Tandoor => {
+            let body: Value = client
+                .get(format!(
+                    "{url}/api/meal-plan/?from_date={from}&to_date={to}"
+                ))
+                .bearer_auth(token)
+                .send()
+                .await
+                .with_context(|| format!("failed to reach Tandoor at {url}"))?
+                .error_for_status()?
+                .json()
+                .await?;
+            // Newer versions page the results; older ones answer with a bare list.
+            let items = match body {
+                Value::Object(mut page) => page.remove("results").unwrap_or_default(),
+                list => list,
+            };
+            let items: Vec<TandoorItem> = serde_json::from_value(items)?;
+            Ok(items.into_iter().filter_map(TandoorItem::entry).collect())
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Recipe {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct MealiePage {
+    items: Vec<MealieItem>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MealieItem {
+    date: NaiveDate,
+    entry_type: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    text: String,
+    recipe: Option<Recipe>,
+}
+
+impl MealieItem {
+    fn entry(self) -> Option<Entry> {
+        let title = self.recipe.map(|r| r.name).unwrap_or(self.title);
+        (!title.trim().is_empty()).then(|| Entry {
+            day: self.date,
+            slot: self.entry_type,
+            title,
+            notes: Some(self.text).filter(|t| !t.trim().is_empty()),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct TandoorItem {
+    /// A date-time since Tandoor 1.5, a date before.
+    #[serde(alias = "date")]
+    from_date: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    note: String,
+    recipe: Option<Recipe>,
+    meal_type: TandoorMealType,
+}
+
+#[derive(Deserialize)]
+struct TandoorMealType {
+    name: String,
+}
+
+impl TandoorItem {
+    fn entry(self) -> Option<Entry> {
+        let day = self.from_date.get(..10)?.parse().ok()?;
+        let title = self.recipe.map(|r| r.name).unwrap_or(self.title);
+        (!title.trim().is_empty()).then(|| Entry {
+            day,
+            slot: self.meal_type.name,
+            title,
+            notes: Some(self.note).filter(|t| !t.trim().is_empty()),
+        })
+    }
+}
@@ -0,0 +1,138 @@
+//! The household's meal plan: what's planned for each meal of each day, entered
+//! through the API or imported from Mealie or Tandoor, and what has to come out of
+//! the freezer the day before.
+
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::error::FieldError;
+use crate::schema::meals;
+
+pub mod import;
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize)]
+#[diesel(table_name = meals)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Meal {
+    pub id: i32,
+    pub day: NaiveDate,
+    /// Which meal of the day, such as `dinner`; one per day.
+    pub slot: String,
+    pub title: String,
+    pub notes: Option<String>,
+    /// What to take out of the freezer the day before.
+    pub defrost: Option<String>,
+    /// Service the meal was imported from, if any.
+    pub source: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Writable fields of a planned meal, whose day and slot come from the path.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlanMeal {
+    pub title: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub defrost: Option<String>,
+}
+
+impl PlanMeal {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        if self.title.trim().is_empty() {
+            return Err(FieldError::new("title", "must not be empty"));
+        }
+        Ok(())
+    }
+}
+
+/// Slots are compared in lower case, so `Dinner` from one service and `dinner` from
+/// another are the same meal.
+pub fn slot_key(slot: &str) -> String {
+    slot.trim().to_lowercase()
+}
+
+/// The meals planned from `from` to `to`, both included, by day.
+pub fn between(conn: &mut SqliteConnection, from: NaiveDate, to: NaiveDate) -> Result<Vec<Meal>> {
+    let rows = meals::table
+        .filter(meals::day.between(from, to))
+        .order((meals::day.asc(), meals::id.asc()))
+        .select(Meal::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+/// Plan `meal` for `slot` on `day`, replacing whatever was planned there.
+pub fn plan(
+    conn: &mut SqliteConnection,
+    day: NaiveDate,
+    slot: &str,
+    meal: PlanMeal,
+) -> Result<Meal> {
+    let now = Utc::now().naive_utc();
+    let row = diesel::insert_into(meals::table)
+        .values((
+            meals::day.eq(day),
+            meals::slot.eq(slot_key(slot)),
+            meals::title.eq(&meal.title),
+            meals::notes.eq(&meal.notes),
+            meals::defrost.eq(&meal.defrost),
+        ))
+        .on_conflict((meals::day, meals::slot))
+        .do_update()
+        .set((
+            meals::title.eq(&meal.title),
+            meals::notes.eq(&meal.notes),
+            meals::defrost.eq(&meal.defrost),
+            meals::source.eq(None::<String>),
+            meals::updated_at.eq(now),
+        ))
+        .returning(Meal::as_returning())
+        .get_result(conn)?;
+    Ok(row)
+}
+
+pub fn remove(conn: &mut SqliteConnection, day: NaiveDate, slot: &str) -> Result<bool> {
+    let deleted = diesel::delete(
+        meals::table
+            .filter(meals::day.eq(day))
+            .filter(meals::slot.eq(slot_key(slot))),
+    )
+    .execute(conn)?;
+    Ok(deleted > 0)
+}
+
+/// Store meals imported from `source`. A meal already planned for the same slot is
+/// replaced, keeping its defrost reminder, which the services don't have.
+pub fn store_imported(
+    conn: &mut SqliteConnection,
+    source: &str,
+    imported: Vec<import::Entry>,
+) -> Result<usize> {
+    let now = Utc::now().naive_utc();
+    conn.transaction(|conn| {
+        for entry in &imported {
+            diesel::insert_into(meals::table)
+                .values((
+                    meals::day.eq(entry.day),
+                    meals::slot.eq(slot_key(&entry.slot)),
+                    meals::title.eq(&entry.title),
+                    meals::notes.eq(&entry.notes),
+                    meals::source.eq(source),
+                ))
+                .on_conflict((meals::day, meals::slot))
+                .do_update()
+                .set((
+                    meals::title.eq(&entry.title),
+                    meals::notes.eq(&entry.notes),
+                    meals::source.eq(source),
+                    meals::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+        }
+        Ok(imported.len())
+    })
+}
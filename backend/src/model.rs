@@ -1,11 +1,22 @@
-#[derive(Debug, Clone)]
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// How a printer is reached. `VirtualDisplay` is a paperless target whose `url` is an
+/// `http(s)://` webhook or an `mqtt://host:port/topic` destination; `Bluetooth` is a
+/// paired device that has not been bound to an rfcomm node yet.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(tag = "transport", rename_all = "snake_case")]
 pub enum Transport {
     UsbLp { path: String },
     Serial { path: String },
+    VirtualDisplay { url: String },
+    Bluetooth { address: String },
 }
 
-#[derive(Debug, Clone)]
+/// A device that looks like a printer, found by [`crate::discover`].
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct Candidate {
+    #[serde(flatten)]
     pub transport: Transport,
     pub make_model: Option<String>,
     pub serial: Option<String>,
@@ -20,6 +31,7 @@ impl Candidate {
         match &self.transport {
             Transport::UsbLp { path } => Some(path.as_str()),
             Transport::Serial { path } => Some(path.as_str()),
+            Transport::VirtualDisplay { .. } | Transport::Bluetooth { .. } => None,
         }
     }
 }
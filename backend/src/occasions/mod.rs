@@ -0,0 +1,192 @@
+//! Birthdays, anniversaries and other dates that come round every year, for the
+//! `birthdays` integration to remind about.
+
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::error::FieldError;
+use crate::schema::occasions;
+
+pub mod vcard;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    #[default]
+    Birthday,
+    Anniversary,
+    /// Any other yearly date, printed by its name alone.
+    Other,
+}
+
+impl Kind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Kind::Birthday => "birthday",
+            Kind::Anniversary => "anniversary",
+            Kind::Other => "other",
+        }
+    }
+}
+
+impl FromStr for Kind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "birthday" => Ok(Kind::Birthday),
+            "anniversary" => Ok(Kind::Anniversary),
+            "other" => Ok(Kind::Other),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize)]
+#[diesel(table_name = occasions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Occasion {
+    pub id: i32,
+    /// Whose birthday or anniversary it is, or what the date is.
+    pub name: String,
+    /// See [`Kind`].
+    pub kind: String,
+    pub month: i32,
+    pub day: i32,
+    /// Year it started, to count the years from, if known.
+    pub year: Option<i32>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl Occasion {
+    pub fn kind(&self) -> Kind {
+        self.kind.parse().unwrap_or_default()
+    }
+
+    /// The first time the occasion falls on or after `date`. The 29th of February is
+    /// kept on the 28th in other years.
+    pub fn next(&self, date: NaiveDate) -> Option<NaiveDate> {
+        let on = |year| {
+            NaiveDate::from_ymd_opt(year, self.month as u32, self.day as u32)
+                .or_else(|| NaiveDate::from_ymd_opt(year, self.month as u32, self.day as u32 - 1))
+        };
+        on(date.year())
+            .filter(|d| *d >= date)
+            .or_else(|| on(date.year() + 1))
+    }
+}
+
+/// Writable occasion fields, used both for creation and full (`PUT`) updates.
+#[derive(Debug, Clone, Deserialize, Insertable, AsChangeset)]
+#[diesel(table_name = occasions)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewOccasion {
+    pub name: String,
+    #[serde(default = "default_kind")]
+    pub kind: String,
+    pub month: i32,
+    pub day: i32,
+    #[serde(default)]
+    pub year: Option<i32>,
+}
+
+fn default_kind() -> String {
+    Kind::default().as_str().into()
+}
+
+impl NewOccasion {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        if self.name.trim().is_empty() {
+            return Err(FieldError::new("name", "must not be empty"));
+        }
+        if self.kind.parse::<Kind>().is_err() {
+            return Err(FieldError::new(
+                "kind",
+                "must be one of: birthday, anniversary, other",
+            ));
+        }
+        if !(1..=12).contains(&self.month) {
+            return Err(FieldError::new("month", "must be between 1 and 12"));
+        }
+        // 2000 was a leap year, so the 29th of February passes.
+        let year = self.year.unwrap_or(2000);
+        if NaiveDate::from_ymd_opt(year, self.month as u32, self.day as u32).is_none() {
+            return Err(FieldError::new("day", "is not a day of that month"));
+        }
+        Ok(())
+    }
+}
+
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Occasion>> {
+    let rows = occasions::table
+        .order((
+            occasions::month.asc(),
+            occasions::day.asc(),
+            occasions::id.asc(),
+        ))
+        .select(Occasion::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn get(conn: &mut SqliteConnection, id: i32) -> Result<Option<Occasion>> {
+    let row = occasions::table
+        .find(id)
+        .select(Occasion::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn create(conn: &mut SqliteConnection, new: NewOccasion) -> Result<Occasion> {
+    let row = diesel::insert_into(occasions::table)
+        .values(&new)
+        .returning(Occasion::as_returning())
+        .get_result(conn)?;
+    Ok(row)
+}
+
+pub fn update(
+    conn: &mut SqliteConnection,
+    id: i32,
+    changes: NewOccasion,
+) -> Result<Option<Occasion>> {
+    let row = diesel::update(occasions::table.find(id))
+        .set((&changes, occasions::updated_at.eq(Utc::now().naive_utc())))
+        .returning(Occasion::as_returning())
+        .get_result(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn delete(conn: &mut SqliteConnection, id: i32) -> Result<bool> {
+    let deleted = diesel::delete(occasions::table.find(id)).execute(conn)?;
+    Ok(deleted > 0)
+}
+
+/// Add `new` occasions, skipping any already stored with the same name, kind and
+/// date. Returns how many were added.
+pub fn import(conn: &mut SqliteConnection, new: Vec<NewOccasion>) -> Result<usize> {
+    conn.transaction(|conn| {
+        let mut added = 0;
+        for occasion in new {
+            let exists = occasions::table
+                .filter(occasions::name.eq(&occasion.name))
+                .filter(occasions::kind.eq(&occasion.kind))
+                .filter(occasions::month.eq(occasion.month))
+                .filter(occasions::day.eq(occasion.day))
+                .count()
+                .get_result::<i64>(conn)?
+                > 0;
+            if !exists {
+                create(conn, occasion)?;
+                added += 1;
+            }
+        }
+        Ok(added)
+    })
+}
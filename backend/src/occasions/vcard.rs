@@ -0,0 +1,99 @@
+//! Birthdays and anniversaries from contacts exported as vCards (`.vcf`), as phone
+//! and mail address books do.
+
+use super::{Kind, NewOccasion};
+
+/// The occasions in every card of `text`: `BDAY`, and `ANNIVERSARY` or the
+/// `X-ANNIVERSARY` that older exports use. Cards without a name are skipped.
+pub fn parse(text: &str) -> Vec<NewOccasion> {
+    let mut occasions = Vec::new();
+    let mut name = None;
+    let mut dates = Vec::new();
+    for line in unfold(text) {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Drop parameters (`BDAY;VALUE=date`) and Apple's `item1.` group prefix.
+        let key = key.split(';').next().unwrap_or_default();
+        let key = key
+            .rsplit('.')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+        match key.as_str() {
+            "BEGIN" => {
+                name = None;
+                dates.clear();
+            }
+            "FN" => name = Some(unescape(value)),
+            "BDAY" => dates.push((Kind::Birthday, value.trim().to_string())),
+            "ANNIVERSARY" | "X-ANNIVERSARY" | "X-MS-ANNIVERSARY" | "X-EVOLUTION-ANNIVERSARY" => {
+                dates.push((Kind::Anniversary, value.trim().to_string()));
+            }
+            "END" => {
+                let Some(name) = name.take().filter(|n| !n.is_empty()) else {
+                    continue;
+                };
+                for (kind, value) in dates.drain(..) {
+                    if let Some((year, month, day)) = date(&value) {
+                        occasions.push(NewOccasion {
+                            name: name.clone(),
+                            kind: kind.as_str().into(),
+                            month,
+                            day,
+                            year,
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    occasions
+        .into_iter()
+        .filter(|o| o.validate().is_ok())
+        .collect()
+}
+
+/// Lines with their folded continuations (lines starting with a space or tab)
+/// joined back on.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .trim()
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+/// A vCard date: `1965-03-14`, `19650314`, or `--03-14` and `--0314` without a
+/// year, optionally followed by a time. Apple writes `1604` for an unknown year.
+fn date(value: &str) -> Option<(Option<i32>, i32, i32)> {
+    let value = value.split('T').next()?;
+    let (year, rest) = match value.strip_prefix("--") {
+        Some(rest) => (None, rest.to_string()),
+        None => {
+            let digits: String = value.chars().filter(char::is_ascii_digit).collect();
+            if digits.len() != 8 {
+                return None;
+            }
+            let year: i32 = digits[..4].parse().ok()?;
+            (Some(year).filter(|&y| y != 1604), digits[4..].to_string())
+        }
+    };
+    let digits: String = rest.chars().filter(char::is_ascii_digit).collect();
+    if digits.len() != 4 {
+        return None;
+    }
+    Some((year, digits[..2].parse().ok()?, digits[2..].parse().ok()?))
+}
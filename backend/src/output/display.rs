@@ -0,0 +1,69 @@
+use anyhow::{Context, Result, anyhow, bail};
+use reqwest::Url;
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use std::time::Duration;
+
+use crate::printers::Printer;
+use crate::render::preview;
+
+/// Rasterize the job and push the image to a virtual display.
+pub async fn push(printer: &Printer, url: &str, text: &str) -> Result<()> {
+    let png = preview::text_to_png(text)?;
+    let url = Url::parse(url).with_context(|| format!("invalid display URL {url}"))?;
+
+    match url.scheme() {
+        "http" | "https" => push_webhook(url, png).await,
+        "mqtt" => push_mqtt(printer, &url, png).await,
+        other => bail!("unsupported display scheme '{other}'"),
+    }
+}
+
+async fn push_webhook(url: Url, png: Vec<u8>) -> Result<()> {
+    reqwest::Client::new()
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "image/png")
+        .timeout(Duration::from_secs(15))
+        .body(png)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Publish the image as a retained message so a frame that wakes up later still
+/// picks up the latest printout.
+async fn push_mqtt(printer: &Printer, url: &Url, png: Vec<u8>) -> Result<()> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow!("MQTT URL is missing a host"))?;
+    let topic = url.path().trim_start_matches('/');
+    if topic.is_empty() {
+        bail!("MQTT URL is missing a topic");
+    }
+
+    let mut options = MqttOptions::new(
+        format!("dayroll-display-{}", printer.id),
+        host,
+        url.port().unwrap_or(1883),
+    );
+    options.set_max_packet_size(64 * 1024, png.len() + 1024);
+    if !url.username().is_empty() {
+        options.set_credentials(url.username(), url.password().unwrap_or_default());
+    }
+
+    let (client, mut eventloop) = AsyncClient::new(options, 4);
+    client.publish(topic, QoS::AtLeastOnce, true, png).await?;
+
+    tokio::time::timeout(Duration::from_secs(15), async {
+        loop {
+            if let Event::Incoming(Packet::PubAck(_)) = eventloop.poll().await? {
+                return Ok::<(), anyhow::Error>(());
+            }
+        }
+    })
+    .await
+    .context("timed out waiting for MQTT broker")??;
+
+    let _ = client.disconnect().await;
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use anyhow::{Context, Result, bail};
+use escpos::driver::{Driver, FileDriver};
+use std::path::Path;
+use std::time::Duration;
+use tracing::instrument;
+
+use crate::model::Transport;
+use crate::printers::Printer;
+use crate::render::Rendered;
+
+mod display;
+
+/// Longest a device may take to accept a job, on top of a second per KiB of it for
+/// slow serial links.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Deliver a rendered job to its output target. A device that stops accepting
+/// data fails the job once its time is up, rather than holding up the printers
+/// waiting for a print permit; the stuck write is left to finish or fail.
+#[instrument(name = "write", skip_all, fields(printer = printer.id, bytes = rendered.bytes.len()))]
+pub async fn send(printer: &Printer, rendered: &Rendered) -> Result<()> {
+    match printer.transport()? {
+        Transport::UsbLp { path } | Transport::Serial { path } => {
+            let bytes = rendered.bytes.clone();
+            let limit = WRITE_TIMEOUT + Duration::from_secs(bytes.len() as u64 / 1024);
+            let device = path.clone();
+            let write = tokio::task::spawn_blocking(move || write_device(&path, &bytes));
+            tokio::time::timeout(limit, write)
+                .await
+                .with_context(|| format!("printer device {device} stopped accepting data"))??
+        }
+        Transport::VirtualDisplay { url } => display::push(printer, &url, &rendered.text).await,
+        Transport::Bluetooth { address } => {
+            bail!("bluetooth device {address} must be bound to an rfcomm node before printing")
+        }
+    }
+}
+
+/// Write a raw ESC/POS stream to a device node. Blocking.
+fn write_device(path: &str, bytes: &[u8]) -> Result<()> {
+    let driver = FileDriver::open(Path::new(path))
+        .with_context(|| format!("failed to open printer device {path}"))?;
+    driver.write(bytes)?;
+    driver.flush()?;
+    Ok(())
+}
@@ -0,0 +1,219 @@
+//! Pairing a new client with the server: a short code printed on a printer, which
+//! whoever is standing at it types into the client to get an API key. Only one code
+//! is pending at a time, and a few wrong guesses lock pairing until it expires.
+
+use anyhow::Result;
+use chrono::{Local, NaiveDateTime, TimeDelta, Utc};
+use diesel::prelude::*;
+
+use crate::api_keys::{self, ApiKey, Scope};
+use crate::document::{Align, Block, Document, Style};
+use crate::schema::pairing_codes;
+
+/// How long a printed code can be used for.
+pub const CODE_TTL: TimeDelta = TimeDelta::minutes(5);
+/// Least time between printed codes.
+pub const REISSUE_AFTER: TimeDelta = TimeDelta::minutes(1);
+/// Wrong codes tried, across the codes replacing each other, before pairing is
+/// locked until the pending one expires.
+const MAX_ATTEMPTS: i32 = 5;
+
+/// Why [`start`] printed no code.
+pub enum Refused {
+    /// The pending code was printed less than [`REISSUE_AFTER`] ago.
+    TooSoon,
+    /// Too many wrong codes were tried; pairing opens again when the pending code
+    /// expires.
+    Locked,
+}
+
+/// Replace any pending code with a new one for `printer_id`, returning it. The new
+/// code inherits the pending one's wrong attempts, so asking for codes again and
+/// again doesn't give more guesses.
+pub fn start(conn: &mut SqliteConnection, printer_id: i32) -> Result<Result<String, Refused>> {
+    let code = format!("{:06}", uuid::Uuid::new_v4().as_u128() % 1_000_000);
+    let now = Utc::now().naive_utc();
+    conn.immediate_transaction(|conn| {
+        diesel::delete(pairing_codes::table.filter(pairing_codes::expires_at.le(now)))
+            .execute(conn)?;
+        let pending: Option<(i32, NaiveDateTime)> = pairing_codes::table
+            .select((pairing_codes::attempts, pairing_codes::created_at))
+            .first(conn)
+            .optional()?;
+        let attempts = match pending {
+            Some((attempts, _)) if attempts >= MAX_ATTEMPTS => return Ok(Err(Refused::Locked)),
+            Some((_, created_at)) if now - created_at < REISSUE_AFTER => {
+                return Ok(Err(Refused::TooSoon));
+            }
+            Some((attempts, _)) => attempts,
+            None => 0,
+        };
+        diesel::delete(pairing_codes::table).execute(conn)?;
+        diesel::insert_into(pairing_codes::table)
+            .values((
+                pairing_codes::code.eq(&code),
+                pairing_codes::printer_id.eq(printer_id),
+                pairing_codes::attempts.eq(attempts),
+                pairing_codes::expires_at.eq(now + CODE_TTL),
+                pairing_codes::created_at.eq(now),
+            ))
+            .execute(conn)?;
+        Ok(Ok(code))
+    })
+}
+
+/// Exchange `code` for a new print-scoped API key named `name`. Wrong and expired
+/// codes, and any code once pairing is locked, give `None`; wrong ones count against
+/// the pending code.
+pub fn complete(
+    conn: &mut SqliteConnection,
+    code: &str,
+    name: &str,
+) -> Result<Option<(ApiKey, String)>> {
+    let code: String = code.chars().filter(char::is_ascii_digit).collect();
+    conn.transaction(|conn| {
+        diesel::delete(
+            pairing_codes::table.filter(pairing_codes::expires_at.le(Utc::now().naive_utc())),
+        )
+        .execute(conn)?;
+        let found = diesel::delete(
+            pairing_codes::table
+                .find(&code)
+                .filter(pairing_codes::attempts.lt(MAX_ATTEMPTS)),
+        )
+        .execute(conn)?
+            > 0;
+        if found {
+            return Ok(Some(api_keys::create(conn, name, Scope::Print, None)?));
+        }
+        diesel::update(pairing_codes::table)
+            .set(pairing_codes::attempts.eq(pairing_codes::attempts + 1))
+            .execute(conn)?;
+        Ok(None)
+    })
+}
+
+/// The slip printed for `code`. With `url`, it carries a QR code opening the
+/// client's pairing page with the code filled in.
+pub fn document(code: &str, url: Option<&str>) -> Document {
+    let centered = |text: String| Block::Text {
+        text,
+        spans: Vec::new(),
+        style: Style {
+            align: Align::Center,
+            ..Style::default()
+        },
+    };
+    let mut blocks = vec![
+        Block::Heading {
+            text: "Pair a device".into(),
+            level: 1,
+            align: Align::Center,
+        },
+        centered("Enter this code on the device:".into()),
+        Block::Heading {
+            text: format!("{} {}", &code[..3], &code[3..]),
+            level: 1,
+            align: Align::Center,
+        },
+    ];
+    if let Some(url) = url {
+        let separator = if url.contains('?') { '&' } else { '?' };
+        blocks.push(Block::Qr {
+            data: format!("{url}{separator}code={code}"),
+            size: 4,
+            align: Align::Center,
+        });
+    }
+    let expires = Local::now() + CODE_TTL;
+    blocks.push(centered(format!("Valid until {}", expires.format("%H:%M"))));
+    blocks.push(Block::Cut { partial: false });
+    Document {
+        blocks,
+        ..Document::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::printers::{self, NewPrinter};
+
+    fn printer(conn: &mut SqliteConnection) -> i32 {
+        printers::create(
+            conn,
+            serde_json::from_value::<NewPrinter>(serde_json::json!({
+                "name": "desk",
+                "transport": "usb_lp",
+                "path": "/dev/usb/lp0",
+            }))
+            .unwrap(),
+        )
+        .unwrap()
+        .id
+    }
+
+    /// Pretend the pending code was printed `ago`.
+    fn printed(conn: &mut SqliteConnection, ago: TimeDelta) {
+        diesel::update(pairing_codes::table)
+            .set(pairing_codes::created_at.eq(Utc::now().naive_utc() - ago))
+            .execute(conn)
+            .unwrap();
+    }
+
+    #[test]
+    fn codes_are_printed_at_most_once_a_minute() {
+        let mut conn = db::test_connection();
+        let printer_id = printer(&mut conn);
+        assert!(start(&mut conn, printer_id).unwrap().is_ok());
+        assert!(matches!(
+            start(&mut conn, printer_id).unwrap(),
+            Err(Refused::TooSoon)
+        ));
+
+        printed(&mut conn, REISSUE_AFTER);
+        let code = start(&mut conn, printer_id).unwrap().ok().unwrap();
+        let pending: Vec<String> = pairing_codes::table
+            .select(pairing_codes::code)
+            .load(&mut conn)
+            .unwrap();
+        assert_eq!(pending, [code]);
+    }
+
+    #[test]
+    fn wrong_codes_count_across_new_codes() {
+        let mut conn = db::test_connection();
+        let printer_id = printer(&mut conn);
+        start(&mut conn, printer_id).unwrap().ok().unwrap();
+        for _ in 0..3 {
+            assert!(complete(&mut conn, "wrong", "tablet").unwrap().is_none());
+        }
+
+        printed(&mut conn, REISSUE_AFTER);
+        let code = start(&mut conn, printer_id).unwrap().ok().unwrap();
+        for _ in 0..2 {
+            assert!(complete(&mut conn, "wrong", "tablet").unwrap().is_none());
+        }
+        assert!(complete(&mut conn, &code, "tablet").unwrap().is_none());
+        printed(&mut conn, REISSUE_AFTER);
+        assert!(matches!(
+            start(&mut conn, printer_id).unwrap(),
+            Err(Refused::Locked)
+        ));
+    }
+
+    #[test]
+    fn the_right_code_issues_a_print_key() {
+        let mut conn = db::test_connection();
+        let printer_id = printer(&mut conn);
+        // The first key issued is an admin key whoever asks for it.
+        api_keys::create(&mut conn, "laptop", Scope::Admin, None).unwrap();
+        let code = start(&mut conn, printer_id).unwrap().ok().unwrap();
+        assert!(complete(&mut conn, "wrong", "tablet").unwrap().is_none());
+
+        let (key, _) = complete(&mut conn, &code, "tablet").unwrap().unwrap();
+        assert_eq!(key.scope, Scope::Print.as_str());
+        assert!(complete(&mut conn, &code, "tablet").unwrap().is_none());
+    }
+}
@@ -0,0 +1,375 @@
+use anyhow::{Result, bail};
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize, Serializer};
+use utoipa::ToSchema;
+
+use crate::document::{Document, Fragment};
+use crate::error::FieldError;
+use crate::model::Transport;
+use crate::render::charset::CodePages;
+use crate::render::emoji::Emoji;
+use crate::schema::{jobs, printers, schedules};
+use crate::templates::json_text;
+
+pub mod monitor;
+pub mod quiet;
+
+use quiet::QuietHours;
+
+/// A registered output device.
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize, ToSchema)]
+#[diesel(table_name = printers)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Printer {
+    pub id: i32,
+    pub name: String,
+    /// `usb_lp`, `serial` or `virtual_display`, matching [`Transport`].
+    pub transport: String,
+    /// Device node, or the push destination URL for virtual displays.
+    pub path: String,
+    pub enabled: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// Friendly name shown instead of `name`, e.g. "Kitchen".
+    pub display_name: Option<String>,
+    /// Emoji shown next to the display name.
+    pub icon: Option<String>,
+    /// Free-form placement, e.g. "Ground floor".
+    pub location: Option<String>,
+    /// Identical jobs submitted within this many minutes of each other are dropped.
+    pub dedup_window_mins: Option<i32>,
+    /// JSON list of [`quiet::QuietWindow`]s.
+    #[serde(serialize_with = "quiet_hours_json")]
+    #[schema(value_type = Vec<quiet::QuietWindow>)]
+    pub quiet_hours: String,
+    /// Queued jobs are held, not printed, until the printer is resumed.
+    pub paused: bool,
+    /// Accepts user-defined characters (`ESC &`), so glyphs are downloaded once per
+    /// job instead of being sent as raster images each time they appear.
+    pub user_glyphs: bool,
+    /// Prints QR codes itself (`GS ( k`); otherwise they are sent as raster images.
+    pub native_qr: bool,
+    /// Set while the printer sits in the trash; it can be restored until the
+    /// retention window passes, after which it is purged with its job history.
+    pub deleted_at: Option<NaiveDateTime>,
+    /// JSON [`Fragment`] printed at the top of every job.
+    #[serde(serialize_with = "json_text")]
+    #[schema(value_type = Vec<crate::document::Block>)]
+    pub header: String,
+    /// JSON [`Fragment`] printed at the end of every job, before the cut.
+    #[serde(serialize_with = "json_text")]
+    #[schema(value_type = Vec<crate::document::Block>)]
+    pub footer: String,
+    /// Printed in place of characters the printer's code page has no equivalent
+    /// for, such as emoji.
+    pub replacement_char: String,
+    /// JSON list of the [`CodePages`] the printer can select, in order of preference.
+    #[serde(serialize_with = "json_text")]
+    #[schema(value_type = Vec<crate::render::charset::CodePage>)]
+    pub code_pages: String,
+    /// See [`Emoji`].
+    pub emoji: String,
+    /// Supports page mode (`ESC L`), so page blocks are laid out at their exact
+    /// positions; otherwise they are printed on a grid of character cells.
+    pub page_mode: bool,
+    /// Prints upside down (`ESC {`) and last line first, for paper that feeds
+    /// downwards.
+    pub rotate_180: bool,
+}
+
+fn quiet_hours_json<S: Serializer>(text: &str, s: S) -> Result<S::Ok, S::Error> {
+    serde_json::from_str::<QuietHours>(text)
+        .map_err(serde::ser::Error::custom)?
+        .serialize(s)
+}
+
+impl Printer {
+    pub fn transport(&self) -> Result<Transport> {
+        let path = self.path.clone();
+        match self.transport.as_str() {
+            "usb_lp" => Ok(Transport::UsbLp { path }),
+            "serial" => Ok(Transport::Serial { path }),
+            "virtual_display" => Ok(Transport::VirtualDisplay { url: path }),
+            other => bail!("unknown transport '{other}'"),
+        }
+    }
+
+    /// Human-facing name: the display name if set, else the registered name.
+    pub fn title(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Title with its icon, e.g. "🍳 Kitchen".
+    pub fn label(&self) -> String {
+        match &self.icon {
+            Some(icon) => format!("{icon} {}", self.title()),
+            None => self.title().to_string(),
+        }
+    }
+
+    /// Plain-text attribution for printouts, e.g. "Kitchen - Ground floor". Icons are
+    /// left out because they are not in the printer's character set.
+    pub fn attribution(&self) -> String {
+        match &self.location {
+            Some(location) => format!("{} - {location}", self.title()),
+            None => self.title().to_string(),
+        }
+    }
+
+    pub fn emoji(&self) -> Emoji {
+        self.emoji.parse().unwrap_or_default()
+    }
+
+    pub fn quiet_hours(&self) -> QuietHours {
+        serde_json::from_str(&self.quiet_hours).unwrap_or_default()
+    }
+
+    /// Wrap `document` in this printer's header and footer.
+    pub fn frame(&self, document: &mut Document) {
+        let header = serde_json::from_str(&self.header).unwrap_or_default();
+        let footer = serde_json::from_str(&self.footer).unwrap_or_default();
+        document.frame(header, footer);
+    }
+}
+
+#[derive(Debug, Deserialize, Insertable, AsChangeset, ToSchema)]
+#[diesel(table_name = printers)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewPrinter {
+    pub name: String,
+    pub transport: String,
+    pub path: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub location: Option<String>,
+    #[serde(default)]
+    pub dedup_window_mins: Option<i32>,
+    #[serde(default)]
+    #[diesel(serialize_as = String)]
+    #[schema(value_type = Vec<quiet::QuietWindow>)]
+    pub quiet_hours: QuietHours,
+    #[serde(default)]
+    pub user_glyphs: bool,
+    #[serde(default = "default_native_qr")]
+    pub native_qr: bool,
+    #[serde(default)]
+    #[diesel(serialize_as = String)]
+    #[schema(value_type = Vec<crate::document::Block>)]
+    pub header: Fragment,
+    #[serde(default)]
+    #[diesel(serialize_as = String)]
+    #[schema(value_type = Vec<crate::document::Block>)]
+    pub footer: Fragment,
+    #[serde(default = "default_replacement_char")]
+    pub replacement_char: String,
+    #[serde(default)]
+    #[diesel(serialize_as = String)]
+    #[schema(value_type = Vec<crate::render::charset::CodePage>)]
+    pub code_pages: CodePages,
+    #[serde(default = "default_emoji")]
+    pub emoji: String,
+    #[serde(default)]
+    pub page_mode: bool,
+    #[serde(default)]
+    pub rotate_180: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_native_qr() -> bool {
+    true
+}
+
+fn default_replacement_char() -> String {
+    "?".into()
+}
+
+fn default_emoji() -> String {
+    Emoji::default().as_str().into()
+}
+
+impl NewPrinter {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        if self.name.trim().is_empty() {
+            return Err(FieldError::new("name", "must not be empty"));
+        }
+        match self.transport.as_str() {
+            "usb_lp" | "serial" if self.path.trim().is_empty() => {
+                return Err(FieldError::new("path", "must not be empty"));
+            }
+            "usb_lp" | "serial" => {}
+            "virtual_display" => {
+                if !["http://", "https://", "mqtt://"]
+                    .iter()
+                    .any(|scheme| self.path.starts_with(scheme))
+                {
+                    return Err(FieldError::new(
+                        "path",
+                        "must be an http(s):// or mqtt:// URL for a virtual_display",
+                    ));
+                }
+            }
+            _ => {
+                return Err(FieldError::new(
+                    "transport",
+                    "must be one of: usb_lp, serial, virtual_display",
+                ));
+            }
+        }
+        if self.dedup_window_mins.is_some_and(|m| m <= 0) {
+            return Err(FieldError::new("dedup_window_mins", "must be positive"));
+        }
+        self.quiet_hours
+            .validate()
+            .map_err(|e| FieldError::new("quiet_hours", e))?;
+        self.header.validate().map_err(|e| e.within("header"))?;
+        self.footer.validate().map_err(|e| e.within("footer"))?;
+        self.code_pages
+            .validate()
+            .map_err(|e| FieldError::new("code_pages", e))?;
+        if self.emoji.parse::<Emoji>().is_err() {
+            return Err(FieldError::new("emoji", "must be one of: raster, strip"));
+        }
+        let replacement = self.replacement_char.as_bytes();
+        if !matches!(replacement, [b] if b.is_ascii_graphic() || *b == b' ') {
+            return Err(FieldError::new(
+                "replacement_char",
+                "must be a single ASCII character",
+            ));
+        }
+        if self.icon.as_ref().is_some_and(|i| i.chars().count() > 8) {
+            return Err(FieldError::new("icon", "must be a single emoji or symbol"));
+        }
+        Ok(())
+    }
+}
+
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Printer>> {
+    let rows = printers::table
+        .filter(printers::deleted_at.is_null())
+        .order(printers::id.asc())
+        .select(Printer::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+/// Printers in the trash, most recently deleted first.
+pub fn list_deleted(conn: &mut SqliteConnection) -> Result<Vec<Printer>> {
+    let rows = printers::table
+        .filter(printers::deleted_at.is_not_null())
+        .order(printers::deleted_at.desc())
+        .select(Printer::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn get(conn: &mut SqliteConnection, id: i32) -> Result<Option<Printer>> {
+    let row = printers::table
+        .find(id)
+        .filter(printers::deleted_at.is_null())
+        .select(Printer::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn create(conn: &mut SqliteConnection, new: NewPrinter) -> Result<Printer> {
+    let row = diesel::insert_into(printers::table)
+        .values(new)
+        .returning(Printer::as_returning())
+        .get_result(conn)?;
+    Ok(row)
+}
+
+pub fn set_paused(conn: &mut SqliteConnection, id: i32, paused: bool) -> Result<Option<Printer>> {
+    let row = diesel::update(
+        printers::table
+            .find(id)
+            .filter(printers::deleted_at.is_null()),
+    )
+    .set((
+        printers::paused.eq(paused),
+        printers::updated_at.eq(Utc::now().naive_utc()),
+    ))
+    .returning(Printer::as_returning())
+    .get_result(conn)
+    .optional()?;
+    Ok(row)
+}
+
+pub fn update(conn: &mut SqliteConnection, id: i32, new: NewPrinter) -> Result<Option<Printer>> {
+    let row = diesel::update(
+        printers::table
+            .find(id)
+            .filter(printers::deleted_at.is_null()),
+    )
+    .set((new, printers::updated_at.eq(Utc::now().naive_utc())))
+    .returning(Printer::as_returning())
+    .get_result(conn)
+    .optional()?;
+    Ok(row)
+}
+
+/// Move a printer to the trash. Its jobs, webhooks and subscriptions are kept, but
+/// it stops printing and no longer appears in listings.
+pub fn delete(conn: &mut SqliteConnection, id: i32) -> Result<bool> {
+    let n = diesel::update(
+        printers::table
+            .find(id)
+            .filter(printers::deleted_at.is_null()),
+    )
+    .set(printers::deleted_at.eq(Utc::now().naive_utc()))
+    .execute(conn)?;
+    Ok(n > 0)
+}
+
+/// Take a printer out of the trash, provided it was deleted no earlier than
+/// `deleted_since`.
+pub fn restore(
+    conn: &mut SqliteConnection,
+    id: i32,
+    deleted_since: NaiveDateTime,
+) -> Result<Option<Printer>> {
+    let row = diesel::update(
+        printers::table
+            .find(id)
+            .filter(printers::deleted_at.ge(deleted_since)),
+    )
+    .set((
+        printers::deleted_at.eq(None::<NaiveDateTime>),
+        printers::updated_at.eq(Utc::now().naive_utc()),
+    ))
+    .returning(Printer::as_returning())
+    .get_result(conn)
+    .optional()?;
+    Ok(row)
+}
+
+/// Permanently remove printers deleted before `deleted_before`, along with their job
+/// history. Schedules that targeted them are left without a printer. Returns the
+/// number of printers removed.
+pub fn purge(conn: &mut SqliteConnection, deleted_before: NaiveDateTime) -> Result<usize> {
+    conn.immediate_transaction(|conn| {
+        let ids: Vec<i32> = printers::table
+            .filter(printers::deleted_at.lt(deleted_before))
+            .select(printers::id)
+            .load(conn)?;
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        diesel::delete(jobs::table.filter(jobs::printer_id.eq_any(&ids))).execute(conn)?;
+        diesel::update(schedules::table.filter(schedules::printer_id.eq_any(&ids)))
+            .set(schedules::printer_id.eq(None::<i32>))
+            .execute(conn)?;
+        let n = diesel::delete(printers::table.filter(printers::id.eq_any(&ids))).execute(conn)?;
+        Ok(n)
+    })
+}
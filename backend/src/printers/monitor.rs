@@ -0,0 +1,93 @@
+//! Watches printers' devices come and go. A USB or serial printer is online while
+//! its device node exists, and devices that look like printers being plugged in or
+//! out are announced so a client can offer to set one up.
+
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::discover::{DefaultDiscovery, DiscoveryProvider};
+use crate::events::{Event, EventBus};
+use crate::model::Transport;
+use crate::{db, printers};
+
+/// How often device nodes are looked for.
+const POLL: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+struct Seen {
+    /// Device nodes found last time; unset before the first look, so the devices
+    /// already attached at startup aren't announced.
+    devices: Option<HashSet<String>>,
+    online: HashMap<i32, bool>,
+}
+
+/// When each USB or serial printer's device was last found, for readiness checks.
+#[derive(Clone, Default)]
+pub struct LastSeen(Arc<Mutex<HashMap<i32, Instant>>>);
+
+impl LastSeen {
+    pub fn get(&self, printer_id: i32) -> Option<Instant> {
+        self.0.lock().unwrap().get(&printer_id).copied()
+    }
+}
+
+pub fn spawn(events: EventBus, last_seen: LastSeen) {
+    tokio::spawn(async move {
+        let mut seen = Seen::default();
+        loop {
+            if let Err(err) = check(&events, &mut seen, &last_seen).await {
+                warn!("checking for printer devices failed: {err:#}");
+            }
+            tokio::time::sleep(POLL).await;
+        }
+    });
+}
+
+async fn check(events: &EventBus, seen: &mut Seen, last_seen: &LastSeen) -> Result<()> {
+    let found = tokio::task::spawn_blocking(|| DefaultDiscovery.discover_default()).await??;
+    let found: HashSet<String> = found
+        .iter()
+        .filter_map(|c| c.transport_path())
+        .map(String::from)
+        .collect();
+    if let Some(known) = &seen.devices {
+        for (paths, attached) in [
+            (found.difference(known), true),
+            (known.difference(&found), false),
+        ] {
+            for path in paths {
+                events.publish(Event::Hotplug {
+                    path: path.clone(),
+                    attached,
+                });
+            }
+        }
+    }
+    seen.devices = Some(found);
+
+    for printer in db::run_blocking_db(printers::list).await? {
+        let path = match printer.transport() {
+            Ok(Transport::UsbLp { path } | Transport::Serial { path }) => path,
+            _ => continue,
+        };
+        let online = Path::new(&path).exists();
+        if online {
+            last_seen
+                .0
+                .lock()
+                .unwrap()
+                .insert(printer.id, Instant::now());
+        }
+        if seen.online.insert(printer.id, online) != Some(online) {
+            events.publish(Event::PrinterOnline {
+                printer_id: printer.id,
+                online,
+            });
+        }
+    }
+    Ok(())
+}
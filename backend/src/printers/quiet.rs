@@ -0,0 +1,153 @@
+use chrono::{NaiveDateTime, NaiveTime, TimeDelta};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use utoipa::ToSchema;
+
+/// Daily windows, in local time, during which a printer only prints urgent jobs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct QuietHours(pub Vec<QuietWindow>);
+
+/// `start` to `end`; a window whose end is before its start runs past midnight,
+/// e.g. 22:00 to 07:00.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct QuietWindow {
+    #[serde(serialize_with = "hh_mm", deserialize_with = "parse_hh_mm")]
+    #[schema(value_type = String, example = "22:00")]
+    pub start: NaiveTime,
+    #[serde(serialize_with = "hh_mm", deserialize_with = "parse_hh_mm")]
+    #[schema(value_type = String, example = "07:00")]
+    pub end: NaiveTime,
+}
+
+fn hh_mm<S: Serializer>(time: &NaiveTime, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&time.format("%H:%M").to_string())
+}
+
+fn parse_hh_mm<'de, D: Deserializer<'de>>(d: D) -> Result<NaiveTime, D::Error> {
+    let text = String::deserialize(d)?;
+    NaiveTime::parse_from_str(&text, "%H:%M")
+        .map_err(|_| serde::de::Error::custom("quiet hours must be formatted HH:MM"))
+}
+
+impl QuietWindow {
+    /// End of this window if `now` falls inside it.
+    fn active_until(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let time = now.time();
+        let today_end = now.date().and_time(self.end);
+        if self.start <= self.end {
+            (self.start <= time && time < self.end).then_some(today_end)
+        } else if time >= self.start {
+            Some(today_end + TimeDelta::days(1))
+        } else {
+            (time < self.end).then_some(today_end)
+        }
+    }
+}
+
+impl QuietHours {
+    /// When the quiet period covering `now` (local time) ends, or `None` outside quiet
+    /// hours. Overlapping windows extend each other.
+    pub fn active_until(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut until = self.0.iter().filter_map(|w| w.active_until(now)).max()?;
+        // Follow chains such as 22:00-00:00 then 00:00-07:00.
+        while let Some(later) = self
+            .0
+            .iter()
+            .filter_map(|w| w.active_until(until))
+            .filter(|end| *end > until)
+            .max()
+        {
+            until = later;
+        }
+        Some(until)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.0.iter().any(|w| w.start == w.end) {
+            return Err("windows must not start and end at the same time".into());
+        }
+        Ok(())
+    }
+}
+
+impl From<QuietHours> for String {
+    fn from(hours: QuietHours) -> Self {
+        serde_json::to_string(&hours).unwrap_or_else(|_| "[]".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hours(windows: &[(&str, &str)]) -> QuietHours {
+        let windows = windows
+            .iter()
+            .map(|(start, end)| serde_json::json!({ "start": start, "end": end }))
+            .collect();
+        serde_json::from_value(serde_json::Value::Array(windows)).unwrap()
+    }
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn windows_start_inclusive_and_end_exclusive() {
+        let quiet = hours(&[("12:00", "13:00")]);
+        assert_eq!(quiet.active_until(at("2024-05-01 11:59")), None);
+        assert_eq!(
+            quiet.active_until(at("2024-05-01 12:00")),
+            Some(at("2024-05-01 13:00"))
+        );
+        assert_eq!(
+            quiet.active_until(at("2024-05-01 12:59")),
+            Some(at("2024-05-01 13:00"))
+        );
+        assert_eq!(quiet.active_until(at("2024-05-01 13:00")), None);
+    }
+
+    #[test]
+    fn windows_past_midnight_end_the_next_morning() {
+        let quiet = hours(&[("22:00", "07:00")]);
+        assert_eq!(quiet.active_until(at("2024-05-01 21:59")), None);
+        assert_eq!(
+            quiet.active_until(at("2024-05-01 22:00")),
+            Some(at("2024-05-02 07:00"))
+        );
+        assert_eq!(
+            quiet.active_until(at("2024-05-01 23:59")),
+            Some(at("2024-05-02 07:00"))
+        );
+        assert_eq!(
+            quiet.active_until(at("2024-05-02 00:00")),
+            Some(at("2024-05-02 07:00"))
+        );
+        assert_eq!(
+            quiet.active_until(at("2024-05-02 06:59")),
+            Some(at("2024-05-02 07:00"))
+        );
+        assert_eq!(quiet.active_until(at("2024-05-02 07:00")), None);
+        assert_eq!(quiet.active_until(at("2024-05-02 12:00")), None);
+    }
+
+    #[test]
+    fn windows_that_meet_run_together() {
+        let quiet = hours(&[("00:00", "07:00"), ("22:00", "00:00")]);
+        assert_eq!(
+            quiet.active_until(at("2024-05-01 23:00")),
+            Some(at("2024-05-02 07:00"))
+        );
+        let quiet = hours(&[("22:00", "01:00"), ("00:30", "06:00")]);
+        assert_eq!(
+            quiet.active_until(at("2024-05-01 22:30")),
+            Some(at("2024-05-02 06:00"))
+        );
+    }
+
+    #[test]
+    fn empty_windows_are_refused() {
+        assert!(hours(&[("22:00", "07:00")]).validate().is_ok());
+        assert!(hours(&[("07:00", "07:00")]).validate().is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+use super::{Message, Overflow};
+
+/// Counters for one subscription since it was (re)started.
+#[derive(Debug, Default)]
+pub struct Stats {
+    received: AtomicU64,
+    dropped_rate: AtomicU64,
+    dropped_overflow: AtomicU64,
+    submitted: AtomicU64,
+    connected: AtomicBool,
+    last_error: Mutex<Option<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatsSnapshot {
+    pub connected: bool,
+    pub received: u64,
+    /// Rejected on arrival for exceeding `rate_per_min`.
+    pub dropped_rate: u64,
+    /// Discarded because the buffer was full.
+    pub dropped_overflow: u64,
+    pub submitted: u64,
+    /// Messages waiting for the printer right now.
+    pub buffered: usize,
+    pub last_error: Option<String>,
+}
+
+impl Stats {
+    pub fn set_connected(&self, connected: bool) {
+        self.connected.store(connected, Ordering::Relaxed);
+        if connected {
+            *self.last_error.lock().unwrap() = None;
+        }
+    }
+
+    pub fn set_error(&self, error: String) {
+        self.connected.store(false, Ordering::Relaxed);
+        *self.last_error.lock().unwrap() = Some(error);
+    }
+
+    pub fn record_submitted(&self) {
+        self.submitted.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Token bucket allowing `per_min` messages a minute, with bursts up to the same size.
+struct RateLimit {
+    per_min: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl RateLimit {
+    fn new(per_min: u32) -> Self {
+        Self {
+            per_min: per_min as f64,
+            tokens: per_min as f64,
+            refilled: Instant::now(),
+        }
+    }
+
+    fn allow(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_min / 60.0).min(self.per_min);
+        self.refilled = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Bounded hand-off between a consumer connection and the task that submits jobs.
+pub struct Buffer {
+    messages: Mutex<VecDeque<Message>>,
+    limit: Mutex<RateLimit>,
+    capacity: usize,
+    overflow: Overflow,
+    notify: Notify,
+    pub stats: Stats,
+}
+
+impl Buffer {
+    pub fn new(capacity: usize, overflow: Overflow, rate_per_min: u32) -> Self {
+        Self {
+            messages: Mutex::new(VecDeque::with_capacity(capacity)),
+            limit: Mutex::new(RateLimit::new(rate_per_min)),
+            capacity: capacity.max(1),
+            overflow,
+            notify: Notify::new(),
+            stats: Stats::default(),
+        }
+    }
+
+    /// Accept a message from the source. Never blocks the connection.
+    pub fn offer(&self, message: Message) {
+        self.stats.received.fetch_add(1, Ordering::Relaxed);
+        if !self.limit.lock().unwrap().allow() {
+            self.stats.dropped_rate.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        let mut messages = self.messages.lock().unwrap();
+        if messages.len() >= self.capacity {
+            self.stats.dropped_overflow.fetch_add(1, Ordering::Relaxed);
+            match self.overflow {
+                Overflow::DropNewest => return,
+                Overflow::DropOldest => {
+                    messages.pop_front();
+                }
+            }
+        }
+        messages.push_back(message);
+        drop(messages);
+        self.notify.notify_one();
+    }
+
+    pub async fn take(&self) -> Message {
+        loop {
+            if let Some(message) = self.messages.lock().unwrap().pop_front() {
+                return message;
+            }
+            self.notify.notified().await;
+        }
+    }
+
+    pub fn snapshot(&self) -> StatsSnapshot {
+        StatsSnapshot {
+            connected: self.stats.connected.load(Ordering::Relaxed),
+            received: self.stats.received.load(Ordering::Relaxed),
+            dropped_rate: self.stats.dropped_rate.load(Ordering::Relaxed),
+            dropped_overflow: self.stats.dropped_overflow.load(Ordering::Relaxed),
+            submitted: self.stats.submitted.load(Ordering::Relaxed),
+            buffered: self.messages.lock().unwrap().len(),
+            last_error: self.stats.last_error.lock().unwrap().clone(),
+        }
+    }
+}
+
+/// Delay before reconnecting after `failures` consecutive connection errors.
+pub fn backoff(failures: u32) -> Duration {
+    Duration::from_secs(5u64.saturating_mul(1 << failures.min(4)).min(60))
+}
@@ -0,0 +1,400 @@
+//! Polls an IMAP mailbox for unread mail, so the household can email the printer.
+//!
+//! Only the handful of commands this needs are spoken: `LOGIN`, `SELECT`, and `UID`
+//! `SEARCH`, `FETCH` and `STORE`. A printed mail is flagged `\Seen`; mail the filters
+//! reject is left unread for whoever else reads the mailbox.
+
+use anyhow::{Context, Result, anyhow, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::{DateTime, Local};
+use mail_parser::{Address, MessageParser, MimeHeaders};
+use reqwest::Url;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore, crypto};
+use tracing::warn;
+
+use super::buffer::Buffer;
+use super::{Message, Subscription};
+use crate::render::photo::{self, PhotoOptions};
+
+/// How often the mailbox is searched for new mail.
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+/// Longest wait for the server to answer a command.
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+/// Longest response line accepted before the connection is treated as garbage.
+const MAX_LINE: usize = 64 * 1024;
+/// Mails larger than this, attachments included, are not fetched.
+const MAX_MAIL: usize = 25 * 1024 * 1024;
+/// Characters of the body printed before the rest is cut off.
+const MAX_BODY_CHARS: usize = 3000;
+/// Attached pictures printed per mail.
+const MAX_IMAGES: usize = 4;
+
+/// Log in, select the mailbox and turn unread mail into messages until the
+/// connection fails. Attached pictures may print up to `max_height` dots tall.
+pub(super) async fn consume(sub: &Subscription, buffer: &Buffer, max_height: u32) -> Result<()> {
+    let url = Url::parse(&sub.url()?).with_context(|| format!("invalid IMAP URL {}", sub.url))?;
+    let mut session = Session::connect(&url).await?;
+    session
+        .command(&format!(
+            "LOGIN {} {}",
+            quote(&percent_decode(url.username()))?,
+            quote(&percent_decode(url.password().unwrap_or_default()))?
+        ))
+        .await?;
+    session
+        .command(&format!("SELECT {}", quote(&sub.topic)?))
+        .await?;
+    buffer.stats.set_connected(true);
+
+    let filter = Arc::new(Filter::new(sub));
+    // Unread mail the filters rejected, so it is not fetched on every poll.
+    let mut rejected = HashSet::new();
+    loop {
+        for uid in session.unseen().await? {
+            if rejected.contains(&uid) {
+                continue;
+            }
+            let Some(raw) = session.fetch(uid).await? else {
+                warn!("subscription {} skipped mail {uid}: too large", sub.id);
+                rejected.insert(uid);
+                continue;
+            };
+            let filter = filter.clone();
+            let message =
+                tokio::task::spawn_blocking(move || to_message(&filter, &raw, max_height)).await?;
+            match message {
+                Some(message) => {
+                    buffer.offer(message);
+                    session
+                        .command(&format!("UID STORE {uid} +FLAGS (\\Seen)"))
+                        .await?;
+                }
+                None => {
+                    rejected.insert(uid);
+                }
+            }
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Which mails are printed, from the subscription's `subject_filter` and
+/// `allowed_senders`.
+struct Filter {
+    subject: Option<String>,
+    senders: Vec<String>,
+}
+
+impl Filter {
+    fn new(sub: &Subscription) -> Self {
+        Self {
+            subject: sub
+                .subject_filter
+                .as_deref()
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_lowercase),
+            senders: sub
+                .allowed_senders
+                .as_deref()
+                .unwrap_or_default()
+                .split(',')
+                .map(|s| s.trim().to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect(),
+        }
+    }
+
+    fn accepts(&self, sender: &str, subject: &str) -> bool {
+        let sender = sender.to_lowercase();
+        let sender_allowed = self.senders.is_empty()
+            || self
+                .senders
+                .iter()
+                .any(|allowed| match allowed.strip_prefix('@') {
+                    Some(domain) => sender.rsplit_once('@').is_some_and(|(_, d)| d == domain),
+                    None => sender == *allowed,
+                });
+        let subject_matches = self
+            .subject
+            .as_ref()
+            .is_none_or(|wanted| subject.to_lowercase().contains(wanted));
+        sender_allowed && subject_matches
+    }
+}
+
+/// The mail as a receipt: its subject as the title, then who sent it and when,
+/// the text, and its pictures. `None` if the filters reject it.
+fn to_message(filter: &Filter, raw: &[u8], max_height: u32) -> Option<Message> {
+    let mail = MessageParser::default().parse(raw)?;
+    let from = mail.from().and_then(Address::first);
+    let sender = from.and_then(|f| f.address()).unwrap_or_default();
+    let subject = mail.subject().unwrap_or_default();
+    if !filter.accepts(sender, subject) {
+        return None;
+    }
+
+    let mut body = String::new();
+    if let Some(from) = from {
+        match from.name() {
+            Some(name) => body.push_str(&format!("From: {name} <{sender}>\n")),
+            None => body.push_str(&format!("From: {sender}\n")),
+        }
+    }
+    if let Some(date) = mail
+        .date()
+        .and_then(|d| DateTime::from_timestamp(d.to_timestamp(), 0))
+    {
+        let date = date.with_timezone(&Local).format("%a %e %b %Y, %H:%M");
+        body.push_str(&format!("Date: {date}\n"));
+    }
+    body.push('\n');
+    let text = mail.body_text(0).unwrap_or_default();
+    let text = text.trim();
+    match text.char_indices().nth(MAX_BODY_CHARS) {
+        Some((end, _)) => {
+            body.push_str(text[..end].trim_end());
+            body.push_str(" [...]");
+        }
+        None => body.push_str(text),
+    }
+
+    let mut images = Vec::new();
+    let mut attached = Vec::new();
+    for part in mail.attachments() {
+        let is_image = part
+            .content_type()
+            .is_some_and(|ct| ct.ctype().eq_ignore_ascii_case("image"));
+        if is_image && images.len() < MAX_IMAGES {
+            let options = PhotoOptions {
+                rotate: true,
+                dither: true,
+                max_height,
+            };
+            match photo::prepare(part.contents(), options) {
+                Ok(png) => {
+                    images.push(BASE64.encode(png));
+                    continue;
+                }
+                Err(err) => warn!("attached picture could not be printed: {err}"),
+            }
+        }
+        attached.push(part.attachment_name().unwrap_or("a file"));
+    }
+    if !attached.is_empty() {
+        body.push_str(&format!("\n\nAttached: {}", attached.join(", ")));
+    }
+
+    Some(Message {
+        title: Some(subject.to_string()).filter(|s| !s.is_empty()),
+        body,
+        images,
+    })
+}
+
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+/// A server response line, with any literals it carried (`{n}` followed by `n`
+/// bytes) lifted out.
+struct Response {
+    line: String,
+    literals: Vec<Vec<u8>>,
+}
+
+struct Session {
+    stream: BufReader<Box<dyn Stream>>,
+    tag: u32,
+}
+
+impl Session {
+    /// Connect over TLS for `imaps://` (port 993) or in the clear for `imap://`
+    /// (port 143), and wait for the greeting.
+    async fn connect(url: &Url) -> Result<Self> {
+        let host = url
+            .host_str()
+            .ok_or_else(|| anyhow!("IMAP URL is missing a host"))?;
+        let tls = url.scheme() == "imaps";
+        let port = url.port().unwrap_or(if tls { 993 } else { 143 });
+        let tcp = TcpStream::connect((host, port)).await?;
+        let stream: Box<dyn Stream> = if tls {
+            let mut roots = RootCertStore::empty();
+            roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+            let config =
+                ClientConfig::builder_with_provider(Arc::new(crypto::ring::default_provider()))
+                    .with_safe_default_protocol_versions()?
+                    .with_root_certificates(roots)
+                    .with_no_client_auth();
+            let name = ServerName::try_from(host.to_string())?;
+            Box::new(
+                TlsConnector::from(Arc::new(config))
+                    .connect(name, tcp)
+                    .await?,
+            )
+        } else {
+            Box::new(tcp)
+        };
+
+        let mut session = Self {
+            stream: BufReader::new(stream),
+            tag: 0,
+        };
+        let greeting = session.read_response().await?;
+        if !greeting.line.starts_with("* OK") {
+            bail!("unexpected IMAP greeting: {}", greeting.line);
+        }
+        Ok(session)
+    }
+
+    /// Send a command and collect the untagged responses up to its tagged `OK`.
+    async fn command(&mut self, command: &str) -> Result<Vec<Response>> {
+        self.tag += 1;
+        let tag = format!("a{} ", self.tag);
+        self.stream
+            .write_all(format!("{tag}{command}\r\n").as_bytes())
+            .await?;
+        self.stream.flush().await?;
+
+        let mut responses = Vec::new();
+        loop {
+            let response = self.read_response().await?;
+            let Some(status) = response.line.strip_prefix(&tag) else {
+                responses.push(response);
+                continue;
+            };
+            if status.starts_with("OK") {
+                return Ok(responses);
+            }
+            // Only the verb, so a rejected LOGIN doesn't log the password.
+            let verb: Vec<&str> = command
+                .split(' ')
+                .take_while(|word| word.chars().all(|c| c.is_ascii_uppercase()))
+                .collect();
+            let verb = verb.join(" ");
+            bail!("IMAP {verb} failed: {status}");
+        }
+    }
+
+    /// UIDs of the unread mail in the selected mailbox.
+    async fn unseen(&mut self) -> Result<Vec<u32>> {
+        let responses = self.command("UID SEARCH UNSEEN").await?;
+        Ok(responses
+            .iter()
+            .filter_map(|r| r.line.strip_prefix("* SEARCH"))
+            .flat_map(|uids| uids.split_whitespace().filter_map(|uid| uid.parse().ok()))
+            .collect())
+    }
+
+    /// The raw mail, without marking it read; `None` if it is too large to print.
+    async fn fetch(&mut self, uid: u32) -> Result<Option<Vec<u8>>> {
+        let sizes = self
+            .command(&format!("UID FETCH {uid} RFC822.SIZE"))
+            .await?;
+        let size = sizes.iter().find_map(|r| {
+            let (_, rest) = r.line.split_once("RFC822.SIZE ")?;
+            rest.split(|c: char| !c.is_ascii_digit())
+                .next()?
+                .parse::<usize>()
+                .ok()
+        });
+        if size.is_none_or(|size| size > MAX_MAIL) {
+            return Ok(None);
+        }
+        let responses = self
+            .command(&format!("UID FETCH {uid} BODY.PEEK[]"))
+            .await?;
+        Ok(responses
+            .into_iter()
+            .find_map(|r| r.literals.into_iter().next()))
+    }
+
+    async fn read_response(&mut self) -> Result<Response> {
+        let mut response = Response {
+            line: String::new(),
+            literals: Vec::new(),
+        };
+        loop {
+            let mut raw = Vec::new();
+            let read = tokio::time::timeout(READ_TIMEOUT, self.read_line(&mut raw))
+                .await
+                .context("IMAP server stopped answering")??;
+            if read == 0 {
+                bail!("IMAP server closed the connection");
+            }
+            let line = String::from_utf8_lossy(&raw);
+            let line = line.trim_end_matches(['\r', '\n']);
+            response.line.push_str(line);
+            // A line ending in `{n}` continues after n bytes of literal data.
+            let Some(len) = line
+                .strip_suffix('}')
+                .and_then(|l| l.rsplit_once('{'))
+                .and_then(|(_, n)| n.parse::<usize>().ok())
+            else {
+                return Ok(response);
+            };
+            if len > MAX_MAIL {
+                bail!("IMAP server sent an over-long literal");
+            }
+            let mut literal = vec![0; len];
+            tokio::time::timeout(READ_TIMEOUT, self.stream.read_exact(&mut literal))
+                .await
+                .context("IMAP server stopped answering")??;
+            response.literals.push(literal);
+        }
+    }
+
+    /// Read up to and including the next newline, failing past [`MAX_LINE`].
+    async fn read_line(&mut self, buf: &mut Vec<u8>) -> Result<usize> {
+        let read = (&mut self.stream)
+            .take(MAX_LINE as u64)
+            .read_until(b'\n', buf)
+            .await?;
+        if read == MAX_LINE && buf.last() != Some(&b'\n') {
+            bail!("IMAP server sent an over-long line");
+        }
+        Ok(read)
+    }
+}
+
+/// An IMAP quoted string.
+fn quote(s: &str) -> Result<String> {
+    if s.contains(['\r', '\n']) {
+        bail!("IMAP names and logins cannot contain line breaks");
+    }
+    Ok(format!(
+        "\"{}\"",
+        s.replace('\\', "\\\\").replace('"', "\\\"")
+    ))
+}
+
+/// Undo the URL encoding of a user name or password, such as `%40` for `@`.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| u8::from_str_radix(std::str::from_utf8(h).ok()?, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -0,0 +1,176 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use super::buffer::{self, Buffer, StatsSnapshot};
+use super::{Subscription, imap, mqtt, ntfy};
+use crate::db;
+use crate::events::{self, Event, EventBus};
+use crate::jobs::{self, JobState, NewJob};
+use crate::queue::QueueManager;
+
+/// A live subscription: its buffer plus the connection and drain tasks.
+struct Running {
+    buffer: Arc<Buffer>,
+    tasks: [JoinHandle<()>; 2],
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Owns the connection and drain tasks of every enabled push subscription.
+pub struct PushManager {
+    running: Mutex<HashMap<i32, Running>>,
+    queue: Arc<QueueManager>,
+    events: EventBus,
+}
+
+impl PushManager {
+    pub fn new(queue: Arc<QueueManager>, events: EventBus) -> Self {
+        Self {
+            running: Mutex::new(HashMap::new()),
+            queue,
+            events,
+        }
+    }
+
+    /// Connect every enabled subscription. Called once at startup.
+    pub async fn start_all(&self) -> Result<()> {
+        let subs = db::run_blocking_db(super::list).await?;
+        for sub in subs.iter().filter(|s| s.enabled) {
+            self.restart(sub);
+        }
+        Ok(())
+    }
+
+    /// (Re)start `sub` with its current settings, or stop it if it is disabled.
+    /// Buffered messages and counters are discarded.
+    pub fn restart(&self, sub: &Subscription) {
+        self.stop(sub.id);
+        if !sub.enabled {
+            return;
+        }
+        info!(
+            "starting {} subscription {} ('{}')",
+            sub.kind, sub.id, sub.name
+        );
+        let buffer = Arc::new(Buffer::new(
+            sub.buffer_size.max(1) as usize,
+            sub.overflow(),
+            sub.rate_per_min.max(1) as u32,
+        ));
+        let tasks = [
+            tokio::spawn(connect(
+                sub.clone(),
+                buffer.clone(),
+                self.queue.limits().max_raster_height,
+            )),
+            tokio::spawn(drain(
+                sub.clone(),
+                buffer.clone(),
+                self.queue.clone(),
+                self.events.clone(),
+            )),
+        ];
+        self.running
+            .lock()
+            .unwrap()
+            .insert(sub.id, Running { buffer, tasks });
+    }
+
+    pub fn stop(&self, id: i32) {
+        self.running.lock().unwrap().remove(&id);
+    }
+
+    /// Counters for a running subscription.
+    pub fn status(&self, id: i32) -> Option<StatsSnapshot> {
+        self.running
+            .lock()
+            .unwrap()
+            .get(&id)
+            .map(|running| running.buffer.snapshot())
+    }
+}
+
+/// Keep the source connected, backing off between failed attempts. Mailed pictures
+/// taller than `max_height` dots once fitted are left out.
+async fn connect(sub: Subscription, buffer: Arc<Buffer>, max_height: u32) {
+    let mut failures = 0;
+    loop {
+        let started = Instant::now();
+        let result = match sub.kind.as_str() {
+            "ntfy" => ntfy::consume(&sub, &buffer).await,
+            "imap" => imap::consume(&sub, &buffer, max_height).await,
+            _ => mqtt::consume(&sub, &buffer).await,
+        };
+        if let Err(err) = result {
+            warn!("subscription {} disconnected: {err:#}", sub.id);
+            buffer.stats.set_error(format!("{err:#}"));
+        }
+        // A connection that held for a while resets the backoff.
+        if started.elapsed() > buffer::backoff(u32::MAX) {
+            failures = 0;
+        }
+        tokio::time::sleep(buffer::backoff(failures)).await;
+        failures += 1;
+    }
+}
+
+/// Turn buffered messages into jobs, one at a time: the next message is only
+/// submitted once the previous job has left the printer.
+async fn drain(sub: Subscription, buffer: Arc<Buffer>, queue: Arc<QueueManager>, events: EventBus) {
+    let mut rx = events.subscribe();
+    loop {
+        let message = buffer.take().await;
+        let payload = match message.to_payload(sub.format()) {
+            Ok(payload) => payload,
+            Err(err) => {
+                warn!("subscription {} dropped a message: {err}", sub.id);
+                continue;
+            }
+        };
+        let submitted = match NewJob::new(sub.printer_id, format!("push:{}", sub.id), &payload) {
+            Ok(new) => queue.submit(new).await,
+            Err(err) => Err(err),
+        };
+        match submitted {
+            Ok(submitted) => {
+                buffer.stats.record_submitted();
+                if !submitted.duplicate {
+                    wait_finished(&mut rx, submitted.job.id).await;
+                }
+            }
+            Err(err) => warn!("subscription {} could not submit a job: {err:#}", sub.id),
+        }
+    }
+}
+
+async fn wait_finished(rx: &mut events::Receiver, job_id: i32) {
+    let finished =
+        |state: &str| state == JobState::Done.as_str() || state == JobState::Failed.as_str();
+    loop {
+        match rx.recv().await {
+            Ok(Event::Job {
+                job_id: id, state, ..
+            }) if id == job_id && finished(state) => return,
+            Ok(_) => {}
+            Err(RecvError::Lagged(_)) => {
+                // The event may have been skipped; ask the database instead.
+                let job = db::run_blocking_db(move |conn| jobs::get(conn, job_id)).await;
+                if !matches!(job, Ok(Some(job)) if !finished(&job.state)) {
+                    return;
+                }
+            }
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
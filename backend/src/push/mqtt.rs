@@ -0,0 +1,41 @@
+use anyhow::{Context, Result, anyhow};
+use reqwest::Url;
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use std::time::Duration;
+
+use super::buffer::Buffer;
+use super::{Message, Subscription};
+
+/// Subscribe to the topic and feed every publish into the buffer until the broker
+/// connection fails.
+pub(super) async fn consume(sub: &Subscription, buffer: &Buffer) -> Result<()> {
+    let url = Url::parse(&sub.url()?).with_context(|| format!("invalid MQTT URL {}", sub.url))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow!("MQTT URL is missing a host"))?;
+
+    let mut options = MqttOptions::new(
+        format!("dayroll-push-{}", sub.id),
+        host,
+        url.port().unwrap_or(1883),
+    );
+    options.set_keep_alive(Duration::from_secs(30));
+    if !url.username().is_empty() {
+        options.set_credentials(url.username(), url.password().unwrap_or_default());
+    }
+
+    let (client, mut eventloop) = AsyncClient::new(options, 10);
+    client.subscribe(&sub.topic, QoS::AtMostOnce).await?;
+
+    loop {
+        match eventloop.poll().await? {
+            Event::Incoming(Packet::ConnAck(_)) => buffer.stats.set_connected(true),
+            Event::Incoming(Packet::Publish(publish)) => buffer.offer(Message {
+                title: None,
+                body: String::from_utf8_lossy(&publish.payload).into_owned(),
+                images: Vec::new(),
+            }),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::time::Duration;
+
+use super::buffer::Buffer;
+use super::{Message, Subscription};
+
+/// ntfy sends a keepalive every 45 s; anything much longer means the stream is dead.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+/// Longest JSON line accepted before the stream is treated as garbage.
+const MAX_LINE: usize = 256 * 1024;
+
+#[derive(Deserialize)]
+struct NtfyEvent {
+    event: String,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    message: Option<String>,
+}
+
+/// Stream `<url>/<topic>/json` until the connection drops.
+pub(super) async fn consume(sub: &Subscription, buffer: &Buffer) -> Result<()> {
+    let url = format!("{}/{}/json", sub.url()?.trim_end_matches('/'), sub.topic);
+    let mut response = reqwest::Client::new()
+        .get(&url)
+        .send()
+        .await?
+        .error_for_status()?;
+    buffer.stats.set_connected(true);
+
+    let mut pending = Vec::new();
+    loop {
+        let chunk = tokio::time::timeout(IDLE_TIMEOUT, response.chunk())
+            .await
+            .context("ntfy stream went quiet")??;
+        let Some(chunk) = chunk else {
+            bail!("ntfy closed the stream");
+        };
+        pending.extend_from_slice(&chunk);
+
+        while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=end).collect();
+            let Ok(event) = serde_json::from_slice::<NtfyEvent>(&line) else {
+                continue;
+            };
+            if event.event == "message" {
+                buffer.offer(Message {
+                    title: event.title,
+                    body: event.message.unwrap_or_default(),
+                    images: Vec::new(),
+                });
+            }
+        }
+        if pending.len() > MAX_LINE {
+            bail!("ntfy sent an over-long line");
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Dispatch order for the jobs waiting on one printer.
+
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+
+/// How a printer chooses its next job when several are waiting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Fairness {
+    /// Strictly oldest first.
+    Fifo,
+    /// Urgent jobs first, then take turns between sources, oldest first within
+    /// each, so a burst from one source cannot starve the others.
+    #[default]
+    RoundRobin,
+}
+
+impl FromStr for Fairness {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fifo" => Ok(Fairness::Fifo),
+            "round_robin" => Ok(Fairness::RoundRobin),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    job_id: i32,
+    urgent: bool,
+    source: String,
+}
+
+/// Waiting jobs, oldest first, plus when each waiting source was last served.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Backlog {
+    entries: VecDeque<Entry>,
+    last_served: HashMap<String, u64>,
+    turns: u64,
+}
+
+impl Backlog {
+    pub fn push(&mut self, job_id: i32, urgent: bool, source: String) {
+        self.entries.push_back(Entry {
+            job_id,
+            urgent,
+            source,
+        });
+    }
+
+    /// Remove the next job to print. With `urgent_only` set, only urgent jobs are
+    /// considered.
+    pub fn pop(&mut self, fairness: Fairness, urgent_only: bool) -> Option<i32> {
+        let index = self.pick(fairness, urgent_only)?;
+        let entry = self.entries.remove(index)?;
+        self.turns += 1;
+        self.last_served.insert(entry.source, self.turns);
+        // A source that stops waiting loses its place, so its next job is served
+        // ahead of sources that have been busy all along.
+        let entries = &self.entries;
+        self.last_served
+            .retain(|source, _| entries.iter().any(|e| &e.source == source));
+        Some(entry.job_id)
+    }
+
+    fn pick(&self, fairness: Fairness, urgent_only: bool) -> Option<usize> {
+        match fairness {
+            Fairness::Fifo => self.entries.iter().position(|e| e.urgent || !urgent_only),
+            Fairness::RoundRobin => {
+                let urgent_only = urgent_only || self.entries.iter().any(|e| e.urgent);
+                self.entries
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, e)| e.urgent || !urgent_only)
+                    .min_by_key(|(i, e)| {
+                        let served = self.last_served.get(&e.source).copied().unwrap_or(0);
+                        (served, *i)
+                    })
+                    .map(|(i, _)| i)
+            }
+        }
+    }
+
+    /// Waiting job ids in the order they will be printed.
+    pub fn order(&self, fairness: Fairness) -> Vec<i32> {
+        let mut rest = self.clone();
+        std::iter::from_fn(|| rest.pop(fairness, false)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backlog(jobs: &[(i32, bool, &str)]) -> Backlog {
+        let mut backlog = Backlog::default();
+        for &(job_id, urgent, source) in jobs {
+            backlog.push(job_id, urgent, source.into());
+        }
+        backlog
+    }
+
+    #[test]
+    fn round_robin_takes_turns_between_sources() {
+        let backlog = backlog(&[
+            (1, false, "hook"),
+            (2, false, "hook"),
+            (3, false, "hook"),
+            (4, false, "schedule"),
+            (5, false, "api"),
+            (6, false, "schedule"),
+        ]);
+        assert_eq!(backlog.order(Fairness::RoundRobin), [1, 4, 5, 2, 6, 3]);
+    }
+
+    #[test]
+    fn fifo_keeps_the_order_jobs_arrived_in() {
+        let backlog = backlog(&[
+            (1, false, "hook"),
+            (2, false, "hook"),
+            (3, true, "api"),
+            (4, false, "schedule"),
+        ]);
+        assert_eq!(backlog.order(Fairness::Fifo), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn urgent_jobs_go_first() {
+        let backlog = backlog(&[
+            (1, false, "hook"),
+            (2, true, "api"),
+            (3, false, "schedule"),
+            (4, true, "api"),
+            (5, true, "hook"),
+        ]);
+        // Taking turns still applies among the urgent jobs, and "hook" was served
+        // more recently than "schedule" once they are done.
+        assert_eq!(backlog.order(Fairness::RoundRobin), [2, 5, 4, 3, 1]);
+    }
+
+    #[test]
+    fn urgent_only_leaves_other_jobs_waiting() {
+        for fairness in [Fairness::Fifo, Fairness::RoundRobin] {
+            let mut backlog = backlog(&[(1, false, "hook"), (2, true, "api")]);
+            assert_eq!(backlog.pop(fairness, true), Some(2));
+            assert_eq!(backlog.pop(fairness, true), None);
+            assert_eq!(backlog.pop(fairness, false), Some(1));
+        }
+    }
+
+    #[test]
+    fn a_source_that_stops_waiting_loses_its_turn() {
+        let mut backlog = backlog(&[(1, false, "hook"), (2, false, "hook"), (3, false, "api")]);
+        assert_eq!(backlog.pop(Fairness::RoundRobin, false), Some(1));
+        assert_eq!(backlog.pop(Fairness::RoundRobin, false), Some(3));
+        // "api" had nothing waiting, so its new job is served before "hook"'s.
+        backlog.push(4, false, "api".into());
+        assert_eq!(backlog.order(Fairness::RoundRobin), [4, 2]);
+    }
+}
@@ -0,0 +1,227 @@
+//! Print-duration estimates learned from each printer's job history.
+
+use anyhow::Result;
+use chrono::Utc;
+use diesel::SqliteConnection;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::jobs::{self, Job};
+use crate::render::{self, Profile};
+
+/// Finished jobs used to fit a printer's throughput.
+const HISTORY: i64 = 50;
+/// Samples needed before the history is trusted over the defaults.
+const MIN_SAMPLES: usize = 3;
+/// Conservative defaults: a 9600 baud serial link moves roughly 1 byte per ms, and
+/// opening the device plus cutting costs about half a second.
+const DEFAULT_OVERHEAD_MS: f64 = 500.0;
+const DEFAULT_MS_PER_BYTE: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+pub struct Estimate {
+    /// Expected time for the printer to output this job.
+    pub estimated_duration_ms: u64,
+    /// Expected time from now until this job has finished printing, including
+    /// everything queued ahead of it.
+    pub eta_ms: u64,
+}
+
+/// Linear fit of `duration = overhead + bytes * ms_per_byte` for one printer.
+#[derive(Debug, Clone, Copy)]
+pub struct Throughput {
+    overhead_ms: f64,
+    ms_per_byte: f64,
+}
+
+impl Default for Throughput {
+    fn default() -> Self {
+        Self {
+            overhead_ms: DEFAULT_OVERHEAD_MS,
+            ms_per_byte: DEFAULT_MS_PER_BYTE,
+        }
+    }
+}
+
+impl Throughput {
+    pub fn learn(conn: &mut SqliteConnection, printer_id: i32) -> Result<Self> {
+        let samples: Vec<(f64, f64)> = jobs::recent_done(conn, printer_id, HISTORY)?
+            .iter()
+            .filter_map(|job| {
+                let bytes = job.bytes? as f64;
+                let ms = (job.finished_at? - job.started_at?).num_milliseconds() as f64;
+                (ms >= 0.0).then_some((bytes, ms))
+            })
+            .collect();
+        Ok(Self::fit(&samples))
+    }
+
+    fn fit(samples: &[(f64, f64)]) -> Self {
+        if samples.len() < MIN_SAMPLES {
+            return Self::default();
+        }
+        let n = samples.len() as f64;
+        let mean_x = samples.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = samples.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let var_x = samples
+            .iter()
+            .map(|(x, _)| (x - mean_x).powi(2))
+            .sum::<f64>();
+        if var_x == 0.0 {
+            // Every job was the same size; attribute it all to throughput.
+            return Self {
+                overhead_ms: 0.0,
+                ms_per_byte: mean_y / mean_x.max(1.0),
+            };
+        }
+        let cov = samples
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum::<f64>();
+        let ms_per_byte = (cov / var_x).max(0.0);
+        Self {
+            overhead_ms: (mean_y - ms_per_byte * mean_x).max(0.0),
+            ms_per_byte,
+        }
+    }
+
+    pub fn duration_ms(&self, bytes: usize) -> u64 {
+        (self.overhead_ms + self.ms_per_byte * bytes as f64).round() as u64
+    }
+}
+
+/// Size of a job on the wire: the recorded size once printed, otherwise a render.
+fn job_bytes(job: &Job) -> usize {
+    if let Some(bytes) = job.bytes {
+        return bytes as usize;
+    }
+    job.payload()
+        .and_then(|payload| render::render(&payload.into_document(), &Profile::default()))
+        .map(|rendered| rendered.bytes.len())
+        .unwrap_or_default()
+}
+
+/// A job in a printer's queue together with its timing estimate.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QueuedJob {
+    #[serde(flatten)]
+    pub job: Job,
+    #[serde(flatten)]
+    pub estimate: Estimate,
+}
+
+/// The job currently printing (if any) followed by `pending` in print order, each
+/// with an estimate. Pending ids that no longer resolve to a job are skipped.
+pub fn for_queue(
+    conn: &mut SqliteConnection,
+    printer_id: i32,
+    pending: &[i32],
+) -> Result<Vec<QueuedJob>> {
+    let model = Throughput::learn(conn, printer_id)?;
+    let mut out = Vec::with_capacity(pending.len() + 1);
+    let mut elapsed = 0;
+
+    if let Some(current) = jobs::printing(conn, printer_id)? {
+        let duration = model.duration_ms(job_bytes(&current));
+        let spent = current
+            .started_at
+            .map(|t| (Utc::now().naive_utc() - t).num_milliseconds().max(0) as u64)
+            .unwrap_or_default();
+        elapsed = duration.saturating_sub(spent);
+        out.push(QueuedJob {
+            job: current,
+            estimate: Estimate {
+                estimated_duration_ms: duration,
+                eta_ms: elapsed,
+            },
+        });
+    }
+
+    for &job_id in pending {
+        let Some(job) = jobs::get(conn, job_id)? else {
+            continue;
+        };
+        let duration = model.duration_ms(job_bytes(&job));
+        elapsed += duration;
+        out.push(QueuedJob {
+            job,
+            estimate: Estimate {
+                estimated_duration_ms: duration,
+                eta_ms: elapsed,
+            },
+        });
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::jobs::{JobPayload, NewJob};
+    use crate::printers::{self, NewPrinter};
+    use crate::schema::jobs as table;
+    use diesel::prelude::*;
+    use serde_json::json;
+
+    #[test]
+    fn too_little_history_uses_the_defaults() {
+        let model = Throughput::fit(&[(100.0, 5000.0), (200.0, 9000.0)]);
+        assert_eq!(model.duration_ms(1000), 1500);
+    }
+
+    #[test]
+    fn history_fits_overhead_and_throughput() {
+        let model = Throughput::fit(&[(1000.0, 2200.0), (2000.0, 4200.0), (4000.0, 8200.0)]);
+        assert_eq!(model.duration_ms(0), 200);
+        assert_eq!(model.duration_ms(3000), 6200);
+    }
+
+    #[test]
+    fn jobs_of_one_size_are_all_throughput() {
+        let model = Throughput::fit(&[(500.0, 1000.0), (500.0, 1200.0), (500.0, 800.0)]);
+        assert_eq!(model.duration_ms(0), 0);
+        assert_eq!(model.duration_ms(1000), 2000);
+    }
+
+    #[test]
+    fn etas_add_up_in_print_order() {
+        let mut conn = db::test_connection();
+        let printer = printers::create(
+            &mut conn,
+            serde_json::from_value::<NewPrinter>(json!({
+                "name": "desk",
+                "transport": "usb_lp",
+                "path": "/dev/usb/lp0"
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+        let payload: JobPayload = serde_json::from_value(json!({ "text": "hi" })).unwrap();
+        let mut queued = Vec::new();
+        for bytes in [1000, 500] {
+            let job =
+                jobs::create(&mut conn, NewJob::new(printer.id, "api", &payload).unwrap()).unwrap();
+            diesel::update(table::table.find(job.id))
+                .set(table::bytes.eq(bytes))
+                .execute(&mut conn)
+                .unwrap();
+            queued.push(job.id);
+        }
+        queued.reverse();
+        queued.push(i32::MAX);
+
+        let estimates = for_queue(&mut conn, printer.id, &queued).unwrap();
+        let times: Vec<_> = estimates
+            .iter()
+            .map(|q| {
+                (
+                    q.job.id,
+                    q.estimate.estimated_duration_ms,
+                    q.estimate.eta_ms,
+                )
+            })
+            .collect();
+        assert_eq!(times, [(queued[0], 1000, 1000), (queued[1], 1500, 2500)]);
+    }
+}
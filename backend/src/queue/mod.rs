@@ -0,0 +1,332 @@
+use anyhow::{Result, bail};
+use chrono::{TimeDelta, Utc};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{Notify, Semaphore};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use crate::events::{ConfigId, ConfigScope, Event, EventBus};
+use crate::jobs::{self, Job, JobState, NewJob};
+use crate::render::limits::Limits;
+use crate::{db, printers, telemetry};
+use backlog::Backlog;
+use eta::QueuedJob;
+
+pub use backlog::Fairness;
+
+mod backlog;
+pub mod eta;
+mod worker;
+
+/// Job ids waiting for one printer, dispatched according to its [`Fairness`].
+pub struct PrinterQueue {
+    jobs: Mutex<Backlog>,
+    fairness: Fairness,
+    notify: Notify,
+    /// Set by [`QueueManager::wake`]; the worker should re-read its printer settings.
+    reload: AtomicBool,
+    /// The request each waiting job was submitted in, to log its printing under.
+    request_ids: Mutex<HashMap<i32, String>>,
+}
+
+impl PrinterQueue {
+    fn new(fairness: Fairness) -> Self {
+        Self {
+            jobs: Mutex::new(Backlog::default()),
+            fairness,
+            notify: Notify::new(),
+            reload: AtomicBool::new(false),
+            request_ids: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn push(&self, job: &Job) {
+        self.jobs
+            .lock()
+            .unwrap()
+            .push(job.id, job.urgent, job.source.clone());
+        self.notify.notify_one();
+    }
+
+    /// Next job to print, considering only urgent ones when `urgent_only` is set.
+    /// Gives up with `None` once `wait` passes without a suitable job, or as soon as
+    /// the printer's settings change.
+    async fn next(&self, urgent_only: bool, wait: Duration) -> Option<i32> {
+        let deadline = Instant::now() + wait;
+        loop {
+            if let Some(job_id) = self.jobs.lock().unwrap().pop(self.fairness, urgent_only) {
+                return Some(job_id);
+            }
+            if tokio::time::timeout_at(deadline, self.notify.notified())
+                .await
+                .is_err()
+                || self.reload.swap(false, Ordering::Relaxed)
+            {
+                return None;
+            }
+        }
+    }
+
+    /// Sleep until something is pushed or [`QueueManager::wake`] is called, at most `wait`.
+    async fn idle(&self, wait: Duration) {
+        let _ = tokio::time::timeout(wait, self.notify.notified()).await;
+        self.reload.store(false, Ordering::Relaxed);
+    }
+
+    pub fn pending(&self) -> Vec<i32> {
+        self.jobs.lock().unwrap().order(self.fairness)
+    }
+
+    /// The id of the request `job_id` was submitted in, if it came from one.
+    fn take_request_id(&self, job_id: i32) -> Option<String> {
+        self.request_ids.lock().unwrap().remove(&job_id)
+    }
+}
+
+/// Outcome of [`QueueManager::submit`].
+pub struct Submitted {
+    pub job: Job,
+    /// The submission matched a recent job inside the printer's dedup window and was
+    /// dropped; `job` is that earlier job.
+    pub duplicate: bool,
+}
+
+/// Why [`QueueManager::submit`] refused a job: the server is stopping.
+#[derive(Debug)]
+pub struct ShuttingDown;
+
+impl fmt::Display for ShuttingDown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the server is shutting down; submit the job again once it's back")
+    }
+}
+
+impl std::error::Error for ShuttingDown {}
+
+/// Owns one queue and worker task per printer.
+///
+/// Printers never wait on each other's queues; the only shared resource is a
+/// semaphore bounding how many workers may be writing to hardware at once.
+pub struct QueueManager {
+    queues: Mutex<HashMap<i32, Arc<PrinterQueue>>>,
+    /// Each queue's worker task, to tell whether it's still running.
+    workers: Mutex<HashMap<i32, JoinHandle<()>>>,
+    permits: Arc<Semaphore>,
+    /// Permits in all, to take back every one of them when draining.
+    max_inflight: u32,
+    /// Set by [`QueueManager::close`]; no more jobs are accepted.
+    closed: AtomicBool,
+    limits: Limits,
+    fairness: Fairness,
+    events: EventBus,
+}
+
+impl QueueManager {
+    pub fn new(max_inflight: usize, limits: Limits, fairness: Fairness, events: EventBus) -> Self {
+        let max_inflight = max_inflight.clamp(1, Semaphore::MAX_PERMITS) as u32;
+        Self {
+            queues: Mutex::new(HashMap::new()),
+            workers: Mutex::new(HashMap::new()),
+            permits: Arc::new(Semaphore::new(max_inflight as usize)),
+            max_inflight,
+            closed: AtomicBool::new(false),
+            limits,
+            fairness,
+            events,
+        }
+    }
+
+    /// Queue for `printer_id`, spawning its worker on first use.
+    fn queue(&self, printer_id: i32) -> Arc<PrinterQueue> {
+        let mut queues = self.queues.lock().unwrap();
+        queues
+            .entry(printer_id)
+            .or_insert_with(|| {
+                let queue = Arc::new(PrinterQueue::new(self.fairness));
+                let worker = tokio::spawn(worker::run(
+                    printer_id,
+                    queue.clone(),
+                    self.permits.clone(),
+                    self.limits,
+                    self.events.clone(),
+                ));
+                self.workers.lock().unwrap().insert(printer_id, worker);
+                queue
+            })
+            .clone()
+    }
+
+    pub fn enqueue(&self, job: &Job) {
+        self.queue(job.printer_id).push(job);
+    }
+
+    /// Persist a new job and queue it on its printer, unless the printer has a dedup
+    /// window and an identical job was already accepted within it. Fails with
+    /// [`ShuttingDown`] once the queue is [closed](QueueManager::close).
+    pub async fn submit(&self, new: NewJob) -> Result<Submitted> {
+        if self.closed.load(Ordering::Relaxed) {
+            bail!(ShuttingDown);
+        }
+        let (job, duplicate) = db::run_blocking_db(move |conn| {
+            conn.immediate_transaction(|conn| {
+                let window = printers::get(conn, new.printer_id)?.and_then(|p| p.dedup_window_mins);
+                if let Some(mins) = window {
+                    let since = Utc::now().naive_utc() - TimeDelta::minutes(mins.into());
+                    if let Some(existing) =
+                        jobs::find_duplicate(conn, new.printer_id, &new.content_hash, since)?
+                    {
+                        return Ok((existing, true));
+                    }
+                }
+                Ok((jobs::create(conn, new)?, false))
+            })
+        })
+        .await?;
+        if duplicate {
+            info!(
+                "dropped duplicate of job {} on printer {}",
+                job.id, job.printer_id
+            );
+            return Ok(Submitted { job, duplicate });
+        }
+
+        self.events.publish(Event::Job {
+            job_id: job.id,
+            printer_id: job.printer_id,
+            state: JobState::Queued.as_str(),
+            error: None,
+        });
+        let queue = self.queue(job.printer_id);
+        if let Some(id) = telemetry::request_id() {
+            queue.request_ids.lock().unwrap().insert(job.id, id);
+        }
+        queue.push(&job);
+        Ok(Submitted { job, duplicate })
+    }
+
+    /// Make the printer's worker re-read its settings now, e.g. after a resume.
+    pub fn wake(&self, printer_id: i32) {
+        if let Some(queue) = self.queues.lock().unwrap().get(&printer_id) {
+            queue.reload.store(true, Ordering::Relaxed);
+            queue.notify.notify_one();
+        }
+    }
+
+    /// Wake a printer's worker whenever its settings change.
+    pub fn watch_config(self: &Arc<Self>) {
+        let queue = self.clone();
+        let mut rx = self.events.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(Event::ConfigChanged {
+                        scope: ConfigScope::Printer,
+                        id: ConfigId::Id(id),
+                    }) => queue.wake(id),
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(missed)) => {
+                        // Some changes were missed; have every worker reload.
+                        warn!("queue missed {missed} event(s); reloading all printers");
+                        let ids: Vec<i32> = queue.queues.lock().unwrap().keys().copied().collect();
+                        for id in ids {
+                            queue.wake(id);
+                        }
+                    }
+                    Err(RecvError::Closed) => return,
+                }
+            }
+        });
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Job ids still waiting for `printer_id`, in print order.
+    pub fn pending(&self, printer_id: i32) -> Vec<i32> {
+        self.queues
+            .lock()
+            .unwrap()
+            .get(&printer_id)
+            .map(|q| q.pending())
+            .unwrap_or_default()
+    }
+
+    /// The printing job and everything waiting behind it, with timing estimates.
+    pub async fn estimates(&self, printer_id: i32) -> Result<Vec<QueuedJob>> {
+        let pending = self.pending(printer_id);
+        db::run_blocking_db(move |conn| eta::for_queue(conn, printer_id, &pending)).await
+    }
+
+    /// Printers whose worker has stopped, having panicked, so their jobs won't print.
+    pub fn stopped_workers(&self) -> Vec<i32> {
+        let mut stopped: Vec<i32> = self
+            .workers
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, worker)| worker.is_finished())
+            .map(|(id, _)| *id)
+            .collect();
+        stopped.sort_unstable();
+        stopped
+    }
+
+    /// Whether [`close`](QueueManager::close) was called.
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+
+    /// Stop accepting jobs, ahead of [`drain`](QueueManager::drain).
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+    }
+
+    /// Wait up to `timeout` for the jobs being printed to finish, and keep workers
+    /// from starting any more. Jobs still waiting stay queued in the database and are
+    /// picked up by [`restore`](QueueManager::restore) on the next start; one still
+    /// printing when `timeout` runs out is printed again from the start. Returns
+    /// whether every job finished in time.
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        self.close();
+        let finished = tokio::time::timeout(timeout, self.permits.acquire_many(self.max_inflight))
+            .await
+            .is_ok();
+        if finished {
+            info!("printing jobs finished");
+        } else {
+            warn!("jobs still printing after {timeout:?}; they'll print again on restart");
+        }
+        // Workers waiting for a permit give up rather than start another job.
+        self.permits.close();
+        finished
+    }
+
+    /// Re-queue jobs left unfinished by a previous process.
+    pub async fn restore(&self) -> Result<()> {
+        let pending = db::run_blocking_db(|conn| {
+            let rows = jobs::unfinished(conn)?;
+            for job in rows
+                .iter()
+                .filter(|j| j.state == JobState::Printing.as_str())
+            {
+                // Interrupted mid-print; start it over rather than leave it stuck.
+                jobs::set_state(conn, job.id, JobState::Queued)?;
+            }
+            Ok(rows)
+        })
+        .await?;
+
+        info!("restoring {} queued job(s)", pending.len());
+        for job in &pending {
+            self.enqueue(job);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::{Result, anyhow};
+use chrono::Local;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tracing::{Instrument, error, info, info_span, warn};
+
+use super::PrinterQueue;
+use crate::events::{Event, EventBus};
+use crate::jobs::{self, JobState};
+use crate::render::Profile;
+use crate::render::limits::Limits;
+use crate::{db, output, printers, transforms, webhooks};
+
+/// Longest a worker sleeps before re-reading its printer's pause state and quiet hours.
+const RECHECK: Duration = Duration::from_secs(60);
+
+pub(super) async fn run(
+    printer_id: i32,
+    queue: Arc<PrinterQueue>,
+    permits: Arc<Semaphore>,
+    limits: Limits,
+    events: EventBus,
+) {
+    info!("worker started for printer {printer_id}");
+    let mut was_quiet = false;
+    loop {
+        // A printer that has gone missing is not held; its jobs fail in `process`.
+        let printer = db::run_blocking_db(move |conn| printers::get(conn, printer_id))
+            .await
+            .ok()
+            .flatten();
+        if printer.as_ref().is_some_and(|p| p.paused) {
+            queue.idle(RECHECK).await;
+            continue;
+        }
+
+        let quiet_until =
+            printer.and_then(|p| p.quiet_hours().active_until(Local::now().naive_local()));
+        match (was_quiet, quiet_until) {
+            (false, Some(until)) => info!("printer {printer_id} in quiet hours until {until}"),
+            (true, None) => info!("quiet hours over for printer {printer_id}; resuming"),
+            _ => {}
+        }
+        was_quiet = quiet_until.is_some();
+
+        // Outside quiet hours, wake up periodically anyway so a window that starts
+        // while the queue is idle is noticed.
+        let wait = quiet_until
+            .and_then(|until| (until - Local::now().naive_local()).to_std().ok())
+            .unwrap_or(RECHECK)
+            .min(RECHECK);
+        let Some(job_id) = queue.next(quiet_until.is_some(), wait).await else {
+            continue;
+        };
+        let Ok(_permit) = permits.acquire().await else {
+            return;
+        };
+
+        // Log the printing under the request that submitted the job, if one did.
+        let request_id = queue.take_request_id(job_id);
+        let span = info_span!(
+            "job",
+            id = job_id,
+            printer = printer_id,
+            request_id = request_id.as_deref(),
+        );
+        let (state, error) = async {
+            match process(printer_id, job_id, &limits, &events).await {
+                Ok(bytes) => {
+                    info!("job {job_id} printed on printer {printer_id} ({bytes} bytes)");
+                    (JobState::Done, None)
+                }
+                Err(err) => {
+                    warn!("job {job_id} failed on printer {printer_id}: {err:#}");
+                    let message = format!("{err:#}");
+                    let recorded = message.clone();
+                    if let Err(err) =
+                        db::run_blocking_db(move |conn| jobs::mark_failed(conn, job_id, recorded))
+                            .await
+                    {
+                        error!("failed to record failure of job {job_id}: {err:#}");
+                    }
+                    (JobState::Failed, Some(message))
+                }
+            }
+        }
+        .instrument(span.clone())
+        .await;
+        events.publish(Event::Job {
+            job_id,
+            printer_id,
+            state: state.as_str(),
+            error,
+        });
+
+        tokio::spawn(
+            async move {
+                if let Err(err) = webhooks::deliver::job_finished(job_id).await {
+                    warn!("could not notify webhooks for job {job_id}: {err:#}");
+                }
+            }
+            .instrument(span),
+        );
+    }
+}
+
+async fn process(
+    printer_id: i32,
+    job_id: i32,
+    limits: &Limits,
+    events: &EventBus,
+) -> Result<usize> {
+    let (printer, document, profile) = db::run_blocking_db(move |conn| {
+        let printer = printers::get(conn, printer_id)?
+            .ok_or_else(|| anyhow!("printer {printer_id} no longer exists"))?;
+        let job = jobs::get(conn, job_id)?.ok_or_else(|| anyhow!("job {job_id} not found"))?;
+        let mut document = transforms::prepare(conn, &job.source, job.payload()?)?;
+        printer.frame(&mut document);
+        let profile = Profile::load(conn, Some(&printer), &document)?;
+        jobs::set_state(conn, job_id, JobState::Printing)?;
+        Ok((printer, document, profile))
+    })
+    .await?;
+    events.publish(Event::Job {
+        job_id,
+        printer_id,
+        state: JobState::Printing.as_str(),
+        error: None,
+    });
+
+    if !printer.enabled {
+        return Err(anyhow!("printer '{}' is disabled", printer.name));
+    }
+
+    let rendered = limits.render(&document, &profile)?;
+    let len = rendered.bytes.len();
+    output::send(&printer, &rendered).await?;
+
+    db::run_blocking_db(move |conn| jobs::mark_done(conn, job_id, len)).await?;
+    Ok(len)
+}
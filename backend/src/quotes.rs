@@ -0,0 +1,156 @@
+//! Lists of quotes users add for the `quotes` integration to print alongside, or
+//! instead of, its built-in ones.
+
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::error::FieldError;
+use crate::schema::{quote_lists, quotes};
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize)]
+#[diesel(table_name = quote_lists)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct QuoteList {
+    pub id: i32,
+    /// What the integration's `lists` setting refers to the list by.
+    pub name: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Associations, Serialize)]
+#[diesel(table_name = quotes)]
+#[diesel(belongs_to(QuoteList, foreign_key = list_id))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Quote {
+    pub id: i32,
+    pub list_id: i32,
+    pub text: String,
+    pub author: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewQuoteList {
+    pub name: String,
+    /// Quotes to start the list with.
+    #[serde(default)]
+    pub quotes: Vec<NewQuote>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewQuote {
+    pub text: String,
+    #[serde(default)]
+    pub author: Option<String>,
+}
+
+impl NewQuoteList {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        if self.name.trim().is_empty() {
+            return Err(FieldError::new("name", "must not be empty"));
+        }
+        for (i, quote) in self.quotes.iter().enumerate() {
+            quote
+                .validate()
+                .map_err(|e| e.within(&format!("quotes.{i}")))?;
+        }
+        Ok(())
+    }
+}
+
+impl NewQuote {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        if self.text.trim().is_empty() {
+            return Err(FieldError::new("text", "must not be empty"));
+        }
+        Ok(())
+    }
+}
+
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<QuoteList>> {
+    let rows = quote_lists::table
+        .order(quote_lists::name.asc())
+        .select(QuoteList::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn get(conn: &mut SqliteConnection, id: i32) -> Result<Option<QuoteList>> {
+    let row = quote_lists::table
+        .find(id)
+        .select(QuoteList::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn get_by_name(conn: &mut SqliteConnection, name: &str) -> Result<Option<QuoteList>> {
+    let row = quote_lists::table
+        .filter(quote_lists::name.eq(name))
+        .select(QuoteList::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+/// The list's quotes, oldest first.
+pub fn quotes_of(conn: &mut SqliteConnection, list: &QuoteList) -> Result<Vec<Quote>> {
+    let rows = Quote::belonging_to(list)
+        .order(quotes::id.asc())
+        .select(Quote::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+/// Quotes in the lists called `names`, in the order they were added.
+pub fn quotes_in(conn: &mut SqliteConnection, names: &[String]) -> Result<Vec<Quote>> {
+    let rows = quotes::table
+        .inner_join(quote_lists::table)
+        .filter(quote_lists::name.eq_any(names))
+        .order(quotes::id.asc())
+        .select(Quote::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn create(conn: &mut SqliteConnection, new: NewQuoteList) -> Result<QuoteList> {
+    conn.transaction(|conn| {
+        let list = diesel::insert_into(quote_lists::table)
+            .values(quote_lists::name.eq(&new.name))
+            .returning(QuoteList::as_returning())
+            .get_result(conn)?;
+        for quote in new.quotes {
+            add(conn, list.id, quote)?;
+        }
+        Ok(list)
+    })
+}
+
+pub fn add(conn: &mut SqliteConnection, list_id: i32, new: NewQuote) -> Result<Quote> {
+    let row = diesel::insert_into(quotes::table)
+        .values((
+            quotes::list_id.eq(list_id),
+            quotes::text.eq(new.text),
+            quotes::author.eq(new.author),
+        ))
+        .returning(Quote::as_returning())
+        .get_result(conn)?;
+    Ok(row)
+}
+
+pub fn delete(conn: &mut SqliteConnection, id: i32) -> Result<bool> {
+    let deleted = diesel::delete(quote_lists::table.find(id)).execute(conn)?;
+    Ok(deleted > 0)
+}
+
+pub fn remove(conn: &mut SqliteConnection, list_id: i32, quote_id: i32) -> Result<bool> {
+    let deleted = diesel::delete(
+        quotes::table
+            .filter(quotes::list_id.eq(list_id))
+            .filter(quotes::id.eq(quote_id)),
+    )
+    .execute(conn)?;
+    Ok(deleted > 0)
+}
@@ -0,0 +1,126 @@
+//! Large type drawn from a TrueType font and printed as an image, for headers bigger
+//! and smoother than the printer's own size multipliers can make them.
+
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont, point};
+use image::{GrayImage, Luma, imageops};
+
+use super::preview::PAPER_WIDTH_DOTS;
+use crate::document::Align;
+
+pub(super) static BANNER_FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans-Bold.ttf");
+
+/// Longest piece of a vertical banner sent as one image. Banners are broken into
+/// pieces between characters, where the paper is blank, so no image is larger than
+/// a printer's buffer.
+const MAX_PIECE: u32 = 1024;
+
+/// `text` in lines `height` dots tall, aligned with each other the way the image will
+/// be on the paper. A line too wide for the paper is drawn smaller until it fits.
+pub(super) fn image(text: &str, height: u16, align: Align) -> Option<GrayImage> {
+    draw(text, height, align, Some(PAPER_WIDTH_DOTS))
+}
+
+/// `text` turned a quarter turn clockwise to run down the length of the paper, its
+/// lines `height` dots across it. The banner is as long as the text needs, in
+/// pieces printed one after another.
+pub(super) fn vertical(text: &str, height: u16, align: Align) -> Option<Vec<GrayImage>> {
+    let image = imageops::rotate90(&draw(text, height, align, None)?);
+    let mut pieces = Vec::new();
+    let mut top = 0;
+    while top < image.height() {
+        let mut bottom = (top + MAX_PIECE).min(image.height());
+        if bottom < image.height() {
+            bottom = (top + 1..bottom)
+                .rev()
+                .find(|&y| (0..image.width()).all(|x| image.get_pixel(x, y).0[0] == 255))
+                .unwrap_or(bottom);
+        }
+        pieces.push(imageops::crop_imm(&image, 0, top, image.width(), bottom - top).to_image());
+        top = bottom;
+    }
+    Some(pieces)
+}
+
+/// `text` drawn left to right, each line scaled down if it is wider than
+/// `max_width`.
+fn draw(text: &str, height: u16, align: Align, max_width: Option<u32>) -> Option<GrayImage> {
+    let font = FontRef::try_from_slice(BANNER_FONT).ok()?;
+    let lines: Vec<(&str, PxScale)> = text
+        .lines()
+        .map(|line| {
+            let scale = PxScale::from(f32::from(height));
+            let width = measure(&font, line, scale);
+            let fit = max_width.map_or(1.0, |max| (max as f32 / width).min(1.0));
+            (line, PxScale::from(scale.y * fit))
+        })
+        .collect();
+    let width = lines
+        .iter()
+        .map(|(line, scale)| measure(&font, line, *scale).ceil() as u32)
+        .max()?
+        .clamp(1, max_width.unwrap_or(u32::MAX));
+    let total: f32 = lines
+        .iter()
+        .map(|(_, scale)| line_height(&font, *scale))
+        .sum();
+
+    let mut image = GrayImage::from_pixel(width, total.ceil() as u32, Luma([255]));
+    let mut top = 0.0;
+    for (line, scale) in lines {
+        let scaled = font.as_scaled(scale);
+        let slack = width as f32 - measure(&font, line, scale);
+        let mut x = match align {
+            Align::Left => 0.0,
+            Align::Center => slack / 2.0,
+            Align::Right => slack,
+        };
+        let mut previous = None;
+        for c in line.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                x += scaled.kern(previous, id);
+            }
+            let glyph = id.with_scale_and_position(scale, point(x, top + scaled.ascent()));
+            if let Some(outlined) = font.outline_glyph(glyph) {
+                let bounds = outlined.px_bounds();
+                outlined.draw(|gx, gy, coverage| {
+                    let px = bounds.min.x as i32 + gx as i32;
+                    let py = bounds.min.y as i32 + gy as i32;
+                    if coverage > 0.5
+                        && px >= 0
+                        && py >= 0
+                        && (px as u32) < image.width()
+                        && (py as u32) < image.height()
+                    {
+                        image.put_pixel(px as u32, py as u32, Luma([0]));
+                    }
+                });
+            }
+            x += scaled.h_advance(id);
+            previous = Some(id);
+        }
+        top += line_height(&font, scale);
+    }
+    Some(image)
+}
+
+/// Advance width of `line`, kerning included.
+fn measure(font: &FontRef, line: &str, scale: PxScale) -> f32 {
+    let scaled = font.as_scaled(scale);
+    let mut width = 0.0;
+    let mut previous = None;
+    for c in line.chars() {
+        let id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            width += scaled.kern(previous, id);
+        }
+        width += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+fn line_height(font: &FontRef, scale: PxScale) -> f32 {
+    let scaled = font.as_scaled(scale);
+    scaled.ascent() - scaled.descent()
+}
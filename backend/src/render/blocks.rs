@@ -0,0 +1,799 @@
+//! Translates document blocks into escpos builder calls, keeping a plain-text
+//! approximation of the paper alongside.
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use escpos::printer::Printer;
+use escpos::utils::{
+    Font as EscposFont, JustifyMode, QRCodeCorrectionLevel, QRCodeModel, QRCodeOption,
+    UnderlineMode,
+};
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayImage, ImageFormat, Luma};
+use qrcode::{Color, EcLevel, QrCode};
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use super::banner;
+use super::canvas::{self, Canvas};
+use super::charset::CodePage;
+use super::preview::{CELL_HEIGHT, CELL_WIDTH, LINE_SPACING, PAPER_WIDTH_DOTS};
+use super::{
+    COLUMNS, COLUMNS_FONT_B, CUT_MARKER, CaptureDriver, Profile, Rendered, charset, emoji, layout,
+    photo,
+};
+use crate::document::{
+    Align, Block, Column, ColumnWidth, Font, Hri, Placed, RuleStyle, Span, Style, Symbology,
+};
+
+const ESC: u8 = 0x1b;
+const GS: u8 = 0x1d;
+/// White space above and below solid and double rules, in dots.
+const RULE_MARGIN: u32 = 8;
+/// Character codes handed out to user-defined glyphs, skipping the space.
+const GLYPH_CODES: std::ops::RangeInclusive<u8> = b'!'..=b'~';
+
+pub(super) struct Writer<'a> {
+    printer: Printer<CaptureDriver>,
+    profile: &'a Profile,
+    /// Drawing of the paper, kept only for image previews.
+    canvas: Option<Canvas>,
+    /// Character code of each glyph downloaded to the printer.
+    codes: HashMap<&'a str, u8>,
+    /// Code page the printer has selected.
+    code_page: CodePage,
+    text: String,
+    lines: usize,
+    /// Paper fed by lines of text and blank lines, in dots.
+    text_height: u32,
+    raster_height: u32,
+}
+
+impl From<Align> for JustifyMode {
+    fn from(align: Align) -> Self {
+        match align {
+            Align::Left => JustifyMode::LEFT,
+            Align::Center => JustifyMode::CENTER,
+            Align::Right => JustifyMode::RIGHT,
+        }
+    }
+}
+
+impl<'a> Writer<'a> {
+    pub fn new(mut printer: Printer<CaptureDriver>, profile: &'a Profile) -> Result<Self> {
+        printer.init()?;
+        Ok(Self {
+            printer,
+            profile,
+            canvas: None,
+            codes: HashMap::new(),
+            code_page: CodePage::Cp437,
+            text: String::new(),
+            lines: 0,
+            text_height: 0,
+            raster_height: 0,
+        })
+    }
+
+    /// Turn the print upside down (`ESC {`) for the rest of the job.
+    pub fn upside_down(&mut self) -> Result<()> {
+        self.printer.custom(&[ESC, b'{', 1])?;
+        Ok(())
+    }
+
+    /// Also draw the paper, for [`Writer::into_png`].
+    pub fn with_canvas(mut self) -> Self {
+        self.canvas = Some(Canvas::default());
+        self
+    }
+
+    /// The drawing of the paper as a PNG.
+    pub fn into_png(self) -> Result<Vec<u8>> {
+        self.canvas.unwrap_or_default().png()
+    }
+
+    /// Flush the printer and report what was laid out. The bytes are left to the
+    /// caller, who holds the driver.
+    pub fn finish(mut self) -> Result<Rendered> {
+        self.printer.print()?;
+        Ok(Rendered {
+            bytes: Vec::new(),
+            text: self.text,
+            lines: self.lines,
+            raster_height: self.raster_height,
+            height: self.text_height + self.raster_height,
+        })
+    }
+
+    /// Download glyphs as user-defined characters (`ESC &`) so each use costs a
+    /// single byte. Glyphs beyond the available codes are printed as raster.
+    pub fn define_glyphs(&mut self, names: &[&str]) -> Result<()> {
+        let profile = self.profile;
+        let glyphs = names.iter().filter_map(|name| profile.glyphs.get(*name));
+        for (glyph, code) in glyphs.zip(GLYPH_CODES) {
+            let mut cmd = vec![ESC, b'&', 3, code, code, glyph.width as u8];
+            cmd.extend_from_slice(&glyph.bitmap);
+            self.printer.custom(&cmd)?;
+            self.codes.insert(glyph.name.as_str(), code);
+        }
+        Ok(())
+    }
+
+    pub fn block(&mut self, block: &Block) -> Result<()> {
+        match block {
+            Block::Text { text, spans, style } if spans.is_empty() => {
+                self.styled(style, text.lines())
+            }
+            Block::Text { text, spans, style } => self.spans(style, text, spans),
+            Block::Heading { text, level, align } => {
+                let style = Style {
+                    bold: true,
+                    underline: *level == 2,
+                    size: if *level == 1 { 2 } else { 1 },
+                    align: *align,
+                    ..Style::default()
+                };
+                self.styled(&style, text.lines())
+            }
+            Block::Banner {
+                text,
+                height,
+                align,
+                vertical,
+            } => self.banner(text, *height, *align, *vertical),
+            Block::Glyph { name, text } => {
+                let text = self.transliterate(text);
+                self.glyph(name)?;
+                self.writeln(&format!(" {text}"))?;
+                if let Some(canvas) = &mut self.canvas {
+                    match self.profile.glyphs.get(name.as_str()) {
+                        Some(glyph) => {
+                            let image = canvas::bit_image(glyph.width as u32, &glyph.bitmap);
+                            canvas.text(&format!(" {text}"), &Style::default(), Some(&image));
+                        }
+                        None => canvas.text(&format!("? {text}"), &Style::default(), None),
+                    }
+                }
+                self.push_line(format!("[{name}] {text}").trim_end(), Align::Left, COLUMNS);
+                self.lines += 1;
+                self.text_height += LINE_SPACING;
+                Ok(())
+            }
+            Block::Rule { style, character } => self.rule(*style, *character),
+            Block::Qr { data, size, align } => self.qr(data, *size, *align),
+            Block::Barcode {
+                data,
+                symbology,
+                height,
+                width,
+                hri,
+                align,
+            } => self.barcode(data, *symbology, *height, *width, *hri, *align),
+            Block::Image { data, align } => self.image(data, *align),
+            Block::Table {
+                header,
+                rows,
+                columns,
+            } => self.table(header.as_deref(), rows, columns),
+            Block::Page { height, items } => self.page(*height, items),
+            Block::Cut { partial } => {
+                if *partial {
+                    self.printer.partial_cut()?;
+                } else {
+                    self.printer.cut()?;
+                }
+                self.text.push_str(CUT_MARKER);
+                self.text.push('\n');
+                if let Some(canvas) = &mut self.canvas {
+                    canvas.cut();
+                }
+                Ok(())
+            }
+            Block::Feed { lines } => {
+                self.printer.feeds(*lines)?;
+                for _ in 0..*lines {
+                    self.text.push('\n');
+                }
+                self.text_height += u32::from(*lines) * LINE_SPACING;
+                if let Some(canvas) = &mut self.canvas {
+                    canvas.feed(u32::from(*lines) * LINE_SPACING);
+                }
+                Ok(())
+            }
+            Block::Space { dots } => {
+                // ESC J feeds at most 255 dots at a time.
+                let mut left = *dots;
+                while left > 0 {
+                    let step = left.min(255);
+                    self.printer.custom(&[ESC, b'J', step as u8])?;
+                    left -= step;
+                }
+                self.raster_height += u32::from(*dots);
+                if let Some(canvas) = &mut self.canvas {
+                    canvas.feed(u32::from(*dots));
+                }
+                for _ in 0..(dots + 12) / 24 {
+                    self.text.push('\n');
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// A full-width line: characters for dashed and custom rules, raster bars for
+    /// solid and double ones.
+    fn rule(&mut self, style: RuleStyle, character: Option<char>) -> Result<()> {
+        let bars: &[u32] = match (style, character) {
+            (_, Some(c)) => {
+                let line = c.to_string().repeat(COLUMNS);
+                return self.styled(&Style::default(), [line.as_str()]);
+            }
+            (RuleStyle::Dashed, None) => {
+                return self.styled(&Style::default(), ["-".repeat(COLUMNS).as_str()]);
+            }
+            (RuleStyle::Solid, None) => &[3],
+            (RuleStyle::Double, None) => &[2, 3, 2],
+        };
+        // Bars alternate black and white, with white margins above and below.
+        let height = bars.iter().sum::<u32>() + 2 * RULE_MARGIN;
+        let mut image = GrayImage::from_pixel(PAPER_WIDTH_DOTS, height, Luma([255]));
+        let mut y = RULE_MARGIN;
+        for (i, bar) in bars.iter().enumerate() {
+            if i % 2 == 0 {
+                for row in y..y + bar {
+                    for x in 0..PAPER_WIDTH_DOTS {
+                        image.put_pixel(x, row, Luma([0]));
+                    }
+                }
+            }
+            y += bar;
+        }
+        let note = if style == RuleStyle::Double {
+            '═'
+        } else {
+            '─'
+        };
+        self.push_line(&note.to_string().repeat(COLUMNS), Align::Left, COLUMNS);
+        self.raster(image, Align::Left)
+    }
+
+    /// Print lines with `style`, laid out to the width of its font and size and
+    /// switching back to plain text afterwards. Only the attributes that differ from
+    /// the default are sent.
+    fn styled<'l>(
+        &mut self,
+        style: &Style,
+        lines: impl IntoIterator<Item = &'l str>,
+    ) -> Result<()> {
+        let columns = columns(style);
+        let lines: Vec<String> = lines
+            .into_iter()
+            .flat_map(|line| layout::fit(&self.transliterate(line), columns, style.overflow))
+            .collect();
+        self.set_style(style, true)?;
+        for line in lines {
+            self.writeln(&line)?;
+            if let Some(canvas) = &mut self.canvas {
+                canvas.text(&line, style, None);
+            }
+            self.push_line(&line, style.align, columns);
+            self.lines += 1;
+            self.text_height += canvas::line_height(style);
+        }
+        self.set_style(style, false)?;
+        Ok(())
+    }
+
+    /// Text followed by spans, laid out together like [`Writer::styled`] lays out a
+    /// line. Between runs only the attributes that change are switched, and the
+    /// printer is back in the block's style before the block's style is turned off.
+    fn spans(&mut self, style: &Style, text: &str, spans: &[Span]) -> Result<()> {
+        let columns = columns(style);
+        let runs = std::iter::once((text, *style)).chain(
+            spans
+                .iter()
+                .map(|span| (span.text.as_str(), span.style(style))),
+        );
+        let mut chars: Vec<(char, Style)> = Vec::new();
+        for (run, run_style) in runs {
+            for (i, part) in run.split('\n').enumerate() {
+                if i > 0 {
+                    chars.push(('\n', run_style));
+                }
+                chars.extend(self.transliterate(part).chars().map(|c| (c, run_style)));
+            }
+        }
+        let lines: Vec<Vec<(char, Style)>> = chars
+            .split(|(c, _)| *c == '\n')
+            .flat_map(|line| layout::fit_styled(line, columns, style.overflow))
+            .collect();
+        self.styled_chars(style, lines, columns)
+    }
+
+    /// Print laid-out lines whose characters carry their own bold, underline and
+    /// reverse on top of `style`.
+    fn styled_chars(
+        &mut self,
+        style: &Style,
+        lines: Vec<Vec<(char, Style)>>,
+        columns: usize,
+    ) -> Result<()> {
+        self.set_style(style, true)?;
+        let mut current = *style;
+        for line in lines {
+            let runs: Vec<(String, Style)> = line
+                .chunk_by(|a, b| a.1 == b.1)
+                .map(|run| (run.iter().map(|(c, _)| *c).collect(), run[0].1))
+                .collect();
+            let mut bytes = Vec::new();
+            for (run, run_style) in &runs {
+                bytes.extend(switch(&current, run_style));
+                current = *run_style;
+                bytes.extend(self.encode(run));
+            }
+            bytes.push(b'\n');
+            self.printer.custom(&bytes)?;
+            if let Some(canvas) = &mut self.canvas {
+                canvas.spans(&runs, style, None);
+            }
+            let plain: String = runs.iter().map(|(run, _)| run.as_str()).collect();
+            self.push_line(&plain, style.align, columns);
+            self.lines += 1;
+            self.text_height += canvas::line_height(style);
+        }
+        self.printer.custom(&switch(&current, style))?;
+        self.set_style(style, false)
+    }
+
+    /// Text at positions in an area `height` dots tall: in page mode (`ESC L`) where
+    /// the printer has it, otherwise on a grid of font A cells, which is also the
+    /// text approximation of the page.
+    fn page(&mut self, height: u16, items: &[Placed]) -> Result<()> {
+        let rows = u32::from(height).div_ceil(CELL_HEIGHT) as usize;
+        let mut grid: Vec<Vec<Option<(char, Style)>>> = vec![vec![None; COLUMNS]; rows];
+        let mut placed = Vec::new();
+        for item in items {
+            let (x, y) = (u32::from(item.x), u32::from(item.y));
+            let width = item
+                .width
+                .map_or(PAPER_WIDTH_DOTS, u32::from)
+                .min(PAPER_WIDTH_DOTS - x);
+            let (cell_width, cell_height) = canvas::cell(&item.style);
+            let lines: Vec<String> = item
+                .text
+                .lines()
+                .flat_map(|line| {
+                    let columns = (width / cell_width).max(1) as usize;
+                    layout::fit(&self.transliterate(line), columns, item.style.overflow)
+                })
+                .collect();
+
+            let grid_columns = (width / CELL_WIDTH).max(1) as usize;
+            let (row, column) = ((y / CELL_HEIGHT) as usize, (x / CELL_WIDTH) as usize);
+            for (i, line) in lines.iter().enumerate() {
+                let Some(cells) = grid.get_mut(row + i) else {
+                    break;
+                };
+                let slack = grid_columns.saturating_sub(line.chars().count());
+                let indent = match item.style.align {
+                    Align::Left => 0,
+                    Align::Center => slack / 2,
+                    Align::Right => slack,
+                };
+                let text = line.chars().take(grid_columns);
+                for (cell, c) in cells.iter_mut().skip(column + indent).zip(text) {
+                    // Blanks only matter where they show, and never cover text.
+                    let shows = item.style.invert || item.style.underline;
+                    if c != ' ' || (shows && cell.is_none()) {
+                        *cell = Some((c, item.style));
+                    }
+                }
+
+                let used: u32 = line.chars().map(|c| canvas::advance(c, cell_width)).sum();
+                let slack = width.saturating_sub(used);
+                let left = match item.style.align {
+                    Align::Left => 0,
+                    Align::Center => slack / 2,
+                    Align::Right => slack,
+                };
+                placed.push((
+                    line.clone(),
+                    item.style,
+                    x + left,
+                    y + i as u32 * cell_height,
+                ));
+            }
+        }
+        let rows: Vec<Vec<(char, Style)>> = grid
+            .into_iter()
+            .map(|row| {
+                let end = row.iter().rposition(Option::is_some).map_or(0, |i| i + 1);
+                row[..end]
+                    .iter()
+                    .map(|cell| cell.unwrap_or((' ', Style::default())))
+                    .collect()
+            })
+            .collect();
+
+        if !self.profile.page_mode {
+            return self.styled_chars(&Style::default(), rows, COLUMNS);
+        }
+
+        let [width_lo, width_hi] = (PAPER_WIDTH_DOTS as u16).to_le_bytes();
+        let [height_lo, height_hi] = height.to_le_bytes();
+        self.printer.custom(&[
+            ESC, b'L', ESC, b'W', 0, 0, 0, 0, width_lo, width_hi, height_lo, height_hi, ESC, b'T',
+            0,
+        ])?;
+        let top = self
+            .canvas
+            .as_mut()
+            .map(|canvas| canvas.area(height.into()));
+        for (line, style, x, y) in placed {
+            let (_, cell_height) = canvas::cell(&style);
+            // Characters stand on the vertical position, so it is set to their
+            // baseline, a sixth of the cell above its bottom.
+            let [x_lo, x_hi] = (x as u16).to_le_bytes();
+            let [y_lo, y_hi] = ((y + cell_height * 5 / 6) as u16).to_le_bytes();
+            let plain = Style {
+                align: Align::Left,
+                ..style
+            };
+            self.set_style(&plain, true)?;
+            let mut bytes = vec![ESC, b'$', x_lo, x_hi, GS, b'$', y_lo, y_hi];
+            bytes.extend(self.encode(&line));
+            self.printer.custom(&bytes)?;
+            self.set_style(&plain, false)?;
+            if let (Some(canvas), Some(top)) = (&mut self.canvas, top) {
+                canvas.text_at(&line, &style, x, top + y);
+            }
+        }
+        // FF prints the page and returns to standard mode.
+        self.printer.custom(&[0x0c])?;
+        for row in rows {
+            let line: String = row.into_iter().map(|(c, _)| c).collect();
+            self.push_line(&line, Align::Left, COLUMNS);
+        }
+        self.raster_height += u32::from(height);
+        Ok(())
+    }
+
+    /// `text` in characters the printer's code pages have, plus emoji it can draw.
+    fn transliterate(&self, text: &str) -> String {
+        let profile = self.profile;
+        charset::transliterate(
+            text,
+            &profile.code_pages,
+            profile.emoji,
+            profile.replacement,
+        )
+    }
+
+    /// Send a line of transliterated text.
+    fn writeln(&mut self, line: &str) -> Result<()> {
+        let mut bytes = self.encode(line);
+        bytes.push(b'\n');
+        self.printer.custom(&bytes)?;
+        Ok(())
+    }
+
+    /// Bytes for transliterated text, switching code pages as it needs and drawing
+    /// emoji as bit images in their place.
+    fn encode(&mut self, text: &str) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut rest = text;
+        while !rest.is_empty() {
+            let end = rest.find(emoji::is_emoji).unwrap_or(rest.len());
+            let (run, tail) = rest.split_at(end);
+            bytes.extend(charset::encode(
+                run,
+                &self.profile.code_pages,
+                &mut self.code_page,
+            ));
+            let mut tail = tail.chars();
+            if let Some(bitmap) = tail.next().and_then(emoji::bitmap) {
+                bytes.extend([ESC, b'*', 33, CELL_WIDTH as u8, 0]);
+                bytes.extend(bitmap);
+            }
+            rest = tail.as_str();
+        }
+        bytes
+    }
+
+    fn set_style(&mut self, style: &Style, on: bool) -> Result<()> {
+        if style.align != Align::Left {
+            let align = if on { style.align } else { Align::Left };
+            self.printer.justify(align.into())?;
+        }
+        if style.bold {
+            self.printer.bold(on)?;
+        }
+        if style.underline {
+            let mode = if on {
+                UnderlineMode::Single
+            } else {
+                UnderlineMode::None
+            };
+            self.printer.underline(mode)?;
+        }
+        if style.invert {
+            self.printer.reverse(on)?;
+        }
+        if style.font == Font::B {
+            self.printer
+                .font(if on { EscposFont::B } else { EscposFont::A })?;
+        }
+        if style.size > 1 {
+            if on {
+                self.printer.size(style.size, style.size)?;
+            } else {
+                self.printer.reset_size()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a line to the text approximation, padded the way the printer justifies
+    /// it. Graphics are described by a bracketed note.
+    fn push_line(&mut self, line: &str, align: Align, columns: usize) {
+        let slack = columns.saturating_sub(line.chars().count());
+        let pad = match align {
+            Align::Left => 0,
+            Align::Center => slack / 2,
+            Align::Right => slack,
+        };
+        self.text.push_str(&" ".repeat(pad));
+        self.text.push_str(line);
+        self.text.push('\n');
+    }
+
+    /// Print a glyph inline: by character code if it was downloaded, otherwise as a
+    /// 24-dot bit image, or as `?` if it no longer exists.
+    fn glyph(&mut self, name: &str) -> Result<()> {
+        if let Some(code) = self.codes.get(name) {
+            self.printer.custom(&[ESC, b'%', 1, *code, ESC, b'%', 0])?;
+            return Ok(());
+        }
+        let Some(glyph) = self.profile.glyphs.get(name) else {
+            self.printer.write("?")?;
+            return Ok(());
+        };
+        let width = glyph.width as u16;
+        let mut cmd = vec![ESC, b'*', 33, width as u8, (width >> 8) as u8];
+        cmd.extend_from_slice(&glyph.bitmap);
+        self.printer.custom(&cmd)?;
+        Ok(())
+    }
+
+    /// Large text as a raster image. It is drawn rather than sent as characters, so
+    /// it isn't limited to the printer's code pages.
+    fn banner(&mut self, text: &str, height: u16, align: Align, vertical: bool) -> Result<()> {
+        for line in text.lines() {
+            self.push_line(line, align, COLUMNS);
+        }
+        if vertical {
+            let pieces =
+                banner::vertical(text, height, align).context("banner could not be drawn")?;
+            for piece in pieces {
+                self.raster(piece, align)?;
+            }
+            return Ok(());
+        }
+        let image = banner::image(text, height, align).context("banner could not be drawn")?;
+        self.raster(image, align)
+    }
+
+    fn image(&mut self, data: &str, align: Align) -> Result<()> {
+        let bytes = BASE64.decode(data).context("image is not valid base64")?;
+        let mut image = photo::decode(&bytes).context("image could not be decoded")?;
+        if image.width() > PAPER_WIDTH_DOTS {
+            image = image.resize(PAPER_WIDTH_DOTS, u32::MAX, FilterType::Triangle);
+        }
+        let image = image.to_luma8();
+        self.push_line(
+            &format!("[image {}x{}]", image.width(), image.height()),
+            align,
+            COLUMNS,
+        );
+        self.raster(image, align)
+    }
+
+    /// Send a grayscale image as a bit image.
+    fn raster(&mut self, image: GrayImage, align: Align) -> Result<()> {
+        let mut png = Vec::new();
+        DynamicImage::ImageLuma8(image.clone())
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+        if let Some(canvas) = &mut self.canvas {
+            canvas.image(&image, align);
+        }
+        self.printer.justify(align.into())?;
+        self.printer.bit_image_from_bytes(&png)?;
+        self.printer.justify(JustifyMode::LEFT)?;
+        self.raster_height += image.height();
+        Ok(())
+    }
+
+    /// A barcode in function B form: `GS k m n data`, after setting the bar height
+    /// (`GS h`), module width (`GS w`) and HRI position (`GS H`).
+    fn barcode(
+        &mut self,
+        data: &str,
+        symbology: Symbology,
+        height: u8,
+        width: u8,
+        hri: Hri,
+        align: Align,
+    ) -> Result<()> {
+        let (m, encoded) = match symbology {
+            Symbology::Upca => (65, data.to_string()),
+            Symbology::Upce => (66, data.to_string()),
+            Symbology::Ean13 => (67, data.to_string()),
+            Symbology::Ean8 => (68, data.to_string()),
+            Symbology::Code39 => (69, data.to_string()),
+            Symbology::Itf => (70, data.to_string()),
+            Symbology::Codabar => (71, data.to_string()),
+            // Code set B covers printable ASCII; a literal '{' is escaped as "{{".
+            Symbology::Code128 => (73, format!("{{B{}", data.replace('{', "{{"))),
+        };
+        let position = match hri {
+            Hri::None => 0,
+            Hri::Above => 1,
+            Hri::Below => 2,
+            Hri::Both => 3,
+        };
+
+        self.printer.justify(align.into())?;
+        let mut cmd = vec![GS, b'h', height, GS, b'w', width, GS, b'H', position];
+        cmd.extend([GS, b'k', m, encoded.len() as u8]);
+        cmd.extend(encoded.as_bytes());
+        self.printer.custom(&cmd)?;
+        self.printer.justify(JustifyMode::LEFT)?;
+
+        let hri_rows = match hri {
+            Hri::None => 0,
+            Hri::Above | Hri::Below => 1,
+            Hri::Both => 2,
+        };
+        self.raster_height += u32::from(height) + hri_rows * 24;
+        if let Some(canvas) = &mut self.canvas {
+            canvas.barcode(data, symbology, height, width, hri, align);
+        }
+        self.push_line(
+            &format!("[{} barcode: {data}]", symbology.as_str()),
+            align,
+            COLUMNS,
+        );
+        Ok(())
+    }
+
+    /// A QR code with `size`-dot modules: drawn by the printer when the profile
+    /// allows, otherwise rasterised here, shrinking the modules if the code would be
+    /// wider than the paper.
+    fn qr(&mut self, data: &str, size: u8, align: Align) -> Result<()> {
+        let code = QrCode::with_error_correction_level(data, EcLevel::M)?;
+        let modules = code.width() as u32;
+        self.push_line(&format!("[qr: {data}]"), align, COLUMNS);
+
+        let image = |scale: u32| {
+            let colors = code.to_colors();
+            GrayImage::from_fn(modules * scale, modules * scale, |x, y| {
+                let i = (y / scale * modules + x / scale) as usize;
+                if colors[i] == Color::Dark {
+                    Luma([0])
+                } else {
+                    Luma([255])
+                }
+            })
+        };
+        let scale = u32::from(size).min(PAPER_WIDTH_DOTS / modules).max(1);
+
+        if self.profile.native_qr {
+            if let Some(canvas) = &mut self.canvas {
+                canvas.image(&image(u32::from(size)), align);
+            }
+            self.printer.justify(align.into())?;
+            self.printer.qrcode_option(
+                data,
+                QRCodeOption::new(QRCodeModel::Model2, size, QRCodeCorrectionLevel::M),
+            )?;
+            self.printer.justify(JustifyMode::LEFT)?;
+            self.raster_height += modules * u32::from(size);
+            return Ok(());
+        }
+
+        let image = image(scale);
+        self.raster(image, align)
+    }
+
+    /// Columns are as wide as their widest cell, with the widest ones narrowed
+    /// until the row fits on the paper. Header cells are printed bold.
+    fn table(
+        &mut self,
+        header: Option<&[String]>,
+        rows: &[Vec<String>],
+        columns: &[Column],
+    ) -> Result<()> {
+        let all = || header.into_iter().chain(rows.iter().map(Vec::as_slice));
+        let count = all()
+            .map(<[String]>::len)
+            .max()
+            .unwrap_or(0)
+            .max(columns.len());
+        if count == 0 {
+            return Ok(());
+        }
+        let column = |i: usize| columns.get(i).copied().unwrap_or_default();
+        let mut natural = vec![0; count];
+        for row in all() {
+            for (width, cell) in natural.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let specs: Vec<ColumnWidth> = (0..count).map(|i| column(i).width).collect();
+        let widths = layout::column_widths(&specs, &natural, COLUMNS.saturating_sub(count - 1));
+
+        // A row is as many printed lines as its tallest wrapped cell.
+        let format = |row: &[String]| -> Vec<String> {
+            let cells: Vec<Vec<String>> = widths
+                .iter()
+                .enumerate()
+                .map(|(i, width)| {
+                    let cell = row.get(i).map(String::as_str).unwrap_or("");
+                    layout::fit(cell, *width, column(i).overflow)
+                })
+                .collect();
+            let height = cells.iter().map(Vec::len).max().unwrap_or(1);
+            (0..height)
+                .map(|line| {
+                    let padded: Vec<String> = cells
+                        .iter()
+                        .zip(&widths)
+                        .enumerate()
+                        .map(|(i, (cell, width))| {
+                            let text = cell.get(line).map(String::as_str).unwrap_or("");
+                            layout::pad(text, *width, column(i).align)
+                        })
+                        .collect();
+                    padded.join(" ").trim_end().to_string()
+                })
+                .collect()
+        };
+        if let Some(header) = header {
+            let bold = Style {
+                bold: true,
+                ..Style::default()
+            };
+            self.styled(&bold, format(header).iter().map(String::as_str))?;
+        }
+        for row in rows {
+            self.styled(&Style::default(), format(row).iter().map(String::as_str))?;
+        }
+        Ok(())
+    }
+}
+
+/// Commands switching bold (`ESC E`), underline (`ESC -`) and reverse (`GS B`) from
+/// `from` to `to`, for the ones that differ.
+fn switch(from: &Style, to: &Style) -> Vec<u8> {
+    let mut cmd = Vec::new();
+    if from.bold != to.bold {
+        cmd.extend([ESC, b'E', u8::from(to.bold)]);
+    }
+    if from.underline != to.underline {
+        cmd.extend([ESC, b'-', u8::from(to.underline)]);
+    }
+    if from.invert != to.invert {
+        cmd.extend([GS, b'B', u8::from(to.invert)]);
+    }
+    cmd
+}
+
+/// Characters that fit on a line in `style`'s font and size.
+fn columns(style: &Style) -> usize {
+    let base = match style.font {
+        Font::A => COLUMNS,
+        Font::B => COLUMNS_FONT_B,
+    };
+    base / style.size.max(1) as usize
+}
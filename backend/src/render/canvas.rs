@@ -0,0 +1,309 @@
+//! A virtual receipt the writer draws on alongside the ESC/POS stream, so previews
+//! show the paper as the printer lays it out: fonts, sizes and styles, graphics and
+//! the space they take.
+
+use ab_glyph::{Font as _, FontRef, PxScale, ScaleFont, point};
+use anyhow::Result;
+use image::{GrayImage, ImageFormat, Luma};
+use std::io::Cursor;
+
+use super::emoji;
+use super::preview::{CELL_HEIGHT, CELL_WIDTH, FONT, LINE_SPACING, PAPER_WIDTH_DOTS};
+use crate::document::{Align, Font, Hri, Style, Symbology};
+
+/// White border around the paper in the PNG.
+const MARGIN: u32 = 16;
+/// Font B character cell, in dots.
+const CELL_WIDTH_FONT_B: u32 = 9;
+const CELL_HEIGHT_FONT_B: u32 = 17;
+/// Pixel size of the preview font in a font A cell.
+const FONT_PX: f32 = 20.0;
+
+const WHITE: u8 = 255;
+const BLACK: u8 = 0;
+
+/// The paper so far, one byte per dot, growing downwards as lines are added.
+#[derive(Default)]
+pub(super) struct Canvas {
+    pixels: Vec<u8>,
+    height: u32,
+}
+
+impl Canvas {
+    /// One printed line of text in `style`, justified the way the printer does it.
+    /// `lead` is drawn first on the line, e.g. a glyph.
+    pub fn text(&mut self, line: &str, style: &Style, lead: Option<&GrayImage>) {
+        self.spans(&[(line, *style)], style, lead);
+    }
+
+    /// A printed line made of runs in their own bold, underline and reverse. `style`
+    /// gives the font, size and justification they share.
+    pub fn spans<S: AsRef<str>>(
+        &mut self,
+        runs: &[(S, Style)],
+        style: &Style,
+        lead: Option<&GrayImage>,
+    ) {
+        let (cell_width, _) = cell(style);
+        let lead_width = lead.map_or(0, GrayImage::width);
+        let width: u32 = lead_width
+            + runs
+                .iter()
+                .flat_map(|(run, _)| run.as_ref().chars())
+                .map(|c| advance(c, cell_width))
+                .sum::<u32>();
+        let top = self.height;
+        let height = line_height(style).max(lead.map_or(0, GrayImage::height));
+        self.grow(top + height);
+
+        let mut x = offset(width, style.align);
+        if let Some(lead) = lead {
+            self.paste(lead, x, top);
+            x += lead_width;
+        }
+        self.draw(runs, style, x, top);
+    }
+
+    /// Leave `height` dots for a page and return its top, for [`Canvas::text_at`].
+    pub fn area(&mut self, height: u32) -> u32 {
+        let top = self.height;
+        self.grow(top + height);
+        top
+    }
+
+    /// Text in `style` with its top left corner at `x`, `y`, inside an area.
+    pub fn text_at(&mut self, line: &str, style: &Style, x: u32, y: u32) {
+        self.draw(&[(line, *style)], style, x, y);
+    }
+
+    /// Runs of text from `x` along a line whose top is `top`.
+    fn draw<S: AsRef<str>>(&mut self, runs: &[(S, Style)], style: &Style, mut x: u32, top: u32) {
+        let size = u32::from(style.size.max(1));
+        let (cell_width, cell_height) = cell(style);
+        let Ok(font) = FontRef::try_from_slice(FONT) else {
+            return;
+        };
+        let scale = PxScale::from(FONT_PX * cell_height as f32 / CELL_HEIGHT as f32);
+        let ascent = font.as_scaled(scale).ascent();
+        for (run, run_style) in runs {
+            let run = run.as_ref();
+            let (ink, paper) = if run_style.invert {
+                (WHITE, BLACK)
+            } else {
+                (BLACK, WHITE)
+            };
+            if run_style.invert {
+                let run_width = run.chars().map(|c| advance(c, cell_width)).sum();
+                self.fill(x, top, run_width, cell_height, paper);
+            }
+            for c in run.chars() {
+                if emoji::is_emoji(c) {
+                    if let Some(image) = emoji::image(c) {
+                        self.paste(&image, x, top);
+                    }
+                    x += CELL_WIDTH;
+                    continue;
+                }
+                let glyph = font
+                    .glyph_id(c)
+                    .with_scale_and_position(scale, point(x as f32, top as f32 + ascent));
+                if let Some(outlined) = font.outline_glyph(glyph) {
+                    let bounds = outlined.px_bounds();
+                    let weight = if run_style.bold {
+                        1 + size.div_ceil(2)
+                    } else {
+                        1
+                    };
+                    outlined.draw(|gx, gy, coverage| {
+                        if coverage > 0.5 {
+                            let gx = bounds.min.x as i32 + gx as i32;
+                            let gy = bounds.min.y as i32 + gy as i32;
+                            for dx in 0..weight as i32 {
+                                self.set(gx + dx, gy, ink);
+                            }
+                        }
+                    });
+                }
+                if run_style.underline {
+                    self.fill(x, top + cell_height - size, cell_width, size, ink);
+                }
+                x += cell_width;
+            }
+        }
+    }
+
+    /// A bit image, thresholded as the print head would.
+    pub fn image(&mut self, image: &GrayImage, align: Align) {
+        let top = self.height;
+        self.grow(top + image.height());
+        self.paste(image, offset(image.width(), align), top);
+    }
+
+    /// A barcode `height` dots tall with `width`-dot modules and its human-readable
+    /// text where `hri` puts it. The bars have the symbology's overall width, but
+    /// their pattern only stands in for the real encoding.
+    pub fn barcode(
+        &mut self,
+        data: &str,
+        symbology: Symbology,
+        height: u8,
+        width: u8,
+        hri: Hri,
+        align: Align,
+    ) {
+        let modules = bars(data, symbology);
+        let module = u32::from(width);
+        let image = GrayImage::from_fn(modules.len() as u32 * module, height.into(), |x, _| {
+            Luma([if modules[(x / module) as usize] {
+                BLACK
+            } else {
+                WHITE
+            }])
+        });
+        let label = Style {
+            align,
+            ..Style::default()
+        };
+        if matches!(hri, Hri::Above | Hri::Both) {
+            self.text(data, &label, None);
+        }
+        self.image(&image, align);
+        if matches!(hri, Hri::Below | Hri::Both) {
+            self.text(data, &label, None);
+        }
+    }
+
+    pub fn feed(&mut self, dots: u32) {
+        self.grow(self.height + dots);
+    }
+
+    /// A dashed line where the knife cuts.
+    pub fn cut(&mut self) {
+        let top = self.height;
+        self.grow(top + CELL_HEIGHT);
+        for x in (0..PAPER_WIDTH_DOTS).filter(|x| (x / 6) % 2 == 0) {
+            self.set(x as i32, (top + CELL_HEIGHT / 2) as i32, BLACK);
+        }
+    }
+
+    /// The paper with a white margin, as a PNG.
+    pub fn png(&self) -> Result<Vec<u8>> {
+        let width = PAPER_WIDTH_DOTS + MARGIN * 2;
+        let mut image = GrayImage::from_pixel(width, self.height + MARGIN * 2, Luma([WHITE]));
+        for (i, pixel) in self.pixels.iter().enumerate() {
+            let (x, y) = (i as u32 % PAPER_WIDTH_DOTS, i as u32 / PAPER_WIDTH_DOTS);
+            image.put_pixel(x + MARGIN, y + MARGIN, Luma([*pixel]));
+        }
+        let mut png = Vec::new();
+        image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+        Ok(png)
+    }
+
+    fn grow(&mut self, height: u32) {
+        if height > self.height {
+            self.height = height;
+            self.pixels
+                .resize((PAPER_WIDTH_DOTS * height) as usize, WHITE);
+        }
+    }
+
+    fn set(&mut self, x: i32, y: i32, value: u8) {
+        if x >= 0 && y >= 0 && (x as u32) < PAPER_WIDTH_DOTS && (y as u32) < self.height {
+            self.pixels[y as usize * PAPER_WIDTH_DOTS as usize + x as usize] = value;
+        }
+    }
+
+    fn fill(&mut self, x: u32, y: u32, width: u32, height: u32, value: u8) {
+        for row in y..y + height {
+            for column in x..x + width {
+                self.set(column as i32, row as i32, value);
+            }
+        }
+    }
+
+    fn paste(&mut self, image: &GrayImage, x: u32, y: u32) {
+        for (ix, iy, pixel) in image.enumerate_pixels() {
+            if pixel.0[0] < 128 {
+                self.set((x + ix) as i32, (y + iy) as i32, BLACK);
+            }
+        }
+    }
+}
+
+/// Character cell of `style`'s font and size, in dots.
+pub(super) fn cell(style: &Style) -> (u32, u32) {
+    let size = u32::from(style.size.max(1));
+    match style.font {
+        Font::A => (CELL_WIDTH * size, CELL_HEIGHT * size),
+        Font::B => (CELL_WIDTH_FONT_B * size, CELL_HEIGHT_FONT_B * size),
+    }
+}
+
+/// Paper fed by a line of text in `style`: the line spacing, or the characters'
+/// height if they are taller.
+pub(super) fn line_height(style: &Style) -> u32 {
+    LINE_SPACING.max(cell(style).1)
+}
+
+/// Width of `c` in a line of `cell_width` characters; emoji are one font A cell.
+pub(super) fn advance(c: char, cell_width: u32) -> u32 {
+    if emoji::is_emoji(c) {
+        CELL_WIDTH
+    } else {
+        cell_width
+    }
+}
+
+/// Left edge of something `width` dots wide, justified on the paper.
+fn offset(width: u32, align: Align) -> u32 {
+    let slack = PAPER_WIDTH_DOTS.saturating_sub(width);
+    match align {
+        Align::Left => 0,
+        Align::Center => slack / 2,
+        Align::Right => slack,
+    }
+}
+
+/// A 24-dot bit image sent column by column (`ESC *`), as an image.
+pub(super) fn bit_image(width: u32, columns: &[u8]) -> GrayImage {
+    GrayImage::from_fn(width, 24, |x, y| {
+        let byte = columns.get((x * 3 + y / 8) as usize).copied().unwrap_or(0);
+        Luma([if byte & (0x80 >> (y % 8)) != 0 {
+            BLACK
+        } else {
+            WHITE
+        }])
+    })
+}
+
+/// Modules of a stand-in barcode: as many as `symbology` needs for `data`, between
+/// the usual guard bars, with bars and spaces of one to three modules derived from
+/// the data.
+fn bars(data: &str, symbology: Symbology) -> Vec<bool> {
+    let n = data.chars().count();
+    let total = match symbology {
+        Symbology::Ean13 | Symbology::Upca => 95,
+        Symbology::Ean8 => 67,
+        Symbology::Upce => 51,
+        Symbology::Code128 => 11 * (n + 2) + 13,
+        Symbology::Code39 => 16 * (n + 2) - 1,
+        Symbology::Itf => 9 * n + 9,
+        Symbology::Codabar => 13 * (n + 2) - 1,
+    };
+    let mut seed = data.bytes().fold(0x811c_9dc5_u32, |h, b| {
+        (h ^ u32::from(b)).wrapping_mul(0x0100_0193)
+    });
+    let mut modules = vec![true, false, true];
+    let mut dark = false;
+    while modules.len() < total.saturating_sub(3) {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        let run = 1 + (seed >> 16) % 3;
+        for _ in 0..run {
+            modules.push(dark);
+        }
+        dark = !dark;
+    }
+    modules.truncate(total.saturating_sub(3));
+    modules.extend([true, false, true]);
+    modules
+}
@@ -0,0 +1,193 @@
+//! Getting text into the printer's character sets. Text is transliterated to what
+//! the printer's code pages can print, then encoded byte by byte, switching code page
+//! with `ESC t` whenever the next character is only found in another one.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::emoji::{self, Emoji};
+
+const ESC: u8 = 0x1b;
+
+/// A code page the printer can select with `ESC t`. The lower half of each is ASCII.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CodePage {
+    /// US English and box drawing; selected when the printer starts up.
+    Cp437,
+    /// Greek.
+    Cp737,
+    /// Western European.
+    Cp850,
+    /// Central European.
+    Cp852,
+    /// Western European with the euro sign.
+    Cp858,
+    /// Cyrillic.
+    Cp866,
+    /// Central European, Windows.
+    Cp1250,
+    /// Cyrillic, Windows.
+    Cp1251,
+    /// Western European, Windows.
+    Cp1252,
+    /// Greek, Windows.
+    Cp1253,
+}
+
+impl CodePage {
+    /// The page's number for `ESC t`, as Epson assigns them.
+    fn number(self) -> u8 {
+        match self {
+            CodePage::Cp437 => 0,
+            CodePage::Cp737 => 14,
+            CodePage::Cp850 => 2,
+            CodePage::Cp852 => 18,
+            CodePage::Cp858 => 19,
+            CodePage::Cp866 => 17,
+            CodePage::Cp1250 => 45,
+            CodePage::Cp1251 => 46,
+            CodePage::Cp1252 => 16,
+            CodePage::Cp1253 => 47,
+        }
+    }
+
+    /// Characters from 0x80 to 0xFF; unassigned bytes are NUL, which is never looked up.
+    fn high(self) -> &'static str {
+        match self {
+            CodePage::Cp437 => CP437,
+            CodePage::Cp737 => CP737,
+            CodePage::Cp850 => CP850,
+            CodePage::Cp852 => CP852,
+            CodePage::Cp858 => CP858,
+            CodePage::Cp866 => CP866,
+            CodePage::Cp1250 => CP1250,
+            CodePage::Cp1251 => CP1251,
+            CodePage::Cp1252 => CP1252,
+            CodePage::Cp1253 => CP1253,
+        }
+    }
+
+    /// The byte for `c` in this page.
+    fn byte(self, c: char) -> Option<u8> {
+        if c.is_ascii() {
+            return Some(c as u8);
+        }
+        self.high()
+            .chars()
+            .position(|high| high == c)
+            .map(|i| 0x80 + i as u8)
+    }
+}
+
+/// The code pages a printer has, in order of preference.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CodePages(pub Vec<CodePage>);
+
+/// Printers start up in code page 437, which is all that can be relied on.
+impl Default for CodePages {
+    fn default() -> Self {
+        Self(vec![CodePage::Cp437])
+    }
+}
+
+impl CodePages {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.0.is_empty() {
+            return Err("must list at least one code page".into());
+        }
+        Ok(())
+    }
+
+    fn printable(&self, c: char) -> bool {
+        c.is_ascii() || self.0.iter().any(|page| page.byte(c).is_some())
+    }
+}
+
+impl From<CodePages> for String {
+    fn from(pages: CodePages) -> Self {
+        serde_json::to_string(&pages).unwrap_or_else(|_| "[\"cp437\"]".into())
+    }
+}
+
+/// Replace what none of `pages` can print: punctuation and symbols by their nearest
+/// ASCII spelling, accented and foreign letters by their closest Latin ones, and
+/// anything else by `replacement`. Emoji are kept when they can be printed as images,
+/// or dropped with the space after them when `emoji` says to strip them. Control
+/// characters are dropped.
+pub fn transliterate(text: &str, pages: &CodePages, emoji: Emoji, replacement: char) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut stripped = false;
+    for c in text.chars() {
+        if stripped && c == ' ' && (out.is_empty() || out.ends_with(' ')) {
+            continue;
+        }
+        stripped = false;
+        match c {
+            '\t' => out.push(' '),
+            c if c.is_control() || emoji::is_modifier(c) => {}
+            c if pages.printable(c) => out.push(c),
+            c if emoji::is_emoji(c) && emoji == Emoji::Strip => stripped = true,
+            c if emoji::is_emoji(c) && emoji::image(c).is_some() => out.push(c),
+            c => match nearest(c) {
+                Some(s) => out.push_str(s),
+                None => out.push(replacement),
+            },
+        }
+    }
+    out
+}
+
+/// Bytes for transliterated text. `active` is the page the printer has selected: it is
+/// kept while it can print the next character, otherwise the first of `pages` that can
+/// is selected. Anything no page has, such as emoji, is skipped.
+pub fn encode(text: &str, pages: &CodePages, active: &mut CodePage) -> Vec<u8> {
+    let mut out = Vec::with_capacity(text.len());
+    for c in text.chars().filter(|c| !c.is_control()) {
+        if let Some(byte) = active.byte(c) {
+            out.push(byte);
+            continue;
+        }
+        let Some((page, byte)) = pages.0.iter().find_map(|page| Some((*page, page.byte(c)?)))
+        else {
+            continue;
+        };
+        out.extend([ESC, b't', page.number(), byte]);
+        *active = page;
+    }
+    out
+}
+
+fn nearest(c: char) -> Option<&'static str> {
+    let s = match c {
+        '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}' | '\u{2032}' => "'",
+        '\u{201c}' | '\u{201d}' | '\u{201e}' | '\u{201f}' | '\u{2033}' => "\"",
+        '\u{2010}'..='\u{2015}' | '\u{2212}' => "-",
+        '\u{2026}' => "...",
+        '\u{2022}' | '\u{2023}' | '\u{25e6}' => "*",
+        '\u{2002}'..='\u{200a}' | '\u{202f}' => " ",
+        '\u{20ac}' => "EUR",
+        '\u{2122}' => "TM",
+        '\u{a9}' => "(c)",
+        '\u{ae}' => "(R)",
+        '\u{d7}' => "x",
+        '\u{2190}' => "<-",
+        '\u{2192}' => "->",
+        '\u{2194}' => "<->",
+        _ if emoji::is_emoji(c) => return None,
+        _ => deunicode::deunicode_char(c)?,
+    };
+    Some(s)
+}
+
+const CP437: &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+const CP737: &str = "ΑΒΓΔΕΖΗΘΙΚΛΜΝΞΟΠΡΣΤΥΦΧΨΩαβγδεζηθικλμνξοπρσςτυφχψ░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀ωάέήϊίόύϋώΆΈΉΊΌΎΏ±≥≤ΪΫ÷≈°∙·√ⁿ²■\u{a0}";
+const CP850: &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜø£Ø×ƒáíóúñÑªº¿®¬½¼¡«»░▒▓│┤ÁÂÀ©╣║╗╝¢¥┐└┴┬├─┼ãÃ╚╔╩╦╠═╬¤ðÐÊËÈıÍÎÏ┘┌█▄¦Ì▀ÓßÔÒõÕµþÞÚÛÙýÝ¯´\u{ad}±‗¾¶§÷¸°¨·¹³²■\u{a0}";
+const CP852: &str = "ÇüéâäůćçłëŐőîŹÄĆÉĹĺôöĽľŚśÖÜŤťŁ×čáíóúĄąŽžĘę¬źČş«»░▒▓│┤ÁÂĚŞ╣║╗╝Żż┐└┴┬├─┼Ăă╚╔╩╦╠═╬¤đĐĎËďŇÍÎě┘┌█▄ŢŮ▀ÓßÔŃńňŠšŔÚŕŰýÝţ´\u{ad}˝˛ˇ˘§÷¸°¨˙űŘř■\u{a0}";
+const CP858: &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜø£Ø×ƒáíóúñÑªº¿®¬½¼¡«»░▒▓│┤ÁÂÀ©╣║╗╝¢¥┐└┴┬├─┼ãÃ╚╔╩╦╠═╬¤ðÐÊËÈ€ÍÎÏ┘┌█▄¦Ì▀ÓßÔÒõÕµþÞÚÛÙýÝ¯´\u{ad}±‗¾¶§÷¸°¨·¹³²■\u{a0}";
+const CP866: &str = "АБВГДЕЖЗИЙКЛМНОПРСТУФХЦЧШЩЪЫЬЭЮЯабвгдежзийклмноп░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀рстуфхцчшщъыьэюяЁёЄєЇїЎў°∙·√№¤■\u{a0}";
+const CP1250: &str = "€\u{0}‚\u{0}„…†‡\u{0}‰Š‹ŚŤŽŹ\u{0}‘’“”•–—\u{0}™š›śťžź\u{a0}ˇ˘Ł¤Ą¦§¨©Ş«¬\u{ad}®Ż°±˛ł´µ¶·¸ąş»Ľ˝ľżŔÁÂĂÄĹĆÇČÉĘËĚÍÎĎĐŃŇÓÔŐÖ×ŘŮÚŰÜÝŢßŕáâăäĺćçčéęëěíîďđńňóôőö÷řůúűüýţ˙";
+const CP1251: &str = "ЂЃ‚ѓ„…†‡€‰Љ‹ЊЌЋЏђ‘’“”•–—\u{0}™љ›њќћџ\u{a0}ЎўЈ¤Ґ¦§Ё©Є«¬\u{ad}®Ї°±Ііґµ¶·ё№є»јЅѕїАБВГДЕЖЗИЙКЛМНОПРСТУФХЦЧШЩЪЫЬЭЮЯабвгдежзийклмнопрстуфхцчшщъыьэюя";
+const CP1252: &str = "€\u{0}‚ƒ„…†‡ˆ‰Š‹Œ\u{0}Ž\u{0}\u{0}‘’“”•–—˜™š›œ\u{0}žŸ\u{a0}¡¢£¤¥¦§¨©ª«¬\u{ad}®¯°±²³´µ¶·¸¹º»¼½¾¿ÀÁÂÃÄÅÆÇÈÉÊËÌÍÎÏÐÑÒÓÔÕÖ×ØÙÚÛÜÝÞßàáâãäåæçèéêëìíîïðñòóôõö÷øùúûüýþÿ";
+const CP1253: &str = "€\u{0}‚ƒ„…†‡\u{0}‰\u{0}‹\u{0}\u{0}\u{0}\u{0}\u{0}‘’“”•–—\u{0}™\u{0}›\u{0}\u{0}\u{0}\u{0}\u{a0}΅Ά£¤¥¦§¨©\u{0}«¬\u{ad}®―°±²³΄µ¶·ΈΉΊ»Ό½ΎΏΐΑΒΓΔΕΖΗΘΙΚΛΜΝΞΟΠΡ\u{0}ΣΤΥΦΧΨΩΪΫάέήίΰαβγδεζηθικλμνξοπρςστυφχψωϊϋόύώ\u{0}";
@@ -0,0 +1,180 @@
+//! Emoji, which no code page has. They are printed inline as small bit images, one
+//! character cell each, drawn from the built-in icons or from the symbols in the preview
+//! font, or left out of the text altogether.
+
+use ab_glyph::{Font, FontRef, PxScale};
+use image::{GrayImage, Luma};
+use std::str::FromStr;
+
+use super::preview::{CELL_HEIGHT, CELL_WIDTH, FONT};
+use crate::glyphs::icons;
+
+/// What a printer does with emoji in text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Emoji {
+    /// Print them as images where there is one; others become the replacement
+    /// character.
+    #[default]
+    Raster,
+    /// Leave them out.
+    Strip,
+}
+
+impl Emoji {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Emoji::Raster => "raster",
+            Emoji::Strip => "strip",
+        }
+    }
+}
+
+impl FromStr for Emoji {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "raster" => Ok(Emoji::Raster),
+            "strip" => Ok(Emoji::Strip),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Emoji, dingbats and other pictographs.
+pub fn is_emoji(c: char) -> bool {
+    matches!(
+        c,
+        '\u{231a}' | '\u{231b}' | '\u{23e9}'..='\u{23fa}' | '\u{2600}'..='\u{27bf}'
+            | '\u{2b00}'..='\u{2bff}' | '\u{1f000}'..
+    )
+}
+
+/// Characters that only shape the emoji around them: joiners, variation selectors and
+/// skin tones.
+pub fn is_modifier(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200b}'..='\u{200d}' | '\u{fe00}'..='\u{fe0f}' | '\u{1f3fb}'..='\u{1f3ff}'
+    )
+}
+
+/// `c` as a black-on-white image filling a font A character cell.
+pub fn image(c: char) -> Option<GrayImage> {
+    if let Some((_, icon)) = DRAWN.iter().find(|(emoji, _)| *emoji == c) {
+        return icons::image(icon);
+    }
+    let symbol = SYMBOLS
+        .iter()
+        .find(|(emoji, _)| *emoji == c)
+        .map_or(c, |(_, symbol)| *symbol);
+    is_emoji(symbol).then(|| symbol_image(symbol)).flatten()
+}
+
+/// `c` as a 24-dot bit image column by column (`ESC * 33`), the way glyphs are sent.
+pub fn bitmap(c: char) -> Option<Vec<u8>> {
+    let image = image(c)?;
+    let mut bitmap = vec![0u8; CELL_WIDTH as usize * 3];
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if pixel.0[0] < 128 {
+            bitmap[x as usize * 3 + y as usize / 8] |= 0x80 >> (y % 8);
+        }
+    }
+    Some(bitmap)
+}
+
+/// A symbol from the preview font, scaled to fit the cell.
+fn symbol_image(symbol: char) -> Option<GrayImage> {
+    let font = FontRef::try_from_slice(FONT).ok()?;
+    let id = font.glyph_id(symbol);
+    if id.0 == 0 {
+        return None;
+    }
+    let bounds = font
+        .outline_glyph(id.with_scale(PxScale::from(CELL_HEIGHT as f32)))?
+        .px_bounds();
+    let fit = (CELL_WIDTH as f32 / bounds.width())
+        .min((CELL_HEIGHT - 4) as f32 / bounds.height())
+        .min(1.0);
+    let outlined = font.outline_glyph(id.with_scale(PxScale::from(CELL_HEIGHT as f32 * fit)))?;
+    let bounds = outlined.px_bounds();
+    let left = ((CELL_WIDTH as f32 - bounds.width()) / 2.0).max(0.0) as u32;
+    let top = ((CELL_HEIGHT as f32 - bounds.height()) / 2.0).max(0.0) as u32;
+
+    let mut image = GrayImage::from_pixel(CELL_WIDTH, CELL_HEIGHT, Luma([255]));
+    outlined.draw(|x, y, coverage| {
+        let (x, y) = (left + x, top + y);
+        if coverage > 0.5 && x < CELL_WIDTH && y < CELL_HEIGHT {
+            image.put_pixel(x, y, Luma([0]));
+        }
+    });
+    Some(image)
+}
+
+/// Emoji with a close enough symbol in the preview font.
+const SYMBOLS: &[(char, char)] = &[
+    ('😀', '☺'),
+    ('😃', '☺'),
+    ('😄', '☺'),
+    ('😁', '☺'),
+    ('😊', '☺'),
+    ('🙂', '☺'),
+    ('😉', '☺'),
+    ('🥳', '☺'),
+    ('🙁', '☹'),
+    ('😞', '☹'),
+    ('😢', '☹'),
+    ('😔', '☹'),
+    ('🌞', '☀'),
+    ('🔆', '☀'),
+    ('⛅', '☁'),
+    ('🌤', '☁'),
+    ('🌥', '☁'),
+    ('🌦', '☂'),
+    ('🌧', '☔'),
+    ('⛈', '☈'),
+    ('🌙', '☾'),
+    ('🌛', '☾'),
+    ('⭐', '★'),
+    ('🌟', '★'),
+    ('💙', '♥'),
+    ('💚', '♥'),
+    ('💛', '♥'),
+    ('💜', '♥'),
+    ('🧡', '♥'),
+    ('🖤', '♥'),
+    ('💕', '♥'),
+    ('💖', '♥'),
+    ('🎵', '♪'),
+    ('🎶', '♫'),
+    ('📞', '☎'),
+    ('📱', '☎'),
+    ('📧', '✉'),
+    ('📨', '✉'),
+    ('📩', '✉'),
+    ('💌', '✉'),
+    ('📝', '✎'),
+    ('✅', '✔'),
+    ('❌', '✘'),
+    ('❎', '✘'),
+    ('❗', '❕'),
+    ('🚩', '⚑'),
+    ('🍀', '☘'),
+    ('💀', '☠'),
+];
+
+/// Common calendar emoji the font has nothing for, drawn with the built-in icons.
+const DRAWN: &[(char, &str)] = &[
+    ('🎂', "cake"),
+    ('🎉', "party"),
+    ('📅', "calendar"),
+    ('⏰', "alarm"),
+    ('🎁', "gift"),
+    ('🏠', "home"),
+    ('🚗', "car"),
+    ('🔔', "bell"),
+    ('💊', "pill"),
+    ('🛒', "cart"),
+    ('💡', "bulb"),
+    ('🗑', "trash"),
+];
@@ -0,0 +1,87 @@
+//! Letter and number grids for puzzles, drawn as images so their squares line up
+//! exactly, whatever the printer's fonts.
+
+use ab_glyph::{Font, FontRef, PxScale, point};
+use image::{DynamicImage, GrayImage, ImageFormat, Luma};
+use std::io::Cursor;
+
+use super::banner::BANNER_FONT;
+use super::preview::PAPER_WIDTH_DOTS;
+
+const BLACK: Luma<u8> = Luma([0]);
+
+/// `rows` of characters, a space for an empty square, in squares `cell` dots wide.
+/// Every `boxes` squares the line is drawn thicker, as between a sudoku's boxes;
+/// `lines` false leaves the squares unruled, as in a word search.
+pub fn png(rows: &[Vec<char>], cell: u32, boxes: usize, lines: bool) -> Result<Vec<u8>, String> {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0) as u32;
+    let cell = cell.min((PAPER_WIDTH_DOTS - 4) / columns.max(1));
+    let (width, height) = (columns * cell + 4, rows.len() as u32 * cell + 4);
+    let mut image = GrayImage::from_pixel(width, height, Luma([255]));
+
+    if lines {
+        let thick = |i: usize| i == 0 || boxes > 0 && i.is_multiple_of(boxes);
+        for i in 0..=columns as usize {
+            let weight = if thick(i) || i == columns as usize {
+                4
+            } else {
+                1
+            };
+            fill(
+                &mut image,
+                i as u32 * cell + 2 - weight / 2,
+                0,
+                weight,
+                height,
+            );
+        }
+        for i in 0..=rows.len() {
+            let weight = if thick(i) || i == rows.len() { 4 } else { 1 };
+            fill(
+                &mut image,
+                0,
+                i as u32 * cell + 2 - weight / 2,
+                width,
+                weight,
+            );
+        }
+    }
+
+    let font = FontRef::try_from_slice(BANNER_FONT).map_err(|e| e.to_string())?;
+    let scale = PxScale::from(cell as f32 * 0.7);
+    for (y, row) in rows.iter().enumerate() {
+        for (x, &c) in row.iter().enumerate() {
+            let glyph = font
+                .glyph_id(c)
+                .with_scale_and_position(scale, point(0.0, 0.0));
+            let Some(outlined) = font.outline_glyph(glyph) else {
+                continue;
+            };
+            // Centre the glyph's ink, not its advance, in the square.
+            let bounds = outlined.px_bounds();
+            let left = (x as u32 * cell + 2) as f32 + (cell as f32 - bounds.width()) / 2.0;
+            let top = (y as u32 * cell + 2) as f32 + (cell as f32 - bounds.height()) / 2.0;
+            outlined.draw(|gx, gy, coverage| {
+                let (px, py) = (left as u32 + gx, top as u32 + gy);
+                if coverage > 0.5 && px < width && py < height {
+                    image.put_pixel(px, py, BLACK);
+                }
+            });
+        }
+    }
+
+    let mut png = Vec::new();
+    DynamicImage::ImageLuma8(image)
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(png)
+}
+
+/// Black out a rectangle, clipped to the image.
+fn fill(image: &mut GrayImage, x: u32, y: u32, width: u32, height: u32) {
+    for py in y..(y + height).min(image.height()) {
+        for px in x..(x + width).min(image.width()) {
+            image.put_pixel(px, py, BLACK);
+        }
+    }
+}
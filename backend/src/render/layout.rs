@@ -0,0 +1,304 @@
+//! Fitting lines of text to the width of the paper.
+
+use crate::document::{Align, ColumnWidth, Overflow};
+
+/// Printed at the end of a line cut short. The printer's code pages have no single
+/// ellipsis character.
+const ELLIPSIS: &str = "...";
+
+/// Lay out `line` in at most `columns` characters per printed line, following
+/// `overflow` if it is too long.
+pub fn fit(line: &str, columns: usize, overflow: Overflow) -> Vec<String> {
+    let chars: Vec<(char, ())> = line.chars().map(|c| (c, ())).collect();
+    fit_styled(&chars, columns, overflow)
+        .into_iter()
+        .map(|line| line.into_iter().map(|(c, _)| c).collect())
+        .collect()
+}
+
+/// [`fit`] for characters that each carry a style, which goes with them onto
+/// whichever printed line they land on.
+pub fn fit_styled<T: Copy>(
+    line: &[(char, T)],
+    columns: usize,
+    overflow: Overflow,
+) -> Vec<Vec<(char, T)>> {
+    let columns = columns.max(1);
+    if line.len() <= columns {
+        return vec![line.to_vec()];
+    }
+    match overflow {
+        Overflow::Wrap => wrap(line, columns),
+        Overflow::Truncate => vec![line[..columns].to_vec()],
+        Overflow::Ellipsis => {
+            let keep = columns.saturating_sub(ELLIPSIS.len());
+            let mut cut = line[..keep].to_vec();
+            while cut.last().is_some_and(|(c, _)| c.is_whitespace()) {
+                cut.pop();
+            }
+            let style = line[keep].1;
+            let dots = ELLIPSIS.chars().take(columns.min(ELLIPSIS.len()));
+            cut.extend(dots.map(|c| (c, style)));
+            vec![cut]
+        }
+    }
+}
+
+/// Break between words, so the printer never has to wrap mid-word. Continuation
+/// lines keep the line's indentation; a word longer than a whole line is split.
+fn wrap<T: Copy>(line: &[(char, T)], columns: usize) -> Vec<Vec<(char, T)>> {
+    let (mut indent, body) =
+        line.split_at(line.iter().take_while(|(c, _)| c.is_whitespace()).count());
+    if indent.len() * 2 > columns {
+        indent = &[];
+    }
+    let room = columns - indent.len();
+
+    let mut lines = Vec::new();
+    let mut current = indent.to_vec();
+    let mut width = 0;
+    let mut start = 0;
+    while start < body.len() {
+        if body[start].0.is_whitespace() {
+            start += 1;
+            continue;
+        }
+        let len = body[start..]
+            .iter()
+            .take_while(|(c, _)| !c.is_whitespace())
+            .count();
+        let word = &body[start..start + len];
+        start += len;
+        if width > 0 && width + 1 + len <= room {
+            // The gap takes the style of the space before the word.
+            let gap = body[start - len - 1].1;
+            current.push((' ', gap));
+            current.extend_from_slice(word);
+            width += 1 + len;
+            continue;
+        }
+        if width > 0 {
+            lines.push(std::mem::replace(&mut current, indent.to_vec()));
+        }
+        let mut rest = word;
+        while rest.len() > room {
+            current.extend_from_slice(&rest[..room]);
+            lines.push(std::mem::replace(&mut current, indent.to_vec()));
+            rest = &rest[room..];
+        }
+        current.extend_from_slice(rest);
+        width = rest.len();
+    }
+    if width > 0 || lines.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// `text` padded to `width` characters according to `align`.
+pub fn pad(text: &str, width: usize, align: Align) -> String {
+    let slack = width.saturating_sub(text.chars().count());
+    let left = match align {
+        Align::Left => 0,
+        Align::Center => slack / 2,
+        Align::Right => slack,
+    };
+    format!("{}{text}{}", " ".repeat(left), " ".repeat(slack - left))
+}
+
+/// Widths of columns sharing `available` characters. Fixed columns get their width
+/// and auto columns their `natural` width, narrowing the widest auto columns (then
+/// fixed columns) while the row is too wide. Weighted columns divide what is left.
+pub fn column_widths(specs: &[ColumnWidth], natural: &[usize], available: usize) -> Vec<usize> {
+    // No column starts wider than the whole row.
+    let most = available.max(1);
+    let mut widths: Vec<usize> = specs
+        .iter()
+        .zip(natural)
+        .map(|(spec, natural)| match spec {
+            ColumnWidth::Auto => (*natural).clamp(1, most),
+            ColumnWidth::Fixed(width) => (*width).min(most),
+            ColumnWidth::Weight(_) => 1,
+        })
+        .collect();
+    let mut excess = sum(&widths).saturating_sub(available);
+    for auto in [true, false] {
+        let group: Vec<usize> = (0..widths.len())
+            .filter(|&i| match specs[i] {
+                ColumnWidth::Auto => auto,
+                ColumnWidth::Fixed(_) => !auto,
+                ColumnWidth::Weight(_) => false,
+            })
+            .collect();
+        excess = narrow(&mut widths, &group, excess);
+    }
+
+    let weight = |spec: &ColumnWidth| match spec {
+        ColumnWidth::Weight(weight) => *weight as u128,
+        _ => 0,
+    };
+    let total: u128 = specs.iter().map(weight).sum();
+    if total == 0 {
+        return widths;
+    }
+    let spare = available.saturating_sub(sum(&widths));
+    let mut given = 0;
+    for (width, spec) in widths.iter_mut().zip(specs) {
+        // No more than `spare`, as the weights are a share of their total.
+        let share = (spare as u128 * weight(spec) / total) as usize;
+        *width += share;
+        given += share;
+    }
+    // Rounding leaves less than one character per weighted column.
+    let weighted = widths
+        .iter_mut()
+        .zip(specs)
+        .filter(|(_, spec)| weight(spec) > 0);
+    for (width, _) in weighted.take(spare - given) {
+        *width += 1;
+    }
+    widths
+}
+
+fn sum(widths: &[usize]) -> usize {
+    widths
+        .iter()
+        .fold(0, |sum, width| sum.saturating_add(*width))
+}
+
+/// Take up to `excess` characters off the columns at `group`, lowering the widest
+/// to a common width but none below one. Returns what couldn't be taken off.
+fn narrow(widths: &mut [usize], group: &[usize], excess: usize) -> usize {
+    let above = |level: usize| {
+        group.iter().fold(0, |sum: usize, &i| {
+            sum.saturating_add(widths[i].saturating_sub(level))
+        })
+    };
+    if excess == 0 || above(1) == 0 {
+        return excess;
+    }
+    // The highest width to lower the group to that takes off enough, or one.
+    let (mut low, mut high) = (1, group.iter().map(|&i| widths[i]).max().unwrap_or(1));
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        if above(mid) >= excess {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    let (taken, mut over) = (above(low).min(excess), above(low).saturating_sub(excess));
+    // Lowering every column above the level can take off more than needed; the
+    // first of them keep a character back.
+    for &i in group {
+        if widths[i] > low {
+            widths[i] = low + usize::from(over > 0);
+            over = over.saturating_sub(1);
+        }
+    }
+    excess - taken
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ColumnWidth::{Auto, Fixed, Weight};
+
+    #[test]
+    fn wrapped_lines_keep_their_indent() {
+        assert_eq!(
+            fit("  one two three four", 10, Overflow::Wrap),
+            ["  one two", "  three", "  four"]
+        );
+        // Indentation taking more than half the line is dropped.
+        assert_eq!(fit("       ab cd", 10, Overflow::Wrap), ["ab cd"]);
+    }
+
+    #[test]
+    fn words_longer_than_a_line_are_split() {
+        assert_eq!(
+            fit("abcdefghijkl xy", 5, Overflow::Wrap),
+            ["abcde", "fghij", "kl xy"]
+        );
+    }
+
+    #[test]
+    fn ellipsis_replaces_the_end() {
+        assert_eq!(fit("hello world", 8, Overflow::Ellipsis), ["hello..."]);
+        assert_eq!(fit("hi there you", 6, Overflow::Ellipsis), ["hi..."]);
+        assert_eq!(fit("hello", 2, Overflow::Ellipsis), [".."]);
+        assert_eq!(fit("hello", 1, Overflow::Ellipsis), ["."]);
+        assert_eq!(fit("hello", 0, Overflow::Ellipsis), ["."]);
+        assert_eq!(fit("hello", 3, Overflow::Truncate), ["hel"]);
+    }
+
+    #[test]
+    fn padding_follows_the_alignment() {
+        assert_eq!(pad("ab", 5, Align::Left), "ab   ");
+        assert_eq!(pad("ab", 5, Align::Center), " ab  ");
+        assert_eq!(pad("ab", 5, Align::Right), "   ab");
+        assert_eq!(pad("abcdef", 5, Align::Right), "abcdef");
+    }
+
+    #[test]
+    fn rounding_goes_to_the_first_weighted_columns() {
+        assert_eq!(
+            column_widths(&[Weight(1), Weight(1), Weight(1)], &[0, 0, 0], 47),
+            [16, 16, 15]
+        );
+        assert_eq!(
+            column_widths(&[Auto, Weight(1), Fixed(4), Weight(1)], &[10, 0, 0, 0], 47),
+            [10, 17, 4, 16]
+        );
+    }
+
+    #[test]
+    fn fixed_columns_keep_their_width() {
+        assert_eq!(column_widths(&[Fixed(10), Fixed(5)], &[3, 20], 47), [10, 5]);
+    }
+
+    #[test]
+    fn auto_columns_narrow_the_widest_first() {
+        assert_eq!(column_widths(&[Auto, Auto], &[4, 9], 47), [4, 9]);
+        assert_eq!(
+            column_widths(&[Auto, Auto, Auto], &[30, 30, 5], 47),
+            [21, 21, 5]
+        );
+        assert_eq!(column_widths(&[Auto, Auto], &[30, 30], 47), [24, 23]);
+        // Fixed columns give way only once the auto ones are down to one character.
+        assert_eq!(column_widths(&[Fixed(20), Auto], &[0, 40], 47), [20, 27]);
+        assert_eq!(
+            column_widths(&[Fixed(40), Fixed(30), Auto], &[0, 0, 10], 47),
+            [23, 23, 1]
+        );
+    }
+
+    #[test]
+    fn weighted_columns_share_what_is_left() {
+        assert_eq!(
+            column_widths(&[Fixed(10), Weight(1), Weight(2)], &[0, 0, 0], 47),
+            [10, 13, 24]
+        );
+        // Nothing is left, so they keep one character each.
+        assert_eq!(column_widths(&[Auto, Weight(3)], &[60, 0], 47), [46, 1]);
+    }
+
+    #[test]
+    fn oversized_columns_fit_the_row() {
+        let widths = column_widths(
+            &[
+                Fixed(usize::MAX),
+                Weight(usize::MAX),
+                Auto,
+                Weight(usize::MAX),
+            ],
+            &[0, 0, usize::MAX, 0],
+            47,
+        );
+        assert_eq!(widths, [44, 1, 1, 1]);
+        assert_eq!(
+            column_widths(&[Weight(usize::MAX), Weight(usize::MAX)], &[0, 0], 47),
+            [24, 23]
+        );
+    }
+}
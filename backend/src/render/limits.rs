@@ -0,0 +1,180 @@
+//! Hard caps on rendered output so a runaway source cannot empty the paper roll.
+
+use anyhow::{Context, Result, bail};
+use std::fmt;
+use std::str::FromStr;
+
+use super::{Profile, Rendered, render};
+use crate::document::{self, Block, Document};
+
+/// Line appended when a job is cut down to fit.
+const TRUNCATED: &str = "[... truncated ...]";
+
+/// What to do with a job that renders larger than the [`Limits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Oversize {
+    /// Refuse the job.
+    #[default]
+    Reject,
+    /// Drop trailing lines until it fits, then print it with a truncation note.
+    Truncate,
+}
+
+impl FromStr for Oversize {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reject" => Ok(Oversize::Reject),
+            "truncate" => Ok(Oversize::Truncate),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub max_bytes: usize,
+    pub max_lines: usize,
+    /// Maximum raster graphics height in dots (about 8 dots per mm).
+    pub max_raster_height: u32,
+    pub oversize: Oversize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_bytes: 64 * 1024,
+            max_lines: 500,
+            max_raster_height: 8000,
+            oversize: Oversize::Reject,
+        }
+    }
+}
+
+/// A job exceeded the configured limits. Routes report this as a client error.
+#[derive(Debug)]
+pub struct LimitExceeded(pub String);
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "job too large: {}", self.0)
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+impl Limits {
+    /// Read `MAX_JOB_BYTES`, `MAX_JOB_LINES`, `MAX_RASTER_HEIGHT` and
+    /// `OVERSIZE_POLICY` (`reject` or `truncate`), keeping defaults for unset ones.
+    pub fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let var = |name: &str| std::env::var(name).ok();
+        Ok(Self {
+            max_bytes: var("MAX_JOB_BYTES")
+                .map(|v| v.parse().context("MAX_JOB_BYTES must be a number"))
+                .transpose()?
+                .unwrap_or(defaults.max_bytes),
+            max_lines: var("MAX_JOB_LINES")
+                .map(|v| v.parse().context("MAX_JOB_LINES must be a number"))
+                .transpose()?
+                .unwrap_or(defaults.max_lines),
+            max_raster_height: var("MAX_RASTER_HEIGHT")
+                .map(|v| v.parse().context("MAX_RASTER_HEIGHT must be a number"))
+                .transpose()?
+                .unwrap_or(defaults.max_raster_height),
+            oversize: var("OVERSIZE_POLICY")
+                .map(|v| {
+                    v.parse()
+                        .map_err(|_| anyhow::anyhow!("OVERSIZE_POLICY must be reject or truncate"))
+                })
+                .transpose()?
+                .unwrap_or_default(),
+        })
+    }
+
+    /// First limit `rendered` breaks, described for the user.
+    fn violation(&self, rendered: &Rendered) -> Option<String> {
+        if rendered.lines > self.max_lines {
+            return Some(format!(
+                "{} lines exceeds the limit of {}",
+                rendered.lines, self.max_lines
+            ));
+        }
+        if rendered.raster_height > self.max_raster_height {
+            return Some(format!(
+                "{} dots of graphics exceeds the limit of {}",
+                rendered.raster_height, self.max_raster_height
+            ));
+        }
+        if rendered.bytes.len() > self.max_bytes {
+            return Some(format!(
+                "{} bytes exceeds the limit of {}",
+                rendered.bytes.len(),
+                self.max_bytes
+            ));
+        }
+        None
+    }
+
+    /// Render `document`, applying the oversize policy. Fails with [`LimitExceeded`]
+    /// if the job is rejected or cannot be truncated to fit.
+    pub fn render(&self, document: &Document, profile: &Profile) -> Result<Rendered> {
+        let rendered = render(document, profile)?;
+        let Some(violation) = self.violation(&rendered) else {
+            return Ok(rendered);
+        };
+        if self.oversize == Oversize::Reject {
+            bail!(LimitExceeded(violation));
+        }
+
+        // Text is split to one block per line so it can be cut between lines, and the
+        // trailing cut, feed or space is kept on whatever remains.
+        let mut blocks = split_lines(&document.blocks);
+        let tail = blocks.split_off(document::body_len(&blocks));
+
+        // Binary search for the longest prefix of blocks that fits with the note.
+        let (mut lo, mut hi) = (0, blocks.len());
+        let mut best = None;
+        while lo <= hi {
+            let mid = (lo + hi) / 2;
+            let mut kept = blocks[..mid].to_vec();
+            kept.push(Block::text(TRUNCATED));
+            kept.extend(tail.iter().cloned());
+            let candidate = render(
+                &Document {
+                    blocks: kept,
+                    rotate_180: document.rotate_180,
+                },
+                profile,
+            )?;
+            if self.violation(&candidate).is_none() {
+                best = Some(candidate);
+                lo = mid + 1;
+            } else if mid == 0 {
+                break;
+            } else {
+                hi = mid - 1;
+            }
+        }
+        best.ok_or_else(|| LimitExceeded(violation).into())
+    }
+}
+
+/// Text blocks split into one block per line.
+pub(super) fn split_lines(blocks: &[Block]) -> Vec<Block> {
+    blocks
+        .iter()
+        .flat_map(|block| match block {
+            Block::Text { text, spans, style } if spans.is_empty() => text
+                .lines()
+                .map(|line| Block::Text {
+                    text: line.to_string(),
+                    spans: Vec::new(),
+                    style: *style,
+                })
+                .collect(),
+            other => vec![other.clone()],
+        })
+        .collect()
+}
@@ -0,0 +1,142 @@
+use anyhow::Result;
+use escpos::driver::Driver;
+use escpos::errors::Result as EscposResult;
+use escpos::printer::Printer;
+use escpos::printer_options::PrinterOptions;
+use escpos::utils::Protocol;
+use std::sync::{Arc, Mutex};
+use tracing::instrument;
+
+use crate::document::{self, Document};
+use blocks::Writer;
+
+mod banner;
+mod blocks;
+mod canvas;
+pub mod charset;
+pub mod emoji;
+pub mod grid;
+mod layout;
+pub mod limits;
+pub mod photo;
+pub mod preview;
+mod profile;
+pub mod sparkline;
+pub mod theme;
+
+pub use profile::Profile;
+
+/// Driver that records everything the escpos builder emits instead of talking to hardware.
+#[derive(Clone, Default)]
+pub struct CaptureDriver {
+    buf: Arc<Mutex<Vec<u8>>>,
+}
+
+impl CaptureDriver {
+    pub fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.buf.lock().unwrap())
+    }
+}
+
+impl Driver for CaptureDriver {
+    fn name(&self) -> String {
+        "capture".into()
+    }
+
+    fn write(&self, data: &[u8]) -> EscposResult<()> {
+        self.buf.lock().unwrap().extend_from_slice(data);
+        Ok(())
+    }
+
+    fn read(&self, _buf: &mut [u8]) -> EscposResult<usize> {
+        Ok(0)
+    }
+
+    fn flush(&self) -> EscposResult<()> {
+        Ok(())
+    }
+}
+
+/// Output of the render pipeline.
+pub struct Rendered {
+    /// Raw ESC/POS stream sent to the printer.
+    pub bytes: Vec<u8>,
+    /// Plain-text approximation of what the paper will look like.
+    pub text: String,
+    /// Printed text lines, excluding the cut.
+    pub lines: usize,
+    /// Total height of raster graphics in printer dots.
+    pub raster_height: u32,
+    /// Paper the job feeds before the cut, text and graphics together, in dots.
+    pub height: u32,
+}
+
+impl Rendered {
+    /// Printed length in millimetres.
+    pub fn length_mm(&self) -> f32 {
+        self.height as f32 / DOTS_PER_MM
+    }
+}
+
+/// Marker used in the text approximation where the paper is cut.
+pub const CUT_MARKER: &str = "-- cut --";
+
+/// Print head resolution: 203 dpi is eight dots to the millimetre.
+pub const DOTS_PER_MM: f32 = 8.0;
+
+/// Characters per line in font A at normal size on 80mm paper.
+pub const COLUMNS: usize = 48;
+/// Characters per line in font B.
+pub const COLUMNS_FONT_B: usize = 64;
+
+/// Render a document for a printer with `profile`. Never touches hardware.
+#[instrument(name = "render", skip_all, fields(blocks = document.blocks.len()))]
+pub fn render(document: &Document, profile: &Profile) -> Result<Rendered> {
+    let driver = CaptureDriver::default();
+    let mut writer = Writer::new(printer(driver.clone()), profile)?;
+    if profile.rotate_180 || document.rotate_180 {
+        writer.upside_down()?;
+        write(&mut writer, &reversed(document), profile)?;
+    } else {
+        write(&mut writer, document, profile)?;
+    }
+    let mut rendered = writer.finish()?;
+    rendered.bytes = driver.take();
+    Ok(rendered)
+}
+
+/// Draw a document the way a printer with `profile` lays it out, as a PNG of the
+/// receipt. Upside-down receipts are drawn the way they are read, right way up.
+pub fn render_png(document: &Document, profile: &Profile) -> Result<Vec<u8>> {
+    let mut writer = Writer::new(printer(CaptureDriver::default()), profile)?.with_canvas();
+    write(&mut writer, document, profile)?;
+    writer.into_png()
+}
+
+fn printer(driver: CaptureDriver) -> Printer<CaptureDriver> {
+    Printer::new(driver, Protocol::default(), Some(PrinterOptions::default()))
+}
+
+/// `document` last line first, for printing upside down. Text blocks are split
+/// into their lines; the lines a long one wraps onto, and the rows of a table, keep
+/// their order. The closing cut or feed stays at the end.
+fn reversed(document: &Document) -> Document {
+    let mut blocks = limits::split_lines(&document.blocks);
+    let tail = blocks.split_off(document::body_len(&blocks));
+    blocks.reverse();
+    blocks.extend(tail);
+    Document {
+        blocks,
+        rotate_180: true,
+    }
+}
+
+fn write(writer: &mut Writer, document: &Document, profile: &Profile) -> Result<()> {
+    if profile.user_glyphs {
+        writer.define_glyphs(&document.glyph_names())?;
+    }
+    for block in &document.blocks {
+        writer.block(block)?;
+    }
+    Ok(())
+}
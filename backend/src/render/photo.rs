@@ -0,0 +1,109 @@
+//! Turns photos and logos into something a 1-bit, fixed-width print head can do
+//! justice to.
+
+use image::imageops::{self, BiLevel, FilterType};
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, ImageResult};
+use std::io::Cursor;
+
+use super::preview::PAPER_WIDTH_DOTS;
+
+/// Widest and tallest picture decoded, in pixels; far past any camera's, so only a
+/// file made to exhaust memory is refused.
+const MAX_DECODED_SIDE: u32 = 16_384;
+/// Most memory the decoder may allocate.
+const MAX_DECODED_BYTES: u64 = 256 * 1024 * 1024;
+
+/// A reader for the picture in `bytes`, whatever its format, that refuses to
+/// decode one too big for memory: every picture from outside, be it an upload, an
+/// image block or a glyph, is decoded through it.
+pub fn reader(bytes: &[u8]) -> std::io::Result<ImageReader<Cursor<&[u8]>>> {
+    let mut reader = ImageReader::new(Cursor::new(bytes)).with_guessed_format()?;
+    let mut limits = image::Limits::default();
+    limits.max_image_width = Some(MAX_DECODED_SIDE);
+    limits.max_image_height = Some(MAX_DECODED_SIDE);
+    limits.max_alloc = Some(MAX_DECODED_BYTES);
+    reader.limits(limits);
+    Ok(reader)
+}
+
+/// Decode the picture in `bytes` within the [`reader`]'s limits.
+pub fn decode(bytes: &[u8]) -> ImageResult<DynamicImage> {
+    reader(bytes)?.decode()
+}
+
+/// How to fit an uploaded picture onto the paper.
+#[derive(Debug, Clone, Copy)]
+pub struct PhotoOptions {
+    /// Turn landscape pictures sideways so they print across the full length
+    /// rather than as a thin strip.
+    pub rotate: bool,
+    /// Diffuse grey levels into dot patterns (Floyd-Steinberg); without it the
+    /// printer's black/white threshold applies, which suits logos and line art.
+    pub dither: bool,
+    /// Tallest the fitted picture may print, in dots; taller ones are refused
+    /// rather than scaled down to a sliver. The render limits' `max_raster_height`.
+    pub max_height: u32,
+}
+
+/// Decode a PNG or JPEG, upright it according to its EXIF orientation, scale it
+/// down to the paper width and return it as a grayscale PNG ready for an image
+/// block. Pictures narrower than the paper keep their size.
+pub fn prepare(bytes: &[u8], options: PhotoOptions) -> Result<Vec<u8>, String> {
+    let mut decoder = reader(bytes)
+        .map_err(|e| e.to_string())?
+        .into_decoder()
+        .map_err(|e| format!("image could not be read: {e}"))?;
+    let orientation = decoder.orientation().map_err(|e| e.to_string())?;
+    let mut image = DynamicImage::from_decoder(decoder)
+        .map_err(|e| format!("image could not be decoded: {e}"))?;
+    image.apply_orientation(orientation);
+
+    if options.rotate && image.width() > image.height() {
+        image = image.rotate90();
+    }
+    let width = image.width().min(PAPER_WIDTH_DOTS);
+    let height =
+        (u64::from(image.height()) * u64::from(width)).div_ceil(u64::from(image.width().max(1)));
+    if height > u64::from(options.max_height) {
+        return Err(format!(
+            "picture would print {height} dots tall, more than the limit of {}",
+            options.max_height
+        ));
+    }
+    if width < image.width() {
+        image = image.resize(width, u32::MAX, FilterType::Lanczos3);
+    }
+    let mut gray = image.to_luma8();
+    if options.dither {
+        imageops::dither(&mut gray, &BiLevel);
+    }
+
+    let mut png = Vec::new();
+    DynamicImage::ImageLuma8(gray)
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::GrayImage;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut png = Vec::new();
+        DynamicImage::ImageLuma8(GrayImage::new(width, height))
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        png
+    }
+
+    #[test]
+    fn pictures_past_the_limits_are_not_decoded() {
+        assert_eq!(decode(&png(40, 30)).unwrap().width(), 40);
+        // A few hundred bytes compressed, but wider than any picture decoded.
+        let wide = png(MAX_DECODED_SIDE + 1, 1);
+        assert!(wide.len() < 1024);
+        assert!(decode(&wide).is_err());
+    }
+}
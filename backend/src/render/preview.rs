@@ -0,0 +1,87 @@
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont, point};
+use anyhow::Result;
+use image::{GrayImage, ImageFormat, Luma};
+use std::io::Cursor;
+
+use super::{CUT_MARKER, emoji};
+
+pub(super) static FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSansMono.ttf");
+
+/// Printable width of an 80mm print head at 203 dpi.
+pub const PAPER_WIDTH_DOTS: u32 = 576;
+/// Font A character cell, in dots.
+pub(super) const CELL_WIDTH: u32 = 12;
+pub(super) const CELL_HEIGHT: u32 = 24;
+/// Distance between printed lines at the default 1/6" line spacing, in dots.
+pub(super) const LINE_SPACING: u32 = 34;
+const MARGIN: u32 = 16;
+const FONT_PX: f32 = 20.0;
+
+const WHITE: Luma<u8> = Luma([255]);
+const BLACK: Luma<u8> = Luma([0]);
+
+/// Draw a plain-text approximation onto a virtual receipt and encode it as PNG.
+///
+/// Lines longer than the paper are wrapped the way the printer hard-wraps them, and
+/// pixels are thresholded to match what a thermal head can reproduce.
+pub fn text_to_png(text: &str) -> Result<Vec<u8>> {
+    let font = FontRef::try_from_slice(FONT)?;
+    let scale = PxScale::from(FONT_PX);
+    let ascent = font.as_scaled(scale).ascent();
+
+    let columns = (PAPER_WIDTH_DOTS / CELL_WIDTH) as usize;
+    let rows: Vec<Vec<char>> = text
+        .lines()
+        .flat_map(|line| {
+            let chars: Vec<char> = line.chars().collect();
+            if chars.is_empty() {
+                vec![Vec::new()]
+            } else {
+                chars.chunks(columns).map(|c| c.to_vec()).collect()
+            }
+        })
+        .collect();
+
+    let width = PAPER_WIDTH_DOTS + MARGIN * 2;
+    let height = rows.len() as u32 * CELL_HEIGHT + MARGIN * 2;
+    let mut img = GrayImage::from_pixel(width, height, WHITE);
+
+    for (row, chars) in rows.iter().enumerate() {
+        let top = MARGIN + row as u32 * CELL_HEIGHT;
+        if chars.iter().collect::<String>() == CUT_MARKER {
+            let y = top + CELL_HEIGHT / 2;
+            for x in (0..width).filter(|x| (x / 6) % 2 == 0) {
+                img.put_pixel(x, y, BLACK);
+            }
+            continue;
+        }
+
+        for (col, &ch) in chars.iter().enumerate() {
+            let left = MARGIN + col as u32 * CELL_WIDTH;
+            if emoji::is_emoji(ch)
+                && let Some(image) = emoji::image(ch)
+            {
+                image::imageops::overlay(&mut img, &image, left.into(), top.into());
+                continue;
+            }
+            let glyph = font
+                .glyph_id(ch)
+                .with_scale_and_position(scale, point(left as f32, top as f32 + ascent));
+            let Some(outlined) = font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                let x = bounds.min.x as i32 + gx as i32;
+                let y = bounds.min.y as i32 + gy as i32;
+                if coverage > 0.5 && x >= 0 && y >= 0 && (x as u32) < width && (y as u32) < height {
+                    img.put_pixel(x as u32, y as u32, BLACK);
+                }
+            });
+        }
+    }
+
+    let mut png = Vec::new();
+    img.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
@@ -0,0 +1,93 @@
+use anyhow::Result;
+use diesel::SqliteConnection;
+use std::collections::HashMap;
+
+use super::charset::CodePages;
+use super::emoji::Emoji;
+use crate::document::Document;
+use crate::glyphs::{self, Glyph, icons};
+use crate::printers::Printer;
+
+/// What the target printer supports, plus the stored resources a document refers to.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    /// Glyphs can be downloaded as user-defined characters.
+    pub user_glyphs: bool,
+    /// QR codes can be printed with `GS ( k` rather than as raster images.
+    pub native_qr: bool,
+    /// Page blocks can be printed in page mode (`ESC L`).
+    pub page_mode: bool,
+    /// Everything is printed upside down and in reverse order.
+    pub rotate_180: bool,
+    /// Code pages the printer can switch between, in order of preference.
+    pub code_pages: CodePages,
+    /// Whether emoji are drawn or left out.
+    pub emoji: Emoji,
+    /// Printed for characters that can't be transliterated into the code page.
+    pub replacement: char,
+    /// Glyphs used by the document being rendered, by name.
+    pub glyphs: HashMap<String, Glyph>,
+}
+
+/// A typical modern printer, for previews and estimates made without one.
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            user_glyphs: false,
+            native_qr: true,
+            page_mode: true,
+            rotate_180: false,
+            code_pages: CodePages::default(),
+            emoji: Emoji::Raster,
+            replacement: '?',
+            glyphs: HashMap::new(),
+        }
+    }
+}
+
+impl Profile {
+    /// Profile for rendering `document` on `printer`, or on a generic printer when
+    /// there is none, e.g. for previews.
+    pub fn load(
+        conn: &mut SqliteConnection,
+        printer: Option<&Printer>,
+        document: &Document,
+    ) -> Result<Self> {
+        let names = document.glyph_names();
+        let mut glyphs: HashMap<String, Glyph> = glyphs::by_names(conn, &names)?
+            .into_iter()
+            .map(|glyph| (glyph.name.clone(), glyph))
+            .collect();
+        // Uploaded glyphs take precedence over built-in icons of the same name.
+        for name in names {
+            if !glyphs.contains_key(name)
+                && let Some(icon) = icons::glyph(name)
+            {
+                glyphs.insert(name.to_string(), icon);
+            }
+        }
+        let defaults = Self::default();
+        Ok(Self {
+            user_glyphs: printer.map_or(defaults.user_glyphs, |p| p.user_glyphs),
+            native_qr: printer.map_or(defaults.native_qr, |p| p.native_qr),
+            page_mode: printer.map_or(defaults.page_mode, |p| p.page_mode),
+            rotate_180: printer.map_or(defaults.rotate_180, |p| p.rotate_180),
+            code_pages: printer
+                .and_then(|p| serde_json::from_str(&p.code_pages).ok())
+                .unwrap_or(defaults.code_pages),
+            emoji: printer.map_or(defaults.emoji, Printer::emoji),
+            replacement: printer
+                .and_then(|p| p.replacement_char.chars().next())
+                .unwrap_or(defaults.replacement),
+            glyphs,
+        })
+    }
+
+    /// The first glyph `document` uses that doesn't exist.
+    pub fn missing_glyph<'a>(&self, document: &'a Document) -> Option<&'a str> {
+        document
+            .glyph_names()
+            .into_iter()
+            .find(|name| !self.glyphs.contains_key(*name))
+    }
+}
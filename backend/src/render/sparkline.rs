@@ -0,0 +1,110 @@
+//! Small line charts of recent values, one row per series with its label on the left,
+//! for a trend at a glance where a table of numbers would be too long.
+
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont, point};
+use image::{DynamicImage, GrayImage, ImageFormat, Luma};
+use std::io::Cursor;
+
+use super::banner::BANNER_FONT;
+use super::preview::PAPER_WIDTH_DOTS;
+
+const BLACK: Luma<u8> = Luma([0]);
+/// Height of each series' row, in dots.
+const ROW: u32 = 48;
+/// Blank dots above and below each line, so neighbouring rows don't touch.
+const PAD: u32 = 6;
+/// Width of the label column, in dots.
+const LABEL: u32 = 140;
+
+/// `series` of labels and values, oldest first, each drawn as a line scaled to its
+/// own low and high across the paper. Series with fewer than two values get an
+/// empty row.
+pub fn png(series: &[(&str, &[f64])]) -> Result<Vec<u8>, String> {
+    let width = PAPER_WIDTH_DOTS;
+    let mut image = GrayImage::from_pixel(width, ROW * series.len() as u32, Luma([255]));
+    let font = FontRef::try_from_slice(BANNER_FONT).map_err(|e| e.to_string())?;
+    let scale = PxScale::from(24.0);
+    let scaled = font.as_scaled(scale);
+
+    for (row, (label, values)) in series.iter().enumerate() {
+        let top = row as u32 * ROW;
+        // Capitals centred in the row.
+        let baseline = top as f32 + (ROW as f32 + scaled.ascent() * 0.75) / 2.0;
+        let mut x = 0.0;
+        for c in label.chars() {
+            let id = font.glyph_id(c);
+            let glyph = id.with_scale_and_position(scale, point(x, baseline));
+            x += scaled.h_advance(id);
+            let Some(outlined) = font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                let (px, py) = (bounds.min.x as u32 + gx, bounds.min.y as u32 + gy);
+                if coverage > 0.5 && px < LABEL - 8 && py < image.height() {
+                    image.put_pixel(px, py, BLACK);
+                }
+            });
+        }
+
+        if values.len() < 2 {
+            continue;
+        }
+        let low = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let high = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let span = (high - low).max(f64::EPSILON);
+        let plot = (width - LABEL - 4) as f64;
+        let points: Vec<(f64, f64)> = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let x = LABEL as f64 + plot * i as f64 / (values.len() - 1) as f64;
+                let y = (top + PAD) as f64 + (ROW - 2 * PAD) as f64 * (high - value) / span;
+                (x, y)
+            })
+            .collect();
+        for pair in points.windows(2) {
+            line(&mut image, pair[0], pair[1]);
+        }
+        // The latest value, as a dot at the end of the line.
+        if let Some(&(x, y)) = points.last() {
+            dot(&mut image, x, y, 3);
+        }
+    }
+
+    let mut png = Vec::new();
+    DynamicImage::ImageLuma8(image)
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(png)
+}
+
+/// A line two dots thick, so it survives the print head's resolution.
+fn line(image: &mut GrayImage, from: (f64, f64), to: (f64, f64)) {
+    let steps = (to.0 - from.0)
+        .abs()
+        .max((to.1 - from.1).abs())
+        .ceil()
+        .max(1.0);
+    for step in 0..=steps as u32 {
+        let t = step as f64 / steps;
+        dot(
+            image,
+            from.0 + (to.0 - from.0) * t,
+            from.1 + (to.1 - from.1) * t,
+            1,
+        );
+    }
+}
+
+/// Black out the square of `radius` dots around a point, clipped to the image.
+fn dot(image: &mut GrayImage, x: f64, y: f64, radius: i64) {
+    let (cx, cy) = (x.round() as i64, y.round() as i64);
+    for py in cy - radius..cy + radius {
+        for px in cx - radius..cx + radius {
+            if (0..image.width() as i64).contains(&px) && (0..image.height() as i64).contains(&py) {
+                image.put_pixel(px as u32, py as u32, BLACK);
+            }
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! Looks for composed digests. A theme is a set of layout rules, and every section
+//! of a digest is laid out with the same rules.
+
+use std::str::FromStr;
+
+use super::layout;
+use super::{COLUMNS, COLUMNS_FONT_B};
+use crate::document::{Align, Block, Font, Overflow, RuleStyle, Style};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Theme {
+    /// Small type and no space between sections, for the shortest slip.
+    Compact,
+    /// Large titles over a rule, indented bodies and room between sections.
+    #[default]
+    Relaxed,
+    /// Each section framed in a box with its title in the top edge.
+    Boxed,
+    /// Condensed capitals with `=` rules under the titles, like a dot-matrix printout.
+    Retro,
+}
+
+impl Theme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Theme::Compact => "compact",
+            Theme::Relaxed => "relaxed",
+            Theme::Boxed => "boxed",
+            Theme::Retro => "retro",
+        }
+    }
+
+    pub fn layout(&self) -> Layout {
+        match self {
+            Theme::Compact => Layout {
+                font: Font::B,
+                title: Style {
+                    bold: true,
+                    font: Font::B,
+                    ..Style::default()
+                },
+                uppercase: true,
+                rule: None,
+                boxed: false,
+                indent: 0,
+                gap: 0,
+            },
+            Theme::Relaxed => Layout {
+                font: Font::A,
+                title: Style {
+                    bold: true,
+                    size: 2,
+                    ..Style::default()
+                },
+                uppercase: false,
+                rule: Some(Block::Rule {
+                    style: RuleStyle::Dashed,
+                    character: None,
+                }),
+                boxed: false,
+                indent: 2,
+                gap: 2,
+            },
+            Theme::Boxed => Layout {
+                font: Font::A,
+                title: Style {
+                    bold: true,
+                    ..Style::default()
+                },
+                uppercase: true,
+                rule: None,
+                boxed: true,
+                indent: 0,
+                gap: 1,
+            },
+            Theme::Retro => Layout {
+                font: Font::B,
+                title: Style {
+                    font: Font::B,
+                    ..Style::default()
+                },
+                uppercase: true,
+                rule: Some(Block::Rule {
+                    style: RuleStyle::Dashed,
+                    character: Some('='),
+                }),
+                boxed: false,
+                indent: 1,
+                gap: 1,
+            },
+        }
+    }
+}
+
+impl FromStr for Theme {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "compact" => Ok(Theme::Compact),
+            "relaxed" => Ok(Theme::Relaxed),
+            "boxed" => Ok(Theme::Boxed),
+            "retro" => Ok(Theme::Retro),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Rules a theme lays sections out by.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    /// Font of section bodies.
+    pub font: Font,
+    /// Style of section titles.
+    pub title: Style,
+    /// Print titles and bodies in capitals.
+    pub uppercase: bool,
+    /// Printed under each title.
+    pub rule: Option<Block>,
+    /// Frame each section in box-drawing characters, title in the top edge. The
+    /// title style's bold applies to the whole frame.
+    pub boxed: bool,
+    /// Spaces before each body line.
+    pub indent: usize,
+    /// Blank lines after each section.
+    pub gap: u8,
+}
+
+impl Layout {
+    /// Blocks for one section: its title, any `graphics`, then its body lines. A
+    /// boxed section's graphics follow its frame. A section without a title has no
+    /// frame or rule either, and reads as part of the section before it.
+    pub fn section(&self, title: &str, graphics: &[Block], lines: &[String]) -> Vec<Block> {
+        let case = |s: &str| {
+            if self.uppercase {
+                s.to_uppercase()
+            } else {
+                s.to_string()
+            }
+        };
+        let body = Style {
+            font: self.font,
+            ..Style::default()
+        };
+        let mut blocks = Vec::new();
+        if self.boxed && !title.is_empty() {
+            let text = self.frame(
+                &case(title),
+                &lines.iter().map(|l| case(l)).collect::<Vec<_>>(),
+            );
+            blocks.push(Block::Text {
+                text,
+                spans: Vec::new(),
+                style: Style {
+                    bold: self.title.bold,
+                    ..body
+                },
+            });
+            blocks.extend_from_slice(graphics);
+        } else {
+            if !title.is_empty() {
+                blocks.push(Block::Text {
+                    text: case(title),
+                    spans: Vec::new(),
+                    style: self.title,
+                });
+                blocks.extend(self.rule.clone());
+            }
+            blocks.extend_from_slice(graphics);
+            if !lines.is_empty() {
+                let indent = " ".repeat(self.indent);
+                let text = lines
+                    .iter()
+                    .map(|line| format!("{indent}{}", case(line)))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                blocks.push(Block::Text {
+                    text,
+                    spans: Vec::new(),
+                    style: body,
+                });
+            }
+        }
+        if self.gap > 0 {
+            blocks.push(Block::Feed { lines: self.gap });
+        }
+        blocks
+    }
+
+    /// `lines` wrapped inside a box the width of the paper.
+    fn frame(&self, title: &str, lines: &[String]) -> String {
+        let columns = match self.font {
+            Font::A => COLUMNS,
+            Font::B => COLUMNS_FONT_B,
+        };
+        let inner = columns - 4;
+        let title = layout::fit(title, inner - 2, Overflow::Ellipsis)
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        let mut out = format!(
+            "┌─ {title} {}┐\n",
+            "─".repeat(columns - 5 - title.chars().count())
+        );
+        for line in lines {
+            for part in layout::fit(line, inner, Overflow::Wrap) {
+                out.push_str(&format!("│ {} │\n", layout::pad(&part, inner, Align::Left)));
+            }
+        }
+        out.push_str(&format!("└{}┘", "─".repeat(columns - 2)));
+        out
+    }
+}
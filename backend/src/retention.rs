@@ -0,0 +1,46 @@
+//! Purges soft-deleted records once their retention window has passed, and
+//! countdowns once their date has.
+
+use anyhow::Result;
+use chrono::{Local, NaiveDateTime, TimeDelta, Utc};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::{countdowns, db, integrations, printers};
+
+const TICK: Duration = Duration::from_secs(60 * 60);
+
+pub fn spawn(retention_days: i64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(TICK);
+        loop {
+            interval.tick().await;
+            if let Err(err) = purge(retention_days).await {
+                warn!("purging deleted records failed: {err:#}");
+            }
+        }
+    });
+}
+
+/// Records deleted before this instant are past restoring.
+pub fn cutoff(retention_days: i64) -> NaiveDateTime {
+    Utc::now().naive_utc() - TimeDelta::days(retention_days)
+}
+
+async fn purge(retention_days: i64) -> Result<()> {
+    let cutoff = cutoff(retention_days);
+    let n = db::run_blocking_db(move |conn| printers::purge(conn, cutoff)).await?;
+    if n > 0 {
+        info!("purged {n} deleted printer(s) and their job history");
+    }
+    let n = db::run_blocking_db(move |conn| integrations::purge(conn, cutoff)).await?;
+    if n > 0 {
+        info!("purged {n} deleted integration instance(s)");
+    }
+    let today = Local::now().date_naive();
+    let n = db::run_blocking_db(move |conn| countdowns::purge(conn, today)).await?;
+    if n > 0 {
+        info!("removed {n} countdown(s) to dates now past");
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use crate::api_keys::{self, ApiKey, Scope};
+use crate::db;
+use crate::error::{ApiError, ApiResult};
+use crate::routes::extract::Json;
+use crate::routes::users::check_owner;
+use crate::state::AppState;
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::{
+    Router,
+    routing::{delete, get},
+};
+use serde::{Deserialize, Serialize};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_keys).post(create_key))
+        .route("/{id}", delete(delete_key))
+}
+
+#[derive(Deserialize)]
+struct NewApiKey {
+    name: String,
+    /// `admin` or `print`, the default.
+    #[serde(default)]
+    scope: Option<String>,
+    /// Whose key it is.
+    #[serde(default)]
+    user_id: Option<i32>,
+}
+
+/// The key is shown once, when it is issued.
+#[derive(Serialize)]
+struct CreatedApiKey {
+    #[serde(flatten)]
+    api_key: ApiKey,
+    key: String,
+}
+
+async fn list_keys() -> ApiResult<Json<Vec<ApiKey>>> {
+    let rows = db::run_blocking_db(api_keys::list).await?;
+    Ok(Json(rows))
+}
+
+/// Issue a key directly, for automations that can't go through pairing. Keys are
+/// print keys unless asked for otherwise, except the first, which is an admin key.
+async fn create_key(Json(new): Json<NewApiKey>) -> ApiResult<(StatusCode, Json<CreatedApiKey>)> {
+    let name = new.name.trim().to_string();
+    if name.is_empty() {
+        return Err(ApiError::BadRequest("name must not be empty".into()));
+    }
+    let scope = match new.scope.as_deref() {
+        None => Scope::default(),
+        Some(scope) => scope.parse().map_err(|_| {
+            ApiError::BadRequest(format!("scope must be admin or print, not {scope}"))
+        })?,
+    };
+    check_owner(new.user_id).await?;
+    let user_id = new.user_id;
+    let (api_key, key) =
+        db::run_blocking_db(move |conn| api_keys::create(conn, &name, scope, user_id)).await?;
+    Ok((StatusCode::CREATED, Json(CreatedApiKey { api_key, key })))
+}
+
+/// Revoke a key. Revoking the last one opens the API up again; the last admin key
+/// can't be revoked while print keys remain.
+async fn delete_key(Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    match db::run_blocking_db(move |conn| api_keys::delete(conn, id)).await? {
+        None => Err(ApiError::Conflict(
+            "this is the last admin key; issue another or revoke the print keys first".into(),
+        )),
+        Some(false) => Err(ApiError::NotFound),
+        Some(true) => Ok(StatusCode::NO_CONTENT),
+    }
+}
@@ -0,0 +1,163 @@
+use crate::api_keys::{self, Scope};
+use crate::db;
+use crate::error::{ApiError, ApiResult};
+use axum::extract::{Query, Request};
+use axum::http::{Method, header};
+use axum::middleware::Next;
+use axum::response::Response;
+use serde::Deserialize;
+use tracing::Span;
+
+#[derive(Deserialize)]
+struct KeyQuery {
+    api_key: Option<String>,
+}
+
+/// Endpoints anyone can call: those guarded by their own tokens, the OAuth
+/// callbacks providers redirect the browser to, pairing itself, and the API's
+/// description.
+fn is_open(method: &Method, path: &str) -> bool {
+    let path = path.trim_end_matches('/');
+    path == "/health"
+        || path.starts_with("/health/")
+        || path == "/pair"
+        || path.starts_with("/pair/")
+        || path.starts_with("/public/")
+        || path == "/users/login"
+        || path == "/integrations/connect/callback"
+        || path == "/api/openapi.json"
+        || path == "/api/docs"
+        || path.starts_with("/api/docs/")
+        // Receiving a hook, which carries the hook's token.
+        || (*method == Method::POST
+            && path
+                .strip_prefix("/hooks/")
+                .is_some_and(|id| id.parse::<i32>().is_ok()))
+}
+
+/// Endpoints only admin keys can call: managing keys, webhooks, users and settings;
+/// adding, changing, removing and restoring printers, whose device paths the server
+/// writes to, and looking for devices to add, over gRPC as well; and changing what
+/// prints on its own, with which credentials: integration instances, hooks,
+/// subscriptions, transforms and templates. Previewing and printing those stays
+/// open to print keys.
+fn is_admin_only(method: &Method, path: &str) -> bool {
+    let path = path.trim_end_matches('/');
+    let under = |prefix: &str| path == prefix || path.starts_with(&format!("{prefix}/"));
+    if under("/api-keys") || under("/webhooks") || path == "/dayroll.v1.Dayroll/Discover" {
+        return true;
+    }
+    if under("/users") || path == "/settings" {
+        return *method != Method::GET;
+    }
+    // Signing in to a service stores its tokens on the instance.
+    if under("/integrations") && path.ends_with("/connect") {
+        return true;
+    }
+    let configured = [
+        "/integrations",
+        "/hooks",
+        "/subscriptions",
+        "/transforms",
+        "/templates",
+    ];
+    if configured.into_iter().any(under) {
+        return *method != Method::GET && !path.ends_with("/preview") && !path.ends_with("/print");
+    }
+    let Some(rest) = path.strip_prefix("/printers") else {
+        return false;
+    };
+    match rest.trim_start_matches('/').split('/').collect::<Vec<_>>()[..] {
+        [""] => *method == Method::POST,
+        ["deleted" | "candidates"] => true,
+        [id] => id.parse::<i32>().is_ok() && matches!(*method, Method::PUT | Method::DELETE),
+        [_, "restore"] => true,
+        _ => false,
+    }
+}
+
+/// Require an API key from an `Authorization: Bearer` header, or `?api_key=` for
+/// clients that can't set headers such as `EventSource` and links, once any key
+/// has been issued. Until then the API is open so a fresh install can be set up.
+/// [Administrative](is_admin_only) endpoints need an admin key; others take a
+/// print key too. The key, and the user it was issued to, are recorded on the
+/// request's span so what's done can be attributed.
+pub async fn require_key(request: Request, next: Next) -> ApiResult<Response> {
+    if is_open(request.method(), request.uri().path()) {
+        return Ok(next.run(request).await);
+    }
+    let key = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string)
+        .or_else(|| {
+            Query::<KeyQuery>::try_from_uri(request.uri())
+                .ok()
+                .and_then(|q| q.0.api_key)
+        });
+    let admin_only = is_admin_only(request.method(), request.uri().path());
+    // `None` while no key has been issued.
+    let found = db::run_blocking_db(move |conn| {
+        if !api_keys::any(conn)? {
+            return Ok(None);
+        }
+        let found = match key {
+            Some(key) => api_keys::authenticate(conn, &key)?,
+            None => None,
+        };
+        Ok(Some(found))
+    })
+    .await?;
+    if let Some(found) = found {
+        let found = found.ok_or(ApiError::Unauthorized)?;
+        let span = Span::current();
+        span.record("key", found.id);
+        if let Some(user_id) = found.user_id {
+            span.record("user", user_id);
+        }
+        if admin_only && found.scope() != Scope::Admin {
+            return Err(ApiError::Forbidden);
+        }
+    }
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configuration_takes_an_admin_key() {
+        for (method, path) in [
+            (Method::POST, "/integrations"),
+            (Method::PATCH, "/integrations/abc/settings"),
+            (Method::GET, "/integrations/abc/connect"),
+            (Method::DELETE, "/hooks/1"),
+            (Method::PUT, "/subscriptions/1"),
+            (Method::POST, "/transforms"),
+            (Method::POST, "/templates/draft"),
+            (Method::PUT, "/printers/1"),
+        ] {
+            assert!(is_admin_only(&method, path), "{method} {path}");
+        }
+    }
+
+    #[test]
+    fn reading_previewing_and_printing_take_a_print_key() {
+        for (method, path) in [
+            (Method::GET, "/integrations"),
+            (Method::GET, "/integrations/abc/settings"),
+            (Method::POST, "/integrations/abc/preview"),
+            (Method::POST, "/integrations/abc/print"),
+            (Method::POST, "/transforms/preview"),
+            (Method::GET, "/templates/1/diff"),
+            (Method::POST, "/jobs"),
+            (Method::GET, "/printers/1"),
+        ] {
+            assert!(!is_admin_only(&method, path), "{method} {path}");
+        }
+        assert!(is_open(&Method::POST, "/hooks/1"));
+    }
+}
@@ -0,0 +1,13 @@
+use crate::discover::capabilities::{self, Capabilities};
+use crate::error::ApiResult;
+use crate::state::AppState;
+use axum::{Json, Router, routing::get};
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/", get(get_capabilities))
+}
+
+async fn get_capabilities() -> ApiResult<Json<Capabilities>> {
+    let caps = tokio::task::spawn_blocking(capabilities::detect).await?;
+    Ok(Json(caps))
+}
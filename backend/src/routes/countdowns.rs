@@ -0,0 +1,67 @@
+use crate::countdowns::{self, Countdown, NewCountdown};
+use crate::db;
+use crate::error::{ApiError, ApiResult, FieldError};
+use crate::routes::extract::Json;
+use crate::state::AppState;
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::{Router, routing::get};
+use chrono::Local;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_countdowns).post(create_countdown))
+        .route(
+            "/{id}",
+            get(get_countdown)
+                .put(update_countdown)
+                .delete(delete_countdown),
+        )
+}
+
+/// Countdowns to dates already past would only be removed again.
+fn validate(countdown: &NewCountdown) -> ApiResult<()> {
+    countdown.validate()?;
+    if countdown.target_date < Local::now().date_naive() {
+        return Err(FieldError::new("target_date", "must not be in the past").into());
+    }
+    Ok(())
+}
+
+async fn list_countdowns() -> ApiResult<Json<Vec<Countdown>>> {
+    let rows = db::run_blocking_db(countdowns::list).await?;
+    Ok(Json(rows))
+}
+
+async fn create_countdown(
+    Json(new): Json<NewCountdown>,
+) -> ApiResult<(StatusCode, Json<Countdown>)> {
+    validate(&new)?;
+    let row = db::run_blocking_db(move |conn| countdowns::create(conn, new)).await?;
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+async fn get_countdown(Path(id): Path<i32>) -> ApiResult<Json<Countdown>> {
+    db::run_blocking_db(move |conn| countdowns::get(conn, id))
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+async fn update_countdown(
+    Path(id): Path<i32>,
+    Json(changes): Json<NewCountdown>,
+) -> ApiResult<Json<Countdown>> {
+    validate(&changes)?;
+    db::run_blocking_db(move |conn| countdowns::update(conn, id, changes))
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+async fn delete_countdown(Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    if !db::run_blocking_db(move |conn| countdowns::delete(conn, id)).await? {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,74 @@
+use crate::events::Numbered;
+use crate::state::AppState;
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::response::sse::{Event as SseEvent, KeepAlive, Sse};
+use axum::{Router, routing::get};
+use futures_core::Stream;
+use serde_json::json;
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio_stream::StreamExt;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::{BroadcastStream, WatchStream};
+
+/// How often a `: heartbeat` comment is sent while nothing happens, so proxies and
+/// clients can tell a quiet stream from a dead one.
+const HEARTBEAT: Duration = Duration::from_secs(15);
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/", get(stream_events))
+}
+
+fn sse_event(numbered: &Numbered) -> Option<SseEvent> {
+    let data = serde_json::to_value(&numbered.event).ok()?;
+    let name = data["type"].as_str().unwrap_or("message").to_string();
+    Some(
+        SseEvent::default()
+            .id(numbered.id.to_string())
+            .event(name)
+            .data(data.to_string()),
+    )
+}
+
+/// Told to a client that missed events, by lagging behind or by resuming from one
+/// that's no longer kept.
+fn lagged(missed: Option<u64>) -> SseEvent {
+    SseEvent::default()
+        .event("lagged")
+        .data(json!({ "type": "lagged", "missed": missed }).to_string())
+}
+
+/// Live server events as `text/event-stream`; the SSE event name is the event's
+/// `type` and its id the event's. A client reconnecting with `Last-Event-ID` first
+/// gets the events it missed, if they're still kept. The stream ends when the
+/// server shuts down.
+async fn stream_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Sse<impl Stream<Item = Result<SseEvent, Infallible>>> {
+    let last_id = headers
+        .get("last-event-id")
+        .and_then(|id| id.to_str().ok())
+        .and_then(|id| id.trim().parse().ok());
+    let (kept, missed, rx) = state.events.resume(last_id);
+
+    let mut replay: Vec<SseEvent> = Vec::new();
+    if missed {
+        replay.push(lagged(None));
+    }
+    replay.extend(kept.iter().filter_map(sse_event));
+    let live = BroadcastStream::new(rx).filter_map(|event| match event {
+        Ok(numbered) => sse_event(&numbered),
+        Err(BroadcastStreamRecvError::Lagged(missed)) => Some(lagged(Some(missed))),
+    });
+    let stopping = WatchStream::new(state.shutdown.subscribe())
+        .filter(|stopping| *stopping)
+        .map(|_| None);
+    let stream = tokio_stream::iter(replay)
+        .chain(live)
+        .map(Some)
+        .merge(stopping)
+        .map_while(|event| event.map(Ok));
+    Sse::new(stream).keep_alive(KeepAlive::new().interval(HEARTBEAT).text("heartbeat"))
+}
@@ -0,0 +1,51 @@
+use crate::db;
+use crate::error::{ApiError, ApiResult};
+use crate::glyphs::{self, Glyph, GlyphInput, icons};
+use crate::routes::extract::Json;
+use crate::state::AppState;
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::{Router, routing::get};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_glyphs).post(create_glyph))
+        .route("/icons", get(list_icons))
+        .route("/{id}", get(get_glyph).delete(delete_glyph))
+}
+
+async fn list_glyphs() -> ApiResult<Json<Vec<Glyph>>> {
+    let rows = db::run_blocking_db(glyphs::list).await?;
+    Ok(Json(rows))
+}
+
+async fn list_icons() -> Json<Vec<&'static str>> {
+    Json(icons::names().collect())
+}
+
+async fn create_glyph(Json(input): Json<GlyphInput>) -> ApiResult<(StatusCode, Json<Glyph>)> {
+    input.validate()?;
+    let row = db::run_blocking_db(move |conn| {
+        if glyphs::get_by_name(conn, &input.name)?.is_some() {
+            return Ok(None);
+        }
+        glyphs::create(conn, input).map(Some)
+    })
+    .await?
+    .ok_or_else(|| ApiError::Conflict("a glyph with that name already exists".into()))?;
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+async fn get_glyph(Path(id): Path<i32>) -> ApiResult<Json<Glyph>> {
+    db::run_blocking_db(move |conn| glyphs::get(conn, id))
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+async fn delete_glyph(Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    if !db::run_blocking_db(move |conn| glyphs::delete(conn, id)).await? {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
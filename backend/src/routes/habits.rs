@@ -0,0 +1,108 @@
+use crate::db;
+use crate::error::{ApiError, ApiResult};
+use crate::habits::{self, Habit, NewHabit, Progress};
+use crate::routes::extract::Json;
+use crate::routes::users::check_owner;
+use crate::state::AppState;
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::{
+    Router,
+    routing::{delete, get, post},
+};
+use chrono::{Local, NaiveDate};
+use serde::Deserialize;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_habits).post(create_habit))
+        .route(
+            "/{id}",
+            get(get_habit).put(update_habit).delete(delete_habit),
+        )
+        .route("/{id}/checks", post(check_habit))
+        .route("/{id}/checks/{date}", delete(uncheck_habit))
+}
+
+#[derive(Deserialize)]
+struct HabitsQuery {
+    /// Only this user's habits.
+    user_id: Option<i32>,
+}
+
+/// Every habit with its streak and whether it is done today.
+async fn list_habits(Query(q): Query<HabitsQuery>) -> ApiResult<Json<Vec<Progress>>> {
+    let today = Local::now().date_naive();
+    let rows = db::run_blocking_db(move |conn| habits::progress(conn, today)).await?;
+    Ok(Json(
+        rows.into_iter()
+            .filter(|p| q.user_id.is_none_or(|id| p.habit.user_id == Some(id)))
+            .collect(),
+    ))
+}
+
+async fn create_habit(Json(new): Json<NewHabit>) -> ApiResult<(StatusCode, Json<Habit>)> {
+    new.validate()?;
+    check_owner(new.user_id).await?;
+    let row = db::run_blocking_db(move |conn| habits::create(conn, new)).await?;
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+async fn get_habit(Path(id): Path<i32>) -> ApiResult<Json<Progress>> {
+    let today = Local::now().date_naive();
+    db::run_blocking_db(move |conn| habits::progress(conn, today))
+        .await?
+        .into_iter()
+        .find(|p| p.habit.id == id)
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+async fn update_habit(
+    Path(id): Path<i32>,
+    Json(changes): Json<NewHabit>,
+) -> ApiResult<Json<Habit>> {
+    changes.validate()?;
+    check_owner(changes.user_id).await?;
+    db::run_blocking_db(move |conn| habits::update(conn, id, changes))
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+async fn delete_habit(Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    if !db::run_blocking_db(move |conn| habits::delete(conn, id)).await? {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct CheckInput {
+    /// Day the habit was done; today when left out.
+    #[serde(default)]
+    date: Option<NaiveDate>,
+}
+
+async fn check_habit(Path(id): Path<i32>, Json(input): Json<CheckInput>) -> ApiResult<StatusCode> {
+    let day = input.date.unwrap_or_else(|| Local::now().date_naive());
+    let found = db::run_blocking_db(move |conn| {
+        if habits::get(conn, id)?.is_none() {
+            return Ok(false);
+        }
+        habits::check(conn, id, day)?;
+        Ok(true)
+    })
+    .await?;
+    if !found {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn uncheck_habit(Path((id, date)): Path<(i32, NaiveDate)>) -> ApiResult<StatusCode> {
+    if !db::run_blocking_db(move |conn| habits::uncheck(conn, id, date)).await? {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
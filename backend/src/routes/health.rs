@@ -1,10 +1,10 @@
 use crate::db;
 use crate::state::AppState;
 use axum::http::StatusCode;
-use axum::response::IntoResponse;
 use axum::{Json, Router, extract::State, routing::get};
 use diesel::RunQueryDsl;
 use serde::Serialize;
+use std::collections::BTreeMap;
 
 #[derive(Serialize)]
 struct HealthResponse {
@@ -12,20 +12,116 @@ struct HealthResponse {
     db: &'static str,
 }
 
+#[derive(Serialize)]
+struct LiveResponse {
+    status: &'static str,
+}
+
+/// One thing readiness depends on.
+#[derive(Serialize)]
+struct Check {
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl From<Result<(), String>> for Check {
+    fn from(result: Result<(), String>) -> Self {
+        match result {
+            Ok(()) => Check {
+                status: "ok",
+                error: None,
+            },
+            Err(error) => Check {
+                status: "failing",
+                error: Some(error),
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ReadyResponse {
+    status: &'static str,
+    checks: BTreeMap<&'static str, Check>,
+}
+
 pub fn router() -> Router<AppState> {
-    Router::new().route("/", get(get_health))
+    Router::new()
+        .route("/", get(get_health))
+        .route("/live", get(get_live))
+        .route("/ready", get(get_ready))
 }
 
 async fn get_health(State(state): State<AppState>) -> Json<HealthResponse> {
-    let db_ok = db::run_blocking_db(|conn| {
-        diesel::sql_query("SELECT 1").execute(conn)?;
-        Ok::<(), anyhow::Error>(())
-    })
-    .await
-    .is_ok();
+    let db_ok = check_db().await.is_ok();
 
     Json(HealthResponse {
         status: "ok",
         db: if db_ok { "ok" } else { "down" },
     })
 }
+
+async fn check_db() -> Result<(), String> {
+    db::run_blocking_db(|conn| {
+        diesel::sql_query("SELECT 1").execute(conn)?;
+        Ok::<(), anyhow::Error>(())
+    })
+    .await
+    .map_err(|err| format!("{err:#}"))
+}
+
+/// Liveness: the server is up and answering, whatever state it's in.
+async fn get_live() -> Json<LiveResponse> {
+    Json(LiveResponse { status: "ok" })
+}
+
+/// Readiness: the database answers and is migrated, every printer's worker is
+/// running, the server isn't stopping and, when `READY_PRINTER_ID` is set, that
+/// printer's device was found recently. A 503 when any of them fails.
+async fn get_ready(State(state): State<AppState>) -> (StatusCode, Json<ReadyResponse>) {
+    let mut checks = BTreeMap::new();
+    checks.insert("db", check_db().await.into());
+    checks.insert("migrations", check_migrations().await.into());
+    checks.insert("queue", check_queue(&state).into());
+    if let Some(printer) = state.config.ready_printer {
+        let seen = match state.printers_seen.get(printer.id) {
+            None => Err(format!("printer {} not found since startup", printer.id)),
+            Some(at) if at.elapsed() > printer.within => Err(format!(
+                "printer {} last found {}s ago",
+                printer.id,
+                at.elapsed().as_secs()
+            )),
+            Some(_) => Ok(()),
+        };
+        checks.insert("printer", seen.into());
+    }
+
+    let ready = checks.values().all(|check: &Check| check.error.is_none());
+    let (code, status) = if ready {
+        (StatusCode::OK, "ok")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+    };
+    (code, Json(ReadyResponse { status, checks }))
+}
+
+async fn check_migrations() -> Result<(), String> {
+    match db::run_blocking_db(db::has_pending_migrations).await {
+        Ok(false) => Ok(()),
+        Ok(true) => Err("migrations are pending".into()),
+        Err(err) => Err(format!("{err:#}")),
+    }
+}
+
+fn check_queue(state: &AppState) -> Result<(), String> {
+    if state.queue.is_closed() {
+        return Err("shutting down".into());
+    }
+    let stopped = state.queue.stopped_workers();
+    if !stopped.is_empty() {
+        let ids: Vec<String> = stopped.iter().map(ToString::to_string).collect();
+        return Err(format!("worker stopped for printer {}", ids.join(", ")));
+    }
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use crate::db;
+use crate::error::{ApiError, ApiResult};
+use crate::hooks::{self, Hook, HookInput};
+use crate::jobs::NewJob;
+use crate::printers;
+use crate::routes::extract::{Json, rejected};
+use crate::routes::jobs::{self, JobResponse};
+use crate::state::AppState;
+use axum::extract::{Form, FromRequest, Path, Query, Request, State};
+use axum::http::header::{AUTHORIZATION, CONTENT_TYPE};
+use axum::http::{HeaderMap, StatusCode};
+use axum::{Router, routing::get};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::warn;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_hooks).post(create_hook))
+        .route(
+            "/{id}",
+            get(get_hook)
+                .post(receive)
+                .put(update_hook)
+                .delete(delete_hook),
+        )
+}
+
+/// The token is shown once, when the hook is created.
+#[derive(Serialize)]
+struct CreatedHook {
+    #[serde(flatten)]
+    hook: Hook,
+    token: String,
+}
+
+async fn list_hooks() -> ApiResult<Json<Vec<Hook>>> {
+    let rows = db::run_blocking_db(hooks::list).await?;
+    Ok(Json(rows))
+}
+
+async fn validate(input: &HookInput) -> ApiResult<()> {
+    input.validate()?;
+    let printer_id = input.printer_id;
+    if db::run_blocking_db(move |conn| printers::get(conn, printer_id))
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::BadRequest(format!(
+            "printer {printer_id} does not exist"
+        )));
+    }
+    Ok(())
+}
+
+async fn create_hook(Json(input): Json<HookInput>) -> ApiResult<(StatusCode, Json<CreatedHook>)> {
+    validate(&input).await?;
+    let (hook, token) = db::run_blocking_db(move |conn| {
+        if hooks::get_by_name(conn, &input.name)?.is_some() {
+            return Ok(None);
+        }
+        hooks::create(conn, input).map(Some)
+    })
+    .await?
+    .ok_or_else(|| ApiError::Conflict("a hook with that name already exists".into()))?;
+    Ok((StatusCode::CREATED, Json(CreatedHook { hook, token })))
+}
+
+async fn get_hook(Path(id): Path<i32>) -> ApiResult<Json<Hook>> {
+    db::run_blocking_db(move |conn| hooks::get(conn, id))
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+async fn update_hook(Path(id): Path<i32>, Json(input): Json<HookInput>) -> ApiResult<Json<Hook>> {
+    validate(&input).await?;
+    let hook = db::run_blocking_db(move |conn| {
+        if hooks::get_by_name(conn, &input.name)?.is_some_and(|other| other.id != id) {
+            return Ok(Err(ApiError::Conflict(
+                "a hook with that name already exists".into(),
+            )));
+        }
+        Ok(hooks::update(conn, id, input)?.ok_or(ApiError::NotFound))
+    })
+    .await??;
+    Ok(Json(hook))
+}
+
+async fn delete_hook(Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    if !db::run_blocking_db(move |conn| hooks::delete(conn, id)).await? {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct ReceiveQuery {
+    #[serde(default)]
+    token: Option<String>,
+    /// Render the hook's receipt and record it as simulated, but never print it.
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Whatever was posted, as JSON: a JSON body as it is, a form as an object of its
+/// fields, and anything else as `{"text": body}`.
+struct HookBody(Value);
+
+impl<S: Send + Sync> FromRequest<S> for HookBody {
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let content_type = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if content_type.starts_with("application/x-www-form-urlencoded") {
+            let Form(fields) = Form::<Vec<(String, String)>>::from_request(req, state)
+                .await
+                .map_err(|e| rejected(e.status(), e.body_text()))?;
+            let fields: Map<String, Value> = fields
+                .into_iter()
+                .map(|(name, value)| (name, value.into()))
+                .collect();
+            return Ok(Self(fields.into()));
+        }
+        if content_type.starts_with("application/json") || content_type.contains("+json") {
+            let Json(body) = Json::<Value>::from_request(req, state).await?;
+            return Ok(Self(body));
+        }
+        let text = String::from_request(req, state)
+            .await
+            .map_err(|e| rejected(e.status(), e.body_text()))?;
+        let mut fields = Map::new();
+        fields.insert("text".into(), text.into());
+        Ok(Self(fields.into()))
+    }
+}
+
+/// Print what a caller posted, mapped through the hook's payload. The token comes
+/// from `?token=`, an `Authorization: Bearer` header or the body's own `token`
+/// field, which is where Slack puts it.
+async fn receive(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(q): Query<ReceiveQuery>,
+    headers: HeaderMap,
+    HookBody(body): HookBody,
+) -> ApiResult<(StatusCode, Json<JobResponse>)> {
+    let token = q
+        .token
+        .or_else(|| {
+            headers
+                .get(AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "))
+                .map(str::to_string)
+        })
+        .or_else(|| {
+            body.get("token")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        })
+        .unwrap_or_default();
+    // Unknown hooks, disabled hooks and bad tokens are indistinguishable on purpose.
+    let hook = db::run_blocking_db(move |conn| hooks::get_with_token(conn, id, &token))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    // The first body is laid out as a draft template, whether or not the hook's
+    // payload fits it, for the hook's owner to start a layout from.
+    if hook.draft_template_id.is_none() {
+        let sample = body.clone();
+        if let Err(err) =
+            db::run_blocking_db(move |conn| hooks::save_draft(conn, id, &sample)).await
+        {
+            warn!("hook {id}'s draft template could not be saved: {err:#}");
+        }
+    }
+    let payload = hook.render(body).map_err(ApiError::BadRequest)?;
+    let new = NewJob::new(hook.printer_id, format!("hook:{id}"), &payload)?;
+    jobs::submit(&state, new, payload, q.dry_run).await
+}
@@ -0,0 +1,633 @@
+use crate::compose::Composition;
+use crate::db;
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::events::{ConfigScope, Event};
+use crate::integrations::{self, Context, Instance, InstancePatch, NewInstance};
+use crate::jobs::{JobPayload, NewJob};
+use crate::render::{self, Profile};
+use crate::routes::extract::Json;
+use crate::routes::jobs::{self, JobResponse};
+use crate::routes::pagination::{ListQuery, Page};
+use crate::routes::settings::printer_or_default;
+use crate::state::AppState;
+use crate::{retention, settings};
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::Redirect;
+use axum::{
+    Router,
+    routing::{get, post, put},
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_instances).post(create_instance))
+        .route("/available", get(list_available))
+        .route("/deleted", get(list_deleted_instances))
+        .route("/order", put(reorder_instances))
+        .route("/connect/callback", get(connect_callback))
+        .route(
+            "/{instance_id}",
+            get(get_instance)
+                .patch(update_instance)
+                .delete(delete_instance),
+        )
+        .route("/{instance_id}/restore", post(restore_instance))
+        .route("/{instance_id}/connect", get(connect_instance))
+        .route("/{instance_id}/preview", post(preview_instance))
+        .route("/{instance_id}/print", post(print_instance))
+        .route(
+            "/{instance_id}/settings",
+            get(get_settings).patch(patch_settings),
+        )
+}
+
+/// These endpoints' part of the [API description](super::openapi).
+#[derive(OpenApi)]
+#[openapi(paths(
+    list_instances,
+    create_instance,
+    list_available,
+    reorder_instances,
+    get_instance,
+    update_instance,
+    delete_instance,
+    list_deleted_instances,
+    restore_instance,
+    get_settings,
+    patch_settings,
+    preview_instance,
+    print_instance,
+    connect_instance,
+    connect_callback,
+))]
+pub(super) struct Api;
+
+/// A built-in integration that instances can be created from.
+#[derive(Serialize, ToSchema)]
+struct Available {
+    slug: &'static str,
+    name: &'static str,
+    #[schema(value_type = Object)]
+    config_schema: Value,
+}
+
+#[utoipa::path(
+    get,
+    path = "/integrations/available",
+    tag = "integrations",
+    responses(
+        (status = 200, description = "Built-in integrations with the JSON Schema of their settings", body = Vec<Available>),
+    )
+)]
+async fn list_available() -> Json<Vec<Available>> {
+    Json(
+        integrations::built_in()
+            .iter()
+            .map(|i| Available {
+                slug: i.slug(),
+                name: i.name(),
+                config_schema: i.config_schema(),
+            })
+            .collect(),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/integrations",
+    tag = "integrations",
+    params(ListQuery),
+    responses(
+        (status = 200, description = "Instances in digest order unless sorted", body = Page<Instance>),
+        (status = 422, description = "Invalid sort, filter or cursor", body = ErrorBody),
+    )
+)]
+async fn list_instances(Query(list): Query<ListQuery>) -> ApiResult<Json<Page<Instance>>> {
+    let rows = db::run_blocking_db(integrations::list).await?;
+    Ok(Json(list.apply(
+        redacted(rows)?,
+        &[
+            "instance_id",
+            "slug",
+            "enabled",
+            "print_on",
+            "position",
+            "created_at",
+            "fetched_at",
+        ],
+    )?))
+}
+
+/// Set the digest order: the instances listed come first, in that order, and the
+/// rest follow as they were.
+#[utoipa::path(
+    put,
+    path = "/integrations/order",
+    tag = "integrations",
+    request_body(content = Vec<String>, description = "Instance ids in their new order"),
+    responses(
+        (status = 200, description = "Instances in their new order", body = Vec<Instance>),
+        (status = 403, description = "Needs an admin key", body = ErrorBody),
+    )
+)]
+async fn reorder_instances(Json(order): Json<Vec<String>>) -> ApiResult<Json<Vec<Instance>>> {
+    let rows = db::run_blocking_db(move |conn| integrations::reorder(conn, &order)).await?;
+    Ok(Json(redacted(rows)?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/integrations",
+    tag = "integrations",
+    request_body = NewInstance,
+    responses(
+        (status = 201, description = "Instance created", body = Instance),
+        (status = 422, description = "Unknown integration or invalid settings, by field", body = ErrorBody),
+        (status = 403, description = "Needs an admin key", body = ErrorBody),
+    )
+)]
+async fn create_instance(
+    State(state): State<AppState>,
+    Json(input): Json<NewInstance>,
+) -> ApiResult<(StatusCode, Json<Instance>)> {
+    input.validate()?;
+    let row = db::run_blocking_db(move |conn| integrations::create(conn, input)).await?;
+    changed(&state, &row.instance_id);
+    Ok((StatusCode::CREATED, Json(row.redacted()?)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/integrations/{instance_id}",
+    tag = "integrations",
+    params(("instance_id" = String, Path, description = "Instance id")),
+    responses(
+        (status = 200, description = "The instance", body = Instance),
+        (status = 404, description = "No such instance", body = ErrorBody),
+    )
+)]
+async fn get_instance(Path(instance_id): Path<String>) -> ApiResult<Json<Instance>> {
+    let instance = db::run_blocking_db(move |conn| integrations::get(conn, &instance_id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    Ok(Json(instance.redacted()?))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/integrations/{instance_id}",
+    tag = "integrations",
+    params(("instance_id" = String, Path, description = "Instance id")),
+    request_body = InstancePatch,
+    responses(
+        (status = 200, description = "Instance updated", body = Instance),
+        (status = 422, description = "Invalid changes, by field", body = ErrorBody),
+        (status = 404, description = "No such instance", body = ErrorBody),
+        (status = 403, description = "Needs an admin key", body = ErrorBody),
+    )
+)]
+async fn update_instance(
+    State(state): State<AppState>,
+    Path(instance_id): Path<String>,
+    Json(patch): Json<InstancePatch>,
+) -> ApiResult<Json<Instance>> {
+    let id = instance_id.clone();
+    let instance = db::run_blocking_db(move |conn| integrations::get(conn, &id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    patch.validate(&instance)?;
+    let row = db::run_blocking_db(move |conn| integrations::update(conn, &instance_id, patch))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    changed(&state, &row.instance_id);
+    Ok(Json(row.redacted()?))
+}
+
+/// Move the instance to the trash, settings and template intact, until the
+/// retention window passes.
+#[utoipa::path(
+    delete,
+    path = "/integrations/{instance_id}",
+    tag = "integrations",
+    params(("instance_id" = String, Path, description = "Instance id")),
+    responses(
+        (status = 204, description = "Instance moved to the trash"),
+        (status = 404, description = "No such instance", body = ErrorBody),
+        (status = 403, description = "Needs an admin key", body = ErrorBody),
+    )
+)]
+async fn delete_instance(
+    State(state): State<AppState>,
+    Path(instance_id): Path<String>,
+) -> ApiResult<StatusCode> {
+    let id = instance_id.clone();
+    if !db::run_blocking_db(move |conn| integrations::delete(conn, &id)).await? {
+        return Err(ApiError::NotFound);
+    }
+    changed(&state, &instance_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/integrations/deleted",
+    tag = "integrations",
+    responses(
+        (status = 200, description = "Instances in the trash", body = Vec<Instance>),
+    )
+)]
+async fn list_deleted_instances() -> ApiResult<Json<Vec<Instance>>> {
+    let rows = db::run_blocking_db(integrations::list_deleted).await?;
+    Ok(Json(redacted(rows)?))
+}
+
+/// Bring a deleted instance back, as long as it has not been purged.
+#[utoipa::path(
+    post,
+    path = "/integrations/{instance_id}/restore",
+    tag = "integrations",
+    params(("instance_id" = String, Path, description = "Instance id")),
+    responses(
+        (status = 200, description = "Instance restored", body = Instance),
+        (status = 404, description = "No such instance in the trash", body = ErrorBody),
+        (status = 403, description = "Needs an admin key", body = ErrorBody),
+    )
+)]
+async fn restore_instance(
+    State(state): State<AppState>,
+    Path(instance_id): Path<String>,
+) -> ApiResult<Json<Instance>> {
+    let since = retention::cutoff(state.config.deleted_retention_days);
+    let instance =
+        db::run_blocking_db(move |conn| integrations::restore(conn, &instance_id, since))
+            .await?
+            .ok_or(ApiError::NotFound)?;
+    changed(&state, &instance.instance_id);
+    Ok(Json(instance.redacted()?))
+}
+
+/// Instances as the API shows them; see [`Instance::redacted`].
+fn redacted(rows: Vec<Instance>) -> anyhow::Result<Vec<Instance>> {
+    rows.into_iter().map(Instance::redacted).collect()
+}
+
+/// Let background tasks acting on instances know one was added, changed or removed.
+fn changed(state: &AppState, instance_id: &str) {
+    state.events.publish(Event::ConfigChanged {
+        scope: ConfigScope::Integration,
+        id: instance_id.to_string().into(),
+    });
+}
+
+/// An instance's settings with the schema they follow, to build a form from. Secrets
+/// are blank.
+#[derive(Serialize, ToSchema)]
+struct InstanceSettings {
+    #[schema(value_type = Object)]
+    schema: Value,
+    #[schema(value_type = Object)]
+    settings: Map<String, Value>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/integrations/{instance_id}/settings",
+    tag = "integrations",
+    params(("instance_id" = String, Path, description = "Instance id")),
+    responses(
+        (status = 200, description = "Settings and the schema they follow", body = InstanceSettings),
+        (status = 404, description = "No such instance", body = ErrorBody),
+    )
+)]
+async fn get_settings(Path(instance_id): Path<String>) -> ApiResult<Json<InstanceSettings>> {
+    let instance = db::run_blocking_db(move |conn| integrations::get(conn, &instance_id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let integration = integrations::find(&instance.slug)
+        .ok_or_else(|| ApiError::BadRequest(format!("unknown integration {}", instance.slug)))?;
+    Ok(Json(InstanceSettings {
+        schema: integration.config_schema(),
+        settings: instance.redacted()?.settings()?,
+    }))
+}
+
+/// Change some of an instance's settings, as a JSON merge patch: the settings
+/// given replace the ones stored and `null` removes one. The result must match
+/// the integration's schema, or every mismatch is reported by field.
+#[utoipa::path(
+    patch,
+    path = "/integrations/{instance_id}/settings",
+    tag = "integrations",
+    params(("instance_id" = String, Path, description = "Instance id")),
+    request_body(content = Object, description = "Settings to change; `null` removes one"),
+    responses(
+        (status = 200, description = "Settings changed", body = Instance),
+        (status = 422, description = "Settings that don't match the schema, by field", body = ErrorBody),
+        (status = 404, description = "No such instance", body = ErrorBody),
+        (status = 403, description = "Needs an admin key", body = ErrorBody),
+    )
+)]
+async fn patch_settings(
+    State(state): State<AppState>,
+    Path(instance_id): Path<String>,
+    Json(changes): Json<Map<String, Value>>,
+) -> ApiResult<Json<Instance>> {
+    let id = instance_id.clone();
+    let instance = db::run_blocking_db(move |conn| integrations::get(conn, &id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let integration = integrations::find(&instance.slug)
+        .ok_or_else(|| ApiError::BadRequest(format!("unknown integration {}", instance.slug)))?;
+    let mut settings = instance.settings()?;
+    for (name, value) in changes {
+        if value.is_null() {
+            settings.remove(&name);
+        } else {
+            settings.insert(name, value);
+        }
+    }
+    let errors = integrations::settings_errors(integration, &settings);
+    if !errors.is_empty() {
+        return Err(ApiError::Invalid(errors));
+    }
+    let patch = InstancePatch {
+        enabled: None,
+        settings: Some(settings),
+        print_on: None,
+        refresh_minutes: None,
+        position: None,
+        template: None,
+    };
+    let row = db::run_blocking_db(move |conn| integrations::update(conn, &instance_id, patch))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    changed(&state, &row.instance_id);
+    Ok(Json(row.redacted()?))
+}
+
+/// An instance's section as it would print.
+#[derive(Serialize, ToSchema)]
+struct Preview {
+    text: String,
+    /// The section drawn in the digest theme, as a `data:` URL.
+    png: String,
+    length_mm: f32,
+    /// What the integration fetched, which an instance's template renders from.
+    data: Value,
+}
+
+/// Fetch and render the instance's section straight away, bypassing kept data,
+/// and show it without printing, to check its settings while setting it up. A
+/// failed fetch is answered with its error.
+#[utoipa::path(
+    post,
+    path = "/integrations/{instance_id}/preview",
+    tag = "integrations",
+    params(("instance_id" = String, Path, description = "Instance id")),
+    responses(
+        (status = 200, description = "The section as it would print", body = Preview),
+        (status = 400, description = "The fetch failed", body = ErrorBody),
+        (status = 404, description = "No such instance", body = ErrorBody),
+    )
+)]
+async fn preview_instance(
+    State(state): State<AppState>,
+    Path(instance_id): Path<String>,
+) -> ApiResult<Json<Preview>> {
+    let instance = db::run_blocking_db(move |conn| integrations::get(conn, &instance_id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let settings = db::run_blocking_db(settings::get).await?;
+    let ctx = Context {
+        date: settings.now().date(),
+    };
+    let fetched = instance
+        .fetch_now(&ctx)
+        .await
+        .map_err(|e| ApiError::BadRequest(format!("{} failed: {e:#}", instance.slug)))?;
+    let composition = Composition {
+        sections: vec![instance.render(&ctx, &fetched)?],
+        trimmed: Vec::new(),
+    };
+    let text = composition.to_text();
+    let document = composition.to_document(settings.theme().unwrap_or(state.config.digest_theme));
+    let (png, length_mm) = db::run_blocking_db(move |conn| {
+        let profile = Profile::load(conn, None, &document)?;
+        let length_mm = render::render(&document, &profile)?.length_mm();
+        Ok((render::render_png(&document, &profile)?, length_mm))
+    })
+    .await?;
+    Ok(Json(Preview {
+        text,
+        png: format!("data:image/png;base64,{}", BASE64.encode(png)),
+        length_mm,
+        data: fetched.data().clone(),
+    }))
+}
+
+#[derive(Deserialize, ToSchema)]
+struct PrintInstance {
+    /// The default printer when left out.
+    #[serde(default)]
+    printer_id: Option<i32>,
+    /// Validate and render the section, record it as simulated, but never print it.
+    #[serde(default)]
+    dry_run: bool,
+    /// Print even if the printer is in quiet hours.
+    #[serde(default)]
+    urgent: bool,
+}
+
+/// Print the instance's section by itself, laid out in the digest theme.
+#[utoipa::path(
+    post,
+    path = "/integrations/{instance_id}/print",
+    tag = "integrations",
+    params(("instance_id" = String, Path, description = "Instance id")),
+    request_body = PrintInstance,
+    responses(
+        (status = 202, description = "Job queued", body = JobResponse),
+        (status = 400, description = "Disabled or digest-only", body = ErrorBody),
+        (status = 404, description = "No such instance", body = ErrorBody),
+        (status = 409, description = "The printer is disabled", body = ErrorBody),
+        (status = 502, description = "The fetch failed", body = ErrorBody),
+        (status = 503, description = "The server is shutting down", body = ErrorBody),
+    )
+)]
+async fn print_instance(
+    State(state): State<AppState>,
+    Path(instance_id): Path<String>,
+    Json(req): Json<PrintInstance>,
+) -> ApiResult<(StatusCode, Json<JobResponse>)> {
+    let id = instance_id.clone();
+    let instance = db::run_blocking_db(move |conn| integrations::get(conn, &id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    if !instance.enabled {
+        return Err(ApiError::BadRequest("the instance is disabled".into()));
+    }
+    if !instance.print_on().on_demand() {
+        return Err(ApiError::BadRequest(
+            "the instance is only printed in the digest".into(),
+        ));
+    }
+    let settings = db::run_blocking_db(settings::get).await?;
+    let ctx = Context {
+        date: settings.now().date(),
+    };
+    let composition = Composition {
+        sections: vec![
+            instance
+                .section(&ctx)
+                .await
+                .map_err(|e| ApiError::Upstream(format!("{} failed: {e:#}", instance.slug)))?,
+        ],
+        trimmed: Vec::new(),
+    };
+    let payload = JobPayload::Document(
+        composition.to_document(settings.theme().unwrap_or(state.config.digest_theme)),
+    );
+    let mut new = NewJob::new(
+        printer_or_default(req.printer_id).await?,
+        format!("integration:{instance_id}"),
+        &payload,
+    )?;
+    new.urgent = req.urgent;
+    jobs::submit(&state, new, payload, req.dry_run).await
+}
+
+/// Where the OAuth services send the user back to, on the host and scheme they
+/// reached the server at.
+fn callback_url(state: &AppState, headers: &HeaderMap) -> ApiResult<String> {
+    let host = headers
+        .get(header::HOST)
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| ApiError::BadRequest("request has no Host header".into()))?;
+    let scheme = headers
+        .get("x-forwarded-proto")
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or(if state.config.tls.is_some() {
+            "https"
+        } else {
+            "http"
+        });
+    Ok(format!("{scheme}://{host}/integrations/connect/callback"))
+}
+
+/// Send the browser to the service to sign the instance in to an account, for
+/// integrations that use OAuth. The service sends it back to
+/// [`connect_callback`].
+#[utoipa::path(
+    get,
+    path = "/integrations/{instance_id}/connect",
+    tag = "integrations",
+    params(("instance_id" = String, Path, description = "Instance id")),
+    responses(
+        (status = 303, description = "Redirect to the service's sign-in page"),
+        (status = 400, description = "The integration doesn't sign in", body = ErrorBody),
+        (status = 404, description = "No such instance", body = ErrorBody),
+        (status = 403, description = "Needs an admin key", body = ErrorBody),
+    )
+)]
+async fn connect_instance(
+    State(state): State<AppState>,
+    Path(instance_id): Path<String>,
+    headers: HeaderMap,
+) -> ApiResult<Redirect> {
+    let instance = db::run_blocking_db(move |conn| integrations::get(conn, &instance_id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let integration = integrations::find(&instance.slug)
+        .ok_or_else(|| ApiError::BadRequest(format!("unknown integration {}", instance.slug)))?;
+    let url = integration
+        .authorize_url(
+            &instance.decrypted_settings()?,
+            &callback_url(&state, &headers)?,
+            &instance.instance_id,
+        )
+        .ok_or_else(|| {
+            ApiError::BadRequest(format!(
+                "{} doesn't sign in, or its client settings are missing",
+                instance.slug
+            ))
+        })?;
+    Ok(Redirect::to(&url))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct CallbackQuery {
+    /// The instance being connected.
+    state: String,
+    #[serde(default)]
+    code: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Finish signing in: trade the service's code for the instance's tokens and
+/// keep the settings the integration returns.
+#[utoipa::path(
+    get,
+    path = "/integrations/connect/callback",
+    tag = "integrations",
+    params(CallbackQuery),
+    responses(
+        (status = 200, description = "Connected", body = String, content_type = "text/plain"),
+        (status = 400, description = "The service refused or the code was rejected", body = ErrorBody),
+    )
+)]
+async fn connect_callback(
+    State(state): State<AppState>,
+    Query(q): Query<CallbackQuery>,
+    headers: HeaderMap,
+) -> ApiResult<String> {
+    let id = q.state.clone();
+    let instance = db::run_blocking_db(move |conn| integrations::get(conn, &id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let integration = integrations::find(&instance.slug)
+        .ok_or_else(|| ApiError::BadRequest(format!("unknown integration {}", instance.slug)))?;
+    let code = match (q.code, q.error) {
+        (_, Some(error)) => {
+            return Err(ApiError::BadRequest(format!(
+                "{} wasn't connected: {error}",
+                integration.name()
+            )));
+        }
+        (Some(code), None) => code,
+        (None, None) => return Err(ApiError::BadRequest("code is missing".into())),
+    };
+    let changes = integration
+        .connect(
+            &instance.decrypted_settings()?,
+            &code,
+            &callback_url(&state, &headers)?,
+        )
+        .await
+        .map_err(|e| ApiError::BadRequest(format!("{} failed: {e:#}", instance.slug)))?;
+    let mut settings = instance.settings()?;
+    settings.extend(changes);
+    let patch = InstancePatch {
+        enabled: None,
+        settings: Some(settings),
+        print_on: None,
+        refresh_minutes: None,
+        position: None,
+        template: None,
+    };
+    let row = db::run_blocking_db(move |conn| integrations::update(conn, &q.state, patch))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    changed(&state, &row.instance_id);
+    Ok(format!(
+        "Connected {}. You can close this page.",
+        integration.name()
+    ))
+}
@@ -0,0 +1,303 @@
+use crate::db;
+use crate::document::Document;
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::jobs::{self, Job, JobContent, JobFilter, JobPayload, JobState, NewJob};
+use crate::printers;
+use crate::queue::Submitted;
+use crate::queue::eta::Estimate;
+use crate::render::{Profile, Rendered};
+use crate::routes::extract::Json;
+use crate::routes::pagination::{self, ListQuery, Page};
+use crate::routes::settings::printer_or_default;
+use crate::state::AppState;
+use crate::templates;
+use crate::transforms;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::{
+    Router,
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{OpenApi, ToSchema};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_jobs).post(create_job))
+        .route("/preview", post(preview_job))
+        .route("/{id}", get(get_job))
+}
+
+/// These endpoints' part of the [API description](super::openapi).
+#[derive(OpenApi)]
+#[openapi(paths(list_jobs, create_job, preview_job, get_job))]
+pub(super) struct Api;
+
+#[derive(Deserialize, ToSchema)]
+struct CreateJob {
+    /// The default printer when left out.
+    #[serde(default)]
+    printer_id: Option<i32>,
+    #[serde(default = "default_source")]
+    source: String,
+    /// Validate and render the job, record it as simulated, but never print it.
+    #[serde(default)]
+    dry_run: bool,
+    /// Print even if the printer is in quiet hours.
+    #[serde(default)]
+    urgent: bool,
+    #[serde(flatten)]
+    content: JobContent,
+}
+
+pub(crate) fn default_source() -> String {
+    "api".into()
+}
+
+#[utoipa::path(
+    post,
+    path = "/jobs",
+    tag = "jobs",
+    request_body = CreateJob,
+    responses(
+        (status = 202, description = "Job queued", body = JobResponse),
+        (status = 200, description = "Dropped as a repeat of a recent job", body = JobResponse),
+        (status = 201, description = "Dry run recorded as simulated", body = JobResponse),
+        (status = 400, description = "Unknown printer, template or glyph", body = ErrorBody),
+        (status = 422, description = "Invalid job, by field", body = ErrorBody),
+        (status = 409, description = "The printer is disabled", body = ErrorBody),
+        (status = 413, description = "Over the size limits", body = ErrorBody),
+        (status = 503, description = "The server is shutting down", body = ErrorBody),
+    )
+)]
+async fn create_job(
+    State(state): State<AppState>,
+    Json(req): Json<CreateJob>,
+) -> ApiResult<(StatusCode, Json<JobResponse>)> {
+    let payload = resolve(req.content).await?;
+    let mut new = NewJob::new(
+        printer_or_default(req.printer_id).await?,
+        req.source,
+        &payload,
+    )?;
+    new.urgent = req.urgent;
+    submit(&state, new, payload, req.dry_run).await
+}
+
+/// Check, render and queue a job, or only record it when `dry_run` is set.
+pub(crate) async fn submit(
+    state: &AppState,
+    mut new: NewJob,
+    payload: JobPayload,
+    dry_run: bool,
+) -> ApiResult<(StatusCode, Json<JobResponse>)> {
+    let printer_id = new.printer_id;
+    let Some(printer) = db::run_blocking_db(move |conn| printers::get(conn, printer_id)).await?
+    else {
+        return Err(ApiError::BadRequest(format!(
+            "printer {printer_id} does not exist"
+        )));
+    };
+    if !printer.enabled && !dry_run {
+        return Err(ApiError::PrinterUnavailable(format!(
+            "printer '{}' is disabled",
+            printer.name
+        )));
+    }
+
+    // Render now so oversized jobs are refused up front rather than failing in the queue.
+    let source = new.source.clone();
+    let (document, profile) = db::run_blocking_db(move |conn| {
+        let mut document = transforms::prepare(conn, &source, payload)?;
+        printer.frame(&mut document);
+        let profile = Profile::load(conn, Some(&printer), &document)?;
+        Ok((document, profile))
+    })
+    .await?;
+    let rendered = render_limited(state, document, profile).await?;
+
+    if dry_run {
+        new.state = JobState::Simulated.as_str().into();
+        let bytes = rendered.bytes.len();
+        let job = db::run_blocking_db(move |conn| jobs::create_simulated(conn, new, bytes)).await?;
+        return Ok((
+            StatusCode::CREATED,
+            Json(JobResponse {
+                job,
+                estimate: None,
+                duplicate: false,
+            }),
+        ));
+    }
+
+    let Submitted { job, duplicate } = state.queue.submit(new).await?;
+    if duplicate {
+        return Ok((
+            StatusCode::OK,
+            Json(JobResponse {
+                job,
+                estimate: None,
+                duplicate,
+            }),
+        ));
+    }
+    let estimate = state
+        .queue
+        .estimates(job.printer_id)
+        .await?
+        .into_iter()
+        .find(|queued| queued.job.id == job.id)
+        .map(|queued| queued.estimate);
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(JobResponse {
+            job,
+            estimate,
+            duplicate,
+        }),
+    ))
+}
+
+/// A submitted job, with its timing estimate while it is still in the queue.
+#[derive(Serialize, ToSchema)]
+pub(crate) struct JobResponse {
+    #[serde(flatten)]
+    pub(crate) job: Job,
+    #[serde(flatten)]
+    pub(crate) estimate: Option<Estimate>,
+    /// The submission was dropped as a repeat of `job` (answered with 200, not 202).
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) duplicate: bool,
+}
+
+/// Expand a stored template, or pass an inline payload through.
+pub(crate) async fn resolve(content: JobContent) -> ApiResult<JobPayload> {
+    match content {
+        JobContent::Inline(payload) => Ok(payload),
+        JobContent::Template { template, vars } => {
+            let name = template.clone();
+            let template = db::run_blocking_db(move |conn| templates::get_by_name(conn, &name))
+                .await?
+                .ok_or_else(|| ApiError::BadRequest(format!("unknown template '{template}'")))?;
+            template.render(&vars).map_err(ApiError::BadRequest)
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+struct PreviewResponse {
+    text: String,
+    bytes: usize,
+    lines: usize,
+    /// Estimated printed length on a typical printer.
+    length_mm: f32,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct PreviewJob {
+    /// Source whose transform pipeline to apply, as if the job were submitted by it.
+    #[serde(default = "default_source")]
+    source: String,
+    #[serde(flatten)]
+    content: JobContent,
+}
+
+/// Render with the configured size limits, reporting an invalid document or a limit
+/// violation as a client error.
+pub(super) async fn render_limited(
+    state: &AppState,
+    document: Document,
+    profile: Profile,
+) -> ApiResult<Rendered> {
+    document.validate()?;
+    if let Some(name) = profile.missing_glyph(&document) {
+        return Err(ApiError::BadRequest(format!("unknown glyph '{name}'")));
+    }
+    let limits = state.config.limits;
+    let rendered = tokio::task::spawn_blocking(move || limits.render(&document, &profile)).await?;
+    Ok(rendered?)
+}
+
+/// Run the render pipeline for a payload and report the result without printing it.
+#[utoipa::path(
+    post,
+    path = "/jobs/preview",
+    tag = "jobs",
+    request_body = PreviewJob,
+    responses(
+        (status = 200, description = "The job as it would print", body = PreviewResponse),
+        (status = 400, description = "Unknown template or glyph", body = ErrorBody),
+        (status = 422, description = "Invalid job, by field", body = ErrorBody),
+        (status = 413, description = "Over the size limits", body = ErrorBody),
+    )
+)]
+async fn preview_job(
+    State(state): State<AppState>,
+    Json(req): Json<PreviewJob>,
+) -> ApiResult<Json<PreviewResponse>> {
+    let payload = resolve(req.content).await?;
+    let source = req.source;
+    let (document, profile) = db::run_blocking_db(move |conn| {
+        let document = transforms::prepare(conn, &source, payload)?;
+        let profile = Profile::load(conn, None, &document)?;
+        Ok((document, profile))
+    })
+    .await?;
+    let rendered = render_limited(&state, document, profile).await?;
+    Ok(Json(PreviewResponse {
+        length_mm: rendered.length_mm(),
+        text: rendered.text,
+        bytes: rendered.bytes.len(),
+        lines: rendered.lines,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/jobs",
+    tag = "jobs",
+    params(JobFilter, ListQuery),
+    responses(
+        (status = 200, description = "Jobs, newest first unless sorted", body = Page<Job>),
+        (status = 422, description = "Invalid sort, filter or cursor", body = ErrorBody),
+    )
+)]
+async fn list_jobs(
+    Query(mut filter): Query<JobFilter>,
+    Query(list): Query<ListQuery>,
+) -> ApiResult<Json<Page<Job>>> {
+    for (field, value) in list.filters(&["printer_id", "state", "source"])? {
+        match field {
+            "printer_id" => filter.printer_id = Some(pagination::parse(field, value)?),
+            "state" => filter.state = Some(value.into()),
+            _ => filter.source = Some(value.into()),
+        }
+    }
+    let sort = list
+        .sort(jobs::SORTABLE)?
+        .map(|sort| (sort.field.to_string(), sort.descending));
+    let (offset, limit) = (list.offset()?, list.limit());
+    let (rows, total) = db::run_blocking_db(move |conn| {
+        let sort = sort.as_ref().map(|(field, desc)| (field.as_str(), *desc));
+        jobs::list(conn, &filter, sort, offset, limit)
+    })
+    .await?;
+    Ok(Json(Page::new(rows, total, offset)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/jobs/{id}",
+    tag = "jobs",
+    params(("id" = i32, Path, description = "Job id")),
+    responses(
+        (status = 200, description = "The job", body = Job),
+        (status = 404, description = "No such job", body = ErrorBody),
+    )
+)]
+async fn get_job(Path(id): Path<i32>) -> ApiResult<Json<Job>> {
+    db::run_blocking_db(move |conn| jobs::get(conn, id))
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
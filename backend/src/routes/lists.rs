@@ -0,0 +1,130 @@
+use crate::db;
+use crate::error::{ApiError, ApiResult};
+use crate::jobs::{JobPayload, NewJob};
+use crate::routes::extract::Json;
+use crate::routes::jobs::{self, JobResponse};
+use crate::routes::settings::printer_or_default;
+use crate::shopping::{self, Item, NewItem, NewShoppingList, ShoppingList};
+use crate::state::AppState;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::{
+    Router,
+    routing::{delete, get, post},
+};
+use serde::{Deserialize, Serialize};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_lists).post(create_list))
+        .route("/{id}", get(get_list).delete(delete_list))
+        .route("/{id}/items", post(add_item))
+        .route("/{id}/items/{item_id}", delete(remove_item))
+        .route("/{id}/print", post(print_list))
+}
+
+#[derive(Serialize)]
+struct ListDetail {
+    #[serde(flatten)]
+    list: ShoppingList,
+    items: Vec<Item>,
+}
+
+async fn list_lists() -> ApiResult<Json<Vec<ShoppingList>>> {
+    let rows = db::run_blocking_db(shopping::list).await?;
+    Ok(Json(rows))
+}
+
+async fn create_list(
+    Json(new): Json<NewShoppingList>,
+) -> ApiResult<(StatusCode, Json<ListDetail>)> {
+    new.validate()?;
+    let detail = db::run_blocking_db(move |conn| {
+        if shopping::get_by_name(conn, &new.name)?.is_some() {
+            return Ok(None);
+        }
+        let list = shopping::create(conn, new)?;
+        let items = shopping::items_of(conn, &list)?;
+        Ok(Some(ListDetail { list, items }))
+    })
+    .await?
+    .ok_or_else(|| ApiError::Conflict("a shopping list with that name already exists".into()))?;
+    Ok((StatusCode::CREATED, Json(detail)))
+}
+
+async fn get_list(Path(id): Path<i32>) -> ApiResult<Json<ListDetail>> {
+    db::run_blocking_db(move |conn| detail(conn, id))
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+fn detail(conn: &mut diesel::SqliteConnection, id: i32) -> anyhow::Result<Option<ListDetail>> {
+    let Some(list) = shopping::get(conn, id)? else {
+        return Ok(None);
+    };
+    let items = shopping::items_of(conn, &list)?;
+    Ok(Some(ListDetail { list, items }))
+}
+
+async fn delete_list(Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    if !db::run_blocking_db(move |conn| shopping::delete(conn, id)).await? {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn add_item(
+    Path(id): Path<i32>,
+    Json(new): Json<NewItem>,
+) -> ApiResult<(StatusCode, Json<Item>)> {
+    new.validate()?;
+    let row = db::run_blocking_db(move |conn| {
+        if shopping::get(conn, id)?.is_none() {
+            return Ok(None);
+        }
+        shopping::add(conn, id, new).map(Some)
+    })
+    .await?
+    .ok_or(ApiError::NotFound)?;
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+async fn remove_item(Path((id, item_id)): Path<(i32, i32)>) -> ApiResult<StatusCode> {
+    if !db::run_blocking_db(move |conn| shopping::remove(conn, id, item_id)).await? {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct PrintList {
+    /// The default printer when left out.
+    #[serde(default)]
+    printer_id: Option<i32>,
+    /// Validate and render the list, record it as simulated, but never print it.
+    #[serde(default)]
+    dry_run: bool,
+    /// Print even if the printer is in quiet hours.
+    #[serde(default)]
+    urgent: bool,
+}
+
+/// Print the list as it stands, with a box to tick for each item.
+async fn print_list(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(req): Json<PrintList>,
+) -> ApiResult<(StatusCode, Json<JobResponse>)> {
+    let ListDetail { list, items } = db::run_blocking_db(move |conn| detail(conn, id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let payload = JobPayload::Document(shopping::document(&list, &items));
+    let mut new = NewJob::new(
+        printer_or_default(req.printer_id).await?,
+        format!("list:{id}"),
+        &payload,
+    )?;
+    new.urgent = req.urgent;
+    jobs::submit(&state, new, payload, req.dry_run).await
+}
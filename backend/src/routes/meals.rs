@@ -0,0 +1,96 @@
+use crate::db;
+use crate::error::{ApiError, ApiResult};
+use crate::meals::import::{self, Provider};
+use crate::meals::{self, Meal, PlanMeal};
+use crate::routes::extract::Json;
+use crate::state::AppState;
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::{
+    Router,
+    routing::{get, post, put},
+};
+use chrono::{Days, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_meals))
+        .route("/import", post(import_meals))
+        .route("/{date}/{slot}", put(plan_meal).delete(remove_meal))
+}
+
+#[derive(Deserialize)]
+struct MealsQuery {
+    /// First day listed; today by default.
+    from: Option<NaiveDate>,
+    /// Last day listed; a week from `from` by default.
+    to: Option<NaiveDate>,
+}
+
+async fn list_meals(Query(q): Query<MealsQuery>) -> ApiResult<Json<Vec<Meal>>> {
+    let from = q.from.unwrap_or_else(|| Local::now().date_naive());
+    let to =
+        q.to.or_else(|| from.checked_add_days(Days::new(6)))
+            .unwrap_or(from);
+    let rows = db::run_blocking_db(move |conn| meals::between(conn, from, to)).await?;
+    Ok(Json(rows))
+}
+
+async fn plan_meal(
+    Path((date, slot)): Path<(NaiveDate, String)>,
+    Json(meal): Json<PlanMeal>,
+) -> ApiResult<Json<Meal>> {
+    meal.validate()?;
+    let row = db::run_blocking_db(move |conn| meals::plan(conn, date, &slot, meal)).await?;
+    Ok(Json(row))
+}
+
+async fn remove_meal(Path((date, slot)): Path<(NaiveDate, String)>) -> ApiResult<StatusCode> {
+    if !db::run_blocking_db(move |conn| meals::remove(conn, date, &slot)).await? {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct ImportInput {
+    /// `mealie` or `tandoor`.
+    provider: String,
+    url: String,
+    /// API token created in the service's user settings.
+    token: String,
+    /// Days of the plan to import, starting today.
+    #[serde(default = "default_days")]
+    days: u64,
+}
+
+fn default_days() -> u64 {
+    7
+}
+
+#[derive(Serialize)]
+struct Imported {
+    imported: usize,
+}
+
+async fn import_meals(Json(input): Json<ImportInput>) -> ApiResult<Json<Imported>> {
+    let provider: Provider = input
+        .provider
+        .parse()
+        .map_err(|_| ApiError::BadRequest("provider must be one of: mealie, tandoor".into()))?;
+    if !(1..=31).contains(&input.days) {
+        return Err(ApiError::BadRequest("days must be between 1 and 31".into()));
+    }
+    let from = Local::now().date_naive();
+    let to = from
+        .checked_add_days(Days::new(input.days - 1))
+        .unwrap_or(from);
+    let entries = import::fetch(provider, &input.url, &input.token, from, to)
+        .await
+        .map_err(|e| ApiError::BadRequest(format!("{e:#}")))?;
+    let imported =
+        db::run_blocking_db(move |conn| meals::store_imported(conn, provider.as_str(), entries))
+            .await?;
+    Ok(Json(Imported { imported }))
+}
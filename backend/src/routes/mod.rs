@@ -1,8 +1,68 @@
 use crate::state::AppState;
 use axum::Router;
 
+pub mod api_keys;
+pub mod auth;
+pub mod capabilities;
+pub mod countdowns;
+pub mod events;
+pub mod extract;
+pub mod glyphs;
+pub mod habits;
 pub mod health;
+pub mod hooks;
+pub mod integrations;
+pub mod jobs;
+pub mod lists;
+pub mod meals;
+pub mod occasions;
+pub mod openapi;
+pub mod pagination;
+pub mod pairing;
+pub mod printers;
+pub mod public;
+pub mod quotes;
+pub mod render;
+pub mod schedules;
+pub mod settings;
+pub mod subscriptions;
+pub mod tasks;
+pub mod templates;
+pub mod transforms;
+pub mod users;
+pub mod version;
+pub mod webhooks;
+pub mod ws;
 
 pub fn router() -> Router<AppState> {
-    Router::new().nest("/health", health::router())
+    Router::new()
+        .nest("/api-keys", api_keys::router())
+        .nest("/capabilities", capabilities::router())
+        .nest("/countdowns", countdowns::router())
+        .nest("/events", events::router())
+        .nest("/glyphs", glyphs::router())
+        .nest("/habits", habits::router())
+        .nest("/health", health::router())
+        .nest("/hooks", hooks::router())
+        .nest("/integrations", integrations::router())
+        .nest("/jobs", jobs::router())
+        .nest("/lists", lists::router())
+        .nest("/meals", meals::router())
+        .nest("/occasions", occasions::router())
+        .nest("/pair", pairing::router())
+        .nest("/printers", printers::router())
+        .nest("/public", public::router())
+        .nest("/quotes", quotes::router())
+        .nest("/render", render::router())
+        .nest("/schedules", schedules::router())
+        .nest("/settings", settings::router())
+        .nest("/subscriptions", subscriptions::router())
+        .nest("/tasks", tasks::router())
+        .nest("/templates", templates::router())
+        .nest("/transforms", transforms::router())
+        .nest("/users", users::router())
+        .nest("/version", version::router())
+        .nest("/webhooks", webhooks::router())
+        .nest("/ws", ws::router())
+        .merge(openapi::router())
 }
@@ -0,0 +1,83 @@
+use crate::db;
+use crate::error::{ApiError, ApiResult};
+use crate::occasions::{self, NewOccasion, Occasion, vcard};
+use crate::routes::extract::Json;
+use crate::state::AppState;
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::{
+    Router,
+    routing::{get, post},
+};
+use serde::Serialize;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_occasions).post(create_occasion))
+        .route("/import", post(import_occasions))
+        .route(
+            "/{id}",
+            get(get_occasion)
+                .put(update_occasion)
+                .delete(delete_occasion),
+        )
+}
+
+async fn list_occasions() -> ApiResult<Json<Vec<Occasion>>> {
+    let rows = db::run_blocking_db(occasions::list).await?;
+    Ok(Json(rows))
+}
+
+async fn create_occasion(Json(new): Json<NewOccasion>) -> ApiResult<(StatusCode, Json<Occasion>)> {
+    new.validate()?;
+    let row = db::run_blocking_db(move |conn| occasions::create(conn, new)).await?;
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+async fn get_occasion(Path(id): Path<i32>) -> ApiResult<Json<Occasion>> {
+    db::run_blocking_db(move |conn| occasions::get(conn, id))
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+async fn update_occasion(
+    Path(id): Path<i32>,
+    Json(changes): Json<NewOccasion>,
+) -> ApiResult<Json<Occasion>> {
+    changes.validate()?;
+    db::run_blocking_db(move |conn| occasions::update(conn, id, changes))
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+async fn delete_occasion(Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    if !db::run_blocking_db(move |conn| occasions::delete(conn, id)).await? {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Serialize)]
+struct Imported {
+    imported: usize,
+    /// Dates already stored, or found more than once in the file.
+    skipped: usize,
+}
+
+/// Birthdays and anniversaries from a vCard (`.vcf`) file sent as the body.
+async fn import_occasions(body: String) -> ApiResult<Json<Imported>> {
+    let found = vcard::parse(&body);
+    if found.is_empty() {
+        return Err(ApiError::BadRequest(
+            "no birthdays or anniversaries found in the vCard file".into(),
+        ));
+    }
+    let total = found.len();
+    let imported = db::run_blocking_db(move |conn| occasions::import(conn, found)).await?;
+    Ok(Json(Imported {
+        imported,
+        skipped: total - imported,
+    }))
+}
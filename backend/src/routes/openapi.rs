@@ -0,0 +1,54 @@
+use crate::routes::{integrations, jobs, printers};
+use crate::state::AppState;
+use axum::Router;
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use utoipa_swagger_ui::SwaggerUi;
+
+/// The API description's own details; the endpoints come from each route module's
+/// `Api`, generated from its annotated handlers and the types they take and return.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Dayroll",
+        description = "A daily task aggregator for receipt printers"
+    ),
+    modifiers(&ApiKey),
+    security(("api_key" = [])),
+    tags(
+        (name = "printers", description = "Registered printers and their queues"),
+        (name = "jobs", description = "Printing documents, text and templates"),
+        (name = "integrations", description = "Instances of the built-in integrations"),
+    )
+)]
+struct ApiDoc;
+
+/// The `Authorization: Bearer` API key every request needs once one is issued.
+struct ApiKey;
+
+impl Modify for ApiKey {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme(
+                "api_key",
+                SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+            );
+    }
+}
+
+pub fn document() -> utoipa::openapi::OpenApi {
+    let mut document = ApiDoc::openapi();
+    document.merge(printers::Api::openapi());
+    document.merge(jobs::Api::openapi());
+    document.merge(integrations::Api::openapi());
+    document
+}
+
+/// `/api/openapi.json`, and Swagger UI to browse and try it at `/api/docs`.
+pub fn router() -> Router<AppState> {
+    SwaggerUi::new("/api/docs")
+        .url("/api/openapi.json", document())
+        .into()
+}
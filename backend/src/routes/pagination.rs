@@ -0,0 +1,201 @@
+//! Paging, sorting and filtering for list endpoints: `?page=2&limit=20`, or the
+//! previous page's `next` as `?cursor=`, `sort=-created_at` for newest first and
+//! `filter=enabled:true,transport:usb_lp`.
+
+use crate::error::{ApiError, ApiResult, FieldError};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::str::FromStr;
+use utoipa::{IntoParams, ToSchema};
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListQuery {
+    /// Page to return, from 1.
+    page: Option<i64>,
+    /// Items per page, at most 1000.
+    limit: Option<i64>,
+    /// The `next` of the page before, in place of `page`.
+    cursor: Option<String>,
+    /// Field to sort by, with a leading `-` for descending.
+    sort: Option<String>,
+    /// Comma-separated `field:value` pairs that items must all match.
+    filter: Option<String>,
+}
+
+/// A field to sort by, checked against those the endpoint allows.
+pub struct Sort<'a> {
+    pub field: &'a str,
+    pub descending: bool,
+}
+
+/// One page of a list, with how many items there are in all.
+#[derive(Serialize, ToSchema)]
+pub struct Page<T> {
+    items: Vec<T>,
+    /// Items matching the filter, across all pages.
+    total: i64,
+    /// Cursor for the following page; unset on the last.
+    next: Option<String>,
+}
+
+fn invalid(field: &str, message: String) -> ApiError {
+    ApiError::Invalid(vec![FieldError {
+        field: field.into(),
+        message,
+    }])
+}
+
+impl ListQuery {
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+    }
+
+    /// How many items the requested page skips.
+    pub fn offset(&self) -> ApiResult<i64> {
+        if let Some(cursor) = &self.cursor {
+            return URL_SAFE_NO_PAD
+                .decode(cursor)
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .and_then(|offset| offset.parse::<i64>().ok())
+                .filter(|offset| *offset >= 0)
+                .ok_or_else(|| invalid("cursor", "is not a cursor from this API".into()));
+        }
+        (self.page.unwrap_or(1).max(1) - 1)
+            .checked_mul(self.limit())
+            .ok_or_else(|| invalid("page", "is too large".into()))
+    }
+
+    /// The sort asked for, if it's by one of `fields`.
+    pub fn sort(&self, fields: &[&str]) -> ApiResult<Option<Sort<'_>>> {
+        let Some(sort) = self.sort.as_deref().filter(|s| !s.is_empty()) else {
+            return Ok(None);
+        };
+        let (field, descending) = match sort.strip_prefix('-') {
+            Some(field) => (field, true),
+            None => (sort, false),
+        };
+        if !fields.contains(&field) {
+            return Err(invalid(
+                "sort",
+                format!("must be one of {}", fields.join(", ")),
+            ));
+        }
+        Ok(Some(Sort { field, descending }))
+    }
+
+    /// The `field:value` pairs asked for, each field one of `fields`.
+    pub fn filters(&self, fields: &[&str]) -> ApiResult<Vec<(&str, &str)>> {
+        let Some(filter) = self.filter.as_deref() else {
+            return Ok(Vec::new());
+        };
+        let mut errors = Vec::new();
+        let mut pairs = Vec::new();
+        for pair in filter.split(',').filter(|p| !p.is_empty()) {
+            match pair.split_once(':') {
+                Some((field, value)) if fields.contains(&field) => pairs.push((field, value)),
+                Some((field, _)) => errors.push(FieldError {
+                    field: format!("filter.{field}"),
+                    message: format!("can't be filtered on; use {}", fields.join(", ")),
+                }),
+                None => errors.push(FieldError {
+                    field: "filter".into(),
+                    message: format!("{pair} is not field:value"),
+                }),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(ApiError::Invalid(errors));
+        }
+        Ok(pairs)
+    }
+
+    /// Filter, sort and page a whole list loaded in memory, for the short lists
+    /// (printers, integrations) that aren't worth doing in SQL. Items keep their
+    /// order unless sorted, and ties keep it too.
+    pub fn apply<T: Serialize>(&self, items: Vec<T>, fields: &[&str]) -> ApiResult<Page<T>> {
+        let sort = self.sort(fields)?;
+        let filters = self.filters(fields)?;
+        let mut items: Vec<(Value, T)> = items
+            .into_iter()
+            .map(|item| Ok((serde_json::to_value(&item)?, item)))
+            .collect::<anyhow::Result<_>>()?;
+        items.retain(|(value, _)| {
+            filters
+                .iter()
+                .all(|(field, wanted)| matches(&value[field], wanted))
+        });
+        if let Some(sort) = &sort {
+            items.sort_by(|(a, _), (b, _)| {
+                let order = compare(&a[sort.field], &b[sort.field]);
+                if sort.descending {
+                    order.reverse()
+                } else {
+                    order
+                }
+            });
+        }
+        let total = items.len() as i64;
+        let offset = self.offset()?;
+        let items = items
+            .into_iter()
+            .skip(offset as usize)
+            .take(self.limit() as usize)
+            .map(|(_, item)| item)
+            .collect();
+        Ok(Page::new(items, total, offset))
+    }
+}
+
+/// A filter's `value` for `field`, as a `T`.
+pub fn parse<T: FromStr>(field: &str, value: &str) -> ApiResult<T> {
+    value.parse().map_err(|_| {
+        invalid(
+            &format!("filter.{field}"),
+            format!("{value} is not valid here"),
+        )
+    })
+}
+
+impl<T> Page<T> {
+    /// The page of `items` starting `offset` into the `total` items.
+    pub fn new(items: Vec<T>, total: i64, offset: i64) -> Self {
+        let end = offset + items.len() as i64;
+        let next =
+            (!items.is_empty() && end < total).then(|| URL_SAFE_NO_PAD.encode(end.to_string()));
+        Self { items, total, next }
+    }
+}
+
+/// Whether a field's `value` is `wanted`, as written in a query string.
+fn matches(value: &Value, wanted: &str) -> bool {
+    match value {
+        Value::String(s) => s.eq_ignore_ascii_case(wanted),
+        Value::Number(n) => n.to_string() == wanted,
+        Value::Bool(b) => wanted.parse() == Ok(*b),
+        Value::Null => wanted == "null",
+        Value::Array(_) | Value::Object(_) => false,
+    }
+}
+
+/// Unset values first, then numbers by value and everything else by its text.
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Less,
+        (_, Value::Null) => Ordering::Greater,
+        (Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+            a.total_cmp(&b)
+        }
+        (Value::String(a), Value::String(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+        (a, b) => a.to_string().cmp(&b.to_string()),
+    }
+}
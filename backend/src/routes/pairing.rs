@@ -0,0 +1,123 @@
+use crate::api_keys::ApiKey;
+use crate::db;
+use crate::error::{ApiError, ApiResult};
+use crate::jobs::{JobPayload, NewJob};
+use crate::pairing::{self, Refused};
+use crate::printers;
+use crate::routes::extract::Json;
+use crate::routes::jobs::{self, JobResponse};
+use crate::state::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::{
+    Router,
+    routing::{get, post},
+};
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Unauthenticated, so a client without a key can get one: whoever can read the
+/// code off the printer is trusted.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_printers))
+        .route("/start", post(start))
+        .route("/complete", post(complete))
+}
+
+/// A printer the code can be printed on.
+#[derive(Serialize)]
+struct PairingPrinter {
+    id: i32,
+    name: String,
+}
+
+async fn list_printers() -> ApiResult<Json<Vec<PairingPrinter>>> {
+    let rows = db::run_blocking_db(printers::list).await?;
+    Ok(Json(
+        rows.into_iter()
+            .filter(|p| p.enabled)
+            .map(|p| PairingPrinter {
+                id: p.id,
+                name: p.display_name.unwrap_or(p.name),
+            })
+            .collect(),
+    ))
+}
+
+#[derive(Deserialize)]
+struct StartPairing {
+    printer_id: i32,
+    /// The client's pairing page; the slip then has a QR code opening it with
+    /// `?code=` filled in.
+    #[serde(default)]
+    url: Option<String>,
+}
+
+#[derive(Serialize)]
+struct PairingStarted {
+    /// The job printing the code.
+    #[serde(flatten)]
+    job: JobResponse,
+    expires_at: NaiveDateTime,
+}
+
+/// Print a new code, replacing any still pending, at most once a minute.
+async fn start(
+    State(state): State<AppState>,
+    Json(req): Json<StartPairing>,
+) -> ApiResult<(StatusCode, Json<PairingStarted>)> {
+    if let Some(url) = &req.url {
+        match reqwest::Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+            _ => return Err(ApiError::BadRequest("url must be an http(s) URL".into())),
+        }
+    }
+    let printer_id = req.printer_id;
+    let code = db::run_blocking_db(move |conn| pairing::start(conn, printer_id))
+        .await?
+        .map_err(|refused| {
+            ApiError::Conflict(match refused {
+                Refused::TooSoon => "a code was printed less than a minute ago".into(),
+                Refused::Locked => {
+                    "too many wrong codes; try again once the pending one expires".into()
+                }
+            })
+        })?;
+    let payload = JobPayload::Document(pairing::document(&code, req.url.as_deref()));
+    let new = NewJob::new(printer_id, "pairing", &payload)?;
+    let (status, Json(job)) = jobs::submit(&state, new, payload, false).await?;
+    Ok((
+        status,
+        Json(PairingStarted {
+            job,
+            expires_at: Utc::now().naive_utc() + pairing::CODE_TTL,
+        }),
+    ))
+}
+
+#[derive(Deserialize)]
+struct CompletePairing {
+    code: String,
+    /// What to call the new key, such as "Kitchen tablet".
+    name: String,
+}
+
+/// The key is shown this once.
+#[derive(Serialize)]
+struct PairedKey {
+    #[serde(flatten)]
+    api_key: ApiKey,
+    key: String,
+}
+
+async fn complete(Json(req): Json<CompletePairing>) -> ApiResult<(StatusCode, Json<PairedKey>)> {
+    let name = req.name.trim().to_string();
+    if name.is_empty() {
+        return Err(ApiError::BadRequest("name must not be empty".into()));
+    }
+    let (api_key, key) = db::run_blocking_db(move |conn| pairing::complete(conn, &req.code, &name))
+        .await?
+        .ok_or_else(|| ApiError::BadRequest("the code is wrong or has expired".into()))?;
+    Ok((StatusCode::CREATED, Json(PairedKey { api_key, key })))
+}
@@ -0,0 +1,475 @@
+use crate::db;
+use crate::discover::{DefaultDiscovery, DiscoveryProvider};
+use crate::document::{Align, Block, Document, Style};
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::events::{ConfigScope, Event};
+use crate::jobs::{JobPayload, NewJob};
+use crate::model::Candidate;
+use crate::printers::{self, NewPrinter, Printer};
+use crate::queue::eta::QueuedJob;
+use crate::render::photo::{self, PhotoOptions};
+use crate::retention;
+use crate::routes::extract::{Json, rejected};
+use crate::routes::jobs::{self, JobResponse};
+use crate::routes::pagination::{ListQuery, Page};
+use crate::state::AppState;
+use axum::extract::{DefaultBodyLimit, FromRequest, Multipart, Path, Query, Request, State};
+use axum::http::StatusCode;
+use axum::http::header::CONTENT_TYPE;
+use axum::{
+    Router,
+    routing::{get, post},
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::Deserialize;
+use utoipa::{OpenApi, ToSchema};
+
+/// Photos straight off a phone are a few megabytes; allow well beyond that.
+const MAX_IMAGE_UPLOAD: usize = 16 * 1024 * 1024;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_printers).post(create_printer))
+        .route("/candidates", get(list_candidates))
+        .route("/deleted", get(list_deleted_printers))
+        .route(
+            "/{id}",
+            get(get_printer).put(update_printer).delete(delete_printer),
+        )
+        .route("/{id}/restore", post(restore_printer))
+        .route(
+            "/{id}/print/image",
+            post(print_image).layer(DefaultBodyLimit::max(MAX_IMAGE_UPLOAD)),
+        )
+        .route("/{id}/queue", get(get_queue))
+        .route("/{id}/pause", post(pause_printer))
+        .route("/{id}/resume", post(resume_printer))
+}
+
+/// These endpoints' part of the [API description](super::openapi).
+#[derive(OpenApi)]
+#[openapi(paths(
+    list_printers,
+    list_candidates,
+    create_printer,
+    list_deleted_printers,
+    get_printer,
+    update_printer,
+    delete_printer,
+    restore_printer,
+    print_image,
+    get_queue,
+    pause_printer,
+    resume_printer,
+))]
+pub(super) struct Api;
+
+#[utoipa::path(
+    get,
+    path = "/printers",
+    tag = "printers",
+    params(ListQuery),
+    responses(
+        (status = 200, description = "Printers not in the trash", body = Page<Printer>),
+        (status = 422, description = "Invalid sort, filter or cursor", body = ErrorBody),
+    )
+)]
+async fn list_printers(Query(list): Query<ListQuery>) -> ApiResult<Json<Page<Printer>>> {
+    let rows = db::run_blocking_db(printers::list).await?;
+    Ok(Json(list.apply(
+        rows,
+        &[
+            "id",
+            "name",
+            "transport",
+            "enabled",
+            "paused",
+            "location",
+            "created_at",
+            "updated_at",
+        ],
+    )?))
+}
+
+/// Devices attached to the server that look like printers, most likely first, to
+/// register one from.
+#[utoipa::path(
+    get,
+    path = "/printers/candidates",
+    tag = "printers",
+    params(ListQuery),
+    responses(
+        (status = 200, description = "Devices found", body = Page<Candidate>),
+        (status = 403, description = "Needs an admin key", body = ErrorBody),
+        (status = 422, description = "Invalid sort, filter or cursor", body = ErrorBody),
+    )
+)]
+async fn list_candidates(Query(list): Query<ListQuery>) -> ApiResult<Json<Page<Candidate>>> {
+    let mut found = tokio::task::spawn_blocking(|| DefaultDiscovery.discover_default()).await??;
+    found.sort_by_key(|c| std::cmp::Reverse(c.confidence));
+    Ok(Json(list.apply(
+        found,
+        &[
+            "transport",
+            "make_model",
+            "serial",
+            "vid",
+            "pid",
+            "confidence",
+        ],
+    )?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/printers",
+    tag = "printers",
+    request_body = NewPrinter,
+    responses(
+        (status = 201, description = "Printer registered", body = Printer),
+        (status = 422, description = "Invalid printer, by field", body = ErrorBody),
+        (status = 403, description = "Needs an admin key", body = ErrorBody),
+    )
+)]
+async fn create_printer(Json(new): Json<NewPrinter>) -> ApiResult<(StatusCode, Json<Printer>)> {
+    new.validate()?;
+    let row = db::run_blocking_db(move |conn| printers::create(conn, new)).await?;
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/printers/{id}",
+    tag = "printers",
+    params(("id" = i32, Path, description = "Printer id")),
+    responses(
+        (status = 200, description = "The printer", body = Printer),
+        (status = 404, description = "No such printer", body = ErrorBody),
+    )
+)]
+async fn get_printer(Path(id): Path<i32>) -> ApiResult<Json<Printer>> {
+    db::run_blocking_db(move |conn| printers::get(conn, id))
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+#[utoipa::path(
+    put,
+    path = "/printers/{id}",
+    tag = "printers",
+    params(("id" = i32, Path, description = "Printer id")),
+    request_body = NewPrinter,
+    responses(
+        (status = 200, description = "Printer updated", body = Printer),
+        (status = 422, description = "Invalid printer, by field", body = ErrorBody),
+        (status = 403, description = "Needs an admin key", body = ErrorBody),
+        (status = 404, description = "No such printer", body = ErrorBody),
+    )
+)]
+async fn update_printer(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(new): Json<NewPrinter>,
+) -> ApiResult<Json<Printer>> {
+    new.validate()?;
+    let printer = db::run_blocking_db(move |conn| printers::update(conn, id, new))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    changed(&state, id);
+    Ok(Json(printer))
+}
+
+/// Tell the printer's worker to pick up new settings, such as quiet hours.
+fn changed(state: &AppState, id: i32) {
+    state.events.publish(Event::ConfigChanged {
+        scope: ConfigScope::Printer,
+        id: id.into(),
+    });
+}
+
+/// Move the printer to the trash. Jobs still waiting fail; history is kept until the
+/// retention window passes.
+#[utoipa::path(
+    delete,
+    path = "/printers/{id}",
+    tag = "printers",
+    params(("id" = i32, Path, description = "Printer id")),
+    responses(
+        (status = 204, description = "Printer moved to the trash"),
+        (status = 403, description = "Needs an admin key", body = ErrorBody),
+        (status = 404, description = "No such printer", body = ErrorBody),
+    )
+)]
+async fn delete_printer(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> ApiResult<StatusCode> {
+    let deleted = db::run_blocking_db(move |conn| printers::delete(conn, id)).await?;
+    if !deleted {
+        return Err(ApiError::NotFound);
+    }
+    changed(&state, id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/printers/deleted",
+    tag = "printers",
+    responses(
+        (status = 200, description = "Printers in the trash", body = Vec<Printer>),
+        (status = 403, description = "Needs an admin key", body = ErrorBody),
+    )
+)]
+async fn list_deleted_printers() -> ApiResult<Json<Vec<Printer>>> {
+    let rows = db::run_blocking_db(printers::list_deleted).await?;
+    Ok(Json(rows))
+}
+
+/// Bring a deleted printer back, as long as it has not been purged.
+#[utoipa::path(
+    post,
+    path = "/printers/{id}/restore",
+    tag = "printers",
+    params(("id" = i32, Path, description = "Printer id")),
+    responses(
+        (status = 200, description = "Printer restored", body = Printer),
+        (status = 403, description = "Needs an admin key", body = ErrorBody),
+        (status = 404, description = "No such printer in the trash", body = ErrorBody),
+    )
+)]
+async fn restore_printer(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> ApiResult<Json<Printer>> {
+    let since = retention::cutoff(state.config.deleted_retention_days);
+    let printer = db::run_blocking_db(move |conn| printers::restore(conn, id, since))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    changed(&state, id);
+    Ok(Json(printer))
+}
+
+/// The job printing on this printer, then the jobs waiting in the order they will
+/// print, each with an estimated duration and ETA.
+#[utoipa::path(
+    get,
+    path = "/printers/{id}/queue",
+    tag = "printers",
+    params(("id" = i32, Path, description = "Printer id")),
+    responses(
+        (status = 200, description = "Printing and waiting jobs in print order", body = Vec<QueuedJob>),
+        (status = 404, description = "No such printer", body = ErrorBody),
+    )
+)]
+async fn get_queue(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> ApiResult<Json<Vec<QueuedJob>>> {
+    db::run_blocking_db(move |conn| printers::get(conn, id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let rows = state.queue.estimates(id).await?;
+    Ok(Json(rows))
+}
+
+/// Stop printing without touching the queue. A job already printing finishes.
+#[utoipa::path(
+    post,
+    path = "/printers/{id}/pause",
+    tag = "printers",
+    params(("id" = i32, Path, description = "Printer id")),
+    responses(
+        (status = 200, description = "Printer paused", body = Printer),
+        (status = 404, description = "No such printer", body = ErrorBody),
+    )
+)]
+async fn pause_printer(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> ApiResult<Json<Printer>> {
+    set_paused(&state, id, true).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/printers/{id}/resume",
+    tag = "printers",
+    params(("id" = i32, Path, description = "Printer id")),
+    responses(
+        (status = 200, description = "Printer resumed", body = Printer),
+        (status = 404, description = "No such printer", body = ErrorBody),
+    )
+)]
+async fn resume_printer(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> ApiResult<Json<Printer>> {
+    set_paused(&state, id, false).await
+}
+
+async fn set_paused(state: &AppState, id: i32, paused: bool) -> ApiResult<Json<Printer>> {
+    let printer = db::run_blocking_db(move |conn| printers::set_paused(conn, id, paused))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    state.events.publish(Event::PrinterPaused {
+        printer_id: id,
+        paused,
+    });
+    changed(state, id);
+    Ok(Json(printer))
+}
+
+/// A picture to print, sent either as JSON with a base64 `image`, or as
+/// `multipart/form-data` with an `image` file part and the options as text fields.
+struct ImageUpload {
+    image: Vec<u8>,
+    options: ImageOptions,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct ImageOptions {
+    /// Printed centred under the picture.
+    #[serde(default)]
+    caption: Option<String>,
+    #[serde(default = "yes")]
+    cut: bool,
+    #[serde(default = "yes")]
+    rotate: bool,
+    #[serde(default = "yes")]
+    dither: bool,
+    #[serde(default)]
+    urgent: bool,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct ImageJson {
+    image: String,
+    #[serde(flatten)]
+    options: ImageOptions,
+}
+
+fn yes() -> bool {
+    true
+}
+
+impl<S: Send + Sync> FromRequest<S> for ImageUpload {
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let multipart = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("multipart/form-data"));
+        if !multipart {
+            let Json(body) = Json::<ImageJson>::from_request(req, state).await?;
+            let image = BASE64
+                .decode(&body.image)
+                .map_err(|e| ApiError::BadRequest(format!("image is not valid base64: {e}")))?;
+            return Ok(Self {
+                image,
+                options: body.options,
+            });
+        }
+
+        let bad_request =
+            |e: axum::extract::multipart::MultipartError| rejected(e.status(), e.body_text());
+        let mut form = Multipart::from_request(req, state)
+            .await
+            .map_err(|e| rejected(e.status(), e.body_text()))?;
+        let mut image = None;
+        let mut options = serde_json::Map::new();
+        while let Some(field) = form.next_field().await.map_err(bad_request)? {
+            let name = field.name().unwrap_or_default().to_string();
+            if name == "image" {
+                image = Some(field.bytes().await.map_err(bad_request)?.to_vec());
+                continue;
+            }
+            let text = field.text().await.map_err(bad_request)?;
+            let value = match name.as_str() {
+                "caption" => text.into(),
+                _ => match text.as_str() {
+                    "true" | "on" | "1" => true.into(),
+                    "false" | "off" | "0" => false.into(),
+                    _ => {
+                        return Err(ApiError::BadRequest(format!(
+                            "{name} must be true or false"
+                        )));
+                    }
+                },
+            };
+            options.insert(name, value);
+        }
+        let image = image.ok_or_else(|| ApiError::BadRequest("missing image part".into()))?;
+        let options = serde_json::from_value(options.into())
+            .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+        Ok(Self { image, options })
+    }
+}
+
+/// Print a photo or logo: uprighted, scaled to the paper width and dithered, with an
+/// optional caption underneath.
+#[utoipa::path(
+    post,
+    path = "/printers/{id}/print/image",
+    tag = "printers",
+    params(("id" = i32, Path, description = "Printer id")),
+    request_body(
+        content = ImageJson,
+        description = "A base64 `image` as JSON, or the same fields as `multipart/form-data` with an `image` file part"
+    ),
+    responses(
+        (status = 202, description = "Job queued", body = JobResponse),
+        (status = 400, description = "Unreadable picture or invalid options", body = ErrorBody),
+        (status = 409, description = "The printer is disabled", body = ErrorBody),
+        (status = 503, description = "The server is shutting down", body = ErrorBody),
+    )
+)]
+async fn print_image(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    upload: ImageUpload,
+) -> ApiResult<(StatusCode, Json<JobResponse>)> {
+    let ImageUpload { image, options } = upload;
+    let photo_options = PhotoOptions {
+        rotate: options.rotate,
+        dither: options.dither,
+        max_height: state.config.limits.max_raster_height,
+    };
+    let png = tokio::task::spawn_blocking(move || photo::prepare(&image, photo_options))
+        .await?
+        .map_err(ApiError::BadRequest)?;
+
+    let mut blocks = vec![Block::Image {
+        data: BASE64.encode(png),
+        align: Align::Center,
+    }];
+    if let Some(caption) = options.caption.filter(|c| !c.trim().is_empty()) {
+        blocks.push(Block::Text {
+            text: caption,
+            spans: Vec::new(),
+            style: Style {
+                align: Align::Center,
+                ..Default::default()
+            },
+        });
+    }
+    blocks.push(if options.cut {
+        Block::Cut { partial: false }
+    } else {
+        Block::Feed { lines: 1 }
+    });
+
+    let payload = JobPayload::Document(Document {
+        blocks,
+        ..Document::default()
+    });
+    let mut new = NewJob::new(id, "api", &payload)?;
+    new.urgent = options.urgent;
+    jobs::submit(&state, new, payload, options.dry_run).await
+}
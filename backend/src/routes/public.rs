@@ -0,0 +1,89 @@
+use crate::db;
+use crate::error::{ApiError, ApiResult};
+use crate::habits;
+use crate::schedules::previews;
+use crate::state::AppState;
+use axum::extract::{Path, Query};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::{Router, routing::get};
+use chrono::{Local, NaiveDate};
+use serde::Deserialize;
+
+/// Unauthenticated endpoints, each guarded by its own share token.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/preview/{schedule}/latest.png", get(latest_preview))
+        .route("/habits/{habit}/check", get(check_habit))
+}
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct CheckQuery {
+    token: String,
+    /// Day the printout was for; today when left out.
+    date: Option<NaiveDate>,
+}
+
+async fn latest_preview(
+    Path(schedule_id): Path<i32>,
+    Query(q): Query<TokenQuery>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    // Unknown schedules and bad tokens are indistinguishable on purpose.
+    let preview =
+        db::run_blocking_db(move |conn| previews::latest_public(conn, schedule_id, &q.token))
+            .await?
+            .ok_or(ApiError::NotFound)?;
+
+    let etag = format!(
+        "\"{}-{}\"",
+        preview.schedule_id,
+        preview.updated_at.and_utc().timestamp()
+    );
+    let last_modified = preview
+        .updated_at
+        .and_utc()
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string();
+
+    let mut response = if headers
+        .get(header::IF_NONE_MATCH)
+        .is_some_and(|v| v.as_bytes() == etag.as_bytes())
+    {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        ([(header::CONTENT_TYPE, "image/png")], preview.png).into_response()
+    };
+
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("public, max-age=60"),
+    );
+    headers.insert(header::ETAG, HeaderValue::from_str(&etag)?);
+    headers.insert(
+        header::LAST_MODIFIED,
+        HeaderValue::from_str(&last_modified)?,
+    );
+    Ok(response)
+}
+
+/// Check a habit off from the QR code printed next to it. A GET, so scanning the
+/// code with any phone camera is enough.
+async fn check_habit(Path(habit_id): Path<i32>, Query(q): Query<CheckQuery>) -> ApiResult<String> {
+    let day = q.date.unwrap_or_else(|| Local::now().date_naive());
+    let habit =
+        db::run_blocking_db(move |conn| habits::check_with_token(conn, habit_id, &q.token, day))
+            .await?
+            .ok_or(ApiError::NotFound)?;
+    Ok(format!(
+        "{} checked off for {}",
+        habit.name,
+        day.format("%A, %B %-d")
+    ))
+}
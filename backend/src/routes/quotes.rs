@@ -0,0 +1,90 @@
+use crate::db;
+use crate::error::{ApiError, ApiResult};
+use crate::quotes::{self, NewQuote, NewQuoteList, Quote, QuoteList};
+use crate::routes::extract::Json;
+use crate::state::AppState;
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::{
+    Router,
+    routing::{delete, get, post},
+};
+use serde::Serialize;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_lists).post(create_list))
+        .route("/{id}", get(get_list).delete(delete_list))
+        .route("/{id}/quotes", post(add_quote))
+        .route("/{id}/quotes/{quote_id}", delete(remove_quote))
+}
+
+#[derive(Serialize)]
+struct ListDetail {
+    #[serde(flatten)]
+    list: QuoteList,
+    quotes: Vec<Quote>,
+}
+
+async fn list_lists() -> ApiResult<Json<Vec<QuoteList>>> {
+    let rows = db::run_blocking_db(quotes::list).await?;
+    Ok(Json(rows))
+}
+
+async fn create_list(Json(new): Json<NewQuoteList>) -> ApiResult<(StatusCode, Json<ListDetail>)> {
+    new.validate()?;
+    let detail = db::run_blocking_db(move |conn| {
+        if quotes::get_by_name(conn, &new.name)?.is_some() {
+            return Ok(None);
+        }
+        let list = quotes::create(conn, new)?;
+        let quotes = quotes::quotes_of(conn, &list)?;
+        Ok(Some(ListDetail { list, quotes }))
+    })
+    .await?
+    .ok_or_else(|| ApiError::Conflict("a quote list with that name already exists".into()))?;
+    Ok((StatusCode::CREATED, Json(detail)))
+}
+
+async fn get_list(Path(id): Path<i32>) -> ApiResult<Json<ListDetail>> {
+    db::run_blocking_db(move |conn| {
+        let Some(list) = quotes::get(conn, id)? else {
+            return Ok(None);
+        };
+        let quotes = quotes::quotes_of(conn, &list)?;
+        Ok(Some(ListDetail { list, quotes }))
+    })
+    .await?
+    .map(Json)
+    .ok_or(ApiError::NotFound)
+}
+
+async fn delete_list(Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    if !db::run_blocking_db(move |conn| quotes::delete(conn, id)).await? {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn add_quote(
+    Path(id): Path<i32>,
+    Json(new): Json<NewQuote>,
+) -> ApiResult<(StatusCode, Json<Quote>)> {
+    new.validate()?;
+    let row = db::run_blocking_db(move |conn| {
+        if quotes::get(conn, id)?.is_none() {
+            return Ok(None);
+        }
+        quotes::add(conn, id, new).map(Some)
+    })
+    .await?
+    .ok_or(ApiError::NotFound)?;
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+async fn remove_quote(Path((id, quote_id)): Path<(i32, i32)>) -> ApiResult<StatusCode> {
+    if !db::run_blocking_db(move |conn| quotes::remove(conn, id, quote_id)).await? {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,109 @@
+use crate::db;
+use crate::document::Document;
+use crate::error::{ApiError, ApiResult};
+use crate::jobs::JobContent;
+use crate::printers;
+use crate::render::{self, Profile};
+use crate::routes::extract::Json;
+use crate::routes::jobs::{default_source, render_limited, resolve};
+use crate::state::AppState;
+use crate::transforms;
+use axum::extract::State;
+use axum::http::{HeaderName, header};
+use axum::response::{IntoResponse, Response};
+use axum::{Router, routing::post};
+use serde::{Deserialize, Serialize};
+
+/// Printed length of a previewed job, in millimetres.
+const LENGTH_HEADER: HeaderName = HeaderName::from_static("x-dayroll-length-mm");
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/estimate", post(estimate))
+        .route("/preview", post(preview))
+}
+
+#[derive(Deserialize)]
+struct PreviewRequest {
+    /// Lay the receipt out for this printer, with its header, footer and character
+    /// sets; otherwise for a typical printer.
+    #[serde(default)]
+    printer_id: Option<i32>,
+    /// Source whose transform pipeline to apply, as if the job were submitted by it.
+    #[serde(default = "default_source")]
+    source: String,
+    #[serde(flatten)]
+    content: JobContent,
+}
+
+#[derive(Serialize)]
+struct Estimate {
+    /// Printed lines of text.
+    lines: usize,
+    /// Paper fed before the cut, in dots and in millimetres.
+    dots: u32,
+    length_mm: f32,
+    bytes: usize,
+}
+
+/// Draw a job as it will come out of the printer and answer with the PNG. Its
+/// printed length is in the `X-Dayroll-Length-Mm` header.
+async fn preview(
+    State(state): State<AppState>,
+    Json(req): Json<PreviewRequest>,
+) -> ApiResult<Response> {
+    let (document, profile) = prepare(req).await?;
+    // Refuse what the printer would refuse before drawing it.
+    let rendered = render_limited(&state, document.clone(), profile.clone()).await?;
+    let png =
+        tokio::task::spawn_blocking(move || render::render_png(&document, &profile)).await??;
+    let headers = [
+        (header::CONTENT_TYPE, "image/png".to_string()),
+        (LENGTH_HEADER, format!("{:.1}", rendered.length_mm())),
+    ];
+    Ok((headers, png).into_response())
+}
+
+/// How much paper a job will take on the printer, without printing it.
+async fn estimate(
+    State(state): State<AppState>,
+    Json(req): Json<PreviewRequest>,
+) -> ApiResult<Json<Estimate>> {
+    let (document, profile) = prepare(req).await?;
+    let rendered = render_limited(&state, document, profile).await?;
+    Ok(Json(Estimate {
+        lines: rendered.lines,
+        dots: rendered.height,
+        length_mm: rendered.length_mm(),
+        bytes: rendered.bytes.len(),
+    }))
+}
+
+/// The request's document, framed for its printer, and the profile to render it with.
+async fn prepare(req: PreviewRequest) -> ApiResult<(Document, Profile)> {
+    let payload = resolve(req.content).await?;
+    let printer_id = req.printer_id;
+    let source = req.source;
+    let prepared = db::run_blocking_db(move |conn| {
+        let printer = match printer_id {
+            Some(id) => match printers::get(conn, id)? {
+                Some(printer) => Some(printer),
+                None => return Ok(None),
+            },
+            None => None,
+        };
+        let mut document = transforms::prepare(conn, &source, payload)?;
+        if let Some(printer) = &printer {
+            printer.frame(&mut document);
+        }
+        let profile = Profile::load(conn, printer.as_ref(), &document)?;
+        Ok(Some((document, profile)))
+    })
+    .await?;
+    prepared.ok_or_else(|| {
+        ApiError::BadRequest(format!(
+            "printer {} does not exist",
+            printer_id.unwrap_or_default()
+        ))
+    })
+}
@@ -0,0 +1,195 @@
+use crate::compose::runner;
+use crate::db;
+use crate::error::{ApiError, ApiResult};
+use crate::events::{ConfigScope, Event};
+use crate::routes::extract::Json;
+use crate::routes::users::check_owner;
+use crate::schedules::runs::{self, RunSection, ScheduleRun};
+use crate::schedules::{self, NewSchedule, Schedule};
+use crate::settings;
+use crate::state::AppState;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::{
+    Router,
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_schedules).post(create_schedule))
+        .route("/{id}", get(get_schedule).put(update_schedule))
+        .route(
+            "/{id}/public-token",
+            post(rotate_public_token).delete(revoke_public_token),
+        )
+        .route("/{id}/run", post(run_now))
+        .route("/{id}/runs", get(list_runs))
+        .route("/{id}/runs/{run_id}", get(get_run))
+}
+
+#[derive(Deserialize)]
+struct SchedulesQuery {
+    /// Only this user's schedules.
+    user_id: Option<i32>,
+}
+
+async fn list_schedules(Query(q): Query<SchedulesQuery>) -> ApiResult<Json<Vec<Schedule>>> {
+    let rows = db::run_blocking_db(schedules::list).await?;
+    Ok(Json(
+        rows.into_iter()
+            .filter(|s| q.user_id.is_none_or(|id| s.user_id == Some(id)))
+            .collect(),
+    ))
+}
+
+/// Schedules left without a time fire at the settings' `digest_time`.
+async fn default_time(schedule: &mut NewSchedule) -> ApiResult<()> {
+    if !schedule.time_of_day.is_empty() {
+        return Ok(());
+    }
+    schedule.time_of_day = db::run_blocking_db(settings::get)
+        .await?
+        .digest_time
+        .ok_or_else(|| {
+            ApiError::BadRequest("time_of_day is required while no digest_time is set".into())
+        })?;
+    Ok(())
+}
+
+async fn create_schedule(
+    State(state): State<AppState>,
+    Json(mut new): Json<NewSchedule>,
+) -> ApiResult<(StatusCode, Json<Schedule>)> {
+    default_time(&mut new).await?;
+    new.validate()?;
+    check_owner(new.user_id).await?;
+    let row = db::run_blocking_db(move |conn| schedules::create(conn, new)).await?;
+    state.events.publish(Event::ConfigChanged {
+        scope: ConfigScope::Schedule,
+        id: row.id.into(),
+    });
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+async fn get_schedule(Path(id): Path<i32>) -> ApiResult<Json<Schedule>> {
+    db::run_blocking_db(move |conn| schedules::get(conn, id))
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+async fn update_schedule(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(mut changes): Json<NewSchedule>,
+) -> ApiResult<Json<Schedule>> {
+    default_time(&mut changes).await?;
+    changes.validate()?;
+    check_owner(changes.user_id).await?;
+    let row = db::run_blocking_db(move |conn| schedules::update(conn, id, changes))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    state.events.publish(Event::ConfigChanged {
+        scope: ConfigScope::Schedule,
+        id: id.into(),
+    });
+    Ok(Json(row))
+}
+
+/// Trigger a schedule immediately, outside its normal time.
+async fn run_now(State(state): State<AppState>, Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    let schedule = db::run_blocking_db(move |conn| schedules::get(conn, id))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    runner::run_schedule(&state.queue, schedule, state.config.digest_theme).await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[derive(Serialize)]
+struct PublicToken {
+    token: String,
+    preview_url: String,
+}
+
+/// Issue a new share token for the public preview image, invalidating any previous one.
+async fn rotate_public_token(Path(id): Path<i32>) -> ApiResult<Json<PublicToken>> {
+    let token = Uuid::new_v4().simple().to_string();
+    let stored = token.clone();
+    if !db::run_blocking_db(move |conn| schedules::set_public_token(conn, id, Some(stored))).await?
+    {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(Json(PublicToken {
+        preview_url: format!("/public/preview/{id}/latest.png?token={token}"),
+        token,
+    }))
+}
+
+async fn revoke_public_token(Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    if !db::run_blocking_db(move |conn| schedules::set_public_token(conn, id, None)).await? {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct RunsQuery {
+    limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct RunSummary {
+    #[serde(flatten)]
+    run: ScheduleRun,
+    duration_ms: Option<i64>,
+}
+
+async fn list_runs(
+    Path(id): Path<i32>,
+    Query(q): Query<RunsQuery>,
+) -> ApiResult<Json<Vec<RunSummary>>> {
+    let limit = q.limit.unwrap_or(50).clamp(1, 500);
+    let rows = db::run_blocking_db(move |conn| {
+        if schedules::get(conn, id)?.is_none() {
+            return Ok(None);
+        }
+        runs::list_for_schedule(conn, id, limit).map(Some)
+    })
+    .await?
+    .ok_or(ApiError::NotFound)?;
+
+    Ok(Json(
+        rows.into_iter()
+            .map(|run| RunSummary {
+                duration_ms: run.duration_ms(),
+                run,
+            })
+            .collect(),
+    ))
+}
+
+#[derive(Serialize)]
+struct RunDetail {
+    #[serde(flatten)]
+    summary: RunSummary,
+    sections: Vec<RunSection>,
+}
+
+async fn get_run(Path((id, run_id)): Path<(i32, i32)>) -> ApiResult<Json<RunDetail>> {
+    let (run, sections) =
+        db::run_blocking_db(move |conn| runs::get_with_sections(conn, id, run_id))
+            .await?
+            .ok_or(ApiError::NotFound)?;
+
+    Ok(Json(RunDetail {
+        summary: RunSummary {
+            duration_ms: run.duration_ms(),
+            run,
+        },
+        sections,
+    }))
+}
@@ -0,0 +1,55 @@
+use crate::db;
+use crate::error::{ApiError, ApiResult};
+use crate::events::{ConfigScope, Event};
+use crate::printers;
+use crate::routes::extract::Json;
+use crate::settings::{self, NewSettings, Settings};
+use crate::state::AppState;
+use axum::extract::State;
+use axum::{Router, routing::get};
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/", get(get_settings).put(update_settings))
+}
+
+/// `printer_id`, or the default printer when it's left out.
+pub(crate) async fn printer_or_default(printer_id: Option<i32>) -> ApiResult<i32> {
+    if let Some(printer_id) = printer_id {
+        return Ok(printer_id);
+    }
+    db::run_blocking_db(settings::get)
+        .await?
+        .default_printer_id
+        .ok_or_else(|| {
+            ApiError::BadRequest("printer_id is required while no default printer is set".into())
+        })
+}
+
+async fn get_settings() -> ApiResult<Json<Settings>> {
+    let row = db::run_blocking_db(settings::get).await?;
+    Ok(Json(row))
+}
+
+/// Replace the settings. Background tasks acting on them pick the change up
+/// straight away.
+async fn update_settings(
+    State(state): State<AppState>,
+    Json(changes): Json<NewSettings>,
+) -> ApiResult<Json<Settings>> {
+    changes.validate()?;
+    if let Some(printer_id) = changes.default_printer_id
+        && db::run_blocking_db(move |conn| printers::get(conn, printer_id))
+            .await?
+            .is_none()
+    {
+        return Err(ApiError::BadRequest(format!(
+            "printer {printer_id} does not exist"
+        )));
+    }
+    let row = db::run_blocking_db(move |conn| settings::update(conn, changes)).await?;
+    state.events.publish(Event::ConfigChanged {
+        scope: ConfigScope::Settings,
+        id: settings::ID.into(),
+    });
+    Ok(Json(row))
+}
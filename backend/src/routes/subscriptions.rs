@@ -0,0 +1,111 @@
+use crate::db;
+use crate::error::{ApiError, ApiResult};
+use crate::printers;
+use crate::push::{self, NewSubscription, StatsSnapshot, Subscription};
+use crate::routes::extract::Json;
+use crate::state::AppState;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::{Router, routing::get};
+use serde::Serialize;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_subscriptions).post(create_subscription))
+        .route(
+            "/{id}",
+            get(get_subscription)
+                .put(update_subscription)
+                .delete(delete_subscription),
+        )
+}
+
+/// A subscription with its live counters; `status` is null while it is disabled.
+#[derive(Serialize)]
+struct SubscriptionView {
+    #[serde(flatten)]
+    subscription: Subscription,
+    status: Option<StatsSnapshot>,
+}
+
+impl SubscriptionView {
+    fn new(state: &AppState, subscription: Subscription) -> Self {
+        let status = state.push.status(subscription.id);
+        Self {
+            subscription,
+            status,
+        }
+    }
+}
+
+async fn list_subscriptions(
+    State(state): State<AppState>,
+) -> ApiResult<Json<Vec<SubscriptionView>>> {
+    let rows = db::run_blocking_db(push::list).await?;
+    Ok(Json(
+        rows.into_iter()
+            .map(|sub| SubscriptionView::new(&state, sub))
+            .collect(),
+    ))
+}
+
+async fn validate(new: &NewSubscription) -> ApiResult<()> {
+    new.validate()?;
+    let printer_id = new.printer_id;
+    if db::run_blocking_db(move |conn| printers::get(conn, printer_id))
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::BadRequest(format!(
+            "printer {printer_id} does not exist"
+        )));
+    }
+    Ok(())
+}
+
+async fn create_subscription(
+    State(state): State<AppState>,
+    Json(new): Json<NewSubscription>,
+) -> ApiResult<(StatusCode, Json<SubscriptionView>)> {
+    validate(&new).await?;
+    let sub = db::run_blocking_db(move |conn| push::create(conn, new)).await?;
+    state.push.restart(&sub);
+    Ok((
+        StatusCode::CREATED,
+        Json(SubscriptionView::new(&state, sub)),
+    ))
+}
+
+async fn get_subscription(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> ApiResult<Json<SubscriptionView>> {
+    db::run_blocking_db(move |conn| push::get(conn, id))
+        .await?
+        .map(|sub| Json(SubscriptionView::new(&state, sub)))
+        .ok_or(ApiError::NotFound)
+}
+
+async fn update_subscription(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(new): Json<NewSubscription>,
+) -> ApiResult<Json<SubscriptionView>> {
+    validate(&new).await?;
+    let sub = db::run_blocking_db(move |conn| push::update(conn, id, new))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    state.push.restart(&sub);
+    Ok(Json(SubscriptionView::new(&state, sub)))
+}
+
+async fn delete_subscription(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> ApiResult<StatusCode> {
+    if !db::run_blocking_db(move |conn| push::delete(conn, id)).await? {
+        return Err(ApiError::NotFound);
+    }
+    state.push.stop(id);
+    Ok(StatusCode::NO_CONTENT)
+}
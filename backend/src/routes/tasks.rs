@@ -0,0 +1,108 @@
+use crate::db;
+use crate::error::{ApiError, ApiResult};
+use crate::routes::extract::Json;
+use crate::routes::users::check_owner;
+use crate::state::AppState;
+use crate::tasks::{self, NewTask, Task};
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::{
+    Router,
+    routing::{get, post},
+};
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_tasks).post(create_task))
+        .route("/{id}", get(get_task).put(update_task).delete(delete_task))
+        .route("/{id}/done", post(mark_done).delete(mark_undone))
+}
+
+/// A task as it stands today.
+#[derive(Serialize)]
+struct TaskView {
+    #[serde(flatten)]
+    task: Task,
+    done: bool,
+    /// Current occurrence of a recurring task, otherwise its due date.
+    due: Option<NaiveDate>,
+}
+
+impl From<Task> for TaskView {
+    fn from(task: Task) -> Self {
+        let today = Local::now().date_naive();
+        TaskView {
+            done: task.is_done(today),
+            due: task.due_on(today),
+            task,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TasksQuery {
+    done: Option<bool>,
+    /// Only this user's tasks.
+    user_id: Option<i32>,
+}
+
+async fn list_tasks(Query(q): Query<TasksQuery>) -> ApiResult<Json<Vec<TaskView>>> {
+    let rows = db::run_blocking_db(tasks::list).await?;
+    Ok(Json(
+        rows.into_iter()
+            .map(TaskView::from)
+            .filter(|t| q.done.is_none_or(|done| t.done == done))
+            .filter(|t| q.user_id.is_none_or(|id| t.task.user_id == Some(id)))
+            .collect(),
+    ))
+}
+
+async fn create_task(Json(new): Json<NewTask>) -> ApiResult<(StatusCode, Json<TaskView>)> {
+    new.validate()?;
+    check_owner(new.user_id).await?;
+    let row = db::run_blocking_db(move |conn| tasks::create(conn, new)).await?;
+    Ok((StatusCode::CREATED, Json(row.into())))
+}
+
+async fn get_task(Path(id): Path<i32>) -> ApiResult<Json<TaskView>> {
+    db::run_blocking_db(move |conn| tasks::get(conn, id))
+        .await?
+        .map(|t| Json(t.into()))
+        .ok_or(ApiError::NotFound)
+}
+
+async fn update_task(
+    Path(id): Path<i32>,
+    Json(changes): Json<NewTask>,
+) -> ApiResult<Json<TaskView>> {
+    changes.validate()?;
+    check_owner(changes.user_id).await?;
+    db::run_blocking_db(move |conn| tasks::update(conn, id, changes))
+        .await?
+        .map(|t| Json(t.into()))
+        .ok_or(ApiError::NotFound)
+}
+
+async fn delete_task(Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    if !db::run_blocking_db(move |conn| tasks::delete(conn, id)).await? {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Tick a task off. A recurring task comes back at its next occurrence.
+async fn mark_done(Path(id): Path<i32>) -> ApiResult<Json<TaskView>> {
+    db::run_blocking_db(move |conn| tasks::set_done(conn, id, true))
+        .await?
+        .map(|t| Json(t.into()))
+        .ok_or(ApiError::NotFound)
+}
+
+async fn mark_undone(Path(id): Path<i32>) -> ApiResult<Json<TaskView>> {
+    db::run_blocking_db(move |conn| tasks::set_done(conn, id, false))
+        .await?
+        .map(|t| Json(t.into()))
+        .ok_or(ApiError::NotFound)
+}
@@ -0,0 +1,213 @@
+use crate::db;
+use crate::error::{ApiError, ApiResult};
+use crate::render::{self, Profile, preview};
+use crate::routes::extract::Json;
+use crate::state::AppState;
+use crate::templates::revisions::{self, DiffRow, Revision};
+use crate::templates::{self, Template, TemplateInput, draft};
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::{
+    Router,
+    routing::{get, post},
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_templates).post(create_template))
+        .route("/draft", post(draft_template))
+        .route(
+            "/{id}",
+            get(get_template)
+                .put(update_template)
+                .delete(delete_template),
+        )
+        .route("/{id}/revisions", get(list_revisions))
+        .route("/{id}/diff", get(diff_revisions))
+}
+
+async fn list_templates() -> ApiResult<Json<Vec<Template>>> {
+    let rows = db::run_blocking_db(templates::list).await?;
+    Ok(Json(rows))
+}
+
+async fn create_template(
+    Json(input): Json<TemplateInput>,
+) -> ApiResult<(StatusCode, Json<Template>)> {
+    input.validate()?;
+    let row = db::run_blocking_db(move |conn| {
+        if templates::get_by_name(conn, &input.name)?.is_some() {
+            return Ok(None);
+        }
+        templates::create(conn, input).map(Some)
+    })
+    .await?
+    .ok_or_else(|| ApiError::Conflict("a template with that name already exists".into()))?;
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+async fn get_template(Path(id): Path<i32>) -> ApiResult<Json<Template>> {
+    db::run_blocking_db(move |conn| templates::get(conn, id))
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+async fn update_template(
+    Path(id): Path<i32>,
+    Json(input): Json<TemplateInput>,
+) -> ApiResult<Json<Template>> {
+    input.validate()?;
+    let row = db::run_blocking_db(move |conn| {
+        if templates::get_by_name(conn, &input.name)?.is_some_and(|other| other.id != id) {
+            return Ok(Err(ApiError::Conflict(
+                "a template with that name already exists".into(),
+            )));
+        }
+        Ok(templates::update(conn, id, input)?.ok_or(ApiError::NotFound))
+    })
+    .await??;
+    Ok(Json(row))
+}
+
+async fn delete_template(Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    if !db::run_blocking_db(move |conn| templates::delete(conn, id)).await? {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct DraftRequest {
+    name: String,
+    /// An example payload, e.g. the first body an inbound hook received.
+    sample: Value,
+}
+
+#[derive(Serialize)]
+struct DraftResponse {
+    template: Template,
+    mapped: BTreeMap<&'static str, String>,
+}
+
+/// Save a starting-point template that lays out the sample's likely title, message,
+/// link and time fields.
+async fn draft_template(
+    Json(req): Json<DraftRequest>,
+) -> ApiResult<(StatusCode, Json<DraftResponse>)> {
+    let draft = draft::from_sample(&req.sample);
+    let input = TemplateInput {
+        name: req.name,
+        description: Some("Draft generated from a sample payload".into()),
+        payload: draft.payload,
+        header: Default::default(),
+        footer: Default::default(),
+    };
+    input.validate()?;
+    let template = db::run_blocking_db(move |conn| {
+        if templates::get_by_name(conn, &input.name)?.is_some() {
+            return Ok(None);
+        }
+        templates::create(conn, input).map(Some)
+    })
+    .await?
+    .ok_or_else(|| ApiError::Conflict("a template with that name already exists".into()))?;
+    Ok((
+        StatusCode::CREATED,
+        Json(DraftResponse {
+            template,
+            mapped: draft.mapped,
+        }),
+    ))
+}
+
+async fn list_revisions(Path(id): Path<i32>) -> ApiResult<Json<Vec<Revision>>> {
+    let rows = db::run_blocking_db(move |conn| {
+        if templates::get(conn, id)?.is_none() {
+            return Ok(None);
+        }
+        revisions::list(conn, id).map(Some)
+    })
+    .await?
+    .ok_or(ApiError::NotFound)?;
+    Ok(Json(rows))
+}
+
+#[derive(Deserialize)]
+struct DiffQuery {
+    /// Defaults to the revision before `to`.
+    from: Option<i32>,
+    /// Defaults to the current revision.
+    to: Option<i32>,
+    /// JSON object of variables both revisions are rendered with.
+    #[serde(default)]
+    context: Option<String>,
+}
+
+#[derive(Serialize)]
+struct DiffResponse {
+    from: RenderedRevision,
+    to: RenderedRevision,
+    rows: Vec<DiffRow>,
+}
+
+#[derive(Serialize)]
+struct RenderedRevision {
+    revision: i32,
+    text: String,
+    /// `data:image/png;base64,...` preview of the printout.
+    png: String,
+}
+
+/// Render two revisions of a template against the same sample context and compare the
+/// printed text line by line.
+async fn diff_revisions(
+    Path(id): Path<i32>,
+    Query(query): Query<DiffQuery>,
+) -> ApiResult<Json<DiffResponse>> {
+    let vars: Map<String, Value> = match query.context.as_deref() {
+        Some(context) => serde_json::from_str(context)
+            .map_err(|e| ApiError::BadRequest(format!("context must be a JSON object: {e}")))?,
+        None => Map::new(),
+    };
+    let (from, to) = db::run_blocking_db(move |conn| {
+        let Some(template) = templates::get(conn, id)? else {
+            return Ok(None);
+        };
+        let to = query.to.unwrap_or(template.revision);
+        let from = query.from.unwrap_or((to - 1).max(1));
+        let from = revisions::get(conn, id, from)?;
+        let to = revisions::get(conn, id, to)?;
+        Ok(from.zip(to))
+    })
+    .await?
+    .ok_or(ApiError::NotFound)?;
+
+    let rendered = tokio::task::spawn_blocking(move || {
+        let from = render_revision(&from, &vars)?;
+        let to = render_revision(&to, &vars)?;
+        Ok::<_, ApiError>((from, to))
+    })
+    .await??;
+    let (from, to) = rendered;
+    let rows = revisions::side_by_side(&from.text, &to.text);
+    Ok(Json(DiffResponse { from, to, rows }))
+}
+
+fn render_revision(revision: &Revision, vars: &Map<String, Value>) -> ApiResult<RenderedRevision> {
+    let payload = revision
+        .render(vars)
+        .map_err(|e| ApiError::BadRequest(format!("revision {}: {e}", revision.revision)))?;
+    let text = render::render(&payload.into_document(), &Profile::default())?.text;
+    let png = preview::text_to_png(&text)?;
+    Ok(RenderedRevision {
+        revision: revision.revision,
+        text,
+        png: format!("data:image/png;base64,{}", BASE64.encode(png)),
+    })
+}
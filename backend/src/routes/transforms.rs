@@ -0,0 +1,105 @@
+use crate::db;
+use crate::error::{ApiError, ApiResult, FieldError};
+use crate::routes::extract::Json;
+use crate::state::AppState;
+use crate::transforms::{self, Pipeline, PipelineInput, Step};
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::{
+    Router,
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_pipelines).post(create_pipeline))
+        .route("/preview", post(preview_transform))
+        .route(
+            "/{id}",
+            get(get_pipeline)
+                .put(update_pipeline)
+                .delete(delete_pipeline),
+        )
+}
+
+async fn list_pipelines() -> ApiResult<Json<Vec<Pipeline>>> {
+    let rows = db::run_blocking_db(transforms::list).await?;
+    Ok(Json(rows))
+}
+
+async fn create_pipeline(
+    Json(input): Json<PipelineInput>,
+) -> ApiResult<(StatusCode, Json<Pipeline>)> {
+    input.validate()?;
+    let row = db::run_blocking_db(move |conn| {
+        if transforms::get_by_source(conn, &input.source)?.is_some() {
+            return Ok(None);
+        }
+        transforms::create(conn, input).map(Some)
+    })
+    .await?
+    .ok_or_else(|| ApiError::Conflict("a pipeline for that source already exists".into()))?;
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+async fn get_pipeline(Path(id): Path<i32>) -> ApiResult<Json<Pipeline>> {
+    db::run_blocking_db(move |conn| transforms::get(conn, id))
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+async fn update_pipeline(
+    Path(id): Path<i32>,
+    Json(input): Json<PipelineInput>,
+) -> ApiResult<Json<Pipeline>> {
+    input.validate()?;
+    db::run_blocking_db(move |conn| transforms::update(conn, id, input))
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+async fn delete_pipeline(Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    if !db::run_blocking_db(move |conn| transforms::delete(conn, id)).await? {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Either explicit `steps` to try out, or a `source` whose configured pipeline is used.
+#[derive(Deserialize)]
+struct PreviewRequest {
+    text: String,
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    steps: Option<Vec<Step>>,
+}
+
+#[derive(Serialize)]
+struct PreviewResponse {
+    text: String,
+    steps: Vec<Step>,
+}
+
+async fn preview_transform(Json(req): Json<PreviewRequest>) -> ApiResult<Json<PreviewResponse>> {
+    let steps = match (req.steps, req.source) {
+        (Some(steps), _) => {
+            for (i, step) in steps.iter().enumerate() {
+                step.validate()
+                    .map_err(|e| FieldError::new(format!("steps.{i}"), e))?;
+            }
+            steps
+        }
+        (None, Some(source)) => {
+            db::run_blocking_db(move |conn| transforms::for_source(conn, &source)).await?
+        }
+        (None, None) => return Err(ApiError::BadRequest("give either steps or source".into())),
+    };
+    Ok(Json(PreviewResponse {
+        text: transforms::apply(&steps, &req.text),
+        steps,
+    }))
+}
@@ -0,0 +1,127 @@
+use crate::api_keys::{self, ApiKey, Scope};
+use crate::db;
+use crate::error::{ApiError, ApiResult};
+use crate::routes::extract::Json;
+use crate::state::AppState;
+use crate::users::{self, NewUser, User};
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::{
+    Router,
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_users).post(create_user))
+        .route("/login", post(login))
+        .route("/{id}", get(get_user).put(update_user).delete(delete_user))
+}
+
+#[derive(Deserialize)]
+struct Login {
+    name: String,
+    password: String,
+    /// What to call the key issued, such as the device signing in.
+    #[serde(default)]
+    key_name: Option<String>,
+}
+
+/// The key is shown once, when it is issued.
+#[derive(Serialize)]
+struct LoggedIn {
+    user: User,
+    api_key: ApiKey,
+    key: String,
+}
+
+/// Users' names are unique whatever their case.
+async fn validate(user: &NewUser, id: Option<i32>) -> ApiResult<()> {
+    user.validate()?;
+    let name = user.name.clone();
+    if db::run_blocking_db(move |conn| users::name_taken(conn, &name, id)).await? {
+        return Err(ApiError::Conflict(format!(
+            "there is already a user named {}",
+            user.name.trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Reject owners that aren't users, for the routes of what users can own.
+pub(crate) async fn check_owner(user_id: Option<i32>) -> ApiResult<()> {
+    let Some(user_id) = user_id else {
+        return Ok(());
+    };
+    if db::run_blocking_db(move |conn| users::get(conn, user_id))
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::BadRequest(format!(
+            "user {user_id} does not exist"
+        )));
+    }
+    Ok(())
+}
+
+async fn list_users() -> ApiResult<Json<Vec<User>>> {
+    let rows = db::run_blocking_db(users::list).await?;
+    Ok(Json(rows))
+}
+
+async fn create_user(Json(new): Json<NewUser>) -> ApiResult<(StatusCode, Json<User>)> {
+    validate(&new, None).await?;
+    let row = db::run_blocking_db(move |conn| users::create(conn, new)).await?;
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+async fn get_user(Path(id): Path<i32>) -> ApiResult<Json<User>> {
+    db::run_blocking_db(move |conn| users::get(conn, id))
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+async fn update_user(Path(id): Path<i32>, Json(changes): Json<NewUser>) -> ApiResult<Json<User>> {
+    validate(&changes, Some(id)).await?;
+    db::run_blocking_db(move |conn| users::update(conn, id, changes))
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+/// Remove a user and revoke their keys. Their tasks, habits and schedules stay, as
+/// everyone's. Users whose keys include the last admin key, or are the only keys
+/// left, can't be removed, as that would lock the API or open it up again.
+async fn delete_user(Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    match db::run_blocking_db(move |conn| users::delete(conn, id)).await? {
+        None => Err(ApiError::Conflict(
+            "this user holds the last admin key; issue another admin key first".into(),
+        )),
+        Some(false) => Err(ApiError::NotFound),
+        Some(true) => Ok(StatusCode::NO_CONTENT),
+    }
+}
+
+/// Sign in with a name and password, which issues a print key attributed to the
+/// user.
+async fn login(Json(login): Json<Login>) -> ApiResult<(StatusCode, Json<LoggedIn>)> {
+    let key_name = login
+        .key_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string);
+    let issued = db::run_blocking_db(move |conn| {
+        let Some(user) = users::authenticate(conn, &login.name, &login.password)? else {
+            return Ok(None);
+        };
+        let key_name = key_name.unwrap_or_else(|| user.name.clone());
+        let (api_key, key) = api_keys::create(conn, &key_name, Scope::Print, Some(user.id))?;
+        Ok(Some(LoggedIn { user, api_key, key }))
+    })
+    .await?
+    .ok_or(ApiError::Unauthorized)?;
+    Ok((StatusCode::CREATED, Json(issued)))
+}
@@ -0,0 +1,56 @@
+use crate::state::AppState;
+use axum::{Json, Router, routing::get};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Cargo features the server was built with.
+const FEATURES: &[(&str, bool)] = &[
+    ("linux-udev", cfg!(feature = "linux-udev")),
+    ("usb", cfg!(feature = "usb")),
+    ("serial", cfg!(feature = "serial")),
+    ("bluetooth", cfg!(feature = "bluetooth")),
+    ("bundled-sqlite", cfg!(feature = "bundled-sqlite")),
+    ("minimal", cfg!(feature = "minimal")),
+    ("otel", cfg!(feature = "otel")),
+    ("grpc", cfg!(feature = "grpc")),
+    ("web-ui", cfg!(feature = "web-ui")),
+];
+
+#[derive(Serialize)]
+struct VersionResponse {
+    version: &'static str,
+    /// Short hash of the commit built, `unknown` when built outside a git checkout.
+    commit: &'static str,
+    built_at: Option<DateTime<Utc>>,
+    features: Vec<&'static str>,
+    /// Target triple the server was compiled for.
+    target: &'static str,
+    os: &'static str,
+    arch: &'static str,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/", get(get_version))
+}
+
+/// What's running, to quote in bug reports and for clients to check what the server
+/// supports.
+async fn get_version() -> Json<VersionResponse> {
+    let built_at = env!("DAYROLL_BUILT_AT")
+        .parse()
+        .ok()
+        .and_then(|secs| DateTime::from_timestamp(secs, 0));
+    Json(VersionResponse {
+        version: env!("CARGO_PKG_VERSION"),
+        commit: env!("DAYROLL_GIT_COMMIT"),
+        built_at,
+        features: FEATURES
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| *name)
+            .collect(),
+        target: env!("DAYROLL_TARGET"),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+    })
+}
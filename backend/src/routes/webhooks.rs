@@ -0,0 +1,73 @@
+use crate::db;
+use crate::error::{ApiError, ApiResult};
+use crate::routes::extract::Json;
+use crate::routes::pagination::{ListQuery, Page};
+use crate::state::AppState;
+use crate::webhooks::{self, Attempt, NewWebhook, Webhook};
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::{Router, routing::get};
+use serde::Serialize;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_webhooks).post(create_webhook))
+        .route("/{id}", get(get_webhook).delete(delete_webhook))
+        .route("/{id}/deliveries", get(list_deliveries))
+}
+
+/// The secret is shown once, when the webhook is created.
+#[derive(Serialize)]
+struct CreatedWebhook {
+    #[serde(flatten)]
+    webhook: Webhook,
+    secret: String,
+}
+
+async fn list_webhooks() -> ApiResult<Json<Vec<Webhook>>> {
+    let rows = db::run_blocking_db(webhooks::list).await?;
+    Ok(Json(rows))
+}
+
+async fn create_webhook(
+    Json(new): Json<NewWebhook>,
+) -> ApiResult<(StatusCode, Json<CreatedWebhook>)> {
+    new.validate()?;
+    let webhook = db::run_blocking_db(move |conn| webhooks::create(conn, new)).await?;
+    let secret = webhook.secret.clone();
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedWebhook { webhook, secret }),
+    ))
+}
+
+async fn get_webhook(Path(id): Path<i32>) -> ApiResult<Json<Webhook>> {
+    db::run_blocking_db(move |conn| webhooks::get(conn, id))
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound)
+}
+
+async fn delete_webhook(Path(id): Path<i32>) -> ApiResult<StatusCode> {
+    if !db::run_blocking_db(move |conn| webhooks::delete(conn, id)).await? {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The webhook's delivery attempts, newest first; the last 500 are kept.
+async fn list_deliveries(
+    Path(id): Path<i32>,
+    Query(list): Query<ListQuery>,
+) -> ApiResult<Json<Page<Attempt>>> {
+    let (offset, limit) = (list.offset()?, list.limit());
+    let page = db::run_blocking_db(move |conn| {
+        if webhooks::get(conn, id)?.is_none() {
+            return Ok(None);
+        }
+        webhooks::attempts(conn, id, offset, limit).map(Some)
+    })
+    .await?;
+    let (rows, total) = page.ok_or(ApiError::NotFound)?;
+    Ok(Json(Page::new(rows, total, offset)))
+}
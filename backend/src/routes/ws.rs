@@ -0,0 +1,140 @@
+use crate::error::{ApiError, ApiResult, FieldError};
+use crate::events::TOPICS;
+use crate::state::AppState;
+use axum::extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade, close_code};
+use axum::extract::{Query, State};
+use axum::response::Response;
+use axum::{Router, routing::get};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::BTreeSet;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Instant;
+
+/// How often an idle connection is pinged, to keep proxies from closing it.
+const PING: Duration = Duration::from_secs(30);
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/", get(upgrade))
+}
+
+#[derive(Deserialize)]
+struct WsQuery {
+    /// Comma-separated topics to start with; all of them when left out.
+    topics: Option<String>,
+}
+
+/// What a client sends to change which events it gets.
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum Command {
+    Subscribe { topics: Vec<String> },
+    Unsubscribe { topics: Vec<String> },
+}
+
+/// Topics that aren't in [`TOPICS`].
+fn unknown<'a>(topics: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    topics.into_iter().filter(|t| !TOPICS.contains(t)).collect()
+}
+
+/// Live server events over a WebSocket, each a JSON text message like those of
+/// `/events`, for the topics the connection is subscribed to. The server closes it
+/// with `1001 Going Away` when it shuts down.
+async fn upgrade(
+    State(state): State<AppState>,
+    Query(q): Query<WsQuery>,
+    ws: WebSocketUpgrade,
+) -> ApiResult<Response> {
+    let topics: BTreeSet<String> = match &q.topics {
+        Some(topics) => {
+            let topics: Vec<&str> = topics.split(',').filter(|t| !t.is_empty()).collect();
+            let unknown = unknown(topics.iter().copied());
+            if !unknown.is_empty() {
+                return Err(ApiError::Invalid(vec![FieldError {
+                    field: "topics".into(),
+                    message: format!("{} unknown; use {}", unknown.join(", "), TOPICS.join(", ")),
+                }]));
+            }
+            topics.into_iter().map(String::from).collect()
+        }
+        None => TOPICS.iter().map(ToString::to_string).collect(),
+    };
+    Ok(ws.on_upgrade(move |socket| serve(socket, state, topics)))
+}
+
+async fn serve(mut socket: WebSocket, state: AppState, mut topics: BTreeSet<String>) {
+    let mut events = state.events.subscribe();
+    let mut ping = tokio::time::interval_at(Instant::now() + PING, PING);
+    loop {
+        let reply = tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let Ok(data) = serde_json::to_value(&event) else {
+                        continue;
+                    };
+                    if !data["type"].as_str().is_some_and(|t| topics.contains(t)) {
+                        continue;
+                    }
+                    data
+                }
+                // The client is told how many it missed, and carries on from here.
+                Err(RecvError::Lagged(missed)) => json!({ "type": "lagged", "missed": missed }),
+                Err(RecvError::Closed) => return,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => command(&text, &mut topics),
+                Some(Ok(Message::Close(_)) | Err(_)) | None => return,
+                Some(Ok(_)) => continue,
+            },
+            _ = ping.tick() => {
+                if socket.send(Message::Ping(Default::default())).await.is_err() {
+                    return;
+                }
+                continue;
+            }
+            _ = state.shutdown.wait() => {
+                let close = CloseFrame {
+                    code: close_code::AWAY,
+                    reason: "shutting down".into(),
+                };
+                let _ = socket.send(Message::Close(Some(close))).await;
+                return;
+            }
+        };
+        if socket
+            .send(Message::Text(reply.to_string().into()))
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+}
+
+/// Apply a client's [`Command`], answering with the topics now subscribed to or
+/// what was wrong with it.
+fn command(text: &str, topics: &mut BTreeSet<String>) -> Value {
+    let command = match serde_json::from_str::<Command>(text) {
+        Ok(command) => command,
+        Err(err) => return json!({ "type": "error", "error": err.to_string() }),
+    };
+    let (Command::Subscribe { topics: changed } | Command::Unsubscribe { topics: changed }) =
+        &command;
+    let unknown = unknown(changed.iter().map(String::as_str));
+    if !unknown.is_empty() {
+        return json!({
+            "type": "error",
+            "error": format!("{} unknown; use {}", unknown.join(", "), TOPICS.join(", ")),
+        });
+    }
+    match command {
+        Command::Subscribe { topics: changed } => topics.extend(changed),
+        Command::Unsubscribe { topics: changed } => {
+            for topic in &changed {
+                topics.remove(topic);
+            }
+        }
+    }
+    json!({ "type": "subscribed", "topics": topics })
+}
@@ -0,0 +1,144 @@
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinSet;
+use tracing::warn;
+
+use crate::compose::runner;
+use crate::events::{ConfigId, ConfigScope, Event, EventBus};
+use crate::integrations::{self, Context};
+use crate::queue::QueueManager;
+use crate::render::theme::Theme;
+use crate::{db, schedules, settings};
+
+const TICK: Duration = Duration::from_secs(20);
+
+/// The day a schedule last fired and the time it was set to then.
+type Fired = HashMap<i32, (NaiveDate, String)>;
+
+/// When each integration instance was last refreshed, successfully or not.
+type Attempted = HashMap<String, NaiveDateTime>;
+
+/// Fire enabled schedules once a day at their configured time, in the settings'
+/// zone, and refresh integration instances' data on their own schedules. Schedule,
+/// settings and instance changes are picked up straight away rather than on the
+/// next tick.
+pub fn spawn(queue: Arc<QueueManager>, events: EventBus, theme: Theme) {
+    let mut instance_changes = events.subscribe();
+    tokio::spawn(async move {
+        let mut attempted = Attempted::new();
+        let mut interval = tokio::time::interval(TICK);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                event = instance_changes.recv() => match event {
+                    // Fetched again with its new settings rather than at its next refresh.
+                    Ok(Event::ConfigChanged {
+                        scope: ConfigScope::Integration,
+                        id: ConfigId::InstanceId(id),
+                    }) => {
+                        attempted.remove(&id);
+                    }
+                    Err(RecvError::Closed) => {
+                        interval.tick().await;
+                    }
+                    _ => continue,
+                },
+            }
+            if let Err(err) = refresh(&mut attempted).await {
+                warn!("integration refresh failed: {err:#}");
+            }
+        }
+    });
+    tokio::spawn(async move {
+        let mut fired = Fired::new();
+        let mut interval = tokio::time::interval(TICK);
+        let mut rx = events.subscribe();
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                event = rx.recv() => match event {
+                    Ok(Event::ConfigChanged {
+                        scope: ConfigScope::Schedule | ConfigScope::Settings,
+                        ..
+                    }) => {}
+                    // A missed event may have been a schedule change; check anyway.
+                    Err(RecvError::Lagged(_)) => {}
+                    // Without a bus, fall back to ticking on the interval alone.
+                    Err(RecvError::Closed) => {
+                        interval.tick().await;
+                    }
+                    Ok(_) => continue,
+                },
+            }
+            if let Err(err) = tick(&queue, &mut fired, theme).await {
+                warn!("scheduler tick failed: {err:#}");
+            }
+        }
+    });
+}
+
+async fn tick(queue: &Arc<QueueManager>, fired: &mut Fired, theme: Theme) -> Result<()> {
+    let (settings, schedules) =
+        db::run_blocking_db(|conn| Ok((settings::get(conn)?, schedules::list(conn)?))).await?;
+    let now = settings.now();
+    let today = now.date();
+    let minute = now.format("%H:%M").to_string();
+
+    // A schedule moved to a later time after firing fires again at the new time.
+    let due = schedules
+        .into_iter()
+        .filter(|s| {
+            s.enabled
+                && s.time_of_day == minute
+                && fired.get(&s.id) != Some(&(today, s.time_of_day.clone()))
+        })
+        .collect::<Vec<_>>();
+
+    for schedule in due {
+        fired.insert(schedule.id, (today, schedule.time_of_day.clone()));
+        let queue = queue.clone();
+        tokio::spawn(async move {
+            let id = schedule.id;
+            if let Err(err) = runner::run_schedule(&queue, schedule, theme).await {
+                warn!("schedule {id} could not be recorded: {err:#}");
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Fetch the data of every instance whose refresh is due, and wait for them all so
+/// a slow service never has two fetches in flight. A failed fetch is retried at
+/// the next refresh rather than on every tick.
+async fn refresh(attempted: &mut Attempted) -> Result<()> {
+    let now = Utc::now().naive_utc();
+    let (settings, instances) =
+        db::run_blocking_db(|conn| Ok((settings::get(conn)?, integrations::list(conn)?))).await?;
+    let due = instances
+        .into_iter()
+        .filter(|i| i.refresh_due(now, attempted.get(&i.instance_id).copied()))
+        .collect::<Vec<_>>();
+
+    let ctx = Context {
+        date: settings.now().date(),
+    };
+    let mut refreshes = JoinSet::new();
+    for instance in due {
+        attempted.insert(instance.instance_id.clone(), now);
+        let ctx = ctx.clone();
+        refreshes.spawn(async move {
+            if let Err(err) = instance.fetch_now(&ctx).await {
+                warn!(
+                    "integration instance {} could not be refreshed: {err:#}",
+                    instance.instance_id
+                );
+            }
+        });
+    }
+    while refreshes.join_next().await.is_some() {}
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::compose::budget::{Budget, Truncation};
+use crate::error::FieldError;
+use crate::render::theme::Theme;
+use crate::schema::schedules;
+
+pub mod previews;
+pub mod runs;
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize)]
+#[diesel(table_name = schedules)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Schedule {
+    pub id: i32,
+    pub name: String,
+    /// Local wall-clock time the schedule fires at, formatted `HH:MM`.
+    pub time_of_day: String,
+    pub enabled: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// Maximum printed length in lines. Combined with `budget_mm` the tighter limit wins.
+    pub budget_lines: Option<i32>,
+    pub budget_mm: Option<i32>,
+    /// How over-budget sections are cut down; see [`Truncation`].
+    pub truncation: String,
+    /// Grants unauthenticated read access to the latest preview image when set.
+    #[serde(skip)]
+    pub public_token: Option<String>,
+    /// Output target the composed printout is submitted to. Without one, runs only
+    /// refresh the stored preview.
+    pub printer_id: Option<i32>,
+    /// Layout of the printout; see [`Theme`]. Unset uses the global `DIGEST_THEME`.
+    pub theme: Option<String>,
+    /// Printed under the date, such as a greeting.
+    pub header: Option<String>,
+    /// Printed after the last section.
+    pub footer: Option<String>,
+    /// Whose printout it is; the household's when unset.
+    pub user_id: Option<i32>,
+}
+
+impl Schedule {
+    pub fn theme(&self) -> Option<Theme> {
+        self.theme.as_deref().and_then(|t| t.parse().ok())
+    }
+
+    pub fn budget(&self) -> Budget {
+        Budget {
+            max_lines: self.budget_lines.map(|l| l.max(0) as usize),
+            max_mm: self.budget_mm.map(|mm| mm.max(0) as u32),
+            truncation: self.truncation.parse().unwrap_or_default(),
+        }
+    }
+}
+
+/// Writable schedule fields, used both for creation and full (`PUT`) updates.
+#[derive(Debug, Deserialize, Insertable, AsChangeset)]
+#[diesel(table_name = schedules)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewSchedule {
+    pub name: String,
+    /// The settings' `digest_time` when left out.
+    #[serde(default)]
+    pub time_of_day: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub budget_lines: Option<i32>,
+    #[serde(default)]
+    pub budget_mm: Option<i32>,
+    #[serde(default = "default_truncation")]
+    pub truncation: String,
+    #[serde(default)]
+    pub printer_id: Option<i32>,
+    #[serde(default)]
+    pub theme: Option<String>,
+    #[serde(default)]
+    pub header: Option<String>,
+    #[serde(default)]
+    pub footer: Option<String>,
+    #[serde(default)]
+    pub user_id: Option<i32>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_truncation() -> String {
+    Truncation::default().as_str().into()
+}
+
+impl NewSchedule {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        if self.name.trim().is_empty() {
+            return Err(FieldError::new("name", "must not be empty"));
+        }
+        if chrono::NaiveTime::parse_from_str(&self.time_of_day, "%H:%M").is_err() {
+            return Err(FieldError::new("time_of_day", "must be formatted HH:MM"));
+        }
+        if self.budget_lines.is_some_and(|l| l <= 0) {
+            return Err(FieldError::new("budget_lines", "must be positive"));
+        }
+        if self.budget_mm.is_some_and(|mm| mm <= 0) {
+            return Err(FieldError::new("budget_mm", "must be positive"));
+        }
+        if self.truncation.parse::<Truncation>().is_err() {
+            return Err(FieldError::new(
+                "truncation",
+                "must be one of: drop, summarize, more",
+            ));
+        }
+        if self
+            .theme
+            .as_deref()
+            .is_some_and(|t| t.parse::<Theme>().is_err())
+        {
+            return Err(FieldError::new(
+                "theme",
+                "must be one of: compact, relaxed, boxed, retro",
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Schedule>> {
+    let rows = schedules::table
+        .order(schedules::id.asc())
+        .select(Schedule::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn get(conn: &mut SqliteConnection, id: i32) -> Result<Option<Schedule>> {
+    let row = schedules::table
+        .find(id)
+        .select(Schedule::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn create(conn: &mut SqliteConnection, new: NewSchedule) -> Result<Schedule> {
+    let row = diesel::insert_into(schedules::table)
+        .values(&new)
+        .returning(Schedule::as_returning())
+        .get_result(conn)?;
+    Ok(row)
+}
+
+pub fn update(
+    conn: &mut SqliteConnection,
+    id: i32,
+    changes: NewSchedule,
+) -> Result<Option<Schedule>> {
+    let row = diesel::update(schedules::table.find(id))
+        .set((&changes, schedules::updated_at.eq(Utc::now().naive_utc())))
+        .returning(Schedule::as_returning())
+        .get_result(conn)
+        .optional()?;
+    Ok(row)
+}
+
+/// Replace the schedule's public preview token, or revoke it with `None`.
+pub fn set_public_token(
+    conn: &mut SqliteConnection,
+    id: i32,
+    token: Option<String>,
+) -> Result<bool> {
+    let updated = diesel::update(schedules::table.find(id))
+        .set(schedules::public_token.eq(token))
+        .execute(conn)?;
+    Ok(updated > 0)
+}
@@ -0,0 +1,63 @@
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+
+use crate::db;
+use crate::document::Document;
+use crate::render::{self, Profile};
+use crate::schema::{schedule_previews, schedules};
+
+/// Latest composed printout of a schedule, kept for digital mirrors.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = schedule_previews)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct SchedulePreview {
+    pub schedule_id: i32,
+    pub png: Vec<u8>,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Draw a composed printout and store it, with its plain text, as the schedule's
+/// latest preview.
+pub async fn store(schedule_id: i32, text: String, document: &Document) -> Result<()> {
+    let document = document.clone();
+    let png =
+        tokio::task::spawn_blocking(move || render::render_png(&document, &Profile::default()))
+            .await??;
+    db::run_blocking_db(move |conn| {
+        let now = Utc::now().naive_utc();
+        diesel::insert_into(schedule_previews::table)
+            .values((
+                schedule_previews::schedule_id.eq(schedule_id),
+                schedule_previews::text.eq(&text),
+                schedule_previews::png.eq(&png),
+                schedule_previews::updated_at.eq(now),
+            ))
+            .on_conflict(schedule_previews::schedule_id)
+            .do_update()
+            .set((
+                schedule_previews::text.eq(&text),
+                schedule_previews::png.eq(&png),
+                schedule_previews::updated_at.eq(now),
+            ))
+            .execute(conn)?;
+        Ok(())
+    })
+    .await
+}
+
+/// Latest preview for a schedule, only if `token` matches its public token.
+pub fn latest_public(
+    conn: &mut SqliteConnection,
+    schedule_id: i32,
+    token: &str,
+) -> Result<Option<SchedulePreview>> {
+    let row = schedule_previews::table
+        .inner_join(schedules::table)
+        .filter(schedule_previews::schedule_id.eq(schedule_id))
+        .filter(schedules::public_token.eq(token))
+        .select(SchedulePreview::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
@@ -0,0 +1,213 @@
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::db;
+use crate::schema::{schedule_run_sections, schedule_runs};
+
+/// Terminal (or in-flight) state of a scheduled run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    Running,
+    Success,
+    /// The job was printed but at least one section failed.
+    Partial,
+    Failed,
+}
+
+impl RunOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunOutcome::Running => "running",
+            RunOutcome::Success => "success",
+            RunOutcome::Partial => "partial",
+            RunOutcome::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize)]
+#[diesel(table_name = schedule_runs)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ScheduleRun {
+    pub id: i32,
+    pub schedule_id: i32,
+    pub started_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+    pub outcome: String,
+    pub bytes_printed: i32,
+    pub error: Option<String>,
+}
+
+impl ScheduleRun {
+    pub fn duration_ms(&self) -> Option<i64> {
+        self.finished_at
+            .map(|end| (end - self.started_at).num_milliseconds())
+    }
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Serialize)]
+#[diesel(table_name = schedule_run_sections)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct RunSection {
+    pub position: i32,
+    pub integration: String,
+    pub fetch_ms: i32,
+    pub render_ms: i32,
+    pub bytes: i32,
+    pub outcome: String,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = schedule_run_sections)]
+struct NewRunSection {
+    run_id: i32,
+    position: i32,
+    integration: String,
+    fetch_ms: i32,
+    render_ms: i32,
+    bytes: i32,
+    outcome: String,
+    error: Option<String>,
+}
+
+/// Timing for one integration's contribution to a run.
+#[derive(Debug, Clone)]
+pub struct SectionTiming {
+    pub integration: String,
+    pub fetch: Duration,
+    pub render: Duration,
+    pub bytes: usize,
+    pub error: Option<String>,
+}
+
+/// Collects timings while a schedule executes and persists them when the run finishes.
+///
+/// The run row is inserted up front so an in-progress (or crashed) run still shows
+/// up in the history as `running`.
+pub struct RunRecorder {
+    run_id: i32,
+    sections: Vec<SectionTiming>,
+}
+
+impl RunRecorder {
+    pub async fn start(schedule_id: i32) -> Result<Self> {
+        let run_id = db::run_blocking_db(move |conn| {
+            let id = diesel::insert_into(schedule_runs::table)
+                .values((
+                    schedule_runs::schedule_id.eq(schedule_id),
+                    schedule_runs::started_at.eq(Utc::now().naive_utc()),
+                    schedule_runs::outcome.eq(RunOutcome::Running.as_str()),
+                ))
+                .returning(schedule_runs::id)
+                .get_result(conn)?;
+            Ok(id)
+        })
+        .await?;
+
+        Ok(Self {
+            run_id,
+            sections: Vec::new(),
+        })
+    }
+
+    pub fn run_id(&self) -> i32 {
+        self.run_id
+    }
+
+    pub fn record_section(&mut self, timing: SectionTiming) {
+        self.sections.push(timing);
+    }
+
+    pub fn failed_sections(&self) -> usize {
+        self.sections.iter().filter(|s| s.error.is_some()).count()
+    }
+
+    pub async fn finish(
+        self,
+        outcome: RunOutcome,
+        bytes_printed: usize,
+        error: Option<String>,
+    ) -> Result<()> {
+        let run_id = self.run_id;
+        let sections: Vec<NewRunSection> = self
+            .sections
+            .into_iter()
+            .enumerate()
+            .map(|(i, s)| NewRunSection {
+                run_id,
+                position: i as i32,
+                outcome: if s.error.is_some() { "failed" } else { "ok" }.into(),
+                integration: s.integration,
+                fetch_ms: millis(s.fetch),
+                render_ms: millis(s.render),
+                bytes: s.bytes as i32,
+                error: s.error,
+            })
+            .collect();
+
+        db::run_blocking_db(move |conn| {
+            conn.transaction(|conn| {
+                diesel::insert_into(schedule_run_sections::table)
+                    .values(&sections)
+                    .execute(conn)?;
+                diesel::update(schedule_runs::table.find(run_id))
+                    .set((
+                        schedule_runs::finished_at.eq(Some(Utc::now().naive_utc())),
+                        schedule_runs::outcome.eq(outcome.as_str()),
+                        schedule_runs::bytes_printed.eq(bytes_printed as i32),
+                        schedule_runs::error.eq(error),
+                    ))
+                    .execute(conn)?;
+                Ok::<(), diesel::result::Error>(())
+            })?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+fn millis(d: Duration) -> i32 {
+    d.as_millis().min(i32::MAX as u128) as i32
+}
+
+pub fn list_for_schedule(
+    conn: &mut SqliteConnection,
+    schedule_id: i32,
+    limit: i64,
+) -> Result<Vec<ScheduleRun>> {
+    let rows = schedule_runs::table
+        .filter(schedule_runs::schedule_id.eq(schedule_id))
+        .order(schedule_runs::started_at.desc())
+        .limit(limit)
+        .select(ScheduleRun::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn get_with_sections(
+    conn: &mut SqliteConnection,
+    schedule_id: i32,
+    run_id: i32,
+) -> Result<Option<(ScheduleRun, Vec<RunSection>)>> {
+    let Some(run) = schedule_runs::table
+        .find(run_id)
+        .filter(schedule_runs::schedule_id.eq(schedule_id))
+        .select(ScheduleRun::as_select())
+        .first(conn)
+        .optional()?
+    else {
+        return Ok(None);
+    };
+
+    let sections = schedule_run_sections::table
+        .filter(schedule_run_sections::run_id.eq(run.id))
+        .order(schedule_run_sections::position.asc())
+        .select(RunSection::as_select())
+        .load(conn)?;
+
+    Ok(Some((run, sections)))
+}
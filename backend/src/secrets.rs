@@ -0,0 +1,100 @@
+//! Encryption of stored credentials: integration API keys and tokens, and the
+//! logins in push subscription URLs. With `SECRET_KEY` set they are kept in the
+//! database as `enc:v1:` followed by the base64 of a random nonce and their
+//! AES-256-GCM ciphertext, under a key derived from it, so a copy of the database
+//! file doesn't give them away. Without it they are stored as they are.
+
+use anyhow::{Result, anyhow, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use std::num::NonZeroU32;
+use std::sync::OnceLock;
+
+const PREFIX: &str = "enc:v1:";
+/// Fixed, since the key must come out the same on every start; the secret is
+/// what keeps it unguessable.
+const SALT: &[u8] = b"dayroll credential storage v1";
+const ITERATIONS: NonZeroU32 = NonZeroU32::new(100_000).unwrap();
+
+/// The key derived from `SECRET_KEY`, worked out on first use.
+fn key() -> Option<&'static LessSafeKey> {
+    static KEY: OnceLock<Option<LessSafeKey>> = OnceLock::new();
+    KEY.get_or_init(|| {
+        let secret = std::env::var("SECRET_KEY").ok().filter(|s| !s.is_empty())?;
+        let mut bytes = [0; 32];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            ITERATIONS,
+            SALT,
+            secret.as_bytes(),
+            &mut bytes,
+        );
+        let key = UnboundKey::new(&AES_256_GCM, &bytes).expect("AES-256 takes a 32-byte key");
+        Some(LessSafeKey::new(key))
+    })
+    .as_ref()
+}
+
+/// Whether `SECRET_KEY` is set, so that credentials are stored encrypted.
+pub fn enabled() -> bool {
+    key().is_some()
+}
+
+/// Whether `value` is a credential as [`seal`] stores it.
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+/// `plain` as it should be stored: encrypted when there's a key, as it is when
+/// not. Values that are already encrypted are kept.
+pub fn seal(plain: &str) -> Result<String> {
+    let Some(key) = key() else {
+        return Ok(plain.to_string());
+    };
+    if is_sealed(plain) {
+        return Ok(plain.to_string());
+    }
+    let mut nonce = [0; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| anyhow!("no randomness for a nonce"))?;
+    let mut sealed = plain.as_bytes().to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::empty(),
+        &mut sealed,
+    )
+    .map_err(|_| anyhow!("couldn't encrypt credential"))?;
+    let mut bytes = nonce.to_vec();
+    bytes.extend(sealed);
+    Ok(format!("{PREFIX}{}", BASE64.encode(bytes)))
+}
+
+/// A stored credential in plain text. Values [`seal`] left unencrypted are
+/// returned as they are.
+pub fn open(stored: &str) -> Result<String> {
+    let Some(encoded) = stored.strip_prefix(PREFIX) else {
+        return Ok(stored.to_string());
+    };
+    let Some(key) = key() else {
+        bail!("a credential is encrypted but SECRET_KEY is not set");
+    };
+    let mut bytes = BASE64
+        .decode(encoded)
+        .map_err(|_| anyhow!("encrypted credential is corrupt"))?;
+    if bytes.len() < NONCE_LEN {
+        bail!("encrypted credential is corrupt");
+    }
+    let mut sealed = bytes.split_off(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(&bytes)
+        .map_err(|_| anyhow!("encrypted credential is corrupt"))?;
+    let plain = key
+        .open_in_place(nonce, Aad::empty(), &mut sealed)
+        .map_err(|_| {
+            anyhow!("couldn't decrypt a credential; SECRET_KEY isn't the one it was stored with")
+        })?;
+    Ok(String::from_utf8(plain.to_vec())?)
+}
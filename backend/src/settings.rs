@@ -0,0 +1,137 @@
+//! Instance-wide settings, changed through the API without editing the environment
+//! or restarting. Each one left unset falls back to the environment or the built-in
+//! default.
+
+use anyhow::Result;
+use chrono::{Local, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::error::FieldError;
+use crate::render::theme::Theme;
+use crate::schema::settings;
+
+/// Id of the one row of settings.
+pub const ID: i32 = 1;
+
+#[derive(Debug, Clone, Queryable, Selectable, Serialize)]
+#[diesel(table_name = settings)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Settings {
+    /// IANA zone, such as `Europe/London`, that schedules fire and digests are dated
+    /// in; the server's own when unset.
+    pub timezone: Option<String>,
+    /// BCP 47 language tag, such as `en-GB`, for frontends to show dates and numbers
+    /// in.
+    pub locale: Option<String>,
+    /// Printer jobs go to when they don't name one.
+    pub default_printer_id: Option<i32>,
+    /// `HH:MM` that new schedules fire at when they don't give a `time_of_day`.
+    pub digest_time: Option<String>,
+    /// Layout of digests whose schedule has no theme of its own; see [`Theme`].
+    /// `DIGEST_THEME` when unset.
+    pub theme: Option<String>,
+    pub updated_at: NaiveDateTime,
+}
+
+impl Settings {
+    pub fn timezone(&self) -> Option<Tz> {
+        self.timezone.as_deref().and_then(|tz| tz.parse().ok())
+    }
+
+    pub fn theme(&self) -> Option<Theme> {
+        self.theme.as_deref().and_then(|t| t.parse().ok())
+    }
+
+    /// The wall-clock time now in the configured zone.
+    pub fn now(&self) -> NaiveDateTime {
+        match self.timezone() {
+            Some(tz) => Utc::now().with_timezone(&tz).naive_local(),
+            None => Local::now().naive_local(),
+        }
+    }
+}
+
+/// Writable settings, replaced all at once by `PUT`.
+#[derive(Debug, Deserialize, AsChangeset)]
+#[diesel(table_name = settings)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewSettings {
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub locale: Option<String>,
+    #[serde(default)]
+    pub default_printer_id: Option<i32>,
+    #[serde(default)]
+    pub digest_time: Option<String>,
+    #[serde(default)]
+    pub theme: Option<String>,
+}
+
+/// Whether `tag` is shaped like a BCP 47 language tag: a language of two or three
+/// letters, then subtags of up to eight letters or digits.
+fn is_language_tag(tag: &str) -> bool {
+    let mut subtags = tag.split('-');
+    let language = subtags.next().unwrap_or_default();
+    (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_alphabetic())
+        && subtags
+            .all(|s| (1..=8).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+impl NewSettings {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        if let Some(timezone) = &self.timezone
+            && timezone.parse::<Tz>().is_err()
+        {
+            return Err(FieldError::new(
+                "timezone",
+                format!("must be an IANA zone such as Europe/London, not {timezone}"),
+            ));
+        }
+        if let Some(locale) = &self.locale
+            && !is_language_tag(locale)
+        {
+            return Err(FieldError::new(
+                "locale",
+                format!("must be a language tag such as en-GB, not {locale}"),
+            ));
+        }
+        if self
+            .digest_time
+            .as_deref()
+            .is_some_and(|t| NaiveTime::parse_from_str(t, "%H:%M").is_err())
+        {
+            return Err(FieldError::new("digest_time", "must be formatted HH:MM"));
+        }
+        if self
+            .theme
+            .as_deref()
+            .is_some_and(|t| t.parse::<Theme>().is_err())
+        {
+            return Err(FieldError::new(
+                "theme",
+                "must be one of: compact, relaxed, boxed, retro",
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub fn get(conn: &mut SqliteConnection) -> Result<Settings> {
+    let row = settings::table
+        .find(ID)
+        .select(Settings::as_select())
+        .first(conn)?;
+    Ok(row)
+}
+
+pub fn update(conn: &mut SqliteConnection, changes: NewSettings) -> Result<Settings> {
+    let row = diesel::update(settings::table.find(ID))
+        .set((&changes, settings::updated_at.eq(Utc::now().naive_utc())))
+        .returning(Settings::as_returning())
+        .get_result(conn)?;
+    Ok(row)
+}
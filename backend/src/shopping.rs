@@ -0,0 +1,182 @@
+//! Shared shopping lists anyone in the household can add to, printed in the digest
+//! or on demand to take to the store.
+
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::document::{Block, Document};
+use crate::error::FieldError;
+use crate::schema::{shopping_items, shopping_lists};
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize)]
+#[diesel(table_name = shopping_lists)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ShoppingList {
+    pub id: i32,
+    /// What the integration's `lists` setting refers to the list by.
+    pub name: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Associations, Serialize)]
+#[diesel(table_name = shopping_items)]
+#[diesel(belongs_to(ShoppingList, foreign_key = list_id))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Item {
+    pub id: i32,
+    pub list_id: i32,
+    pub name: String,
+    /// How much to buy, as written, such as `2` or `500 g`.
+    pub quantity: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl Item {
+    /// The item as printed: `Milk` or `Milk (2)`.
+    pub fn label(&self) -> String {
+        match &self.quantity {
+            Some(quantity) => format!("{} ({quantity})", self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewShoppingList {
+    pub name: String,
+    /// Items to start the list with.
+    #[serde(default)]
+    pub items: Vec<NewItem>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewItem {
+    pub name: String,
+    #[serde(default)]
+    pub quantity: Option<String>,
+}
+
+impl NewShoppingList {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        if self.name.trim().is_empty() {
+            return Err(FieldError::new("name", "must not be empty"));
+        }
+        for (i, item) in self.items.iter().enumerate() {
+            item.validate()
+                .map_err(|e| e.within(&format!("items.{i}")))?;
+        }
+        Ok(())
+    }
+}
+
+impl NewItem {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        if self.name.trim().is_empty() {
+            return Err(FieldError::new("name", "must not be empty"));
+        }
+        Ok(())
+    }
+}
+
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<ShoppingList>> {
+    let rows = shopping_lists::table
+        .order(shopping_lists::name.asc())
+        .select(ShoppingList::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn get(conn: &mut SqliteConnection, id: i32) -> Result<Option<ShoppingList>> {
+    let row = shopping_lists::table
+        .find(id)
+        .select(ShoppingList::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn get_by_name(conn: &mut SqliteConnection, name: &str) -> Result<Option<ShoppingList>> {
+    let row = shopping_lists::table
+        .filter(shopping_lists::name.eq(name))
+        .select(ShoppingList::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+/// The list's items, in the order they were added.
+pub fn items_of(conn: &mut SqliteConnection, list: &ShoppingList) -> Result<Vec<Item>> {
+    let rows = Item::belonging_to(list)
+        .order(shopping_items::id.asc())
+        .select(Item::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn create(conn: &mut SqliteConnection, new: NewShoppingList) -> Result<ShoppingList> {
+    conn.transaction(|conn| {
+        let list = diesel::insert_into(shopping_lists::table)
+            .values(shopping_lists::name.eq(&new.name))
+            .returning(ShoppingList::as_returning())
+            .get_result(conn)?;
+        for item in new.items {
+            add(conn, list.id, item)?;
+        }
+        Ok(list)
+    })
+}
+
+pub fn add(conn: &mut SqliteConnection, list_id: i32, new: NewItem) -> Result<Item> {
+    let row = diesel::insert_into(shopping_items::table)
+        .values((
+            shopping_items::list_id.eq(list_id),
+            shopping_items::name.eq(new.name),
+            shopping_items::quantity.eq(new.quantity),
+        ))
+        .returning(Item::as_returning())
+        .get_result(conn)?;
+    Ok(row)
+}
+
+pub fn delete(conn: &mut SqliteConnection, id: i32) -> Result<bool> {
+    let deleted = diesel::delete(shopping_lists::table.find(id)).execute(conn)?;
+    Ok(deleted > 0)
+}
+
+pub fn remove(conn: &mut SqliteConnection, list_id: i32, item_id: i32) -> Result<bool> {
+    let deleted = diesel::delete(
+        shopping_items::table
+            .filter(shopping_items::list_id.eq(list_id))
+            .filter(shopping_items::id.eq(item_id)),
+    )
+    .execute(conn)?;
+    Ok(deleted > 0)
+}
+
+/// The list as a slip to take to the store: its name, then a box to tick for
+/// each item.
+pub fn document(list: &ShoppingList, items: &[Item]) -> Document {
+    let mut blocks = vec![Block::Heading {
+        text: list.name.clone(),
+        level: 1,
+        align: Default::default(),
+    }];
+    blocks.extend(items.iter().map(|item| Block::Glyph {
+        name: "checkbox".into(),
+        text: item.label(),
+    }));
+    if items.is_empty() {
+        blocks.push(Block::Text {
+            text: "Nothing to buy".into(),
+            spans: Vec::new(),
+            style: Default::default(),
+        });
+    }
+    blocks.push(Block::Cut { partial: false });
+    Document {
+        blocks,
+        ..Document::default()
+    }
+}
@@ -0,0 +1,54 @@
+//! Stopping on SIGTERM or Ctrl-C without cutting a receipt in half: new jobs are
+//! refused, live event streams are closed so the server can finish its requests,
+//! and the printing job gets a while to finish before the process exits.
+
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Tells long-lived connections that the server is stopping.
+#[derive(Clone)]
+pub struct Shutdown(Arc<watch::Sender<bool>>);
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self(Arc::new(watch::channel(false).0))
+    }
+}
+
+impl Shutdown {
+    pub fn begin(&self) {
+        self.0.send_replace(true);
+    }
+
+    /// Whether the server is stopping, now and as it changes.
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.0.subscribe()
+    }
+
+    /// Resolves once the server starts stopping, at once if it already has.
+    pub async fn wait(&self) {
+        let _ = self.subscribe().wait_for(|stopping| *stopping).await;
+    }
+}
+
+/// Resolves on Ctrl-C or, on Unix, SIGTERM, as sent by `systemctl stop`.
+pub async fn signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
@@ -1,12 +1,39 @@
 use crate::config::Config;
+use crate::events::EventBus;
+use crate::printers::monitor::LastSeen;
+use crate::push::manager::PushManager;
+use crate::queue::QueueManager;
+use crate::shutdown::Shutdown;
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct AppState {
     pub config: Config,
+    pub queue: Arc<QueueManager>,
+    pub events: EventBus,
+    pub push: Arc<PushManager>,
+    pub shutdown: Shutdown,
+    /// Kept up to date by [`crate::printers::monitor`].
+    pub printers_seen: LastSeen,
 }
 
 impl AppState {
     pub fn new(config: Config) -> Self {
-        Self { config }
+        let events = EventBus::new();
+        let queue = Arc::new(QueueManager::new(
+            config.max_inflight_prints,
+            config.limits,
+            config.queue_fairness,
+            events.clone(),
+        ));
+        let push = Arc::new(PushManager::new(queue.clone(), events.clone()));
+        Self {
+            config,
+            queue,
+            events,
+            push,
+            shutdown: Shutdown::default(),
+            printers_seen: LastSeen::default(),
+        }
     }
 }
@@ -0,0 +1,227 @@
+//! The household's own to-do list, for those who don't keep one elsewhere. Open
+//! tasks are printed as a checklist by the `tasks` integration.
+
+use anyhow::Result;
+use chrono::{Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::error::FieldError;
+use crate::schema::tasks;
+
+/// How often a task comes back once done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recurrence {
+    Daily,
+    /// Monday to Friday.
+    Weekdays,
+    Weekly,
+    /// On the due date's day of the month, or the month's last day if it is shorter.
+    Monthly,
+    Yearly,
+}
+
+impl Recurrence {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Recurrence::Daily => "daily",
+            Recurrence::Weekdays => "weekdays",
+            Recurrence::Weekly => "weekly",
+            Recurrence::Monthly => "monthly",
+            Recurrence::Yearly => "yearly",
+        }
+    }
+
+    /// The last occurrence on or before `date` of a task first due on `first`;
+    /// `first` itself while that is still ahead.
+    pub fn latest(&self, first: NaiveDate, date: NaiveDate) -> NaiveDate {
+        if date <= first {
+            return first;
+        }
+        let months = |step: u32| {
+            let elapsed = (date.year() - first.year()) as u32 * 12 + date.month() - first.month();
+            let mut n = elapsed / step;
+            loop {
+                let candidate = first + Months::new(n * step);
+                if candidate <= date || n == 0 {
+                    return candidate;
+                }
+                n -= 1;
+            }
+        };
+        match self {
+            Recurrence::Daily => date,
+            Recurrence::Weekdays => {
+                let mut day = date;
+                while matches!(day.weekday(), Weekday::Sat | Weekday::Sun) && day > first {
+                    day = day - Days::new(1);
+                }
+                day
+            }
+            Recurrence::Weekly => {
+                let weeks = (date - first).num_days() / 7;
+                first + Days::new(weeks as u64 * 7)
+            }
+            Recurrence::Monthly => months(1),
+            Recurrence::Yearly => months(12),
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "daily" => Ok(Recurrence::Daily),
+            "weekdays" => Ok(Recurrence::Weekdays),
+            "weekly" => Ok(Recurrence::Weekly),
+            "monthly" => Ok(Recurrence::Monthly),
+            "yearly" => Ok(Recurrence::Yearly),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize)]
+#[diesel(table_name = tasks)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Task {
+    pub id: i32,
+    pub title: String,
+    pub notes: Option<String>,
+    /// Day the task is due, or first due if it recurs.
+    pub due_date: Option<NaiveDate>,
+    /// See [`Recurrence`]. Recurring tasks always have a due date.
+    pub recurrence: Option<String>,
+    /// When the task was last ticked off. A recurring task is open again from its
+    /// next occurrence.
+    pub done_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// Whose task it is; everyone's when unset.
+    pub user_id: Option<i32>,
+}
+
+impl Task {
+    pub fn recurrence(&self) -> Option<Recurrence> {
+        self.recurrence.as_deref().and_then(|r| r.parse().ok())
+    }
+
+    /// When the task is due as of `date`: the latest occurrence of a recurring task.
+    pub fn due_on(&self, date: NaiveDate) -> Option<NaiveDate> {
+        let first = self.due_date?;
+        Some(match self.recurrence() {
+            Some(recurrence) => recurrence.latest(first, date),
+            None => first,
+        })
+    }
+
+    /// Whether the task is ticked off as of `date`.
+    pub fn is_done(&self, date: NaiveDate) -> bool {
+        let Some(done_at) = self.done_at else {
+            return false;
+        };
+        match (self.recurrence(), self.due_on(date)) {
+            (Some(_), Some(due)) => Local.from_utc_datetime(&done_at).date_naive() >= due,
+            _ => true,
+        }
+    }
+}
+
+/// Writable task fields, used both for creation and full (`PUT`) updates.
+#[derive(Debug, Deserialize, Insertable, AsChangeset)]
+#[diesel(table_name = tasks)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewTask {
+    pub title: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub due_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub recurrence: Option<String>,
+    #[serde(default)]
+    pub user_id: Option<i32>,
+}
+
+impl NewTask {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        if self.title.trim().is_empty() {
+            return Err(FieldError::new("title", "must not be empty"));
+        }
+        if let Some(recurrence) = &self.recurrence {
+            if recurrence.parse::<Recurrence>().is_err() {
+                return Err(FieldError::new(
+                    "recurrence",
+                    "must be one of: daily, weekdays, weekly, monthly, yearly",
+                ));
+            }
+            if self.due_date.is_none() {
+                return Err(FieldError::new(
+                    "due_date",
+                    "is required for recurring tasks",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Task>> {
+    let rows = tasks::table
+        .order((
+            tasks::due_date.is_null(),
+            tasks::due_date.asc(),
+            tasks::id.asc(),
+        ))
+        .select(Task::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn get(conn: &mut SqliteConnection, id: i32) -> Result<Option<Task>> {
+    let row = tasks::table
+        .find(id)
+        .select(Task::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn create(conn: &mut SqliteConnection, new: NewTask) -> Result<Task> {
+    let row = diesel::insert_into(tasks::table)
+        .values(&new)
+        .returning(Task::as_returning())
+        .get_result(conn)?;
+    Ok(row)
+}
+
+pub fn update(conn: &mut SqliteConnection, id: i32, changes: NewTask) -> Result<Option<Task>> {
+    let row = diesel::update(tasks::table.find(id))
+        .set((&changes, tasks::updated_at.eq(Utc::now().naive_utc())))
+        .returning(Task::as_returning())
+        .get_result(conn)
+        .optional()?;
+    Ok(row)
+}
+
+/// Tick the task off now, or clear its tick with `done = false`.
+pub fn set_done(conn: &mut SqliteConnection, id: i32, done: bool) -> Result<Option<Task>> {
+    let now = Utc::now().naive_utc();
+    let row = diesel::update(tasks::table.find(id))
+        .set((
+            tasks::done_at.eq(done.then_some(now)),
+            tasks::updated_at.eq(now),
+        ))
+        .returning(Task::as_returning())
+        .get_result(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn delete(conn: &mut SqliteConnection, id: i32) -> Result<bool> {
+    let deleted = diesel::delete(tasks::table.find(id)).execute(conn)?;
+    Ok(deleted > 0)
+}
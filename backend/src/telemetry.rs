@@ -0,0 +1,144 @@
+//! Logging, and the request ids that tie a request's log lines together with those
+//! of the jobs it queued, as they go through the queue and out to the printer.
+//! With the `otel` feature, spans are also exported over OTLP.
+
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use tower_http::request_id::RequestId;
+use tracing::{Span, field, info_span};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::prelude::*;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Flushes the spans still waiting to be exported when dropped.
+#[must_use]
+pub struct Guard {
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider.take()
+            && let Err(err) = provider.shutdown()
+        {
+            eprintln!("exporting the last spans failed: {err}");
+        }
+    }
+}
+
+/// Log to stderr, filtered by `RUST_LOG` (`info` when unset). Crates still using
+/// `log` are picked up too. With the `otel` feature and an OTLP endpoint set, the
+/// spans `RUST_LOG` lets through are exported as well; keep the returned guard until
+/// exiting so the last of them are sent.
+pub fn init() -> Guard {
+    let registry = tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
+        .with(tracing_subscriber::fmt::layer());
+
+    #[cfg(feature = "otel")]
+    {
+        use opentelemetry::trace::TracerProvider as _;
+
+        let provider = otel::provider();
+        let layer = provider
+            .as_ref()
+            .ok()
+            .and_then(Option::as_ref)
+            .map(|provider| {
+                tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+            });
+        registry.with(layer).init();
+        match provider {
+            Ok(provider) => Guard { provider },
+            Err(err) => {
+                tracing::warn!("not exporting traces: {err:#}");
+                Guard { provider: None }
+            }
+        }
+    }
+    #[cfg(not(feature = "otel"))]
+    {
+        registry.init();
+        Guard {}
+    }
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use anyhow::Result;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::SpanExporter;
+    use opentelemetry_sdk::Resource;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+
+    fn set(name: &str) -> bool {
+        std::env::var(name).is_ok_and(|v| !v.is_empty())
+    }
+
+    /// A provider sending spans over OTLP/HTTP to `OTEL_EXPORTER_OTLP_ENDPOINT` or
+    /// `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`, or none when neither is set or
+    /// `OTEL_SDK_DISABLED` is. Headers, timeout, sampler and resource attributes
+    /// come from the other standard `OTEL_*` variables.
+    pub fn provider() -> Result<Option<SdkTracerProvider>> {
+        let disabled =
+            std::env::var("OTEL_SDK_DISABLED").is_ok_and(|v| v.eq_ignore_ascii_case("true"));
+        if disabled
+            || !(set("OTEL_EXPORTER_OTLP_ENDPOINT") || set("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT"))
+        {
+            return Ok(None);
+        }
+        let exporter = SpanExporter::builder().with_http().build()?;
+        let mut resource = Resource::builder()
+            .with_attribute(KeyValue::new("service.version", env!("CARGO_PKG_VERSION")));
+        if !set("OTEL_SERVICE_NAME") {
+            resource = resource.with_service_name("dayroll");
+        }
+        Ok(Some(
+            SdkTracerProvider::builder()
+                .with_batch_exporter(exporter)
+                .with_resource(resource.build())
+                .build(),
+        ))
+    }
+}
+
+/// The id of the request being handled, outside of one `None`.
+pub fn request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// The span a request's log lines are recorded in. Only the path is recorded, as
+/// the query can carry an API key. The key used, and whose it is, are filled in
+/// once it's been checked.
+pub fn request_span(request: &Request) -> Span {
+    info_span!(
+        "request",
+        id = id_of(request),
+        method = %request.method(),
+        path = request.uri().path(),
+        key = field::Empty,
+        user = field::Empty,
+    )
+}
+
+/// Make the request's id available to [`request_id`] while it's handled.
+pub async fn scope_request_id(request: Request, next: Next) -> Response {
+    let id = id_of(&request).to_string();
+    REQUEST_ID.scope(id, next.run(request)).await
+}
+
+/// The id set on `request` by `SetRequestIdLayer`, either the client's own
+/// `X-Request-Id` or one made up for it.
+fn id_of(request: &Request) -> &str {
+    request
+        .extensions()
+        .get::<RequestId>()
+        .and_then(|id| id.header_value().to_str().ok())
+        .unwrap_or_default()
+}
@@ -0,0 +1,163 @@
+//! Heuristic first-draft templates for payloads nobody has written a layout for yet.
+
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+
+/// Deepest nesting searched for candidate fields.
+const MAX_DEPTH: usize = 4;
+/// Fields listed when none of the well-known names are present.
+const MAX_FALLBACK_FIELDS: usize = 8;
+/// Rule under the title; matches a 58 mm printer's 32 columns.
+const RULE: &str = "--------------------------------";
+
+/// Field names, most likely first, for each part of the slip.
+const TITLE_KEYS: &[&str] = &[
+    "title",
+    "subject",
+    "summary",
+    "heading",
+    "name",
+    "alertname",
+    "event",
+];
+const MESSAGE_KEYS: &[&str] = &[
+    "message",
+    "body",
+    "text",
+    "description",
+    "content",
+    "msg",
+    "details",
+];
+const URL_KEYS: &[&str] = &["url", "html_url", "web_url", "link", "permalink", "href"];
+const TIME_KEYS: &[&str] = &["timestamp", "time", "date", "created_at", "updated_at"];
+
+#[derive(Debug, Serialize)]
+pub struct Draft {
+    /// A job payload whose text references the sample's fields as `{{ path }}`.
+    pub payload: Value,
+    /// Which payload path was chosen for each slip role (`title`, `message`, ...).
+    pub mapped: BTreeMap<&'static str, String>,
+}
+
+/// Build a template payload for payloads shaped like `sample`.
+pub fn from_sample(sample: &Value) -> Draft {
+    let mut fields = Vec::new();
+    collect_scalars(sample, String::new(), 0, &mut fields);
+
+    let mut mapped = BTreeMap::new();
+    for (role, keys) in [
+        ("title", TITLE_KEYS),
+        ("message", MESSAGE_KEYS),
+        ("url", URL_KEYS),
+        ("time", TIME_KEYS),
+    ] {
+        let taken: Vec<&String> = mapped.values().collect();
+        if let Some(path) = best_match(&fields, keys, &taken) {
+            mapped.insert(role, path);
+        }
+    }
+
+    let mut lines = Vec::new();
+    if let Some(title) = mapped.get("title") {
+        lines.push(placeholder(title));
+        lines.push(RULE.to_string());
+    }
+    if let Some(message) = mapped.get("message") {
+        lines.push(placeholder(message));
+    }
+    if mapped.contains_key("url") || mapped.contains_key("time") {
+        lines.push(String::new());
+    }
+    for role in ["url", "time"] {
+        if let Some(path) = mapped.get(role) {
+            lines.push(placeholder(path));
+        }
+    }
+    if mapped.is_empty() {
+        // Nothing recognisable: list the first few fields so the user has something
+        // to rearrange.
+        for path in fields.iter().take(MAX_FALLBACK_FIELDS) {
+            lines.push(format!("{path}: {}", placeholder(path)));
+        }
+    }
+
+    Draft {
+        payload: json!({ "text": lines.join("\n"), "cut": true }),
+        mapped,
+    }
+}
+
+/// Dotted paths of every string or number in `value`, shallowest first.
+fn collect_scalars(value: &Value, prefix: String, depth: usize, out: &mut Vec<String>) {
+    match value {
+        Value::String(_) | Value::Number(_) if !prefix.is_empty() => out.push(prefix),
+        Value::Object(map) if depth < MAX_DEPTH => {
+            for (key, child) in map {
+                // Top-level names must be identifiers to be referenced at all.
+                if key.contains('.') || (depth == 0 && !is_identifier(key)) {
+                    continue;
+                }
+                collect_scalars(child, join(&prefix, key), depth + 1, out);
+            }
+        }
+        Value::Array(items) if depth < MAX_DEPTH => {
+            if let Some(first) = items.first() {
+                collect_scalars(first, join(&prefix, "0"), depth + 1, out);
+            }
+        }
+        _ => {}
+    }
+    if depth == 0 {
+        out.sort_by_key(|path| path.matches('.').count());
+    }
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// A `{{ }}` placeholder for a dotted path, subscripting keys that are not
+/// identifiers, e.g. `{{ user["display-name"] }}`.
+fn placeholder(path: &str) -> String {
+    let mut expr = String::new();
+    for (i, segment) in path.split('.').enumerate() {
+        if i == 0 || is_identifier(segment) || segment.bytes().all(|b| b.is_ascii_digit()) {
+            if i > 0 {
+                expr.push('.');
+            }
+            expr.push_str(segment);
+        } else {
+            expr.push_str(&format!("[{}]", Value::String(segment.into())));
+        }
+    }
+    format!("{{{{ {expr} }}}}")
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+/// The shallowest unused path whose last segment is the highest-ranked key.
+fn best_match(fields: &[String], keys: &[&str], taken: &[&String]) -> Option<String> {
+    keys.iter().find_map(|key| {
+        fields
+            .iter()
+            .filter(|path| !taken.contains(path))
+            .find(|path| {
+                path.rsplit('.')
+                    .next()
+                    .is_some_and(|last| last.eq_ignore_ascii_case(key))
+            })
+            .cloned()
+    })
+}
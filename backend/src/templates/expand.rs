@@ -0,0 +1,69 @@
+use chrono::Local;
+use serde_json::{Map, Value};
+use std::error::Error;
+use tera::{Context, Tera};
+
+/// Render every string of a JSON value as a Tera template: `{{ name }}` placeholders,
+/// filters such as `{{ date | date(format="%A, %B %e") }}`, and `{% if %}` and
+/// `{% for %}` blocks. Names may be dotted paths into nested objects and arrays, e.g.
+/// `{{ issue.labels.0.name }}`. `date` is today's date unless `vars` sets it.
+///
+/// Missing variables are an error rather than silently printing an empty field.
+pub fn expand_value(value: Value, vars: &Map<String, Value>) -> Result<Value, String> {
+    let mut context = Context::new();
+    context.insert("date", &Local::now().format("%Y-%m-%d").to_string());
+    for (name, value) in vars {
+        context.insert(name, value);
+    }
+    expand_with(value, &context)
+}
+
+/// Check that every string of a JSON value is a well-formed template, so mistakes
+/// surface when a template is saved rather than when it is first printed.
+pub fn check(value: &Value) -> Result<(), String> {
+    match value {
+        Value::String(s) if is_template(s) => Tera::default()
+            .add_raw_template("check", s)
+            .map_err(describe),
+        Value::Array(items) => items.iter().try_for_each(check),
+        Value::Object(map) => map.values().try_for_each(check),
+        _ => Ok(()),
+    }
+}
+
+fn expand_with(value: Value, context: &Context) -> Result<Value, String> {
+    Ok(match value {
+        Value::String(s) if is_template(&s) => {
+            Value::String(Tera::one_off(&s, context, false).map_err(describe)?)
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|v| expand_with(v, context))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| Ok((k, expand_with(v, context)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        other => other,
+    })
+}
+
+/// Plain strings are left alone, so stray braces elsewhere never trip the parser.
+fn is_template(s: &str) -> bool {
+    ["{{", "{%", "{#"].iter().any(|open| s.contains(open))
+}
+
+/// The innermost cause of a Tera error, which names the missing variable or the
+/// position of the syntax error; the outer layers only name Tera's scratch template.
+fn describe(err: tera::Error) -> String {
+    let mut cause: &dyn Error = &err;
+    while let Some(source) = cause.source() {
+        cause = source;
+    }
+    cause
+        .to_string()
+        .replace(" while rendering '__tera_one_off'", "")
+}
@@ -0,0 +1,193 @@
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use crate::document::Fragment;
+use crate::error::FieldError;
+use crate::jobs::JobPayload;
+use crate::schema::templates;
+
+pub mod draft;
+pub mod expand;
+pub mod revisions;
+
+/// A named, reusable job payload whose strings are Tera templates, e.g.
+/// `{{ var }}` placeholders; see [`expand::expand_value`].
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize)]
+#[diesel(table_name = templates)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Template {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(serialize_with = "json_text")]
+    pub payload: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// Bumped on every update; earlier payloads are kept in [`revisions`].
+    pub revision: i32,
+    /// JSON [`Fragment`] put before the payload; placeholders are filled like the
+    /// payload's.
+    #[serde(serialize_with = "json_text")]
+    pub header: String,
+    /// JSON [`Fragment`] put after the payload, before its cut.
+    #[serde(serialize_with = "json_text")]
+    pub footer: String,
+}
+
+pub(crate) fn json_text<S: Serializer>(text: &str, s: S) -> Result<S::Ok, S::Error> {
+    serde_json::from_str::<Value>(text)
+        .map_err(serde::ser::Error::custom)?
+        .serialize(s)
+}
+
+impl Template {
+    /// Substitute `vars` into the stored payload, framed by the template's header
+    /// and footer.
+    pub fn render(&self, vars: &Map<String, Value>) -> Result<JobPayload, String> {
+        let payload = render_payload(&self.payload, vars)?;
+        let header = render_fragment(&self.header, vars)?;
+        let footer = render_fragment(&self.footer, vars)?;
+        if header.0.is_empty() && footer.0.is_empty() {
+            return Ok(payload);
+        }
+        let mut document = payload.into_document();
+        document.frame(header, footer);
+        Ok(JobPayload::Document(document))
+    }
+}
+
+/// Substitute `vars` into a stored payload's JSON text.
+fn render_payload(payload: &str, vars: &Map<String, Value>) -> Result<JobPayload, String> {
+    let payload: Value = serde_json::from_str(payload).map_err(|e| e.to_string())?;
+    let expanded = expand::expand_value(payload, vars)?;
+    serde_json::from_value(expanded).map_err(|e| e.to_string())
+}
+
+fn render_fragment(fragment: &str, vars: &Map<String, Value>) -> Result<Fragment, String> {
+    let fragment: Value = serde_json::from_str(fragment).map_err(|e| e.to_string())?;
+    let expanded = expand::expand_value(fragment, vars)?;
+    serde_json::from_value(expanded).map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TemplateInput {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub payload: Value,
+    #[serde(default)]
+    pub header: Fragment,
+    #[serde(default)]
+    pub footer: Fragment,
+}
+
+#[derive(Debug, Insertable, AsChangeset)]
+#[diesel(table_name = templates)]
+#[diesel(treat_none_as_null = true)]
+struct TemplateRow {
+    name: String,
+    description: Option<String>,
+    payload: String,
+    header: String,
+    footer: String,
+}
+
+impl TemplateInput {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        if self.name.trim().is_empty() {
+            return Err(FieldError::new("name", "must not be empty"));
+        }
+        serde_json::from_value::<JobPayload>(self.payload.clone())
+            .map_err(|e| FieldError::new("payload", format!("is not a valid job: {e}")))?;
+        expand::check(&self.payload)
+            .map_err(|e| FieldError::new("payload", format!("is not a valid template: {e}")))?;
+        for (name, fragment) in [("header", &self.header), ("footer", &self.footer)] {
+            let value =
+                serde_json::to_value(fragment).map_err(|e| FieldError::new(name, e.to_string()))?;
+            expand::check(&value)
+                .map_err(|e| FieldError::new(name, format!("is not a valid template: {e}")))?;
+            fragment.validate().map_err(|e| e.within(name))?;
+        }
+        Ok(())
+    }
+
+    fn into_row(self) -> TemplateRow {
+        TemplateRow {
+            name: self.name,
+            description: self.description,
+            payload: self.payload.to_string(),
+            header: self.header.into(),
+            footer: self.footer.into(),
+        }
+    }
+}
+
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Template>> {
+    let rows = templates::table
+        .order(templates::name.asc())
+        .select(Template::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn get(conn: &mut SqliteConnection, id: i32) -> Result<Option<Template>> {
+    let row = templates::table
+        .find(id)
+        .select(Template::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn get_by_name(conn: &mut SqliteConnection, name: &str) -> Result<Option<Template>> {
+    let row = templates::table
+        .filter(templates::name.eq(name))
+        .select(Template::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn create(conn: &mut SqliteConnection, input: TemplateInput) -> Result<Template> {
+    conn.immediate_transaction(|conn| insert(conn, input))
+}
+
+/// [`create`] for callers already inside a transaction of their own.
+pub(crate) fn insert(conn: &mut SqliteConnection, input: TemplateInput) -> Result<Template> {
+    let row = diesel::insert_into(templates::table)
+        .values(input.into_row())
+        .returning(Template::as_returning())
+        .get_result(conn)?;
+    revisions::record(conn, &row)?;
+    Ok(row)
+}
+
+pub fn update(
+    conn: &mut SqliteConnection,
+    id: i32,
+    input: TemplateInput,
+) -> Result<Option<Template>> {
+    conn.immediate_transaction(|conn| {
+        let row = diesel::update(templates::table.find(id))
+            .set((
+                input.into_row(),
+                templates::revision.eq(templates::revision + 1),
+                templates::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .returning(Template::as_returning())
+            .get_result(conn)
+            .optional()?;
+        if let Some(row) = &row {
+            revisions::record(conn, row)?;
+        }
+        Ok(row)
+    })
+}
+
+pub fn delete(conn: &mut SqliteConnection, id: i32) -> Result<bool> {
+    let deleted = diesel::delete(templates::table.find(id)).execute(conn)?;
+    Ok(deleted > 0)
+}
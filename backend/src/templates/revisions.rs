@@ -0,0 +1,119 @@
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use similar::{ChangeTag, TextDiff};
+
+use super::{Template, json_text, render_payload};
+use crate::jobs::JobPayload;
+use crate::schema::template_revisions;
+
+/// A template's payload as it was at one revision.
+#[derive(Debug, Clone, Queryable, Selectable, Serialize)]
+#[diesel(table_name = template_revisions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Revision {
+    pub template_id: i32,
+    pub revision: i32,
+    #[serde(serialize_with = "json_text")]
+    pub payload: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl Revision {
+    pub fn render(&self, vars: &Map<String, Value>) -> Result<JobPayload, String> {
+        render_payload(&self.payload, vars)
+    }
+}
+
+/// Snapshot `template` at its current revision.
+pub(super) fn record(conn: &mut SqliteConnection, template: &Template) -> Result<()> {
+    diesel::insert_into(template_revisions::table)
+        .values((
+            template_revisions::template_id.eq(template.id),
+            template_revisions::revision.eq(template.revision),
+            template_revisions::payload.eq(&template.payload),
+        ))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Every revision of a template, newest first.
+pub fn list(conn: &mut SqliteConnection, template_id: i32) -> Result<Vec<Revision>> {
+    let rows = template_revisions::table
+        .filter(template_revisions::template_id.eq(template_id))
+        .order(template_revisions::revision.desc())
+        .select(Revision::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn get(
+    conn: &mut SqliteConnection,
+    template_id: i32,
+    revision: i32,
+) -> Result<Option<Revision>> {
+    let row = template_revisions::table
+        .filter(template_revisions::template_id.eq(template_id))
+        .filter(template_revisions::revision.eq(revision))
+        .select(Revision::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+/// One row of a side-by-side diff. `left` is absent for inserted lines and `right`
+/// for deleted ones.
+#[derive(Debug, Serialize)]
+pub struct DiffRow {
+    pub op: &'static str,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+/// Line diff of two rendered texts, paired up for side-by-side display: a run of
+/// deletions followed by insertions is shown as changed lines next to each other.
+pub fn side_by_side(old: &str, new: &str) -> Vec<DiffRow> {
+    let diff = TextDiff::from_lines(old, new);
+    let mut rows = Vec::new();
+    let mut deleted: Vec<String> = Vec::new();
+    let mut inserted: Vec<String> = Vec::new();
+
+    let flush = |rows: &mut Vec<DiffRow>, deleted: &mut Vec<String>, inserted: &mut Vec<String>| {
+        let paired = deleted.len().max(inserted.len());
+        let mut left = deleted.drain(..);
+        let mut right = inserted.drain(..);
+        for _ in 0..paired {
+            let (l, r) = (left.next(), right.next());
+            let op = match (&l, &r) {
+                (Some(_), Some(_)) => "change",
+                (Some(_), None) => "delete",
+                _ => "insert",
+            };
+            rows.push(DiffRow {
+                op,
+                left: l,
+                right: r,
+            });
+        }
+    };
+
+    for change in diff.iter_all_changes() {
+        let line = change.value().trim_end_matches('\n').to_string();
+        match change.tag() {
+            ChangeTag::Delete => deleted.push(line),
+            ChangeTag::Insert => inserted.push(line),
+            ChangeTag::Equal => {
+                flush(&mut rows, &mut deleted, &mut inserted);
+                rows.push(DiffRow {
+                    op: "equal",
+                    left: Some(line.clone()),
+                    right: Some(line),
+                });
+            }
+        }
+    }
+    flush(&mut rows, &mut deleted, &mut inserted);
+    rows
+}
@@ -0,0 +1,164 @@
+//! Serving HTTPS directly, for installs on a LAN without a reverse proxy in front,
+//! so API keys don't cross the network in the clear.
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::{ServerConfig, crypto};
+use tokio_rustls::server::TlsStream;
+use tracing::{debug, info, warn};
+
+use crate::config::Tls;
+
+/// Longest a client may take to finish the handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connections that have finished their handshake, for `axum::serve`.
+pub struct TlsListener {
+    incoming: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    local_addr: SocketAddr,
+}
+
+/// Listen on `addr`, handshaking each connection on its own task so a slow or
+/// broken client doesn't hold up the others.
+pub async fn bind(addr: &str, tls: &Tls) -> Result<TlsListener> {
+    let acceptor = TlsAcceptor::from(server_config(addr, tls)?);
+    let tcp = TcpListener::bind(addr).await?;
+    let local_addr = tcp.local_addr()?;
+    let (tx, incoming) = mpsc::channel(64);
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match tcp.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    // Usually out of file descriptors; give some a chance to close.
+                    warn!("tls: accepting a connection: {e}");
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            };
+            let (acceptor, tx) = (acceptor.clone(), tx.clone());
+            tokio::spawn(async move {
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => {
+                        let _ = tx.send((stream, peer)).await;
+                    }
+                    Ok(Err(e)) => debug!("tls: handshake with {peer} failed: {e}"),
+                    Err(_) => debug!("tls: handshake with {peer} timed out"),
+                }
+            });
+        }
+    });
+    Ok(TlsListener {
+        incoming,
+        local_addr,
+    })
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.incoming.recv().await {
+            Some(connection) => connection,
+            // The accepting task never stops, but wait rather than panic if it did.
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+fn server_config(addr: &str, tls: &Tls) -> Result<Arc<ServerConfig>> {
+    if tls.self_signed && !tls.cert_path.exists() && !tls.key_path.exists() {
+        generate(addr, tls)?;
+    }
+    let certs = CertificateDer::pem_file_iter(&tls.cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("reading certificates from {}", tls.cert_path.display()))?;
+    let key = PrivateKeyDer::from_pem_file(&tls.key_path)
+        .with_context(|| format!("reading a private key from {}", tls.key_path.display()))?;
+    if let Some(cert) = certs.first() {
+        let fingerprint: Vec<String> = Sha256::digest(cert)
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect();
+        info!(
+            "serving HTTPS with {}, SHA-256 fingerprint {}",
+            tls.cert_path.display(),
+            fingerprint.join(":")
+        );
+    }
+    let mut config =
+        ServerConfig::builder_with_provider(Arc::new(crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .context("the certificate and key don't match")?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    // HTTP/2 for gRPC clients, which offer nothing else. The server's order wins, so
+    // browsers stay on HTTP/1.1, which WebSockets need.
+    #[cfg(feature = "grpc")]
+    config.alpn_protocols.push(b"h2".to_vec());
+    Ok(Arc::new(config))
+}
+
+/// Write a self-signed certificate for `localhost`, this machine's host name as
+/// is and under `.local`, and the address listened on when it is a specific one.
+fn generate(addr: &str, tls: &Tls) -> Result<()> {
+    let mut names = vec!["localhost".to_string()];
+    if let Ok(host) = std::fs::read_to_string("/etc/hostname") {
+        let host = host.trim();
+        if !host.is_empty() {
+            names.push(host.to_string());
+            names.push(format!("{host}.local"));
+        }
+    }
+    if let Ok(ip) = addr.parse::<SocketAddr>().map(|a| a.ip())
+        && !ip.is_unspecified()
+    {
+        names.push(ip.to_string());
+    }
+    let rcgen::CertifiedKey { cert, key_pair } =
+        rcgen::generate_simple_self_signed(names.clone()).context("generating a certificate")?;
+    write(&tls.cert_path, &cert.pem(), false)?;
+    write(&tls.key_path, &key_pair.serialize_pem(), true)?;
+    info!(
+        "generated a self-signed certificate for {} at {}",
+        names.join(", "),
+        tls.cert_path.display()
+    );
+    Ok(())
+}
+
+fn write(path: &Path, contents: &str, private: bool) -> Result<()> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .with_context(|| format!("writing {}", path.display()))?;
+    Ok(())
+}
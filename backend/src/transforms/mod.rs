@@ -0,0 +1,231 @@
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::document::Document;
+use crate::error::FieldError;
+use crate::jobs::JobPayload;
+use crate::schema::transform_pipelines;
+
+mod steps;
+
+pub use steps::Step;
+
+/// Ordered text transforms applied to every job from a matching source before it
+/// is rendered.
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize)]
+#[diesel(table_name = transform_pipelines)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Pipeline {
+    pub id: i32,
+    /// Job source this applies to: an exact source such as `api`, or a prefix
+    /// ending in `*` such as `schedule:*`. A lone `*` matches everything.
+    pub source: String,
+    #[serde(serialize_with = "steps_json")]
+    pub steps: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+fn steps_json<S: Serializer>(text: &str, s: S) -> Result<S::Ok, S::Error> {
+    serde_json::from_str::<Vec<Step>>(text)
+        .map_err(serde::ser::Error::custom)?
+        .serialize(s)
+}
+
+impl Pipeline {
+    pub fn steps(&self) -> Result<Vec<Step>> {
+        Ok(serde_json::from_str(&self.steps)?)
+    }
+
+    fn matches(&self, source: &str) -> bool {
+        match self.source.strip_suffix('*') {
+            Some(prefix) => source.starts_with(prefix),
+            None => self.source == source,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PipelineInput {
+    pub source: String,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Insertable, AsChangeset)]
+#[diesel(table_name = transform_pipelines)]
+struct PipelineRow {
+    source: String,
+    steps: String,
+}
+
+impl PipelineInput {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        if self.source.trim().is_empty() {
+            return Err(FieldError::new("source", "must not be empty"));
+        }
+        if self
+            .source
+            .find('*')
+            .is_some_and(|i| i + 1 != self.source.len())
+        {
+            return Err(FieldError::new("source", "may only have '*' at the end"));
+        }
+        for (i, step) in self.steps.iter().enumerate() {
+            step.validate()
+                .map_err(|e| FieldError::new(format!("steps.{i}"), e))?;
+        }
+        Ok(())
+    }
+
+    fn into_row(self) -> Result<PipelineRow> {
+        Ok(PipelineRow {
+            source: self.source,
+            steps: serde_json::to_string(&self.steps)?,
+        })
+    }
+}
+
+/// Run `steps` over `text` in order.
+pub fn apply(steps: &[Step], text: &str) -> String {
+    steps
+        .iter()
+        .fold(text.to_string(), |text, step| step.apply(&text))
+}
+
+/// Steps for `source`: an exact match wins, then the longest matching prefix.
+pub fn for_source(conn: &mut SqliteConnection, source: &str) -> Result<Vec<Step>> {
+    let best = list(conn)?
+        .into_iter()
+        .filter(|p| p.matches(source))
+        .max_by_key(|p| (!p.source.ends_with('*'), p.source.len()));
+    match best {
+        Some(pipeline) => pipeline.steps(),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Turn a job payload into the document to render, applying the pipeline
+/// configured for `source` to its text.
+pub fn prepare(conn: &mut SqliteConnection, source: &str, payload: JobPayload) -> Result<Document> {
+    let steps = for_source(conn, source)?;
+    let mut document = payload.into_document();
+    if !steps.is_empty() {
+        document.map_text(|text| apply(&steps, text));
+    }
+    Ok(document)
+}
+
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Pipeline>> {
+    let rows = transform_pipelines::table
+        .order(transform_pipelines::source.asc())
+        .select(Pipeline::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn get(conn: &mut SqliteConnection, id: i32) -> Result<Option<Pipeline>> {
+    let row = transform_pipelines::table
+        .find(id)
+        .select(Pipeline::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn get_by_source(conn: &mut SqliteConnection, source: &str) -> Result<Option<Pipeline>> {
+    let row = transform_pipelines::table
+        .filter(transform_pipelines::source.eq(source))
+        .select(Pipeline::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn create(conn: &mut SqliteConnection, input: PipelineInput) -> Result<Pipeline> {
+    let row = diesel::insert_into(transform_pipelines::table)
+        .values(input.into_row()?)
+        .returning(Pipeline::as_returning())
+        .get_result(conn)?;
+    Ok(row)
+}
+
+pub fn update(
+    conn: &mut SqliteConnection,
+    id: i32,
+    input: PipelineInput,
+) -> Result<Option<Pipeline>> {
+    let row = diesel::update(transform_pipelines::table.find(id))
+        .set((
+            input.into_row()?,
+            transform_pipelines::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .returning(Pipeline::as_returning())
+        .get_result(conn)
+        .optional()?;
+    Ok(row)
+}
+
+pub fn delete(conn: &mut SqliteConnection, id: i32) -> Result<bool> {
+    let deleted = diesel::delete(transform_pipelines::table.find(id)).execute(conn)?;
+    Ok(deleted > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use serde_json::json;
+
+    fn steps(value: serde_json::Value) -> Vec<Step> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn steps_run_in_order() {
+        let cut_first = steps(json!([
+            { "op": "max_line_length", "chars": 8 },
+            { "op": "collapse_whitespace" }
+        ]));
+        assert_eq!(apply(&cut_first, "a    b    c"), "a b");
+        let collapse_first = steps(json!([
+            { "op": "collapse_whitespace" },
+            { "op": "max_line_length", "chars": 8 }
+        ]));
+        assert_eq!(apply(&collapse_first, "a    b    c"), "a b c");
+    }
+
+    #[test]
+    fn exact_sources_win_over_the_longest_prefix() {
+        let mut conn = db::test_connection();
+        for (source, chars) in [("*", 1), ("schedule:*", 2), ("schedule:morning", 3)] {
+            create(
+                &mut conn,
+                PipelineInput {
+                    source: source.into(),
+                    steps: vec![Step::MaxLineLength { chars }],
+                },
+            )
+            .unwrap();
+        }
+        let mut chars = |source| match for_source(&mut conn, source).unwrap()[..] {
+            [Step::MaxLineLength { chars }] => chars,
+            ref steps => panic!("unexpected steps {steps:?}"),
+        };
+        assert_eq!(chars("schedule:morning"), 3);
+        assert_eq!(chars("schedule:evening"), 2);
+        assert_eq!(chars("api"), 1);
+    }
+
+    #[test]
+    fn wildcards_only_end_a_source() {
+        let input = |source: &str| PipelineInput {
+            source: source.into(),
+            steps: Vec::new(),
+        };
+        assert!(input("schedule:*").validate().is_ok());
+        assert!(input("*:morning").validate().is_err());
+        assert!(input(" ").validate().is_err());
+    }
+}
@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+
+/// Words masked by [`Step::MaskProfanity`] in addition to any configured ones.
+const PROFANITY: &[&str] = &[
+    "arse", "ass", "asshole", "bastard", "bitch", "bollocks", "crap", "damn", "dick", "fuck",
+    "fucking", "piss", "shit", "shitty", "wanker",
+];
+
+/// One text transformation. Pipelines apply their steps in order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Step {
+    /// Uppercase the first line of every paragraph.
+    UppercaseHeaders,
+    /// Remove `http://`, `https://` and `www.` links.
+    StripUrls,
+    /// Squeeze runs of spaces, trim lines and drop repeated blank lines.
+    CollapseWhitespace,
+    /// Replace all but the first letter of offensive words with `*`.
+    MaskProfanity {
+        #[serde(default)]
+        words: Vec<String>,
+    },
+    /// Cut lines longer than `chars` characters.
+    MaxLineLength { chars: usize },
+}
+
+impl Step {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Step::MaxLineLength { chars: 0 } => Err("max_line_length needs chars > 0".into()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            Step::UppercaseHeaders => uppercase_headers(text),
+            Step::StripUrls => map_words(text, |word| (!is_url(word)).then(|| word.to_string())),
+            Step::CollapseWhitespace => collapse_whitespace(text),
+            Step::MaskProfanity { words } => map_words(text, |word| Some(mask(word, words))),
+            Step::MaxLineLength { chars } => {
+                map_lines(text, |line| line.chars().take(*chars).collect::<String>())
+            }
+        }
+    }
+}
+
+fn map_lines(text: &str, f: impl Fn(&str) -> String) -> String {
+    let mut out: Vec<String> = text.lines().map(f).collect();
+    if text.ends_with('\n') {
+        out.push(String::new());
+    }
+    out.join("\n")
+}
+
+/// Rewrite or drop the space-separated words of each line.
+fn map_words(text: &str, f: impl Fn(&str) -> Option<String>) -> String {
+    map_lines(text, |line| {
+        line.split(' ')
+            .filter_map(|word| {
+                if word.is_empty() {
+                    Some(String::new())
+                } else {
+                    f(word)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    })
+}
+
+fn uppercase_headers(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut paragraph_start = true;
+    for line in text.lines() {
+        let blank = line.trim().is_empty();
+        if paragraph_start && !blank {
+            out.push_str(&line.to_uppercase());
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+        paragraph_start = blank;
+    }
+    if !text.ends_with('\n') {
+        out.pop();
+    }
+    out
+}
+
+fn is_url(word: &str) -> bool {
+    let word = word.trim_start_matches(['(', '<', '"', '\'']);
+    ["http://", "https://", "www."]
+        .iter()
+        .any(|prefix| word.len() > prefix.len() && word.to_ascii_lowercase().starts_with(prefix))
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut blank_run = 0;
+    for line in text.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() {
+            blank_run += 1;
+            if blank_run > 1 {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    if !text.ends_with('\n') {
+        out.pop();
+    }
+    out
+}
+
+fn mask(word: &str, extra: &[String]) -> String {
+    let core = word.trim_matches(|c: char| !c.is_alphanumeric());
+    let lower = core.to_lowercase();
+    let offensive =
+        PROFANITY.contains(&lower.as_str()) || extra.iter().any(|w| w.eq_ignore_ascii_case(&lower));
+    if core.is_empty() || !offensive {
+        return word.to_string();
+    }
+    let mut masked = String::with_capacity(word.len());
+    let mut seen_first = false;
+    for c in word.chars() {
+        if c.is_alphanumeric() {
+            masked.push(if seen_first { '*' } else { c });
+            seen_first = true;
+        } else {
+            masked.push(c);
+        }
+    }
+    masked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_lines_of_paragraphs_are_uppercased() {
+        let text = "Weather\nsunny, 21°\n\nnews\nnothing new\n";
+        assert_eq!(
+            Step::UppercaseHeaders.apply(text),
+            "WEATHER\nsunny, 21°\n\nNEWS\nnothing new\n"
+        );
+    }
+
+    #[test]
+    fn urls_are_removed_with_their_word() {
+        let text = "see https://example.com now (www.example.com) or http://";
+        assert_eq!(Step::StripUrls.apply(text), "see now or http://");
+    }
+
+    #[test]
+    fn whitespace_is_squeezed() {
+        let text = "  two   words \n\n\n\n next\t line \n";
+        assert_eq!(
+            Step::CollapseWhitespace.apply(text),
+            "two words\n\nnext line\n"
+        );
+    }
+
+    #[test]
+    fn profanity_keeps_its_first_letter_and_punctuation() {
+        let step = Step::MaskProfanity {
+            words: vec!["Darn".into()],
+        };
+        assert_eq!(
+            step.apply("Shit, the class is darn late!"),
+            "S***, the class is d*** late!"
+        );
+    }
+
+    #[test]
+    fn long_lines_are_cut_by_character() {
+        let step = Step::MaxLineLength { chars: 3 };
+        assert_eq!(step.apply("héllo\nab\n"), "hél\nab\n");
+        assert!(Step::MaxLineLength { chars: 0 }.validate().is_err());
+    }
+}
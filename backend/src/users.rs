@@ -0,0 +1,208 @@
+//! The people sharing the install. Tasks, habits and schedules can each be one
+//! person's, so a shared printer can print everyone's tasks as sections of their
+//! own, and the API keys issued when someone signs in attribute what they do to
+//! them. Passwords are kept only as argon2 hashes.
+
+use anyhow::{Result, anyhow};
+use argon2::Argon2;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::api_keys;
+use crate::error::FieldError;
+use crate::schema::users;
+
+/// Shortest password accepted.
+const MIN_PASSWORD_LEN: usize = 8;
+
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize)]
+#[diesel(table_name = users)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct User {
+    pub id: i32,
+    /// Signed in with, and unique whatever its case.
+    pub name: String,
+    #[serde(skip)]
+    pub password_hash: String,
+    pub created_at: NaiveDateTime,
+}
+
+/// Writable user fields, used both for creation and full (`PUT`) updates.
+#[derive(Debug, Deserialize)]
+pub struct NewUser {
+    pub name: String,
+    pub password: String,
+}
+
+impl NewUser {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        if self.name.trim().is_empty() {
+            return Err(FieldError::new("name", "must not be empty"));
+        }
+        if self.password.chars().count() < MIN_PASSWORD_LEN {
+            return Err(FieldError::new(
+                "password",
+                format!("must be at least {MIN_PASSWORD_LEN} characters"),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn hash(password: &str) -> Result<String> {
+    let salt = SaltString::encode_b64(uuid::Uuid::new_v4().as_bytes())
+        .map_err(|err| anyhow!("could not make a salt: {err}"))?;
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|err| anyhow!("could not hash the password: {err}"))?;
+    Ok(hash.to_string())
+}
+
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<User>> {
+    let rows = users::table
+        .order(users::id.asc())
+        .select(User::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn get(conn: &mut SqliteConnection, id: i32) -> Result<Option<User>> {
+    let row = users::table
+        .find(id)
+        .select(User::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+/// Whether `name` is taken by someone other than `except`.
+pub fn name_taken(conn: &mut SqliteConnection, name: &str, except: Option<i32>) -> Result<bool> {
+    let found: Option<i32> = users::table
+        .filter(users::name.eq(name.trim()))
+        .select(users::id)
+        .first(conn)
+        .optional()?;
+    Ok(found.is_some_and(|id| Some(id) != except))
+}
+
+pub fn create(conn: &mut SqliteConnection, new: NewUser) -> Result<User> {
+    let row = diesel::insert_into(users::table)
+        .values((
+            users::name.eq(new.name.trim()),
+            users::password_hash.eq(hash(&new.password)?),
+        ))
+        .returning(User::as_returning())
+        .get_result(conn)?;
+    Ok(row)
+}
+
+pub fn update(conn: &mut SqliteConnection, id: i32, changes: NewUser) -> Result<Option<User>> {
+    let row = diesel::update(users::table.find(id))
+        .set((
+            users::name.eq(changes.name.trim()),
+            users::password_hash.eq(hash(&changes.password)?),
+        ))
+        .returning(User::as_returning())
+        .get_result(conn)
+        .optional()?;
+    Ok(row)
+}
+
+/// Remove a user along with their API keys, returning whether they existed, or
+/// `None`, keeping them, when that would take the last admin key or every key with
+/// them. What they owned becomes the household's.
+pub fn delete(conn: &mut SqliteConnection, id: i32) -> Result<Option<bool>> {
+    conn.transaction(|conn| {
+        if api_keys::strands_user(conn, id)? {
+            return Ok(None);
+        }
+        let deleted = diesel::delete(users::table.find(id)).execute(conn)?;
+        Ok(Some(deleted > 0))
+    })
+}
+
+/// The user named `name`, if `password` is theirs.
+pub fn authenticate(
+    conn: &mut SqliteConnection,
+    name: &str,
+    password: &str,
+) -> Result<Option<User>> {
+    let user: Option<User> = users::table
+        .filter(users::name.eq(name.trim()))
+        .select(User::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(user.filter(|user| {
+        PasswordHash::new(&user.password_hash).is_ok_and(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_keys::Scope;
+    use crate::db;
+
+    fn user(conn: &mut SqliteConnection, name: &str) -> User {
+        create(
+            conn,
+            NewUser {
+                name: name.into(),
+                password: "correct horse".into(),
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn delete_keeps_the_user_holding_every_key() {
+        let mut conn = db::test_connection();
+        let alice = user(&mut conn, "alice");
+        api_keys::create(&mut conn, "laptop", Scope::Print, Some(alice.id)).unwrap();
+
+        assert_eq!(delete(&mut conn, alice.id).unwrap(), None);
+        assert!(api_keys::any(&mut conn).unwrap());
+    }
+
+    #[test]
+    fn delete_keeps_the_user_holding_the_last_admin_key() {
+        let mut conn = db::test_connection();
+        let alice = user(&mut conn, "alice");
+        api_keys::create(&mut conn, "laptop", Scope::Admin, Some(alice.id)).unwrap();
+        api_keys::create(&mut conn, "kiosk", Scope::Print, None).unwrap();
+
+        assert_eq!(delete(&mut conn, alice.id).unwrap(), None);
+        assert!(get(&mut conn, alice.id).unwrap().is_some());
+    }
+
+    #[test]
+    fn delete_revokes_keys_while_an_admin_key_remains() {
+        let mut conn = db::test_connection();
+        let alice = user(&mut conn, "alice");
+        let bob = user(&mut conn, "bob");
+        api_keys::create(&mut conn, "server", Scope::Admin, Some(bob.id)).unwrap();
+        api_keys::create(&mut conn, "laptop", Scope::Admin, Some(alice.id)).unwrap();
+
+        assert_eq!(delete(&mut conn, alice.id).unwrap(), Some(true));
+        let keys = api_keys::list(&mut conn).unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].user_id, Some(bob.id));
+    }
+
+    #[test]
+    fn delete_allows_users_without_keys() {
+        let mut conn = db::test_connection();
+        let alice = user(&mut conn, "alice");
+        let bob = user(&mut conn, "bob");
+        api_keys::create(&mut conn, "laptop", Scope::Admin, Some(alice.id)).unwrap();
+
+        assert_eq!(delete(&mut conn, bob.id).unwrap(), Some(true));
+        assert_eq!(delete(&mut conn, bob.id).unwrap(), Some(false));
+    }
+}
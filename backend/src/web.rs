@@ -0,0 +1,66 @@
+//! The web UI, built into the binary from `../frontend/dist` with the `web-ui` feature
+//! so one file serves both it and the API, with no web server in front. Build the
+//! frontend first (`trunk build --release` in `frontend/`).
+
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode, Uri, header};
+use axum::response::{IntoResponse, Response};
+use rust_embed::RustEmbed;
+
+use crate::error::ApiError;
+
+#[derive(RustEmbed)]
+#[folder = "../frontend/dist"]
+struct Assets;
+
+/// Answers what no API route does: the UI's files, and its page for any other path
+/// so the app can route it itself. Paths naming a file that isn't there, and other
+/// methods, are still not found.
+pub async fn serve(method: Method, uri: Uri, headers: HeaderMap) -> Response {
+    if !matches!(method, Method::GET | Method::HEAD) {
+        return ApiError::NotFound.into_response();
+    }
+    let path = uri.path().trim_start_matches('/');
+    let path = if path.is_empty() { "index.html" } else { path };
+    if let Some(response) = asset(path, &headers) {
+        return response;
+    }
+    if path
+        .rsplit('/')
+        .next()
+        .is_some_and(|name| name.contains('.'))
+    {
+        return ApiError::NotFound.into_response();
+    }
+    asset("index.html", &headers).unwrap_or_else(|| ApiError::NotFound.into_response())
+}
+
+/// The file at `path`, or `304 Not Modified` when the browser has it already.
+fn asset(path: &str, headers: &HeaderMap) -> Option<Response> {
+    let file = Assets::get(path)?;
+    let etag = format!("\"{}\"", hex::encode(file.metadata.sha256_hash()));
+    // The page is checked on every load, so a new build's scripts, whose names change
+    // with their content, are picked up straight away.
+    let cache = if path == "index.html" {
+        "no-cache"
+    } else {
+        "public, max-age=3600"
+    };
+    let mut response = if headers
+        .get(header::IF_NONE_MATCH)
+        .is_some_and(|tag| tag.as_bytes() == etag.as_bytes())
+    {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        (
+            [(header::CONTENT_TYPE, file.metadata.mimetype().to_string())],
+            file.data,
+        )
+            .into_response()
+    };
+    let headers = response.headers_mut();
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static(cache));
+    if let Ok(etag) = HeaderValue::from_str(&etag) {
+        headers.insert(header::ETAG, etag);
+    }
+    Some(response)
+}
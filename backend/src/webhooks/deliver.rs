@@ -0,0 +1,208 @@
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde_json::{Value, json};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{Instrument, warn};
+
+use super::{NewAttempt, Webhook};
+use crate::events::{Event, EventBus};
+use crate::{db, jobs, printers};
+
+pub const SIGNATURE_HEADER: &str = "X-Dayroll-Signature";
+/// The same for every try at one delivery, for endpoints to ignore repeats.
+pub const DELIVERY_HEADER: &str = "X-Dayroll-Delivery";
+pub const EVENT_HEADER: &str = "X-Dayroll-Event";
+
+/// How long to wait before each retry of a delivery the endpoint failed with a 5xx
+/// or 429, or couldn't be reached for. Other answers are not retried.
+const RETRY_DELAYS: [Duration; 4] = [
+    Duration::from_secs(5),
+    Duration::from_secs(30),
+    Duration::from_secs(2 * 60),
+    Duration::from_secs(10 * 60),
+];
+
+/// Send `event` to every webhook subscribed to it, in the background. `data` is an
+/// object whose fields are sent along with `event` and `sent_at`; `printer_id` is
+/// the printer the event is about, if any.
+pub fn notify(event: &'static str, printer_id: Option<i32>, data: Value) {
+    tokio::spawn(
+        async move {
+            if let Err(err) = deliver(event, printer_id, data).await {
+                warn!("could not notify webhooks of {event}: {err:#}");
+            }
+        }
+        .in_current_span(),
+    );
+}
+
+/// Tell every matching webhook that `job_id` reached a terminal state.
+///
+/// Deliveries are fire-and-forget: a slow or failing endpoint is logged and retried
+/// and never holds up the printer queue.
+pub async fn job_finished(job_id: i32) -> Result<()> {
+    let (job, printer) = db::run_blocking_db(move |conn| {
+        let job = jobs::get(conn, job_id)?.ok_or_else(|| anyhow!("job {job_id} not found"))?;
+        let printer = printers::get(conn, job.printer_id)?;
+        Ok((job, printer))
+    })
+    .await?;
+    let event = match job.state.as_str() {
+        "done" => "job.done",
+        "failed" => "job.failed",
+        _ => return Ok(()),
+    };
+    let data = json!({
+        "job_id": job.id,
+        "printer": printer.map(|p| json!({
+            "id": p.id,
+            "name": p.name,
+            "label": p.label(),
+            "location": p.location,
+        })),
+        "state": job.state,
+        "error": job.error,
+        "source": job.source,
+        "bytes": job.bytes,
+        "finished_at": job.finished_at,
+    });
+    deliver(event, Some(job.printer_id), data).await
+}
+
+/// Send `printer.online` and `printer.offline` as printers' devices come and go.
+/// Whether a device is there when the server starts isn't sent, only changes.
+pub fn spawn_printer_alerts(events: &EventBus) {
+    let mut rx = events.subscribe();
+    tokio::spawn(async move {
+        let mut known = HashMap::new();
+        loop {
+            match rx.recv().await {
+                Ok(Event::PrinterOnline { printer_id, online }) => {
+                    if known.insert(printer_id, online).is_none() {
+                        continue;
+                    }
+                    let event = if online {
+                        "printer.online"
+                    } else {
+                        "printer.offline"
+                    };
+                    notify(event, Some(printer_id), json!({ "printer_id": printer_id }));
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("webhooks missed {missed} event(s); printer alerts may be lost");
+                }
+                Err(RecvError::Closed) => return,
+            }
+        }
+    });
+}
+
+async fn deliver(event: &'static str, printer_id: Option<i32>, data: Value) -> Result<()> {
+    let hooks = db::run_blocking_db(move |conn| super::for_event(conn, event, printer_id)).await?;
+    if hooks.is_empty() {
+        return Ok(());
+    }
+
+    let mut body = json!({ "event": event });
+    if let (Some(body), Value::Object(data)) = (body.as_object_mut(), data) {
+        body.extend(data);
+        body.insert("sent_at".into(), json!(Utc::now()));
+    }
+    let body = body.to_string();
+
+    let client = reqwest::Client::new();
+    for hook in hooks {
+        tokio::spawn(deliver_to(client.clone(), hook, event, body.clone()).in_current_span());
+    }
+    Ok(())
+}
+
+/// Post `body` to `hook`, retrying as [`RETRY_DELAYS`] says, and record each try.
+async fn deliver_to(client: reqwest::Client, hook: Webhook, event: &'static str, body: String) {
+    let delivery_id = uuid::Uuid::new_v4().to_string();
+    let signature = sign(&hook.secret, body.as_bytes());
+    for attempt in 1..=RETRY_DELAYS.len() + 1 {
+        let started = Instant::now();
+        let sent = client
+            .post(&hook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, &signature)
+            .header(DELIVERY_HEADER, &delivery_id)
+            .header(EVENT_HEADER, event)
+            .timeout(Duration::from_secs(10))
+            .body(body.clone())
+            .send()
+            .await;
+        let duration_ms = started.elapsed().as_millis().try_into().unwrap_or(i32::MAX);
+        let (status, error, retry) = match sent {
+            Ok(res) if res.status().is_success() => (Some(res.status()), None, false),
+            Ok(res) => {
+                let status = res.status();
+                let retry = status.is_server_error() || status.as_u16() == 429;
+                (Some(status), Some(format!("answered {status}")), retry)
+            }
+            Err(err) => (None, Some(format!("{err:#}")), true),
+        };
+
+        let new = NewAttempt {
+            webhook_id: hook.id,
+            delivery_id: delivery_id.clone(),
+            event: event.into(),
+            attempt: attempt as i32,
+            status: status.map(|s| s.as_u16().into()),
+            error: error.clone(),
+            duration_ms,
+        };
+        if let Err(err) = db::run_blocking_db(move |conn| super::record_attempt(conn, new)).await {
+            warn!("could not record webhook {}'s delivery: {err:#}", hook.id);
+        }
+
+        let Some(error) = error else {
+            return;
+        };
+        let Some(delay) = RETRY_DELAYS.get(attempt - 1).filter(|_| retry) else {
+            warn!(
+                "webhook {} ({}) failed {event} after {attempt} attempt(s): {error}",
+                hook.id, hook.url
+            );
+            return;
+        };
+        warn!(
+            "webhook {} ({}) failed {event}, retrying in {delay:?}: {error}",
+            hook.id, hook.url
+        );
+        tokio::time::sleep(*delay).await;
+    }
+}
+
+/// `sha256=<hex>` HMAC of the raw request body, GitHub-style.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signatures_match_known_vectors() {
+        // RFC 4231, test case 2.
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // The example in GitHub's guide to validating webhook deliveries.
+        assert_eq!(
+            sign("It's a Secret to Everybody", b"Hello, World!"),
+            "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17"
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::error::FieldError;
+use crate::schema::{job_webhooks, webhook_attempts};
+use crate::templates::json_text;
+
+pub mod deliver;
+
+/// Every event a webhook can be sent.
+pub const EVENTS: &[&str] = &[
+    "job.done",
+    "job.failed",
+    "printer.online",
+    "printer.offline",
+    "integration.failed",
+    "integration.credentials_invalid",
+];
+
+/// The events a webhook is sent, the jobs finishing unless it says otherwise.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Events(pub Vec<String>);
+
+impl Default for Events {
+    fn default() -> Self {
+        Self(vec!["job.done".into(), "job.failed".into()])
+    }
+}
+
+impl From<Events> for String {
+    fn from(events: Events) -> Self {
+        serde_json::to_string(&events).unwrap_or_else(|_| "[]".into())
+    }
+}
+
+/// An endpoint notified of the [`EVENTS`] it subscribed to.
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize)]
+#[diesel(table_name = job_webhooks)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Webhook {
+    pub id: i32,
+    pub url: String,
+    /// HMAC key for the `X-Dayroll-Signature` header. Only returned on creation.
+    #[serde(skip_serializing)]
+    pub secret: String,
+    /// Only fire for jobs on this printer; `None` means every printer.
+    pub printer_id: Option<i32>,
+    pub enabled: bool,
+    pub created_at: NaiveDateTime,
+    /// JSON list of the [`EVENTS`] the webhook is sent.
+    #[serde(serialize_with = "json_text")]
+    pub events: String,
+}
+
+impl Webhook {
+    pub fn wants(&self, event: &str) -> bool {
+        serde_json::from_str::<Vec<String>>(&self.events)
+            .is_ok_and(|events| events.iter().any(|e| e == event))
+    }
+}
+
+#[derive(Debug, Deserialize, Insertable)]
+#[diesel(table_name = job_webhooks)]
+pub struct NewWebhook {
+    pub url: String,
+    /// Generated when omitted.
+    #[serde(default)]
+    pub secret: String,
+    #[serde(default)]
+    pub printer_id: Option<i32>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    #[diesel(serialize_as = String)]
+    pub events: Events,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl NewWebhook {
+    pub fn validate(&self) -> Result<(), FieldError> {
+        match reqwest::Url::parse(&self.url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            _ => return Err(FieldError::new("url", "must be an http(s) URL")),
+        }
+        if !self.secret.is_empty() && self.secret.len() < 16 {
+            return Err(FieldError::new("secret", "must be at least 16 characters"));
+        }
+        if self.events.0.is_empty() {
+            return Err(FieldError::new("events", "must list at least one event"));
+        }
+        if let Some(i) = self
+            .events
+            .0
+            .iter()
+            .position(|e| !EVENTS.contains(&e.as_str()))
+        {
+            return Err(FieldError::new(
+                format!("events.{i}"),
+                format!("must be one of {}", EVENTS.join(", ")),
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub fn list(conn: &mut SqliteConnection) -> Result<Vec<Webhook>> {
+    let rows = job_webhooks::table
+        .order(job_webhooks::id.asc())
+        .select(Webhook::as_select())
+        .load(conn)?;
+    Ok(rows)
+}
+
+pub fn get(conn: &mut SqliteConnection, id: i32) -> Result<Option<Webhook>> {
+    let row = job_webhooks::table
+        .find(id)
+        .select(Webhook::as_select())
+        .first(conn)
+        .optional()?;
+    Ok(row)
+}
+
+/// Enabled hooks sent `event`. Those limited to one printer only get the events of
+/// that printer, besides those about no printer in particular.
+pub fn for_event(
+    conn: &mut SqliteConnection,
+    event: &str,
+    printer_id: Option<i32>,
+) -> Result<Vec<Webhook>> {
+    let mut query = job_webhooks::table
+        .filter(job_webhooks::enabled.eq(true))
+        .into_boxed();
+    if let Some(printer_id) = printer_id {
+        query = query.filter(
+            job_webhooks::printer_id
+                .is_null()
+                .or(job_webhooks::printer_id.eq(printer_id)),
+        );
+    }
+    let rows: Vec<Webhook> = query.select(Webhook::as_select()).load(conn)?;
+    Ok(rows.into_iter().filter(|hook| hook.wants(event)).collect())
+}
+
+pub fn create(conn: &mut SqliteConnection, mut new: NewWebhook) -> Result<Webhook> {
+    if new.secret.is_empty() {
+        new.secret = uuid::Uuid::new_v4().simple().to_string();
+    }
+    let row = diesel::insert_into(job_webhooks::table)
+        .values(new)
+        .returning(Webhook::as_returning())
+        .get_result(conn)?;
+    Ok(row)
+}
+
+pub fn delete(conn: &mut SqliteConnection, id: i32) -> Result<bool> {
+    let deleted = diesel::delete(job_webhooks::table.find(id)).execute(conn)?;
+    Ok(deleted > 0)
+}
+
+/// Attempts kept per webhook; older ones are dropped as new ones are recorded.
+const KEPT_ATTEMPTS: i64 = 500;
+
+/// One try at delivering an event to a webhook.
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Serialize)]
+#[diesel(table_name = webhook_attempts)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Attempt {
+    pub id: i32,
+    pub webhook_id: i32,
+    /// Shared by the retries of one delivery, and sent as `X-Dayroll-Delivery`.
+    pub delivery_id: String,
+    pub event: String,
+    /// 1 for the first try.
+    pub attempt: i32,
+    /// The endpoint's HTTP status, unset when it couldn't be reached.
+    pub status: Option<i32>,
+    pub error: Option<String>,
+    pub duration_ms: i32,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = webhook_attempts)]
+pub struct NewAttempt {
+    pub webhook_id: i32,
+    pub delivery_id: String,
+    pub event: String,
+    pub attempt: i32,
+    pub status: Option<i32>,
+    pub error: Option<String>,
+    pub duration_ms: i32,
+}
+
+pub fn record_attempt(conn: &mut SqliteConnection, new: NewAttempt) -> Result<()> {
+    let webhook_id = new.webhook_id;
+    diesel::insert_into(webhook_attempts::table)
+        .values(new)
+        .execute(conn)?;
+    let oldest_kept: Option<i32> = webhook_attempts::table
+        .filter(webhook_attempts::webhook_id.eq(webhook_id))
+        .order(webhook_attempts::id.desc())
+        .offset(KEPT_ATTEMPTS - 1)
+        .select(webhook_attempts::id)
+        .first(conn)
+        .optional()?;
+    if let Some(oldest_kept) = oldest_kept {
+        diesel::delete(
+            webhook_attempts::table
+                .filter(webhook_attempts::webhook_id.eq(webhook_id))
+                .filter(webhook_attempts::id.lt(oldest_kept)),
+        )
+        .execute(conn)?;
+    }
+    Ok(())
+}
+
+/// A page of `webhook_id`'s attempts, newest first, and how many are kept in all.
+pub fn attempts(
+    conn: &mut SqliteConnection,
+    webhook_id: i32,
+    offset: i64,
+    limit: i64,
+) -> Result<(Vec<Attempt>, i64)> {
+    let total = webhook_attempts::table
+        .filter(webhook_attempts::webhook_id.eq(webhook_id))
+        .count()
+        .get_result(conn)?;
+    let rows = webhook_attempts::table
+        .filter(webhook_attempts::webhook_id.eq(webhook_id))
+        .order(webhook_attempts::id.desc())
+        .offset(offset)
+        .limit(limit)
+        .select(Attempt::as_select())
+        .load(conn)?;
+    Ok((rows, total))
+}